@@ -0,0 +1,1871 @@
+//! Assertion helpers.
+//!
+//! These helpers wrap the [`Serializer`] and [`Deserializer`] for common assertions, removing the
+//! boilerplate of constructing them manually within each test.
+//!
+//! [`Deserializer`]: crate::Deserializer
+//! [`Serializer`]: crate::Serializer
+
+use crate::{
+    de::{
+        self,
+        Consumption,
+        Error,
+    },
+    token::{
+        self,
+        CanonicalToken,
+        Labels,
+        Nesting,
+        Tokens,
+    },
+    Deserializer,
+    Serializer,
+    Token,
+};
+use alloc::{
+    format,
+    string::{
+        String,
+        ToString,
+    },
+    vec,
+    vec::Vec,
+};
+use core::{
+    any::type_name,
+    fmt::{
+        Debug,
+        Display,
+        Write,
+    },
+};
+use serde::{
+    de::{
+        DeserializeOwned,
+        DeserializeSeed,
+    },
+    Serialize,
+};
+
+/// Asserts that deserializing the given tokens as the given type fails with an error matching a
+/// pattern.
+///
+/// The pattern is matched against the [`Display`] output of the returned [`de::Error`]. The
+/// pattern matches if it is found anywhere within the error message, with any `*` within the
+/// pattern matching an arbitrary sequence of characters. This is less brittle than comparing
+/// errors exactly, as error messages often embed [`Expected`] text which can change between
+/// versions of `serde`.
+///
+/// A pattern can also be provided to match against the [`de::Error`] variant itself, asserting
+/// the kind of error returned.
+///
+/// # Panics
+/// Panics if deserialization succeeds, if the error message does not match the pattern, or if
+/// the error does not match the provided variant pattern.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_de_err_matches,
+///     de::Error,
+///     Token,
+/// };
+///
+/// assert_de_err_matches!(
+///     u32,
+///     [Token::Bool(true)],
+///     "invalid type: expected *, found boolean"
+/// );
+/// assert_de_err_matches!(
+///     u32,
+///     [Token::Bool(true)],
+///     "found boolean",
+///     Error::InvalidType(..)
+/// );
+/// ```
+///
+/// [`de::Error`]: crate::de::Error
+/// [`Display`]: core::fmt::Display
+/// [`Expected`]: serde::de::Expected
+#[macro_export]
+macro_rules! assert_de_err_matches {
+    ($ty:ty, $tokens:expr, $pattern:expr $(,)?) => {
+        $crate::__private::assert_de_err_matches(
+            <$ty as $crate::__private::Deserialize>::deserialize(
+                &mut $crate::Deserializer::builder($tokens).build(),
+            ),
+            $pattern,
+            |_| true,
+            "_",
+        )
+    };
+    ($ty:ty, $tokens:expr, $pattern:expr, $kind:pat $(,)?) => {
+        $crate::__private::assert_de_err_matches(
+            <$ty as $crate::__private::Deserialize>::deserialize(
+                &mut $crate::Deserializer::builder($tokens).build(),
+            ),
+            $pattern,
+            |error| match error {
+                $kind => true,
+                _ => false,
+            },
+            stringify!($kind),
+        )
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_de_err_matches<T, F>(result: Result<T, Error>, pattern: &str, kind: F, kind_str: &str)
+where
+    T: Debug,
+    F: FnOnce(&Error) -> bool,
+{
+    match result {
+        Ok(value) => panic!("assertion failed, expected Err(..), got Ok({value:?})"),
+        Err(error) => {
+            let message = error.to_string();
+            assert!(
+                matches_pattern(pattern, &message),
+                "assertion failed, expected error matching pattern `{pattern}`, got error `{message}`"
+            );
+            assert!(
+                kind(&error),
+                "assertion failed, expected error matching `{kind_str}`, got error {error:?}"
+            );
+        }
+    }
+}
+
+/// Asserts that a value serializes to the given tokens, and that the tokens deserialize back to
+/// the value.
+///
+/// This combines [`assert_ser_tokens()`] and [`assert_de_tokens()`] into a single assertion for
+/// types whose [`Serialize`] and [`Deserialize`] implementations are symmetric. The value is
+/// serialized using a default [`Serializer`] and compared against the expected tokens, which are
+/// then deserialized using a default [`Deserializer`] and compared against the value.
+///
+/// # Panics
+/// Panics if the value fails to serialize, if the serialized tokens do not match the expected
+/// tokens, if the expected tokens fail to deserialize, or if the deserialized value is not equal
+/// to the original value.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_tokens,
+///     Token,
+/// };
+///
+/// assert_tokens!(Some(true), [Token::Some, Token::Bool(true)]);
+/// ```
+///
+/// [`assert_de_tokens()`]: crate::assert_de_tokens()
+/// [`assert_ser_tokens()`]: crate::assert_ser_tokens()
+/// [`Deserialize`]: serde::Deserialize
+/// [`Deserializer`]: crate::Deserializer
+/// [`Serialize`]: serde::Serialize
+/// [`Serializer`]: crate::Serializer
+#[macro_export]
+macro_rules! assert_tokens {
+    ($value:expr, $tokens:expr $(,)?) => {
+        $crate::__private::assert_tokens(&$value, $tokens)
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_tokens<T, I>(value: &T, tokens: I)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+    I: IntoIterator<Item = Token>,
+{
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    assert_ser_tokens(value, tokens.iter().cloned());
+    assert_de_tokens(value, tokens);
+}
+
+/// Asserts that [`Tokens`] are equal to the expected tokens, printing an aligned diff on failure.
+///
+/// Long token streams are difficult to compare when printed as two vectors, as
+/// [`assert_eq!`] does. On a mismatch, this instead prints the expected and actual tokens side
+/// by side, one token per row, indented by their nesting depth. Each row is prefixed by its index,
+/// and rows whose tokens differ are marked with `>`.
+///
+/// # Panics
+/// Panics if the tokens are not equal to the expected tokens.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok;
+/// use serde::Serialize;
+/// use serde_assert::{
+///     assert_tokens_eq,
+///     Serializer,
+///     Token,
+/// };
+///
+/// let serializer = Serializer::new();
+///
+/// assert_tokens_eq!(
+///     assert_ok!(Some(true).serialize(&serializer)),
+///     [Token::Some, Token::Bool(true)]
+/// );
+/// ```
+///
+/// A mismatch is reported like the following:
+///
+/// ``` text
+/// assertion failed, tokens differ at index 2:
+///       expected          | actual
+///     0 Seq { len: None } | Seq { len: None }
+///     1   U8(1)           |   U8(1)
+///   > 2   U8(2)           |   U8(3)
+///     3 SeqEnd            | SeqEnd
+/// ```
+#[macro_export]
+macro_rules! assert_tokens_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::__private::assert_tokens_eq(&$actual, $expected)
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_tokens_eq<I>(actual: &Tokens, expected: I)
+where
+    I: IntoIterator<Item = Token>,
+{
+    let expected = expected.into_iter().collect::<Vec<_>>();
+    assert!(
+        *actual == expected,
+        "assertion failed, tokens {}",
+        describe_diff(&actual.0, &Tokens::canonicalize(expected.clone()).0)
+    );
+}
+
+/// Asserts that the given tokens can be deserialized as `T`, returning the deserialized value.
+///
+/// This is useful for verifying that a type can still read data written by another version of
+/// itself, or by another type entirely. All of the tokens must be consumed by the deserialization.
+///
+/// # Panics
+/// Panics if the tokens cannot be deserialized as `T`, or if any tokens remain after the value is
+/// deserialized.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_compatible,
+///     Token,
+/// };
+///
+/// assert_eq!(assert_compatible::<u32, _>([Token::U32(42)]), 42);
+/// ```
+#[track_caller]
+pub fn assert_compatible<T, I>(tokens: I) -> T
+where
+    T: DeserializeOwned,
+    I: IntoIterator<Item = Token>,
+{
+    match de::from_tokens::<T, _>(tokens) {
+        Ok(value) => value,
+        Err(error) => panic!(
+            "assertion failed, tokens are not compatible with `{}`: {error}",
+            type_name::<T>()
+        ),
+    }
+}
+
+/// Asserts that the given tokens can be deserialized as `T`, and that serializing that value
+/// results in tokens that can be deserialized as `U`.
+///
+/// This checks compatibility in both directions between two types, such as two versions of the
+/// same type: `T` must be able to read the given tokens, and `U` must be able to read what `T`
+/// writes. The value deserialized as `U` is returned.
+///
+/// # Panics
+/// Panics if the tokens cannot be deserialized as `T`, if the deserialized value cannot be
+/// serialized, or if the serialized tokens cannot be deserialized as `U`. In both directions, all
+/// of the tokens must be consumed by the deserialization.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_compatible_with,
+///     Token,
+/// };
+///
+/// mod v1 {
+///     use serde_derive::{
+///         Deserialize,
+///         Serialize,
+///     };
+///
+///     #[derive(Deserialize, Serialize)]
+///     pub struct Foo {
+///         pub a: u32,
+///     }
+/// }
+///
+/// mod v2 {
+///     use serde_derive::Deserialize;
+///
+///     #[derive(Debug, Deserialize, PartialEq)]
+///     pub struct Foo {
+///         pub a: u32,
+///         #[serde(default)]
+///         pub b: bool,
+///     }
+/// }
+///
+/// assert_eq!(
+///     assert_compatible_with::<v1::Foo, v2::Foo, _>([
+///         Token::Struct {
+///             name: "Foo",
+///             len: 1,
+///         },
+///         Token::Field("a"),
+///         Token::U32(42),
+///         Token::StructEnd,
+///     ]),
+///     v2::Foo { a: 42, b: false }
+/// );
+/// ```
+#[track_caller]
+pub fn assert_compatible_with<T, U, I>(tokens: I) -> U
+where
+    T: DeserializeOwned + Serialize,
+    U: DeserializeOwned,
+    I: IntoIterator<Item = Token>,
+{
+    let value = assert_compatible::<T, _>(tokens);
+    let tokens = match value.serialize(&Serializer::builder().build()) {
+        Ok(tokens) => tokens,
+        Err(error) => panic!(
+            "assertion failed, could not serialize `{}`: {error}",
+            type_name::<T>()
+        ),
+    };
+    match de::from_tokens::<U, _>(tokens) {
+        Ok(value) => value,
+        Err(error) => panic!(
+            "assertion failed, tokens serialized by `{}` are not compatible with `{}`: {error}",
+            type_name::<T>(),
+            type_name::<U>()
+        ),
+    }
+}
+
+/// Asserts that every fixture in a corpus of tokens can be deserialized as `T`.
+///
+/// Each fixture is a name paired with a sequence of [`Token`]s, such as previously recorded
+/// serializations of older versions of a type. This turns historical serialized states into a
+/// regression suite: if the current [`Deserialize`] implementation no longer accepts a fixture,
+/// the assertion fails.
+///
+/// Every fixture is checked before failing, and the failure message reports each fixture that
+/// could not be deserialized along with the index of the token at which deserialization failed. If
+/// deserialization failed before consuming a token, or by running out of tokens, the number of
+/// tokens consumed is reported instead.
+///
+/// Fixtures are provided in memory. Loading fixtures from a directory is not supported, as
+/// [`Token`]s have no file format; fixtures stored in separate files can instead be embedded as
+/// Rust expressions using [`include!`].
+///
+/// # Panics
+/// Panics if any fixture cannot be deserialized as `T`.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_corpus,
+///     Token,
+/// };
+///
+/// assert_corpus::<Option<u32>, _, _, _>([
+///     ("none", vec![Token::None]),
+///     ("some", vec![Token::Some, Token::U32(42)]),
+/// ]);
+/// ```
+///
+/// [`Deserialize`]: serde::Deserialize
+#[track_caller]
+pub fn assert_corpus<T, C, N, I>(corpus: C)
+where
+    T: DeserializeOwned,
+    C: IntoIterator<Item = (N, I)>,
+    N: Display,
+    I: IntoIterator<Item = Token>,
+{
+    let mut failures = String::new();
+    for (name, tokens) in corpus {
+        let tokens = tokens.into_iter().collect::<Vec<_>>();
+        let labels = token::labels(&tokens);
+        let mut deserializer = Deserializer::builder(tokens.iter().cloned()).build();
+        let tokens = Tokens::canonicalize(tokens);
+        if let Err(error) = T::deserialize(&mut deserializer) {
+            let consumed = deserializer.position();
+            // The failing token is the last one consumed, unless the tokens ran out.
+            let index = match error {
+                Error::EndOfTokens => None,
+                _ => consumed.checked_sub(1),
+            };
+            // Writing to a `String` cannot fail.
+            let _ = match (index.and_then(|index| tokens.0.get(index)), index) {
+                (Some(token), Some(index)) => match labels.get(&index) {
+                    Some(label) => write!(
+                        failures,
+                        "\n  fixture `{name}` failed at token {index} ({:?}, labeled `{label}`): \
+                         {error}",
+                        Token::from(token.clone())
+                    ),
+                    None => write!(
+                        failures,
+                        "\n  fixture `{name}` failed at token {index} ({:?}): {error}",
+                        Token::from(token.clone())
+                    ),
+                },
+                _ => write!(
+                    failures,
+                    "\n  fixture `{name}` failed after consuming {consumed} of {} tokens: {error}",
+                    tokens.0.len()
+                ),
+            };
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "assertion failed, corpus is not compatible with `{}`:{failures}",
+        type_name::<T>()
+    );
+}
+
+/// Asserts serialization, deserialization, and round-trips for a table of cases.
+///
+/// Each case is a value paired with the [`Token`]s it is expected to serialize to. For every case,
+/// this asserts that:
+///
+/// - the value serializes to the expected tokens,
+/// - the expected tokens deserialize to the value, and
+/// - the serialized tokens deserialize back to the value.
+///
+/// Every case is checked before failing, and the failure message reports each failed assertion
+/// along with the index of its case.
+///
+/// # Panics
+/// Panics if any assertion fails for any case.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_cases,
+///     Token,
+/// };
+///
+/// assert_cases([
+///     (None, vec![Token::None]),
+///     (Some(true), vec![Token::Some, Token::Bool(true)]),
+/// ]);
+/// ```
+#[track_caller]
+pub fn assert_cases<T, C, I>(cases: C)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+    C: IntoIterator<Item = (T, I)>,
+    I: IntoIterator<Item = Token>,
+{
+    let serializer = Serializer::new();
+    let mut failures = String::new();
+    for (index, (value, expected)) in cases.into_iter().enumerate() {
+        let expected = expected.into_iter().collect::<Vec<_>>();
+        // Writing to a `String` cannot fail.
+        match value.serialize(&serializer) {
+            Ok(tokens) => {
+                if tokens == expected {
+                    match T::deserialize(&mut Deserializer::new(tokens)) {
+                        Ok(roundtrip) if roundtrip == value => {}
+                        Ok(roundtrip) => {
+                            let _ = write!(
+                                failures,
+                                "\n  case {index}: round-tripped to {roundtrip:?}, expected \
+                                 {value:?}"
+                            );
+                        }
+                        Err(error) => {
+                            let _ =
+                                write!(failures, "\n  case {index}: failed to round-trip: {error}");
+                        }
+                    }
+                } else {
+                    let _ = write!(
+                        failures,
+                        "\n  case {index}: serialized tokens {}",
+                        describe_mismatch(
+                            &tokens.0,
+                            &Tokens::canonicalize(expected.clone()).0,
+                            &token::labels(&expected)
+                        )
+                    );
+                }
+            }
+            Err(error) => {
+                let _ = write!(failures, "\n  case {index}: failed to serialize: {error}");
+            }
+        }
+        let mut deserializer = Deserializer::builder(expected)
+            .record_consumptions(true)
+            .build();
+        match T::deserialize(&mut deserializer) {
+            Ok(actual) if actual == value => {}
+            Ok(actual) => {
+                let _ = write!(
+                    failures,
+                    "\n  case {index}: deserialized to {actual:?}, expected {value:?}"
+                );
+            }
+            Err(error) => {
+                let _ = write!(failures, "\n  case {index}: failed to deserialize: {error}");
+                if let Some(label) = deserializer
+                    .consumptions()
+                    .last()
+                    .and_then(Consumption::label)
+                {
+                    let _ = write!(failures, " (at token labeled `{label}`)");
+                }
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "assertion failed, cases failed for `{}`:{failures}",
+        type_name::<T>()
+    );
+}
+
+/// Asserts that a value serializes to the given tokens.
+///
+/// The value is serialized using a default [`Serializer`], and the resulting tokens are compared
+/// against the expected tokens. [`Token::Unordered`] may be used within the expected tokens to
+/// match types with arbitrary iteration order.
+///
+/// # Panics
+/// Panics if the value fails to serialize or if the serialized tokens do not match the expected
+/// tokens. On a mismatch, the failure message reports the index of the first differing token,
+/// along with both full token streams.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_ser_tokens,
+///     Token,
+/// };
+///
+/// assert_ser_tokens(&Some(true), [Token::Some, Token::Bool(true)]);
+/// ```
+#[track_caller]
+pub fn assert_ser_tokens<T, I>(value: &T, tokens: I)
+where
+    T: Serialize + ?Sized,
+    I: IntoIterator<Item = Token>,
+{
+    let expected = tokens.into_iter().collect::<Vec<_>>();
+    let actual = match value.serialize(&Serializer::new()) {
+        Ok(tokens) => tokens,
+        Err(error) => panic!(
+            "assertion failed, could not serialize `{}`: {error}",
+            type_name::<T>()
+        ),
+    };
+    assert!(
+        actual == expected,
+        "assertion failed, `{}` serialized tokens {}\n  got:      {:?}\n  expected: {:?}",
+        type_name::<T>(),
+        describe_mismatch(
+            &actual.0,
+            &Tokens::canonicalize(expected.clone()).0,
+            &token::labels(&expected)
+        ),
+        actual
+            .0
+            .iter()
+            .cloned()
+            .map(Token::from)
+            .collect::<Vec<_>>(),
+        expected
+    );
+}
+
+/// Asserts that the given tokens deserialize to a value.
+///
+/// The tokens are deserialized as `T` using a default [`Deserializer`], and the result is compared
+/// against the expected value. Every token must be consumed by deserialization.
+///
+/// # Panics
+/// Panics if the tokens fail to deserialize, if any tokens remain after deserialization, or if the
+/// deserialized value is not equal to the expected value. The failure message includes the full
+/// token stream.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_de_tokens,
+///     Token,
+/// };
+///
+/// assert_de_tokens(&Some(true), [Token::Some, Token::Bool(true)]);
+/// ```
+#[track_caller]
+pub fn assert_de_tokens<T, I>(value: &T, tokens: I)
+where
+    T: DeserializeOwned + PartialEq + Debug,
+    I: IntoIterator<Item = Token>,
+{
+    let tokens = tokens.into_iter().collect::<Vec<_>>();
+    let mut deserializer = Deserializer::builder(tokens.iter().cloned())
+        .record_consumptions(true)
+        .build();
+    let result = T::deserialize(&mut deserializer).and_then(|actual| {
+        deserializer.end()?;
+        Ok(actual)
+    });
+    match result {
+        Ok(actual) => assert!(
+            actual == *value,
+            "assertion failed, tokens deserialized to {actual:?}, expected {value:?}\n  tokens: \
+             {tokens:?}"
+        ),
+        Err(error) => {
+            let label = deserializer
+                .consumptions()
+                .last()
+                .and_then(Consumption::label)
+                .map(|label| format!(" (at token labeled `{label}`)"))
+                .unwrap_or_default();
+            panic!(
+                "assertion failed, could not deserialize `{}`: {error}{label}\n  tokens: \
+                 {tokens:?}",
+                type_name::<T>()
+            );
+        }
+    }
+}
+
+/// Asserts that a value serializes to a sequence or map containing the given groups of tokens in
+/// any order.
+///
+/// Each group is the [`Token`]s for a single element of a sequence, or a single key and value of
+/// a map. The value must serialize to a [`Seq`] or [`Map`] containing exactly those groups,
+/// followed by the corresponding end token. This is equivalent to comparing against
+/// [`Unordered`] tokens wrapped in the `Seq` or `Map` framing, and is useful for asserting the
+/// serialization of types with arbitrary iteration order, such as [`HashSet`] and [`HashMap`].
+///
+/// If the `Seq` or `Map` provides a length, it must be equal to the number of groups.
+///
+/// # Panics
+/// Panics if the value fails to serialize, if it does not serialize to a `Seq` or `Map`, or if its
+/// elements do not match the groups.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_ser_unordered,
+///     Token,
+/// };
+/// use std::collections::HashMap;
+///
+/// let mut map = HashMap::new();
+/// map.insert('a', 1u32);
+/// map.insert('b', 2u32);
+///
+/// assert_ser_unordered(
+///     &map,
+///     [
+///         [Token::Char('a'), Token::U32(1)],
+///         [Token::Char('b'), Token::U32(2)],
+///     ],
+/// );
+/// ```
+///
+/// [`HashMap`]: std::collections::HashMap
+/// [`HashSet`]: std::collections::HashSet
+/// [`Map`]: Token::Map
+/// [`Seq`]: Token::Seq
+/// [`Unordered`]: Token::Unordered
+#[track_caller]
+pub fn assert_ser_unordered<T, G, I>(value: &T, groups: G)
+where
+    T: Serialize + ?Sized,
+    G: IntoIterator<Item = I>,
+    I: IntoIterator<Item = Token>,
+{
+    let groups = groups
+        .into_iter()
+        .map(|group| Tokens::canonicalize(group).0)
+        .collect::<Vec<_>>();
+    let tokens = match value.serialize(&Serializer::new()) {
+        Ok(tokens) => tokens.0,
+        Err(error) => panic!(
+            "assertion failed, could not serialize `{}`: {error}",
+            type_name::<T>()
+        ),
+    };
+
+    let matches = match (tokens.first(), tokens.last()) {
+        (Some(CanonicalToken::Seq { len }), Some(CanonicalToken::SeqEnd))
+        | (Some(CanonicalToken::Map { len }), Some(CanonicalToken::MapEnd)) => {
+            tokens.len() >= 2
+                && len.map_or(true, |len| len == groups.len())
+                && matches_groups(&tokens[1..(tokens.len() - 1)], &groups)
+        }
+        _ => false,
+    };
+    assert!(
+        matches,
+        "assertion failed, `{}` serialized to {:?}, expected a sequence or map of {:?} in any order",
+        type_name::<T>(),
+        tokens.into_iter().map(Token::from).collect::<Vec<_>>(),
+        groups
+            .into_iter()
+            .map(|group| group.into_iter().map(Token::from).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    );
+}
+
+/// The number of tokens shown on either side of a mismatch.
+const MISMATCH_CONTEXT: usize = 3;
+
+/// Describes the first position at which two token streams differ.
+///
+/// Only a window of tokens surrounding the mismatch is included, as full token streams are often
+/// too long to compare by eye.
+///
+/// If the expected token at the mismatch is labeled, the label is included as well.
+pub(crate) fn describe_mismatch(
+    actual: &[CanonicalToken],
+    expected: &[CanonicalToken],
+    labels: &Labels,
+) -> String {
+    let index = actual
+        .iter()
+        .zip(expected)
+        .position(|(actual, expected)| actual != expected)
+        .unwrap_or_else(|| actual.len().min(expected.len()));
+    let mut description = format!("differ at index {index}");
+    if let Some(label) = labels.get(&index) {
+        // Writing to a `String` cannot fail.
+        let _ = write!(description, " (labeled `{label}`)");
+    }
+    let _ = write!(
+        description,
+        ": got {} ({} tokens), expected {} ({} tokens)",
+        token_window(actual, index),
+        actual.len(),
+        token_window(expected, index),
+        expected.len()
+    );
+    if let (Some(CanonicalToken::Bytes(actual)), Some(CanonicalToken::Bytes(expected))) =
+        (actual.get(index), expected.get(index))
+    {
+        description.push('\n');
+        description.push_str(&describe_bytes_mismatch(actual, expected));
+    }
+    description
+}
+
+/// Number of bytes rendered on each row of a hex dump.
+const HEX_DUMP_WIDTH: usize = 16;
+
+/// Describes the first offset at which two byte payloads differ, rendered as hex dumps.
+///
+/// Only the rows surrounding the first differing offset are rendered. The row containing the
+/// difference is marked with `>`, and the differing byte is underlined with `^^`.
+fn describe_bytes_mismatch(actual: &[u8], expected: &[u8]) -> String {
+    let offset = actual
+        .iter()
+        .zip(expected)
+        .position(|(actual, expected)| actual != expected)
+        .unwrap_or_else(|| actual.len().min(expected.len()));
+    format!(
+        "bytes differ at offset {offset:#x}:\ngot ({} bytes):\n{}expected ({} bytes):\n{}",
+        actual.len(),
+        hex_dump(actual, offset),
+        expected.len(),
+        hex_dump(expected, offset)
+    )
+}
+
+/// Renders the hex dump rows surrounding the given offset.
+///
+/// Each row contains the row's offset, the hex values of its bytes, and their ASCII
+/// representation, with non-printable bytes rendered as `.`.
+fn hex_dump(bytes: &[u8], offset: usize) -> String {
+    let row = offset / HEX_DUMP_WIDTH;
+    let rows = (bytes.len() + HEX_DUMP_WIDTH - 1) / HEX_DUMP_WIDTH;
+    let start = row.saturating_sub(1).min(rows);
+    let end = row.saturating_add(2).min(rows);
+    let mut dump = String::new();
+    if start > 0 {
+        dump.push_str("  ...\n");
+    }
+    for current in start..end {
+        let chunk =
+            &bytes[current * HEX_DUMP_WIDTH..((current + 1) * HEX_DUMP_WIDTH).min(bytes.len())];
+        let marker = if current == row { '>' } else { ' ' };
+        // Writing to a `String` cannot fail.
+        let _ = write!(dump, "{marker} {:08x} ", current * HEX_DUMP_WIDTH);
+        for index in 0..HEX_DUMP_WIDTH {
+            match chunk.get(index) {
+                Some(byte) => {
+                    let _ = write!(dump, " {byte:02x}");
+                }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        dump.extend(chunk.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                char::from(byte)
+            } else {
+                '.'
+            }
+        }));
+        dump.push_str("|\n");
+        if current == row && offset < bytes.len() {
+            let _ = writeln!(
+                dump,
+                "{:width$}^^",
+                "",
+                width = 12 + (offset % HEX_DUMP_WIDTH) * 3
+            );
+        }
+    }
+    if end < rows {
+        dump.push_str("  ...\n");
+    }
+    dump
+}
+
+/// Formats the tokens surrounding the given index, eliding the rest.
+fn token_window(tokens: &[CanonicalToken], index: usize) -> String {
+    let start = index.saturating_sub(MISMATCH_CONTEXT).min(tokens.len());
+    let end = index.saturating_add(MISMATCH_CONTEXT + 1).min(tokens.len());
+    let mut window = String::from("[");
+    if start > 0 {
+        window.push_str("..., ");
+    }
+    for (offset, token) in tokens[start..end].iter().enumerate() {
+        if offset > 0 {
+            window.push_str(", ");
+        }
+        // Writing to a `String` cannot fail.
+        let _ = write!(window, "{:?}", Token::from(token.clone()));
+    }
+    if end < tokens.len() {
+        window.push_str(", ...");
+    }
+    window.push(']');
+    window
+}
+
+/// Describes the differences between two token streams as an aligned, side-by-side diff.
+///
+/// Each row contains the index of a token, followed by the expected and actual tokens at that
+/// index, indented by their nesting depth. Rows that differ are marked with `>`.
+fn describe_diff(actual: &[CanonicalToken], expected: &[CanonicalToken]) -> String {
+    let index = actual
+        .iter()
+        .zip(expected)
+        .position(|(actual, expected)| actual != expected)
+        .unwrap_or_else(|| actual.len().min(expected.len()));
+    let actual_rows = diff_rows(actual);
+    let expected_rows = diff_rows(expected);
+    let width = expected_rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("expected".len());
+    let index_width = actual.len().max(expected.len()).to_string().len();
+
+    let mut description = format!(
+        "differ at index {index}:\n  {:index_width$}   {:width$} | actual",
+        "", "expected"
+    );
+    for row in 0..actual.len().max(expected.len()) {
+        let marker = if actual.get(row) == expected.get(row) {
+            ' '
+        } else {
+            '>'
+        };
+        // Writing to a `String` cannot fail.
+        let _ = write!(
+            description,
+            "\n  {marker} {row:>index_width$} {:width$} | {}",
+            expected_rows.get(row).map_or("", String::as_str),
+            actual_rows.get(row).map_or("", String::as_str)
+        );
+    }
+    description
+}
+
+/// Renders each token on its own row, indented by its nesting depth.
+fn diff_rows(tokens: &[CanonicalToken]) -> Vec<String> {
+    let mut nesting = Nesting::default();
+    tokens
+        .iter()
+        .map(|token| {
+            let depth = nesting.depth();
+            nesting.step(token);
+            // Tokens ending a compound value are indented at the depth of the value they end.
+            let depth = depth.min(nesting.depth());
+            format!("{:1$}{2:?}", "", depth * 2, Token::from(token.clone()))
+        })
+        .collect()
+}
+
+/// Returns whether the tokens consist of every group, in any order.
+///
+/// The search backtracks using an explicit stack rather than recursion, so large numbers of groups
+/// cannot overflow the call stack.
+fn matches_groups(tokens: &[CanonicalToken], groups: &[Vec<CanonicalToken>]) -> bool {
+    let mut used = vec![false; groups.len()];
+    // The index of each chosen group, along with the position at which it was matched.
+    let mut chosen = Vec::new();
+    let mut position = 0;
+    let mut next_group = 0;
+    loop {
+        if position == tokens.len()
+            && used
+                .iter()
+                .zip(groups)
+                .all(|(used, group)| *used || group.is_empty())
+        {
+            return true;
+        }
+        if let Some(index) = (next_group..groups.len()).find(|&index| {
+            !used[index]
+                && !groups[index].is_empty()
+                && tokens[position..].starts_with(&groups[index])
+        }) {
+            used[index] = true;
+            chosen.push((index, position));
+            position += groups[index].len();
+            next_group = 0;
+        } else if let Some((index, previous_position)) = chosen.pop() {
+            // Backtrack, trying the groups after the one previously chosen at this position.
+            used[index] = false;
+            position = previous_position;
+            next_group = index + 1;
+        } else {
+            return false;
+        }
+    }
+}
+
+/// Asserts that every fixture can be deserialized using the same stateful seed, returning the
+/// deserialized values.
+///
+/// The fixtures are deserialized in order, each using a [`DeserializeSeed`] implemented on a
+/// mutable reference to `seed`. This allows testing seeds that accumulate state across values,
+/// such as string interning tables. The state can then be asserted after all fixtures have been
+/// deserialized.
+///
+/// Since the fixtures are owned by this function, zero-copy deserialization is disabled.
+///
+/// # Panics
+/// Panics if any fixture cannot be deserialized, reporting the index of each failed fixture.
+///
+/// # Example
+/// ``` rust
+/// use serde::de::{
+///     Deserialize,
+///     DeserializeSeed,
+/// };
+/// use serde_assert::{
+///     assert_seed_mut,
+///     Token,
+/// };
+///
+/// #[derive(Default)]
+/// struct Interner(Vec<String>);
+///
+/// impl<'de> DeserializeSeed<'de> for &mut Interner {
+///     type Value = usize;
+///
+///     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+///     where
+///         D: serde::Deserializer<'de>,
+///     {
+///         let string = String::deserialize(deserializer)?;
+///         Ok(
+///             match self.0.iter().position(|interned| *interned == string) {
+///                 Some(index) => index,
+///                 None => {
+///                     self.0.push(string);
+///                     self.0.len() - 1
+///                 }
+///             },
+///         )
+///     }
+/// }
+///
+/// let mut interner = Interner::default();
+///
+/// assert_eq!(
+///     assert_seed_mut(
+///         &mut interner,
+///         [
+///             [Token::Str("foo".to_owned())],
+///             [Token::Str("bar".to_owned())],
+///             [Token::Str("foo".to_owned())],
+///         ]
+///     ),
+///     [0, 1, 0]
+/// );
+/// assert_eq!(interner.0, ["foo", "bar"]);
+/// ```
+#[track_caller]
+pub fn assert_seed_mut<S, T, C, I>(seed: &mut S, fixtures: C) -> Vec<T>
+where
+    for<'a> &'a mut S: DeserializeSeed<'static, Value = T>,
+    C: IntoIterator<Item = I>,
+    I: IntoIterator<Item = Token>,
+{
+    assert_seed_each(fixtures, |deserializer| seed.deserialize(deserializer))
+}
+
+/// Asserts that every fixture can be deserialized using a clone of the same seed, returning the
+/// deserialized values.
+///
+/// The fixtures are deserialized in order, each using a fresh clone of `seed`. Seeds which share
+/// state between clones (for example, through an [`Rc`]) will accumulate that state across the
+/// fixtures.
+///
+/// Since the fixtures are owned by this function, zero-copy deserialization is disabled.
+///
+/// # Panics
+/// Panics if any fixture cannot be deserialized, reporting the index of each failed fixture.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     assert_seed_cloned,
+///     Token,
+/// };
+/// use std::marker::PhantomData;
+///
+/// assert_eq!(
+///     assert_seed_cloned(
+///         &PhantomData::<bool>,
+///         [[Token::Bool(true)], [Token::Bool(false)]]
+///     ),
+///     [true, false]
+/// );
+/// ```
+///
+/// [`Rc`]: alloc::rc::Rc
+#[track_caller]
+pub fn assert_seed_cloned<S, C, I>(seed: &S, fixtures: C) -> Vec<S::Value>
+where
+    S: DeserializeSeed<'static> + Clone,
+    C: IntoIterator<Item = I>,
+    I: IntoIterator<Item = Token>,
+{
+    assert_seed_each(fixtures, |deserializer| {
+        seed.clone().deserialize(deserializer)
+    })
+}
+
+#[track_caller]
+fn assert_seed_each<T, F, C, I>(fixtures: C, mut deserialize: F) -> Vec<T>
+where
+    F: FnMut(&mut Deserializer<'static>) -> Result<T, Error>,
+    C: IntoIterator<Item = I>,
+    I: IntoIterator<Item = Token>,
+{
+    let mut values = Vec::new();
+    let mut failures = String::new();
+    for (index, tokens) in fixtures.into_iter().enumerate() {
+        // Zero-copy deserialization is disabled, so no values can borrow from the tokens.
+        match deserialize(&mut Deserializer::builder(tokens).zero_copy(false).build()) {
+            Ok(value) => values.push(value),
+            Err(error) => {
+                // Writing to a `String` cannot fail.
+                let _ = write!(failures, "\n  fixture {index}: {error}");
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "assertion failed, fixtures could not be deserialized as `{}`:{failures}",
+        type_name::<T>()
+    );
+    values
+}
+
+/// Returns whether the pattern is found within the text.
+///
+/// Any `*` within the pattern matches an arbitrary sequence of characters.
+pub(crate) fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let mut remaining = text;
+    for segment in pattern.split('*') {
+        if let Some(index) = remaining.find(segment) {
+            remaining = &remaining[(index + segment.len())..];
+        } else {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        assert_cases,
+        assert_compatible,
+        assert_compatible_with,
+        assert_corpus,
+        assert_de_tokens,
+        assert_seed_cloned,
+        assert_seed_mut,
+        assert_ser_tokens,
+        assert_ser_unordered,
+        describe_diff,
+        describe_mismatch,
+        matches_pattern,
+    };
+    use crate::{
+        de::Error,
+        token::{
+            self,
+            Labels,
+            Tokens,
+        },
+        Token,
+    };
+    use alloc::{
+        vec,
+        vec::Vec,
+    };
+    use core::marker::PhantomData;
+    use serde::de::{
+        Deserialize as _,
+        DeserializeSeed,
+    };
+    use serde_derive::{
+        Deserialize,
+        Serialize,
+    };
+    use std::collections::{
+        HashMap,
+        HashSet,
+    };
+
+    #[test]
+    fn matches_pattern_exact() {
+        assert!(matches_pattern("foo", "foo"));
+    }
+
+    #[test]
+    fn matches_pattern_substring() {
+        assert!(matches_pattern("oo b", "foo bar"));
+    }
+
+    #[test]
+    fn matches_pattern_wildcard() {
+        assert!(matches_pattern("foo*baz", "foo bar baz"));
+    }
+
+    #[test]
+    fn matches_pattern_wildcard_out_of_order() {
+        assert!(!matches_pattern("baz*foo", "foo bar baz"));
+    }
+
+    #[test]
+    fn matches_pattern_empty() {
+        assert!(matches_pattern("", "foo"));
+    }
+
+    #[test]
+    fn matches_pattern_no_match() {
+        assert!(!matches_pattern("qux", "foo bar baz"));
+    }
+
+    #[test]
+    fn assert_de_err_matches_pattern() {
+        assert_de_err_matches!(u32, [Token::Bool(true)], "invalid type: * boolean");
+    }
+
+    #[test]
+    fn assert_de_err_matches_kind() {
+        assert_de_err_matches!(u32, [], "end of tokens", Error::EndOfTokens);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected Err(..), got Ok(true)")]
+    fn assert_de_err_matches_ok() {
+        assert_de_err_matches!(bool, [Token::Bool(true)], "");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected error matching pattern `invalid value`")]
+    fn assert_de_err_matches_pattern_mismatch() {
+        assert_de_err_matches!(u32, [Token::Bool(true)], "invalid value");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected error matching `Error::EndOfTokens`")]
+    fn assert_de_err_matches_kind_mismatch() {
+        assert_de_err_matches!(u32, [Token::Bool(true)], "invalid type", Error::EndOfTokens);
+    }
+
+    #[test]
+    fn assert_tokens_roundtrip() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Struct {
+            foo: u32,
+            bar: Option<bool>,
+        }
+
+        assert_tokens!(
+            Struct {
+                foo: 42,
+                bar: Some(true),
+            },
+            [
+                Token::Struct {
+                    name: "Struct",
+                    len: 2,
+                },
+                Token::Field("foo"),
+                Token::U32(42),
+                Token::Field("bar"),
+                Token::Some,
+                Token::Bool(true),
+                Token::StructEnd,
+            ],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "serialized tokens differ at index 1")]
+    fn assert_tokens_ser_mismatch() {
+        assert_tokens!(Some(42_u8), [Token::Some, Token::U16(42)]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "tokens deserialized to Struct { foo: 21 }, expected Struct { foo: 42 }"
+    )]
+    fn assert_tokens_de_mismatch() {
+        fn halve<'de, D>(deserializer: D) -> Result<u32, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            u32::deserialize(deserializer).map(|value| value / 2)
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Struct {
+            #[serde(deserialize_with = "halve")]
+            foo: u32,
+        }
+
+        assert_tokens!(
+            Struct { foo: 42 },
+            [
+                Token::Struct {
+                    name: "Struct",
+                    len: 1,
+                },
+                Token::Field("foo"),
+                Token::U32(42),
+                Token::StructEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn assert_tokens_eq_equal() {
+        assert_tokens_eq!(
+            Tokens::canonicalize([Token::Some, Token::Bool(true)]),
+            [Token::Some, Token::Bool(true)]
+        );
+    }
+
+    #[test]
+    fn assert_tokens_eq_unordered() {
+        assert_tokens_eq!(
+            Tokens::canonicalize([
+                Token::Seq { len: Some(2) },
+                Token::U8(1),
+                Token::U8(2),
+                Token::SeqEnd,
+            ]),
+            [
+                Token::Seq { len: Some(2) },
+                Token::Unordered(&[&[Token::U8(2)], &[Token::U8(1)]]),
+                Token::SeqEnd,
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion failed, tokens differ at index 1")]
+    fn assert_tokens_eq_mismatch() {
+        assert_tokens_eq!(
+            Tokens::canonicalize([Token::Some, Token::Bool(true)]),
+            [Token::Some, Token::Bool(false)]
+        );
+    }
+
+    #[test]
+    fn describe_diff_nested() {
+        assert_eq!(
+            describe_diff(
+                &Tokens::canonicalize([
+                    Token::Seq { len: None },
+                    Token::U8(1),
+                    Token::U8(3),
+                    Token::SeqEnd,
+                ])
+                .0,
+                &Tokens::canonicalize([
+                    Token::Seq { len: None },
+                    Token::U8(1),
+                    Token::U8(2),
+                    Token::SeqEnd,
+                ])
+                .0
+            ),
+            "differ at index 2:\n      expected          | actual\n    0 Seq { len: None } | \
+             Seq { len: None }\n    1   U8(1)           |   U8(1)\n  > 2   U8(2)           |   \
+             U8(3)\n    3 SeqEnd            | SeqEnd"
+        );
+    }
+
+    #[test]
+    fn describe_diff_different_lengths() {
+        assert_eq!(
+            describe_diff(
+                &Tokens::canonicalize([Token::Some, Token::Unit]).0,
+                &Tokens::canonicalize([Token::Some]).0
+            ),
+            "differ at index 1:\n      expected | actual\n    0 Some     | Some\n  > 1          | \
+             Unit"
+        );
+    }
+
+    #[test]
+    fn assert_compatible_ok() {
+        assert!(assert_compatible::<bool, _>([Token::Bool(true)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "tokens are not compatible with `bool`")]
+    fn assert_compatible_incompatible() {
+        assert_compatible::<bool, _>([Token::U8(1)]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "tokens are not compatible with `bool`: 1 trailing tokens after value"
+    )]
+    fn assert_compatible_trailing_tokens() {
+        assert_compatible::<bool, _>([Token::Bool(true), Token::Unit]);
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct V1 {
+        a: u32,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename = "V1")]
+    struct V2 {
+        a: u32,
+        #[serde(default)]
+        b: bool,
+    }
+
+    #[test]
+    fn assert_compatible_with_ok() {
+        assert_eq!(
+            assert_compatible_with::<V1, V2, _>([
+                Token::Struct { name: "V1", len: 1 },
+                Token::Field("a"),
+                Token::U32(1),
+                Token::StructEnd,
+            ]),
+            V2 { a: 1, b: false }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "are not compatible with `bool`")]
+    fn assert_compatible_with_incompatible() {
+        assert_compatible_with::<V1, bool, _>([
+            Token::Struct { name: "V1", len: 1 },
+            Token::Field("a"),
+            Token::U32(1),
+            Token::StructEnd,
+        ]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "are not compatible with `serde_assert::assert::tests::V1`: 1 trailing"
+    )]
+    fn assert_compatible_with_trailing_tokens() {
+        assert_compatible_with::<V1, V2, _>([
+            Token::Struct { name: "V1", len: 1 },
+            Token::Field("a"),
+            Token::U32(1),
+            Token::StructEnd,
+            Token::Unit,
+        ]);
+    }
+
+    #[test]
+    fn assert_corpus_ok() {
+        assert_corpus::<u32, _, _, _>([("a", [Token::U32(1)]), ("b", [Token::U32(2)])]);
+    }
+
+    #[test]
+    fn assert_corpus_empty() {
+        assert_corpus::<u32, _, &str, [Token; 0]>([]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture `b` failed at token 2 (Bool(true))")]
+    fn assert_corpus_failure_index() {
+        assert_corpus::<(u32, u32), _, _, _>([
+            (
+                "a",
+                [
+                    Token::Tuple { len: 2 },
+                    Token::U32(1),
+                    Token::U32(2),
+                    Token::TupleEnd,
+                ],
+            ),
+            (
+                "b",
+                [
+                    Token::Tuple { len: 2 },
+                    Token::U32(1),
+                    Token::Bool(true),
+                    Token::TupleEnd,
+                ],
+            ),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture `a` failed after consuming 0 of 0 tokens: end of tokens")]
+    fn assert_corpus_failure_end_of_tokens() {
+        assert_corpus::<u32, _, _, _>([("a", [])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture `a` failed after consuming 2 of 2 tokens: end of tokens")]
+    fn assert_corpus_failure_end_of_tokens_after_consuming() {
+        assert_corpus::<(u32, u32), _, _, _>([("a", [Token::Tuple { len: 2 }, Token::U32(1)])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture `a` failed after consuming 0 of 1 tokens: rejected")]
+    fn assert_corpus_failure_before_consuming() {
+        struct Rejected;
+
+        impl<'de> serde::Deserialize<'de> for Rejected {
+            fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                Err(serde::de::Error::custom("rejected"))
+            }
+        }
+
+        assert_corpus::<Rejected, _, _, _>([("a", [Token::Unit])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "found boolean `true`\n  fixture `b` failed at token 0 (Unit)")]
+    fn assert_corpus_multiple_failures() {
+        assert_corpus::<u32, _, _, _>([("a", [Token::Bool(true)]), ("b", [Token::Unit])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture `a` failed at token 1 (Bool(true), labeled `second`)")]
+    fn assert_corpus_failure_labeled() {
+        assert_corpus::<(u32, u32), _, _, _>([(
+            "a",
+            [
+                Token::Tuple { len: 2 },
+                Token::labeled("second", Token::Bool(true)),
+                Token::U32(2),
+                Token::TupleEnd,
+            ],
+        )]);
+    }
+
+    #[test]
+    fn assert_cases_ok() {
+        assert_cases([(1_u8, [Token::U8(1)]), (2_u8, [Token::U8(2)])]);
+    }
+
+    #[test]
+    fn assert_cases_unordered() {
+        assert_cases([(
+            (1_u8, 2_u8),
+            [
+                Token::Tuple { len: 2 },
+                Token::Unordered(&[&[Token::U8(1), Token::U8(2)]]),
+                Token::TupleEnd,
+            ],
+        )]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "case 1: serialized tokens differ at index 0: got [U8(2)] (1 tokens), expected \
+                    [U8(3)] (1 tokens)\n  case 1: deserialized to 3, expected 2"
+    )]
+    fn assert_cases_mismatch() {
+        assert_cases([(1_u8, [Token::U8(1)]), (2_u8, [Token::U8(3)])]);
+    }
+
+    #[test]
+    fn describe_mismatch_window() {
+        let actual = Tokens::canonicalize((0..20).map(Token::U8));
+        let expected =
+            Tokens::canonicalize((0..20).map(|i| Token::U8(if i == 10 { 42 } else { i })));
+
+        assert_eq!(
+            describe_mismatch(&actual.0, &expected.0, &Labels::new()),
+            "differ at index 10: got [..., U8(7), U8(8), U8(9), U8(10), U8(11), U8(12), U8(13), \
+             ...] (20 tokens), expected [..., U8(7), U8(8), U8(9), U8(42), U8(11), U8(12), U8(13), \
+             ...] (20 tokens)"
+        );
+    }
+
+    #[test]
+    fn describe_mismatch_labeled() {
+        let actual = Tokens::canonicalize([Token::U8(1), Token::U8(2)]);
+        let expected = [Token::U8(1), Token::labeled("second", Token::U8(3))];
+
+        assert_eq!(
+            describe_mismatch(
+                &actual.0,
+                &Tokens::canonicalize(expected.clone()).0,
+                &token::labels(&expected)
+            ),
+            "differ at index 1 (labeled `second`): got [U8(1), U8(2)] (2 tokens), expected [U8(1), \
+             U8(3)] (2 tokens)"
+        );
+    }
+
+    #[test]
+    fn describe_mismatch_shorter() {
+        let actual = Tokens::canonicalize((0..5).map(Token::U8));
+        let expected = Tokens::canonicalize((0..3).map(Token::U8));
+
+        assert_eq!(
+            describe_mismatch(&actual.0, &expected.0, &Labels::new()),
+            "differ at index 3: got [U8(0), U8(1), U8(2), U8(3), U8(4)] (5 tokens), expected \
+             [U8(0), U8(1), U8(2)] (3 tokens)"
+        );
+    }
+
+    #[test]
+    fn describe_mismatch_bytes() {
+        let actual = Tokens::canonicalize([Token::Bytes((0..40).collect())]);
+        let expected = Tokens::canonicalize([Token::Bytes(
+            (0..40).map(|i| if i == 20 { b'A' } else { i }).collect(),
+        )]);
+
+        assert_eq!(
+            describe_mismatch(&actual.0, &expected.0, &Labels::new())
+                .split_once('\n')
+                .map(|(_, dump)| dump),
+            Some(
+                "bytes differ at offset 0x14:
+got (40 bytes):
+  00000000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f  |................|
+> 00000010  10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f  |................|
+                        ^^
+  00000020  20 21 22 23 24 25 26 27                          | !\"#$%&'|
+expected (40 bytes):
+  00000000  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f  |................|
+> 00000010  10 11 12 13 41 15 16 17 18 19 1a 1b 1c 1d 1e 1f  |....A...........|
+                        ^^
+  00000020  20 21 22 23 24 25 26 27                          | !\"#$%&'|
+"
+            )
+        );
+    }
+
+    #[test]
+    fn describe_mismatch_bytes_shorter() {
+        let actual = Tokens::canonicalize([Token::Bytes(b"foobar".to_vec())]);
+        let expected = Tokens::canonicalize([Token::Bytes(b"foo".to_vec())]);
+
+        assert_eq!(
+            describe_mismatch(&actual.0, &expected.0, &Labels::new())
+                .split_once('\n')
+                .map(|(_, dump)| dump),
+            Some(
+                "bytes differ at offset 0x3:
+got (6 bytes):
+> 00000000  66 6f 6f 62 61 72                                |foobar|
+                     ^^
+expected (3 bytes):
+> 00000000  66 6f 6f                                         |foo|
+"
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "case 0: serialized tokens differ at index 31: got [..., U8(27), \
+                               U8(28), U8(29), U8(42), U8(31), U8(32), U8(33), ...] (102 tokens)"
+    )]
+    fn assert_cases_mismatch_long() {
+        let mut value = (0..100).collect::<Vec<u8>>();
+        let mut expected = vec![Token::Seq { len: Some(100) }];
+        expected.extend(value.iter().copied().map(Token::U8));
+        expected.push(Token::SeqEnd);
+        value[30] = 42;
+
+        assert_cases([(value, expected)]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "case 0: failed to deserialize: invalid type: expected a boolean, found \
+                    integer `1` (at token labeled `value`)"
+    )]
+    fn assert_cases_deserialize_failure_labeled() {
+        assert_cases([(true, [Token::labeled("value", Token::U8(1))])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "case 0: round-tripped to Lossy(0), expected Lossy(1)")]
+    fn assert_cases_roundtrip_failure() {
+        #[derive(Debug, PartialEq, Serialize)]
+        #[serde(transparent)]
+        struct Lossy(u8);
+
+        impl<'de> serde::Deserialize<'de> for Lossy {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                <u8 as serde::Deserialize>::deserialize(deserializer).map(|_| Lossy(0))
+            }
+        }
+
+        assert_cases([(Lossy(1), [Token::U8(1)])]);
+    }
+
+    #[derive(Default)]
+    struct Counter(usize);
+
+    impl<'de> DeserializeSeed<'de> for &mut Counter {
+        type Value = usize;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            <()>::deserialize(deserializer)?;
+            self.0 += 1;
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn assert_seed_mut_accumulates() {
+        let mut counter = Counter::default();
+
+        assert_eq!(
+            assert_seed_mut(&mut counter, [[Token::Unit], [Token::Unit]]),
+            [1, 2]
+        );
+        assert_eq!(counter.0, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture 1: end of tokens")]
+    fn assert_seed_mut_failure() {
+        let mut counter = Counter::default();
+
+        assert_seed_mut(&mut counter, [vec![Token::Unit], vec![]]);
+    }
+
+    #[test]
+    fn assert_seed_cloned_independent() {
+        assert_eq!(
+            assert_seed_cloned(&PhantomData::<u8>, [[Token::U8(1)], [Token::U8(2)]]),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture 0: invalid type: expected u8, found unit value")]
+    fn assert_seed_cloned_failure() {
+        assert_seed_cloned(&PhantomData::<u8>, [[Token::Unit]]);
+    }
+
+    #[test]
+    fn assert_ser_tokens_matching() {
+        assert_ser_tokens(&Some(42_u8), [Token::Some, Token::U8(42)]);
+    }
+
+    #[test]
+    fn assert_ser_tokens_unordered() {
+        let set = [1_u32, 2].into_iter().collect::<HashSet<_>>();
+
+        assert_ser_tokens(
+            &set,
+            [
+                Token::Seq { len: Some(2) },
+                Token::Unordered(&[&[Token::U32(2)], &[Token::U32(1)]]),
+                Token::SeqEnd,
+            ],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "serialized tokens differ at index 1")]
+    fn assert_ser_tokens_mismatch() {
+        assert_ser_tokens(&Some(42_u8), [Token::Some, Token::U8(43)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected: [Some, U8(43)]")]
+    fn assert_ser_tokens_mismatch_full_tokens() {
+        assert_ser_tokens(&Some(42_u8), [Token::Some, Token::U8(43)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "could not serialize")]
+    fn assert_ser_tokens_serialize_error() {
+        struct Failing;
+
+        impl serde::Serialize for Failing {
+            fn serialize<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                Err(serde::ser::Error::custom("failed"))
+            }
+        }
+
+        assert_ser_tokens(&Failing, [Token::Unit]);
+    }
+
+    #[test]
+    fn assert_de_tokens_matching() {
+        assert_de_tokens(&Some(42_u8), [Token::Some, Token::U8(42)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "tokens deserialized to Some(42), expected Some(43)")]
+    fn assert_de_tokens_mismatch() {
+        assert_de_tokens(&Some(43_u8), [Token::Some, Token::U8(42)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "could not deserialize `bool`: invalid type")]
+    fn assert_de_tokens_deserialize_error() {
+        assert_de_tokens(&true, [Token::U8(1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "tokens: [Bool(true), Unit]")]
+    fn assert_de_tokens_trailing_tokens() {
+        assert_de_tokens(&true, [Token::Bool(true), Token::Unit]);
+    }
+
+    #[test]
+    fn assert_ser_unordered_set() {
+        let set = [1_u32, 2, 3].into_iter().collect::<HashSet<_>>();
+
+        assert_ser_unordered(&set, [[Token::U32(3)], [Token::U32(1)], [Token::U32(2)]]);
+    }
+
+    #[test]
+    fn assert_ser_unordered_map() {
+        let map = [('a', 1_u32), ('b', 2)]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        assert_ser_unordered(
+            &map,
+            [
+                [Token::Char('b'), Token::U32(2)],
+                [Token::Char('a'), Token::U32(1)],
+            ],
+        );
+    }
+
+    #[test]
+    fn assert_ser_unordered_nested() {
+        assert_ser_unordered(
+            &vec![vec![1_u8], vec![1, 1]],
+            [
+                vec![
+                    Token::Seq { len: Some(2) },
+                    Token::U8(1),
+                    Token::U8(1),
+                    Token::SeqEnd,
+                ],
+                vec![Token::Seq { len: Some(1) }, Token::U8(1), Token::SeqEnd],
+            ],
+        );
+    }
+
+    #[test]
+    fn assert_ser_unordered_many_groups() {
+        let set = (0..10_000_u32).collect::<HashSet<_>>();
+
+        assert_ser_unordered(&set, (0..10_000).map(|i| [Token::U32(i)]));
+    }
+
+    #[test]
+    fn assert_ser_unordered_empty() {
+        assert_ser_unordered(&HashSet::<u32>::new(), [[Token::U32(1); 0]; 0]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "serialized to [Seq { len: Some(1) }, U32(1), SeqEnd], expected a \
+                               sequence or map of [[U32(2)]] in any order"
+    )]
+    fn assert_ser_unordered_mismatch() {
+        assert_ser_unordered(
+            &[1_u32].into_iter().collect::<HashSet<_>>(),
+            [[Token::U32(2)]],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a sequence or map")]
+    fn assert_ser_unordered_missing_group() {
+        assert_ser_unordered(
+            &[1_u32].into_iter().collect::<HashSet<_>>(),
+            [[Token::U32(1)], [Token::U32(1)]],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a sequence or map")]
+    fn assert_ser_unordered_not_seq() {
+        assert_ser_unordered(&(1_u32,), [[Token::U32(1)]]);
+    }
+}
@@ -19,35 +19,64 @@
 //! ```
 
 use crate::{
+    profile::Profile,
     token,
     token::{
         CanonicalToken,
+        EnumTagging,
+        Labels,
+        Nesting,
+        Pass,
+        Step,
         Tokens,
-        UnorderedTokens,
+        VariantId,
     },
     Token,
 };
 use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    rc::Rc,
     string::{
         String,
         ToString,
     },
     vec::Vec,
 };
+#[cfg(target_has_atomic = "ptr")]
+use core::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
 use core::{
+    cell::{
+        Cell,
+        RefCell,
+    },
     fmt,
-    fmt::Display,
+    fmt::{
+        Display,
+        Write as _,
+    },
     mem,
 };
 use serde::{
     de,
     de::{
+        value::StrDeserializer,
+        DeserializeOwned,
         DeserializeSeed,
         Error as _,
         Expected,
         Unexpected,
+        Visitor as _,
     },
 };
+#[cfg(target_has_atomic = "ptr")]
+use serde::{
+    ser::SerializeStruct as _,
+    Serialize,
+};
 
 /// Deserializer for testing [`Deserialize`] implementations.
 ///
@@ -65,8 +94,58 @@ use serde::{
 ///   tokens as self-describing, meaning the type the tokens should deserialize to can be discerned
 ///   directly from the tokens themselves. If this is set to `false`, calls to [`deserialize_any()`]
 ///   will result in an error.
+/// - [`content_compatible()`]: Determines whether [`deserialize_any()`] presents enum variants and
+///   newtype structs the way self-describing formats do, allowing them to survive the buffering
+///   performed by `#[serde(untagged)]` enums.
 /// - [`zero_copy()`]: Defines whether zero-copy deserialization should be permitted by the
 ///   `Deserializer`, allowing deserializations of strings and byte sequences to avoid allocations.
+/// - [`str_delivery()`]: Specifies the [`Delivery`] used for the contents of every `Str` token, for
+///   exercising each of the string methods of a [`Visitor`].
+/// - [`bytes_delivery()`]: Specifies the [`Delivery`] used for the contents of every `Bytes` token,
+///   for exercising each of the byte methods of a [`Visitor`].
+/// - [`deserialize_bytes_as_seq()`]: Determines whether bytes may also be deserialized from a
+///   sequence of `u8`s, as is done by formats without a native bytes type.
+/// - [`char_as_str()`]: Determines whether `char`s may also be deserialized from single-character
+///   strings, as is done by formats without a native `char` type.
+/// - [`deserialize_struct_from_seq()`]: Determines whether structs may also be deserialized from a
+///   sequence of their fields, as is done by compact formats.
+/// - [`deserialize_unit_as_none()`]: Determines whether options may also be deserialized as `None`
+///   from a unit value, as is done by formats encoding `None` as null.
+/// - [`deserialize_enum_from_map()`]: Determines whether enums may also be deserialized from a map
+///   of their variant name to their contents, as is done by self-describing formats like JSON.
+/// - [`enum_tagging()`]: Specifies the [`EnumTagging`] that enum variant tokens are rewritten to,
+///   for testing internally and adjacently tagged enums.
+/// - [`deserialize_bool_from_int()`]: Determines whether `bool`s may also be deserialized from the
+///   integers `0` and `1`, as is done by formats encoding booleans numerically.
+/// - [`deserialize_128_as()`]: Specifies which tokens 128-bit integers are deserialized from, for
+///   emulating formats that only support them through string or byte encodings.
+/// - [`support_128_bit()`]: Determines whether 128-bit integers can be deserialized at all, for
+///   emulating formats without support for them.
+/// - [`deny_unknown_fields()`]: Determines whether `Field` tokens not known to the struct being
+///   deserialized are rejected, emulating `#[serde(deny_unknown_fields)]`.
+/// - [`deny_duplicate_keys()`]: Determines whether keys appearing more than once within a single
+///   map or struct are rejected.
+/// - [`require_string_keys()`]: Determines whether map keys must be strings, emulating JSON-like
+///   formats.
+/// - [`allow_trailing_tokens()`]: Determines whether tokens may remain after the root value is
+///   deserialized, as is expected by framed protocols.
+/// - [`implicit_map_end()`]: Determines whether maps and structs are implicitly ended when the
+///   tokens run out, emulating forgiving streaming decoders.
+/// - [`fail_after_tokens()`]: Causes the `Deserializer` to fail once a number of tokens have been
+///   consumed, for testing how a [`Deserialize`] implementation propagates errors.
+/// - [`record_consumptions()`]: Determines whether every consumed token is recorded, for inspecting
+///   them afterwards through [`consumptions()`] and [`trace()`].
+/// - [`coverage()`]: Records which `Deserializer` and `Visitor` methods are called into a
+///   [`Coverage`] shared across many deserializers, for flagging untested branches of an
+///   implementation.
+/// - [`pre_process()`]: Adds a pass that rewrites the tokens before they are deserialized, for
+///   turning one set of tokens into many input variants.
+/// - [`dump_on_panic()`]: Determines whether the remaining tokens are printed if the thread panics
+///   while the `Deserializer` is alive. Requires the `std` feature.
+/// - [`log_consumption()`]: Determines whether each consumed token is printed along with the method
+///   that consumed it. Requires the `std` feature.
+/// - [`profile()`]: Applies a [`Profile`] configuring the options above to approximate a commonly
+///   targeted format.
 ///
 /// # Example
 /// ``` rust
@@ -82,20 +161,81 @@ use serde::{
 /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
 /// ```
 ///
+/// [`allow_trailing_tokens()`]: Builder::allow_trailing_tokens()
+/// [`bytes_delivery()`]: Builder::bytes_delivery()
+/// [`char_as_str()`]: Builder::char_as_str()
+/// [`consumptions()`]: Deserializer::consumptions()
+/// [`content_compatible()`]: Builder::content_compatible()
+/// [`coverage()`]: Builder::coverage()
+/// [`deserialize_128_as()`]: Builder::deserialize_128_as()
+/// [`deserialize_bool_from_int()`]: Builder::deserialize_bool_from_int()
+/// [`deserialize_bytes_as_seq()`]: Builder::deserialize_bytes_as_seq()
+/// [`deserialize_enum_from_map()`]: Builder::deserialize_enum_from_map()
+/// [`deserialize_struct_from_seq()`]: Builder::deserialize_struct_from_seq()
+/// [`deserialize_unit_as_none()`]: Builder::deserialize_unit_as_none()
+/// [`deny_duplicate_keys()`]: Builder::deny_duplicate_keys()
+/// [`deny_unknown_fields()`]: Builder::deny_unknown_fields()
+/// [`dump_on_panic()`]: Builder::dump_on_panic()
+/// [`enum_tagging()`]: Builder::enum_tagging()
+/// [`fail_after_tokens()`]: Builder::fail_after_tokens()
+/// [`implicit_map_end()`]: Builder::implicit_map_end()
 /// [`is_human_readable()`]: Builder::is_human_readable()
+/// [`log_consumption()`]: Builder::log_consumption()
+/// [`pre_process()`]: Builder::pre_process()
+/// [`profile()`]: Builder::profile()
+/// [`record_consumptions()`]: Builder::record_consumptions()
+/// [`require_string_keys()`]: Builder::require_string_keys()
 /// [`Deserialize`]: serde::Deserialize
 /// [`deserialize_any()`]: #method.deserialize_any
 /// [`self_describing()`]: Builder::self_describing()
+/// [`str_delivery()`]: Builder::str_delivery()
+/// [`support_128_bit()`]: Builder::support_128_bit()
+/// [`trace()`]: Deserializer::trace()
+/// [`Visitor`]: serde::de::Visitor
 /// [`zero_copy()`]: Builder::zero_copy()
+#[allow(clippy::struct_excessive_bools)] // Each bool is an independent configuration option.
 #[derive(Debug)]
 pub struct Deserializer<'a> {
     tokens: token::OwningIter<'a>,
+    labels: Labels,
+
+    revisited_token: Option<token::IterToken<'a>>,
 
-    revisited_token: Option<&'a mut CanonicalToken>,
+    visits: Rc<RefCell<Vec<Visit>>>,
+    /// The number of tokens consumed so far, not counting revisited tokens more than once.
+    consumed: usize,
+    consumptions: Vec<Consumption>,
+    deliveries: Vec<Delivery>,
+    /// The tokens consumed while deserializing the current map key, if they are being captured.
+    key_tokens: Option<Vec<CanonicalToken>>,
+    #[cfg(target_has_atomic = "ptr")]
+    coverage: Option<&'static Coverage>,
 
     is_human_readable: bool,
     self_describing: bool,
+    content_compatible: bool,
     zero_copy: bool,
+    str_delivery: Option<Delivery>,
+    bytes_delivery: Option<Delivery>,
+    deserialize_bytes_as_seq: bool,
+    char_as_str: bool,
+    deserialize_struct_from_seq: bool,
+    deserialize_unit_as_none: bool,
+    deserialize_enum_from_map: bool,
+    deserialize_bool_from_int: bool,
+    deserialize_128_as: Deserialize128As,
+    support_128_bit: bool,
+    deny_unknown_fields: bool,
+    deny_duplicate_keys: bool,
+    require_string_keys: bool,
+    allow_trailing_tokens: bool,
+    implicit_map_end: bool,
+    fail_after_tokens: Option<usize>,
+    record_consumptions: bool,
+    #[cfg(feature = "std")]
+    dump_on_panic: bool,
+    #[cfg(feature = "std")]
+    log_consumption: bool,
 }
 
 impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
@@ -105,119 +245,54 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        if !self.self_describing {
-            return Err(Error::NotSelfDescribing);
-        }
-        let token = self.next_token()?;
-        match token {
-            CanonicalToken::Bool(v) => visitor.visit_bool(*v),
-            CanonicalToken::I8(v) => visitor.visit_i8(*v),
-            CanonicalToken::I16(v) => visitor.visit_i16(*v),
-            CanonicalToken::I32(v) => visitor.visit_i32(*v),
-            CanonicalToken::I64(v) => visitor.visit_i64(*v),
-            CanonicalToken::I128(v) => visitor.visit_i128(*v),
-            CanonicalToken::U8(v) => visitor.visit_u8(*v),
-            CanonicalToken::U16(v) => visitor.visit_u16(*v),
-            CanonicalToken::U32(v) => visitor.visit_u32(*v),
-            CanonicalToken::U64(v) => visitor.visit_u64(*v),
-            CanonicalToken::U128(v) => visitor.visit_u128(*v),
-            CanonicalToken::F32(v) => visitor.visit_f32(*v),
-            CanonicalToken::F64(v) => visitor.visit_f64(*v),
-            CanonicalToken::Char(v) => visitor.visit_char(*v),
-            CanonicalToken::Str(v) => visitor.visit_string(mem::take(v)),
-            CanonicalToken::Bytes(v) => visitor.visit_byte_buf(mem::take(v)),
-            CanonicalToken::None => visitor.visit_none(),
-            CanonicalToken::Some => visitor.visit_some(self),
-            CanonicalToken::Unit | CanonicalToken::UnitStruct { .. } => visitor.visit_unit(),
-            CanonicalToken::UnitVariant { .. }
-            | CanonicalToken::NewtypeVariant { .. }
-            | CanonicalToken::TupleVariant { .. }
-            | CanonicalToken::StructVariant { .. } => {
-                // `EnumDeserializer` takes care of the enum deserialization, which will consume
-                // this token later.
-                self.revisit_token(token);
-                visitor.visit_enum(EnumAccess { deserializer: self })
-            }
-            CanonicalToken::NewtypeStruct { .. } => visitor.visit_newtype_struct(self),
-            CanonicalToken::Seq { len } => {
-                let mut access = SeqAccess {
-                    deserializer: self,
-
-                    len: *len,
-
-                    end_token: EndToken::Seq,
-                    ended: false,
-                };
-                let result = visitor.visit_seq(&mut access)?;
-                access.assert_ended()?;
-                Ok(result)
-            }
-            CanonicalToken::Tuple { len } => {
-                let mut access = SeqAccess {
-                    deserializer: self,
-
-                    len: Some(*len),
-
-                    end_token: EndToken::Tuple,
-                    ended: false,
-                };
-                let result = visitor.visit_seq(&mut access)?;
-                access.assert_ended()?;
-                Ok(result)
-            }
-            CanonicalToken::TupleStruct { name: _, len } => {
-                let mut access = SeqAccess {
-                    deserializer: self,
-
-                    len: Some(*len),
-
-                    end_token: EndToken::TupleStruct,
-                    ended: false,
-                };
-                let result = visitor.visit_seq(&mut access)?;
-                access.assert_ended()?;
-                Ok(result)
-            }
-            CanonicalToken::Map { len } => {
-                let mut access = MapAccess {
-                    deserializer: self,
-
-                    len: *len,
-
-                    end_token: EndToken::Map,
-                    ended: false,
-                };
-                let result = visitor.visit_map(&mut access)?;
-                access.assert_ended()?;
-                Ok(result)
-            }
-            CanonicalToken::Field(v) => visitor.visit_str(v),
-            CanonicalToken::Struct { name: _, len } => {
-                let mut access = MapAccess {
-                    deserializer: self,
-
-                    len: Some(*len),
-
-                    end_token: EndToken::Struct,
-                    ended: false,
-                };
-                let result = visitor.visit_map(&mut access)?;
-                access.assert_ended()?;
-                Ok(result)
-            }
-            _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
-        }
+        self.any("deserialize_any", visitor)
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
-        if let CanonicalToken::Bool(v) = token {
-            visitor.visit_bool(*v)
-        } else {
-            Err(Self::Error::invalid_type((token).into(), &visitor))
+        let visitor = self.record_visits("deserialize_bool", visitor);
+        let token = &mut *self.next_token("deserialize_bool")?;
+        match token {
+            CanonicalToken::Bool(v) => visitor.visit_bool(*v),
+            CanonicalToken::I8(_)
+            | CanonicalToken::I16(_)
+            | CanonicalToken::I32(_)
+            | CanonicalToken::I64(_)
+            | CanonicalToken::I128(_)
+            | CanonicalToken::U8(_)
+            | CanonicalToken::U16(_)
+            | CanonicalToken::U32(_)
+            | CanonicalToken::U64(_)
+            | CanonicalToken::U128(_)
+                if self.deserialize_bool_from_int =>
+            {
+                match token {
+                    CanonicalToken::I8(0)
+                    | CanonicalToken::I16(0)
+                    | CanonicalToken::I32(0)
+                    | CanonicalToken::I64(0)
+                    | CanonicalToken::I128(0)
+                    | CanonicalToken::U8(0)
+                    | CanonicalToken::U16(0)
+                    | CanonicalToken::U32(0)
+                    | CanonicalToken::U64(0)
+                    | CanonicalToken::U128(0) => visitor.visit_bool(false),
+                    CanonicalToken::I8(1)
+                    | CanonicalToken::I16(1)
+                    | CanonicalToken::I32(1)
+                    | CanonicalToken::I64(1)
+                    | CanonicalToken::I128(1)
+                    | CanonicalToken::U8(1)
+                    | CanonicalToken::U16(1)
+                    | CanonicalToken::U32(1)
+                    | CanonicalToken::U64(1)
+                    | CanonicalToken::U128(1) => visitor.visit_bool(true),
+                    _ => Err(Self::Error::invalid_value((token).into(), &visitor)),
+                }
+            }
+            _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
     }
 
@@ -225,7 +300,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_i8", visitor);
+        let token = &mut *self.next_token("deserialize_i8")?;
         if let CanonicalToken::I8(v) = token {
             visitor.visit_i8(*v)
         } else {
@@ -237,7 +313,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_i16", visitor);
+        let token = &mut *self.next_token("deserialize_i16")?;
         if let CanonicalToken::I16(v) = token {
             visitor.visit_i16(*v)
         } else {
@@ -249,7 +326,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_i32", visitor);
+        let token = &mut *self.next_token("deserialize_i32")?;
         if let CanonicalToken::I32(v) = token {
             visitor.visit_i32(*v)
         } else {
@@ -261,7 +339,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_i64", visitor);
+        let token = &mut *self.next_token("deserialize_i64")?;
         if let CanonicalToken::I64(v) = token {
             visitor.visit_i64(*v)
         } else {
@@ -273,11 +352,28 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
-        if let CanonicalToken::I128(v) = token {
-            visitor.visit_i128(*v)
-        } else {
-            Err(Self::Error::invalid_type((token).into(), &visitor))
+        let visitor = self.record_visits("deserialize_i128", visitor);
+        if !self.support_128_bit {
+            return Err(Self::Error::custom("i128 is not supported"));
+        }
+        let token = &mut *self.next_token("deserialize_i128")?;
+        match token {
+            CanonicalToken::I128(v) if self.deserialize_128_as == Deserialize128As::Integer => {
+                visitor.visit_i128(*v)
+            }
+            CanonicalToken::Str(v) if self.deserialize_128_as == Deserialize128As::Str => {
+                match v.parse() {
+                    Ok(v) => visitor.visit_i128(v),
+                    Err(_) => Err(Self::Error::invalid_value(Unexpected::Str(v), &visitor)),
+                }
+            }
+            CanonicalToken::Bytes(v) if self.deserialize_128_as == Deserialize128As::Bytes => {
+                match <[u8; 16]>::try_from(v.as_slice()) {
+                    Ok(bytes) => visitor.visit_i128(i128::from_be_bytes(bytes)),
+                    Err(_) => Err(Self::Error::invalid_value(Unexpected::Bytes(v), &visitor)),
+                }
+            }
+            _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
     }
 
@@ -285,7 +381,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_u8", visitor);
+        let token = &mut *self.next_token("deserialize_u8")?;
         if let CanonicalToken::U8(v) = token {
             visitor.visit_u8(*v)
         } else {
@@ -297,7 +394,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_u16", visitor);
+        let token = &mut *self.next_token("deserialize_u16")?;
         if let CanonicalToken::U16(v) = token {
             visitor.visit_u16(*v)
         } else {
@@ -309,7 +407,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_u32", visitor);
+        let token = &mut *self.next_token("deserialize_u32")?;
         if let CanonicalToken::U32(v) = token {
             visitor.visit_u32(*v)
         } else {
@@ -321,7 +420,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_u64", visitor);
+        let token = &mut *self.next_token("deserialize_u64")?;
         if let CanonicalToken::U64(v) = token {
             visitor.visit_u64(*v)
         } else {
@@ -333,11 +433,28 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
-        if let CanonicalToken::U128(v) = token {
-            visitor.visit_u128(*v)
-        } else {
-            Err(Self::Error::invalid_type((token).into(), &visitor))
+        let visitor = self.record_visits("deserialize_u128", visitor);
+        if !self.support_128_bit {
+            return Err(Self::Error::custom("u128 is not supported"));
+        }
+        let token = &mut *self.next_token("deserialize_u128")?;
+        match token {
+            CanonicalToken::U128(v) if self.deserialize_128_as == Deserialize128As::Integer => {
+                visitor.visit_u128(*v)
+            }
+            CanonicalToken::Str(v) if self.deserialize_128_as == Deserialize128As::Str => {
+                match v.parse() {
+                    Ok(v) => visitor.visit_u128(v),
+                    Err(_) => Err(Self::Error::invalid_value(Unexpected::Str(v), &visitor)),
+                }
+            }
+            CanonicalToken::Bytes(v) if self.deserialize_128_as == Deserialize128As::Bytes => {
+                match <[u8; 16]>::try_from(v.as_slice()) {
+                    Ok(bytes) => visitor.visit_u128(u128::from_be_bytes(bytes)),
+                    Err(_) => Err(Self::Error::invalid_value(Unexpected::Bytes(v), &visitor)),
+                }
+            }
+            _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
     }
 
@@ -345,7 +462,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_f32", visitor);
+        let token = &mut *self.next_token("deserialize_f32")?;
         if let CanonicalToken::F32(v) = token {
             visitor.visit_f32(*v)
         } else {
@@ -357,7 +475,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_f64", visitor);
+        let token = &mut *self.next_token("deserialize_f64")?;
         if let CanonicalToken::F64(v) = token {
             visitor.visit_f64(*v)
         } else {
@@ -369,11 +488,18 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
-        if let CanonicalToken::Char(v) = token {
-            visitor.visit_char(*v)
-        } else {
-            Err(Self::Error::invalid_type((token).into(), &visitor))
+        let visitor = self.record_visits("deserialize_char", visitor);
+        let token = &mut *self.next_token("deserialize_char")?;
+        match token {
+            CanonicalToken::Char(v) => visitor.visit_char(*v),
+            CanonicalToken::Str(v) if self.char_as_str => {
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => visitor.visit_char(c),
+                    _ => Err(Self::Error::invalid_value(Unexpected::Str(v), &visitor)),
+                }
+            }
+            _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
     }
 
@@ -381,13 +507,15 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_str", visitor);
+        let token = &mut *self.next_token("deserialize_str")?;
         if let CanonicalToken::Str(v) = token {
-            if self.zero_copy {
-                visitor.visit_borrowed_str(v)
+            let delivery = if self.zero_copy {
+                Delivery::Borrowed
             } else {
-                visitor.visit_str(v)
-            }
+                Delivery::Transient
+            };
+            self.visit_str_contents(v, visitor, delivery)
         } else {
             Err(Self::Error::invalid_type((token).into(), &visitor))
         }
@@ -397,9 +525,10 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_string", visitor);
+        let token = &mut *self.next_token("deserialize_string")?;
         if let CanonicalToken::Str(v) = token {
-            visitor.visit_string(mem::take(v))
+            self.visit_str_contents(v, visitor, Delivery::Owned)
         } else {
             Err(Self::Error::invalid_type((token).into(), &visitor))
         }
@@ -409,15 +538,21 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
-        if let CanonicalToken::Bytes(v) = token {
-            if self.zero_copy {
-                visitor.visit_borrowed_bytes(v)
-            } else {
-                visitor.visit_bytes(v)
+        let visitor = self.record_visits("deserialize_bytes", visitor);
+        let token = &mut *self.next_token("deserialize_bytes")?;
+        match token {
+            CanonicalToken::Bytes(v) => {
+                let delivery = if self.zero_copy {
+                    Delivery::Borrowed
+                } else {
+                    Delivery::Transient
+                };
+                self.visit_bytes_contents(v, visitor, delivery)
             }
-        } else {
-            Err(Self::Error::invalid_type((token).into(), &visitor))
+            CanonicalToken::Seq { len } if self.deserialize_bytes_as_seq => {
+                self.visit_seq_contents(*len, visitor, "deserialize_bytes")
+            }
+            _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
     }
 
@@ -425,11 +560,14 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
-        if let CanonicalToken::Bytes(v) = token {
-            visitor.visit_byte_buf(mem::take(v))
-        } else {
-            Err(Self::Error::invalid_type((token).into(), &visitor))
+        let visitor = self.record_visits("deserialize_byte_buf", visitor);
+        let token = &mut *self.next_token("deserialize_byte_buf")?;
+        match token {
+            CanonicalToken::Bytes(v) => self.visit_bytes_contents(v, visitor, Delivery::Owned),
+            CanonicalToken::Seq { len } if self.deserialize_bytes_as_seq => {
+                self.visit_seq_contents(*len, visitor, "deserialize_byte_buf")
+            }
+            _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
     }
 
@@ -437,9 +575,11 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        match self.next_token()? {
+        let visitor = self.record_visits("deserialize_option", visitor);
+        match &mut *self.next_token("deserialize_option")? {
             CanonicalToken::Some => visitor.visit_some(self),
             CanonicalToken::None => visitor.visit_none(),
+            CanonicalToken::Unit if self.deserialize_unit_as_none => visitor.visit_none(),
             token => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
     }
@@ -448,7 +588,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_unit", visitor);
+        let token = &mut *self.next_token("deserialize_unit")?;
         if let CanonicalToken::Unit = token {
             visitor.visit_unit()
         } else {
@@ -464,7 +605,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_unit_struct", visitor);
+        let token = &mut *self.next_token("deserialize_unit_struct")?;
         if let CanonicalToken::UnitStruct { name: struct_name } = token {
             if name == *struct_name {
                 visitor.visit_unit()
@@ -484,7 +626,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_newtype_struct", visitor);
+        let token = &mut *self.next_token("deserialize_newtype_struct")?;
         if let CanonicalToken::NewtypeStruct { name: struct_name } = token {
             if name == *struct_name {
                 visitor.visit_newtype_struct(self)
@@ -500,19 +643,10 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_seq", visitor);
+        let token = &mut *self.next_token("deserialize_seq")?;
         if let CanonicalToken::Seq { len } = token {
-            let mut access = SeqAccess {
-                deserializer: self,
-
-                len: *len,
-
-                end_token: EndToken::Seq,
-                ended: false,
-            };
-            let result = visitor.visit_seq(&mut access)?;
-            access.assert_ended()?;
-            Ok(result)
+            self.visit_seq_contents(*len, visitor, "deserialize_seq")
         } else {
             Err(Self::Error::invalid_type((token).into(), &visitor))
         }
@@ -522,7 +656,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_tuple", visitor);
+        let token = &mut *self.next_token("deserialize_tuple")?;
         if let CanonicalToken::Tuple { len: token_len } = token {
             if len == *token_len {
                 let mut access = SeqAccess {
@@ -534,7 +669,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
                     ended: false,
                 };
                 let result = visitor.visit_seq(&mut access)?;
-                access.assert_ended()?;
+                access.assert_ended("deserialize_tuple")?;
                 Ok(result)
             } else {
                 Err(Self::Error::invalid_length(*token_len, &visitor))
@@ -553,7 +688,8 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_tuple_struct", visitor);
+        let token = &mut *self.next_token("deserialize_tuple_struct")?;
         if let CanonicalToken::TupleStruct {
             name: token_name,
             len: token_len,
@@ -573,7 +709,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
                     ended: false,
                 };
                 let result = visitor.visit_seq(&mut access)?;
-                access.assert_ended()?;
+                access.assert_ended("deserialize_tuple_struct")?;
                 Ok(result)
             }
         } else {
@@ -585,18 +721,21 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_map", visitor);
+        let token = &mut *self.next_token("deserialize_map")?;
         if let CanonicalToken::Map { len } = token {
             let mut access = MapAccess {
                 deserializer: self,
 
                 len: *len,
+                fields: None,
+                keys: Vec::new(),
 
                 end_token: EndToken::Map,
                 ended: false,
             };
             let result = visitor.visit_map(&mut access)?;
-            access.assert_ended()?;
+            access.assert_ended("deserialize_map")?;
             Ok(result)
         } else {
             Err(Self::Error::invalid_type((token).into(), &visitor))
@@ -606,13 +745,14 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     fn deserialize_struct<V>(
         self,
         name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_struct", visitor);
+        let token = &mut *self.next_token("deserialize_struct")?;
 
         match token {
             CanonicalToken::Struct {
@@ -620,22 +760,25 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
                 len,
             } => {
                 if name == *token_name {
+                    let deny_unknown_fields = self.deny_unknown_fields;
                     let mut access = MapAccess {
                         deserializer: self,
 
                         len: Some(*len),
+                        fields: deny_unknown_fields.then(|| fields),
+                        keys: Vec::new(),
 
                         end_token: EndToken::Struct,
                         ended: false,
                     };
                     let result = visitor.visit_map(&mut access)?;
-                    access.assert_ended()?;
+                    access.assert_ended("deserialize_struct")?;
                     Ok(result)
                 } else {
                     Err(Self::Error::invalid_value((token).into(), &visitor))
                 }
             }
-            CanonicalToken::Seq { len } => {
+            CanonicalToken::Seq { len } if self.deserialize_struct_from_seq => {
                 let mut access = SeqAccess {
                     deserializer: self,
 
@@ -645,7 +788,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
                     ended: false,
                 };
                 let result = visitor.visit_seq(&mut access)?;
-                access.assert_ended()?;
+                access.assert_ended("deserialize_struct")?;
                 Ok(result)
             }
             _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
@@ -661,21 +804,14 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_enum", visitor);
+        let token = &mut *self.next_token("deserialize_enum")?;
         match token {
-            CanonicalToken::UnitVariant {
-                name: token_name, ..
-            }
-            | CanonicalToken::NewtypeVariant {
-                name: token_name, ..
-            }
-            | CanonicalToken::TupleVariant {
-                name: token_name, ..
-            }
-            | CanonicalToken::StructVariant {
-                name: token_name, ..
-            } => {
-                if name == *token_name {
+            CanonicalToken::UnitVariant { id, .. }
+            | CanonicalToken::NewtypeVariant { id, .. }
+            | CanonicalToken::TupleVariant { id, .. }
+            | CanonicalToken::StructVariant { id, .. } => {
+                if name == id.name {
                     // `EnumDeserializer` takes care of the enum deserialization, which will consume
                     // this token later.
                     self.revisit_token(token);
@@ -684,6 +820,28 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
                     Err(Self::Error::invalid_value((token).into(), &visitor))
                 }
             }
+            CanonicalToken::Str(_) if self.deserialize_enum_from_map => {
+                // `MapEnumAccess` deserializes the variant name from this token.
+                self.revisit_token(token);
+                visitor.visit_enum(MapEnumAccess {
+                    deserializer: self,
+                    unit: true,
+                })
+            }
+            CanonicalToken::Map { .. } if self.deserialize_enum_from_map => {
+                let value = visitor.visit_enum(MapEnumAccess {
+                    deserializer: &mut *self,
+                    unit: false,
+                })?;
+                if matches!(
+                    *self.next_token("deserialize_enum")?,
+                    CanonicalToken::MapEnd
+                ) {
+                    Ok(value)
+                } else {
+                    Err(Error::ExpectedMapEnd)
+                }
+            }
             _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
     }
@@ -692,10 +850,11 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let token = self.next_token()?;
+        let visitor = self.record_visits("deserialize_identifier", visitor);
+        let token = &mut *self.next_token("deserialize_identifier")?;
         match token {
-            CanonicalToken::Str(v) => visitor.visit_str(v),
-            CanonicalToken::Bytes(v) => visitor.visit_bytes(v),
+            CanonicalToken::Str(v) => self.visit_str_contents(v, visitor, Delivery::Transient),
+            CanonicalToken::Bytes(v) => self.visit_bytes_contents(v, visitor, Delivery::Transient),
             CanonicalToken::Field(v) => visitor.visit_str(v),
             _ => Err(Self::Error::invalid_type((token).into(), &visitor)),
         }
@@ -705,7 +864,7 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        self.any("deserialize_ignored_any", visitor)
     }
 
     fn is_human_readable(&self) -> bool {
@@ -714,6 +873,50 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
 }
 
 impl<'a> Deserializer<'a> {
+    /// Creates a new `Deserializer` of the given tokens using the default configuration.
+    ///
+    /// This is equivalent to `Deserializer::builder(tokens).build()`. To configure the
+    /// `Deserializer`, use [`builder()`] instead.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::new([Token::Bool(true)]);
+    ///
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// ```
+    ///
+    /// [`builder()`]: Deserializer::builder()
+    #[must_use]
+    pub fn new<T>(tokens: T) -> Self
+    where
+        T: IntoIterator<Item = Token>,
+    {
+        Self::builder(tokens).build()
+    }
+
+    /// Returns a [`Builder`] of the given tokens, used to configure a `Deserializer`.
+    ///
+    /// Unlike [`Builder::tokens()`], this only accepts [`Token`]s, so that the item type of empty
+    /// inputs such as `Deserializer::builder([])` can still be inferred. Values convertible into
+    /// `Token`s can be provided through [`Builder::tokens()`] instead.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::Deserializer;
+    ///
+    /// let mut deserializer = Deserializer::builder([]).tokens([42u32]).build();
+    ///
+    /// assert_ok_eq!(u32::deserialize(&mut deserializer), 42);
+    /// ```
     #[must_use]
     pub fn builder<T>(tokens: T) -> Builder
     where
@@ -722,681 +925,3870 @@ impl<'a> Deserializer<'a> {
         Builder::new(tokens)
     }
 
-    fn next_token(&mut self) -> Result<&'a mut CanonicalToken, Error> {
-        loop {
-            let token = self
-                .revisited_token
-                .take()
-                .into_iter()
-                .chain(&mut self.tokens)
-                .next()
-                .ok_or(Error::EndOfTokens)?;
-            if !matches!(token, CanonicalToken::SkippedField(_)) {
-                return Ok(token);
-            }
-        }
+    /// Deserializes a value from a single token, asserting that the token was consumed.
+    ///
+    /// This is a shorthand for testing values represented by exactly one token, such as map keys,
+    /// identifiers, and unit enum variants, without constructing a `Deserializer` manually. The
+    /// token is deserialized using a `Deserializer` with the default configuration.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] if the value cannot be deserialized from the token.
+    ///
+    /// # Panics
+    /// Panics if deserialization succeeds without consuming the token.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// use serde_derive::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// enum Key {
+    ///     Foo,
+    ///     Bar,
+    /// }
+    ///
+    /// assert_ok_eq!(
+    ///     Deserializer::single::<Key>(Token::UnitVariant {
+    ///         name: "Key",
+    ///         variant_index: 1,
+    ///         variant: "Bar",
+    ///     }),
+    ///     Key::Bar
+    /// );
+    /// ```
+    #[track_caller]
+    pub fn single<T>(token: Token) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let mut deserializer = Deserializer::builder([token]).build();
+        let value = T::deserialize(&mut deserializer)?;
+        assert!(
+            deserializer.position() == 1,
+            "assertion failed, expected the token to be consumed"
+        );
+        Ok(value)
     }
 
-    fn revisit_token(&mut self, token: &'a mut CanonicalToken) {
-        self.revisited_token = Some(token);
+    /// Deserializes a value using a [`DeserializeSeed`].
+    ///
+    /// This allows testing stateful deserialization, such as interners or arena allocators. As the
+    /// seed is passed by value, a seed holding a mutable reference to its state allows the state
+    /// to be asserted after deserialization completes.
+    ///
+    /// # Errors
+    /// Returns any [`Error`] returned by the seed.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::de::{
+    ///     Deserialize,
+    ///     DeserializeSeed,
+    /// };
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// struct Interner<'a>(&'a mut Vec<String>);
+    ///
+    /// impl<'de> DeserializeSeed<'de> for Interner<'_> {
+    ///     type Value = usize;
+    ///
+    ///     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    ///     where
+    ///         D: serde::Deserializer<'de>,
+    ///     {
+    ///         let string = String::deserialize(deserializer)?;
+    ///         Ok(
+    ///             match self.0.iter().position(|interned| *interned == string) {
+    ///                 Some(index) => index,
+    ///                 None => {
+    ///                     self.0.push(string);
+    ///                     self.0.len() - 1
+    ///                 }
+    ///             },
+    ///         )
+    ///     }
+    /// }
+    ///
+    /// let mut interned = Vec::new();
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Str("foo".to_owned()),
+    ///     Token::Str("bar".to_owned()),
+    ///     Token::Str("foo".to_owned()),
+    /// ])
+    /// .build();
+    ///
+    /// assert_ok_eq!(deserializer.deserialize_seed(Interner(&mut interned)), 0);
+    /// assert_ok_eq!(deserializer.deserialize_seed(Interner(&mut interned)), 1);
+    /// assert_ok_eq!(deserializer.deserialize_seed(Interner(&mut interned)), 0);
+    /// assert_eq!(interned, ["foo", "bar"]);
+    /// ```
+    pub fn deserialize_seed<S>(&mut self, seed: S) -> Result<S::Value, Error>
+    where
+        S: DeserializeSeed<'a>,
+    {
+        seed.deserialize(self)
     }
-}
-
-#[derive(Clone, Copy, Debug)]
-enum EndToken {
-    Seq,
-    Tuple,
-    TupleStruct,
-    TupleVariant,
-    Map,
-    Struct,
-    StructVariant,
-}
 
-impl PartialEq<EndToken> for CanonicalToken {
-    fn eq(&self, other: &EndToken) -> bool {
-        matches!(
-            (self, other),
-            (Self::SeqEnd, EndToken::Seq)
-                | (Self::TupleEnd, EndToken::Tuple)
-                | (Self::TupleStructEnd, EndToken::TupleStruct)
-                | (Self::TupleVariantEnd, EndToken::TupleVariant)
-                | (Self::MapEnd, EndToken::Map)
-                | (Self::StructEnd, EndToken::Struct)
-                | (Self::StructVariantEnd, EndToken::StructVariant)
-        )
-    }
-}
-
-struct SeqAccess<'a, 'b> {
-    deserializer: &'a mut Deserializer<'b>,
-
-    len: Option<usize>,
-
-    end_token: EndToken,
-    ended: bool,
-}
-
-impl<'de> de::SeqAccess<'de> for SeqAccess<'_, 'de> {
-    type Error = Error;
-
-    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    /// Deserializes a value using a function with the signature used by `deserialize_with`.
+    ///
+    /// Functions used with the `#[serde(deserialize_with = "...")]` and `#[serde(with = "...")]`
+    /// attributes have the signature `fn<D>(D) -> Result<T, D::Error>`. This method runs such a
+    /// function using this `Deserializer`, allowing these functions to be tested without defining
+    /// wrapper types.
+    ///
+    /// # Errors
+    /// Returns any [`Error`] returned by the provided function.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// fn deserialize_negated<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    /// where
+    ///     D: serde::Deserializer<'de>,
+    /// {
+    ///     bool::deserialize(deserializer).map(|value| !value)
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    ///
+    /// assert_ok_eq!(deserializer.deserialize_with(deserialize_negated), false);
+    /// ```
+    pub fn deserialize_with<'b, T, F>(&'b mut self, deserialize: F) -> Result<T, Error>
     where
-        T: DeserializeSeed<'de>,
+        F: FnOnce(&'b mut Deserializer<'a>) -> Result<T, Error>,
     {
-        if self.ended {
-            return Ok(None);
-        }
-        let token = self.deserializer.next_token()?;
-        if *token == self.end_token {
-            self.ended = true;
-            return Ok(None);
-        }
-        self.deserializer.revisit_token(token);
-        seed.deserialize(&mut *self.deserializer).map(Some)
+        deserialize(self)
     }
 
-    fn size_hint(&self) -> Option<usize> {
-        self.len
+    /// Returns the [`Visitor`] methods called during deserialization, in the order they were
+    /// called.
+    ///
+    /// This allows asserting which path was taken through a [`Deserialize`] implementation, which
+    /// is often not visible from the deserialized value itself. For example, this can be used to
+    /// verify that [`visit_string()`] is called, allowing the visitor to take ownership of the
+    /// contents rather than copying them.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Visit,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+    ///
+    /// assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+    /// assert_eq!(deserializer.visits(), [Visit::String]);
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    /// [`visit_string()`]: serde::de::Visitor::visit_string()
+    /// [`Visitor`]: serde::de::Visitor
+    #[must_use]
+    pub fn visits(&self) -> Vec<Visit> {
+        self.visits.borrow().clone()
     }
-}
 
-impl SeqAccess<'_, '_> {
-    fn assert_ended(&mut self) -> Result<(), Error> {
-        if !self.ended && *self.deserializer.next_token()? != self.end_token {
-            return Err(Error::expected_end_token(self.end_token));
-        }
-        self.ended = true;
-        Ok(())
+    /// Returns how each `Str` and `Bytes` token was delivered to a [`Visitor`], in the order they
+    /// were consumed.
+    ///
+    /// This allows asserting that zero-copy deserialization actually results in borrowing: with
+    /// [`zero_copy()`] enabled, a type that borrows its strings should only receive
+    /// [`Delivery::Borrowed`] contents. When the `safe` feature is enabled, contents can't be
+    /// borrowed from the input, and are delivered as [`Delivery::Transient`] instead. Note that
+    /// this reports how the contents were offered to the `Visitor`; a `Visitor` receiving
+    /// borrowed contents may still copy them.
+    ///
+    /// Tokens that were consumed without being delivered as a string or byte slice, such as those
+    /// parsed into other values, are not included.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Delivery,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Tuple { len: 2 },
+    ///     Token::Str("foo".to_owned()),
+    ///     Token::Str("bar".to_owned()),
+    ///     Token::TupleEnd,
+    /// ])
+    /// .build();
+    ///
+    /// assert_ok!(<(String, String)>::deserialize(&mut deserializer));
+    /// assert_eq!(
+    ///     deserializer.deliveries(),
+    ///     [Delivery::Owned, Delivery::Owned]
+    /// );
+    /// ```
+    ///
+    /// [`Visitor`]: serde::de::Visitor
+    /// [`zero_copy()`]: Builder::zero_copy()
+    #[must_use]
+    pub fn deliveries(&self) -> Vec<Delivery> {
+        self.deliveries.clone()
     }
-}
-
-struct MapAccess<'a, 'b> {
-    deserializer: &'a mut Deserializer<'b>,
-
-    len: Option<usize>,
-
-    end_token: EndToken,
-    ended: bool,
-}
 
-impl<'de> de::MapAccess<'de> for MapAccess<'_, 'de> {
-    type Error = Error;
+    /// Returns the next [`Token`] to be deserialized without consuming it.
+    ///
+    /// This is useful for asserting what is about to be read from within a test-only
+    /// [`Deserialize`] implementation or [`Visitor`] callback. [`Token::SkippedField`]s are passed
+    /// over, since they are never read by a [`Deserialize`] implementation. As the tokens are
+    /// stored in a canonical form, an owned `Token` is returned.
+    ///
+    /// Returns [`None`] if there are no tokens left.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::{
+    ///     assert_matches,
+    ///     assert_none,
+    ///     assert_ok_eq,
+    /// };
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    ///
+    /// assert_matches!(deserializer.peek_token(), Some(Token::Bool(true)));
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// assert_none!(deserializer.peek_token());
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    /// [`Visitor`]: serde::de::Visitor
+    #[must_use]
+    pub fn peek_token(&self) -> Option<Token> {
+        self.peek().cloned().map(Token::from)
+    }
 
-    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
-    where
-        K: DeserializeSeed<'de>,
-    {
-        if self.ended {
-            return Ok(None);
-        }
-        let token = self.deserializer.next_token()?;
-        if *token == self.end_token {
-            self.ended = true;
-            return Ok(None);
+    /// Consumes the tokens of exactly one value, discarding them.
+    ///
+    /// Compound values are skipped in their entirety, including their end token, and any
+    /// [`Some`], [`NewtypeStruct`], or [`NewtypeVariant`] tokens are skipped along with the value
+    /// they wrap. Unlike deserializing into [`IgnoredAny`], this does not require the
+    /// `Deserializer` to be [`self_describing()`], making it useful for testing partial or
+    /// streaming consumers that must explicitly discard values.
+    ///
+    /// The skipped tokens are recorded in the [`consumptions()`] as consumed by `skip_value`.
+    ///
+    /// # Errors
+    /// Returns an [`Error::EndOfTokens`] if the tokens end before a complete value is skipped, or
+    /// an [`Error::InvalidType`] if the next token is the end of a compound value. In the
+    /// latter case, the end token is not consumed.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::{
+    ///     assert_ok,
+    ///     assert_ok_eq,
+    /// };
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Seq { len: Some(2) },
+    ///     Token::U8(1),
+    ///     Token::U8(2),
+    ///     Token::SeqEnd,
+    ///     Token::Bool(true),
+    /// ])
+    /// .self_describing(false)
+    /// .build();
+    ///
+    /// assert_ok!(deserializer.skip_value());
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// ```
+    ///
+    /// [`consumptions()`]: Deserializer::consumptions()
+    /// [`IgnoredAny`]: serde::de::IgnoredAny
+    /// [`NewtypeStruct`]: Token::NewtypeStruct
+    /// [`NewtypeVariant`]: Token::NewtypeVariant
+    /// [`self_describing()`]: Builder::self_describing()
+    /// [`Some`]: Token::Some
+    pub fn skip_value(&mut self) -> Result<(), Error> {
+        let mut nesting = Nesting::default();
+        loop {
+            let token = &mut *self.next_token("skip_value")?;
+            match nesting.step(token) {
+                Step::Continues => {}
+                Step::Ends => {
+                    if nesting.depth() == 0 {
+                        return Ok(());
+                    }
+                }
+                Step::Unbalanced => {
+                    let error = Error::invalid_type((&mut *token).into(), &"a value");
+                    self.revisit_token(token);
+                    return Err(error);
+                }
+            }
         }
-        self.deserializer.revisit_token(token);
-        seed.deserialize(&mut *self.deserializer).map(Some)
     }
 
-    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
-    where
-        V: DeserializeSeed<'de>,
-    {
-        seed.deserialize(&mut *self.deserializer)
+    /// Returns every token consumed so far, together with the [`serde::Deserializer`] method that
+    /// consumed it, in the order they were consumed.
+    ///
+    /// This trace describes the behavior of a [`Deserialize`] implementation rather than just its
+    /// result. Tokens that are only inspected internally before being handed to another method are
+    /// attributed to the method that finally consumes them.
+    ///
+    /// Consumptions are only recorded if [`record_consumptions()`] was enabled. Otherwise, this is
+    /// always empty.
+    ///
+    /// See also [`trace()`] for a deterministic string form suitable for snapshot testing.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .record_consumptions(true)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// assert_eq!(deserializer.consumptions()[0].method(), "deserialize_bool");
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    /// [`record_consumptions()`]: Builder::record_consumptions()
+    /// [`trace()`]: Deserializer::trace()
+    #[must_use]
+    pub fn consumptions(&self) -> &[Consumption] {
+        &self.consumptions
     }
 
-    fn size_hint(&self) -> Option<usize> {
-        self.len
+    /// Returns the consumption trace as a string, with one consumed token per line.
+    ///
+    /// Each line has the form `method: token`, where `method` is the [`serde::Deserializer`]
+    /// method that consumed the token. The output is deterministic, allowing the behavior of a
+    /// [`Deserialize`] implementation to be compared against a golden snapshot.
+    ///
+    /// Like [`consumptions()`], this requires [`record_consumptions()`] to be enabled.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Seq { len: Some(1) },
+    ///     Token::Bool(true),
+    ///     Token::SeqEnd,
+    /// ])
+    /// .record_consumptions(true)
+    /// .build();
+    ///
+    /// assert_ok_eq!(Vec::<bool>::deserialize(&mut deserializer), [true]);
+    /// assert_eq!(
+    ///     deserializer.trace(),
+    ///     "deserialize_seq: Seq { len: Some(1) }\n\
+    ///      deserialize_bool: Bool(true)\n\
+    ///      next_element_seed: SeqEnd\n"
+    /// );
+    /// ```
+    ///
+    /// [`consumptions()`]: Deserializer::consumptions()
+    /// [`Deserialize`]: serde::Deserialize
+    /// [`record_consumptions()`]: Builder::record_consumptions()
+    #[must_use]
+    pub fn trace(&self) -> String {
+        let mut trace = String::new();
+        for consumption in &self.consumptions {
+            // Writing to a `String` never fails.
+            let _ = writeln!(trace, "{consumption}");
+        }
+        trace
     }
-}
 
-impl MapAccess<'_, '_> {
-    fn assert_ended(&mut self) -> Result<(), Error> {
-        if !self.ended && *self.deserializer.next_token()? != self.end_token {
-            return Err(Error::expected_end_token(self.end_token));
-        }
-        self.ended = true;
-        Ok(())
+    /// Returns the number of tokens that have not yet been consumed.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true), Token::Unit]).build();
+    ///
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// assert_eq!(deserializer.remaining(), 1);
+    /// ```
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.revisited_token
+            .as_deref()
+            .into_iter()
+            .chain(self.tokens.as_slice())
+            .filter(|token| !matches!(token, CanonicalToken::SkippedField(_)))
+            .count()
     }
-}
 
-struct EnumAccess<'a, 'b> {
-    deserializer: &'a mut Deserializer<'b>,
-}
+    /// Asserts that the end of the input has been reached.
+    ///
+    /// This should be called after deserializing the root value to ensure no tokens were left
+    /// unconsumed. If [`allow_trailing_tokens()`] was enabled, trailing tokens are permitted and
+    /// can instead be queried using [`remaining()`].
+    ///
+    /// # Errors
+    /// Returns an [`Error::TrailingTokens`] if any tokens remain and trailing tokens are not
+    /// allowed.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::{
+    ///     assert_err_eq,
+    ///     assert_ok_eq,
+    /// };
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Error,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true), Token::Unit]).build();
+    ///
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// assert_err_eq!(deserializer.end(), Error::TrailingTokens(1));
+    /// ```
+    ///
+    /// [`allow_trailing_tokens()`]: Builder::allow_trailing_tokens()
+    /// [`remaining()`]: Deserializer::remaining()
+    pub fn end(&self) -> Result<(), Error> {
+        let remaining = self.remaining();
+        if remaining > 0 && !self.allow_trailing_tokens {
+            Err(Error::TrailingTokens(remaining))
+        } else {
+            Ok(())
+        }
+    }
 
-impl<'a, 'de> de::EnumAccess<'de> for EnumAccess<'a, 'de> {
-    type Error = Error;
-    type Variant = VariantAccess<'a, 'de>;
+    /// Consumes the `Deserializer`, returning the tokens that have not been consumed.
+    ///
+    /// This allows deserializing a stream in multiple phases, such as deserializing a header and
+    /// then handing the remaining tokens to another `Deserializer` for a different type.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer =
+    ///     Deserializer::builder([Token::U8(2), Token::Str("foo".to_owned())]).build();
+    ///
+    /// assert_ok_eq!(u8::deserialize(&mut deserializer), 2);
+    ///
+    /// let mut deserializer = Deserializer::builder(deserializer.into_tokens()).build();
+    ///
+    /// assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+    /// ```
+    #[must_use]
+    pub fn into_tokens(self) -> Tokens {
+        Tokens(
+            self.revisited_token
+                .as_deref()
+                .into_iter()
+                .chain(self.tokens.as_slice())
+                .cloned()
+                .collect(),
+        )
+    }
 
-    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    /// Deserializes any value on behalf of the given [`serde::Deserializer`] method.
+    ///
+    /// Both [`deserialize_any()`] and [`deserialize_ignored_any()`] are implemented using this, so
+    /// that each records only itself.
+    ///
+    /// [`deserialize_any()`]: de::Deserializer::deserialize_any()
+    /// [`deserialize_ignored_any()`]: de::Deserializer::deserialize_ignored_any()
+    #[allow(clippy::too_many_lines)] // Every token must be handled.
+    fn any<V>(&mut self, method: &'static str, visitor: V) -> Result<V::Value, Error>
     where
-        V: DeserializeSeed<'de>,
+        V: de::Visitor<'a>,
     {
-        let value = seed.deserialize(EnumDeserializer {
-            deserializer: self.deserializer,
-        })?;
-        Ok((
-            value,
-            VariantAccess {
-                deserializer: self.deserializer,
-            },
-        ))
-    }
-}
+        let visitor = self.record_visits(method, visitor);
+        if !self.self_describing {
+            return Err(Error::NotSelfDescribing);
+        }
+        // Self-describing formats have no representation of newtype structs.
+        while self.content_compatible
+            && matches!(self.peek(), Some(CanonicalToken::NewtypeStruct { .. }))
+        {
+            self.next_token(method)?;
+        }
+        let token = &mut *self.next_token(method)?;
+        match token {
+            CanonicalToken::Bool(v) => visitor.visit_bool(*v),
+            CanonicalToken::I8(v) => visitor.visit_i8(*v),
+            CanonicalToken::I16(v) => visitor.visit_i16(*v),
+            CanonicalToken::I32(v) => visitor.visit_i32(*v),
+            CanonicalToken::I64(v) => visitor.visit_i64(*v),
+            CanonicalToken::I128(v) => visitor.visit_i128(*v),
+            CanonicalToken::U8(v) => visitor.visit_u8(*v),
+            CanonicalToken::U16(v) => visitor.visit_u16(*v),
+            CanonicalToken::U32(v) => visitor.visit_u32(*v),
+            CanonicalToken::U64(v) => visitor.visit_u64(*v),
+            CanonicalToken::U128(v) => visitor.visit_u128(*v),
+            CanonicalToken::F32(v) => visitor.visit_f32(*v),
+            CanonicalToken::F64(v) => visitor.visit_f64(*v),
+            CanonicalToken::Char(v) => visitor.visit_char(*v),
+            // Borrowing allows buffering `Deserialize` implementations, such as those derived for
+            // untagged enums, to deserialize borrowed variants.
+            CanonicalToken::Str(v) => {
+                let delivery = if self.zero_copy {
+                    Delivery::Borrowed
+                } else {
+                    Delivery::Owned
+                };
+                self.visit_str_contents(v, visitor, delivery)
+            }
+            CanonicalToken::Bytes(v) => {
+                let delivery = if self.zero_copy {
+                    Delivery::Borrowed
+                } else {
+                    Delivery::Owned
+                };
+                self.visit_bytes_contents(v, visitor, delivery)
+            }
+            CanonicalToken::None => visitor.visit_none(),
+            CanonicalToken::Some => visitor.visit_some(self),
+            CanonicalToken::Unit | CanonicalToken::UnitStruct { .. } => visitor.visit_unit(),
+            CanonicalToken::UnitVariant { id } if self.content_compatible => {
+                visitor.visit_str(id.variant)
+            }
+            CanonicalToken::NewtypeVariant { id }
+            | CanonicalToken::TupleVariant { id, .. }
+            | CanonicalToken::StructVariant { id, .. }
+                if self.content_compatible =>
+            {
+                visitor.visit_map(VariantMapAccess {
+                    deserializer: self,
+                    variant: Some(id.variant),
+                    token,
+                })
+            }
+            CanonicalToken::UnitVariant { .. }
+            | CanonicalToken::NewtypeVariant { .. }
+            | CanonicalToken::TupleVariant { .. }
+            | CanonicalToken::StructVariant { .. } => {
+                // `EnumDeserializer` takes care of the enum deserialization, which will consume
+                // this token later.
+                self.revisit_token(token);
+                visitor.visit_enum(EnumAccess { deserializer: self })
+            }
+            CanonicalToken::NewtypeStruct { .. } => visitor.visit_newtype_struct(self),
+            CanonicalToken::Seq { len } => {
+                let mut access = SeqAccess {
+                    deserializer: self,
 
-struct VariantAccess<'a, 'b> {
-    deserializer: &'a mut Deserializer<'b>,
-}
+                    len: *len,
 
-impl<'de> de::VariantAccess<'de> for VariantAccess<'_, 'de> {
-    type Error = Error;
+                    end_token: EndToken::Seq,
+                    ended: false,
+                };
+                let result = visitor.visit_seq(&mut access)?;
+                access.assert_ended(method)?;
+                Ok(result)
+            }
+            CanonicalToken::Tuple { len } => {
+                let mut access = SeqAccess {
+                    deserializer: self,
 
-    fn unit_variant(self) -> Result<(), Self::Error> {
-        Ok(())
-    }
+                    len: Some(*len),
 
-    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
-    where
-        T: DeserializeSeed<'de>,
-    {
-        seed.deserialize(self.deserializer)
-    }
+                    end_token: EndToken::Tuple,
+                    ended: false,
+                };
+                let result = visitor.visit_seq(&mut access)?;
+                access.assert_ended(method)?;
+                Ok(result)
+            }
+            CanonicalToken::TupleStruct { name: _, len } => {
+                let mut access = SeqAccess {
+                    deserializer: self,
 
-    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        visitor.visit_seq(SeqAccess {
-            deserializer: self.deserializer,
+                    len: Some(*len),
 
-            len: Some(len),
+                    end_token: EndToken::TupleStruct,
+                    ended: false,
+                };
+                let result = visitor.visit_seq(&mut access)?;
+                access.assert_ended(method)?;
+                Ok(result)
+            }
+            CanonicalToken::Map { len } => {
+                let mut access = MapAccess {
+                    deserializer: self,
 
-            end_token: EndToken::TupleVariant,
-            ended: false,
-        })
-    }
+                    len: *len,
+                    fields: None,
+                    keys: Vec::new(),
 
-    fn struct_variant<V>(
-        self,
-        _fields: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        visitor.visit_map(MapAccess {
-            deserializer: self.deserializer,
+                    end_token: EndToken::Map,
+                    ended: false,
+                };
+                let result = visitor.visit_map(&mut access)?;
+                access.assert_ended(method)?;
+                Ok(result)
+            }
+            CanonicalToken::Field(v) => visitor.visit_str(v),
+            CanonicalToken::Struct { name: _, len } => {
+                let mut access = MapAccess {
+                    deserializer: self,
 
-            len: None,
+                    len: Some(*len),
+                    fields: None,
+                    keys: Vec::new(),
 
-            end_token: EndToken::StructVariant,
-            ended: false,
-        })
+                    end_token: EndToken::Struct,
+                    ended: false,
+                };
+                let result = visitor.visit_map(&mut access)?;
+                access.assert_ended(method)?;
+                Ok(result)
+            }
+            _ => Err(Error::invalid_type((token).into(), &visitor)),
+        }
     }
-}
-
-/// Wrapper around `Deserializer` to deserialize enum tokens directly, rather than using
-/// `EnumAccess`.
-///
-/// This is required to ensure the token can be properly deserialized into a variant.
-struct EnumDeserializer<'a, 'b> {
-    deserializer: &'a mut Deserializer<'b>,
-}
-
-impl<'de> de::Deserializer<'de> for EnumDeserializer<'_, 'de> {
-    type Error = Error;
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        match self.deserializer.next_token()? {
-            CanonicalToken::UnitVariant { variant, .. }
-            | CanonicalToken::TupleVariant { variant, .. }
-            | CanonicalToken::NewtypeVariant { variant, .. }
-            | CanonicalToken::StructVariant { variant, .. } => visitor.visit_str(variant),
-            _ => unreachable!(),
+    /// Wraps the visitor passed to the given [`serde::Deserializer`] method, recording which of
+    /// its methods are called.
+    #[cfg_attr(not(target_has_atomic = "ptr"), allow(unused_variables))]
+    fn record_visits<V>(&self, method: &'static str, visitor: V) -> RecordingVisitor<V> {
+        #[cfg(target_has_atomic = "ptr")]
+        if let Some(coverage) = self.coverage {
+            coverage.record_method(method);
+        }
+        RecordingVisitor {
+            visitor,
+            visits: self.visits.clone(),
+            #[cfg(target_has_atomic = "ptr")]
+            coverage: self.coverage,
         }
     }
 
-    fn deserialize_bool<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+    /// Returns the number of tokens that have been consumed.
+    pub(crate) fn position(&self) -> usize {
+        self.tokens.position() - usize::from(self.revisited_token.is_some())
+    }
+
+    /// Returns a message describing the consumption position and the remaining tokens.
+    #[cfg(feature = "std")]
+    fn dump(&self) -> String {
+        let remaining = self
+            .revisited_token
+            .as_deref()
+            .into_iter()
+            .chain(self.tokens.as_slice())
+            .cloned()
+            .map(Token::from)
+            .collect::<Vec<_>>();
+        alloc::format!(
+            "Deserializer consumed {} tokens, remaining tokens: {remaining:?}",
+            self.position()
+        )
     }
 
-    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_u32(visitor)
+    /// Returns the message printed when the `Deserializer` is dropped, if any.
+    ///
+    /// A message is only printed if the `Deserializer` dumps on panic and the thread is currently
+    /// panicking.
+    #[cfg(feature = "std")]
+    fn panic_dump(&self) -> Option<String> {
+        (self.dump_on_panic && std::thread::panicking()).then(|| self.dump())
     }
 
-    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    /// Visits the elements of a sequence whose [`Token::Seq`] has already been consumed.
+    fn visit_seq_contents<V>(
+        &mut self,
+        len: Option<usize>,
+        visitor: V,
+        method: &'static str,
+    ) -> Result<V::Value, Error>
     where
-        V: de::Visitor<'de>,
+        V: de::Visitor<'a>,
     {
-        self.deserialize_u32(visitor)
+        let mut access = SeqAccess {
+            deserializer: self,
+
+            len,
+
+            end_token: EndToken::Seq,
+            ended: false,
+        };
+        let result = visitor.visit_seq(&mut access)?;
+        access.assert_ended(method)?;
+        Ok(result)
     }
 
-    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_u32(visitor)
+    /// Returns the next token without consuming it.
+    fn peek(&self) -> Option<&CanonicalToken> {
+        self.revisited_token
+            .as_deref()
+            .into_iter()
+            .chain(self.tokens.as_slice())
+            .find(|token| !matches!(token, CanonicalToken::SkippedField(_)))
     }
 
-    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_u32(visitor)
+    /// Consumes the next token on behalf of the given `Deserializer` method.
+    ///
+    /// Without the `safe` feature, this is a reference into the input that lives for its entire
+    /// lifetime `'a`. With it, the token is moved out of the input instead. In both cases it can be
+    /// dereferenced to a `&mut CanonicalToken`.
+    fn next_token(&mut self, method: &'static str) -> Result<token::IterToken<'a>, Error> {
+        if let Some(fail_after_tokens) = self.fail_after_tokens {
+            if self.consumed >= fail_after_tokens {
+                return Err(Error::Injected(fail_after_tokens));
+            }
+        }
+        loop {
+            let index = self.position();
+            let token = self
+                .revisited_token
+                .take()
+                .into_iter()
+                .chain(&mut self.tokens)
+                .next()
+                .ok_or(Error::EndOfTokens)?;
+            if !matches!(*token, CanonicalToken::SkippedField(_)) {
+                self.consumed += 1;
+                #[cfg(feature = "std")]
+                let log_consumption = self.log_consumption;
+                #[cfg(not(feature = "std"))]
+                let log_consumption = false;
+                if self.record_consumptions || log_consumption {
+                    let consumption = Consumption {
+                        method,
+                        token: (*token).clone().into(),
+                        label: self.labels.get(&index).copied(),
+                        delivery: None,
+                    };
+                    #[cfg(feature = "std")]
+                    if log_consumption {
+                        std::eprintln!("{consumption}");
+                    }
+                    if self.record_consumptions {
+                        self.consumptions.push(consumption);
+                    }
+                }
+                if let Some(key_tokens) = &mut self.key_tokens {
+                    key_tokens.push((*token).clone());
+                }
+                if let CanonicalToken::Error(message) = &*token {
+                    return Err(Error::Custom(message.clone()));
+                }
+                return Ok(token);
+            }
+        }
     }
 
-    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_u32(visitor)
+    /// Records how the most recently consumed `Str` or `Bytes` token is delivered to a visitor.
+    fn deliver(&mut self, delivery: Delivery) {
+        self.deliveries.push(delivery);
+        if let Some(consumption) = self.consumptions.last_mut() {
+            consumption.delivery = Some(delivery);
+        }
     }
 
-    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_u32(visitor)
+    /// Marks the most recently consumed token as no longer consumed.
+    fn unconsume(&mut self) {
+        self.consumed -= 1;
+        self.consumptions.pop();
+        if let Some(key_tokens) = &mut self.key_tokens {
+            key_tokens.pop();
+        }
     }
 
-    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    /// Delivers the contents of a `Str` token to the visitor.
+    ///
+    /// The configured `str_delivery` takes precedence over the `delivery` requested by the calling
+    /// method.
+    #[cfg(not(feature = "safe"))]
+    fn visit_str_contents<V>(
+        &mut self,
+        v: &'a mut String,
+        visitor: V,
+        delivery: Delivery,
+    ) -> Result<V::Value, Error>
     where
-        V: de::Visitor<'de>,
+        V: de::Visitor<'a>,
     {
-        self.deserialize_u32(visitor)
+        let delivery = self.str_delivery.unwrap_or(delivery);
+        self.deliver(delivery);
+        match delivery {
+            Delivery::Borrowed => visitor.visit_borrowed_str(v),
+            Delivery::Transient => visitor.visit_str(v),
+            Delivery::Owned => visitor.visit_string(mem::take(v)),
+        }
     }
 
-    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    /// Delivers the contents of a `Str` token to the visitor.
+    ///
+    /// The configured `str_delivery` takes precedence over the `delivery` requested by the calling
+    /// method. As the token is not borrowed from the input, [`Delivery::Borrowed`] falls back to
+    /// [`Delivery::Transient`].
+    #[cfg(feature = "safe")]
+    fn visit_str_contents<V>(
+        &mut self,
+        v: &mut String,
+        visitor: V,
+        delivery: Delivery,
+    ) -> Result<V::Value, Error>
     where
-        V: de::Visitor<'de>,
+        V: de::Visitor<'a>,
     {
-        match self.deserializer.next_token()? {
-            CanonicalToken::UnitVariant { variant_index, .. }
-            | CanonicalToken::TupleVariant { variant_index, .. }
-            | CanonicalToken::NewtypeVariant { variant_index, .. }
-            | CanonicalToken::StructVariant { variant_index, .. } => {
-                visitor.visit_u32(*variant_index)
-            }
-            _ => unreachable!(),
+        let delivery = match self.str_delivery.unwrap_or(delivery) {
+            Delivery::Borrowed => Delivery::Transient,
+            delivery => delivery,
+        };
+        self.deliver(delivery);
+        match delivery {
+            Delivery::Borrowed | Delivery::Transient => visitor.visit_str(v),
+            Delivery::Owned => visitor.visit_string(mem::take(v)),
         }
     }
 
-    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    /// Delivers the contents of a `Bytes` token to the visitor.
+    ///
+    /// The configured `bytes_delivery` takes precedence over the `delivery` requested by the
+    /// calling method.
+    #[cfg(not(feature = "safe"))]
+    fn visit_bytes_contents<V>(
+        &mut self,
+        v: &'a mut Vec<u8>,
+        visitor: V,
+        delivery: Delivery,
+    ) -> Result<V::Value, Error>
     where
-        V: de::Visitor<'de>,
+        V: de::Visitor<'a>,
     {
-        self.deserialize_u32(visitor)
+        let delivery = self.bytes_delivery.unwrap_or(delivery);
+        self.deliver(delivery);
+        match delivery {
+            Delivery::Borrowed => visitor.visit_borrowed_bytes(v),
+            Delivery::Transient => visitor.visit_bytes(v),
+            Delivery::Owned => visitor.visit_byte_buf(mem::take(v)),
+        }
     }
 
-    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    /// Delivers the contents of a `Bytes` token to the visitor.
+    ///
+    /// The configured `bytes_delivery` takes precedence over the `delivery` requested by the
+    /// calling method. As the token is not borrowed from the input, [`Delivery::Borrowed`] falls
+    /// back to [`Delivery::Transient`].
+    #[cfg(feature = "safe")]
+    fn visit_bytes_contents<V>(
+        &mut self,
+        v: &mut Vec<u8>,
+        visitor: V,
+        delivery: Delivery,
+    ) -> Result<V::Value, Error>
     where
-        V: de::Visitor<'de>,
+        V: de::Visitor<'a>,
     {
-        self.deserialize_u32(visitor)
+        let delivery = match self.bytes_delivery.unwrap_or(delivery) {
+            Delivery::Borrowed => Delivery::Transient,
+            delivery => delivery,
+        };
+        self.deliver(delivery);
+        match delivery {
+            Delivery::Borrowed | Delivery::Transient => visitor.visit_bytes(v),
+            Delivery::Owned => visitor.visit_byte_buf(mem::take(v)),
+        }
     }
 
-    fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+    #[cfg(not(feature = "safe"))]
+    fn revisit_token(&mut self, token: &'a mut CanonicalToken) {
+        // The token was only peeked at, so it is no longer considered consumed.
+        self.unconsume();
+        self.revisited_token = Some(token);
     }
 
-    fn deserialize_f64<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+    #[cfg(feature = "safe")]
+    fn revisit_token(&mut self, token: &mut CanonicalToken) {
+        // The token was only peeked at, so it is no longer considered consumed.
+        self.unconsume();
+        self.revisited_token = Some(token::TakenToken(mem::replace(token, CanonicalToken::Unit)));
     }
+}
 
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+#[cfg(feature = "std")]
+impl Drop for Deserializer<'_> {
+    fn drop(&mut self) {
+        if let Some(dump) = self.panic_dump() {
+            std::eprintln!("{dump}");
+        }
     }
+}
 
-    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_any(visitor)
+/// Deserializes a value of type `T` from the given tokens using a default [`Deserializer`].
+///
+/// All tokens must be consumed by the deserialization. This is equivalent to building a
+/// `Deserializer` with `Deserializer::builder(tokens).build()`, deserializing `T` from it, and
+/// then calling [`Deserializer::end()`].
+///
+/// # Errors
+/// Returns an [`Error`] if the tokens fail to deserialize as `T`, or if any tokens remain after the
+/// value is deserialized.
+///
+/// # Example
+/// ``` rust
+/// use claims::{
+///     assert_err_eq,
+///     assert_ok_eq,
+/// };
+/// use serde_assert::{
+///     de::Error,
+///     from_tokens,
+///     Token,
+/// };
+///
+/// assert_ok_eq!(
+///     from_tokens::<Option<u8>, _>([Token::Some, Token::U8(42)]),
+///     Some(42)
+/// );
+/// assert_err_eq!(
+///     from_tokens::<bool, _>([Token::Bool(true), Token::Unit]),
+///     Error::TrailingTokens(1)
+/// );
+/// ```
+pub fn from_tokens<T, I>(tokens: I) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+    I: IntoIterator<Item = Token>,
+{
+    let mut deserializer = Deserializer::builder(tokens).build();
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+#[derive(Clone, Copy, Debug)]
+enum EndToken {
+    Seq,
+    Tuple,
+    TupleStruct,
+    TupleVariant,
+    Map,
+    Struct,
+    StructVariant,
+}
+
+impl PartialEq<EndToken> for CanonicalToken {
+    fn eq(&self, other: &EndToken) -> bool {
+        matches!(
+            (self, other),
+            (Self::SeqEnd, EndToken::Seq)
+                | (Self::TupleEnd, EndToken::Tuple)
+                | (Self::TupleStructEnd, EndToken::TupleStruct)
+                | (Self::TupleVariantEnd, EndToken::TupleVariant)
+                | (Self::MapEnd, EndToken::Map)
+                | (Self::StructEnd, EndToken::Struct)
+                | (Self::StructVariantEnd, EndToken::StructVariant)
+        )
     }
+}
 
-    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+struct SeqAccess<'a, 'b> {
+    deserializer: &'a mut Deserializer<'b>,
+
+    len: Option<usize>,
+
+    end_token: EndToken,
+    ended: bool,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
     where
-        V: de::Visitor<'de>,
+        T: DeserializeSeed<'de>,
     {
-        self.deserialize_any(visitor)
+        if self.ended {
+            return Ok(None);
+        }
+        let token = &mut *self.deserializer.next_token("next_element_seed")?;
+        if *token == self.end_token {
+            self.ended = true;
+            return Ok(None);
+        }
+        self.deserializer.revisit_token(token);
+        seed.deserialize(&mut *self.deserializer).map(Some)
     }
 
-    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+    fn size_hint(&self) -> Option<usize> {
+        self.len
     }
+}
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+impl SeqAccess<'_, '_> {
+    /// Consumes the end token, attributing its consumption to the given `Deserializer` method.
+    fn assert_ended(&mut self, method: &'static str) -> Result<(), Error> {
+        if !self.ended && *self.deserializer.next_token(method)? != self.end_token {
+            return Err(Error::expected_end_token(self.end_token));
+        }
+        self.ended = true;
+        Ok(())
     }
+}
 
-    fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+struct MapAccess<'a, 'b> {
+    deserializer: &'a mut Deserializer<'b>,
+
+    len: Option<usize>,
+    /// The known fields of the struct being deserialized, if unknown fields should be rejected.
+    fields: Option<&'static [&'static str]>,
+    /// The tokens of each key deserialized so far, used to detect duplicate keys.
+    keys: Vec<Vec<CanonicalToken>>,
+
+    end_token: EndToken,
+    ended: bool,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
     where
-        V: de::Visitor<'de>,
+        K: DeserializeSeed<'de>,
     {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+        if self.ended {
+            return Ok(None);
+        }
+        let token = &mut *match self.deserializer.next_token("next_key_seed") {
+            Err(Error::EndOfTokens) if self.deserializer.implicit_map_end => {
+                self.ended = true;
+                return Ok(None);
+            }
+            result => result?,
+        };
+        if *token == self.end_token {
+            self.ended = true;
+            return Ok(None);
+        }
+        if let (CanonicalToken::Field(field), Some(fields)) = (&*token, self.fields) {
+            if !fields.contains(field) {
+                return Err(Error::unknown_field(field, fields));
+            }
+        }
+        if self.deserializer.require_string_keys
+            && !matches!(
+                token,
+                CanonicalToken::Str(_) | CanonicalToken::Field(_) | CanonicalToken::Char(_)
+            )
+        {
+            return Err(Error::invalid_type(token.into(), &"a string key"));
+        }
+        self.deserializer.revisit_token(token);
+        if !self.deserializer.deny_duplicate_keys {
+            return seed.deserialize(&mut *self.deserializer).map(Some);
+        }
+        // Capture the tokens of this key, restoring the capture of any enclosing key afterwards.
+        let outer_key_tokens = self.deserializer.key_tokens.replace(Vec::new());
+        let result = seed.deserialize(&mut *self.deserializer);
+        let key_tokens =
+            mem::replace(&mut self.deserializer.key_tokens, outer_key_tokens).unwrap_or_default();
+        if let Some(outer_key_tokens) = &mut self.deserializer.key_tokens {
+            outer_key_tokens.extend(key_tokens.iter().cloned());
+        }
+        let key = result?;
+        if self.keys.contains(&key_tokens) {
+            return Err(Error::duplicate_key(&key_tokens));
+        }
+        self.keys.push(key_tokens);
+        Ok(Some(key))
     }
 
-    fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
     where
-        V: de::Visitor<'de>,
+        V: DeserializeSeed<'de>,
     {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+        seed.deserialize(&mut *self.deserializer)
     }
 
-    fn deserialize_unit_struct<V>(
-        self,
-        _name: &'static str,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+    fn size_hint(&self) -> Option<usize> {
+        self.len
     }
+}
 
-    fn deserialize_newtype_struct<V>(
-        self,
-        _name: &'static str,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error>
+impl MapAccess<'_, '_> {
+    /// Consumes the end token, attributing its consumption to the given `Deserializer` method.
+    ///
+    /// If maps are implicitly ended, reaching the end of the tokens is also accepted.
+    fn assert_ended(&mut self, method: &'static str) -> Result<(), Error> {
+        if !self.ended {
+            match self.deserializer.next_token(method) {
+                Ok(token) if *token == self.end_token => {}
+                Ok(_) => return Err(Error::expected_end_token(self.end_token)),
+                Err(Error::EndOfTokens) if self.deserializer.implicit_map_end => {}
+                Err(error) => return Err(error),
+            }
+        }
+        self.ended = true;
+        Ok(())
+    }
+}
+
+struct EnumAccess<'a, 'b> {
+    deserializer: &'a mut Deserializer<'b>,
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for EnumAccess<'a, 'de> {
+    type Error = Error;
+    type Variant = VariantAccess<'a, 'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
     where
-        V: de::Visitor<'de>,
+        V: DeserializeSeed<'de>,
     {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+        let value = seed.deserialize(EnumDeserializer {
+            deserializer: self.deserializer,
+        })?;
+        Ok((
+            value,
+            VariantAccess {
+                deserializer: self.deserializer,
+            },
+        ))
     }
+}
 
-    fn deserialize_seq<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+struct VariantAccess<'a, 'b> {
+    deserializer: &'a mut Deserializer<'b>,
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
     where
-        V: de::Visitor<'de>,
+        T: DeserializeSeed<'de>,
     {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+        seed.deserialize(self.deserializer)
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+        let visitor = self.deserializer.record_visits("tuple_variant", visitor);
+        visitor.visit_seq(SeqAccess {
+            deserializer: self.deserializer,
+
+            len: Some(len),
+
+            end_token: EndToken::TupleVariant,
+            ended: false,
+        })
     }
 
-    fn deserialize_tuple_struct<V>(
+    fn struct_variant<V>(
         self,
-        _name: &'static str,
-        _len: usize,
-        _visitor: V,
+        fields: &'static [&'static str],
+        visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+        let visitor = self.deserializer.record_visits("struct_variant", visitor);
+        let deny_unknown_fields = self.deserializer.deny_unknown_fields;
+        visitor.visit_map(MapAccess {
+            deserializer: self.deserializer,
+
+            len: None,
+            fields: deny_unknown_fields.then(|| fields),
+            keys: Vec::new(),
+
+            end_token: EndToken::StructVariant,
+            ended: false,
+        })
     }
+}
 
-    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+/// Presents an enum variant as a map containing a single entry from its name to its contents.
+struct VariantMapAccess<'a, 'b, 'c> {
+    deserializer: &'a mut Deserializer<'b>,
+    /// The name of the variant, if it has not yet been deserialized as a key.
+    variant: Option<&'static str>,
+    /// The variant token, which has already been consumed.
+    token: &'c CanonicalToken,
+}
+
+impl<'de> de::MapAccess<'de> for VariantMapAccess<'_, 'de, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
     where
-        V: de::Visitor<'de>,
+        K: DeserializeSeed<'de>,
     {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+        self.variant
+            .take()
+            .map(|variant| seed.deserialize(StrDeserializer::new(variant)))
+            .transpose()
     }
 
-    fn deserialize_struct<V>(
-        self,
-        _name: &'static str,
-        _fields: &'static [&'static str],
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error>
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
     where
-        V: de::Visitor<'de>,
+        V: DeserializeSeed<'de>,
     {
-        Err(Error::UnsupportedEnumDeserializerMethod)
+        seed.deserialize(VariantContentsDeserializer {
+            deserializer: &mut *self.deserializer,
+            token: self.token,
+        })
     }
 
-    fn deserialize_enum<V>(
-        self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_any(visitor)
+    fn size_hint(&self) -> Option<usize> {
+        Some(1)
     }
+}
 
-    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_any(visitor)
-    }
+/// Deserializes the contents of a non-unit enum variant as a self-describing value.
+struct VariantContentsDeserializer<'a, 'b, 'c> {
+    deserializer: &'a mut Deserializer<'b>,
+    token: &'c CanonicalToken,
+}
 
-    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+impl<'de> de::Deserializer<'de> for VariantContentsDeserializer<'_, 'de, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        match self.token {
+            CanonicalToken::TupleVariant { len, .. } => {
+                let mut access = SeqAccess {
+                    deserializer: self.deserializer,
+
+                    len: Some(*len),
+
+                    end_token: EndToken::TupleVariant,
+                    ended: false,
+                };
+                let result = visitor.visit_seq(&mut access)?;
+                access.assert_ended("deserialize_any")?;
+                Ok(result)
+            }
+            CanonicalToken::StructVariant { len, .. } => {
+                let mut access = MapAccess {
+                    deserializer: self.deserializer,
+
+                    len: Some(*len),
+                    fields: None,
+                    keys: Vec::new(),
+
+                    end_token: EndToken::StructVariant,
+                    ended: false,
+                };
+                let result = visitor.visit_map(&mut access)?;
+                access.assert_ended("deserialize_any")?;
+                Ok(result)
+            }
+            _ => self.deserializer.deserialize_any(visitor),
+        }
     }
 
-    fn is_human_readable(&self) -> bool {
-        self.deserializer.is_human_readable()
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
     }
 }
 
-/// A builder for a [`Deserializer`].
-///
-/// Construction of a `Deserializer` follows the builder pattern. Configuration options can be set
-/// on the `Builder`, and then the actual `Deserializer` is constructed by calling [`build()`].
-///
-/// # Example
-/// ``` rust
-/// use serde_assert::{
-///     Deserializer,
-///     Token,
-/// };
-///
-/// let deserializer = Deserializer::builder([Token::Bool(true)])
-///     .is_human_readable(false)
-///     .self_describing(true)
-///     .build();
-/// ```
+/// Provides access to an enum represented as a map from its variant name to its contents.
 ///
-/// [`build()`]: Builder::build()
-/// [::builder()`]: Builder::tokens()
-#[derive(Debug)]
-pub struct Builder {
-    tokens: Tokens,
-
-    is_human_readable: bool,
-    self_describing: bool,
-    zero_copy: bool,
+/// A unit variant may also be represented by its name alone, in which case `unit` is `true`.
+struct MapEnumAccess<'a, 'b> {
+    deserializer: &'a mut Deserializer<'b>,
+    unit: bool,
 }
 
-impl Builder {
-    /// Creates a new `Builder` containing the provided tokens.
-    fn new<T>(tokens: T) -> Self
+impl<'a, 'de> de::EnumAccess<'de> for MapEnumAccess<'a, 'de> {
+    type Error = Error;
+    type Variant = MapVariantAccess<'a, 'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
     where
-        T: IntoIterator<Item = Token>,
+        V: DeserializeSeed<'de>,
     {
-        fn collect_canonical<I>(tokens: &mut Vec<CanonicalToken>, iter: I)
-        where
-            I: Iterator<Item = Token>,
-        {
-            for token in iter {
-                match token.try_into() {
-                    Ok(canonical_token) => tokens.push(canonical_token),
-                    Err(UnorderedTokens(unordered_tokens)) => {
-                        collect_canonical(
-                            tokens,
-                            unordered_tokens.iter().copied().flatten().cloned(),
-                        );
-                    }
-                }
-            }
-        }
-
-        Self {
-            tokens: {
-                let mut canonical_tokens = Vec::new();
-                collect_canonical(&mut canonical_tokens, tokens.into_iter());
-                Tokens(canonical_tokens)
+        let value = seed.deserialize(&mut *self.deserializer)?;
+        Ok((
+            value,
+            MapVariantAccess {
+                deserializer: self.deserializer,
+                unit: self.unit,
             },
+        ))
+    }
+}
 
-            is_human_readable: true,
-            self_describing: false,
-            zero_copy: true,
+struct MapVariantAccess<'a, 'b> {
+    deserializer: &'a mut Deserializer<'b>,
+    unit: bool,
+}
+
+impl<'de> de::VariantAccess<'de> for MapVariantAccess<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        if self.unit {
+            Ok(())
+        } else {
+            <() as de::Deserialize>::deserialize(self.deserializer)
         }
     }
 
-    /// Determines whether the deserializer will interpret the input tokens in a readable or compact
-    /// format.
-    ///
-    /// Useful for complicated structs wishing to provide different outputs depending on the
-    /// readability of the serialization type.
-    ///
-    /// If not set, the default value is `true`.
-    ///
-    /// # Example
-    /// ``` rust
-    /// use serde_assert::{
-    ///     Deserializer,
-    ///     Token,
-    /// };
-    ///
-    /// let deserializer = Deserializer::builder([Token::Bool(true)])
-    ///     .is_human_readable(false)
-    ///     .build();
-    /// ```
-    pub fn is_human_readable(&mut self, is_human_readable: bool) -> &mut Self {
-        self.is_human_readable = is_human_readable;
-        self
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.unit {
+            Err(Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            ))
+        } else {
+            seed.deserialize(self.deserializer)
+        }
     }
 
-    /// Determines whether the deserialization should interpret the input tokens as self-describing,
-    /// meaning the type the tokens should deserialize to can be discerned directly from the tokens
-    /// themselves.
-    ///
-    /// If this is set to `false`, calls to [`deserialize_any()`] will result in an error.
-    ///
-    /// If not set, the default value is `false`.
-    ///
-    /// # Example
-    /// ``` rust
-    /// use serde_assert::{
-    ///     Deserializer,
-    ///     Token,
-    /// };
-    ///
-    /// let deserializer = Deserializer::builder([Token::Bool(true)])
-    ///     .self_describing(true)
-    ///     .build();
-    /// ```
-    ///
-    /// [`deserialize_any()`]: ../struct.Deserializer.html#method.deserialize_any
-    pub fn self_describing(&mut self, self_describing: bool) -> &mut Self {
-        self.self_describing = self_describing;
-        self
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.unit {
+            Err(Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            ))
+        } else {
+            de::Deserializer::deserialize_seq(self.deserializer, visitor)
+        }
     }
 
-    /// Defines whether zero-copy deserialization should be permitted by the `Deserializer`,
-    /// allowing deserializations of strings and byte sequences to avoid allocations.
-    ///
-    /// If not set, the default value is `true`.
-    ///
-    /// Some `serde` formats do not permit zero-copy deserialization. Setting this value to `false`
-    /// allows testing `Deserialize` implementations in a similar environment.
-    ///
-    /// # Example
-    /// ``` rust
-    /// use serde_assert::{
-    ///     Deserializer,
-    ///     Token,
-    /// };
-    ///
-    /// let deserializer = Deserializer::builder([Token::Bool(true)])
-    ///     .zero_copy(false)
-    ///     .build();
-    /// ```
-    pub fn zero_copy(&mut self, zero_copy: bool) -> &mut Self {
-        self.zero_copy = zero_copy;
-        self
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.unit {
+            Err(Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            ))
+        } else {
+            de::Deserializer::deserialize_map(self.deserializer, visitor)
+        }
     }
+}
 
-    /// Build a new [`Deserializer`] using this `Builder`.
-    ///
-    /// Constructs a new `Deserializer` using the configuration options set on this `Builder`.
-    ///
-    /// # Example
-    /// ``` rust
-    /// use serde_assert::{
-    ///     Deserializer,
-    ///     Token,
-    /// };
-    ///
-    /// let deserializer = Deserializer::builder([Token::Bool(true)])
-    ///     .is_human_readable(false)
-    ///     .build();
-    /// ```
-    #[must_use]
-    pub fn build<'a>(&self) -> Deserializer<'a> {
-        Deserializer {
-            tokens: token::OwningIter::new(self.tokens.clone()),
+/// Wrapper around `Deserializer` to deserialize enum tokens directly, rather than using
+/// `EnumAccess`.
+///
+/// This is required to ensure the token can be properly deserialized into a variant.
+struct EnumDeserializer<'a, 'b> {
+    deserializer: &'a mut Deserializer<'b>,
+}
 
-            revisited_token: None,
+impl<'de> de::Deserializer<'de> for EnumDeserializer<'_, 'de> {
+    type Error = Error;
 
-            is_human_readable: self.is_human_readable,
-            self_describing: self.self_describing,
-            zero_copy: self.zero_copy,
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let visitor = self.deserializer.record_visits("deserialize_any", visitor);
+        match &mut *self.deserializer.next_token("deserialize_any")? {
+            CanonicalToken::UnitVariant { id, .. }
+            | CanonicalToken::TupleVariant { id, .. }
+            | CanonicalToken::NewtypeVariant { id, .. }
+            | CanonicalToken::StructVariant { id, .. } => visitor.visit_str(id.variant),
+            _ => unreachable!(),
         }
     }
-}
 
-/// An error encountered during deserialization.
-///
-/// # Example
-/// ```rust
-/// use serde::de::Error as _;
-/// use serde_assert::de::Error;
-///
-/// assert_eq!(
-///     format!("{}", Error::missing_field("foo")),
-///     "missing field foo"
-/// );
-/// ```
-#[derive(Debug, PartialEq)]
-pub enum Error {
-    /// The [`Deserializer`] reached the end of the input [`Token`]s before deserialization was
-    /// completed.
-    EndOfTokens,
+    fn deserialize_bool<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
 
-    /// Expected a `Token::SeqEnd`.
-    ExpectedSeqEnd,
-    /// Expected a `Token::TupleEnd`.
-    ExpectedTupleEnd,
-    /// Expected a `Token::TupleStructEnd`.
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let visitor = self.deserializer.record_visits("deserialize_u32", visitor);
+        match &mut *self.deserializer.next_token("deserialize_u32")? {
+            CanonicalToken::UnitVariant { id, .. }
+            | CanonicalToken::TupleVariant { id, .. }
+            | CanonicalToken::NewtypeVariant { id, .. }
+            | CanonicalToken::StructVariant { id, .. } => visitor.visit_u32(id.variant_index),
+            _ => unreachable!(),
+        }
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_u32(visitor)
+    }
+
+    fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_f64<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let visitor = self.deserializer.record_visits("deserialize_char", visitor);
+        match &mut *self.deserializer.next_token("deserialize_char")? {
+            CanonicalToken::UnitVariant { id, .. }
+            | CanonicalToken::TupleVariant { id, .. }
+            | CanonicalToken::NewtypeVariant { id, .. }
+            | CanonicalToken::StructVariant { id, .. } => {
+                // The variant is identified by its first character.
+                if let Some(c) = id.variant.chars().next() {
+                    visitor.visit_char(c)
+                } else {
+                    Err(Error::invalid_value(Unexpected::Str(id.variant), &visitor))
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_seq<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::UnsupportedEnumDeserializerMethod)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+}
+
+/// A method on a [`Visitor`] called by the [`Deserializer`].
+///
+/// These are recorded during deserialization and can be obtained through
+/// [`Deserializer::visits()`].
+///
+/// [`Visitor`]: serde::de::Visitor
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Visit {
+    /// [`Visitor::visit_bool()`](serde::de::Visitor::visit_bool()).
+    Bool,
+    /// [`Visitor::visit_i8()`](serde::de::Visitor::visit_i8()).
+    I8,
+    /// [`Visitor::visit_i16()`](serde::de::Visitor::visit_i16()).
+    I16,
+    /// [`Visitor::visit_i32()`](serde::de::Visitor::visit_i32()).
+    I32,
+    /// [`Visitor::visit_i64()`](serde::de::Visitor::visit_i64()).
+    I64,
+    /// [`Visitor::visit_i128()`](serde::de::Visitor::visit_i128()).
+    I128,
+    /// [`Visitor::visit_u8()`](serde::de::Visitor::visit_u8()).
+    U8,
+    /// [`Visitor::visit_u16()`](serde::de::Visitor::visit_u16()).
+    U16,
+    /// [`Visitor::visit_u32()`](serde::de::Visitor::visit_u32()).
+    U32,
+    /// [`Visitor::visit_u64()`](serde::de::Visitor::visit_u64()).
+    U64,
+    /// [`Visitor::visit_u128()`](serde::de::Visitor::visit_u128()).
+    U128,
+    /// [`Visitor::visit_f32()`](serde::de::Visitor::visit_f32()).
+    F32,
+    /// [`Visitor::visit_f64()`](serde::de::Visitor::visit_f64()).
+    F64,
+    /// [`Visitor::visit_char()`](serde::de::Visitor::visit_char()).
+    Char,
+    /// [`Visitor::visit_str()`](serde::de::Visitor::visit_str()).
+    Str,
+    /// [`Visitor::visit_borrowed_str()`](serde::de::Visitor::visit_borrowed_str()).
+    BorrowedStr,
+    /// [`Visitor::visit_string()`](serde::de::Visitor::visit_string()).
+    String,
+    /// [`Visitor::visit_bytes()`](serde::de::Visitor::visit_bytes()).
+    Bytes,
+    /// [`Visitor::visit_borrowed_bytes()`](serde::de::Visitor::visit_borrowed_bytes()).
+    BorrowedBytes,
+    /// [`Visitor::visit_byte_buf()`](serde::de::Visitor::visit_byte_buf()).
+    ByteBuf,
+    /// [`Visitor::visit_none()`](serde::de::Visitor::visit_none()).
+    None,
+    /// [`Visitor::visit_some()`](serde::de::Visitor::visit_some()).
+    Some,
+    /// [`Visitor::visit_unit()`](serde::de::Visitor::visit_unit()).
+    Unit,
+    /// [`Visitor::visit_newtype_struct()`](serde::de::Visitor::visit_newtype_struct()).
+    NewtypeStruct,
+    /// [`Visitor::visit_seq()`](serde::de::Visitor::visit_seq()).
+    Seq,
+    /// [`Visitor::visit_map()`](serde::de::Visitor::visit_map()).
+    Map,
+    /// [`Visitor::visit_enum()`](serde::de::Visitor::visit_enum()).
+    Enum,
+}
+
+/// The methods tracked by a [`Coverage`]: the entry points of [`serde::Deserializer`], followed by
+/// the methods of [`Visitor`](serde::de::Visitor) in the same order as [`Visit`].
+#[cfg(target_has_atomic = "ptr")]
+const COVERED_METHODS: [&str; 58] = [
+    "deserialize_any",
+    "deserialize_bool",
+    "deserialize_i8",
+    "deserialize_i16",
+    "deserialize_i32",
+    "deserialize_i64",
+    "deserialize_i128",
+    "deserialize_u8",
+    "deserialize_u16",
+    "deserialize_u32",
+    "deserialize_u64",
+    "deserialize_u128",
+    "deserialize_f32",
+    "deserialize_f64",
+    "deserialize_char",
+    "deserialize_str",
+    "deserialize_string",
+    "deserialize_bytes",
+    "deserialize_byte_buf",
+    "deserialize_option",
+    "deserialize_unit",
+    "deserialize_unit_struct",
+    "deserialize_newtype_struct",
+    "deserialize_seq",
+    "deserialize_tuple",
+    "deserialize_tuple_struct",
+    "deserialize_map",
+    "deserialize_struct",
+    "deserialize_enum",
+    "deserialize_identifier",
+    "deserialize_ignored_any",
+    "visit_bool",
+    "visit_i8",
+    "visit_i16",
+    "visit_i32",
+    "visit_i64",
+    "visit_i128",
+    "visit_u8",
+    "visit_u16",
+    "visit_u32",
+    "visit_u64",
+    "visit_u128",
+    "visit_f32",
+    "visit_f64",
+    "visit_char",
+    "visit_str",
+    "visit_borrowed_str",
+    "visit_string",
+    "visit_bytes",
+    "visit_borrowed_bytes",
+    "visit_byte_buf",
+    "visit_none",
+    "visit_some",
+    "visit_unit",
+    "visit_newtype_struct",
+    "visit_seq",
+    "visit_map",
+    "visit_enum",
+];
+
+/// The number of [`serde::Deserializer`] methods at the start of [`COVERED_METHODS`].
+#[cfg(target_has_atomic = "ptr")]
+const DESERIALIZER_METHODS: usize = 31;
+
+/// A collector of which [`serde::Deserializer`] methods and [`Visitor`] methods were called,
+/// aggregated across many [`Deserializer`]s.
+///
+/// Unlike [`Deserializer::visits()`], which are recorded per `Deserializer`, a `Coverage` is
+/// intended to be declared as a `static` and shared by every `Deserializer` in a test run using
+/// [`Builder::coverage()`]. The methods that were never called can then be listed, which helps
+/// flag untested branches of large hand-written [`Deserialize`] implementations.
+///
+/// The [`Display`] implementation prints a summary of the methods that were and were not called.
+/// For a machine-readable report, such as for checking coverage in CI, a `Coverage` implements
+/// [`Serialize`] as a struct with a `deserializer` field and a `visitor` field, each mapping every
+/// tracked method name to the number of times it was called.
+///
+/// `Coverage` counts calls atomically, and is therefore only available on targets supporting
+/// atomic operations on pointer-sized integers.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok;
+/// use serde::Deserialize;
+/// use serde_assert::{
+///     de::Coverage,
+///     Deserializer,
+///     Token,
+/// };
+///
+/// static COVERAGE: Coverage = Coverage::new();
+///
+/// let mut deserializer = Deserializer::builder([Token::Some, Token::U8(42)])
+///     .coverage(&COVERAGE)
+///     .build();
+/// assert_ok!(Option::<u8>::deserialize(&mut deserializer));
+///
+/// assert_eq!(
+///     COVERAGE.hit(),
+///     [
+///         "deserialize_u8",
+///         "deserialize_option",
+///         "visit_u8",
+///         "visit_some"
+///     ]
+/// );
+/// assert!(COVERAGE.missed().contains(&"visit_none"));
+///
+/// let report = serde_json::to_value(&COVERAGE).unwrap();
+/// assert_eq!(report["deserializer"]["deserialize_option"], 1);
+/// assert_eq!(report["visitor"]["visit_none"], 0);
+/// ```
+///
+/// [`Deserialize`]: serde::Deserialize
+/// [`Visitor`]: serde::de::Visitor
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug)]
+pub struct Coverage {
+    counts: [AtomicUsize; COVERED_METHODS.len()],
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Coverage {
+    /// Creates a new `Coverage` with no recorded calls.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::de::Coverage;
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    /// ```
+    #[must_use]
+    pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)] // Only used to initialize the array.
+        const ZERO: AtomicUsize = AtomicUsize::new(0);
+        Self {
+            counts: [ZERO; COVERED_METHODS.len()],
+        }
+    }
+
+    /// Returns the number of calls to the [`serde::Deserializer`] or [`Visitor`] method with the
+    /// given name, such as `"deserialize_u32"` or `"visit_u32"`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Coverage,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::U32(42)])
+    ///     .coverage(&COVERAGE)
+    ///     .build();
+    /// assert_ok!(u32::deserialize(&mut deserializer));
+    ///
+    /// assert_eq!(COVERAGE.count("deserialize_u32"), 1);
+    /// assert_eq!(COVERAGE.count("visit_u32"), 1);
+    /// assert_eq!(COVERAGE.count("visit_u64"), 0);
+    /// ```
+    ///
+    /// [`Visitor`]: serde::de::Visitor
+    #[must_use]
+    pub fn count(&self, method: &str) -> usize {
+        COVERED_METHODS
+            .iter()
+            .position(|name| *name == method)
+            .map_or(0, |index| self.counts[index].load(Ordering::Relaxed))
+    }
+
+    /// Returns the names of the methods that were called at least once.
+    ///
+    /// The [`serde::Deserializer`] methods are listed first, followed by the [`Visitor`] methods,
+    /// each in the order they are declared by `serde`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Coverage,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .coverage(&COVERAGE)
+    ///     .build();
+    /// assert_ok!(bool::deserialize(&mut deserializer));
+    ///
+    /// assert_eq!(COVERAGE.hit(), ["deserialize_bool", "visit_bool"]);
+    /// ```
+    ///
+    /// [`Visitor`]: serde::de::Visitor
+    #[must_use]
+    pub fn hit(&self) -> Vec<&'static str> {
+        self.methods(|count| count > 0)
+    }
+
+    /// Returns the names of the methods that were never called.
+    ///
+    /// The [`serde::Deserializer`] methods are listed first, followed by the [`Visitor`] methods,
+    /// each in the order they are declared by `serde`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::de::Coverage;
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// assert_eq!(COVERAGE.missed().len(), 58);
+    /// ```
+    ///
+    /// [`Visitor`]: serde::de::Visitor
+    #[must_use]
+    pub fn missed(&self) -> Vec<&'static str> {
+        self.methods(|count| count == 0)
+    }
+
+    /// Clears all recorded calls.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Coverage,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .coverage(&COVERAGE)
+    ///     .build();
+    /// assert_ok!(bool::deserialize(&mut deserializer));
+    /// COVERAGE.reset();
+    ///
+    /// assert_eq!(COVERAGE.hit(), [] as [&str; 0]);
+    /// ```
+    pub fn reset(&self) {
+        for count in &self.counts {
+            count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn methods<F>(&self, filter: F) -> Vec<&'static str>
+    where
+        F: Fn(usize) -> bool,
+    {
+        COVERED_METHODS
+            .iter()
+            .zip(&self.counts)
+            .filter(|(_, count)| filter(count.load(Ordering::Relaxed)))
+            .map(|(method, _)| *method)
+            .collect()
+    }
+
+    fn record_method(&self, method: &str) {
+        if let Some(index) = COVERED_METHODS[..DESERIALIZER_METHODS]
+            .iter()
+            .position(|name| *name == method)
+        {
+            self.counts[index].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn record_visit(&self, visit: Visit) {
+        self.counts[DESERIALIZER_METHODS + visit as usize].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hit = self.hit();
+        let visits_hit = hit
+            .iter()
+            .filter(|method| method.starts_with("visit_"))
+            .count();
+        write!(
+            f,
+            "deserializer coverage: {} of {} deserializer methods and {} of {} visitor methods \
+             called",
+            hit.len() - visits_hit,
+            DESERIALIZER_METHODS,
+            visits_hit,
+            COVERED_METHODS.len() - DESERIALIZER_METHODS
+        )?;
+        for method in hit {
+            write!(f, "\n  hit:    {method} ({})", self.count(method))?;
+        }
+        for method in self.missed() {
+            write!(f, "\n  missed: {method}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Serialize for Coverage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        /// The counts of a range of the covered methods.
+        struct Counts<'a>(&'a [&'static str], &'a [AtomicUsize]);
+
+        impl Serialize for Counts<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_map(
+                    self.0
+                        .iter()
+                        .zip(self.1)
+                        .map(|(method, count)| (method, count.load(Ordering::Relaxed))),
+                )
+            }
+        }
+
+        let (deserializer_methods, visitor_methods) =
+            COVERED_METHODS.split_at(DESERIALIZER_METHODS);
+        let (deserializer_counts, visitor_counts) = self.counts.split_at(DESERIALIZER_METHODS);
+        let mut report = serializer.serialize_struct("Coverage", 2)?;
+        report.serialize_field(
+            "deserializer",
+            &Counts(deserializer_methods, deserializer_counts),
+        )?;
+        report.serialize_field("visitor", &Counts(visitor_methods, visitor_counts))?;
+        report.end()
+    }
+}
+
+/// How the contents of a `Str` or `Bytes` token were delivered to a [`Visitor`].
+///
+/// These are recorded during deserialization and can be obtained through
+/// [`Deserializer::deliveries()`] or [`Consumption::delivery()`]. The delivery used for strings
+/// and bytes can be fixed using [`Builder::str_delivery()`] and [`Builder::bytes_delivery()`].
+///
+/// [`Visitor`]: serde::de::Visitor
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Delivery {
+    /// Borrowed for the lifetime of the input, through
+    /// [`Visitor::visit_borrowed_str()`](serde::de::Visitor::visit_borrowed_str()) or
+    /// [`Visitor::visit_borrowed_bytes()`](serde::de::Visitor::visit_borrowed_bytes()).
+    Borrowed,
+    /// Borrowed only for the duration of the call, through
+    /// [`Visitor::visit_str()`](serde::de::Visitor::visit_str()) or
+    /// [`Visitor::visit_bytes()`](serde::de::Visitor::visit_bytes()).
+    Transient,
+    /// Given by value, through
+    /// [`Visitor::visit_string()`](serde::de::Visitor::visit_string()) or
+    /// [`Visitor::visit_byte_buf()`](serde::de::Visitor::visit_byte_buf()).
+    Owned,
+}
+
+/// A single token consumed by the [`Deserializer`].
+///
+/// These are recorded during deserialization and can be obtained through
+/// [`Deserializer::consumptions()`].
+#[derive(Clone, Debug)]
+pub struct Consumption {
+    method: &'static str,
+    token: Token,
+    label: Option<&'static str>,
+    delivery: Option<Delivery>,
+}
+
+impl Consumption {
+    /// Returns the name of the [`serde::Deserializer`] method that consumed the token.
+    #[must_use]
+    pub fn method(&self) -> &'static str {
+        self.method
+    }
+
+    /// Returns the consumed token.
+    #[must_use]
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    /// Returns the label of the consumed token, if it was provided as a [`Token::Labeled`].
+    #[must_use]
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// Returns how the consumed token was delivered to the [`Visitor`], if it was a `Str` or
+    /// `Bytes` token passed to the `Visitor` as a string or byte slice.
+    ///
+    /// [`Visitor`]: serde::de::Visitor
+    #[must_use]
+    pub fn delivery(&self) -> Option<Delivery> {
+        self.delivery
+    }
+}
+
+impl Display for Consumption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?}", self.method, self.token)?;
+        if let Some(label) = self.label {
+            write!(f, " ({label})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Wrapper around a `Visitor` that records which of its methods are called.
+struct RecordingVisitor<V> {
+    visitor: V,
+    visits: Rc<RefCell<Vec<Visit>>>,
+    #[cfg(target_has_atomic = "ptr")]
+    coverage: Option<&'static Coverage>,
+}
+
+impl<V> RecordingVisitor<V> {
+    fn record(self, visit: Visit) -> V {
+        self.visits.borrow_mut().push(visit);
+        #[cfg(target_has_atomic = "ptr")]
+        if let Some(coverage) = self.coverage {
+            coverage.record_visit(visit);
+        }
+        self.visitor
+    }
+}
+
+impl<'de, V> de::Visitor<'de> for RecordingVisitor<V>
+where
+    V: de::Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::Bool).visit_bool(v)
+    }
+
+    fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::I8).visit_i8(v)
+    }
+
+    fn visit_i16<E>(self, v: i16) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::I16).visit_i16(v)
+    }
+
+    fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::I32).visit_i32(v)
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::I64).visit_i64(v)
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::I128).visit_i128(v)
+    }
+
+    fn visit_u8<E>(self, v: u8) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::U8).visit_u8(v)
+    }
+
+    fn visit_u16<E>(self, v: u16) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::U16).visit_u16(v)
+    }
+
+    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::U32).visit_u32(v)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::U64).visit_u64(v)
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::U128).visit_u128(v)
+    }
+
+    fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::F32).visit_f32(v)
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::F64).visit_f64(v)
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::Char).visit_char(v)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::Str).visit_str(v)
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::BorrowedStr).visit_borrowed_str(v)
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::String).visit_string(v)
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::Bytes).visit_bytes(v)
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::BorrowedBytes).visit_borrowed_bytes(v)
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::ByteBuf).visit_byte_buf(v)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::None).visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.record(Visit::Some).visit_some(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(Visit::Unit).visit_unit()
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.record(Visit::NewtypeStruct)
+            .visit_newtype_struct(deserializer)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        self.record(Visit::Seq).visit_seq(seq)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        self.record(Visit::Map).visit_map(map)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        self.record(Visit::Enum).visit_enum(data)
+    }
+}
+
+/// Configuration for deserializing 128-bit integers.
+///
+/// Can be passed to a [`Builder`] to determine which tokens `i128` and `u128` values are
+/// deserialized from by the [`Deserializer`].
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde::Deserialize;
+/// use serde_assert::{
+///     de::Deserialize128As,
+///     Deserializer,
+///     Token,
+/// };
+///
+/// let mut deserializer = Deserializer::builder([Token::Bytes(42i128.to_be_bytes().to_vec())])
+///     .deserialize_128_as(Deserialize128As::Bytes)
+///     .build();
+///
+/// assert_ok_eq!(i128::deserialize(&mut deserializer), 42);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Deserialize128As {
+    /// Deserialize 128-bit integers from [`Token::I128`] and [`Token::U128`].
+    Integer,
+    /// Deserialize 128-bit integers from a [`Token::Str`] containing their decimal
+    /// representation.
+    Str,
+    /// Deserialize 128-bit integers from a [`Token::Bytes`] containing their 16-byte big-endian
+    /// representation.
+    Bytes,
+}
+
+/// A builder for a [`Deserializer`].
+///
+/// Construction of a `Deserializer` follows the builder pattern. Configuration options can be set
+/// on the `Builder`, and then the actual `Deserializer` is constructed by calling [`build()`].
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     Deserializer,
+///     Token,
+/// };
+///
+/// let deserializer = Deserializer::builder([Token::Bool(true)])
+///     .is_human_readable(false)
+///     .self_describing(true)
+///     .build();
+/// ```
+///
+/// [`build()`]: Builder::build()
+#[allow(clippy::struct_excessive_bools)] // Each bool is an independent configuration option.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    tokens: Tokens,
+    labels: Labels,
+
+    is_human_readable: bool,
+    self_describing: bool,
+    content_compatible: bool,
+    zero_copy: bool,
+    str_delivery: Option<Delivery>,
+    bytes_delivery: Option<Delivery>,
+    deserialize_bytes_as_seq: bool,
+    char_as_str: bool,
+    deserialize_struct_from_seq: bool,
+    deserialize_unit_as_none: bool,
+    deserialize_enum_from_map: bool,
+    enum_tagging: EnumTagging,
+    deserialize_bool_from_int: bool,
+    deserialize_128_as: Deserialize128As,
+    support_128_bit: bool,
+    deny_unknown_fields: bool,
+    deny_duplicate_keys: bool,
+    require_string_keys: bool,
+    allow_trailing_tokens: bool,
+    implicit_map_end: bool,
+    fail_after_tokens: Option<usize>,
+    record_consumptions: bool,
+    #[cfg(target_has_atomic = "ptr")]
+    coverage: Option<&'static Coverage>,
+    pre_processors: Vec<PreProcessor>,
+    #[cfg(feature = "std")]
+    dump_on_panic: bool,
+    #[cfg(feature = "std")]
+    log_consumption: bool,
+}
+
+impl Builder {
+    /// Creates a new `Builder` containing the provided tokens.
+    fn new<T>(tokens: T) -> Self
+    where
+        T: IntoIterator<Item = Token>,
+    {
+        let tokens = tokens.into_iter().collect::<Vec<_>>();
+        Self {
+            labels: token::labels(&tokens),
+            tokens: Tokens::canonicalize(tokens),
+
+            is_human_readable: true,
+            self_describing: false,
+            content_compatible: false,
+            zero_copy: true,
+            str_delivery: None,
+            bytes_delivery: None,
+            deserialize_bytes_as_seq: false,
+            char_as_str: false,
+            deserialize_struct_from_seq: true,
+            deserialize_unit_as_none: false,
+            deserialize_enum_from_map: false,
+            enum_tagging: EnumTagging::External,
+            deserialize_bool_from_int: false,
+            deserialize_128_as: Deserialize128As::Integer,
+            support_128_bit: true,
+            deny_unknown_fields: false,
+            deny_duplicate_keys: false,
+            require_string_keys: false,
+            allow_trailing_tokens: false,
+            implicit_map_end: false,
+            fail_after_tokens: None,
+            record_consumptions: false,
+            #[cfg(target_has_atomic = "ptr")]
+            coverage: None,
+            pre_processors: Vec::new(),
+            #[cfg(feature = "std")]
+            dump_on_panic: false,
+            #[cfg(feature = "std")]
+            log_consumption: false,
+        }
+    }
+
+    /// Replaces the tokens to be deserialized.
+    ///
+    /// Any type convertible into a [`Token`] may be provided, allowing primitive values and
+    /// `Token`s produced by helper functions to be composed.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([])
+    ///     .tokens(
+    ///         [Token::Seq { len: Some(3) }]
+    ///             .into_iter()
+    ///             .chain([1u8, 2u8, 3u8].map(Token::from))
+    ///             .chain([Token::SeqEnd]),
+    ///     )
+    ///     .build();
+    ///
+    /// assert_ok_eq!(Vec::<u8>::deserialize(&mut deserializer), [1, 2, 3]);
+    /// ```
+    pub fn tokens<T>(&mut self, tokens: T) -> &mut Self
+    where
+        T: IntoIterator,
+        T::Item: Into<Token>,
+    {
+        let tokens = tokens.into_iter().map(Into::into).collect::<Vec<_>>();
+        self.labels = token::labels(&tokens);
+        self.tokens = Tokens::canonicalize(tokens);
+        self
+    }
+
+    /// Determines whether the deserializer will interpret the input tokens in a readable or compact
+    /// format.
+    ///
+    /// Useful for complicated structs wishing to provide different outputs depending on the
+    /// readability of the serialization type.
+    ///
+    /// If not set, the default value is `true`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .is_human_readable(false)
+    ///     .build();
+    /// ```
+    pub fn is_human_readable(&mut self, is_human_readable: bool) -> &mut Self {
+        self.is_human_readable = is_human_readable;
+        self
+    }
+
+    /// Determines whether the deserialization should interpret the input tokens as self-describing,
+    /// meaning the type the tokens should deserialize to can be discerned directly from the tokens
+    /// themselves.
+    ///
+    /// If this is set to `false`, calls to [`deserialize_any()`] will result in an error.
+    ///
+    /// This must be enabled to test types whose [`Deserialize`] implementations buffer their input
+    /// before deciding how to interpret it, such as those derived for `#[serde(untagged)]` enums.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// #[serde(untagged)]
+    /// enum Untagged {
+    ///     Bool(bool),
+    ///     Str(String),
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .self_describing(true)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(
+    ///     Untagged::deserialize(&mut deserializer),
+    ///     Untagged::Bool(true)
+    /// );
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    ///
+    /// [`deserialize_any()`]: ../struct.Deserializer.html#method.deserialize_any
+    pub fn self_describing(&mut self, self_describing: bool) -> &mut Self {
+        self.self_describing = self_describing;
+        self
+    }
+
+    /// Determines whether [`deserialize_any()`] presents enum variants and newtype structs the way
+    /// self-describing formats do.
+    ///
+    /// `Deserialize` implementations that buffer their input, such as those derived for
+    /// `#[serde(untagged)]` enums, do so through [`deserialize_any()`] into serde's internal
+    /// `Content` type, which cannot hold enums. When enabled, a unit variant is presented as a
+    /// string containing its name, and any other variant is presented as a map containing a single
+    /// entry from its name to its contents, as is done by formats like JSON. Newtype structs are
+    /// presented as their contained value. This allows untagged enums containing externally tagged
+    /// enums and newtype structs to be deserialized.
+    ///
+    /// This only has an effect when [`self_describing()`] is enabled.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// enum Shape {
+    ///     Point,
+    ///     Circle(u32),
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// #[serde(untagged)]
+    /// enum Untagged {
+    ///     Bool(bool),
+    ///     Shape(Shape),
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::NewtypeVariant {
+    ///         name: "Shape",
+    ///         variant_index: 1,
+    ///         variant: "Circle",
+    ///     },
+    ///     Token::U32(42),
+    /// ])
+    /// .self_describing(true)
+    /// .content_compatible(true)
+    /// .build();
+    ///
+    /// assert_ok_eq!(
+    ///     Untagged::deserialize(&mut deserializer),
+    ///     Untagged::Shape(Shape::Circle(42))
+    /// );
+    /// ```
+    ///
+    /// [`deserialize_any()`]: ../struct.Deserializer.html#method.deserialize_any
+    /// [`self_describing()`]: Builder::self_describing()
+    pub fn content_compatible(&mut self, content_compatible: bool) -> &mut Self {
+        self.content_compatible = content_compatible;
+        self
+    }
+
+    /// Defines whether zero-copy deserialization should be permitted by the `Deserializer`,
+    /// allowing deserializations of strings and byte sequences to avoid allocations.
+    ///
+    /// If not set, the default value is `true`.
+    ///
+    /// Some `serde` formats do not permit zero-copy deserialization. Setting this value to `false`
+    /// allows testing `Deserialize` implementations in a similar environment.
+    ///
+    /// When the `safe` feature is enabled, tokens can't be borrowed for the lifetime of the input,
+    /// so zero-copy deserialization is never permitted. Contents that would otherwise be borrowed
+    /// are instead delivered through [`Visitor::visit_str()`] and [`Visitor::visit_bytes()`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .zero_copy(false)
+    ///     .build();
+    /// ```
+    ///
+    /// [`Visitor::visit_bytes()`]: serde::de::Visitor::visit_bytes()
+    /// [`Visitor::visit_str()`]: serde::de::Visitor::visit_str()
+    pub fn zero_copy(&mut self, zero_copy: bool) -> &mut Self {
+        self.zero_copy = zero_copy;
+        self
+    }
+
+    /// Specifies the [`Delivery`] used for the contents of every `Str` token.
+    ///
+    /// Formats differ in how they provide strings: some borrow from the input, some only lend a
+    /// temporary buffer, and some always allocate. When set, every string is given to the visitor
+    /// through the corresponding [`visit_borrowed_str()`], [`visit_str()`], or [`visit_string()`]
+    /// method, regardless of the `Deserializer` method called or of [`zero_copy()`]. This allows
+    /// each of those code paths within a [`Visitor`] to be exercised.
+    ///
+    /// If not set, the visit method is chosen based on the `Deserializer` method called and
+    /// [`zero_copy()`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Delivery,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+    ///     .str_delivery(Delivery::Owned)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+    /// assert_eq!(deserializer.deliveries(), [Delivery::Owned]);
+    /// ```
+    ///
+    /// [`visit_borrowed_str()`]: serde::de::Visitor::visit_borrowed_str()
+    /// [`visit_str()`]: serde::de::Visitor::visit_str()
+    /// [`visit_string()`]: serde::de::Visitor::visit_string()
+    /// [`Visitor`]: serde::de::Visitor
+    /// [`zero_copy()`]: Builder::zero_copy()
+    pub fn str_delivery(&mut self, str_delivery: Delivery) -> &mut Self {
+        self.str_delivery = Some(str_delivery);
+        self
+    }
+
+    /// Specifies the [`Delivery`] used for the contents of every `Bytes` token.
+    ///
+    /// This is the byte equivalent of [`str_delivery()`], and is configured independently of it.
+    /// When set, all bytes are given to the visitor through the corresponding
+    /// [`visit_borrowed_bytes()`], [`visit_bytes()`], or [`visit_byte_buf()`] method, regardless of
+    /// the `Deserializer` method called or of [`zero_copy()`].
+    ///
+    /// If not set, the visit method is chosen based on the `Deserializer` method called and
+    /// [`zero_copy()`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Delivery,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// use serde_bytes::ByteBuf;
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+    ///     .bytes_delivery(Delivery::Transient)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(
+    ///     ByteBuf::deserialize(&mut deserializer),
+    ///     ByteBuf::from(b"foo".to_vec())
+    /// );
+    /// assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
+    /// ```
+    ///
+    /// [`str_delivery()`]: Builder::str_delivery()
+    /// [`visit_borrowed_bytes()`]: serde::de::Visitor::visit_borrowed_bytes()
+    /// [`visit_byte_buf()`]: serde::de::Visitor::visit_byte_buf()
+    /// [`visit_bytes()`]: serde::de::Visitor::visit_bytes()
+    /// [`zero_copy()`]: Builder::zero_copy()
+    pub fn bytes_delivery(&mut self, bytes_delivery: Delivery) -> &mut Self {
+        self.bytes_delivery = Some(bytes_delivery);
+        self
+    }
+
+    /// Determines whether bytes may be deserialized from a sequence of `u8`s.
+    ///
+    /// Formats without a native bytes type represent byte slices as sequences. When enabled,
+    /// [`deserialize_bytes()`] and [`deserialize_byte_buf()`] also accept a [`Token::Seq`]
+    /// containing the bytes as [`Token::U8`]s, calling [`visit_seq()`] on the visitor just as those
+    /// formats would. This exercises the fallback path of `Deserialize` implementations for byte
+    /// types.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// use serde_bytes::ByteBuf;
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Seq { len: Some(2) },
+    ///     Token::U8(b'a'),
+    ///     Token::U8(b'b'),
+    ///     Token::SeqEnd,
+    /// ])
+    /// .deserialize_bytes_as_seq(true)
+    /// .build();
+    ///
+    /// assert_ok_eq!(
+    ///     ByteBuf::deserialize(&mut deserializer),
+    ///     ByteBuf::from(b"ab".to_vec())
+    /// );
+    /// ```
+    ///
+    /// [`deserialize_bytes()`]: serde::Deserializer::deserialize_bytes()
+    /// [`deserialize_byte_buf()`]: serde::Deserializer::deserialize_byte_buf()
+    /// [`visit_seq()`]: serde::de::Visitor::visit_seq()
+    pub fn deserialize_bytes_as_seq(&mut self, deserialize_bytes_as_seq: bool) -> &mut Self {
+        self.deserialize_bytes_as_seq = deserialize_bytes_as_seq;
+        self
+    }
+
+    /// Determines whether `char`s may also be deserialized from single-character strings.
+    ///
+    /// Formats without a native `char` type represent `char`s as strings. When enabled,
+    /// [`deserialize_char()`] also accepts a [`Token::Str`] containing exactly one character,
+    /// matching the output of a [`Serializer`] with [`ser::Builder::char_as_str()`] enabled.
+    /// Strings containing any other number of characters result in an invalid value error.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Str("a".to_owned())])
+    ///     .char_as_str(true)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(char::deserialize(&mut deserializer), 'a');
+    /// ```
+    ///
+    /// [`deserialize_char()`]: serde::Deserializer::deserialize_char()
+    /// [`ser::Builder::char_as_str()`]: crate::ser::Builder::char_as_str()
+    /// [`Serializer`]: crate::Serializer
+    pub fn char_as_str(&mut self, char_as_str: bool) -> &mut Self {
+        self.char_as_str = char_as_str;
+        self
+    }
+
+    /// Determines whether structs may also be deserialized from a sequence of their fields.
+    ///
+    /// Compact formats often serialize structs as sequences, so by default
+    /// [`deserialize_struct()`] accepts a [`Token::Seq`] and provides it to [`visit_seq()`].
+    /// Self-describing formats like JSON never present structs as sequences, so disabling this
+    /// ensures a [`Deserialize`] implementation does not rely on positional decoding.
+    ///
+    /// If not set, the default value is `true`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Struct {
+    ///     foo: bool,
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Seq { len: Some(1) },
+    ///     Token::Bool(true),
+    ///     Token::SeqEnd,
+    /// ])
+    /// .deserialize_struct_from_seq(false)
+    /// .build();
+    ///
+    /// assert_err!(Struct::deserialize(&mut deserializer));
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    /// [`deserialize_struct()`]: serde::Deserializer::deserialize_struct()
+    /// [`visit_seq()`]: serde::de::Visitor::visit_seq()
+    pub fn deserialize_struct_from_seq(&mut self, deserialize_struct_from_seq: bool) -> &mut Self {
+        self.deserialize_struct_from_seq = deserialize_struct_from_seq;
+        self
+    }
+
+    /// Determines whether options may also be deserialized as `None` from a unit value.
+    ///
+    /// Some formats encode `None` as a null value rather than an absent marker. When enabled,
+    /// [`deserialize_option()`] accepts a [`Token::Unit`] and calls [`visit_none()`], allowing
+    /// fixtures captured from those formats to be used with types containing `Option`s.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Unit])
+    ///     .deserialize_unit_as_none(true)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(Option::<u32>::deserialize(&mut deserializer), None);
+    /// ```
+    ///
+    /// [`deserialize_option()`]: serde::Deserializer::deserialize_option()
+    /// [`visit_none()`]: serde::de::Visitor::visit_none()
+    pub fn deserialize_unit_as_none(&mut self, deserialize_unit_as_none: bool) -> &mut Self {
+        self.deserialize_unit_as_none = deserialize_unit_as_none;
+        self
+    }
+
+    /// Determines whether enums may also be deserialized from a map of their variant name to their
+    /// contents.
+    ///
+    /// Self-describing formats like JSON represent enums as `{"Variant": value}` rather than using
+    /// dedicated variant markers. When enabled, [`deserialize_enum()`] also accepts a
+    /// [`Token::Map`] containing a single entry, whose key is the variant name and whose value
+    /// is the variant's contents. Tuple variants are deserialized from a [`Token::Seq`] and
+    /// struct variants from a [`Token::Map`]. A unit variant may also be represented by a
+    /// [`Token::Str`] containing its name alone.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// enum Shape {
+    ///     Point,
+    ///     Circle(u32),
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Map { len: Some(1) },
+    ///     Token::Str("Circle".to_owned()),
+    ///     Token::U32(42),
+    ///     Token::MapEnd,
+    /// ])
+    /// .deserialize_enum_from_map(true)
+    /// .build();
+    ///
+    /// assert_ok_eq!(Shape::deserialize(&mut deserializer), Shape::Circle(42));
+    /// ```
+    ///
+    /// [`deserialize_enum()`]: serde::Deserializer::deserialize_enum()
+    pub fn deserialize_enum_from_map(&mut self, deserialize_enum_from_map: bool) -> &mut Self {
+        self.deserialize_enum_from_map = deserialize_enum_from_map;
+        self
+    }
+
+    /// Specifies the [`EnumTagging`] that enum variant tokens are rewritten to.
+    ///
+    /// Internally tagged (`#[serde(tag = "...")]`) and adjacently tagged
+    /// (`#[serde(tag = "...", content = "...")]`) enums are deserialized from structs rather than
+    /// from variant tokens. When set, the variant tokens provided to the `Deserializer` are
+    /// rewritten using [`Tokens::tag_enums()`] before being deserialized, allowing the same tokens
+    /// to be used for every representation of an enum.
+    ///
+    /// Note that the `Deserialize` implementations derived for tagged enums call
+    /// [`deserialize_any()`], so [`self_describing()`] must also be enabled.
+    ///
+    /// If not set, the default value is [`EnumTagging::External`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     token::EnumTagging,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// #[serde(tag = "type")]
+    /// enum Shape {
+    ///     Point,
+    ///     Circle { radius: u32 },
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::StructVariant {
+    ///         name: "Shape",
+    ///         variant_index: 1,
+    ///         variant: "Circle",
+    ///         len: 1,
+    ///     },
+    ///     Token::Field("radius"),
+    ///     Token::U32(42),
+    ///     Token::StructVariantEnd,
+    /// ])
+    /// .enum_tagging(EnumTagging::Internal { tag: "type" })
+    /// .self_describing(true)
+    /// .build();
+    ///
+    /// assert_ok_eq!(
+    ///     Shape::deserialize(&mut deserializer),
+    ///     Shape::Circle { radius: 42 }
+    /// );
+    /// ```
+    ///
+    /// [`deserialize_any()`]: serde::Deserializer::deserialize_any()
+    /// [`self_describing()`]: Builder::self_describing()
+    pub fn enum_tagging(&mut self, enum_tagging: EnumTagging) -> &mut Self {
+        self.enum_tagging = enum_tagging;
+        self
+    }
+
+    /// Determines whether `bool`s may also be deserialized from the integers `0` and `1`.
+    ///
+    /// Some formats and legacy data encode booleans numerically. When enabled,
+    /// [`deserialize_bool()`] accepts any integer token, calling [`visit_bool()`] with `false` for
+    /// `0` and `true` for `1`. Any other integer results in an [`Error::InvalidValue`].
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::U8(1)])
+    ///     .deserialize_bool_from_int(true)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// ```
+    ///
+    /// [`deserialize_bool()`]: serde::Deserializer::deserialize_bool()
+    /// [`visit_bool()`]: serde::de::Visitor::visit_bool()
+    pub fn deserialize_bool_from_int(&mut self, deserialize_bool_from_int: bool) -> &mut Self {
+        self.deserialize_bool_from_int = deserialize_bool_from_int;
+        self
+    }
+
+    /// Specifies which tokens 128-bit integers are deserialized from.
+    ///
+    /// Some formats only support `i128` and `u128` through a string or byte encoding. Setting this
+    /// to something other than [`Deserialize128As::Integer`] requires [`deserialize_i128()`] and
+    /// [`deserialize_u128()`] to be given tokens in that encoding, rejecting [`Token::I128`] and
+    /// [`Token::U128`].
+    ///
+    /// If not set, the default value is [`Deserialize128As::Integer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Deserialize128As,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Str("42".to_owned())])
+    ///     .deserialize_128_as(Deserialize128As::Str)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(u128::deserialize(&mut deserializer), 42);
+    /// ```
+    ///
+    /// [`deserialize_i128()`]: serde::Deserializer::deserialize_i128()
+    /// [`deserialize_u128()`]: serde::Deserializer::deserialize_u128()
+    pub fn deserialize_128_as(&mut self, deserialize_128_as: Deserialize128As) -> &mut Self {
+        self.deserialize_128_as = deserialize_128_as;
+        self
+    }
+
+    /// Determines whether the deserializer supports 128-bit integers.
+    ///
+    /// Many formats don't support `i128` and `u128`, and return an error from
+    /// [`deserialize_i128()`] and [`deserialize_u128()`]. When disabled, those methods return an
+    /// [`Error::Custom`] without consuming a token, regardless of [`deserialize_128_as()`],
+    /// allowing the fallback paths of [`Deserialize`] implementations targeting such formats to be
+    /// tested.
+    ///
+    /// If not set, the default value is `true`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Error,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::U128(42)])
+    ///     .support_128_bit(false)
+    ///     .build();
+    ///
+    /// assert_err_eq!(
+    ///     u128::deserialize(&mut deserializer),
+    ///     Error::Custom("u128 is not supported".to_owned())
+    /// );
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    /// [`deserialize_128_as()`]: Builder::deserialize_128_as()
+    /// [`deserialize_i128()`]: serde::Deserializer::deserialize_i128()
+    /// [`deserialize_u128()`]: serde::Deserializer::deserialize_u128()
+    pub fn support_128_bit(&mut self, support_128_bit: bool) -> &mut Self {
+        self.support_128_bit = support_128_bit;
+        self
+    }
+
+    /// Determines whether `Field` tokens not known to the struct being deserialized are rejected.
+    ///
+    /// When enabled, a [`Token::Field`] whose name is not in the `fields` list passed to
+    /// [`deserialize_struct()`] or [`struct_variant()`] results in an [`Error::UnknownField`]
+    /// before the field is provided to the visitor. This emulates the behavior of types using
+    /// `#[serde(deny_unknown_fields)]`, allowing both tolerant and strict handling of unknown
+    /// fields to be tested.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Error,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Struct {
+    ///     foo: bool,
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Struct {
+    ///         name: "Struct",
+    ///         len: 2,
+    ///     },
+    ///     Token::Field("foo"),
+    ///     Token::Bool(true),
+    ///     Token::Field("bar"),
+    ///     Token::Bool(false),
+    ///     Token::StructEnd,
+    /// ])
+    /// .deny_unknown_fields(true)
+    /// .build();
+    ///
+    /// assert_err_eq!(
+    ///     Struct::deserialize(&mut deserializer),
+    ///     Error::UnknownField("bar".to_owned(), &["foo"])
+    /// );
+    /// ```
+    ///
+    /// [`deserialize_struct()`]: serde::Deserializer::deserialize_struct()
+    /// [`struct_variant()`]: serde::de::VariantAccess::struct_variant()
+    pub fn deny_unknown_fields(&mut self, deny_unknown_fields: bool) -> &mut Self {
+        self.deny_unknown_fields = deny_unknown_fields;
+        self
+    }
+
+    /// Determines whether keys appearing more than once within a single map or struct are rejected.
+    ///
+    /// When enabled, the tokens of each key are compared against those of the previous keys in the
+    /// same map or struct. A repeated [`Token::Field`] results in an [`Error::DuplicateField`], and
+    /// any other repeated key results in an [`Error::DuplicateKey`]. This emulates strict formats,
+    /// allowing a [`Deserialize`] implementation's behavior to be verified independently of whether
+    /// its visitor tracks duplicates itself.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_matches;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Error,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// use std::collections::HashMap;
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Map { len: Some(2) },
+    ///     Token::Char('a'),
+    ///     Token::U32(1),
+    ///     Token::Char('a'),
+    ///     Token::U32(2),
+    ///     Token::MapEnd,
+    /// ])
+    /// .deny_duplicate_keys(true)
+    /// .build();
+    ///
+    /// assert_matches!(
+    ///     HashMap::<char, u32>::deserialize(&mut deserializer),
+    ///     Err(Error::DuplicateKey(key)) if key == [Token::Char('a')]
+    /// );
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    pub fn deny_duplicate_keys(&mut self, deny_duplicate_keys: bool) -> &mut Self {
+        self.deny_duplicate_keys = deny_duplicate_keys;
+        self
+    }
+
+    /// Determines whether map keys must be strings.
+    ///
+    /// JSON-like formats only support string keys, so consumers of those formats fail when a map
+    /// key is anything else. When enabled, any key that is not a [`Token::Str`], [`Token::Field`],
+    /// or [`Token::Char`] results in an [`Error::InvalidType`]. This allows verifying that types
+    /// with non-string keys either produce the expected error or fall back to string keys.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// use std::collections::HashMap;
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Map { len: Some(1) },
+    ///     Token::U32(1),
+    ///     Token::Bool(true),
+    ///     Token::MapEnd,
+    /// ])
+    /// .require_string_keys(true)
+    /// .build();
+    ///
+    /// assert_err!(HashMap::<u32, bool>::deserialize(&mut deserializer));
+    /// ```
+    pub fn require_string_keys(&mut self, require_string_keys: bool) -> &mut Self {
+        self.require_string_keys = require_string_keys;
+        self
+    }
+
+    /// Determines whether tokens may remain after the root value is deserialized.
+    ///
+    /// By default, [`Deserializer::end()`] returns an [`Error::TrailingTokens`] if any tokens were
+    /// left unconsumed. Framed protocols often expect trailing data to be handled by an outer
+    /// layer, so enabling this permits leftover tokens. The number of leftover tokens can be
+    /// queried using [`Deserializer::remaining()`].
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::{
+    ///     assert_ok,
+    ///     assert_ok_eq,
+    /// };
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true), Token::Unit])
+    ///     .allow_trailing_tokens(true)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// assert_ok!(deserializer.end());
+    /// assert_eq!(deserializer.remaining(), 1);
+    /// ```
+    pub fn allow_trailing_tokens(&mut self, allow_trailing_tokens: bool) -> &mut Self {
+        self.allow_trailing_tokens = allow_trailing_tokens;
+        self
+    }
+
+    /// Determines whether maps and structs are implicitly ended when the tokens run out.
+    ///
+    /// By default, reaching the end of the tokens while deserializing a map, struct, or struct
+    /// variant results in an [`Error::EndOfTokens`]. Some streaming decoders are more forgiving,
+    /// treating the end of the input as the end of any open maps. When enabled, running out of
+    /// tokens where the next key or end token is expected ends the map instead, allowing testing
+    /// of the partial values produced from truncated input.
+    ///
+    /// Running out of tokens anywhere else, such as between a key and its value, still results in
+    /// an [`Error::EndOfTokens`].
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// use std::collections::HashMap;
+    ///
+    /// let mut deserializer =
+    ///     Deserializer::builder([Token::Map { len: Some(2) }, Token::Char('a'), Token::U32(1)])
+    ///         .implicit_map_end(true)
+    ///         .build();
+    ///
+    /// assert_ok_eq!(
+    ///     HashMap::<char, u32>::deserialize(&mut deserializer),
+    ///     [('a', 1)].into_iter().collect::<HashMap<_, _>>()
+    /// );
+    /// ```
+    pub fn implicit_map_end(&mut self, implicit_map_end: bool) -> &mut Self {
+        self.implicit_map_end = implicit_map_end;
+        self
+    }
+
+    /// Causes the `Deserializer` to fail once `fail_after_tokens` tokens have been consumed.
+    ///
+    /// Any attempt to consume another token results in an [`Error::Injected`]. Valid input
+    /// normally never exercises the error paths of a [`Deserialize`] implementation, such as
+    /// cleanup performed when a nested value fails. Injecting a failure at each token in turn
+    /// allows every such path to be tested.
+    ///
+    /// If not set, the `Deserializer` never fails because of the number of tokens consumed.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Error,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Seq { len: Some(2) },
+    ///     Token::U32(1),
+    ///     Token::U32(2),
+    ///     Token::SeqEnd,
+    /// ])
+    /// .fail_after_tokens(2)
+    /// .build();
+    ///
+    /// assert_err_eq!(
+    ///     Vec::<u32>::deserialize(&mut deserializer),
+    ///     Error::Injected(2)
+    /// );
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    pub fn fail_after_tokens(&mut self, fail_after_tokens: usize) -> &mut Self {
+        self.fail_after_tokens = Some(fail_after_tokens);
+        self
+    }
+
+    /// Determines whether every consumed token is recorded.
+    ///
+    /// When enabled, each consumed token is recorded along with the [`serde::Deserializer`] method
+    /// that consumed it, which can be retrieved afterwards using [`consumptions()`] or [`trace()`].
+    /// As this requires copying every consumed token, it is disabled by default.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .record_consumptions(true)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// assert_eq!(deserializer.trace(), "deserialize_bool: Bool(true)\n");
+    /// ```
+    ///
+    /// [`consumptions()`]: Deserializer::consumptions()
+    /// [`trace()`]: Deserializer::trace()
+    pub fn record_consumptions(&mut self, record_consumptions: bool) -> &mut Self {
+        self.record_consumptions = record_consumptions;
+        self
+    }
+
+    /// Records the [`serde::Deserializer`] and [`Visitor`] methods called during deserialization
+    /// into the given [`Coverage`].
+    ///
+    /// The `Coverage` is shared by every `Deserializer` built with it, allowing calls to be
+    /// aggregated across an entire test run. See [`Coverage`] for more details.
+    ///
+    /// If not set, calls are not recorded into any `Coverage`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Coverage,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .coverage(&COVERAGE)
+    ///     .build();
+    /// assert_ok!(bool::deserialize(&mut deserializer));
+    ///
+    /// assert_eq!(COVERAGE.count("deserialize_bool"), 1);
+    /// ```
+    ///
+    /// [`Visitor`]: serde::de::Visitor
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn coverage(&mut self, coverage: &'static Coverage) -> &mut Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// Adds a pass that rewrites the tokens before they are deserialized.
+    ///
+    /// Each pass is given the [`Token`]s provided to the `Builder`, and may modify them
+    /// arbitrarily, such as injecting unknown fields into structs or upgrading old field names.
+    /// Passes are run in the order they are added, each time a `Deserializer` is built. This
+    /// allows a single canonical set of tokens to be turned into many input variants.
+    ///
+    /// Any [`Unordered`] tokens produced by a pass are flattened, in the order they are provided.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Struct {
+    ///     bar: bool,
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Struct {
+    ///         name: "Struct",
+    ///         len: 1,
+    ///     },
+    ///     Token::Field("foo"),
+    ///     Token::Bool(true),
+    ///     Token::StructEnd,
+    /// ])
+    /// .pre_process(|tokens| {
+    ///     for token in tokens {
+    ///         if let Token::Field("foo") = token {
+    ///             *token = Token::Field("bar");
+    ///         }
+    ///     }
+    /// })
+    /// .build();
+    ///
+    /// assert_ok_eq!(Struct::deserialize(&mut deserializer), Struct { bar: true });
+    /// ```
+    ///
+    /// [`Unordered`]: Token::Unordered
+    pub fn pre_process<F>(&mut self, pass: F) -> &mut Self
+    where
+        F: Fn(&mut Vec<Token>) + 'static,
+    {
+        self.pre_processors.push(PreProcessor(Rc::new(pass)));
+        self
+    }
+
+    /// Determines whether the remaining tokens are printed if the thread panics while the
+    /// `Deserializer` is alive.
+    ///
+    /// When enabled, dropping the `Deserializer` during a panic (such as a failed assertion in a
+    /// test) prints the number of tokens consumed and the tokens that remain to standard error.
+    /// This helps diagnose where deserialization stopped without adding prints manually.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// This option requires the `std` feature.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .dump_on_panic(true)
+    ///     .build();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn dump_on_panic(&mut self, dump_on_panic: bool) -> &mut Self {
+        self.dump_on_panic = dump_on_panic;
+        self
+    }
+
+    /// Determines whether each consumed token is printed as it is consumed.
+    ///
+    /// When enabled, every token consumed by the `Deserializer` is printed to standard error along
+    /// with the name of the [`serde::Deserializer`] method that consumed it. This is useful for
+    /// quickly seeing how far a [`Deserialize`] implementation gets through the tokens during local
+    /// debugging.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// This option requires the `std` feature.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .log_consumption(true)
+    ///     .build();
+    /// ```
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    #[cfg(feature = "std")]
+    pub fn log_consumption(&mut self, log_consumption: bool) -> &mut Self {
+        self.log_consumption = log_consumption;
+        self
+    }
+
+    /// Applies a [`Profile`] approximating a commonly targeted format.
+    ///
+    /// This sets [`is_human_readable()`], [`self_describing()`], [`content_compatible()`],
+    /// [`zero_copy()`], [`deserialize_bytes_as_seq()`], [`char_as_str()`],
+    /// [`deserialize_struct_from_seq()`], [`deserialize_unit_as_none()`],
+    /// [`deserialize_enum_from_map()`], [`deserialize_bool_from_int()`], and
+    /// [`deserialize_128_as()`] to match the format. Any of these can be overridden by setting them
+    /// after the profile is applied.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     profile::Profile,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer = Deserializer::builder([Token::Unit])
+    ///     .profile(Profile::Json)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(Option::<u32>::deserialize(&mut deserializer), None);
+    /// ```
+    ///
+    /// [`char_as_str()`]: Builder::char_as_str()
+    /// [`content_compatible()`]: Builder::content_compatible()
+    /// [`deserialize_128_as()`]: Builder::deserialize_128_as()
+    /// [`deserialize_bool_from_int()`]: Builder::deserialize_bool_from_int()
+    /// [`deserialize_bytes_as_seq()`]: Builder::deserialize_bytes_as_seq()
+    /// [`deserialize_enum_from_map()`]: Builder::deserialize_enum_from_map()
+    /// [`deserialize_struct_from_seq()`]: Builder::deserialize_struct_from_seq()
+    /// [`deserialize_unit_as_none()`]: Builder::deserialize_unit_as_none()
+    /// [`is_human_readable()`]: Builder::is_human_readable()
+    /// [`self_describing()`]: Builder::self_describing()
+    /// [`zero_copy()`]: Builder::zero_copy()
+    pub fn profile(&mut self, profile: Profile) -> &mut Self {
+        match profile {
+            Profile::Json => self
+                .is_human_readable(true)
+                .self_describing(true)
+                .content_compatible(true)
+                .zero_copy(true)
+                .deserialize_bytes_as_seq(true)
+                .char_as_str(true)
+                .deserialize_struct_from_seq(true)
+                .deserialize_unit_as_none(true)
+                .deserialize_enum_from_map(true)
+                .deserialize_bool_from_int(false)
+                .deserialize_128_as(Deserialize128As::Integer),
+            Profile::Bincode | Profile::Postcard => self
+                .is_human_readable(false)
+                .self_describing(false)
+                .content_compatible(false)
+                .zero_copy(true)
+                .deserialize_bytes_as_seq(false)
+                .char_as_str(false)
+                .deserialize_struct_from_seq(true)
+                .deserialize_unit_as_none(false)
+                .deserialize_enum_from_map(false)
+                .deserialize_bool_from_int(false)
+                .deserialize_128_as(Deserialize128As::Integer),
+        }
+    }
+
+    /// Build a new [`Deserializer`] using this `Builder`.
+    ///
+    /// Constructs a new `Deserializer` using the configuration options set on this `Builder`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let deserializer = Deserializer::builder([Token::Bool(true)])
+    ///     .is_human_readable(false)
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn build<'a>(&self) -> Deserializer<'a> {
+        let (tokens, labels) = if self.pre_processors.is_empty() {
+            (self.tokens.clone(), self.labels.clone())
+        } else {
+            // Labels are reattached to their tokens, allowing them to be moved by the passes.
+            let mut tokens = self
+                .tokens
+                .clone()
+                .into_iter()
+                .enumerate()
+                .map(|(index, token)| match self.labels.get(&index) {
+                    Some(label) => Token::labeled(label, token),
+                    None => token,
+                })
+                .collect::<Vec<_>>();
+            for pre_processor in &self.pre_processors {
+                (pre_processor.0)(&mut tokens);
+            }
+            let labels = token::labels(&tokens);
+            (Tokens::canonicalize(tokens), labels)
+        };
+        let (tokens, labels) = if self.enum_tagging == EnumTagging::External {
+            (tokens, labels)
+        } else {
+            let mut tagged = Vec::with_capacity(tokens.0.len());
+            token::tag_enums(&tokens.0, self.enum_tagging, &mut tagged);
+            // Rewritten tokens keep the label of the token they originate from.
+            let labels = tagged
+                .iter()
+                .enumerate()
+                .filter_map(|(index, (origin, _))| labels.get(origin).map(|label| (index, *label)))
+                .collect();
+            (
+                Tokens(tagged.into_iter().map(|(_, token)| token).collect()),
+                labels,
+            )
+        };
+
+        Deserializer {
+            tokens: token::OwningIter::new(tokens),
+            labels,
+
+            revisited_token: None,
+
+            visits: Rc::new(RefCell::new(Vec::new())),
+            consumed: 0,
+            consumptions: Vec::new(),
+            deliveries: Vec::new(),
+            key_tokens: None,
+            #[cfg(target_has_atomic = "ptr")]
+            coverage: self.coverage,
+
+            is_human_readable: self.is_human_readable,
+            self_describing: self.self_describing,
+            content_compatible: self.content_compatible,
+            zero_copy: self.zero_copy,
+            str_delivery: self.str_delivery,
+            bytes_delivery: self.bytes_delivery,
+            deserialize_bytes_as_seq: self.deserialize_bytes_as_seq,
+            char_as_str: self.char_as_str,
+            deserialize_struct_from_seq: self.deserialize_struct_from_seq,
+            deserialize_unit_as_none: self.deserialize_unit_as_none,
+            deserialize_enum_from_map: self.deserialize_enum_from_map,
+            deserialize_bool_from_int: self.deserialize_bool_from_int,
+            deserialize_128_as: self.deserialize_128_as,
+            support_128_bit: self.support_128_bit,
+            deny_unknown_fields: self.deny_unknown_fields,
+            deny_duplicate_keys: self.deny_duplicate_keys,
+            require_string_keys: self.require_string_keys,
+            allow_trailing_tokens: self.allow_trailing_tokens,
+            implicit_map_end: self.implicit_map_end,
+            fail_after_tokens: self.fail_after_tokens,
+            record_consumptions: self.record_consumptions,
+            #[cfg(feature = "std")]
+            dump_on_panic: self.dump_on_panic,
+            #[cfg(feature = "std")]
+            log_consumption: self.log_consumption,
+        }
+    }
+}
+
+/// A pre-processing pass registered on a [`Builder`].
+#[derive(Clone)]
+struct PreProcessor(Rc<Pass>);
+
+impl fmt::Debug for PreProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PreProcessor")
+    }
+}
+
+/// Drives a [`Visitor`] directly using the given tokens.
+///
+/// The tokens should represent a single value, such as a single [`Token`] or a balanced group of
+/// tokens (for example, a [`Token::Seq`] followed by its elements and a [`Token::SeqEnd`]). The
+/// visitor is called using the `visit_*` method corresponding to the tokens, in the same way as
+/// a self-describing format would call it through [`deserialize_any()`].
+///
+/// This is useful for unit testing helper visitors used within larger [`Deserialize`]
+/// implementations in isolation, without needing to wrap them in a full `Deserialize` type.
+///
+/// Since the tokens are owned by this function, zero-copy deserialization is disabled.
+///
+/// # Errors
+/// Returns an [`Error`] if the visitor rejects the tokens, or if the tokens are not a complete
+/// value.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde::de::{
+///     Error,
+///     Visitor,
+/// };
+/// use serde_assert::{
+///     de::drive_visitor,
+///     Token,
+/// };
+/// use std::fmt;
+///
+/// struct EvenVisitor;
+///
+/// impl<'de> Visitor<'de> for EvenVisitor {
+///     type Value = u32;
+///
+///     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+///         formatter.write_str("an even number")
+///     }
+///
+///     fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+///     where
+///         E: Error,
+///     {
+///         if v % 2 == 0 {
+///             Ok(v)
+///         } else {
+///             Err(E::custom("odd number"))
+///         }
+///     }
+/// }
+///
+/// assert_ok_eq!(drive_visitor([Token::U32(42)], EvenVisitor), 42);
+/// ```
+///
+/// [`Deserialize`]: serde::Deserialize
+/// [`deserialize_any()`]: serde::Deserializer::deserialize_any()
+/// [`Visitor`]: serde::de::Visitor
+pub fn drive_visitor<'de, T, V>(tokens: T, visitor: V) -> Result<V::Value, Error>
+where
+    T: IntoIterator<Item = Token>,
+    V: de::Visitor<'de>,
+{
+    let mut deserializer = Deserializer::builder(tokens)
+        .self_describing(true)
+        .zero_copy(false)
+        .build();
+    de::Deserializer::deserialize_any(&mut deserializer, visitor)
+}
+
+/// An error encountered during deserialization.
+///
+/// # Example
+/// ```rust
+/// use serde::de::Error as _;
+/// use serde_assert::de::Error;
+///
+/// assert_eq!(
+///     format!("{}", Error::missing_field("foo")),
+///     "missing field foo"
+/// );
+/// ```
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The [`Deserializer`] reached the end of the input [`Token`]s before deserialization was
+    /// completed.
+    EndOfTokens,
+
+    /// Expected a `Token::SeqEnd`.
+    ExpectedSeqEnd,
+    /// Expected a `Token::TupleEnd`.
+    ExpectedTupleEnd,
+    /// Expected a `Token::TupleStructEnd`.
     ExpectedTupleStructEnd,
     /// Expected a `Token::TupleVariantEnd`.
     ExpectedTupleVariantEnd,
@@ -1407,444 +4799,3337 @@ pub enum Error {
     /// Expected a `Token::StructVariantEnd`.
     ExpectedStructVariantEnd,
 
-    /// An unsupported [`serde::Deserializer`] method was called during deserialization of an
-    /// `enum` variant.
-    ///
-    /// If you encounter this error, check what methods you are calling when deserializing your
-    /// `enum` variants. Many standard `serde` types are not supported in this context.
-    UnsupportedEnumDeserializerMethod,
+    /// An unsupported [`serde::Deserializer`] method was called during deserialization of an
+    /// `enum` variant.
+    ///
+    /// If you encounter this error, check what methods you are calling when deserializing your
+    /// `enum` variants. Many standard `serde` types are not supported in this context.
+    UnsupportedEnumDeserializerMethod,
+
+    /// The [`Deserializer`] was set to be non-self-describing, but the [`Deserialize`]
+    /// implementation made a call to [`deserialize_any()`].
+    ///
+    /// [`Deserialize`]: serde::Deserialize
+    /// [`deserialize_any()`]: ../struct.Deserializer.html#method.deserialize_any
+    NotSelfDescribing,
+
+    /// An error created by calling [`custom()`].
+    ///
+    /// [`custom()`]: Error::custom()
+    Custom(String),
+    /// An error created by calling [`invalid_type()`].
+    ///
+    /// Contains the unexpected value that was found and the rendered expectation.
+    ///
+    /// [`invalid_type()`]: Error::invalid_type()
+    InvalidType(UnexpectedValue, String),
+    /// An error created by calling [`invalid_value()`].
+    ///
+    /// Contains the unexpected value that was found and the rendered expectation.
+    ///
+    /// [`invalid_value()`]: Error::invalid_value()
+    InvalidValue(UnexpectedValue, String),
+    /// An error created by calling [`invalid_length()`].
+    ///
+    /// [`invalid_length()`]: Error::invalid_length()
+    InvalidLength(usize, String),
+    /// An error created by calling [`unknown_variant()`].
+    ///
+    /// [`unknown_variant()`]: Error::unknown_variant()
+    UnknownVariant(String, &'static [&'static str]),
+    /// An error created by calling [`unknown_field()`].
+    ///
+    /// [`unknown_field()`]: Error::unknown_field()
+    UnknownField(String, &'static [&'static str]),
+    /// An error created by calling [`missing_field()`].
+    ///
+    /// [`missing_field()`]: Error::missing_field()
+    MissingField(&'static str),
+    /// An error created by calling [`duplicate_field()`].
+    ///
+    /// [`duplicate_field()`]: Error::duplicate_field()
+    DuplicateField(&'static str),
+    /// A key appeared more than once within a single map or struct.
+    ///
+    /// This is only returned when [`deny_duplicate_keys()`] is enabled. The contained value is the
+    /// tokens of the key.
+    ///
+    /// [`deny_duplicate_keys()`]: Builder::deny_duplicate_keys()
+    DuplicateKey(Tokens),
+    /// Tokens remained after the root value was deserialized.
+    ///
+    /// This is returned by [`Deserializer::end()`] unless [`allow_trailing_tokens()`] is enabled.
+    /// The contained value is the number of remaining tokens.
+    ///
+    /// [`allow_trailing_tokens()`]: Builder::allow_trailing_tokens()
+    TrailingTokens(usize),
+    /// A failure was injected after the contained number of tokens were consumed.
+    ///
+    /// This is only returned when [`fail_after_tokens()`] is set.
+    ///
+    /// [`fail_after_tokens()`]: Builder::fail_after_tokens()
+    Injected(usize),
+}
+
+impl Error {
+    /// Returns the unexpected value that caused this error, if there is one.
+    ///
+    /// This is only present for [`InvalidType`] and [`InvalidValue`] errors, and allows asserting
+    /// on what was found without comparing formatted text.
+    ///
+    /// # Example
+    /// ```rust
+    /// use claims::assert_some_eq;
+    /// use serde::de::{
+    ///     Error as _,
+    ///     Unexpected,
+    /// };
+    /// use serde_assert::de::{
+    ///     Error,
+    ///     UnexpectedValue,
+    /// };
+    ///
+    /// let error = Error::invalid_type(Unexpected::Signed(-1), &"a u8");
+    ///
+    /// assert_some_eq!(error.unexpected(), &UnexpectedValue::Signed(-1));
+    /// ```
+    ///
+    /// [`InvalidType`]: Error::InvalidType
+    /// [`InvalidValue`]: Error::InvalidValue
+    #[must_use]
+    pub fn unexpected(&self) -> Option<&UnexpectedValue> {
+        match self {
+            Self::InvalidType(unexpected, _) | Self::InvalidValue(unexpected, _) => {
+                Some(unexpected)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the rendered expectation of this error, if there is one.
+    ///
+    /// This is only present for [`InvalidType`], [`InvalidValue`], and [`InvalidLength`] errors.
+    ///
+    /// # Example
+    /// ```rust
+    /// use claims::assert_some_eq;
+    /// use serde::de::{
+    ///     Error as _,
+    ///     Unexpected,
+    /// };
+    /// use serde_assert::de::Error;
+    ///
+    /// let error = Error::invalid_value(Unexpected::Unsigned(300), &"a u8");
+    ///
+    /// assert_some_eq!(error.expected(), "a u8");
+    /// ```
+    ///
+    /// [`InvalidLength`]: Error::InvalidLength
+    /// [`InvalidType`]: Error::InvalidType
+    /// [`InvalidValue`]: Error::InvalidValue
+    #[must_use]
+    pub fn expected(&self) -> Option<&str> {
+        match self {
+            Self::InvalidType(_, expected)
+            | Self::InvalidValue(_, expected)
+            | Self::InvalidLength(_, expected) => Some(expected),
+            _ => None,
+        }
+    }
+
+    fn duplicate_key(key: &[CanonicalToken]) -> Self {
+        match key {
+            [CanonicalToken::Field(field)] => Self::DuplicateField(field),
+            _ => Self::DuplicateKey(Tokens(key.to_vec())),
+        }
+    }
+
+    fn expected_end_token(end_token: EndToken) -> Self {
+        match end_token {
+            EndToken::Seq => Self::ExpectedSeqEnd,
+            EndToken::Tuple => Self::ExpectedTupleEnd,
+            EndToken::TupleStruct => Self::ExpectedTupleStructEnd,
+            EndToken::TupleVariant => Self::ExpectedTupleVariantEnd,
+            EndToken::Map => Self::ExpectedMapEnd,
+            EndToken::Struct => Self::ExpectedStructEnd,
+            EndToken::StructVariant => Self::ExpectedStructVariantEnd,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EndOfTokens => f.write_str("end of tokens"),
+            Self::ExpectedSeqEnd => f.write_str("expected token SeqEnd"),
+            Self::ExpectedTupleEnd => f.write_str("expected token TupleEnd"),
+            Self::ExpectedTupleStructEnd => f.write_str("expected token TupleStructEnd"),
+            Self::ExpectedTupleVariantEnd => f.write_str("expected token TupleVariantEnd"),
+            Self::ExpectedMapEnd => f.write_str("expected token MapEnd"),
+            Self::ExpectedStructEnd => f.write_str("expected token StructEnd"),
+            Self::ExpectedStructVariantEnd => f.write_str("expected token StructVariantEnd"),
+            Self::UnsupportedEnumDeserializerMethod => f.write_str("use of unsupported enum deserializer method"),
+            Self::NotSelfDescribing => f.write_str("attempted to deserialize as self-describing when deserializer is not set as self-describing"),
+            Self::Custom(s) => f.write_str(s),
+            Self::InvalidType(unexpected, expected) => write!(f, "invalid type: expected {expected}, found {unexpected}"),
+            Self::InvalidValue(unexpected, expected) => write!(f, "invalid value: expected {expected}, found {unexpected}"),
+            Self::InvalidLength(length, expected) => write!(f, "invalid length {length}, expected {expected}"),
+            Self::UnknownVariant(variant, expected) => write!(f, "unknown variant {variant}, expected one of {expected:?}"),
+            Self::UnknownField(field, expected) => write!(f, "unknown field {field}, expected one of {expected:?}"),
+            Self::MissingField(field) => write!(f, "missing field {field}"),
+            Self::DuplicateField(field) => write!(f, "duplicate field {field}"),
+            Self::DuplicateKey(key) => {
+                f.write_str("duplicate key ")?;
+                for (index, token) in key.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{token:?}")?;
+                }
+                Ok(())
+            }
+            Self::TrailingTokens(remaining) => write!(f, "{remaining} trailing tokens after value"),
+            Self::Injected(consumed) => write!(f, "injected failure after {consumed} tokens"),
+        }
+    }
+}
+
+impl de::StdError for Error {}
+
+impl de::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    fn invalid_type(unexpected: Unexpected, expected: &dyn Expected) -> Self {
+        Self::InvalidType(unexpected.into(), expected.to_string())
+    }
+
+    fn invalid_value(unexpected: Unexpected, expected: &dyn Expected) -> Self {
+        Self::InvalidValue(unexpected.into(), expected.to_string())
+    }
+
+    fn invalid_length(len: usize, expected: &dyn Expected) -> Self {
+        Self::InvalidLength(len, expected.to_string())
+    }
+
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        Self::UnknownVariant(variant.to_string(), expected)
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Self::UnknownField(field.to_string(), expected)
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::MissingField(field)
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        Self::DuplicateField(field)
+    }
+}
+
+/// An owned equivalent of [`serde::de::Unexpected`].
+///
+/// Stored by [`Error::InvalidType`] and [`Error::InvalidValue`] so that the kind and value of what
+/// was found can be inspected directly.
+///
+/// # Example
+/// ```rust
+/// use serde::de::Unexpected;
+/// use serde_assert::de::UnexpectedValue;
+///
+/// assert_eq!(
+///     UnexpectedValue::from(Unexpected::Str("foo")),
+///     UnexpectedValue::Str("foo".to_owned())
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnexpectedValue {
+    /// A boolean.
+    Bool(bool),
+    /// An unsigned integer.
+    Unsigned(u64),
+    /// A signed integer.
+    Signed(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A `char`.
+    Char(char),
+    /// A string.
+    Str(String),
+    /// A byte array.
+    Bytes(Vec<u8>),
+    /// A unit value.
+    Unit,
+    /// An `Option`.
+    Option,
+    /// A newtype struct.
+    NewtypeStruct,
+    /// A sequence.
+    Seq,
+    /// A map.
+    Map,
+    /// An enum.
+    Enum,
+    /// A unit variant.
+    UnitVariant,
+    /// A newtype variant.
+    NewtypeVariant,
+    /// A tuple variant.
+    TupleVariant,
+    /// A struct variant.
+    StructVariant,
+    /// Any other unexpected value, described by the contained string.
+    Other(String),
+}
+
+impl UnexpectedValue {
+    /// Returns the equivalent [`serde::de::Unexpected`], borrowing from `self`.
+    #[must_use]
+    pub fn as_unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Self::Bool(value) => Unexpected::Bool(*value),
+            Self::Unsigned(value) => Unexpected::Unsigned(*value),
+            Self::Signed(value) => Unexpected::Signed(*value),
+            Self::Float(value) => Unexpected::Float(*value),
+            Self::Char(value) => Unexpected::Char(*value),
+            Self::Str(value) => Unexpected::Str(value),
+            Self::Bytes(value) => Unexpected::Bytes(value),
+            Self::Unit => Unexpected::Unit,
+            Self::Option => Unexpected::Option,
+            Self::NewtypeStruct => Unexpected::NewtypeStruct,
+            Self::Seq => Unexpected::Seq,
+            Self::Map => Unexpected::Map,
+            Self::Enum => Unexpected::Enum,
+            Self::UnitVariant => Unexpected::UnitVariant,
+            Self::NewtypeVariant => Unexpected::NewtypeVariant,
+            Self::TupleVariant => Unexpected::TupleVariant,
+            Self::StructVariant => Unexpected::StructVariant,
+            Self::Other(value) => Unexpected::Other(value),
+        }
+    }
+}
+
+impl From<Unexpected<'_>> for UnexpectedValue {
+    fn from(unexpected: Unexpected<'_>) -> Self {
+        match unexpected {
+            Unexpected::Bool(value) => Self::Bool(value),
+            Unexpected::Unsigned(value) => Self::Unsigned(value),
+            Unexpected::Signed(value) => Self::Signed(value),
+            Unexpected::Float(value) => Self::Float(value),
+            Unexpected::Char(value) => Self::Char(value),
+            Unexpected::Str(value) => Self::Str(value.to_owned()),
+            Unexpected::Bytes(value) => Self::Bytes(value.to_vec()),
+            Unexpected::Unit => Self::Unit,
+            Unexpected::Option => Self::Option,
+            Unexpected::NewtypeStruct => Self::NewtypeStruct,
+            Unexpected::Seq => Self::Seq,
+            Unexpected::Map => Self::Map,
+            Unexpected::Enum => Self::Enum,
+            Unexpected::UnitVariant => Self::UnitVariant,
+            Unexpected::NewtypeVariant => Self::NewtypeVariant,
+            Unexpected::TupleVariant => Self::TupleVariant,
+            Unexpected::StructVariant => Self::StructVariant,
+            Unexpected::Other(value) => Self::Other(value.to_owned()),
+        }
+    }
+}
+
+impl Display for UnexpectedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_unexpected().fmt(f)
+    }
+}
+
+/// A [`Deserializer`](de::Deserializer) adapter recording the [`Tokens`] observed while
+/// deserializing from another format.
+///
+/// Every call is forwarded to the wrapped deserializer, and every value it delivers to the
+/// visitor is recorded as the equivalent [`Token`]. This allows capturing the tokens for an
+/// existing document in a real format, such as JSON, to be used as test input.
+///
+/// Names and lengths that are not part of the wrapped format are taken from the hints given by
+/// the `Deserialize` implementation. For example, a derived struct deserialized from a JSON object
+/// is recorded as a [`Token::Struct`], and keys matching its fields are recorded as
+/// [`Token::Field`]s. Compound lengths are the number of elements or entries actually delivered.
+///
+/// Only values delivered to the visitor are recorded. Values skipped by the wrapped deserializer,
+/// such as those ignored through [`deserialize_ignored_any()`], are recorded as whatever the format
+/// delivers in their place, which for many formats is a single [`Token::Unit`].
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok;
+/// use serde::Deserialize;
+/// use serde_assert::{
+///     de::RecordingDeserializer,
+///     Token,
+/// };
+///
+/// let mut json = serde_json::Deserializer::from_str("[1, 2, 3]");
+/// let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+///     &mut json,
+///     Vec::<u8>::deserialize
+/// ));
+///
+/// assert_eq!(value, [1, 2, 3]);
+/// assert_eq!(
+///     tokens,
+///     [
+///         Token::Seq { len: Some(3) },
+///         Token::U64(1),
+///         Token::U64(2),
+///         Token::U64(3),
+///         Token::SeqEnd,
+///     ]
+/// );
+/// ```
+///
+/// [`deserialize_ignored_any()`]: de::Deserializer::deserialize_ignored_any()
+#[derive(Debug)]
+pub struct RecordingDeserializer<D> {
+    deserializer: D,
+    tokens: Rc<RefCell<Vec<CanonicalToken>>>,
+}
+
+impl<D> RecordingDeserializer<D> {
+    /// Records the [`Tokens`] observed by a deserialization function.
+    ///
+    /// The provided function is called with a `RecordingDeserializer` wrapping the given
+    /// deserializer. The value it returns is returned along with the recorded `Tokens`.
+    ///
+    /// # Errors
+    /// Returns any error returned by the provided function.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::RecordingDeserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut json = serde_json::Deserializer::from_str("null");
+    /// let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+    ///     &mut json,
+    ///     Option::<bool>::deserialize
+    /// ));
+    ///
+    /// assert_eq!(value, None);
+    /// assert_eq!(tokens, [Token::None]);
+    /// ```
+    pub fn record<'de, T, E, F>(deserializer: D, deserialize: F) -> Result<(T, Tokens), E>
+    where
+        D: de::Deserializer<'de>,
+        F: FnOnce(RecordingDeserializer<D>) -> Result<T, E>,
+    {
+        let tokens = Rc::new(RefCell::new(Vec::new()));
+        let value = deserialize(RecordingDeserializer {
+            deserializer,
+            tokens: Rc::clone(&tokens),
+        })?;
+        Ok((value, Tokens(tokens.take())))
+    }
+}
+
+macro_rules! forward_recording_deserialize {
+    ($($method:ident),* $(,)?) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: de::Visitor<'de>,
+            {
+                self.deserializer
+                    .$method(TokenRecordingVisitor::new(visitor, self.tokens, Hint::None))
+            }
+        )*
+    };
+}
+
+impl<'de, D> de::Deserializer<'de> for RecordingDeserializer<D>
+where
+    D: de::Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    forward_recording_deserialize! {
+        deserialize_any,
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_bytes,
+        deserialize_byte_buf,
+        deserialize_option,
+        deserialize_unit,
+        deserialize_seq,
+        deserialize_map,
+        deserialize_identifier,
+        deserialize_ignored_any,
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserializer.deserialize_unit_struct(
+            name,
+            TokenRecordingVisitor::new(visitor, self.tokens, Hint::UnitStruct(name)),
+        )
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserializer.deserialize_newtype_struct(
+            name,
+            TokenRecordingVisitor::new(visitor, self.tokens, Hint::NewtypeStruct(name)),
+        )
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserializer.deserialize_tuple(
+            len,
+            TokenRecordingVisitor::new(visitor, self.tokens, Hint::Tuple),
+        )
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserializer.deserialize_tuple_struct(
+            name,
+            len,
+            TokenRecordingVisitor::new(visitor, self.tokens, Hint::TupleStruct(name)),
+        )
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserializer.deserialize_struct(
+            name,
+            fields,
+            TokenRecordingVisitor::new(visitor, self.tokens, Hint::Struct(name, fields)),
+        )
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserializer.deserialize_enum(
+            name,
+            variants,
+            TokenRecordingVisitor::new(visitor, self.tokens, Hint::Enum(name, variants)),
+        )
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.deserializer.is_human_readable()
+    }
+}
+
+/// The type hint given to a [`RecordingDeserializer`].
+///
+/// This provides the names and kinds of tokens that cannot be observed from the visited values
+/// alone.
+#[derive(Clone, Debug)]
+enum Hint {
+    None,
+    UnitStruct(&'static str),
+    NewtypeStruct(&'static str),
+    Tuple,
+    TupleStruct(&'static str),
+    Struct(&'static str, &'static [&'static str]),
+    Enum(&'static str, &'static [&'static str]),
+    TupleVariant(VariantId),
+    StructVariant(VariantId, &'static [&'static str]),
+}
+
+impl Hint {
+    /// Returns the start and end tokens of a sequence of the given length.
+    fn seq_tokens(self, len: usize) -> (CanonicalToken, CanonicalToken) {
+        match self {
+            Self::Tuple => (CanonicalToken::Tuple { len }, CanonicalToken::TupleEnd),
+            Self::TupleStruct(name) => (
+                CanonicalToken::TupleStruct { name, len },
+                CanonicalToken::TupleStructEnd,
+            ),
+            Self::TupleVariant(id) => (
+                CanonicalToken::TupleVariant {
+                    id: Box::new(id),
+                    len,
+                },
+                CanonicalToken::TupleVariantEnd,
+            ),
+            _ => (
+                CanonicalToken::Seq { len: Some(len) },
+                CanonicalToken::SeqEnd,
+            ),
+        }
+    }
+
+    /// Returns the start and end tokens of a map of the given length.
+    fn map_tokens(self, len: usize) -> (CanonicalToken, CanonicalToken) {
+        match self {
+            Self::Struct(name, _) => (
+                CanonicalToken::Struct { name, len },
+                CanonicalToken::StructEnd,
+            ),
+            Self::StructVariant(id, _) => (
+                CanonicalToken::StructVariant {
+                    id: Box::new(id),
+                    len,
+                },
+                CanonicalToken::StructVariantEnd,
+            ),
+            _ => (
+                CanonicalToken::Map { len: Some(len) },
+                CanonicalToken::MapEnd,
+            ),
+        }
+    }
+
+    /// Returns the field names whose keys should be recorded as [`Token::Field`]s.
+    fn fields(&self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::Struct(_, fields) | Self::StructVariant(_, fields) => Some(fields),
+            _ => None,
+        }
+    }
+}
+
+/// A visitor recording the values delivered to the wrapped visitor.
+struct TokenRecordingVisitor<V> {
+    visitor: V,
+    tokens: Rc<RefCell<Vec<CanonicalToken>>>,
+    hint: Hint,
+}
+
+impl<V> TokenRecordingVisitor<V> {
+    fn new(visitor: V, tokens: Rc<RefCell<Vec<CanonicalToken>>>, hint: Hint) -> Self {
+        Self {
+            visitor,
+            tokens,
+            hint,
+        }
+    }
+
+    fn record(self, token: CanonicalToken) -> V {
+        self.tokens.borrow_mut().push(token);
+        self.visitor
+    }
+}
+
+macro_rules! record_visit {
+    ($($method:ident($ty:ty) => $variant:ident),* $(,)?) => {
+        $(
+            fn $method<E>(self, v: $ty) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                self.record(CanonicalToken::$variant(v.to_owned())).$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, V> de::Visitor<'de> for TokenRecordingVisitor<V>
+where
+    V: de::Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    record_visit! {
+        visit_bool(bool) => Bool,
+        visit_i8(i8) => I8,
+        visit_i16(i16) => I16,
+        visit_i32(i32) => I32,
+        visit_i64(i64) => I64,
+        visit_i128(i128) => I128,
+        visit_u8(u8) => U8,
+        visit_u16(u16) => U16,
+        visit_u32(u32) => U32,
+        visit_u64(u64) => U64,
+        visit_u128(u128) => U128,
+        visit_f32(f32) => F32,
+        visit_f64(f64) => F64,
+        visit_char(char) => Char,
+        visit_str(&str) => Str,
+        visit_borrowed_str(&'de str) => Str,
+        visit_bytes(&[u8]) => Bytes,
+        visit_borrowed_bytes(&'de [u8]) => Bytes,
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(CanonicalToken::Str(v.clone())).visit_string(v)
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(CanonicalToken::Bytes(v.clone()))
+            .visit_byte_buf(v)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.record(CanonicalToken::None).visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let tokens = Rc::clone(&self.tokens);
+        self.record(CanonicalToken::Some)
+            .visit_some(RecordingDeserializer {
+                deserializer,
+                tokens,
+            })
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let token = if let Hint::UnitStruct(name) = self.hint {
+            CanonicalToken::UnitStruct { name }
+        } else {
+            CanonicalToken::Unit
+        };
+        self.record(token).visit_unit()
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let name = if let Hint::NewtypeStruct(name) = self.hint {
+            name
+        } else {
+            ""
+        };
+        let tokens = Rc::clone(&self.tokens);
+        self.record(CanonicalToken::NewtypeStruct { name })
+            .visit_newtype_struct(RecordingDeserializer {
+                deserializer,
+                tokens,
+            })
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        // The start token is only known once all elements have been visited.
+        let start = self.tokens.borrow().len();
+        self.tokens.borrow_mut().push(CanonicalToken::Unit);
+        let len = Cell::new(0);
+        let result = self.visitor.visit_seq(RecordingSeqAccess {
+            seq,
+            tokens: Rc::clone(&self.tokens),
+            len: &len,
+        });
+
+        let (start_token, end_token) = self.hint.seq_tokens(len.get());
+        let mut tokens = self.tokens.borrow_mut();
+        tokens[start] = start_token;
+        if result.is_ok() {
+            tokens.push(end_token);
+        }
+        result
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        // The start token is only known once all entries have been visited.
+        let start = self.tokens.borrow().len();
+        self.tokens.borrow_mut().push(CanonicalToken::Unit);
+        let len = Cell::new(0);
+        let result = self.visitor.visit_map(RecordingMapAccess {
+            map,
+            tokens: Rc::clone(&self.tokens),
+            len: &len,
+            fields: self.hint.fields(),
+        });
+
+        let (start_token, end_token) = self.hint.map_tokens(len.get());
+        let mut tokens = self.tokens.borrow_mut();
+        tokens[start] = start_token;
+        if result.is_ok() {
+            tokens.push(end_token);
+        }
+        result
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        let (name, variants) = if let Hint::Enum(name, variants) = self.hint {
+            (name, variants)
+        } else {
+            ("", &[][..])
+        };
+        self.visitor.visit_enum(RecordingEnumAccess {
+            data,
+            tokens: self.tokens,
+            name,
+            variants,
+        })
+    }
+}
+
+/// A seed deserializing through a [`RecordingDeserializer`].
+struct RecordingSeed<T> {
+    seed: T,
+    tokens: Rc<RefCell<Vec<CanonicalToken>>>,
+}
+
+impl<'de, T> DeserializeSeed<'de> for RecordingSeed<T>
+where
+    T: DeserializeSeed<'de>,
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.seed.deserialize(RecordingDeserializer {
+            deserializer,
+            tokens: self.tokens,
+        })
+    }
+}
+
+struct RecordingSeqAccess<'a, A> {
+    seq: A,
+    tokens: Rc<RefCell<Vec<CanonicalToken>>>,
+    len: &'a Cell<usize>,
+}
+
+impl<'de, A> de::SeqAccess<'de> for RecordingSeqAccess<'_, A>
+where
+    A: de::SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let element = self.seq.next_element_seed(RecordingSeed {
+            seed,
+            tokens: Rc::clone(&self.tokens),
+        })?;
+        if element.is_some() {
+            self.len.set(self.len.get() + 1);
+        }
+        Ok(element)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.seq.size_hint()
+    }
+}
+
+struct RecordingMapAccess<'a, A> {
+    map: A,
+    tokens: Rc<RefCell<Vec<CanonicalToken>>>,
+    len: &'a Cell<usize>,
+    fields: Option<&'static [&'static str]>,
+}
+
+impl<'de, A> de::MapAccess<'de> for RecordingMapAccess<'_, A>
+where
+    A: de::MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let start = self.tokens.borrow().len();
+        let key = self.map.next_key_seed(RecordingSeed {
+            seed,
+            tokens: Rc::clone(&self.tokens),
+        })?;
+        if key.is_some() {
+            self.len.set(self.len.get() + 1);
+            // Keys naming a known field are recorded as fields, matching the `Serializer`.
+            if let Some(fields) = self.fields {
+                let mut tokens = self.tokens.borrow_mut();
+                let field = match &tokens[start..] {
+                    [CanonicalToken::Str(key)] => fields.iter().find(|field| **field == key),
+                    _ => None,
+                };
+                if let Some(field) = field {
+                    tokens[start] = CanonicalToken::Field(field);
+                }
+            }
+        }
+        Ok(key)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.map.next_value_seed(RecordingSeed {
+            seed,
+            tokens: Rc::clone(&self.tokens),
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.map.size_hint()
+    }
+}
+
+struct RecordingEnumAccess<A> {
+    data: A,
+    tokens: Rc<RefCell<Vec<CanonicalToken>>>,
+    name: &'static str,
+    variants: &'static [&'static str],
+}
+
+impl<'de, A> de::EnumAccess<'de> for RecordingEnumAccess<A>
+where
+    A: de::EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = RecordingVariantAccess<A::Variant>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        // The variant identifier is recorded as part of the variant token rather than on its own.
+        let start = self.tokens.borrow().len();
+        let (value, variant) = self.data.variant_seed(RecordingSeed {
+            seed,
+            tokens: Rc::clone(&self.tokens),
+        })?;
+        let identifier = self.tokens.borrow_mut().split_off(start);
+        let variant_index = match identifier.as_slice() {
+            [CanonicalToken::Str(name)] => self.variants.iter().position(|variant| variant == name),
+            [CanonicalToken::U8(index)] => Some(usize::from(*index)),
+            [CanonicalToken::U16(index)] => Some(usize::from(*index)),
+            [CanonicalToken::U32(index)] => usize::try_from(*index).ok(),
+            [CanonicalToken::U64(index)] => usize::try_from(*index).ok(),
+            _ => None,
+        };
+
+        Ok((
+            value,
+            RecordingVariantAccess {
+                variant,
+                tokens: self.tokens,
+                id: VariantId {
+                    name: self.name,
+                    variant_index: variant_index
+                        .and_then(|index| u32::try_from(index).ok())
+                        .unwrap_or_default(),
+                    variant: variant_index
+                        .and_then(|index| self.variants.get(index))
+                        .copied()
+                        .unwrap_or_default(),
+                },
+            },
+        ))
+    }
+}
+
+struct RecordingVariantAccess<A> {
+    variant: A,
+    tokens: Rc<RefCell<Vec<CanonicalToken>>>,
+    id: VariantId,
+}
+
+impl<'de, A> de::VariantAccess<'de> for RecordingVariantAccess<A>
+where
+    A: de::VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        self.tokens.borrow_mut().push(CanonicalToken::UnitVariant {
+            id: Box::new(self.id),
+        });
+        self.variant.unit_variant()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.tokens
+            .borrow_mut()
+            .push(CanonicalToken::NewtypeVariant {
+                id: Box::new(self.id),
+            });
+        self.variant.newtype_variant_seed(RecordingSeed {
+            seed,
+            tokens: self.tokens,
+        })
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.variant.tuple_variant(
+            len,
+            TokenRecordingVisitor::new(visitor, self.tokens, Hint::TupleVariant(self.id)),
+        )
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.variant.struct_variant(
+            fields,
+            TokenRecordingVisitor::new(visitor, self.tokens, Hint::StructVariant(self.id, fields)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        drive_visitor,
+        from_tokens,
+        Coverage,
+        Delivery,
+        Deserialize128As,
+        Deserializer,
+        EnumDeserializer,
+        Error,
+        RecordingDeserializer,
+        UnexpectedValue,
+        Visit,
+    };
+    use crate::{
+        profile::Profile,
+        token::{
+            CanonicalToken,
+            EnumTagging,
+            Tokens,
+            VariantId,
+        },
+        Serializer,
+        Token,
+    };
+    use alloc::{
+        borrow::ToOwned,
+        boxed::Box,
+        fmt,
+        format,
+        string::String,
+        vec,
+        vec::Vec,
+    };
+    use claims::{
+        assert_err,
+        assert_err_eq,
+        assert_matches,
+        assert_none,
+        assert_ok,
+        assert_ok_eq,
+        assert_some,
+        assert_some_eq,
+    };
+    use core::{
+        iter,
+        marker::PhantomData,
+    };
+    use serde::{
+        de,
+        de::{
+            Deserialize,
+            DeserializeSeed,
+            Error as _,
+            IgnoredAny,
+            Unexpected,
+            VariantAccess,
+            Visitor,
+        },
+        Deserializer as _,
+        Serialize as _,
+    };
+    use serde_bytes::ByteBuf;
+    use serde_derive::Deserialize;
+    use std::collections::{
+        BTreeMap,
+        HashMap,
+    };
+
+    #[derive(Debug, PartialEq)]
+    enum Any {
+        Bool(bool),
+        I8(i8),
+        I16(i16),
+        I32(i32),
+        I64(i64),
+        I128(i128),
+        U8(u8),
+        U16(u16),
+        U32(u32),
+        U64(u64),
+        U128(u128),
+        F32(f32),
+        F64(f64),
+        Char(char),
+        Str(String),
+        Bytes(Vec<u8>),
+        Option(Option<u32>),
+        Unit,
+        UnitVariant,
+        NewtypeStruct(u32),
+        NewtypeVariant(u32),
+        Seq(u32, u32, u32),
+        TupleVariant(u32, u32, u32),
+        Map { foo: u32, bar: bool },
+        StructVariant { foo: u32, bar: bool },
+    }
+
+    impl<'de> Deserialize<'de> for Any {
+        #[allow(clippy::too_many_lines)] // Every visitor method must be implemented.
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct AnyVisitor;
+
+            impl<'de> Visitor<'de> for AnyVisitor {
+                type Value = Any;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("struct Any")
+                }
+
+                fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::Bool(v))
+                }
+
+                fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::I8(v))
+                }
+
+                fn visit_i16<E>(self, v: i16) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::I16(v))
+                }
+
+                fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::I32(v))
+                }
+
+                fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::I64(v))
+                }
+
+                fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::I128(v))
+                }
+
+                fn visit_u8<E>(self, v: u8) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::U8(v))
+                }
+
+                fn visit_u16<E>(self, v: u16) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::U16(v))
+                }
+
+                fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::U32(v))
+                }
+
+                fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::U64(v))
+                }
+
+                fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::U128(v))
+                }
+
+                fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::F32(v))
+                }
+
+                fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::F64(v))
+                }
+
+                fn visit_char<E>(self, v: char) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::Char(v))
+                }
+
+                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    Ok(Any::Str(v.to_owned()))
+                }
+
+                fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::Bytes(v.to_owned()))
+                }
+
+                fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    if let Any::U32(v) = deserializer.deserialize_any(self)? {
+                        Ok(Any::Option(Some(v)))
+                    } else {
+                        unreachable!()
+                    }
+                }
+
+                fn visit_none<E>(self) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::Option(None))
+                }
+
+                fn visit_unit<E>(self) -> Result<Self::Value, E>
+                where
+                    E: serde::de::Error,
+                {
+                    Ok(Any::Unit)
+                }
+
+                fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+                where
+                    A: serde::de::EnumAccess<'de>,
+                {
+                    enum Variant {
+                        Unit,
+                        Newtype,
+                        Tuple,
+                        Struct,
+                    }
+
+                    impl<'de> Deserialize<'de> for Variant {
+                        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                        where
+                            D: serde::Deserializer<'de>,
+                        {
+                            struct VariantVisitor;
+
+                            impl Visitor<'_> for VariantVisitor {
+                                type Value = Variant;
+
+                                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                                    formatter.write_str("enum Variant")
+                                }
+
+                                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                                where
+                                    E: de::Error,
+                                {
+                                    match v {
+                                        "unit" => Ok(Variant::Unit),
+                                        "newtype" => Ok(Variant::Newtype),
+                                        "tuple" => Ok(Variant::Tuple),
+                                        "struct" => Ok(Variant::Struct),
+                                        _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+                                    }
+                                }
+                            }
+
+                            deserializer.deserialize_any(VariantVisitor)
+                        }
+                    }
+
+                    let (variant, access) = data.variant()?;
+
+                    match variant {
+                        Variant::Unit => {
+                            access.unit_variant()?;
+                            Ok(Any::UnitVariant)
+                        }
+                        Variant::Newtype => {
+                            if let Any::U32(v) = access.newtype_variant()? {
+                                Ok(Any::NewtypeVariant(v))
+                            } else {
+                                unreachable!()
+                            }
+                        }
+                        Variant::Tuple => {
+                            if let Any::Seq(a, b, c) = access.tuple_variant(3, self)? {
+                                Ok(Any::TupleVariant(a, b, c))
+                            } else {
+                                unreachable!()
+                            }
+                        }
+                        Variant::Struct => {
+                            if let Any::Map { foo, bar } =
+                                access.struct_variant(&["foo", "bar"], self)?
+                            {
+                                Ok(Any::StructVariant { foo, bar })
+                            } else {
+                                unreachable!()
+                            }
+                        }
+                    }
+                }
+
+                fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    if let Any::U32(v) = deserializer.deserialize_any(self)? {
+                        Ok(Any::NewtypeStruct(v))
+                    } else {
+                        unreachable!()
+                    }
+                }
+
+                fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                where
+                    A: serde::de::SeqAccess<'de>,
+                {
+                    Ok(Any::Seq(
+                        seq.next_element()?
+                            .ok_or(A::Error::invalid_length(0, &self))?,
+                        seq.next_element()?
+                            .ok_or(A::Error::invalid_length(1, &self))?,
+                        seq.next_element()?
+                            .ok_or(A::Error::invalid_length(2, &self))?,
+                    ))
+                }
+
+                fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                where
+                    A: serde::de::MapAccess<'de>,
+                {
+                    enum Field {
+                        Foo,
+                        Bar,
+                    }
+
+                    impl<'de> Deserialize<'de> for Field {
+                        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                        where
+                            D: serde::Deserializer<'de>,
+                        {
+                            struct FieldVisitor;
+
+                            impl Visitor<'_> for FieldVisitor {
+                                type Value = Field;
+
+                                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                                    formatter.write_str("`foo` or `bar`")
+                                }
+
+                                fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+                                where
+                                    E: de::Error,
+                                {
+                                    match value {
+                                        "foo" => Ok(Field::Foo),
+                                        "bar" => Ok(Field::Bar),
+                                        _ => Err(E::unknown_field(value, &["foo", "bar"])),
+                                    }
+                                }
+                            }
+
+                            deserializer.deserialize_identifier(FieldVisitor)
+                        }
+                    }
+
+                    let mut foo = None;
+                    let mut bar = None;
+
+                    while let Some(key) = map.next_key()? {
+                        match key {
+                            Field::Foo => {
+                                if foo.is_some() {
+                                    return Err(A::Error::duplicate_field("foo"));
+                                }
+                                foo = Some(map.next_value()?);
+                            }
+                            Field::Bar => {
+                                if bar.is_some() {
+                                    return Err(A::Error::duplicate_field("bar"));
+                                }
+                                bar = Some(map.next_value()?);
+                            }
+                        }
+                    }
+
+                    if foo.is_none() {
+                        return Err(A::Error::missing_field("foo"));
+                    }
+                    if bar.is_none() {
+                        return Err(A::Error::missing_field("bar"));
+                    }
+
+                    Ok(Any::Map {
+                        foo: foo.unwrap(),
+                        bar: bar.unwrap(),
+                    })
+                }
+            }
+
+            deserializer.deserialize_any(AnyVisitor)
+        }
+    }
+
+    #[test]
+    fn deserialize_any_bool() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Bool(true));
+    }
+
+    #[test]
+    fn deserialize_any_i8() {
+        let mut deserializer = Deserializer::builder([Token::I8(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I8(42));
+    }
+
+    #[test]
+    fn deserialize_any_i16() {
+        let mut deserializer = Deserializer::builder([Token::I16(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I16(42));
+    }
+
+    #[test]
+    fn deserialize_any_i32() {
+        let mut deserializer = Deserializer::builder([Token::I32(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I32(42));
+    }
+
+    #[test]
+    fn deserialize_any_i64() {
+        let mut deserializer = Deserializer::builder([Token::I64(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I64(42));
+    }
+
+    #[test]
+    fn deserialize_any_i128() {
+        let mut deserializer = Deserializer::builder([Token::I128(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I128(42));
+    }
+
+    #[test]
+    fn deserialize_any_u8() {
+        let mut deserializer = Deserializer::builder([Token::U8(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U8(42));
+    }
+
+    #[test]
+    fn deserialize_any_u16() {
+        let mut deserializer = Deserializer::builder([Token::U16(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U16(42));
+    }
+
+    #[test]
+    fn deserialize_any_u32() {
+        let mut deserializer = Deserializer::builder([Token::U32(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U32(42));
+    }
+
+    #[test]
+    fn deserialize_any_u64() {
+        let mut deserializer = Deserializer::builder([Token::U64(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U64(42));
+    }
+
+    #[test]
+    fn deserialize_any_u128() {
+        let mut deserializer = Deserializer::builder([Token::U128(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U128(42));
+    }
+
+    #[test]
+    fn deserialize_any_f32() {
+        let mut deserializer = Deserializer::builder([Token::F32(42.)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::F32(42.));
+    }
+
+    #[test]
+    fn deserialize_any_f64() {
+        let mut deserializer = Deserializer::builder([Token::F64(42.)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::F64(42.));
+    }
+
+    #[test]
+    fn deserialize_any_char() {
+        let mut deserializer = Deserializer::builder([Token::Char('a')])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Char('a'));
+    }
+
+    #[test]
+    fn deserialize_any_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(
+            Any::deserialize(&mut deserializer),
+            Any::Str("foo".to_owned())
+        );
+    }
+
+    #[test]
+    fn deserialize_any_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(
+            Any::deserialize(&mut deserializer),
+            Any::Bytes(b"foo".to_vec())
+        );
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn deserialize_any_str_visits_borrowed_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .self_describing(true)
+            .build();
+
+        assert_ok!(de::IgnoredAny::deserialize(&mut deserializer));
+        assert_eq!(deserializer.visits(), [Visit::BorrowedStr]);
+    }
+
+    #[test]
+    fn deserialize_any_str_no_zero_copy_visits_string() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .self_describing(true)
+            .zero_copy(false)
+            .build();
+
+        assert_ok!(de::IgnoredAny::deserialize(&mut deserializer));
+        assert_eq!(deserializer.visits(), [Visit::String]);
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn deserialize_any_bytes_visits_borrowed_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .self_describing(true)
+            .build();
+
+        assert_ok!(de::IgnoredAny::deserialize(&mut deserializer));
+        assert_eq!(deserializer.visits(), [Visit::BorrowedBytes]);
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(untagged)]
+    enum Untagged<'a> {
+        Unit,
+        Bool(bool),
+        Borrowed(&'a str),
+        Owned(String),
+        Tuple(u8, u8),
+        Struct { foo: u32 },
+    }
+
+    #[test]
+    fn deserialize_untagged_unit() {
+        let mut deserializer = Deserializer::builder([Token::Unit])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Untagged::deserialize(&mut deserializer), Untagged::Unit);
+    }
+
+    #[test]
+    fn deserialize_untagged_bool() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(
+            Untagged::deserialize(&mut deserializer),
+            Untagged::Bool(true)
+        );
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn deserialize_untagged_borrowed() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(
+            Untagged::deserialize(&mut deserializer),
+            Untagged::Borrowed("foo")
+        );
+    }
+
+    #[test]
+    fn deserialize_untagged_no_zero_copy_falls_through_to_owned() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .self_describing(true)
+            .zero_copy(false)
+            .build();
+
+        assert_ok_eq!(
+            Untagged::deserialize(&mut deserializer),
+            Untagged::Owned("foo".to_owned())
+        );
+    }
+
+    #[test]
+    fn deserialize_untagged_tuple() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 2 },
+            Token::U8(1),
+            Token::U8(2),
+            Token::TupleEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(
+            Untagged::deserialize(&mut deserializer),
+            Untagged::Tuple(1, 2)
+        );
+    }
+
+    #[test]
+    fn deserialize_untagged_struct() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Untagged",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::StructEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(
+            Untagged::deserialize(&mut deserializer),
+            Untagged::Struct { foo: 42 }
+        );
+    }
+
+    #[test]
+    fn deserialize_untagged_no_match() {
+        let mut deserializer = Deserializer::builder([Token::F32(1.0)])
+            .self_describing(true)
+            .build();
+
+        assert_err!(Untagged::deserialize(&mut deserializer));
+    }
+
+    #[test]
+    fn deserialize_untagged_not_self_describing() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            Untagged::deserialize(&mut deserializer),
+            Error::NotSelfDescribing
+        );
+    }
+
+    #[test]
+    fn deserialize_any_some() {
+        let mut deserializer = Deserializer::builder([Token::Some, Token::U32(42)])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Option(Some(42)),);
+    }
+
+    #[test]
+    fn deserialize_any_none() {
+        let mut deserializer = Deserializer::builder([Token::None])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Option(None),);
+    }
+
+    #[test]
+    fn deserialize_any_unit() {
+        let mut deserializer = Deserializer::builder([Token::Unit])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Unit,);
+    }
+
+    #[test]
+    fn deserialize_any_unit_struct() {
+        let mut deserializer = Deserializer::builder([Token::UnitStruct { name: "foo" }])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Unit,);
+    }
+
+    #[test]
+    fn deserialize_any_unit_variant() {
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "foo",
+            variant_index: 0,
+            variant: "unit",
+        }])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::UnitVariant,);
+    }
+
+    #[test]
+    fn deserialize_any_newtype_struct() {
+        let mut deserializer =
+            Deserializer::builder([Token::NewtypeStruct { name: "foo" }, Token::U32(42)])
+                .self_describing(true)
+                .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::NewtypeStruct(42),);
+    }
+
+    #[test]
+    fn deserialize_any_newtype_variant() {
+        let mut deserializer = Deserializer::builder([
+            Token::NewtypeVariant {
+                name: "foo",
+                variant_index: 0,
+                variant: "newtype",
+            },
+            Token::U32(42),
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::NewtypeVariant(42),);
+    }
+
+    #[test]
+    fn deserialize_any_seq() {
+        let mut deserializer = Deserializer::builder([
+            Token::Seq { len: None },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::SeqEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Seq(1, 2, 3),);
+    }
+
+    #[test]
+    fn deserialize_any_tuple() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 3 },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Seq(1, 2, 3),);
+    }
+
+    #[test]
+    fn deserialize_any_tuple_struct() {
+        let mut deserializer = Deserializer::builder([
+            Token::TupleStruct {
+                name: "foo",
+                len: 3,
+            },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleStructEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Seq(1, 2, 3),);
+    }
+
+    #[test]
+    fn deserialize_any_tuple_variant() {
+        let mut deserializer = Deserializer::builder([
+            Token::TupleVariant {
+                name: "foo",
+                variant_index: 0,
+                variant: "tuple",
+                len: 3,
+            },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleVariantEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(
+            Any::deserialize(&mut deserializer),
+            Any::TupleVariant(1, 2, 3),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_map() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(3) },
+            Token::Str("foo".to_owned()),
+            Token::U32(42),
+            Token::Str("bar".to_owned()),
+            Token::Bool(false),
+            Token::MapEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(
+            Any::deserialize(&mut deserializer),
+            Any::Map {
+                foo: 42,
+                bar: false
+            },
+        );
+    }
+
+    #[test]
+    fn deserialize_any_field() {
+        let mut deserializer = Deserializer::builder([Token::Field("foo")])
+            .self_describing(true)
+            .build();
+
+        assert_ok_eq!(
+            Any::deserialize(&mut deserializer),
+            Any::Str("foo".to_owned()),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_struct() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "foo",
+                len: 3,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::Field("bar"),
+            Token::Bool(false),
+            Token::StructEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(
+            Any::deserialize(&mut deserializer),
+            Any::Map {
+                foo: 42,
+                bar: false
+            },
+        );
+    }
+
+    #[test]
+    fn deserialize_any_struct_variant() {
+        let mut deserializer = Deserializer::builder([
+            Token::StructVariant {
+                name: "foo",
+                variant_index: 0,
+                variant: "struct",
+                len: 3,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::Field("bar"),
+            Token::Bool(false),
+            Token::StructVariantEnd,
+        ])
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(
+            Any::deserialize(&mut deserializer),
+            Any::StructVariant {
+                foo: 42,
+                bar: false
+            },
+        );
+    }
+
+    #[test]
+    fn deserialize_any_seq_end_fails() {
+        let mut deserializer = Deserializer::builder([Token::SeqEnd])
+            .self_describing(true)
+            .build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::SeqEnd).into(), &"struct Any"),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_tuple_end_fails() {
+        let mut deserializer = Deserializer::builder([Token::TupleEnd])
+            .self_describing(true)
+            .build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::TupleEnd).into(), &"struct Any"),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_tuple_struct_end_fails() {
+        let mut deserializer = Deserializer::builder([Token::TupleStructEnd])
+            .self_describing(true)
+            .build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::TupleStructEnd).into(), &"struct Any"),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_tuple_variant_end_fails() {
+        let mut deserializer = Deserializer::builder([Token::TupleVariantEnd])
+            .self_describing(true)
+            .build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::TupleVariantEnd).into(), &"struct Any"),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_map_end_fails() {
+        let mut deserializer = Deserializer::builder([Token::MapEnd])
+            .self_describing(true)
+            .build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::MapEnd).into(), &"struct Any"),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_struct_end_fails() {
+        let mut deserializer = Deserializer::builder([Token::StructEnd])
+            .self_describing(true)
+            .build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::StructEnd).into(), &"struct Any"),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_struct_variant_end_fails() {
+        let mut deserializer = Deserializer::builder([Token::StructVariantEnd])
+            .self_describing(true)
+            .build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::invalid_type(
+                (&mut CanonicalToken::StructVariantEnd).into(),
+                &"struct Any"
+            ),
+        );
+    }
+
+    #[test]
+    fn deserialize_any_default_not_self_describing() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::NotSelfDescribing
+        );
+    }
+
+    #[test]
+    fn deserialize_any_not_self_describing() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .self_describing(false)
+            .build();
+
+        assert_err_eq!(
+            Any::deserialize(&mut deserializer),
+            Error::NotSelfDescribing
+        );
+    }
+
+    #[test]
+    fn deserialize_bool() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    }
+
+    #[test]
+    fn deserialize_bool_error() {
+        let mut deserializer = Deserializer::builder([Token::I8(42)]).build();
+
+        assert_err_eq!(
+            bool::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::I8(42)).into(), &"a boolean")
+        );
+    }
+
+    #[test]
+    fn deserialize_bool_from_int() {
+        let mut deserializer = Deserializer::builder([
+            Token::U8(0),
+            Token::I32(1),
+            Token::U128(1),
+            Token::I64(0),
+            Token::Bool(true),
+        ])
+        .deserialize_bool_from_int(true)
+        .build();
+
+        assert_ok_eq!(bool::deserialize(&mut deserializer), false);
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_ok_eq!(bool::deserialize(&mut deserializer), false);
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    }
+
+    #[test]
+    fn deserialize_bool_from_int_invalid_value() {
+        let mut deserializer = Deserializer::builder([Token::U8(2)])
+            .deserialize_bool_from_int(true)
+            .build();
+
+        assert_err_eq!(
+            bool::deserialize(&mut deserializer),
+            Error::invalid_value((&mut CanonicalToken::U8(2)).into(), &"a boolean")
+        );
+    }
+
+    #[test]
+    fn deserialize_bool_from_int_disabled() {
+        let mut deserializer = Deserializer::builder([Token::U8(1)]).build();
+
+        assert_err_eq!(
+            bool::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::U8(1)).into(), &"a boolean")
+        );
+    }
+
+    #[test]
+    fn deserialize_i8() {
+        let mut deserializer = Deserializer::builder([Token::I8(42)]).build();
+
+        assert_ok_eq!(i8::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_i8_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            i8::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i8")
+        );
+    }
+
+    #[test]
+    fn deserialize_i16() {
+        let mut deserializer = Deserializer::builder([Token::I16(42)]).build();
+
+        assert_ok_eq!(i16::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_i16_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            i16::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i16")
+        );
+    }
+
+    #[test]
+    fn deserialize_i32() {
+        let mut deserializer = Deserializer::builder([Token::I32(42)]).build();
+
+        assert_ok_eq!(i32::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_i32_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            i32::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i32")
+        );
+    }
+
+    #[test]
+    fn deserialize_i64() {
+        let mut deserializer = Deserializer::builder([Token::I64(42)]).build();
+
+        assert_ok_eq!(i64::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_i64_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            i64::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i64")
+        );
+    }
+
+    #[test]
+    fn deserialize_i128() {
+        let mut deserializer = Deserializer::builder([Token::I128(42)]).build();
+
+        assert_ok_eq!(i128::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_i128_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            i128::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i128")
+        );
+    }
+
+    #[test]
+    fn deserialize_u8() {
+        let mut deserializer = Deserializer::builder([Token::U8(42)]).build();
+
+        assert_ok_eq!(u8::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_u8_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            u8::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u8")
+        );
+    }
+
+    #[test]
+    fn deserialize_u16() {
+        let mut deserializer = Deserializer::builder([Token::U16(42)]).build();
+
+        assert_ok_eq!(u16::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_u16_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            u16::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u16")
+        );
+    }
+
+    #[test]
+    fn deserialize_u32() {
+        let mut deserializer = Deserializer::builder([Token::U32(42)]).build();
+
+        assert_ok_eq!(u32::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_u32_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            u32::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u32")
+        );
+    }
+
+    #[test]
+    fn deserialize_u64() {
+        let mut deserializer = Deserializer::builder([Token::U64(42)]).build();
+
+        assert_ok_eq!(u64::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_u64_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            u64::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u64")
+        );
+    }
+
+    #[test]
+    fn deserialize_u128() {
+        let mut deserializer = Deserializer::builder([Token::U128(42)]).build();
+
+        assert_ok_eq!(u128::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_u128_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            u128::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u128")
+        );
+    }
+
+    #[test]
+    fn deserialize_i128_as_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("-42".to_owned())])
+            .deserialize_128_as(Deserialize128As::Str)
+            .build();
+
+        assert_ok_eq!(i128::deserialize(&mut deserializer), -42);
+    }
+
+    #[test]
+    fn deserialize_i128_as_str_invalid() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .deserialize_128_as(Deserialize128As::Str)
+            .build();
+
+        assert_err_eq!(
+            i128::deserialize(&mut deserializer),
+            Error::invalid_value(Unexpected::Str("foo"), &"i128")
+        );
+    }
+
+    #[test]
+    fn deserialize_i128_as_str_rejects_integer() {
+        let mut deserializer = Deserializer::builder([Token::I128(-42)])
+            .deserialize_128_as(Deserialize128As::Str)
+            .build();
+
+        assert_err_eq!(
+            i128::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::I128(-42)).into(), &"i128")
+        );
+    }
+
+    #[test]
+    fn deserialize_i128_as_bytes() {
+        let mut deserializer =
+            Deserializer::builder([Token::Bytes((-42i128).to_be_bytes().to_vec())])
+                .deserialize_128_as(Deserialize128As::Bytes)
+                .build();
+
+        assert_ok_eq!(i128::deserialize(&mut deserializer), -42);
+    }
+
+    #[test]
+    fn deserialize_u128_as_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("42".to_owned())])
+            .deserialize_128_as(Deserialize128As::Str)
+            .build();
+
+        assert_ok_eq!(u128::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_u128_as_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(42u128.to_be_bytes().to_vec())])
+            .deserialize_128_as(Deserialize128As::Bytes)
+            .build();
+
+        assert_ok_eq!(u128::deserialize(&mut deserializer), 42);
+    }
+
+    #[test]
+    fn deserialize_u128_as_bytes_wrong_length() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .deserialize_128_as(Deserialize128As::Bytes)
+            .build();
+
+        assert_err_eq!(
+            u128::deserialize(&mut deserializer),
+            Error::invalid_value(Unexpected::Bytes(b"foo"), &"u128")
+        );
+    }
+
+    #[test]
+    fn deserialize_i128_unsupported() {
+        let mut deserializer = Deserializer::builder([Token::I128(42)])
+            .support_128_bit(false)
+            .build();
+
+        assert_err_eq!(
+            i128::deserialize(&mut deserializer),
+            Error::Custom("i128 is not supported".to_owned())
+        );
+        assert!(deserializer.consumptions().is_empty());
+    }
+
+    #[test]
+    fn deserialize_u128_unsupported() {
+        let mut deserializer = Deserializer::builder([Token::U128(42)])
+            .support_128_bit(false)
+            .build();
+
+        assert_err_eq!(
+            u128::deserialize(&mut deserializer),
+            Error::Custom("u128 is not supported".to_owned())
+        );
+        assert!(deserializer.consumptions().is_empty());
+    }
+
+    #[test]
+    fn deserialize_128_as_str_unsupported() {
+        let mut deserializer = Deserializer::builder([Token::Str("42".to_owned())])
+            .deserialize_128_as(Deserialize128As::Str)
+            .support_128_bit(false)
+            .build();
+
+        assert_err_eq!(
+            u128::deserialize(&mut deserializer),
+            Error::Custom("u128 is not supported".to_owned())
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)] // The value is exactly representable.
+    fn deserialize_f32() {
+        let mut deserializer = Deserializer::builder([Token::F32(42.)]).build();
+
+        assert_ok_eq!(f32::deserialize(&mut deserializer), 42.);
+    }
+
+    #[test]
+    fn deserialize_f32_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            f32::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"f32")
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)] // The value is exactly representable.
+    fn deserialize_f64() {
+        let mut deserializer = Deserializer::builder([Token::F64(42.)]).build();
+
+        assert_ok_eq!(f64::deserialize(&mut deserializer), 42.);
+    }
+
+    #[test]
+    fn deserialize_f64_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            f64::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"f64")
+        );
+    }
+
+    #[test]
+    fn deserialize_char() {
+        let mut deserializer = Deserializer::builder([Token::Char('a')]).build();
+
+        assert_ok_eq!(char::deserialize(&mut deserializer), 'a');
+    }
+
+    #[test]
+    fn deserialize_char_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            char::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"a character")
+        );
+    }
+
+    #[test]
+    fn char_as_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("a".to_owned())])
+            .char_as_str(true)
+            .build();
+
+        assert_ok_eq!(char::deserialize(&mut deserializer), 'a');
+    }
+
+    #[test]
+    fn char_as_str_still_accepts_char() {
+        let mut deserializer = Deserializer::builder([Token::Char('a')])
+            .char_as_str(true)
+            .build();
+
+        assert_ok_eq!(char::deserialize(&mut deserializer), 'a');
+    }
+
+    #[test]
+    fn char_as_str_multiple_chars() {
+        let mut deserializer = Deserializer::builder([Token::Str("ab".to_owned())])
+            .char_as_str(true)
+            .build();
+
+        assert_err_eq!(
+            char::deserialize(&mut deserializer),
+            Error::invalid_value(Unexpected::Str("ab"), &"a character")
+        );
+    }
+
+    #[test]
+    fn char_as_str_empty() {
+        let mut deserializer = Deserializer::builder([Token::Str(String::new())])
+            .char_as_str(true)
+            .build();
+
+        assert_err_eq!(
+            char::deserialize(&mut deserializer),
+            Error::invalid_value(Unexpected::Str(""), &"a character")
+        );
+    }
+
+    #[test]
+    fn char_as_str_roundtrip() {
+        let serializer = Serializer::builder().char_as_str(true).build();
+        let tokens = assert_ok!('a'.serialize(&serializer));
+        let mut deserializer = Deserializer::builder(tokens).char_as_str(true).build();
+
+        assert_ok_eq!(char::deserialize(&mut deserializer), 'a');
+    }
+
+    #[test]
+    fn char_as_str_disabled() {
+        let mut deserializer = Deserializer::builder([Token::Str("a".to_owned())]).build();
+
+        assert_err_eq!(
+            char::deserialize(&mut deserializer),
+            Error::invalid_type(
+                (&mut CanonicalToken::Str("a".to_owned())).into(),
+                &"a character"
+            )
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Str(String);
+
+    impl<'de> Deserialize<'de> for Str {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct StrVisitor;
+
+            impl Visitor<'_> for StrVisitor {
+                type Value = Str;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("str")
+                }
+
+                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    Ok(Str(v.to_owned()))
+                }
+            }
+
+            deserializer.deserialize_str(StrVisitor)
+        }
+    }
+
+    #[test]
+    fn deserialize_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+
+        assert_ok_eq!(Str::deserialize(&mut deserializer), Str("foo".to_owned()));
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn deserialize_str_static() {
+        let mut deserializer = Deserializer::builder([Token::StaticStr("foo")]).build();
+
+        assert_ok_eq!(<&str>::deserialize(&mut deserializer), "foo");
+    }
+
+    #[test]
+    fn deserialize_str_zero_copy_disabled() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .zero_copy(false)
+            .build();
+
+        assert_ok_eq!(Str::deserialize(&mut deserializer), Str("foo".to_owned()));
+    }
+
+    #[test]
+    fn deserialize_str_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            Str::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"str")
+        );
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct BorrowedStr<'a>(&'a str);
+
+    impl<'de> Deserialize<'de> for BorrowedStr<'de> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct BorrowedStrVisitor;
+
+            impl<'de> Visitor<'de> for BorrowedStrVisitor {
+                type Value = BorrowedStr<'de>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("a borrowed str")
+                }
+
+                fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    Ok(BorrowedStr(v))
+                }
+            }
+
+            deserializer.deserialize_str(BorrowedStrVisitor)
+        }
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn deserialize_borrowed_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+
+        assert_ok_eq!(
+            BorrowedStr::deserialize(&mut deserializer),
+            BorrowedStr("foo")
+        );
+    }
+
+    #[test]
+    fn deserialize_borrowed_str_zero_copy_disabled_error() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .zero_copy(false)
+            .build();
 
-    /// The [`Deserializer`] was set to be non-self-describing, but the [`Deserialize`]
-    /// implementation made a call to [`deserialize_any()`].
-    ///
-    /// [`Deserialize`]: serde::Deserialize
-    /// [`deserialize_any()`]: ../struct.Deserializer.html#method.deserialize_any
-    NotSelfDescribing,
+        assert_err_eq!(
+            BorrowedStr::deserialize(&mut deserializer),
+            Error::invalid_type(
+                (&mut CanonicalToken::Str("foo".to_owned())).into(),
+                &"a borrowed str"
+            )
+        );
+    }
 
-    /// An error created by calling [`custom()`].
-    ///
-    /// [`custom()`]: Error::custom()
-    Custom(String),
-    /// An error created by calling [`invalid_type()`].
-    ///
-    /// [`invalid_type()`]: Error::invalid_type()
-    InvalidType(String, String),
-    /// An error created by calling [`invalid_value()`].
-    ///
-    /// [`invalid_value()`]: Error::invalid_value()
-    InvalidValue(String, String),
-    /// An error created by calling [`invalid_length()`].
-    ///
-    /// [`invalid_length()`]: Error::invalid_length()
-    InvalidLength(usize, String),
-    /// An error created by calling [`unknown_variant()`].
-    ///
-    /// [`unknown_variant()`]: Error::unknown_variant()
-    UnknownVariant(String, &'static [&'static str]),
-    /// An error created by calling [`unknown_field()`].
-    ///
-    /// [`unknown_field()`]: Error::unknown_field()
-    UnknownField(String, &'static [&'static str]),
-    /// An error created by calling [`missing_field()`].
-    ///
-    /// [`missing_field()`]: Error::missing_field()
-    MissingField(&'static str),
-    /// An error created by calling [`duplicate_field()`].
-    ///
-    /// [`duplicate_field()`]: Error::duplicate_field()
-    DuplicateField(&'static str),
-}
+    #[cfg(feature = "safe")]
+    #[test]
+    fn deserialize_borrowed_str_safe_error() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+
+        assert_err_eq!(
+            BorrowedStr::deserialize(&mut deserializer),
+            Error::invalid_type(
+                (&mut CanonicalToken::Str("foo".to_owned())).into(),
+                &"a borrowed str"
+            )
+        );
+    }
+
+    #[test]
+    fn deserialize_string() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+
+        assert_ok_eq!(String::deserialize(&mut deserializer), "foo".to_owned());
+    }
+
+    #[test]
+    fn deserialize_string_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            String::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"a string")
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Bytes(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for Bytes {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct BytesVisitor;
+
+            impl Visitor<'_> for BytesVisitor {
+                type Value = Bytes;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("bytes")
+                }
+
+                fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    Ok(Bytes(v.to_vec()))
+                }
+            }
+
+            deserializer.deserialize_bytes(BytesVisitor)
+        }
+    }
+
+    #[test]
+    fn deserialize_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
+
+        assert_ok_eq!(
+            Bytes::deserialize(&mut deserializer),
+            Bytes(b"foo".to_vec())
+        );
+    }
+
+    #[test]
+    fn deserialize_bytes_static() {
+        let mut deserializer = Deserializer::builder([Token::StaticBytes(b"foo")]).build();
+
+        assert_ok_eq!(
+            Bytes::deserialize(&mut deserializer),
+            Bytes(b"foo".to_vec())
+        );
+    }
+
+    #[test]
+    fn deserialize_bytes_zero_copy_disabled() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .zero_copy(false)
+            .build();
+
+        assert_ok_eq!(
+            Bytes::deserialize(&mut deserializer),
+            Bytes(b"foo".to_vec())
+        );
+    }
+
+    #[test]
+    fn deserialize_bytes_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            Bytes::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"bytes")
+        );
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct BorrowedBytes<'a>(&'a [u8]);
+
+    impl<'de> Deserialize<'de> for BorrowedBytes<'de> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct BorrowedBytesVisitor;
+
+            impl<'de> Visitor<'de> for BorrowedBytesVisitor {
+                type Value = BorrowedBytes<'de>;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("borrowed bytes")
+                }
+
+                fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    Ok(BorrowedBytes(v))
+                }
+            }
+
+            deserializer.deserialize_bytes(BorrowedBytesVisitor)
+        }
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn deserialize_borrowed_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
+
+        assert_ok_eq!(
+            BorrowedBytes::deserialize(&mut deserializer),
+            BorrowedBytes(b"foo")
+        );
+    }
+
+    #[test]
+    fn deserialize_borrowed_bytes_zero_copy_disabled_error() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .zero_copy(false)
+            .build();
+
+        assert_err_eq!(
+            BorrowedBytes::deserialize(&mut deserializer),
+            Error::invalid_type(
+                (&mut CanonicalToken::Bytes(b"foo".to_vec())).into(),
+                &"borrowed bytes"
+            )
+        );
+    }
+
+    #[cfg(feature = "safe")]
+    #[test]
+    fn deserialize_borrowed_bytes_safe_error() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
+
+        assert_err_eq!(
+            BorrowedBytes::deserialize(&mut deserializer),
+            Error::invalid_type(
+                (&mut CanonicalToken::Bytes(b"foo".to_vec())).into(),
+                &"borrowed bytes"
+            )
+        );
+    }
+
+    #[test]
+    fn deserialize_byte_buf() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
+
+        assert_ok_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            ByteBuf::from(b"foo".to_vec())
+        );
+    }
+
+    #[test]
+    fn deserialize_byte_buf_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"byte array")
+        );
+    }
+
+    #[test]
+    fn deserialize_byte_buf_as_seq() {
+        let mut deserializer = Deserializer::builder([
+            Token::Seq { len: Some(3) },
+            Token::U8(b'f'),
+            Token::U8(b'o'),
+            Token::U8(b'o'),
+            Token::SeqEnd,
+        ])
+        .deserialize_bytes_as_seq(true)
+        .build();
+
+        assert_ok_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            ByteBuf::from(b"foo".to_vec())
+        );
+        assert_eq!(
+            deserializer.visits(),
+            [Visit::Seq, Visit::U8, Visit::U8, Visit::U8]
+        );
+    }
+
+    #[test]
+    fn deserialize_byte_buf_as_seq_disabled_error() {
+        let mut deserializer =
+            Deserializer::builder([Token::Seq { len: Some(0) }, Token::SeqEnd]).build();
 
-impl Error {
-    fn expected_end_token(end_token: EndToken) -> Self {
-        match end_token {
-            EndToken::Seq => Self::ExpectedSeqEnd,
-            EndToken::Tuple => Self::ExpectedTupleEnd,
-            EndToken::TupleStruct => Self::ExpectedTupleStructEnd,
-            EndToken::TupleVariant => Self::ExpectedTupleVariantEnd,
-            EndToken::Map => Self::ExpectedMapEnd,
-            EndToken::Struct => Self::ExpectedStructEnd,
-            EndToken::StructVariant => Self::ExpectedStructVariantEnd,
-        }
+        assert_err_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            Error::invalid_type(
+                (&mut CanonicalToken::Seq { len: Some(0) }).into(),
+                &"byte array"
+            )
+        );
     }
-}
 
-impl Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::EndOfTokens => f.write_str("end of tokens"),
-            Self::ExpectedSeqEnd => f.write_str("expected token SeqEnd"),
-            Self::ExpectedTupleEnd => f.write_str("expected token TupleEnd"),
-            Self::ExpectedTupleStructEnd => f.write_str("expected token TupleStructEnd"),
-            Self::ExpectedTupleVariantEnd => f.write_str("expected token TupleVariantEnd"),
-            Self::ExpectedMapEnd => f.write_str("expected token MapEnd"),
-            Self::ExpectedStructEnd => f.write_str("expected token StructEnd"),
-            Self::ExpectedStructVariantEnd => f.write_str("expected token StructVariantEnd"),
-            Self::UnsupportedEnumDeserializerMethod => f.write_str("use of unsupported enum deserializer method"),
-            Self::NotSelfDescribing => f.write_str("attempted to deserialize as self-describing when deserializer is not set as self-describing"),
-            Self::Custom(s) => f.write_str(s),
-            Self::InvalidType(unexpected, expected) => write!(f, "invalid type: expected {expected}, found {unexpected}"),
-            Self::InvalidValue(unexpected, expected) => write!(f, "invalid value: expected {expected}, found {unexpected}"),
-            Self::InvalidLength(length, expected) => write!(f, "invalid length {length}, expected {expected}"),
-            Self::UnknownVariant(variant, expected) => write!(f, "unknown variant {variant}, expected one of {expected:?}"),
-            Self::UnknownField(field, expected) => write!(f, "unknown field {field}, expected one of {expected:?}"),
-            Self::MissingField(field) => write!(f, "missing field {field}"),
-            Self::DuplicateField(field) => write!(f, "duplicate field {field}"),
-        }
+    #[test]
+    fn deserialize_bytes_as_seq_visits_seq() {
+        let mut deserializer =
+            Deserializer::builder([Token::Seq { len: Some(1) }, Token::U8(b'f'), Token::SeqEnd])
+                .deserialize_bytes_as_seq(true)
+                .build();
+
+        // `Bytes` does not implement `visit_seq()`.
+        assert_err!(Bytes::deserialize(&mut deserializer));
+        assert_eq!(deserializer.visits(), [Visit::Seq]);
     }
-}
 
-impl de::StdError for Error {}
+    #[test]
+    fn deserialize_bytes_as_seq_still_accepts_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .deserialize_bytes_as_seq(true)
+            .build();
 
-impl de::Error for Error {
-    fn custom<T>(msg: T) -> Self
-    where
-        T: fmt::Display,
-    {
-        Self::Custom(msg.to_string())
+        assert_ok_eq!(
+            Bytes::deserialize(&mut deserializer),
+            Bytes(b"foo".to_vec())
+        );
     }
 
-    fn invalid_type(unexpected: Unexpected, expected: &dyn Expected) -> Self {
-        Self::InvalidType(unexpected.to_string(), expected.to_string())
+    #[test]
+    fn deserialize_option_some() {
+        let mut deserializer = Deserializer::builder([Token::Some, Token::U32(42)]).build();
+
+        assert_ok_eq!(Option::<u32>::deserialize(&mut deserializer), Some(42));
     }
 
-    fn invalid_value(unexpected: Unexpected, expected: &dyn Expected) -> Self {
-        Self::InvalidValue(unexpected.to_string(), expected.to_string())
+    #[test]
+    fn deserialize_option_none() {
+        let mut deserializer = Deserializer::builder([Token::None]).build();
+
+        assert_ok_eq!(Option::<u32>::deserialize(&mut deserializer), None);
     }
 
-    fn invalid_length(len: usize, expected: &dyn Expected) -> Self {
-        Self::InvalidLength(len, expected.to_string())
+    #[test]
+    fn deserialize_option_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            Option::<u32>::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"option")
+        );
     }
 
-    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
-        Self::UnknownVariant(variant.to_string(), expected)
+    #[test]
+    fn deserialize_option_unit_as_none() {
+        let mut deserializer = Deserializer::builder([Token::Unit])
+            .deserialize_unit_as_none(true)
+            .build();
+
+        assert_ok_eq!(Option::<u32>::deserialize(&mut deserializer), None);
     }
 
-    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
-        Self::UnknownField(field.to_string(), expected)
+    #[test]
+    fn deserialize_option_unit_as_none_in_struct() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Struct {
+            foo: Option<u32>,
+            bar: Option<u32>,
+        }
+
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::Unit,
+            Token::Field("bar"),
+            Token::Some,
+            Token::U32(42),
+            Token::StructEnd,
+        ])
+        .deserialize_unit_as_none(true)
+        .build();
+
+        assert_ok_eq!(
+            Struct::deserialize(&mut deserializer),
+            Struct {
+                foo: None,
+                bar: Some(42)
+            }
+        );
     }
 
-    fn missing_field(field: &'static str) -> Self {
-        Self::MissingField(field)
+    #[test]
+    fn deserialize_option_unit_error_by_default() {
+        let mut deserializer = Deserializer::builder([Token::Unit]).build();
+
+        assert_err_eq!(
+            Option::<u32>::deserialize(&mut deserializer),
+            Error::invalid_type(Unexpected::Unit, &"option")
+        );
     }
 
-    fn duplicate_field(field: &'static str) -> Self {
-        Self::DuplicateField(field)
+    #[test]
+    fn deserialize_unit() {
+        let mut deserializer = Deserializer::builder([Token::Unit]).build();
+
+        assert_ok_eq!(<()>::deserialize(&mut deserializer), ());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{
-        Deserializer,
-        EnumDeserializer,
-        Error,
-    };
-    use crate::{
-        token::CanonicalToken,
-        Token,
-    };
-    use alloc::{
-        borrow::ToOwned,
-        fmt,
-        format,
-        string::String,
-        vec,
-        vec::Vec,
-    };
-    use claims::{
-        assert_err_eq,
-        assert_ok,
-        assert_ok_eq,
-    };
-    use serde::{
-        de,
-        de::{
-            Deserialize,
-            Error as _,
-            IgnoredAny,
-            Unexpected,
-            VariantAccess,
-            Visitor,
-        },
-        Deserializer as _,
-    };
-    use serde_bytes::ByteBuf;
-    use serde_derive::Deserialize;
-    use std::collections::HashMap;
+    #[test]
+    fn deserialize_unit_error() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            <()>::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"unit")
+        );
+    }
 
     #[derive(Debug, PartialEq)]
-    enum Any {
-        Bool(bool),
-        I8(i8),
-        I16(i16),
-        I32(i32),
-        I64(i64),
-        I128(i128),
-        U8(u8),
-        U16(u16),
-        U32(u32),
-        U64(u64),
-        U128(u128),
-        F32(f32),
-        F64(f64),
-        Char(char),
-        Str(String),
-        Bytes(Vec<u8>),
-        Option(Option<u32>),
-        Unit,
-        UnitVariant,
-        NewtypeStruct(u32),
-        NewtypeVariant(u32),
-        Seq(u32, u32, u32),
-        TupleVariant(u32, u32, u32),
-        Map { foo: u32, bar: bool },
-        StructVariant { foo: u32, bar: bool },
+    struct Unit;
+
+    impl<'de> Deserialize<'de> for Unit {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct UnitVisitor;
+
+            impl Visitor<'_> for UnitVisitor {
+                type Value = Unit;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("unit struct")
+                }
+
+                fn visit_unit<E>(self) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    Ok(Unit)
+                }
+            }
+
+            deserializer.deserialize_unit_struct("Unit", UnitVisitor)
+        }
+    }
+
+    #[test]
+    fn deserialize_unit_struct() {
+        let mut deserializer = Deserializer::builder([Token::UnitStruct { name: "Unit" }]).build();
+
+        assert_ok_eq!(Unit::deserialize(&mut deserializer), Unit);
+    }
+
+    #[test]
+    fn deserialize_unit_struct_error_invalid_name() {
+        let mut deserializer =
+            Deserializer::builder([Token::UnitStruct { name: "Not Unit" }]).build();
+
+        assert_err_eq!(
+            Unit::deserialize(&mut deserializer),
+            Error::invalid_value(
+                (&mut CanonicalToken::UnitStruct { name: "Not Unit" }).into(),
+                &"unit struct"
+            )
+        );
+    }
+
+    #[test]
+    fn deserialize_unit_struct_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_err_eq!(
+            Unit::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"unit struct")
+        );
     }
 
-    impl<'de> Deserialize<'de> for Any {
+    #[derive(Debug, PartialEq)]
+    struct Newtype(u32);
+
+    impl<'de> Deserialize<'de> for Newtype {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
             D: serde::Deserializer<'de>,
         {
-            struct AnyVisitor;
+            struct NewtypeVisitor;
 
-            impl<'de> Visitor<'de> for AnyVisitor {
-                type Value = Any;
+            impl<'de> Visitor<'de> for NewtypeVisitor {
+                type Value = Newtype;
 
                 fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("struct Any")
+                    formatter.write_str("newtype struct")
                 }
 
-                fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+                fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
                 where
-                    E: serde::de::Error,
+                    D: serde::Deserializer<'de>,
                 {
-                    Ok(Any::Bool(v))
+                    Ok(Newtype(u32::deserialize(deserializer)?))
                 }
+            }
 
-                fn visit_i8<E>(self, v: i8) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::I8(v))
-                }
+            deserializer.deserialize_newtype_struct("Newtype", NewtypeVisitor)
+        }
+    }
 
-                fn visit_i16<E>(self, v: i16) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::I16(v))
-                }
+    #[test]
+    fn deserialize_newtype_struct() {
+        let mut deserializer =
+            Deserializer::builder([Token::NewtypeStruct { name: "Newtype" }, Token::U32(42)])
+                .build();
 
-                fn visit_i32<E>(self, v: i32) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::I32(v))
-                }
+        assert_ok_eq!(Newtype::deserialize(&mut deserializer), Newtype(42));
+    }
 
-                fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::I64(v))
-                }
+    #[test]
+    fn deserialize_newtype_struct_error_invalid_name() {
+        let mut deserializer = Deserializer::builder([
+            Token::NewtypeStruct {
+                name: "Not Newtype",
+            },
+            Token::U32(42),
+        ])
+        .build();
 
-                fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::I128(v))
-                }
+        assert_err_eq!(
+            Newtype::deserialize(&mut deserializer),
+            Error::invalid_value(
+                (&mut CanonicalToken::NewtypeStruct {
+                    name: "Not Newtype"
+                })
+                    .into(),
+                &"newtype struct"
+            )
+        );
+    }
 
-                fn visit_u8<E>(self, v: u8) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::U8(v))
-                }
+    #[test]
+    fn deserialize_newtype_struct_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-                fn visit_u16<E>(self, v: u16) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::U16(v))
-                }
+        assert_err_eq!(
+            Newtype::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"newtype struct")
+        );
+    }
 
-                fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::U32(v))
-                }
+    #[test]
+    fn deserialize_seq() {
+        let mut deserializer = Deserializer::builder([
+            Token::Seq { len: Some(3) },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::SeqEnd,
+        ])
+        .build();
 
-                fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::U64(v))
-                }
+        assert_ok_eq!(Vec::<u32>::deserialize(&mut deserializer), vec![1, 2, 3]);
+    }
 
-                fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::U128(v))
-                }
+    #[test]
+    fn deserialize_seq_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-                fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::F32(v))
-                }
+        assert_err_eq!(
+            Vec::<u32>::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"a sequence")
+        );
+    }
 
-                fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::F64(v))
-                }
+    #[test]
+    fn deserialize_seq_after_ended() {
+        #[derive(Debug, PartialEq)]
+        struct Seq;
 
-                fn visit_char<E>(self, v: char) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::Char(v))
-                }
+        impl<'de> Deserialize<'de> for Seq {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct SeqVisitor;
 
-                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-                where
-                    E: de::Error,
-                {
-                    Ok(Any::Str(v.to_owned()))
-                }
+                impl<'de> Visitor<'de> for SeqVisitor {
+                    type Value = Seq;
 
-                fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::Bytes(v.to_owned()))
-                }
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("Seq")
+                    }
 
-                fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-                where
-                    D: serde::Deserializer<'de>,
-                {
-                    if let Any::U32(v) = deserializer.deserialize_any(self)? {
-                        Ok(Any::Option(Some(v)))
-                    } else {
-                        unreachable!()
+                    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                    where
+                        A: de::SeqAccess<'de>,
+                    {
+                        for _ in 0..2 {
+                            if seq.next_element::<()>()?.is_some() {
+                                return Err(A::Error::custom(
+                                    "found element when no element was expected",
+                                ));
+                            }
+                        }
+
+                        Ok(Seq)
                     }
                 }
 
-                fn visit_none<E>(self) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::Option(None))
-                }
+                deserializer.deserialize_seq(SeqVisitor)
+            }
+        }
 
-                fn visit_unit<E>(self) -> Result<Self::Value, E>
-                where
-                    E: serde::de::Error,
-                {
-                    Ok(Any::Unit)
-                }
+        let mut deserializer =
+            Deserializer::builder([Token::Seq { len: Some(0) }, Token::SeqEnd]).build();
 
-                fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
-                where
-                    A: serde::de::EnumAccess<'de>,
-                {
-                    enum Variant {
-                        Unit,
-                        Newtype,
-                        Tuple,
-                        Struct,
-                    }
+        assert_ok_eq!(Seq::deserialize(&mut deserializer), Seq);
+    }
+
+    #[test]
+    fn deserialize_tuple() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 3 },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleEnd,
+        ])
+        .build();
+
+        assert_ok_eq!(<(u32, u32, u32)>::deserialize(&mut deserializer), (1, 2, 3));
+    }
+
+    #[test]
+    fn deserialize_tuple_error_len() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 1 },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleEnd,
+        ])
+        .build();
 
-                    impl<'de> Deserialize<'de> for Variant {
-                        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-                        where
-                            D: serde::Deserializer<'de>,
-                        {
-                            struct VariantVisitor;
+        assert_err_eq!(
+            <(u32, u32, u32)>::deserialize(&mut deserializer),
+            Error::invalid_length(1, &"a tuple of size 3")
+        );
+    }
 
-                            impl Visitor<'_> for VariantVisitor {
-                                type Value = Variant;
+    #[test]
+    fn deserialize_tuple_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-                                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                                    formatter.write_str("enum Variant")
-                                }
+        assert_err_eq!(
+            <(u32, u32, u32)>::deserialize(&mut deserializer),
+            Error::invalid_type(
+                (&mut CanonicalToken::Bool(true)).into(),
+                &"a tuple of size 3"
+            )
+        );
+    }
 
-                                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-                                where
-                                    E: de::Error,
-                                {
-                                    match v {
-                                        "unit" => Ok(Variant::Unit),
-                                        "newtype" => Ok(Variant::Newtype),
-                                        "tuple" => Ok(Variant::Tuple),
-                                        "struct" => Ok(Variant::Struct),
-                                        _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
-                                    }
-                                }
-                            }
+    #[test]
+    fn deserialize_tuple_error_too_many_elements() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 3 },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::U32(4),
+            Token::TupleEnd,
+        ])
+        .build();
 
-                            deserializer.deserialize_any(VariantVisitor)
-                        }
-                    }
+        assert_err_eq!(
+            <(u32, u32, u32)>::deserialize(&mut deserializer),
+            Error::ExpectedTupleEnd
+        );
+    }
 
-                    let (variant, access) = data.variant()?;
+    #[derive(Debug, PartialEq)]
+    struct TupleStruct(u32, u32, u32);
 
-                    match variant {
-                        Variant::Unit => {
-                            access.unit_variant()?;
-                            Ok(Any::UnitVariant)
-                        }
-                        Variant::Newtype => {
-                            if let Any::U32(v) = access.newtype_variant()? {
-                                Ok(Any::NewtypeVariant(v))
-                            } else {
-                                unreachable!()
-                            }
-                        }
-                        Variant::Tuple => {
-                            if let Any::Seq(a, b, c) = access.tuple_variant(3, self)? {
-                                Ok(Any::TupleVariant(a, b, c))
-                            } else {
-                                unreachable!()
-                            }
-                        }
-                        Variant::Struct => {
-                            if let Any::Map { foo, bar } =
-                                access.struct_variant(&["foo", "bar"], self)?
-                            {
-                                Ok(Any::StructVariant { foo, bar })
-                            } else {
-                                unreachable!()
-                            }
-                        }
-                    }
-                }
+    impl<'de> Deserialize<'de> for TupleStruct {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct TupleStructVisitor;
 
-                fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-                where
-                    D: serde::Deserializer<'de>,
-                {
-                    if let Any::U32(v) = deserializer.deserialize_any(self)? {
-                        Ok(Any::NewtypeStruct(v))
-                    } else {
-                        unreachable!()
-                    }
+            impl<'de> Visitor<'de> for TupleStructVisitor {
+                type Value = TupleStruct;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("TupleStruct")
                 }
 
                 fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
                 where
-                    A: serde::de::SeqAccess<'de>,
+                    A: de::SeqAccess<'de>,
                 {
-                    Ok(Any::Seq(
+                    Ok(TupleStruct(
                         seq.next_element()?
                             .ok_or(A::Error::invalid_length(0, &self))?,
                         seq.next_element()?
@@ -1853,420 +8138,639 @@ mod tests {
                             .ok_or(A::Error::invalid_length(2, &self))?,
                     ))
                 }
+            }
 
-                fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
-                where
-                    A: serde::de::MapAccess<'de>,
-                {
-                    enum Field {
-                        Foo,
-                        Bar,
-                    }
-
-                    impl<'de> Deserialize<'de> for Field {
-                        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-                        where
-                            D: serde::Deserializer<'de>,
-                        {
-                            struct FieldVisitor;
-
-                            impl Visitor<'_> for FieldVisitor {
-                                type Value = Field;
-
-                                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                                    formatter.write_str("`foo` or `bar`")
-                                }
-
-                                fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-                                where
-                                    E: de::Error,
-                                {
-                                    match value {
-                                        "foo" => Ok(Field::Foo),
-                                        "bar" => Ok(Field::Bar),
-                                        _ => Err(E::unknown_field(value, &["foo", "bar"])),
-                                    }
-                                }
-                            }
-
-                            deserializer.deserialize_identifier(FieldVisitor)
-                        }
-                    }
-
-                    let mut foo = None;
-                    let mut bar = None;
+            deserializer.deserialize_tuple_struct("TupleStruct", 3, TupleStructVisitor)
+        }
+    }
 
-                    while let Some(key) = map.next_key()? {
-                        match key {
-                            Field::Foo => {
-                                if foo.is_some() {
-                                    return Err(A::Error::duplicate_field("foo"));
-                                }
-                                foo = Some(map.next_value()?);
-                            }
-                            Field::Bar => {
-                                if bar.is_some() {
-                                    return Err(A::Error::duplicate_field("bar"));
-                                }
-                                bar = Some(map.next_value()?);
-                            }
-                        }
-                    }
+    #[test]
+    fn deserialize_tuple_struct() {
+        let mut deserializer = Deserializer::builder([
+            Token::TupleStruct {
+                name: "TupleStruct",
+                len: 3,
+            },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleStructEnd,
+        ])
+        .build();
 
-                    if foo.is_none() {
-                        return Err(A::Error::missing_field("foo"));
-                    }
-                    if bar.is_none() {
-                        return Err(A::Error::missing_field("bar"));
-                    }
+        assert_ok_eq!(
+            TupleStruct::deserialize(&mut deserializer),
+            TupleStruct(1, 2, 3)
+        );
+    }
 
-                    Ok(Any::Map {
-                        foo: foo.unwrap(),
-                        bar: bar.unwrap(),
-                    })
-                }
-            }
+    #[test]
+    fn deserialize_tuple_struct_error_name() {
+        let mut deserializer = Deserializer::builder([
+            Token::TupleStruct {
+                name: "Not TupleStruct",
+                len: 3,
+            },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleStructEnd,
+        ])
+        .build();
 
-            deserializer.deserialize_any(AnyVisitor)
-        }
+        assert_err_eq!(
+            TupleStruct::deserialize(&mut deserializer),
+            Error::invalid_value(
+                (&mut CanonicalToken::TupleStruct {
+                    name: "Not TupleStruct",
+                    len: 3
+                })
+                    .into(),
+                &"TupleStruct"
+            )
+        );
     }
 
     #[test]
-    fn deserialize_any_bool() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)])
-            .self_describing(true)
-            .build();
+    fn deserialize_tuple_struct_error_len() {
+        let mut deserializer = Deserializer::builder([
+            Token::TupleStruct {
+                name: "TupleStruct",
+                len: 1,
+            },
+            Token::U32(1),
+            Token::U32(2),
+            Token::U32(3),
+            Token::TupleStructEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Bool(true));
+        assert_err_eq!(
+            TupleStruct::deserialize(&mut deserializer),
+            Error::invalid_length(1, &"TupleStruct")
+        );
     }
 
     #[test]
-    fn deserialize_any_i8() {
-        let mut deserializer = Deserializer::builder([Token::I8(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_tuple_struct_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I8(42));
+        assert_err_eq!(
+            TupleStruct::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"TupleStruct")
+        );
     }
 
     #[test]
-    fn deserialize_any_i16() {
-        let mut deserializer = Deserializer::builder([Token::I16(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_map() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(3) },
+            Token::Char('a'),
+            Token::U32(1),
+            Token::Char('b'),
+            Token::U32(2),
+            Token::Char('c'),
+            Token::U32(3),
+            Token::MapEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I16(42));
+        assert_ok_eq!(HashMap::<char, u32>::deserialize(&mut deserializer), {
+            let mut map = HashMap::new();
+            map.insert('a', 1);
+            map.insert('b', 2);
+            map.insert('c', 3);
+            map
+        });
     }
 
     #[test]
-    fn deserialize_any_i32() {
-        let mut deserializer = Deserializer::builder([Token::I32(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_map_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I32(42));
+        assert_err_eq!(
+            HashMap::<char, u32>::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"a map")
+        );
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Struct {
+        foo: u32,
+        bar: bool,
     }
 
     #[test]
-    fn deserialize_any_i64() {
-        let mut deserializer = Deserializer::builder([Token::I64(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::Field("bar"),
+            Token::Bool(false),
+            Token::StructEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I64(42));
+        assert_ok_eq!(
+            Struct::deserialize(&mut deserializer),
+            Struct {
+                foo: 42,
+                bar: false,
+            }
+        );
     }
 
     #[test]
-    fn deserialize_any_i128() {
-        let mut deserializer = Deserializer::builder([Token::I128(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_string_fields() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 2,
+            },
+            Token::Str("foo".to_owned()),
+            Token::U32(42),
+            Token::Str("bar".to_owned()),
+            Token::Bool(false),
+            Token::StructEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::I128(42));
+        assert_ok_eq!(
+            Struct::deserialize(&mut deserializer),
+            Struct {
+                foo: 42,
+                bar: false,
+            }
+        );
     }
 
     #[test]
-    fn deserialize_any_u8() {
-        let mut deserializer = Deserializer::builder([Token::U8(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_byte_fields() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 2,
+            },
+            Token::Bytes(b"foo".to_vec()),
+            Token::U32(42),
+            Token::Bytes(b"bar".to_vec()),
+            Token::Bool(false),
+            Token::StructEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U8(42));
+        assert_ok_eq!(
+            Struct::deserialize(&mut deserializer),
+            Struct {
+                foo: 42,
+                bar: false,
+            }
+        );
     }
 
     #[test]
-    fn deserialize_any_u16() {
-        let mut deserializer = Deserializer::builder([Token::U16(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_error_name() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Not Struct",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::Field("bar"),
+            Token::Bool(false),
+            Token::StructEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U16(42));
+        assert_err_eq!(
+            Struct::deserialize(&mut deserializer),
+            Error::invalid_value(
+                (&mut CanonicalToken::Struct {
+                    name: "Not Struct",
+                    len: 2
+                })
+                    .into(),
+                &"struct Struct"
+            )
+        );
     }
 
     #[test]
-    fn deserialize_any_u32() {
-        let mut deserializer = Deserializer::builder([Token::U32(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U32(42));
+        assert_err_eq!(
+            Struct::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"struct Struct")
+        );
     }
 
-    #[test]
-    fn deserialize_any_u64() {
-        let mut deserializer = Deserializer::builder([Token::U64(42)])
-            .self_describing(true)
-            .build();
+    #[derive(Debug, PartialEq)]
+    struct EmptyStruct;
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U64(42));
+    impl<'de> Deserialize<'de> for EmptyStruct {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct EmptyStructVisitor;
+
+            impl<'de> Visitor<'de> for EmptyStructVisitor {
+                type Value = EmptyStruct;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("EmptyStruct")
+                }
+
+                fn visit_map<A>(self, _map: A) -> Result<Self::Value, A::Error>
+                where
+                    A: de::MapAccess<'de>,
+                {
+                    Ok(EmptyStruct)
+                }
+            }
+
+            deserializer.deserialize_struct("EmptyStruct", &[], EmptyStructVisitor)
+        }
     }
 
     #[test]
-    fn deserialize_any_u128() {
-        let mut deserializer = Deserializer::builder([Token::U128(42)])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_error_end_token_assertion_succeeds() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "EmptyStruct",
+                len: 0,
+            },
+            Token::StructEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::U128(42));
+        assert_ok_eq!(EmptyStruct::deserialize(&mut deserializer), EmptyStruct,);
     }
 
     #[test]
-    fn deserialize_any_f32() {
-        let mut deserializer = Deserializer::builder([Token::F32(42.)])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_error_end_token_assertion_failed() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "EmptyStruct",
+                len: 0,
+            },
+            Token::MapEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::F32(42.));
+        assert_err_eq!(
+            EmptyStruct::deserialize(&mut deserializer),
+            Error::ExpectedStructEnd,
+        );
     }
 
     #[test]
-    fn deserialize_any_f64() {
-        let mut deserializer = Deserializer::builder([Token::F64(42.)])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_after_ended() {
+        #[derive(Debug, PartialEq)]
+        struct Struct;
+
+        impl<'de> Deserialize<'de> for Struct {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct StructVisitor;
+
+                impl<'de> Visitor<'de> for StructVisitor {
+                    type Value = Struct;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("Struct")
+                    }
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::F64(42.));
-    }
+                    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                    where
+                        A: de::MapAccess<'de>,
+                    {
+                        for _ in 0..2 {
+                            if map.next_key::<()>()?.is_some() {
+                                return Err(A::Error::custom(
+                                    "found element when no element was expected",
+                                ));
+                            }
+                        }
 
-    #[test]
-    fn deserialize_any_char() {
-        let mut deserializer = Deserializer::builder([Token::Char('a')])
-            .self_describing(true)
-            .build();
+                        Ok(Struct)
+                    }
+                }
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Char('a'));
-    }
+                deserializer.deserialize_struct("Struct", &[], StructVisitor)
+            }
+        }
 
-    #[test]
-    fn deserialize_any_str() {
-        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
-            .self_describing(true)
-            .build();
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 0,
+            },
+            Token::StructEnd,
+        ])
+        .build();
 
-        assert_ok_eq!(
-            Any::deserialize(&mut deserializer),
-            Any::Str("foo".to_owned())
-        );
+        assert_ok_eq!(Struct::deserialize(&mut deserializer), Struct);
     }
 
     #[test]
-    fn deserialize_any_bytes() {
-        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_from_seq() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Struct {
+            foo: bool,
+            bar: u32,
+        }
+
+        let mut deserializer = Deserializer::builder([
+            Token::Seq { len: Some(2) },
+            Token::Bool(true),
+            Token::U32(42),
+            Token::SeqEnd,
+        ])
+        .build();
 
         assert_ok_eq!(
-            Any::deserialize(&mut deserializer),
-            Any::Bytes(b"foo".to_vec())
+            Struct::deserialize(&mut deserializer),
+            Struct { foo: true, bar: 42 }
         );
     }
 
     #[test]
-    fn deserialize_any_some() {
-        let mut deserializer = Deserializer::builder([Token::Some, Token::U32(42)])
-            .self_describing(true)
-            .build();
-
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Option(Some(42)),);
-    }
+    fn deserialize_struct_from_seq_disabled() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Struct {
+            foo: bool,
+            bar: u32,
+        }
 
-    #[test]
-    fn deserialize_any_none() {
-        let mut deserializer = Deserializer::builder([Token::None])
-            .self_describing(true)
-            .build();
+        let mut deserializer = Deserializer::builder([
+            Token::Seq { len: Some(2) },
+            Token::Bool(true),
+            Token::U32(42),
+            Token::SeqEnd,
+        ])
+        .deserialize_struct_from_seq(false)
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Option(None),);
+        assert_err_eq!(
+            Struct::deserialize(&mut deserializer),
+            Error::invalid_type(Unexpected::Seq, &"struct Struct")
+        );
     }
 
     #[test]
-    fn deserialize_any_unit() {
-        let mut deserializer = Deserializer::builder([Token::Unit])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_from_struct_with_struct_from_seq_disabled() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Struct {
+            foo: bool,
+        }
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Unit,);
-    }
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::Bool(true),
+            Token::StructEnd,
+        ])
+        .deserialize_struct_from_seq(false)
+        .build();
 
-    #[test]
-    fn deserialize_any_unit_struct() {
-        let mut deserializer = Deserializer::builder([Token::UnitStruct { name: "foo" }])
-            .self_describing(true)
-            .build();
+        assert_ok_eq!(Struct::deserialize(&mut deserializer), Struct { foo: true });
+    }
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Unit,);
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Enum {
+        Unit,
+        Newtype(u32),
+        Tuple(u32, u32, u32),
+        Struct { foo: u32, bar: bool },
     }
 
     #[test]
-    fn deserialize_any_unit_variant() {
+    fn deserialize_unit_variant() {
         let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "foo",
+            name: "Enum",
             variant_index: 0,
-            variant: "unit",
+            variant: "Unit",
         }])
-        .self_describing(true)
         .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::UnitVariant,);
+        assert_ok_eq!(Enum::deserialize(&mut deserializer), Enum::Unit,);
     }
 
     #[test]
-    fn deserialize_any_newtype_struct() {
-        let mut deserializer =
-            Deserializer::builder([Token::NewtypeStruct { name: "foo" }, Token::U32(42)])
-                .self_describing(true)
-                .build();
+    fn deserialize_unit_variant_error_name() {
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "Not Enum",
+            variant_index: 0,
+            variant: "Unit",
+        }])
+        .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::NewtypeStruct(42),);
+        assert_err_eq!(
+            Enum::deserialize(&mut deserializer),
+            Error::invalid_value(
+                (&mut CanonicalToken::UnitVariant {
+                    id: Box::new(VariantId {
+                        name: "Not Enum",
+                        variant_index: 0,
+                        variant: "Unit"
+                    })
+                })
+                    .into(),
+                &"enum Enum"
+            )
+        );
     }
 
     #[test]
-    fn deserialize_any_newtype_variant() {
+    fn deserialize_newtype_variant() {
         let mut deserializer = Deserializer::builder([
             Token::NewtypeVariant {
-                name: "foo",
-                variant_index: 0,
-                variant: "newtype",
+                name: "Enum",
+                variant_index: 1,
+                variant: "Newtype",
             },
             Token::U32(42),
         ])
-        .self_describing(true)
         .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::NewtypeVariant(42),);
+        assert_ok_eq!(Enum::deserialize(&mut deserializer), Enum::Newtype(42),);
     }
 
     #[test]
-    fn deserialize_any_seq() {
+    fn deserialize_newtype_variant_error_name() {
         let mut deserializer = Deserializer::builder([
-            Token::Seq { len: None },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::SeqEnd,
+            Token::NewtypeVariant {
+                name: "Not Enum",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::U32(42),
         ])
-        .self_describing(true)
         .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Seq(1, 2, 3),);
+        assert_err_eq!(
+            Enum::deserialize(&mut deserializer),
+            Error::invalid_value(
+                (&mut CanonicalToken::NewtypeVariant {
+                    id: Box::new(VariantId {
+                        name: "Not Enum",
+                        variant_index: 1,
+                        variant: "Newtype"
+                    })
+                })
+                    .into(),
+                &"enum Enum"
+            )
+        );
     }
 
     #[test]
-    fn deserialize_any_tuple() {
+    fn deserialize_tuple_variant() {
         let mut deserializer = Deserializer::builder([
-            Token::Tuple { len: 3 },
+            Token::TupleVariant {
+                name: "Enum",
+                variant_index: 2,
+                variant: "Tuple",
+                len: 3,
+            },
             Token::U32(1),
             Token::U32(2),
             Token::U32(3),
-            Token::TupleEnd,
+            Token::TupleVariantEnd,
         ])
-        .self_describing(true)
         .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Seq(1, 2, 3),);
+        assert_ok_eq!(Enum::deserialize(&mut deserializer), Enum::Tuple(1, 2, 3),);
     }
 
     #[test]
-    fn deserialize_any_tuple_struct() {
+    fn deserialize_tuple_variant_error_name() {
         let mut deserializer = Deserializer::builder([
-            Token::TupleStruct {
-                name: "foo",
+            Token::TupleVariant {
+                name: "Not Enum",
+                variant_index: 2,
+                variant: "Tuple",
                 len: 3,
             },
             Token::U32(1),
             Token::U32(2),
             Token::U32(3),
-            Token::TupleStructEnd,
+            Token::TupleVariantEnd,
         ])
-        .self_describing(true)
         .build();
 
-        assert_ok_eq!(Any::deserialize(&mut deserializer), Any::Seq(1, 2, 3),);
+        assert_err_eq!(
+            Enum::deserialize(&mut deserializer),
+            Error::invalid_value(
+                (&mut CanonicalToken::TupleVariant {
+                    id: Box::new(VariantId {
+                        name: "Not Enum",
+                        variant_index: 2,
+                        variant: "Tuple"
+                    }),
+                    len: 3
+                })
+                    .into(),
+                &"enum Enum"
+            )
+        );
     }
 
     #[test]
-    fn deserialize_any_tuple_variant() {
+    fn deserialize_struct_variant() {
         let mut deserializer = Deserializer::builder([
-            Token::TupleVariant {
-                name: "foo",
-                variant_index: 0,
-                variant: "tuple",
-                len: 3,
+            Token::StructVariant {
+                name: "Enum",
+                variant_index: 3,
+                variant: "Struct",
+                len: 2,
             },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::TupleVariantEnd,
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::Field("bar"),
+            Token::Bool(false),
+            Token::StructVariantEnd,
         ])
-        .self_describing(true)
         .build();
 
-        assert_ok_eq!(
-            Any::deserialize(&mut deserializer),
-            Any::TupleVariant(1, 2, 3),
+        assert_ok_eq!(
+            Enum::deserialize(&mut deserializer),
+            Enum::Struct {
+                foo: 42,
+                bar: false,
+            },
         );
     }
 
     #[test]
-    fn deserialize_any_map() {
+    fn deserialize_struct_unknown_field_ignored() {
         let mut deserializer = Deserializer::builder([
-            Token::Map { len: Some(3) },
-            Token::Str("foo".to_owned()),
+            Token::Struct {
+                name: "Struct",
+                len: 3,
+            },
+            Token::Field("foo"),
             Token::U32(42),
-            Token::Str("bar".to_owned()),
+            Token::Field("baz"),
+            Token::Unit,
+            Token::Field("bar"),
             Token::Bool(false),
-            Token::MapEnd,
+            Token::StructEnd,
         ])
         .self_describing(true)
         .build();
 
         assert_ok_eq!(
-            Any::deserialize(&mut deserializer),
-            Any::Map {
+            Struct::deserialize(&mut deserializer),
+            Struct {
                 foo: 42,
-                bar: false
-            },
+                bar: false,
+            }
         );
     }
 
     #[test]
-    fn deserialize_any_field() {
-        let mut deserializer = Deserializer::builder([Token::Field("foo")])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_deny_unknown_fields() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 3,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::Field("baz"),
+            Token::Unit,
+            Token::Field("bar"),
+            Token::Bool(false),
+            Token::StructEnd,
+        ])
+        .deny_unknown_fields(true)
+        .build();
 
-        assert_ok_eq!(
-            Any::deserialize(&mut deserializer),
-            Any::Str("foo".to_owned()),
+        assert_err_eq!(
+            Struct::deserialize(&mut deserializer),
+            Error::UnknownField("baz".to_owned(), &["foo", "bar"])
         );
     }
 
     #[test]
-    fn deserialize_any_struct() {
+    fn deserialize_struct_deny_unknown_fields_known() {
         let mut deserializer = Deserializer::builder([
             Token::Struct {
-                name: "foo",
-                len: 3,
+                name: "Struct",
+                len: 2,
             },
             Token::Field("foo"),
             Token::U32(42),
@@ -2274,1721 +8778,2367 @@ mod tests {
             Token::Bool(false),
             Token::StructEnd,
         ])
-        .self_describing(true)
+        .deny_unknown_fields(true)
         .build();
 
         assert_ok_eq!(
-            Any::deserialize(&mut deserializer),
-            Any::Map {
+            Struct::deserialize(&mut deserializer),
+            Struct {
                 foo: 42,
-                bar: false
-            },
+                bar: false,
+            }
         );
     }
 
     #[test]
-    fn deserialize_any_struct_variant() {
+    fn deserialize_struct_variant_deny_unknown_fields() {
         let mut deserializer = Deserializer::builder([
             Token::StructVariant {
-                name: "foo",
-                variant_index: 0,
-                variant: "struct",
-                len: 3,
+                name: "Enum",
+                variant_index: 3,
+                variant: "Struct",
+                len: 2,
             },
             Token::Field("foo"),
             Token::U32(42),
-            Token::Field("bar"),
+            Token::Field("baz"),
             Token::Bool(false),
             Token::StructVariantEnd,
         ])
-        .self_describing(true)
+        .deny_unknown_fields(true)
+        .build();
+
+        assert_err_eq!(
+            Enum::deserialize(&mut deserializer),
+            Error::UnknownField("baz".to_owned(), &["foo", "bar"])
+        );
+    }
+
+    #[test]
+    fn deserialize_map_duplicate_keys_allowed() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(2) },
+            Token::Char('a'),
+            Token::U32(1),
+            Token::Char('a'),
+            Token::U32(2),
+            Token::MapEnd,
+        ])
         .build();
 
+        let mut expected = HashMap::new();
+        expected.insert('a', 2);
         assert_ok_eq!(
-            Any::deserialize(&mut deserializer),
-            Any::StructVariant {
-                foo: 42,
-                bar: false
-            },
+            HashMap::<char, u32>::deserialize(&mut deserializer),
+            expected
         );
     }
 
     #[test]
-    fn deserialize_any_seq_end_fails() {
-        let mut deserializer = Deserializer::builder([Token::SeqEnd])
-            .self_describing(true)
-            .build();
+    fn deserialize_map_deny_duplicate_keys() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(3) },
+            Token::Char('a'),
+            Token::U32(1),
+            Token::Char('b'),
+            Token::U32(2),
+            Token::Char('a'),
+            Token::U32(3),
+            Token::MapEnd,
+        ])
+        .deny_duplicate_keys(true)
+        .build();
 
         assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::SeqEnd).into(), &"struct Any"),
+            HashMap::<char, u32>::deserialize(&mut deserializer),
+            Error::DuplicateKey(Tokens(vec![CanonicalToken::Char('a')]))
         );
     }
 
     #[test]
-    fn deserialize_any_tuple_end_fails() {
-        let mut deserializer = Deserializer::builder([Token::TupleEnd])
-            .self_describing(true)
-            .build();
+    fn deserialize_map_deny_duplicate_keys_compound() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(2) },
+            Token::Tuple { len: 2 },
+            Token::U8(1),
+            Token::U8(2),
+            Token::TupleEnd,
+            Token::Bool(true),
+            Token::Tuple { len: 2 },
+            Token::U8(1),
+            Token::U8(2),
+            Token::TupleEnd,
+            Token::Bool(false),
+            Token::MapEnd,
+        ])
+        .deny_duplicate_keys(true)
+        .build();
 
         assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::TupleEnd).into(), &"struct Any"),
+            HashMap::<(u8, u8), bool>::deserialize(&mut deserializer),
+            Error::DuplicateKey(Tokens(vec![
+                CanonicalToken::Tuple { len: 2 },
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::TupleEnd,
+            ]))
         );
     }
 
     #[test]
-    fn deserialize_any_tuple_struct_end_fails() {
-        let mut deserializer = Deserializer::builder([Token::TupleStructEnd])
-            .self_describing(true)
-            .build();
+    fn deserialize_map_deny_duplicate_keys_nested() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(2) },
+            Token::Map { len: Some(1) },
+            Token::U8(1),
+            Token::U8(2),
+            Token::MapEnd,
+            Token::Bool(true),
+            Token::Map { len: Some(1) },
+            Token::U8(1),
+            Token::U8(2),
+            Token::MapEnd,
+            Token::Bool(false),
+            Token::MapEnd,
+        ])
+        .deny_duplicate_keys(true)
+        .build();
 
         assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::TupleStructEnd).into(), &"struct Any"),
+            BTreeMap::<BTreeMap<u8, u8>, bool>::deserialize(&mut deserializer),
+            Error::DuplicateKey(Tokens(vec![
+                CanonicalToken::Map { len: Some(1) },
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::MapEnd,
+            ]))
         );
     }
 
     #[test]
-    fn deserialize_any_tuple_variant_end_fails() {
-        let mut deserializer = Deserializer::builder([Token::TupleVariantEnd])
-            .self_describing(true)
-            .build();
+    fn deserialize_map_deny_duplicate_keys_distinct() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(2) },
+            Token::Char('a'),
+            Token::U32(1),
+            Token::Char('b'),
+            Token::U32(2),
+            Token::MapEnd,
+        ])
+        .deny_duplicate_keys(true)
+        .build();
 
-        assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::TupleVariantEnd).into(), &"struct Any"),
+        let mut expected = HashMap::new();
+        expected.insert('a', 1);
+        expected.insert('b', 2);
+        assert_ok_eq!(
+            HashMap::<char, u32>::deserialize(&mut deserializer),
+            expected
         );
     }
 
     #[test]
-    fn deserialize_any_map_end_fails() {
-        let mut deserializer = Deserializer::builder([Token::MapEnd])
-            .self_describing(true)
-            .build();
+    fn deserialize_struct_deny_duplicate_keys() {
+        #[derive(Debug, PartialEq)]
+        struct Lenient {
+            foo: u32,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "lowercase")]
+        enum Field {
+            Foo,
+        }
+
+        impl<'de> Deserialize<'de> for Lenient {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct LenientVisitor;
+
+                impl<'de> Visitor<'de> for LenientVisitor {
+                    type Value = Lenient;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("struct Lenient")
+                    }
+
+                    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                    where
+                        A: de::MapAccess<'de>,
+                    {
+                        // Later values overwrite earlier ones rather than being rejected.
+                        let mut foo = None;
+                        while let Some(Field::Foo) = map.next_key()? {
+                            foo = Some(map.next_value()?);
+                        }
+                        foo.map(|foo| Lenient { foo })
+                            .ok_or_else(|| A::Error::missing_field("foo"))
+                    }
+                }
+
+                deserializer.deserialize_struct("Lenient", &["foo"], LenientVisitor)
+            }
+        }
+
+        let tokens = [
+            Token::Struct {
+                name: "Lenient",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::U32(1),
+            Token::Field("foo"),
+            Token::U32(2),
+            Token::StructEnd,
+        ];
 
+        let mut deserializer = Deserializer::builder(tokens.clone()).build();
+        assert_ok_eq!(Lenient::deserialize(&mut deserializer), Lenient { foo: 2 });
+
+        let mut deserializer = Deserializer::builder(tokens)
+            .deny_duplicate_keys(true)
+            .build();
         assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::MapEnd).into(), &"struct Any"),
+            Lenient::deserialize(&mut deserializer),
+            Error::DuplicateField("foo")
         );
     }
 
     #[test]
-    fn deserialize_any_struct_end_fails() {
-        let mut deserializer = Deserializer::builder([Token::StructEnd])
-            .self_describing(true)
-            .build();
+    fn deserialize_map_require_string_keys_rejects_integer() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(1) },
+            Token::U32(1),
+            Token::Bool(true),
+            Token::MapEnd,
+        ])
+        .require_string_keys(true)
+        .build();
 
         assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::StructEnd).into(), &"struct Any"),
+            HashMap::<u32, bool>::deserialize(&mut deserializer),
+            Error::InvalidType(UnexpectedValue::Unsigned(1), "a string key".to_owned())
         );
     }
 
     #[test]
-    fn deserialize_any_struct_variant_end_fails() {
-        let mut deserializer = Deserializer::builder([Token::StructVariantEnd])
-            .self_describing(true)
-            .build();
+    fn deserialize_map_require_string_keys_accepts_strings() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(2) },
+            Token::Str("a".to_owned()),
+            Token::Bool(true),
+            Token::Str("b".to_owned()),
+            Token::Bool(false),
+            Token::MapEnd,
+        ])
+        .require_string_keys(true)
+        .build();
 
-        assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::invalid_type(
-                (&mut CanonicalToken::StructVariantEnd).into(),
-                &"struct Any"
-            ),
+        let mut expected = HashMap::new();
+        expected.insert("a".to_owned(), true);
+        expected.insert("b".to_owned(), false);
+        assert_ok_eq!(
+            HashMap::<String, bool>::deserialize(&mut deserializer),
+            expected
+        );
+    }
+
+    #[test]
+    fn deserialize_struct_require_string_keys() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Struct {
+            foo: bool,
+        }
+
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::Bool(true),
+            Token::StructEnd,
+        ])
+        .require_string_keys(true)
+        .build();
+
+        assert_ok_eq!(Struct::deserialize(&mut deserializer), Struct { foo: true });
+    }
+
+    #[test]
+    fn deserialize_map_integer_keys_by_default() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(1) },
+            Token::U32(1),
+            Token::Bool(true),
+            Token::MapEnd,
+        ])
+        .build();
+
+        let mut expected = HashMap::new();
+        expected.insert(1, true);
+        assert_ok_eq!(
+            HashMap::<u32, bool>::deserialize(&mut deserializer),
+            expected
         );
     }
 
     #[test]
-    fn deserialize_any_default_not_self_describing() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deserialize_struct_variant_error_name() {
+        let mut deserializer = Deserializer::builder([
+            Token::StructVariant {
+                name: "Not Enum",
+                variant_index: 3,
+                variant: "Struct",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::Field("bar"),
+            Token::Bool(false),
+            Token::StructVariantEnd,
+        ])
+        .build();
 
         assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::NotSelfDescribing
+            Enum::deserialize(&mut deserializer),
+            Error::invalid_value(
+                (&mut CanonicalToken::StructVariant {
+                    id: Box::new(VariantId {
+                        name: "Not Enum",
+                        variant_index: 3,
+                        variant: "Struct"
+                    }),
+                    len: 2
+                })
+                    .into(),
+                &"enum Enum"
+            )
         );
     }
 
     #[test]
-    fn deserialize_any_not_self_describing() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)])
-            .self_describing(false)
-            .build();
+    fn deserialize_enum_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
         assert_err_eq!(
-            Any::deserialize(&mut deserializer),
-            Error::NotSelfDescribing
+            Enum::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"enum Enum"),
         );
     }
 
-    #[test]
-    fn deserialize_bool() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    #[derive(Debug, PartialEq)]
+    struct Identifier(String);
 
-        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
-    }
+    impl<'de> Deserialize<'de> for Identifier {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct IdentifierVisitor;
 
-    #[test]
-    fn deserialize_bool_error() {
-        let mut deserializer = Deserializer::builder([Token::I8(42)]).build();
+            impl Visitor<'_> for IdentifierVisitor {
+                type Value = Identifier;
 
-        assert_err_eq!(
-            bool::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::I8(42)).into(), &"a boolean")
-        );
-    }
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("identifier")
+                }
 
-    #[test]
-    fn deserialize_i8() {
-        let mut deserializer = Deserializer::builder([Token::I8(42)]).build();
+                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    Ok(Identifier(v.to_owned()))
+                }
+            }
 
-        assert_ok_eq!(i8::deserialize(&mut deserializer), 42);
+            deserializer.deserialize_identifier(IdentifierVisitor)
+        }
     }
 
     #[test]
-    fn deserialize_i8_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deserialize_identifier_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
 
-        assert_err_eq!(
-            i8::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i8")
+        assert_ok_eq!(
+            Identifier::deserialize(&mut deserializer),
+            Identifier("foo".to_owned())
         );
     }
 
     #[test]
-    fn deserialize_i16() {
-        let mut deserializer = Deserializer::builder([Token::I16(42)]).build();
+    fn deserialize_identifier_field() {
+        let mut deserializer = Deserializer::builder([Token::Field("foo")]).build();
 
-        assert_ok_eq!(i16::deserialize(&mut deserializer), 42);
+        assert_ok_eq!(
+            Identifier::deserialize(&mut deserializer),
+            Identifier("foo".to_owned())
+        );
     }
 
     #[test]
-    fn deserialize_i16_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deserialize_identifier_error_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(false)]).build();
 
         assert_err_eq!(
-            i16::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i16")
+            Identifier::deserialize(&mut deserializer),
+            Error::invalid_type((&mut CanonicalToken::Bool(false)).into(), &"identifier")
         );
     }
 
     #[test]
-    fn deserialize_i32() {
-        let mut deserializer = Deserializer::builder([Token::I32(42)]).build();
+    fn deserialize_ignored_any() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .self_describing(true)
+            .build();
 
-        assert_ok_eq!(i32::deserialize(&mut deserializer), 42);
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
     }
 
     #[test]
-    fn deserialize_i32_error() {
+    fn deserialize_ignored_any_default_not_self_describing() {
         let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
         assert_err_eq!(
-            i32::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i32")
+            IgnoredAny::deserialize(&mut deserializer),
+            Error::NotSelfDescribing
         );
     }
 
     #[test]
-    fn deserialize_i64() {
-        let mut deserializer = Deserializer::builder([Token::I64(42)]).build();
-
-        assert_ok_eq!(i64::deserialize(&mut deserializer), 42);
-    }
-
-    #[test]
-    fn deserialize_i64_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deserialize_ignored_any_not_self_describing() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .self_describing(false)
+            .build();
 
         assert_err_eq!(
-            i64::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i64")
+            IgnoredAny::deserialize(&mut deserializer),
+            Error::NotSelfDescribing
         );
     }
 
     #[test]
-    fn deserialize_i128() {
-        let mut deserializer = Deserializer::builder([Token::I128(42)]).build();
+    fn deserialize_skips_skipped_field() {
+        let mut deserializer =
+            Deserializer::builder([Token::SkippedField("foo"), Token::Bool(true)]).build();
 
-        assert_ok_eq!(i128::deserialize(&mut deserializer), 42);
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
     }
 
     #[test]
-    fn deserialize_i128_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deserialize_from_unordered_tokens() {
+        let mut deserializer =
+            Deserializer::builder([Token::Unordered(&[&[Token::Bool(true)]])]).build();
 
-        assert_err_eq!(
-            i128::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"i128")
-        );
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
     }
 
     #[test]
-    fn deserialize_u8() {
-        let mut deserializer = Deserializer::builder([Token::U8(42)]).build();
+    fn deserialize_with() {
+        fn deserialize_negated<'de, D>(deserializer: D) -> Result<bool, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            bool::deserialize(deserializer).map(|value| !value)
+        }
 
-        assert_ok_eq!(u8::deserialize(&mut deserializer), 42);
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+
+        assert_ok_eq!(deserializer.deserialize_with(deserialize_negated), false);
     }
 
     #[test]
-    fn deserialize_u8_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deserialize_with_error() {
+        fn deserialize_negated<'de, D>(deserializer: D) -> Result<bool, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            bool::deserialize(deserializer).map(|value| !value)
+        }
+
+        let mut deserializer = Deserializer::builder([Token::U8(1)]).build();
 
         assert_err_eq!(
-            u8::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u8")
+            deserializer.deserialize_with(deserialize_negated),
+            Error::invalid_type(Unexpected::Unsigned(1), &"a boolean")
         );
     }
 
     #[test]
-    fn deserialize_u16() {
-        let mut deserializer = Deserializer::builder([Token::U16(42)]).build();
+    #[allow(clippy::unnecessary_mut_passed)] // The trait is implemented on `&mut Deserializer`.
+    fn new() {
+        let mut deserializer = Deserializer::new([Token::Bool(true)]);
 
-        assert_ok_eq!(u16::deserialize(&mut deserializer), 42);
+        assert!((&mut deserializer).is_human_readable());
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
     }
 
     #[test]
-    fn deserialize_u16_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deserialize_seed() {
+        struct Counter<'a>(&'a mut usize);
 
-        assert_err_eq!(
-            u16::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u16")
-        );
-    }
+        impl<'de> DeserializeSeed<'de> for Counter<'_> {
+            type Value = bool;
 
-    #[test]
-    fn deserialize_u32() {
-        let mut deserializer = Deserializer::builder([Token::U32(42)]).build();
+            fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                *self.0 += 1;
+                bool::deserialize(deserializer)
+            }
+        }
 
-        assert_ok_eq!(u32::deserialize(&mut deserializer), 42);
+        let mut count = 0;
+        let mut deserializer =
+            Deserializer::builder([Token::Bool(true), Token::Bool(false)]).build();
+
+        assert_ok_eq!(deserializer.deserialize_seed(Counter(&mut count)), true);
+        assert_ok_eq!(deserializer.deserialize_seed(Counter(&mut count)), false);
+        assert_eq!(count, 2);
     }
 
     #[test]
-    fn deserialize_u32_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deserialize_seed_error() {
+        let mut deserializer = Deserializer::builder([Token::Unit]).build();
 
         assert_err_eq!(
-            u32::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u32")
+            deserializer.deserialize_seed(PhantomData::<bool>),
+            Error::invalid_type(Unexpected::Unit, &"a boolean")
         );
     }
 
     #[test]
-    fn deserialize_u64() {
-        let mut deserializer = Deserializer::builder([Token::U64(42)]).build();
-
-        assert_ok_eq!(u64::deserialize(&mut deserializer), 42);
+    fn single() {
+        assert_ok_eq!(Deserializer::single::<u8>(Token::U8(42)), 42);
     }
 
     #[test]
-    fn deserialize_u64_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
-
+    fn single_error() {
         assert_err_eq!(
-            u64::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u64")
+            Deserializer::single::<u8>(Token::Bool(true)),
+            Error::invalid_type(Unexpected::Bool(true), &"u8")
         );
     }
 
     #[test]
-    fn deserialize_u128() {
-        let mut deserializer = Deserializer::builder([Token::U128(42)]).build();
+    #[should_panic(expected = "expected the token to be consumed")]
+    fn single_not_consumed() {
+        struct Unconsumed;
 
-        assert_ok_eq!(u128::deserialize(&mut deserializer), 42);
+        impl<'de> Deserialize<'de> for Unconsumed {
+            fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                Ok(Unconsumed)
+            }
+        }
+
+        let _ = Deserializer::single::<Unconsumed>(Token::Unit);
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn deserialize_u128_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn dump() {
+        let mut deserializer =
+            Deserializer::builder([Token::Bool(true), Token::U8(1), Token::Unit]).build();
 
-        assert_err_eq!(
-            u128::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"u128")
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_eq!(
+            deserializer.dump(),
+            "Deserializer consumed 1 tokens, remaining tokens: [U8(1), Unit]"
         );
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn deserialize_f32() {
-        let mut deserializer = Deserializer::builder([Token::F32(42.)]).build();
+    fn log_consumption() {
+        let mut deserializer = Deserializer::builder([
+            Token::Seq { len: Some(1) },
+            Token::Bool(true),
+            Token::SeqEnd,
+        ])
+        .log_consumption(true)
+        .build();
 
-        assert_ok_eq!(f32::deserialize(&mut deserializer), 42.);
+        assert_ok_eq!(Vec::<bool>::deserialize(&mut deserializer), [true]);
+    }
+
+    /// Records the message that would be printed by a `Deserializer` when dropped, if any.
+    ///
+    /// This is dropped before the `Deserializer`, allowing the message to be captured while the
+    /// thread is panicking.
+    #[cfg(feature = "std")]
+    struct CapturePanicDump<'a, 'b> {
+        deserializer: &'b Deserializer<'a>,
+        dump: &'b core::cell::RefCell<Option<String>>,
+    }
+
+    #[cfg(feature = "std")]
+    impl Drop for CapturePanicDump<'_, '_> {
+        fn drop(&mut self) {
+            *self.dump.borrow_mut() = self.deserializer.panic_dump();
+        }
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn deserialize_f32_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn dump_on_panic() {
+        let dump = core::cell::RefCell::new(None);
 
-        assert_err_eq!(
-            f32::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"f32")
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut deserializer = Deserializer::builder([Token::Bool(true), Token::Unit])
+                .dump_on_panic(true)
+                .build();
+            assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+            let _capture = CapturePanicDump {
+                deserializer: &deserializer,
+                dump: &dump,
+            };
+            panic!("test failure");
+        }));
+
+        assert_err!(result);
+        assert_some_eq!(
+            dump.into_inner(),
+            "Deserializer consumed 1 tokens, remaining tokens: [Unit]"
         );
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn deserialize_f64() {
-        let mut deserializer = Deserializer::builder([Token::F64(42.)]).build();
+    fn dump_on_panic_not_panicking() {
+        let deserializer = Deserializer::builder([Token::Bool(true)])
+            .dump_on_panic(true)
+            .build();
 
-        assert_ok_eq!(f64::deserialize(&mut deserializer), 42.);
+        assert_none!(deserializer.panic_dump());
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn deserialize_f64_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
-
-        assert_err_eq!(
-            f64::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"f64")
-        );
+    fn dump_on_panic_disabled() {
+        let dump = core::cell::RefCell::new(None);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let deserializer = Deserializer::builder([Token::Bool(true)]).build();
+            let _capture = CapturePanicDump {
+                deserializer: &deserializer,
+                dump: &dump,
+            };
+            panic!("test failure");
+        }));
+
+        assert_err!(result);
+        assert_none!(dump.into_inner());
     }
 
     #[test]
-    fn deserialize_char() {
-        let mut deserializer = Deserializer::builder([Token::Char('a')]).build();
+    fn builder_tokens() {
+        let mut deserializer = Deserializer::builder([Token::Unit]).tokens([true]).build();
 
-        assert_ok_eq!(char::deserialize(&mut deserializer), 'a');
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
     }
 
     #[test]
-    fn deserialize_char_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn builder_tokens_mixed() {
+        let mut deserializer = Deserializer::builder([])
+            .tokens([
+                Token::Tuple { len: 2 },
+                42u32.into(),
+                "foo".into(),
+                Token::TupleEnd,
+            ])
+            .build();
 
-        assert_err_eq!(
-            char::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"a character")
+        assert_ok_eq!(
+            <(u32, String)>::deserialize(&mut deserializer),
+            (42, "foo".to_owned())
         );
     }
 
-    #[derive(Debug, PartialEq)]
-    struct Str(String);
-
-    impl<'de> Deserialize<'de> for Str {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct StrVisitor;
-
-            impl Visitor<'_> for StrVisitor {
-                type Value = Str;
+    #[test]
+    fn peek_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true), Token::Unit]).build();
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("str")
-                }
+        assert_matches!(deserializer.peek_token(), Some(Token::Bool(true)));
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_matches!(deserializer.peek_token(), Some(Token::Unit));
+        assert_ok!(<()>::deserialize(&mut deserializer));
+        assert_none!(deserializer.peek_token());
+    }
 
-                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-                where
-                    E: de::Error,
-                {
-                    Ok(Str(v.to_owned()))
-                }
-            }
+    #[test]
+    fn peek_token_skips_skipped_field() {
+        let deserializer =
+            Deserializer::builder([Token::SkippedField("foo"), Token::Bool(true)]).build();
 
-            deserializer.deserialize_str(StrVisitor)
-        }
+        assert_matches!(deserializer.peek_token(), Some(Token::Bool(true)));
     }
 
     #[test]
-    fn deserialize_str() {
-        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+    fn peek_token_revisited() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+        let token = &mut *assert_ok!(deserializer.next_token("deserialize_bool"));
+        deserializer.revisit_token(token);
 
-        assert_ok_eq!(Str::deserialize(&mut deserializer), Str("foo".to_owned()));
+        assert_matches!(deserializer.peek_token(), Some(Token::Bool(true)));
     }
 
     #[test]
-    fn deserialize_str_zero_copy_disabled() {
-        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
-            .zero_copy(false)
-            .build();
+    fn remaining() {
+        let mut deserializer =
+            Deserializer::builder([Token::Bool(true), Token::Unit, Token::SkippedField("foo")])
+                .build();
 
-        assert_ok_eq!(Str::deserialize(&mut deserializer), Str("foo".to_owned()));
+        assert_eq!(deserializer.remaining(), 2);
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_eq!(deserializer.remaining(), 1);
+        assert_ok!(<()>::deserialize(&mut deserializer));
+        assert_eq!(deserializer.remaining(), 0);
     }
 
     #[test]
-    fn deserialize_str_error() {
+    fn remaining_revisited() {
         let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+        let token = &mut *assert_ok!(deserializer.next_token("deserialize_bool"));
+        deserializer.revisit_token(token);
 
-        assert_err_eq!(
-            Str::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"str")
-        );
+        assert_eq!(deserializer.remaining(), 1);
     }
 
-    #[derive(Debug, Eq, PartialEq)]
-    struct BorrowedStr<'a>(&'a str);
-
-    impl<'de> Deserialize<'de> for BorrowedStr<'de> {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct BorrowedStrVisitor;
-
-            impl<'de> Visitor<'de> for BorrowedStrVisitor {
-                type Value = BorrowedStr<'de>;
+    #[test]
+    fn end() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("a borrowed str")
-                }
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_ok!(deserializer.end());
+    }
 
-                fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
-                where
-                    E: de::Error,
-                {
-                    Ok(BorrowedStr(v))
-                }
-            }
+    #[test]
+    fn end_trailing_tokens() {
+        let mut deserializer =
+            Deserializer::builder([Token::Bool(true), Token::Unit, Token::Unit]).build();
 
-            deserializer.deserialize_str(BorrowedStrVisitor)
-        }
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_err_eq!(deserializer.end(), Error::TrailingTokens(2));
     }
 
     #[test]
-    fn deserialize_borrowed_str() {
-        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+    fn end_allow_trailing_tokens() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true), Token::Unit])
+            .allow_trailing_tokens(true)
+            .build();
 
-        assert_ok_eq!(
-            BorrowedStr::deserialize(&mut deserializer),
-            BorrowedStr("foo")
-        );
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_ok!(deserializer.end());
+        assert_eq!(deserializer.remaining(), 1);
     }
 
     #[test]
-    fn deserialize_borrowed_str_zero_copy_disabled_error() {
-        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
-            .zero_copy(false)
-            .build();
+    fn skip_value_primitive() {
+        let mut deserializer = Deserializer::builder([Token::U8(1), Token::Bool(true)]).build();
 
-        assert_err_eq!(
-            BorrowedStr::deserialize(&mut deserializer),
-            Error::invalid_type(
-                (&mut CanonicalToken::Str("foo".to_owned())).into(),
-                &"a borrowed str"
-            )
-        );
+        assert_ok!(deserializer.skip_value());
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
     }
 
     #[test]
-    fn deserialize_string() {
-        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+    fn skip_value_nested() {
+        let mut deserializer = Deserializer::builder([
+            Token::Some,
+            Token::Struct {
+                name: "Struct",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::Seq { len: Some(1) },
+            Token::Unit,
+            Token::SeqEnd,
+            Token::Field("bar"),
+            Token::NewtypeStruct { name: "Newtype" },
+            Token::Map { len: Some(0) },
+            Token::MapEnd,
+            Token::StructEnd,
+            Token::Bool(true),
+        ])
+        .self_describing(false)
+        .build();
 
-        assert_ok_eq!(String::deserialize(&mut deserializer), "foo".to_owned());
+        assert_ok!(deserializer.skip_value());
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_ok!(deserializer.end());
     }
 
     #[test]
-    fn deserialize_string_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn skip_value_deeply_nested() {
+        let mut deserializer = Deserializer::builder(
+            iter::repeat(Token::Seq { len: Some(1) })
+                .take(100_000)
+                .chain(iter::once(Token::Unit))
+                .chain(iter::repeat(Token::SeqEnd).take(100_000))
+                .chain(iter::once(Token::Bool(true))),
+        )
+        .self_describing(false)
+        .build();
 
-        assert_err_eq!(
-            String::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"a string")
-        );
+        assert_ok!(deserializer.skip_value());
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_ok!(deserializer.end());
     }
 
-    #[derive(Debug, PartialEq)]
-    struct Bytes(Vec<u8>);
+    #[test]
+    fn skip_value_within_seq() {
+        #[derive(Debug, PartialEq)]
+        struct Last(u8);
 
-    impl<'de> Deserialize<'de> for Bytes {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct BytesVisitor;
+        impl<'de> Deserialize<'de> for Last {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                <Vec<u8>>::deserialize(deserializer).map(|values| Last(values[values.len() - 1]))
+            }
+        }
 
-            impl Visitor<'_> for BytesVisitor {
-                type Value = Bytes;
+        let mut deserializer = Deserializer::builder([
+            Token::Seq { len: Some(2) },
+            Token::U8(1),
+            Token::SeqEnd,
+            Token::Seq { len: Some(1) },
+            Token::U8(2),
+            Token::SeqEnd,
+        ])
+        .build();
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("bytes")
-                }
+        assert_ok!(deserializer.skip_value());
+        assert_ok_eq!(Last::deserialize(&mut deserializer), Last(2));
+    }
 
-                fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
-                where
-                    E: de::Error,
-                {
-                    Ok(Bytes(v.to_vec()))
-                }
-            }
+    #[test]
+    fn skip_value_end_of_tokens() {
+        let mut deserializer =
+            Deserializer::builder([Token::Seq { len: Some(1) }, Token::U8(1)]).build();
 
-            deserializer.deserialize_bytes(BytesVisitor)
-        }
+        assert_err_eq!(deserializer.skip_value(), Error::EndOfTokens);
     }
 
     #[test]
-    fn deserialize_bytes() {
-        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
+    fn skip_value_end_token() {
+        let mut deserializer = Deserializer::builder([Token::SeqEnd]).build();
 
-        assert_ok_eq!(
-            Bytes::deserialize(&mut deserializer),
-            Bytes(b"foo".to_vec())
+        assert_err_eq!(
+            deserializer.skip_value(),
+            Error::InvalidType(
+                UnexpectedValue::Other("SeqEnd".to_owned()),
+                "a value".to_owned()
+            )
         );
+        assert_eq!(deserializer.remaining(), 1);
     }
 
     #[test]
-    fn deserialize_bytes_zero_copy_disabled() {
-        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
-            .zero_copy(false)
+    fn skip_value_consumptions() {
+        let mut deserializer = Deserializer::builder([Token::Some, Token::U8(1)])
+            .record_consumptions(true)
             .build();
 
-        assert_ok_eq!(
-            Bytes::deserialize(&mut deserializer),
-            Bytes(b"foo".to_vec())
+        assert_ok!(deserializer.skip_value());
+        assert_eq!(
+            deserializer.trace(),
+            "skip_value: Some\nskip_value: U8(1)\n"
         );
     }
 
     #[test]
-    fn deserialize_bytes_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn implicit_map_end_map() {
+        let mut deserializer =
+            Deserializer::builder([Token::Map { len: Some(2) }, Token::Char('a'), Token::U32(1)])
+                .implicit_map_end(true)
+                .build();
 
-        assert_err_eq!(
-            Bytes::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"bytes")
+        assert_ok_eq!(
+            HashMap::<char, u32>::deserialize(&mut deserializer),
+            [('a', 1)].into_iter().collect::<HashMap<_, _>>()
         );
     }
 
-    #[derive(Debug, Eq, PartialEq)]
-    struct BorrowedBytes<'a>(&'a [u8]);
-
-    impl<'de> Deserialize<'de> for BorrowedBytes<'de> {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct BorrowedBytesVisitor;
+    #[test]
+    fn implicit_map_end_nested_struct() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Inner {
+            #[serde(default)]
+            foo: u32,
+            #[serde(default)]
+            bar: u32,
+        }
 
-            impl<'de> Visitor<'de> for BorrowedBytesVisitor {
-                type Value = BorrowedBytes<'de>;
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Outer {
+            inner: Inner,
+            #[serde(default)]
+            baz: bool,
+        }
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("borrowed bytes")
-                }
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Outer",
+                len: 2,
+            },
+            Token::Field("inner"),
+            Token::Struct {
+                name: "Inner",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+        ])
+        .implicit_map_end(true)
+        .build();
 
-                fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
-                where
-                    E: de::Error,
-                {
-                    Ok(BorrowedBytes(v))
-                }
+        assert_ok_eq!(
+            Outer::deserialize(&mut deserializer),
+            Outer {
+                inner: Inner { foo: 42, bar: 0 },
+                baz: false,
             }
+        );
+    }
 
-            deserializer.deserialize_bytes(BorrowedBytesVisitor)
-        }
+    #[test]
+    fn implicit_map_end_missing_value() {
+        let mut deserializer =
+            Deserializer::builder([Token::Map { len: Some(1) }, Token::Char('a')])
+                .implicit_map_end(true)
+                .build();
+
+        assert_err_eq!(
+            HashMap::<char, u32>::deserialize(&mut deserializer),
+            Error::EndOfTokens
+        );
     }
 
     #[test]
-    fn deserialize_borrowed_bytes() {
-        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
+    fn implicit_map_end_seq() {
+        let mut deserializer = Deserializer::builder([Token::Seq { len: Some(2) }, Token::U32(1)])
+            .implicit_map_end(true)
+            .build();
 
-        assert_ok_eq!(
-            BorrowedBytes::deserialize(&mut deserializer),
-            BorrowedBytes(b"foo")
+        assert_err_eq!(
+            Vec::<u32>::deserialize(&mut deserializer),
+            Error::EndOfTokens
         );
     }
 
     #[test]
-    fn deserialize_borrowed_bytes_zero_copy_disabled_error() {
-        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
-            .zero_copy(false)
-            .build();
+    fn implicit_map_end_disabled() {
+        let mut deserializer =
+            Deserializer::builder([Token::Map { len: Some(2) }, Token::Char('a'), Token::U32(1)])
+                .build();
 
         assert_err_eq!(
-            BorrowedBytes::deserialize(&mut deserializer),
-            Error::invalid_type(
-                (&mut CanonicalToken::Bytes(b"foo".to_vec())).into(),
-                &"borrowed bytes"
-            )
+            HashMap::<char, u32>::deserialize(&mut deserializer),
+            Error::EndOfTokens
         );
     }
 
     #[test]
-    fn deserialize_byte_buf() {
-        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
+    fn fail_after_tokens_zero() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .fail_after_tokens(0)
+            .build();
 
-        assert_ok_eq!(
-            ByteBuf::deserialize(&mut deserializer),
-            ByteBuf::from(b"foo".to_vec())
-        );
+        assert_err_eq!(bool::deserialize(&mut deserializer), Error::Injected(0));
+        assert!(deserializer.consumptions().is_empty());
     }
 
     #[test]
-    fn deserialize_byte_buf_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn fail_after_tokens_nested() {
+        let mut deserializer = Deserializer::builder([
+            Token::Seq { len: Some(2) },
+            Token::Some,
+            Token::U32(1),
+            Token::None,
+            Token::SeqEnd,
+        ])
+        .fail_after_tokens(3)
+        .record_consumptions(true)
+        .build();
 
         assert_err_eq!(
-            ByteBuf::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"byte array")
+            Vec::<Option<u32>>::deserialize(&mut deserializer),
+            Error::Injected(3)
         );
+        assert_eq!(deserializer.consumptions().len(), 3);
     }
 
     #[test]
-    fn deserialize_option_some() {
-        let mut deserializer = Deserializer::builder([Token::Some, Token::U32(42)]).build();
+    fn fail_after_tokens_not_reached() {
+        let mut deserializer =
+            Deserializer::builder([Token::Seq { len: Some(1) }, Token::U32(1), Token::SeqEnd])
+                .fail_after_tokens(3)
+                .build();
 
-        assert_ok_eq!(Option::<u32>::deserialize(&mut deserializer), Some(42));
+        assert_ok_eq!(Vec::<u32>::deserialize(&mut deserializer), vec![1]);
     }
 
     #[test]
-    fn deserialize_option_none() {
-        let mut deserializer = Deserializer::builder([Token::None]).build();
+    fn fail_after_tokens_every_position() {
+        let tokens = [
+            Token::Tuple { len: 2 },
+            Token::U32(1),
+            Token::Str("foo".to_owned()),
+            Token::TupleEnd,
+        ];
 
-        assert_ok_eq!(Option::<u32>::deserialize(&mut deserializer), None);
+        for fail_after_tokens in 0..tokens.len() {
+            let mut deserializer = Deserializer::builder(tokens.clone())
+                .fail_after_tokens(fail_after_tokens)
+                .build();
+
+            assert_err_eq!(
+                <(u32, String)>::deserialize(&mut deserializer),
+                Error::Injected(fail_after_tokens)
+            );
+        }
     }
 
     #[test]
-    fn deserialize_option_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn error_token() {
+        let mut deserializer = Deserializer::builder([Token::Error("foo".to_owned())]).build();
 
         assert_err_eq!(
-            Option::<u32>::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"option")
+            bool::deserialize(&mut deserializer),
+            Error::Custom("foo".to_owned())
         );
     }
 
     #[test]
-    fn deserialize_unit() {
-        let mut deserializer = Deserializer::builder([Token::Unit]).build();
+    fn error_token_nested() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(3) },
+            Token::Char('a'),
+            Token::U32(1),
+            Token::Char('b'),
+            Token::Error("foo".to_owned()),
+            Token::Char('c'),
+            Token::U32(3),
+            Token::MapEnd,
+        ])
+        .record_consumptions(true)
+        .build();
 
-        assert_ok_eq!(<()>::deserialize(&mut deserializer), ());
+        assert_err_eq!(
+            HashMap::<char, u32>::deserialize(&mut deserializer),
+            Error::Custom("foo".to_owned())
+        );
+        assert_eq!(deserializer.consumptions().len(), 5);
     }
 
     #[test]
-    fn deserialize_unit_error() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn into_tokens() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 2 },
+            Token::U8(1),
+            Token::U8(2),
+            Token::TupleEnd,
+            Token::Str("foo".to_owned()),
+            Token::Bool(true),
+        ])
+        .build();
 
-        assert_err_eq!(
-            <()>::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"unit")
+        assert_ok_eq!(<(u8, u8)>::deserialize(&mut deserializer), (1, 2));
+        assert_eq!(
+            deserializer.into_tokens(),
+            [Token::Str("foo".to_owned()), Token::Bool(true)]
         );
     }
 
-    #[derive(Debug, PartialEq)]
-    struct Unit;
+    #[test]
+    fn into_tokens_unconsumed() {
+        let deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-    impl<'de> Deserialize<'de> for Unit {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct UnitVisitor;
+        assert_eq!(deserializer.into_tokens(), [Token::Bool(true)]);
+    }
 
-            impl Visitor<'_> for UnitVisitor {
-                type Value = Unit;
+    #[test]
+    fn into_tokens_consumed() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("unit struct")
-                }
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_eq!(deserializer.into_tokens(), []);
+    }
 
-                fn visit_unit<E>(self) -> Result<Self::Value, E>
-                where
-                    E: de::Error,
-                {
-                    Ok(Unit)
-                }
-            }
+    #[test]
+    fn into_tokens_revisited() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true), Token::Unit]).build();
+        let token = &mut *assert_ok!(deserializer.next_token("deserialize_bool"));
+        deserializer.revisit_token(token);
 
-            deserializer.deserialize_unit_struct("Unit", UnitVisitor)
-        }
+        assert_eq!(deserializer.into_tokens(), [Token::Bool(true), Token::Unit]);
     }
 
     #[test]
-    fn deserialize_unit_struct() {
-        let mut deserializer = Deserializer::builder([Token::UnitStruct { name: "Unit" }]).build();
+    fn into_tokens_second_phase() {
+        let mut deserializer =
+            Deserializer::builder([Token::U32(42), Token::Some, Token::Char('a')]).build();
 
-        assert_ok_eq!(Unit::deserialize(&mut deserializer), Unit);
+        assert_ok_eq!(u32::deserialize(&mut deserializer), 42);
+
+        let mut deserializer = Deserializer::builder(deserializer.into_tokens()).build();
+
+        assert_ok_eq!(Option::<char>::deserialize(&mut deserializer), Some('a'));
     }
 
     #[test]
-    fn deserialize_unit_struct_error_invalid_name() {
-        let mut deserializer =
-            Deserializer::builder([Token::UnitStruct { name: "Not Unit" }]).build();
+    fn consumptions() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .record_consumptions(true)
+            .build();
 
-        assert_err_eq!(
-            Unit::deserialize(&mut deserializer),
-            Error::invalid_value(
-                (&mut CanonicalToken::UnitStruct { name: "Not Unit" }).into(),
-                &"unit struct"
-            )
-        );
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        let consumptions = deserializer.consumptions();
+        assert_eq!(consumptions.len(), 1);
+        assert_eq!(consumptions[0].method(), "deserialize_bool");
+        assert_matches!(consumptions[0].token(), Token::Bool(true));
     }
 
     #[test]
-    fn deserialize_unit_struct_error_token() {
+    fn consumptions_not_recorded() {
         let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-        assert_err_eq!(
-            Unit::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"unit struct")
-        );
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert!(deserializer.consumptions().is_empty());
+        assert_eq!(deserializer.trace(), "");
     }
 
-    #[derive(Debug, PartialEq)]
-    struct Newtype(u32);
+    #[test]
+    fn consumptions_label() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 2 },
+            Token::labeled("first", Token::U8(1)),
+            Token::U8(2),
+            Token::TupleEnd,
+        ])
+        .record_consumptions(true)
+        .build();
 
-    impl<'de> Deserialize<'de> for Newtype {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct NewtypeVisitor;
+        assert_ok_eq!(<(u8, u8)>::deserialize(&mut deserializer), (1, 2));
+        let consumptions = deserializer.consumptions();
+        assert_none!(consumptions[0].label());
+        assert_some_eq!(consumptions[1].label(), "first");
+        assert_none!(consumptions[2].label());
+    }
 
-            impl<'de> Visitor<'de> for NewtypeVisitor {
-                type Value = Newtype;
+    #[test]
+    fn trace_empty() {
+        let deserializer = Deserializer::builder([]).build();
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("newtype struct")
-                }
+        assert_eq!(deserializer.trace(), "");
+    }
 
-                fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-                where
-                    D: serde::Deserializer<'de>,
-                {
-                    Ok(Newtype(u32::deserialize(deserializer)?))
-                }
-            }
+    #[test]
+    fn trace_revisited() {
+        let mut deserializer = Deserializer::builder([Token::Some, Token::Bool(true)])
+            .record_consumptions(true)
+            .build();
 
-            deserializer.deserialize_newtype_struct("Newtype", NewtypeVisitor)
-        }
+        assert_ok_eq!(Option::<bool>::deserialize(&mut deserializer), Some(true));
+        assert_eq!(
+            deserializer.trace(),
+            "deserialize_option: Some\ndeserialize_bool: Bool(true)\n"
+        );
     }
 
     #[test]
-    fn deserialize_newtype_struct() {
+    fn trace_labeled() {
+        let mut deserializer = Deserializer::builder([Token::labeled("flag", Token::Bool(true))])
+            .record_consumptions(true)
+            .build();
+
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_eq!(
+            deserializer.trace(),
+            "deserialize_bool: Bool(true) (flag)\n"
+        );
+    }
+
+    #[test]
+    fn trace_skips_skipped_field() {
         let mut deserializer =
-            Deserializer::builder([Token::NewtypeStruct { name: "Newtype" }, Token::U32(42)])
+            Deserializer::builder([Token::SkippedField("foo"), Token::Bool(true)])
+                .record_consumptions(true)
                 .build();
 
-        assert_ok_eq!(Newtype::deserialize(&mut deserializer), Newtype(42));
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_eq!(deserializer.trace(), "deserialize_bool: Bool(true)\n");
     }
 
     #[test]
-    fn deserialize_newtype_struct_error_invalid_name() {
-        let mut deserializer = Deserializer::builder([
-            Token::NewtypeStruct {
-                name: "Not Newtype",
-            },
-            Token::U32(42),
-        ])
-        .build();
+    fn visits_empty() {
+        let deserializer = Deserializer::builder([Token::Bool(true)]).build();
 
-        assert_err_eq!(
-            Newtype::deserialize(&mut deserializer),
-            Error::invalid_value(
-                (&mut CanonicalToken::NewtypeStruct {
-                    name: "Not Newtype"
-                })
-                    .into(),
-                &"newtype struct"
-            )
-        );
+        assert_eq!(deserializer.visits(), []);
     }
 
+    #[cfg(not(feature = "safe"))]
     #[test]
-    fn deserialize_newtype_struct_error_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn visits_borrowed_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
 
-        assert_err_eq!(
-            Newtype::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"newtype struct")
-        );
+        assert_ok_eq!(<&str>::deserialize(&mut deserializer), "foo");
+        assert_eq!(deserializer.visits(), [Visit::BorrowedStr]);
     }
 
     #[test]
-    fn deserialize_seq() {
+    fn visits_string() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .zero_copy(false)
+            .build();
+
+        assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+        assert_eq!(deserializer.visits(), [Visit::String]);
+    }
+
+    #[test]
+    fn visits_nested() {
         let mut deserializer = Deserializer::builder([
-            Token::Seq { len: Some(3) },
+            Token::Seq { len: Some(2) },
+            Token::Some,
             Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
+            Token::None,
             Token::SeqEnd,
         ])
         .build();
 
-        assert_ok_eq!(Vec::<u32>::deserialize(&mut deserializer), vec![1, 2, 3]);
+        assert_ok_eq!(
+            Vec::<Option<u32>>::deserialize(&mut deserializer),
+            vec![Some(1), None]
+        );
+        assert_eq!(
+            deserializer.visits(),
+            [Visit::Seq, Visit::Some, Visit::U32, Visit::None]
+        );
+    }
+
+    #[test]
+    fn visits_enum() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Enum {
+            Unit,
+        }
+
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "Enum",
+            variant_index: 0,
+            variant: "Unit",
+        }])
+        .build();
+
+        assert_ok_eq!(Enum::deserialize(&mut deserializer), Enum::Unit);
+        assert_eq!(deserializer.visits(), [Visit::Enum, Visit::Str]);
+    }
+
+    #[test]
+    fn deliveries_empty() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .record_consumptions(true)
+            .build();
+
+        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+        assert_eq!(deserializer.deliveries(), []);
+        assert_none!(deserializer.consumptions()[0].delivery());
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn deliveries_borrowed_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+
+        assert_ok_eq!(<&str>::deserialize(&mut deserializer), "foo");
+        assert_eq!(deserializer.deliveries(), [Delivery::Borrowed]);
+    }
+
+    #[cfg(feature = "safe")]
+    #[test]
+    fn deliveries_borrowed_str_safe() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+
+        assert_ok_eq!(Str::deserialize(&mut deserializer), Str("foo".to_owned()));
+        assert_eq!(deserializer.visits(), [Visit::Str]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
+    }
+
+    #[test]
+    fn deliveries_owned_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+
+        assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+        assert_eq!(deserializer.deliveries(), [Delivery::Owned]);
     }
 
     #[test]
-    fn deserialize_seq_error_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn deliveries_transient_identifier() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .record_consumptions(true)
+            .build();
 
-        assert_err_eq!(
-            Vec::<u32>::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"a sequence")
+        assert_ok!((&mut deserializer).deserialize_identifier(IgnoredAny));
+        assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
+        assert_some_eq!(
+            deserializer.consumptions()[0].delivery(),
+            Delivery::Transient
         );
     }
 
+    #[cfg(not(feature = "safe"))]
     #[test]
-    fn deserialize_seq_after_ended() {
-        #[derive(Debug, PartialEq)]
-        struct Seq;
-
-        impl<'de> Deserialize<'de> for Seq {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct SeqVisitor;
-
-                impl<'de> Visitor<'de> for SeqVisitor {
-                    type Value = Seq;
-
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("Seq")
-                    }
+    fn deliveries_borrowed_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
 
-                    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-                    where
-                        A: de::SeqAccess<'de>,
-                    {
-                        for _ in 0..2 {
-                            if seq.next_element::<()>()?.is_some() {
-                                return Err(A::Error::custom(
-                                    "found element when no element was expected",
-                                ));
-                            }
-                        }
+        assert_ok_eq!(<&[u8]>::deserialize(&mut deserializer), b"foo");
+        assert_eq!(deserializer.deliveries(), [Delivery::Borrowed]);
+    }
 
-                        Ok(Seq)
-                    }
-                }
+    #[cfg(feature = "safe")]
+    #[test]
+    fn deliveries_borrowed_bytes_safe() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
 
-                deserializer.deserialize_seq(SeqVisitor)
-            }
-        }
+        assert_ok!((&mut deserializer).deserialize_bytes(IgnoredAny));
+        assert_eq!(deserializer.visits(), [Visit::Bytes]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
+    }
 
-        let mut deserializer =
-            Deserializer::builder([Token::Seq { len: Some(0) }, Token::SeqEnd]).build();
+    #[test]
+    fn deliveries_owned_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())]).build();
 
-        assert_ok_eq!(Seq::deserialize(&mut deserializer), Seq);
+        assert_ok_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            ByteBuf::from(b"foo".to_vec())
+        );
+        assert_eq!(deserializer.deliveries(), [Delivery::Owned]);
     }
 
     #[test]
-    fn deserialize_tuple() {
+    fn deliveries_any_no_zero_copy() {
         let mut deserializer = Deserializer::builder([
-            Token::Tuple { len: 3 },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
+            Token::Tuple { len: 2 },
+            Token::Str("foo".to_owned()),
+            Token::Bytes(b"bar".to_vec()),
             Token::TupleEnd,
         ])
+        .self_describing(true)
+        .zero_copy(false)
         .build();
 
-        assert_ok_eq!(<(u32, u32, u32)>::deserialize(&mut deserializer), (1, 2, 3));
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
+        assert_eq!(
+            deserializer.deliveries(),
+            [Delivery::Owned, Delivery::Owned]
+        );
     }
 
+    #[cfg(not(feature = "safe"))]
     #[test]
-    fn deserialize_tuple_error_len() {
-        let mut deserializer = Deserializer::builder([
-            Token::Tuple { len: 1 },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::TupleEnd,
-        ])
-        .build();
+    fn str_delivery_borrowed() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .str_delivery(Delivery::Borrowed)
+            .build();
 
-        assert_err_eq!(
-            <(u32, u32, u32)>::deserialize(&mut deserializer),
-            Error::invalid_length(1, &"a tuple of size 3")
-        );
+        assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+        assert_eq!(deserializer.visits(), [Visit::BorrowedStr]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Borrowed]);
     }
 
+    #[cfg(feature = "safe")]
     #[test]
-    fn deserialize_tuple_error_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn str_delivery_borrowed_safe() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .str_delivery(Delivery::Borrowed)
+            .build();
 
-        assert_err_eq!(
-            <(u32, u32, u32)>::deserialize(&mut deserializer),
-            Error::invalid_type(
-                (&mut CanonicalToken::Bool(true)).into(),
-                &"a tuple of size 3"
-            )
-        );
+        assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+        assert_eq!(deserializer.visits(), [Visit::Str]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
     }
 
     #[test]
-    fn deserialize_tuple_error_too_many_elements() {
-        let mut deserializer = Deserializer::builder([
-            Token::Tuple { len: 3 },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::U32(4),
-            Token::TupleEnd,
-        ])
-        .build();
+    fn str_delivery_transient() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .str_delivery(Delivery::Transient)
+            .build();
 
-        assert_err_eq!(
-            <(u32, u32, u32)>::deserialize(&mut deserializer),
-            Error::ExpectedTupleEnd
-        );
+        assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+        assert_eq!(deserializer.visits(), [Visit::Str]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
     }
 
-    #[derive(Debug, PartialEq)]
-    struct TupleStruct(u32, u32, u32);
+    #[test]
+    fn str_delivery_owned() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .str_delivery(Delivery::Owned)
+            .build();
 
-    impl<'de> Deserialize<'de> for TupleStruct {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct TupleStructVisitor;
+        assert_ok_eq!(String::deserialize(&mut deserializer), "foo");
+        assert_eq!(deserializer.visits(), [Visit::String]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Owned]);
+    }
 
-            impl<'de> Visitor<'de> for TupleStructVisitor {
-                type Value = TupleStruct;
+    #[test]
+    fn str_delivery_owned_borrowed_str_error() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .str_delivery(Delivery::Owned)
+            .build();
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("TupleStruct")
-                }
+        assert_err_eq!(
+            <&str>::deserialize(&mut deserializer),
+            Error::invalid_type(Unexpected::Str("foo"), &"a borrowed string")
+        );
+    }
 
-                fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-                where
-                    A: de::SeqAccess<'de>,
-                {
-                    Ok(TupleStruct(
-                        seq.next_element()?
-                            .ok_or(A::Error::invalid_length(0, &self))?,
-                        seq.next_element()?
-                            .ok_or(A::Error::invalid_length(1, &self))?,
-                        seq.next_element()?
-                            .ok_or(A::Error::invalid_length(2, &self))?,
-                    ))
-                }
-            }
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn str_delivery_overrides_zero_copy() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .zero_copy(false)
+            .str_delivery(Delivery::Borrowed)
+            .build();
 
-            deserializer.deserialize_tuple_struct("TupleStruct", 3, TupleStructVisitor)
-        }
+        assert_ok_eq!(<&str>::deserialize(&mut deserializer), "foo");
     }
 
     #[test]
-    fn deserialize_tuple_struct() {
-        let mut deserializer = Deserializer::builder([
-            Token::TupleStruct {
-                name: "TupleStruct",
-                len: 3,
-            },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::TupleStructEnd,
-        ])
-        .build();
+    fn str_delivery_any() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .self_describing(true)
+            .str_delivery(Delivery::Transient)
+            .build();
 
-        assert_ok_eq!(
-            TupleStruct::deserialize(&mut deserializer),
-            TupleStruct(1, 2, 3)
-        );
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
+        assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
     }
 
     #[test]
-    fn deserialize_tuple_struct_error_name() {
-        let mut deserializer = Deserializer::builder([
-            Token::TupleStruct {
-                name: "Not TupleStruct",
-                len: 3,
-            },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::TupleStructEnd,
-        ])
-        .build();
+    fn str_delivery_identifier() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())])
+            .str_delivery(Delivery::Owned)
+            .build();
 
-        assert_err_eq!(
-            TupleStruct::deserialize(&mut deserializer),
-            Error::invalid_value(
-                (&mut CanonicalToken::TupleStruct {
-                    name: "Not TupleStruct",
-                    len: 3
-                })
-                    .into(),
-                &"TupleStruct"
-            )
-        );
+        assert_ok!((&mut deserializer).deserialize_identifier(IgnoredAny));
+        assert_eq!(deserializer.deliveries(), [Delivery::Owned]);
     }
 
+    #[cfg(not(feature = "safe"))]
     #[test]
-    fn deserialize_tuple_struct_error_len() {
-        let mut deserializer = Deserializer::builder([
-            Token::TupleStruct {
-                name: "TupleStruct",
-                len: 1,
-            },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::TupleStructEnd,
-        ])
-        .build();
+    fn str_delivery_does_not_affect_bytes() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .str_delivery(Delivery::Owned)
+            .build();
 
-        assert_err_eq!(
-            TupleStruct::deserialize(&mut deserializer),
-            Error::invalid_length(1, &"TupleStruct")
+        assert_ok_eq!(<&[u8]>::deserialize(&mut deserializer), b"foo");
+        assert_eq!(deserializer.deliveries(), [Delivery::Borrowed]);
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn bytes_delivery_borrowed() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .bytes_delivery(Delivery::Borrowed)
+            .build();
+
+        assert_ok_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            ByteBuf::from(b"foo".to_vec())
         );
+        assert_eq!(deserializer.visits(), [Visit::BorrowedBytes]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Borrowed]);
     }
 
     #[test]
-    fn deserialize_tuple_struct_error_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn bytes_delivery_transient() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .bytes_delivery(Delivery::Transient)
+            .build();
 
-        assert_err_eq!(
-            TupleStruct::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"TupleStruct")
+        assert_ok_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            ByteBuf::from(b"foo".to_vec())
         );
+        assert_eq!(deserializer.visits(), [Visit::Bytes]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
     }
 
     #[test]
-    fn deserialize_map() {
-        let mut deserializer = Deserializer::builder([
-            Token::Map { len: Some(3) },
-            Token::Char('a'),
-            Token::U32(1),
-            Token::Char('b'),
-            Token::U32(2),
-            Token::Char('c'),
-            Token::U32(3),
-            Token::MapEnd,
-        ])
-        .build();
+    fn bytes_delivery_owned() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .bytes_delivery(Delivery::Owned)
+            .build();
 
-        assert_ok_eq!(HashMap::<char, u32>::deserialize(&mut deserializer), {
-            let mut map = HashMap::new();
-            map.insert('a', 1);
-            map.insert('b', 2);
-            map.insert('c', 3);
-            map
-        });
+        assert_ok_eq!(
+            ByteBuf::deserialize(&mut deserializer),
+            ByteBuf::from(b"foo".to_vec())
+        );
+        assert_eq!(deserializer.visits(), [Visit::ByteBuf]);
+        assert_eq!(deserializer.deliveries(), [Delivery::Owned]);
     }
 
+    #[cfg(not(feature = "safe"))]
     #[test]
-    fn deserialize_map_error_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn bytes_delivery_overrides_zero_copy() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .zero_copy(false)
+            .bytes_delivery(Delivery::Borrowed)
+            .build();
 
-        assert_err_eq!(
-            HashMap::<char, u32>::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"a map")
-        );
+        assert_ok_eq!(<&[u8]>::deserialize(&mut deserializer), b"foo");
     }
 
-    #[derive(Debug, Deserialize, PartialEq)]
-    struct Struct {
-        foo: u32,
-        bar: bool,
+    #[test]
+    fn bytes_delivery_any() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .self_describing(true)
+            .bytes_delivery(Delivery::Transient)
+            .build();
+
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
+        assert_eq!(deserializer.deliveries(), [Delivery::Transient]);
     }
 
     #[test]
-    fn deserialize_struct() {
-        let mut deserializer = Deserializer::builder([
-            Token::Struct {
-                name: "Struct",
-                len: 2,
-            },
-            Token::Field("foo"),
-            Token::U32(42),
-            Token::Field("bar"),
-            Token::Bool(false),
-            Token::StructEnd,
-        ])
-        .build();
+    fn bytes_delivery_identifier() {
+        let mut deserializer = Deserializer::builder([Token::Bytes(b"foo".to_vec())])
+            .bytes_delivery(Delivery::Owned)
+            .build();
 
-        assert_ok_eq!(
-            Struct::deserialize(&mut deserializer),
-            Struct {
-                foo: 42,
-                bar: false,
-            }
-        );
+        assert_ok!((&mut deserializer).deserialize_identifier(IgnoredAny));
+        assert_eq!(deserializer.deliveries(), [Delivery::Owned]);
     }
 
     #[test]
-    fn deserialize_struct_string_fields() {
+    fn bytes_delivery_independent_of_str_delivery() {
         let mut deserializer = Deserializer::builder([
-            Token::Struct {
-                name: "Struct",
-                len: 2,
-            },
+            Token::Tuple { len: 2 },
             Token::Str("foo".to_owned()),
-            Token::U32(42),
-            Token::Str("bar".to_owned()),
-            Token::Bool(false),
-            Token::StructEnd,
+            Token::Bytes(b"bar".to_vec()),
+            Token::TupleEnd,
         ])
+        .self_describing(true)
+        .str_delivery(Delivery::Owned)
+        .bytes_delivery(Delivery::Transient)
         .build();
 
-        assert_ok_eq!(
-            Struct::deserialize(&mut deserializer),
-            Struct {
-                foo: 42,
-                bar: false,
-            }
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
+        assert_eq!(
+            deserializer.deliveries(),
+            [Delivery::Owned, Delivery::Transient]
         );
     }
 
     #[test]
-    fn deserialize_struct_byte_fields() {
+    fn coverage_empty() {
+        static COVERAGE: Coverage = Coverage::new();
+
+        assert_eq!(COVERAGE.hit(), [] as [&str; 0]);
+        assert_eq!(COVERAGE.missed().len(), 58);
+    }
+
+    #[test]
+    fn coverage_aggregates_deserializers() {
+        static COVERAGE: Coverage = Coverage::new();
+
+        let mut first = Deserializer::builder([Token::Bool(true)])
+            .coverage(&COVERAGE)
+            .build();
+        let mut second = Deserializer::builder([Token::Bool(false)])
+            .coverage(&COVERAGE)
+            .build();
+        assert_ok!(bool::deserialize(&mut first));
+        assert_ok!(bool::deserialize(&mut second));
+
+        assert_eq!(COVERAGE.count("deserialize_bool"), 2);
+        assert_eq!(COVERAGE.count("visit_bool"), 2);
+        assert_eq!(COVERAGE.hit(), ["deserialize_bool", "visit_bool"]);
+    }
+
+    #[cfg(not(feature = "safe"))]
+    #[test]
+    fn coverage_visits() {
+        static COVERAGE: Coverage = Coverage::new();
+
         let mut deserializer = Deserializer::builder([
-            Token::Struct {
-                name: "Struct",
-                len: 2,
-            },
+            Token::Tuple { len: 2 },
             Token::Bytes(b"foo".to_vec()),
-            Token::U32(42),
-            Token::Bytes(b"bar".to_vec()),
-            Token::Bool(false),
-            Token::StructEnd,
+            Token::None,
+            Token::TupleEnd,
         ])
+        .self_describing(true)
+        .coverage(&COVERAGE)
         .build();
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
 
-        assert_ok_eq!(
-            Struct::deserialize(&mut deserializer),
-            Struct {
-                foo: 42,
-                bar: false,
-            }
+        assert_eq!(
+            COVERAGE.hit(),
+            [
+                "deserialize_ignored_any",
+                "visit_borrowed_bytes",
+                "visit_none",
+                "visit_seq"
+            ]
         );
     }
 
     #[test]
-    fn deserialize_struct_error_name() {
+    fn coverage_ignored_any_counts() {
+        static COVERAGE: Coverage = Coverage::new();
+
         let mut deserializer = Deserializer::builder([
-            Token::Struct {
-                name: "Not Struct",
-                len: 2,
-            },
-            Token::Field("foo"),
-            Token::U32(42),
-            Token::Field("bar"),
-            Token::Bool(false),
-            Token::StructEnd,
+            Token::Seq { len: Some(2) },
+            Token::U8(1),
+            Token::U8(2),
+            Token::SeqEnd,
         ])
+        .self_describing(true)
+        .coverage(&COVERAGE)
         .build();
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
 
-        assert_err_eq!(
-            Struct::deserialize(&mut deserializer),
-            Error::invalid_value(
-                (&mut CanonicalToken::Struct {
-                    name: "Not Struct",
-                    len: 2
-                })
-                    .into(),
-                &"struct Struct"
-            )
-        );
+        assert_eq!(COVERAGE.count("deserialize_ignored_any"), 3);
+        assert_eq!(COVERAGE.count("deserialize_any"), 0);
+        assert_eq!(COVERAGE.count("visit_seq"), 1);
+        assert_eq!(COVERAGE.count("visit_u8"), 2);
     }
 
     #[test]
-    fn deserialize_struct_error_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+    fn coverage_reset() {
+        static COVERAGE: Coverage = Coverage::new();
 
-        assert_err_eq!(
-            Struct::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"struct Struct")
-        );
+        let mut deserializer = Deserializer::builder([Token::Unit])
+            .coverage(&COVERAGE)
+            .build();
+        assert_ok!(<()>::deserialize(&mut deserializer));
+        COVERAGE.reset();
+
+        assert_eq!(COVERAGE.count("deserialize_unit"), 0);
+        assert_eq!(COVERAGE.count("visit_unit"), 0);
     }
 
-    #[derive(Debug, PartialEq)]
-    struct EmptyStruct;
+    #[test]
+    fn coverage_display() {
+        static COVERAGE: Coverage = Coverage::new();
 
-    impl<'de> Deserialize<'de> for EmptyStruct {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct EmptyStructVisitor;
+        let mut deserializer = Deserializer::builder([Token::U8(1)])
+            .coverage(&COVERAGE)
+            .build();
+        assert_ok!(u8::deserialize(&mut deserializer));
+        let display = format!("{COVERAGE}");
 
-            impl<'de> Visitor<'de> for EmptyStructVisitor {
-                type Value = EmptyStruct;
+        assert!(display.starts_with(
+            "deserializer coverage: 1 of 31 deserializer methods and 1 of 27 visitor methods \
+             called\n  hit:    deserialize_u8 (1)\n  hit:    visit_u8 (1)\n  missed: \
+             deserialize_any\n"
+        ));
+        assert!(display.ends_with("\n  missed: visit_enum"));
+    }
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("EmptyStruct")
-                }
+    #[test]
+    fn coverage_serialize() {
+        static COVERAGE: Coverage = Coverage::new();
 
-                fn visit_map<A>(self, _map: A) -> Result<Self::Value, A::Error>
-                where
-                    A: de::MapAccess<'de>,
-                {
-                    Ok(EmptyStruct)
-                }
+        let mut deserializer = Deserializer::builder([Token::Some, Token::U8(1)])
+            .coverage(&COVERAGE)
+            .build();
+        assert_ok!(Option::<u8>::deserialize(&mut deserializer));
+        let report = assert_ok!(serde_json::to_value(&COVERAGE));
+
+        assert_eq!(report["deserializer"]["deserialize_option"], 1);
+        assert_eq!(report["deserializer"]["deserialize_u8"], 1);
+        assert_eq!(report["deserializer"]["deserialize_any"], 0);
+        assert_eq!(report["visitor"]["visit_some"], 1);
+        assert_eq!(report["visitor"]["visit_none"], 0);
+        assert_eq!(assert_some!(report["deserializer"].as_object()).len(), 31);
+        assert_eq!(assert_some!(report["visitor"].as_object()).len(), 27);
+    }
+
+    #[test]
+    fn drive_visitor_single_token() {
+        struct BoolVisitor;
+
+        impl Visitor<'_> for BoolVisitor {
+            type Value = bool;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a bool")
             }
 
-            deserializer.deserialize_struct("EmptyStruct", &[], EmptyStructVisitor)
+            fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(v)
+            }
         }
+
+        assert_ok_eq!(drive_visitor([Token::Bool(true)], BoolVisitor), true);
     }
 
     #[test]
-    fn deserialize_struct_error_end_token_assertion_succeeds() {
-        let mut deserializer = Deserializer::builder([
-            Token::Struct {
-                name: "EmptyStruct",
-                len: 0,
-            },
-            Token::StructEnd,
-        ])
-        .build();
+    fn drive_visitor_token_group() {
+        struct SumVisitor;
 
-        assert_ok_eq!(EmptyStruct::deserialize(&mut deserializer), EmptyStruct,);
+        impl<'de> Visitor<'de> for SumVisitor {
+            type Value = u32;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a sequence of u32s")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut sum = 0;
+                while let Some(value) = seq.next_element::<u32>()? {
+                    sum += value;
+                }
+                Ok(sum)
+            }
+        }
+
+        assert_ok_eq!(
+            drive_visitor(
+                [
+                    Token::Seq { len: Some(3) },
+                    Token::U32(1),
+                    Token::U32(2),
+                    Token::U32(3),
+                    Token::SeqEnd
+                ],
+                SumVisitor
+            ),
+            6
+        );
     }
 
     #[test]
-    fn deserialize_struct_error_end_token_assertion_failed() {
-        let mut deserializer = Deserializer::builder([
-            Token::Struct {
-                name: "EmptyStruct",
-                len: 0,
-            },
-            Token::MapEnd,
-        ])
-        .build();
-
+    fn drive_visitor_error() {
         assert_err_eq!(
-            EmptyStruct::deserialize(&mut deserializer),
-            Error::ExpectedStructEnd,
+            drive_visitor([Token::Seq { len: None }], IgnoredAny),
+            Error::EndOfTokens
         );
     }
 
     #[test]
-    fn deserialize_struct_after_ended() {
-        #[derive(Debug, PartialEq)]
-        struct Struct;
+    #[allow(clippy::unnecessary_mut_passed)] // The trait is implemented on `&mut Deserializer`.
+    fn is_human_readable_default() {
+        let mut deserializer = Deserializer::builder([]).build();
 
-        impl<'de> Deserialize<'de> for Struct {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct StructVisitor;
+        assert!((&mut deserializer).is_human_readable());
+    }
 
-                impl<'de> Visitor<'de> for StructVisitor {
-                    type Value = Struct;
+    #[test]
+    #[allow(clippy::unnecessary_mut_passed)] // The trait is implemented on `&mut Deserializer`.
+    fn is_human_readable_true() {
+        let mut deserializer = Deserializer::builder([]).is_human_readable(true).build();
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("Struct")
-                    }
+        assert!((&mut deserializer).is_human_readable());
+    }
 
-                    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
-                    where
-                        A: de::MapAccess<'de>,
-                    {
-                        for _ in 0..2 {
-                            if map.next_key::<()>()?.is_some() {
-                                return Err(A::Error::custom(
-                                    "found element when no element was expected",
-                                ));
-                            }
-                        }
+    #[test]
+    #[allow(clippy::unnecessary_mut_passed)] // The trait is implemented on `&mut Deserializer`.
+    fn is_human_readable_false() {
+        let mut deserializer = Deserializer::builder([]).is_human_readable(false).build();
+
+        assert!(!(&mut deserializer).is_human_readable());
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum EnumVariant {
+        Unit,
+        Newtype,
+        Tuple,
+        Struct,
+    }
+
+    impl<'de> Deserialize<'de> for EnumVariant {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            struct EnumVariantVisitor;
+
+            impl Visitor<'_> for EnumVariantVisitor {
+                type Value = EnumVariant;
 
-                        Ok(Struct)
-                    }
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("EnumVariant")
                 }
 
-                deserializer.deserialize_struct("Struct", &[], StructVisitor)
+                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where
+                    E: de::Error,
+                {
+                    match v {
+                        "Unit" => Ok(EnumVariant::Unit),
+                        "Newtype" => Ok(EnumVariant::Newtype),
+                        "Tuple" => Ok(EnumVariant::Tuple),
+                        "Struct" => Ok(EnumVariant::Struct),
+                        _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+                    }
+                }
             }
-        }
-
-        let mut deserializer = Deserializer::builder([
-            Token::Struct {
-                name: "Struct",
-                len: 0,
-            },
-            Token::StructEnd,
-        ])
-        .build();
-
-        assert_ok_eq!(Struct::deserialize(&mut deserializer), Struct);
-    }
 
-    #[test]
-    fn deserialize_struct_from_seq() {
-        #[derive(Debug, Deserialize, PartialEq)]
-        struct Struct {
-            foo: bool,
-            bar: u32,
+            deserializer.deserialize_any(EnumVariantVisitor)
         }
-
-        let mut deserializer = Deserializer::builder([
-            Token::Seq { len: Some(2) },
-            Token::Bool(true),
-            Token::U32(42),
-            Token::SeqEnd,
-        ])
-        .build();
-
-        assert_ok_eq!(
-            Struct::deserialize(&mut deserializer),
-            Struct { foo: true, bar: 42 }
-        );
-    }
-
-    #[derive(Debug, Deserialize, PartialEq)]
-    enum Enum {
-        Unit,
-        Newtype(u32),
-        Tuple(u32, u32, u32),
-        Struct { foo: u32, bar: bool },
     }
 
     #[test]
-    fn deserialize_unit_variant() {
+    fn enum_deserializer_deserialize_any_unit() {
         let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "Enum",
+            name: "EnumVariant",
             variant_index: 0,
             variant: "Unit",
         }])
         .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert_ok_eq!(Enum::deserialize(&mut deserializer), Enum::Unit,);
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Unit
+        );
     }
 
     #[test]
-    fn deserialize_unit_variant_error_name() {
-        let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "Not Enum",
-            variant_index: 0,
-            variant: "Unit",
+    fn enum_deserializer_deserialize_any_newtype() {
+        let mut deserializer = Deserializer::builder([Token::NewtypeVariant {
+            name: "EnumVariant",
+            variant_index: 1,
+            variant: "Newtype",
         }])
         .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert_err_eq!(
-            Enum::deserialize(&mut deserializer),
-            Error::invalid_value(
-                (&mut CanonicalToken::UnitVariant {
-                    name: "Not Enum",
-                    variant_index: 0,
-                    variant: "Unit",
-                })
-                    .into(),
-                &"enum Enum"
-            )
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Newtype
         );
     }
 
     #[test]
-    fn deserialize_newtype_variant() {
+    fn enum_deserializer_deserialize_any_tuple() {
         let mut deserializer = Deserializer::builder([
-            Token::NewtypeVariant {
-                name: "Enum",
-                variant_index: 1,
-                variant: "Newtype",
+            Token::TupleVariant {
+                name: "EnumVariant",
+                variant_index: 2,
+                variant: "Tuple",
+                len: 0,
             },
-            Token::U32(42),
+            Token::TupleVariantEnd,
         ])
         .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert_ok_eq!(Enum::deserialize(&mut deserializer), Enum::Newtype(42),);
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Tuple
+        );
     }
 
     #[test]
-    fn deserialize_newtype_variant_error_name() {
+    fn enum_deserializer_deserialize_any_struct() {
         let mut deserializer = Deserializer::builder([
-            Token::NewtypeVariant {
-                name: "Not Enum",
-                variant_index: 1,
-                variant: "Newtype",
+            Token::StructVariant {
+                name: "EnumVariant",
+                variant_index: 3,
+                variant: "Struct",
+                len: 0,
             },
-            Token::U32(42),
+            Token::StructVariantEnd,
         ])
         .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert_err_eq!(
-            Enum::deserialize(&mut deserializer),
-            Error::invalid_value(
-                (&mut CanonicalToken::NewtypeVariant {
-                    name: "Not Enum",
-                    variant_index: 1,
-                    variant: "Newtype",
-                })
-                    .into(),
-                &"enum Enum"
-            )
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Struct
         );
     }
 
     #[test]
-    fn deserialize_tuple_variant() {
-        let mut deserializer = Deserializer::builder([
-            Token::TupleVariant {
-                name: "Enum",
-                variant_index: 2,
-                variant: "Tuple",
-                len: 3,
-            },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::TupleVariantEnd,
-        ])
-        .build();
+    #[should_panic(expected = "internal error: entered unreachable code")]
+    fn enum_deserializer_deserialize_any_invalid_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(false)]).build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert_ok_eq!(Enum::deserialize(&mut deserializer), Enum::Tuple(1, 2, 3),);
+        #[allow(unused_must_use)]
+        {
+            // This should panic, so it doesn't matter what value it returns.
+            EnumVariant::deserialize(enum_deserializer);
+        }
     }
 
     #[test]
-    fn deserialize_tuple_variant_error_name() {
-        let mut deserializer = Deserializer::builder([
-            Token::TupleVariant {
-                name: "Not Enum",
-                variant_index: 2,
-                variant: "Tuple",
-                len: 3,
-            },
-            Token::U32(1),
-            Token::U32(2),
-            Token::U32(3),
-            Token::TupleVariantEnd,
-        ])
+    fn enum_deserializer_deserialize_bool() {
+        #[derive(Debug)]
+        enum EnumVariant {}
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
+                }
+
+                deserializer.deserialize_bool(EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([]).build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
+
+        assert_err_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            Error::UnsupportedEnumDeserializerMethod
+        );
+    }
+
+    #[test]
+    fn enum_deserializer_deserialize_i8() {
+        #[derive(Debug, PartialEq)]
+        enum EnumVariant {
+            Foo,
+        }
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
+
+                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        if v == 0 {
+                            Ok(EnumVariant::Foo)
+                        } else {
+                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        }
+                    }
+                }
+
+                deserializer.deserialize_i8(EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "EnumVariant",
+            variant_index: 0,
+            variant: "Foo",
+        }])
         .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert_err_eq!(
-            Enum::deserialize(&mut deserializer),
-            Error::invalid_value(
-                (&mut CanonicalToken::TupleVariant {
-                    name: "Not Enum",
-                    variant_index: 2,
-                    variant: "Tuple",
-                    len: 3,
-                })
-                    .into(),
-                &"enum Enum"
-            )
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Foo,
         );
     }
 
     #[test]
-    fn deserialize_struct_variant() {
-        let mut deserializer = Deserializer::builder([
-            Token::StructVariant {
-                name: "Enum",
-                variant_index: 3,
-                variant: "Struct",
-                len: 2,
-            },
-            Token::Field("foo"),
-            Token::U32(42),
-            Token::Field("bar"),
-            Token::Bool(false),
-            Token::StructVariantEnd,
-        ])
+    fn enum_deserializer_deserialize_i16() {
+        #[derive(Debug, PartialEq)]
+        enum EnumVariant {
+            Foo,
+        }
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
+
+                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        if v == 0 {
+                            Ok(EnumVariant::Foo)
+                        } else {
+                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        }
+                    }
+                }
+
+                deserializer.deserialize_i16(EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "EnumVariant",
+            variant_index: 0,
+            variant: "Foo",
+        }])
         .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
         assert_ok_eq!(
-            Enum::deserialize(&mut deserializer),
-            Enum::Struct {
-                foo: 42,
-                bar: false,
-            },
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Foo,
         );
     }
 
     #[test]
-    fn deserialize_struct_variant_error_name() {
-        let mut deserializer = Deserializer::builder([
-            Token::StructVariant {
-                name: "Not Enum",
-                variant_index: 3,
-                variant: "Struct",
-                len: 2,
-            },
-            Token::Field("foo"),
-            Token::U32(42),
-            Token::Field("bar"),
-            Token::Bool(false),
-            Token::StructVariantEnd,
-        ])
+    fn enum_deserializer_deserialize_i32() {
+        #[derive(Debug, PartialEq)]
+        enum EnumVariant {
+            Foo,
+        }
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
+
+                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        if v == 0 {
+                            Ok(EnumVariant::Foo)
+                        } else {
+                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        }
+                    }
+                }
+
+                deserializer.deserialize_i32(EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "EnumVariant",
+            variant_index: 0,
+            variant: "Foo",
+        }])
         .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert_err_eq!(
-            Enum::deserialize(&mut deserializer),
-            Error::invalid_value(
-                (&mut CanonicalToken::StructVariant {
-                    name: "Not Enum",
-                    variant_index: 3,
-                    variant: "Struct",
-                    len: 2,
-                })
-                    .into(),
-                &"enum Enum"
-            )
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Foo,
         );
     }
 
     #[test]
-    fn deserialize_enum_error_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
-
-        assert_err_eq!(
-            Enum::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"enum Enum"),
-        );
-    }
+    fn enum_deserializer_deserialize_i64() {
+        #[derive(Debug, PartialEq)]
+        enum EnumVariant {
+            Foo,
+        }
 
-    #[derive(Debug, PartialEq)]
-    struct Identifier(String);
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
 
-    impl<'de> Deserialize<'de> for Identifier {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct IdentifierVisitor;
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
 
-            impl Visitor<'_> for IdentifierVisitor {
-                type Value = Identifier;
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("identifier")
+                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        if v == 0 {
+                            Ok(EnumVariant::Foo)
+                        } else {
+                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        }
+                    }
                 }
 
-                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-                where
-                    E: de::Error,
-                {
-                    Ok(Identifier(v.to_owned()))
-                }
+                deserializer.deserialize_i64(EnumVariantVisitor)
             }
-
-            deserializer.deserialize_identifier(IdentifierVisitor)
         }
+
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "EnumVariant",
+            variant_index: 0,
+            variant: "Foo",
+        }])
+        .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
+
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Foo,
+        );
     }
 
     #[test]
-    fn deserialize_identifier_str() {
-        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
+    fn enum_deserializer_deserialize_i128() {
+        #[derive(Debug, PartialEq)]
+        enum EnumVariant {
+            Foo,
+        }
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
+
+                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        if v == 0 {
+                            Ok(EnumVariant::Foo)
+                        } else {
+                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        }
+                    }
+                }
 
-        assert_ok_eq!(
-            Identifier::deserialize(&mut deserializer),
-            Identifier("foo".to_owned())
-        );
-    }
+                deserializer.deserialize_i128(EnumVariantVisitor)
+            }
+        }
 
-    #[test]
-    fn deserialize_identifier_field() {
-        let mut deserializer = Deserializer::builder([Token::Field("foo")]).build();
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "EnumVariant",
+            variant_index: 0,
+            variant: "Foo",
+        }])
+        .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
         assert_ok_eq!(
-            Identifier::deserialize(&mut deserializer),
-            Identifier("foo".to_owned())
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Foo,
         );
     }
 
     #[test]
-    fn deserialize_identifier_error_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(false)]).build();
+    fn enum_deserializer_deserialize_u8() {
+        #[derive(Debug, PartialEq)]
+        enum EnumVariant {
+            Foo,
+        }
 
-        assert_err_eq!(
-            Identifier::deserialize(&mut deserializer),
-            Error::invalid_type((&mut CanonicalToken::Bool(false)).into(), &"identifier")
-        );
-    }
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
 
-    #[test]
-    fn deserialize_ignored_any() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)])
-            .self_describing(true)
-            .build();
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
 
-        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
-    }
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
 
-    #[test]
-    fn deserialize_ignored_any_default_not_self_describing() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)]).build();
+                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        if v == 0 {
+                            Ok(EnumVariant::Foo)
+                        } else {
+                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        }
+                    }
+                }
 
-        assert_err_eq!(
-            IgnoredAny::deserialize(&mut deserializer),
-            Error::NotSelfDescribing
-        );
-    }
+                deserializer.deserialize_u8(EnumVariantVisitor)
+            }
+        }
 
-    #[test]
-    fn deserialize_ignored_any_not_self_describing() {
-        let mut deserializer = Deserializer::builder([Token::Bool(true)])
-            .self_describing(false)
-            .build();
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "EnumVariant",
+            variant_index: 0,
+            variant: "Foo",
+        }])
+        .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert_err_eq!(
-            IgnoredAny::deserialize(&mut deserializer),
-            Error::NotSelfDescribing
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Foo,
         );
     }
 
     #[test]
-    fn deserialize_skips_skipped_field() {
-        let mut deserializer =
-            Deserializer::builder([Token::SkippedField("foo"), Token::Bool(true)]).build();
-
-        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
-    }
-
-    #[test]
-    fn deserialize_from_unordered_tokens() {
-        let mut deserializer =
-            Deserializer::builder([Token::Unordered(&[&[Token::Bool(true)]])]).build();
+    fn enum_deserializer_deserialize_u16() {
+        #[derive(Debug, PartialEq)]
+        enum EnumVariant {
+            Foo,
+        }
 
-        assert_ok_eq!(bool::deserialize(&mut deserializer), true);
-    }
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
 
-    #[test]
-    fn is_human_readable_default() {
-        let mut deserializer = Deserializer::builder([]).build();
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
 
-        assert!((&mut deserializer).is_human_readable());
-    }
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
 
-    #[test]
-    fn is_human_readable_true() {
-        let mut deserializer = Deserializer::builder([]).is_human_readable(true).build();
+                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        if v == 0 {
+                            Ok(EnumVariant::Foo)
+                        } else {
+                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        }
+                    }
+                }
 
-        assert!((&mut deserializer).is_human_readable());
-    }
+                deserializer.deserialize_u16(EnumVariantVisitor)
+            }
+        }
 
-    #[test]
-    fn is_human_readable_false() {
-        let mut deserializer = Deserializer::builder([]).is_human_readable(false).build();
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "EnumVariant",
+            variant_index: 0,
+            variant: "Foo",
+        }])
+        .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        assert!(!(&mut deserializer).is_human_readable());
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Foo,
+        );
     }
 
     #[derive(Debug, PartialEq)]
-    enum EnumVariant {
+    enum U32EnumVariant {
         Unit,
         Newtype,
         Tuple,
         Struct,
     }
 
-    impl<'de> Deserialize<'de> for EnumVariant {
+    impl<'de> Deserialize<'de> for U32EnumVariant {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
             D: serde::Deserializer<'de>,
         {
-            struct EnumVariantVisitor;
+            struct U32EnumVariantVisitor;
 
-            impl Visitor<'_> for EnumVariantVisitor {
-                type Value = EnumVariant;
+            impl Visitor<'_> for U32EnumVariantVisitor {
+                type Value = U32EnumVariant;
 
                 fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("EnumVariant")
+                    formatter.write_str("U32EnumVariant")
                 }
 
-                fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
                 where
                     E: de::Error,
                 {
                     match v {
-                        "Unit" => Ok(EnumVariant::Unit),
-                        "Newtype" => Ok(EnumVariant::Newtype),
-                        "Tuple" => Ok(EnumVariant::Tuple),
-                        "Struct" => Ok(EnumVariant::Struct),
-                        _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+                        0 => Ok(U32EnumVariant::Unit),
+                        1 => Ok(U32EnumVariant::Newtype),
+                        2 => Ok(U32EnumVariant::Tuple),
+                        3 => Ok(U32EnumVariant::Struct),
+                        _ => Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self)),
                     }
                 }
             }
 
-            deserializer.deserialize_any(EnumVariantVisitor)
+            deserializer.deserialize_u32(U32EnumVariantVisitor)
         }
     }
 
     #[test]
-    fn enum_deserializer_deserialize_any_unit() {
+    fn enum_deserializer_deserialize_u32_unit() {
         let mut deserializer = Deserializer::builder([Token::UnitVariant {
             name: "EnumVariant",
             variant_index: 0,
@@ -4000,13 +11150,13 @@ mod tests {
         };
 
         assert_ok_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Unit
+            U32EnumVariant::deserialize(enum_deserializer),
+            U32EnumVariant::Unit,
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_any_newtype() {
+    fn enum_deserializer_deserialize_u32_newtype() {
         let mut deserializer = Deserializer::builder([Token::NewtypeVariant {
             name: "EnumVariant",
             variant_index: 1,
@@ -4018,13 +11168,13 @@ mod tests {
         };
 
         assert_ok_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Newtype
+            U32EnumVariant::deserialize(enum_deserializer),
+            U32EnumVariant::Newtype,
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_any_tuple() {
+    fn enum_deserializer_deserialize_u32_tuple() {
         let mut deserializer = Deserializer::builder([
             Token::TupleVariant {
                 name: "EnumVariant",
@@ -4040,13 +11190,13 @@ mod tests {
         };
 
         assert_ok_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Tuple
+            U32EnumVariant::deserialize(enum_deserializer),
+            U32EnumVariant::Tuple,
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_any_struct() {
+    fn enum_deserializer_deserialize_u32_struct() {
         let mut deserializer = Deserializer::builder([
             Token::StructVariant {
                 name: "EnumVariant",
@@ -4061,64 +11211,29 @@ mod tests {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Struct
-        );
-    }
-
-    #[test]
-    #[should_panic(expected = "internal error: entered unreachable code")]
-    fn enum_deserializer_deserialize_any_invalid_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(false)]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
-
-        #[allow(unused_must_use)]
-        {
-            // This should panic, so it doesn't matter what value it returns.
-            EnumVariant::deserialize(enum_deserializer);
-        }
-    }
-
-    #[test]
-    fn enum_deserializer_deserialize_bool() {
-        #[derive(Debug)]
-        enum EnumVariant {}
-
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
-
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
-
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
-
-                deserializer.deserialize_bool(EnumVariantVisitor)
-            }
-        }
+        assert_ok_eq!(
+            U32EnumVariant::deserialize(enum_deserializer),
+            U32EnumVariant::Struct,
+        );
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
+    #[test]
+    #[should_panic(expected = "internal error: entered unreachable code")]
+    fn enum_deserializer_deserialize_u32_invalid_token() {
+        let mut deserializer = Deserializer::builder([Token::Bool(false)]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
-        );
+        #[allow(unused_must_use)]
+        {
+            // This should panic, so it doesn't matter what value it returns.
+            U32EnumVariant::deserialize(enum_deserializer);
+        }
     }
 
     #[test]
-    fn enum_deserializer_deserialize_i8() {
+    fn enum_deserializer_deserialize_u64() {
         #[derive(Debug, PartialEq)]
         enum EnumVariant {
             Foo,
@@ -4150,7 +11265,7 @@ mod tests {
                     }
                 }
 
-                deserializer.deserialize_i8(EnumVariantVisitor)
+                deserializer.deserialize_u64(EnumVariantVisitor)
             }
         }
 
@@ -4171,7 +11286,7 @@ mod tests {
     }
 
     #[test]
-    fn enum_deserializer_deserialize_i16() {
+    fn enum_deserializer_deserialize_u128() {
         #[derive(Debug, PartialEq)]
         enum EnumVariant {
             Foo,
@@ -4203,7 +11318,7 @@ mod tests {
                     }
                 }
 
-                deserializer.deserialize_i16(EnumVariantVisitor)
+                deserializer.deserialize_u128(EnumVariantVisitor)
             }
         }
 
@@ -4224,11 +11339,9 @@ mod tests {
     }
 
     #[test]
-    fn enum_deserializer_deserialize_i32() {
-        #[derive(Debug, PartialEq)]
-        enum EnumVariant {
-            Foo,
-        }
+    fn enum_deserializer_deserialize_f32() {
+        #[derive(Debug)]
+        enum EnumVariant {}
 
         impl<'de> Deserialize<'de> for EnumVariant {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -4243,44 +11356,64 @@ mod tests {
                     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                         formatter.write_str("EnumVariant")
                     }
+                }
 
-                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
-                    where
-                        E: de::Error,
-                    {
-                        if v == 0 {
-                            Ok(EnumVariant::Foo)
-                        } else {
-                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
-                        }
+                deserializer.deserialize_f32(EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([]).build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
+
+        assert_err_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            Error::UnsupportedEnumDeserializerMethod
+        );
+    }
+
+    #[test]
+    fn enum_deserializer_deserialize_f64() {
+        #[derive(Debug)]
+        enum EnumVariant {}
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
                     }
                 }
 
-                deserializer.deserialize_i32(EnumVariantVisitor)
+                deserializer.deserialize_f64(EnumVariantVisitor)
             }
         }
 
-        let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "EnumVariant",
-            variant_index: 0,
-            variant: "Foo",
-        }])
-        .build();
+        let mut deserializer = Deserializer::builder([]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
+        assert_err_eq!(
             EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Foo,
+            Error::UnsupportedEnumDeserializerMethod
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_i64() {
+    fn enum_deserializer_deserialize_char() {
         #[derive(Debug, PartialEq)]
         enum EnumVariant {
             Foo,
+            Bar,
         }
 
         impl<'de> Deserialize<'de> for EnumVariant {
@@ -4297,26 +11430,26 @@ mod tests {
                         formatter.write_str("EnumVariant")
                     }
 
-                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    fn visit_char<E>(self, v: char) -> Result<Self::Value, E>
                     where
                         E: de::Error,
                     {
-                        if v == 0 {
-                            Ok(EnumVariant::Foo)
-                        } else {
-                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        match v {
+                            'F' => Ok(EnumVariant::Foo),
+                            'B' => Ok(EnumVariant::Bar),
+                            _ => Err(E::invalid_value(Unexpected::Char(v), &self)),
                         }
                     }
                 }
 
-                deserializer.deserialize_i64(EnumVariantVisitor)
+                deserializer.deserialize_char(EnumVariantVisitor)
             }
         }
 
         let mut deserializer = Deserializer::builder([Token::UnitVariant {
             name: "EnumVariant",
-            variant_index: 0,
-            variant: "Foo",
+            variant_index: 1,
+            variant: "Bar",
         }])
         .build();
         let enum_deserializer = EnumDeserializer {
@@ -4325,12 +11458,30 @@ mod tests {
 
         assert_ok_eq!(
             EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Foo,
+            EnumVariant::Bar
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_i128() {
+    fn enum_deserializer_deserialize_char_empty_variant() {
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "EnumVariant",
+            variant_index: 0,
+            variant: "",
+        }])
+        .build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
+
+        assert_err_eq!(
+            char::deserialize(enum_deserializer),
+            Error::invalid_value(Unexpected::Str(""), &"a character")
+        );
+    }
+
+    #[test]
+    fn enum_deserializer_deserialize_str() {
         #[derive(Debug, PartialEq)]
         enum EnumVariant {
             Foo,
@@ -4350,19 +11501,18 @@ mod tests {
                         formatter.write_str("EnumVariant")
                     }
 
-                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
                     where
                         E: de::Error,
                     {
-                        if v == 0 {
-                            Ok(EnumVariant::Foo)
-                        } else {
-                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        match v {
+                            "Foo" => Ok(EnumVariant::Foo),
+                            _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
                         }
                     }
                 }
 
-                deserializer.deserialize_i128(EnumVariantVisitor)
+                deserializer.deserialize_str(EnumVariantVisitor)
             }
         }
 
@@ -4383,7 +11533,7 @@ mod tests {
     }
 
     #[test]
-    fn enum_deserializer_deserialize_u8() {
+    fn enum_deserializer_deserialize_string() {
         #[derive(Debug, PartialEq)]
         enum EnumVariant {
             Foo,
@@ -4403,19 +11553,18 @@ mod tests {
                         formatter.write_str("EnumVariant")
                     }
 
-                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
+                    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
                     where
                         E: de::Error,
                     {
-                        if v == 0 {
-                            Ok(EnumVariant::Foo)
-                        } else {
-                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                        match v {
+                            "Foo" => Ok(EnumVariant::Foo),
+                            _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
                         }
                     }
                 }
 
-                deserializer.deserialize_u8(EnumVariantVisitor)
+                deserializer.deserialize_string(EnumVariantVisitor)
             }
         }
 
@@ -4436,11 +11585,9 @@ mod tests {
     }
 
     #[test]
-    fn enum_deserializer_deserialize_u16() {
-        #[derive(Debug, PartialEq)]
-        enum EnumVariant {
-            Foo,
-        }
+    fn enum_deserializer_deserialize_bytes() {
+        #[derive(Debug)]
+        enum EnumVariant {}
 
         impl<'de> Deserialize<'de> for EnumVariant {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -4455,180 +11602,202 @@ mod tests {
                     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                         formatter.write_str("EnumVariant")
                     }
-
-                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
-                    where
-                        E: de::Error,
-                    {
-                        if v == 0 {
-                            Ok(EnumVariant::Foo)
-                        } else {
-                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
-                        }
-                    }
                 }
 
-                deserializer.deserialize_u16(EnumVariantVisitor)
+                deserializer.deserialize_bytes(EnumVariantVisitor)
             }
         }
 
-        let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "EnumVariant",
-            variant_index: 0,
-            variant: "Foo",
-        }])
-        .build();
+        let mut deserializer = Deserializer::builder([]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
+        assert_err_eq!(
             EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Foo,
+            Error::UnsupportedEnumDeserializerMethod
         );
     }
 
-    #[derive(Debug, PartialEq)]
-    enum U32EnumVariant {
-        Unit,
-        Newtype,
-        Tuple,
-        Struct,
-    }
+    #[test]
+    fn enum_deserializer_deserialize_byte_buf() {
+        #[derive(Debug)]
+        enum EnumVariant {}
 
-    impl<'de> Deserialize<'de> for U32EnumVariant {
-        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where
-            D: serde::Deserializer<'de>,
-        {
-            struct U32EnumVariantVisitor;
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
 
-            impl Visitor<'_> for U32EnumVariantVisitor {
-                type Value = U32EnumVariant;
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
 
-                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                    formatter.write_str("U32EnumVariant")
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
                 }
 
-                fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
-                where
-                    E: de::Error,
-                {
-                    match v {
-                        0 => Ok(U32EnumVariant::Unit),
-                        1 => Ok(U32EnumVariant::Newtype),
-                        2 => Ok(U32EnumVariant::Tuple),
-                        3 => Ok(U32EnumVariant::Struct),
-                        _ => Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self)),
+                deserializer.deserialize_byte_buf(EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([]).build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
+
+        assert_err_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            Error::UnsupportedEnumDeserializerMethod
+        );
+    }
+
+    #[test]
+    fn enum_deserializer_deserialize_option() {
+        #[derive(Debug)]
+        enum EnumVariant {}
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
                     }
                 }
-            }
 
-            deserializer.deserialize_u32(U32EnumVariantVisitor)
+                deserializer.deserialize_option(EnumVariantVisitor)
+            }
         }
-    }
 
-    #[test]
-    fn enum_deserializer_deserialize_u32_unit() {
-        let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "EnumVariant",
-            variant_index: 0,
-            variant: "Unit",
-        }])
-        .build();
+        let mut deserializer = Deserializer::builder([]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
-            U32EnumVariant::deserialize(enum_deserializer),
-            U32EnumVariant::Unit,
+        assert_err_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            Error::UnsupportedEnumDeserializerMethod
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_u32_newtype() {
-        let mut deserializer = Deserializer::builder([Token::NewtypeVariant {
-            name: "EnumVariant",
-            variant_index: 1,
-            variant: "Newtype",
-        }])
-        .build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    fn enum_deserializer_deserialize_unit() {
+        #[derive(Debug)]
+        enum EnumVariant {}
 
-        assert_ok_eq!(
-            U32EnumVariant::deserialize(enum_deserializer),
-            U32EnumVariant::Newtype,
-        );
-    }
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
 
-    #[test]
-    fn enum_deserializer_deserialize_u32_tuple() {
-        let mut deserializer = Deserializer::builder([
-            Token::TupleVariant {
-                name: "EnumVariant",
-                variant_index: 2,
-                variant: "Tuple",
-                len: 0,
-            },
-            Token::TupleVariantEnd,
-        ])
-        .build();
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
+                }
+
+                deserializer.deserialize_unit(EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
-            U32EnumVariant::deserialize(enum_deserializer),
-            U32EnumVariant::Tuple,
+        assert_err_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            Error::UnsupportedEnumDeserializerMethod
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_u32_struct() {
-        let mut deserializer = Deserializer::builder([
-            Token::StructVariant {
-                name: "EnumVariant",
-                variant_index: 3,
-                variant: "Struct",
-                len: 0,
-            },
-            Token::StructVariantEnd,
-        ])
-        .build();
+    fn enum_deserializer_deserialize_unit_struct() {
+        #[derive(Debug)]
+        enum EnumVariant {}
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
+                }
+
+                deserializer.deserialize_unit_struct("EnumVariant", EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
-            U32EnumVariant::deserialize(enum_deserializer),
-            U32EnumVariant::Struct,
+        assert_err_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            Error::UnsupportedEnumDeserializerMethod
         );
     }
 
     #[test]
-    #[should_panic(expected = "internal error: entered unreachable code")]
-    fn enum_deserializer_deserialize_u32_invalid_token() {
-        let mut deserializer = Deserializer::builder([Token::Bool(false)]).build();
+    fn enum_deserializer_deserialize_newtype_struct() {
+        #[derive(Debug)]
+        enum EnumVariant {}
+
+        impl<'de> Deserialize<'de> for EnumVariant {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct EnumVariantVisitor;
+
+                impl Visitor<'_> for EnumVariantVisitor {
+                    type Value = EnumVariant;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str("EnumVariant")
+                    }
+                }
+
+                deserializer.deserialize_newtype_struct("EnumVariant", EnumVariantVisitor)
+            }
+        }
+
+        let mut deserializer = Deserializer::builder([]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        #[allow(unused_must_use)]
-        {
-            // This should panic, so it doesn't matter what value it returns.
-            U32EnumVariant::deserialize(enum_deserializer);
-        }
+        assert_err_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            Error::UnsupportedEnumDeserializerMethod
+        );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_u64() {
-        #[derive(Debug, PartialEq)]
-        enum EnumVariant {
-            Foo,
-        }
+    fn enum_deserializer_deserialize_seq() {
+        #[derive(Debug)]
+        enum EnumVariant {}
 
         impl<'de> Deserialize<'de> for EnumVariant {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -4643,45 +11812,27 @@ mod tests {
                     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                         formatter.write_str("EnumVariant")
                     }
-
-                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
-                    where
-                        E: de::Error,
-                    {
-                        if v == 0 {
-                            Ok(EnumVariant::Foo)
-                        } else {
-                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
-                        }
-                    }
                 }
 
-                deserializer.deserialize_u64(EnumVariantVisitor)
+                deserializer.deserialize_seq(EnumVariantVisitor)
             }
         }
 
-        let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "EnumVariant",
-            variant_index: 0,
-            variant: "Foo",
-        }])
-        .build();
+        let mut deserializer = Deserializer::builder([]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
+        assert_err_eq!(
             EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Foo,
+            Error::UnsupportedEnumDeserializerMethod
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_u128() {
-        #[derive(Debug, PartialEq)]
-        enum EnumVariant {
-            Foo,
-        }
+    fn enum_deserializer_deserialize_tuple() {
+        #[derive(Debug)]
+        enum EnumVariant {}
 
         impl<'de> Deserialize<'de> for EnumVariant {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -4696,41 +11847,25 @@ mod tests {
                     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                         formatter.write_str("EnumVariant")
                     }
-
-                    fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
-                    where
-                        E: de::Error,
-                    {
-                        if v == 0 {
-                            Ok(EnumVariant::Foo)
-                        } else {
-                            Err(E::invalid_value(Unexpected::Unsigned(v.into()), &self))
-                        }
-                    }
                 }
 
-                deserializer.deserialize_u128(EnumVariantVisitor)
+                deserializer.deserialize_tuple(0, EnumVariantVisitor)
             }
         }
 
-        let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "EnumVariant",
-            variant_index: 0,
-            variant: "Foo",
-        }])
-        .build();
+        let mut deserializer = Deserializer::builder([]).build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
+        assert_err_eq!(
             EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Foo,
+            Error::UnsupportedEnumDeserializerMethod
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_f32() {
+    fn enum_deserializer_deserialize_tuple_struct() {
         #[derive(Debug)]
         enum EnumVariant {}
 
@@ -4749,7 +11884,7 @@ mod tests {
                     }
                 }
 
-                deserializer.deserialize_f32(EnumVariantVisitor)
+                deserializer.deserialize_tuple_struct("EnumVariant", 0, EnumVariantVisitor)
             }
         }
 
@@ -4765,7 +11900,7 @@ mod tests {
     }
 
     #[test]
-    fn enum_deserializer_deserialize_f64() {
+    fn enum_deserializer_deserialize_map() {
         #[derive(Debug)]
         enum EnumVariant {}
 
@@ -4784,7 +11919,7 @@ mod tests {
                     }
                 }
 
-                deserializer.deserialize_f64(EnumVariantVisitor)
+                deserializer.deserialize_map(EnumVariantVisitor)
             }
         }
 
@@ -4800,7 +11935,7 @@ mod tests {
     }
 
     #[test]
-    fn enum_deserializer_deserialize_char() {
+    fn enum_deserializer_deserialize_struct() {
         #[derive(Debug)]
         enum EnumVariant {}
 
@@ -4819,7 +11954,7 @@ mod tests {
                     }
                 }
 
-                deserializer.deserialize_char(EnumVariantVisitor)
+                deserializer.deserialize_struct("EnumVariant", &[], EnumVariantVisitor)
             }
         }
 
@@ -4835,7 +11970,7 @@ mod tests {
     }
 
     #[test]
-    fn enum_deserializer_deserialize_str() {
+    fn enum_deserializer_deserialize_enum() {
         #[derive(Debug, PartialEq)]
         enum EnumVariant {
             Foo,
@@ -4866,7 +12001,7 @@ mod tests {
                     }
                 }
 
-                deserializer.deserialize_str(EnumVariantVisitor)
+                deserializer.deserialize_enum("EnumVariant", &[], EnumVariantVisitor)
             }
         }
 
@@ -4887,7 +12022,7 @@ mod tests {
     }
 
     #[test]
-    fn enum_deserializer_deserialize_string() {
+    fn enum_deserializer_deserialize_ignored_any() {
         #[derive(Debug, PartialEq)]
         enum EnumVariant {
             Foo,
@@ -4918,7 +12053,7 @@ mod tests {
                     }
                 }
 
-                deserializer.deserialize_string(EnumVariantVisitor)
+                deserializer.deserialize_ignored_any(EnumVariantVisitor)
             }
         }
 
@@ -4927,731 +12062,1199 @@ mod tests {
             variant_index: 0,
             variant: "Foo",
         }])
+        .self_describing(true)
         .build();
         let enum_deserializer = EnumDeserializer {
             deserializer: &mut deserializer,
         };
 
-        assert_ok_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Foo,
-        );
+        assert_ok_eq!(
+            EnumVariant::deserialize(enum_deserializer),
+            EnumVariant::Foo,
+        );
+    }
+
+    #[test]
+    fn enum_deserializer_is_human_readable_default() {
+        let mut deserializer = Deserializer::builder([]).build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
+
+        assert!(enum_deserializer.is_human_readable());
+    }
+
+    #[test]
+    fn enum_deserializer_is_human_readable_true() {
+        let mut deserializer = Deserializer::builder([]).is_human_readable(true).build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
+
+        assert!(enum_deserializer.is_human_readable());
     }
 
     #[test]
-    fn enum_deserializer_deserialize_bytes() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn enum_deserializer_is_human_readable_false() {
+        let mut deserializer = Deserializer::builder([]).is_human_readable(false).build();
+        let enum_deserializer = EnumDeserializer {
+            deserializer: &mut deserializer,
+        };
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+        assert!(!enum_deserializer.is_human_readable());
+    }
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+    #[test]
+    fn display_error_end_of_tokens() {
+        assert_eq!(format!("{}", Error::EndOfTokens), "end of tokens");
+    }
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+    #[test]
+    fn display_error_expected_seq_end() {
+        assert_eq!(
+            format!("{}", Error::ExpectedSeqEnd),
+            "expected token SeqEnd"
+        );
+    }
 
-                deserializer.deserialize_bytes(EnumVariantVisitor)
-            }
-        }
+    #[test]
+    fn display_error_expected_tuple_end() {
+        assert_eq!(
+            format!("{}", Error::ExpectedTupleEnd),
+            "expected token TupleEnd"
+        );
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[test]
+    fn display_error_expected_tuple_struct_end() {
+        assert_eq!(
+            format!("{}", Error::ExpectedTupleStructEnd),
+            "expected token TupleStructEnd"
+        );
+    }
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+    #[test]
+    fn display_error_expected_tuple_variant_end() {
+        assert_eq!(
+            format!("{}", Error::ExpectedTupleVariantEnd),
+            "expected token TupleVariantEnd"
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_byte_buf() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn display_error_expected_map_end() {
+        assert_eq!(
+            format!("{}", Error::ExpectedMapEnd),
+            "expected token MapEnd"
+        );
+    }
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+    #[test]
+    fn display_error_expected_struct_end() {
+        assert_eq!(
+            format!("{}", Error::ExpectedStructEnd),
+            "expected token StructEnd"
+        );
+    }
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+    #[test]
+    fn display_error_expected_struct_variant_end() {
+        assert_eq!(
+            format!("{}", Error::ExpectedStructVariantEnd),
+            "expected token StructVariantEnd"
+        );
+    }
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+    #[test]
+    fn display_error_unsupported_enum_deserializer_method() {
+        assert_eq!(
+            format!("{}", Error::UnsupportedEnumDeserializerMethod),
+            "use of unsupported enum deserializer method"
+        );
+    }
 
-                deserializer.deserialize_byte_buf(EnumVariantVisitor)
-            }
-        }
+    #[test]
+    fn display_error_not_self_describing() {
+        assert_eq!(format!("{}", Error::NotSelfDescribing), "attempted to deserialize as self-describing when deserializer is not set as self-describing");
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[test]
+    fn display_error_custom() {
+        assert_eq!(format!("{}", Error::custom("foo")), "foo");
+    }
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+    #[test]
+    fn display_error_invalid_type() {
+        assert_eq!(
+            format!(
+                "{}",
+                Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"foo")
+            ),
+            "invalid type: expected foo, found boolean `true`"
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_option() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn display_error_invalid_value() {
+        assert_eq!(
+            format!(
+                "{}",
+                Error::invalid_value((&mut CanonicalToken::Bool(true)).into(), &"foo")
+            ),
+            "invalid value: expected foo, found boolean `true`"
+        );
+    }
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+    #[test]
+    fn display_error_invalid_length() {
+        assert_eq!(
+            format!("{}", Error::invalid_length(42, &"foo")),
+            "invalid length 42, expected foo"
+        );
+    }
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+    #[test]
+    fn display_error_unknown_variant() {
+        assert_eq!(
+            format!("{}", Error::unknown_variant("foo", &["bar", "baz"])),
+            "unknown variant foo, expected one of [\"bar\", \"baz\"]"
+        );
+    }
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+    #[test]
+    fn display_error_unknown_field() {
+        assert_eq!(
+            format!("{}", Error::unknown_field("foo", &["bar", "baz"])),
+            "unknown field foo, expected one of [\"bar\", \"baz\"]"
+        );
+    }
 
-                deserializer.deserialize_option(EnumVariantVisitor)
-            }
-        }
+    #[test]
+    fn display_error_missing_field() {
+        assert_eq!(
+            format!("{}", Error::missing_field("foo")),
+            "missing field foo"
+        );
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[test]
+    fn display_error_duplicate_field() {
+        assert_eq!(
+            format!("{}", Error::duplicate_field("foo")),
+            "duplicate field foo"
+        );
+    }
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+    #[test]
+    fn display_error_duplicate_key() {
+        assert_eq!(
+            format!(
+                "{}",
+                Error::DuplicateKey(Tokens(vec![
+                    CanonicalToken::Tuple { len: 2 },
+                    CanonicalToken::U8(1),
+                    CanonicalToken::U8(2),
+                    CanonicalToken::TupleEnd,
+                ]))
+            ),
+            "duplicate key Tuple { len: 2 }, U8(1), U8(2), TupleEnd"
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_unit() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn display_error_injected() {
+        assert_eq!(
+            format!("{}", Error::Injected(2)),
+            "injected failure after 2 tokens"
+        );
+    }
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+    #[test]
+    fn display_error_trailing_tokens() {
+        assert_eq!(
+            format!("{}", Error::TrailingTokens(2)),
+            "2 trailing tokens after value"
+        );
+    }
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+    #[test]
+    fn error_expected_end_token_seq() {
+        assert_eq!(
+            Error::expected_end_token(crate::de::EndToken::Seq),
+            Error::ExpectedSeqEnd
+        );
+    }
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+    #[test]
+    fn error_expected_end_token_tuple() {
+        assert_eq!(
+            Error::expected_end_token(crate::de::EndToken::Tuple),
+            Error::ExpectedTupleEnd
+        );
+    }
 
-                deserializer.deserialize_unit(EnumVariantVisitor)
-            }
-        }
+    #[test]
+    fn error_expected_end_token_tuple_struct() {
+        assert_eq!(
+            Error::expected_end_token(crate::de::EndToken::TupleStruct),
+            Error::ExpectedTupleStructEnd
+        );
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[test]
+    fn error_expected_end_token_tuple_variant() {
+        assert_eq!(
+            Error::expected_end_token(crate::de::EndToken::TupleVariant),
+            Error::ExpectedTupleVariantEnd
+        );
+    }
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+    #[test]
+    fn error_expected_end_token_map() {
+        assert_eq!(
+            Error::expected_end_token(crate::de::EndToken::Map),
+            Error::ExpectedMapEnd
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_unit_struct() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn error_expected_end_token_struct() {
+        assert_eq!(
+            Error::expected_end_token(crate::de::EndToken::Struct),
+            Error::ExpectedStructEnd
+        );
+    }
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+    #[test]
+    fn error_expected_end_token_struct_variant() {
+        assert_eq!(
+            Error::expected_end_token(crate::de::EndToken::StructVariant),
+            Error::ExpectedStructVariantEnd
+        );
+    }
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+    #[test]
+    fn error_invalid_type_unexpected() {
+        let mut deserializer = Deserializer::builder([Token::Str("foo".to_owned())]).build();
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+        let error = assert_err!(u8::deserialize(&mut deserializer));
 
-                deserializer.deserialize_unit_struct("EnumVariant", EnumVariantVisitor)
-            }
-        }
+        assert_some_eq!(error.unexpected(), &UnexpectedValue::Str("foo".to_owned()));
+        assert_some_eq!(error.expected(), "u8");
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[test]
+    fn error_invalid_value_unexpected() {
+        let error = Error::invalid_value(Unexpected::Bytes(b"foo"), &"bar");
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
-        );
+        assert_some_eq!(error.unexpected(), &UnexpectedValue::Bytes(b"foo".to_vec()));
+        assert_some_eq!(error.expected(), "bar");
     }
 
     #[test]
-    fn enum_deserializer_deserialize_newtype_struct() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn error_invalid_length_expected() {
+        let error = Error::invalid_length(42, &"foo");
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+        assert_none!(error.unexpected());
+        assert_some_eq!(error.expected(), "foo");
+    }
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+    #[test]
+    fn error_without_unexpected_or_expected() {
+        let error = Error::missing_field("foo");
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+        assert_none!(error.unexpected());
+        assert_none!(error.expected());
+    }
 
-                deserializer.deserialize_newtype_struct("EnumVariant", EnumVariantVisitor)
-            }
+    #[test]
+    fn unexpected_value_round_trip() {
+        for unexpected in [
+            Unexpected::Bool(true),
+            Unexpected::Unsigned(42),
+            Unexpected::Signed(-42),
+            Unexpected::Float(1.5),
+            Unexpected::Char('a'),
+            Unexpected::Str("foo"),
+            Unexpected::Bytes(b"foo"),
+            Unexpected::Unit,
+            Unexpected::Option,
+            Unexpected::NewtypeStruct,
+            Unexpected::Seq,
+            Unexpected::Map,
+            Unexpected::Enum,
+            Unexpected::UnitVariant,
+            Unexpected::NewtypeVariant,
+            Unexpected::TupleVariant,
+            Unexpected::StructVariant,
+            Unexpected::Other("foo"),
+        ] {
+            assert_eq!(
+                UnexpectedValue::from(unexpected).as_unexpected(),
+                unexpected
+            );
         }
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
-
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+    #[test]
+    fn display_unexpected_value() {
+        assert_eq!(
+            format!("{}", UnexpectedValue::Char('a')),
+            format!("{}", Unexpected::Char('a'))
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_seq() {
-        #[derive(Debug)]
-        enum EnumVariant {}
-
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
-
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
-
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
+    fn pre_process_inject_unknown_field() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::Field("bar"),
+            Token::Bool(false),
+            Token::StructEnd,
+        ])
+        .pre_process(|tokens| {
+            let mut index = 0;
+            while index < tokens.len() {
+                if let Token::Struct { .. } = tokens[index] {
+                    tokens.insert(index + 1, Token::Field("unknown"));
+                    tokens.insert(index + 2, Token::Unit);
                 }
+                index += 1;
+            }
+        })
+        .self_describing(true)
+        .build();
 
-                deserializer.deserialize_seq(EnumVariantVisitor)
+        assert_ok_eq!(
+            Struct::deserialize(&mut deserializer),
+            Struct {
+                foo: 42,
+                bar: false,
             }
-        }
+        );
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[test]
+    fn pre_process_order() {
+        let mut deserializer = Deserializer::builder([Token::U8(1)])
+            .pre_process(|tokens| tokens.push(Token::U8(2)))
+            .pre_process(|tokens| {
+                tokens.remove(0);
+            })
+            .build();
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
-        );
+        assert_ok_eq!(u8::deserialize(&mut deserializer), 2);
     }
 
     #[test]
-    fn enum_deserializer_deserialize_tuple() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn pre_process_preserves_labels() {
+        let mut deserializer =
+            Deserializer::builder([Token::Unit, Token::labeled("value", Token::U8(1))])
+                .pre_process(|tokens| {
+                    tokens.remove(0);
+                })
+                .record_consumptions(true)
+                .build();
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+        assert_ok_eq!(u8::deserialize(&mut deserializer), 1);
+        assert_some_eq!(deserializer.consumptions()[0].label(), "value");
+    }
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+    #[test]
+    fn pre_process_applied_to_replaced_tokens() {
+        let mut builder = Deserializer::builder([Token::U8(1)]);
+        builder.pre_process(|tokens| tokens.reverse());
+        builder.tokens([Token::Bool(true), Token::U8(2)]);
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+        let mut first = builder.build();
+        let mut second = builder.build();
 
-                deserializer.deserialize_tuple(0, EnumVariantVisitor)
-            }
+        assert_ok_eq!(u8::deserialize(&mut first), 2);
+        assert_ok_eq!(u8::deserialize(&mut second), 2);
+    }
+
+    #[test]
+    fn recording_deserializer_struct() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point {
+            x: u32,
+            y: u32,
         }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+        let mut json = serde_json::Deserializer::from_str(r#"{"x": 1, "y": 2}"#);
+        let (value, tokens) =
+            assert_ok!(RecordingDeserializer::record(&mut json, Point::deserialize));
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+        assert_eq!(value, Point { x: 1, y: 2 });
+        assert_eq!(
+            tokens,
+            [
+                Token::Struct {
+                    name: "Point",
+                    len: 2,
+                },
+                Token::Field("x"),
+                Token::U64(1),
+                Token::Field("y"),
+                Token::U64(2),
+                Token::StructEnd,
+            ]
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_tuple_struct() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn recording_deserializer_ignored_field() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Point {
+            x: u32,
+        }
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+        let mut json = serde_json::Deserializer::from_str(r#"{"x": 1, "z": [true]}"#);
+        let (value, tokens) =
+            assert_ok!(RecordingDeserializer::record(&mut json, Point::deserialize));
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+        assert_eq!(value, Point { x: 1 });
+        assert_eq!(
+            tokens,
+            [
+                Token::Struct {
+                    name: "Point",
+                    len: 2,
+                },
+                Token::Field("x"),
+                Token::U64(1),
+                Token::Str("z".to_owned()),
+                Token::Unit,
+                Token::StructEnd,
+            ]
+        );
+    }
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+    #[test]
+    fn recording_deserializer_option() {
+        let mut json = serde_json::Deserializer::from_str(r#""foo""#);
+        let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            Option::<String>::deserialize
+        ));
 
-                deserializer.deserialize_tuple_struct("EnumVariant", 0, EnumVariantVisitor)
-            }
-        }
+        assert_some_eq!(value, "foo");
+        assert_eq!(tokens, [Token::Some, Token::Str("foo".to_owned())]);
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[test]
+    fn recording_deserializer_map() {
+        let mut json = serde_json::Deserializer::from_str(r#"{"a": -1}"#);
+        let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            HashMap::<String, i8>::deserialize
+        ));
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+        assert_eq!(value.len(), 1);
+        assert_eq!(
+            tokens,
+            [
+                Token::Map { len: Some(1) },
+                Token::Str("a".to_owned()),
+                Token::I64(-1),
+                Token::MapEnd,
+            ]
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_map() {
-        #[derive(Debug)]
-        enum EnumVariant {}
+    fn recording_deserializer_newtype_struct() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Meters(u8);
 
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+        let mut json = serde_json::Deserializer::from_str("42");
+        let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            Meters::deserialize
+        ));
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+        assert_eq!(value, Meters(42));
+        assert_eq!(
+            tokens,
+            [Token::NewtypeStruct { name: "Meters" }, Token::U64(42)]
+        );
+    }
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Recorded {
+        Unit,
+        Newtype(u8),
+        Tuple(u8, u8),
+        Struct { a: u8 },
+    }
 
-                deserializer.deserialize_map(EnumVariantVisitor)
-            }
-        }
+    #[test]
+    fn recording_deserializer_unit_variant() {
+        let mut json = serde_json::Deserializer::from_str(r#""Unit""#);
+        let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            Recorded::deserialize
+        ));
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+        assert_eq!(value, Recorded::Unit);
+        assert_eq!(
+            tokens,
+            [Token::UnitVariant {
+                name: "Recorded",
+                variant_index: 0,
+                variant: "Unit",
+            }]
+        );
+    }
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+    #[test]
+    fn recording_deserializer_newtype_variant() {
+        let mut json = serde_json::Deserializer::from_str(r#"{"Newtype": 1}"#);
+        let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            Recorded::deserialize
+        ));
+
+        assert_eq!(value, Recorded::Newtype(1));
+        assert_eq!(
+            tokens,
+            [
+                Token::NewtypeVariant {
+                    name: "Recorded",
+                    variant_index: 1,
+                    variant: "Newtype",
+                },
+                Token::U64(1),
+            ]
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_struct() {
-        #[derive(Debug)]
-        enum EnumVariant {}
-
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
-
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
-
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
-                }
+    fn recording_deserializer_tuple_variant() {
+        let mut json = serde_json::Deserializer::from_str(r#"{"Tuple": [1, 2]}"#);
+        let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            Recorded::deserialize
+        ));
 
-                deserializer.deserialize_struct("EnumVariant", &[], EnumVariantVisitor)
-            }
-        }
+        assert_eq!(value, Recorded::Tuple(1, 2));
+        assert_eq!(
+            tokens,
+            [
+                Token::TupleVariant {
+                    name: "Recorded",
+                    variant_index: 2,
+                    variant: "Tuple",
+                    len: 2,
+                },
+                Token::U64(1),
+                Token::U64(2),
+                Token::TupleVariantEnd,
+            ]
+        );
+    }
 
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[test]
+    fn recording_deserializer_struct_variant() {
+        let mut json = serde_json::Deserializer::from_str(r#"{"Struct": {"a": 1}}"#);
+        let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            Recorded::deserialize
+        ));
 
-        assert_err_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            Error::UnsupportedEnumDeserializerMethod
+        assert_eq!(value, Recorded::Struct { a: 1 });
+        assert_eq!(
+            tokens,
+            [
+                Token::StructVariant {
+                    name: "Recorded",
+                    variant_index: 3,
+                    variant: "Struct",
+                    len: 1,
+                },
+                Token::Field("a"),
+                Token::U64(1),
+                Token::StructVariantEnd,
+            ]
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_enum() {
-        #[derive(Debug, PartialEq)]
-        enum EnumVariant {
-            Foo,
-        }
-
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
+    fn recording_deserializer_tokens_deserialize() {
+        let mut json = serde_json::Deserializer::from_str(r#"{"a": [1, null], "b": []}"#);
+        let (value, tokens) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            HashMap::<String, Vec<Option<u64>>>::deserialize
+        ));
 
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+        let mut deserializer = Deserializer::builder(tokens).build();
+        assert_ok_eq!(
+            HashMap::<String, Vec<Option<u64>>>::deserialize(&mut deserializer),
+            value
+        );
+    }
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
+    #[test]
+    fn recording_deserializer_error() {
+        let mut json = serde_json::Deserializer::from_str("[1, true]");
 
-                    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-                    where
-                        E: de::Error,
-                    {
-                        match v {
-                            "Foo" => Ok(EnumVariant::Foo),
-                            _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
-                        }
-                    }
-                }
+        assert_err!(RecordingDeserializer::record(
+            &mut json,
+            Vec::<u8>::deserialize
+        ));
+    }
 
-                deserializer.deserialize_enum("EnumVariant", &[], EnumVariantVisitor)
-            }
-        }
+    #[test]
+    fn recording_deserializer_is_human_readable() {
+        let mut json = serde_json::Deserializer::from_str("null");
+        let (is_human_readable, _) = assert_ok!(RecordingDeserializer::record(
+            &mut json,
+            |deserializer| Ok::<_, serde_json::Error>(deserializer.is_human_readable())
+        ));
 
-        let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "EnumVariant",
-            variant_index: 0,
-            variant: "Foo",
-        }])
-        .build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+        assert!(is_human_readable);
+    }
 
+    #[test]
+    fn from_tokens_value() {
         assert_ok_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Foo,
+            from_tokens::<Vec<u8>, _>([
+                Token::Seq { len: Some(2) },
+                Token::U8(1),
+                Token::U8(2),
+                Token::SeqEnd,
+            ]),
+            vec![1, 2]
         );
     }
 
     #[test]
-    fn enum_deserializer_deserialize_ignored_any() {
-        #[derive(Debug, PartialEq)]
-        enum EnumVariant {
-            Foo,
-        }
-
-        impl<'de> Deserialize<'de> for EnumVariant {
-            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-            where
-                D: serde::Deserializer<'de>,
-            {
-                struct EnumVariantVisitor;
-
-                impl Visitor<'_> for EnumVariantVisitor {
-                    type Value = EnumVariant;
+    fn from_tokens_error() {
+        assert_err_eq!(
+            from_tokens::<bool, _>([Token::U8(1)]),
+            Error::invalid_type(Unexpected::Unsigned(1), &"a boolean")
+        );
+    }
 
-                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                        formatter.write_str("EnumVariant")
-                    }
+    #[test]
+    fn from_tokens_trailing_tokens() {
+        assert_err_eq!(
+            from_tokens::<bool, _>([Token::Bool(true), Token::Unit, Token::Unit]),
+            Error::TrailingTokens(2)
+        );
+    }
 
-                    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-                    where
-                        E: de::Error,
-                    {
-                        match v {
-                            "Foo" => Ok(EnumVariant::Foo),
-                            _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
-                        }
-                    }
-                }
+    #[test]
+    fn from_tokens_to_tokens() {
+        let tokens = assert_ok!(crate::to_tokens(&(1u8, 'a')));
 
-                deserializer.deserialize_ignored_any(EnumVariantVisitor)
-            }
-        }
+        assert_ok_eq!(from_tokens::<(u8, char), _>(tokens), (1, 'a'));
+    }
 
-        let mut deserializer = Deserializer::builder([Token::UnitVariant {
-            name: "EnumVariant",
-            variant_index: 0,
-            variant: "Foo",
-        }])
-        .self_describing(true)
+    #[test]
+    #[allow(clippy::unnecessary_mut_passed)] // The trait is implemented on `&mut Deserializer`.
+    fn profile_json() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 2 },
+            Token::Unit,
+            Token::Seq { len: Some(1) },
+            Token::U8(1),
+            Token::SeqEnd,
+            Token::TupleEnd,
+        ])
+        .profile(Profile::Json)
         .build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
 
+        assert!((&mut deserializer).is_human_readable());
         assert_ok_eq!(
-            EnumVariant::deserialize(enum_deserializer),
-            EnumVariant::Foo,
+            <(Option<u8>, ByteBuf)>::deserialize(&mut deserializer),
+            (None, ByteBuf::from(vec![1]))
         );
     }
 
     #[test]
-    fn enum_deserializer_is_human_readable_default() {
-        let mut deserializer = Deserializer::builder([]).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    fn profile_json_self_describing() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .profile(Profile::Json)
+            .build();
 
-        assert!(enum_deserializer.is_human_readable());
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
     }
 
     #[test]
-    fn enum_deserializer_is_human_readable_true() {
-        let mut deserializer = Deserializer::builder([]).is_human_readable(true).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    #[allow(clippy::unnecessary_mut_passed)] // The trait is implemented on `&mut Deserializer`.
+    fn profile_bincode() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .profile(Profile::Bincode)
+            .build();
 
-        assert!(enum_deserializer.is_human_readable());
+        assert!(!(&mut deserializer).is_human_readable());
+        assert_err_eq!(
+            IgnoredAny::deserialize(&mut deserializer),
+            Error::NotSelfDescribing
+        );
     }
 
     #[test]
-    fn enum_deserializer_is_human_readable_false() {
-        let mut deserializer = Deserializer::builder([]).is_human_readable(false).build();
-        let enum_deserializer = EnumDeserializer {
-            deserializer: &mut deserializer,
-        };
+    fn profile_overridden() {
+        let mut deserializer = Deserializer::builder([Token::Bool(true)])
+            .profile(Profile::Postcard)
+            .self_describing(true)
+            .build();
 
-        assert!(!enum_deserializer.is_human_readable());
+        assert_ok!(IgnoredAny::deserialize(&mut deserializer));
     }
 
-    #[test]
-    fn display_error_end_of_tokens() {
-        assert_eq!(format!("{}", Error::EndOfTokens), "end of tokens");
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum External {
+        Unit,
+        Newtype(u32),
+        Tuple(u32, bool),
+        Struct { foo: u32 },
     }
 
-    #[test]
-    fn display_error_expected_seq_end() {
-        assert_eq!(
-            format!("{}", Error::ExpectedSeqEnd),
-            "expected token SeqEnd"
-        );
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(tag = "type")]
+    enum Internal {
+        Unit,
+        Struct { foo: u32 },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(tag = "t", content = "c")]
+    enum Adjacent {
+        Unit,
+        Newtype(u32),
+        Tuple(u32, bool),
+        Struct { foo: u32 },
     }
 
     #[test]
-    fn display_error_expected_tuple_end() {
-        assert_eq!(
-            format!("{}", Error::ExpectedTupleEnd),
-            "expected token TupleEnd"
-        );
+    fn enum_tagging_internal_unit() {
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "Internal",
+            variant_index: 0,
+            variant: "Unit",
+        }])
+        .enum_tagging(EnumTagging::Internal { tag: "type" })
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(Internal::deserialize(&mut deserializer), Internal::Unit);
     }
 
     #[test]
-    fn display_error_expected_tuple_struct_end() {
-        assert_eq!(
-            format!("{}", Error::ExpectedTupleStructEnd),
-            "expected token TupleStructEnd"
+    fn enum_tagging_internal_struct() {
+        let mut deserializer = Deserializer::builder([
+            Token::StructVariant {
+                name: "Internal",
+                variant_index: 1,
+                variant: "Struct",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::StructVariantEnd,
+        ])
+        .enum_tagging(EnumTagging::Internal { tag: "type" })
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(
+            Internal::deserialize(&mut deserializer),
+            Internal::Struct { foo: 42 }
         );
     }
 
-    #[test]
-    fn display_error_expected_tuple_variant_end() {
-        assert_eq!(
-            format!("{}", Error::ExpectedTupleVariantEnd),
-            "expected token TupleVariantEnd"
+    #[test]
+    fn enum_tagging_adjacent() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 4 },
+            Token::UnitVariant {
+                name: "Adjacent",
+                variant_index: 0,
+                variant: "Unit",
+            },
+            Token::NewtypeVariant {
+                name: "Adjacent",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::U32(42),
+            Token::TupleVariant {
+                name: "Adjacent",
+                variant_index: 2,
+                variant: "Tuple",
+                len: 2,
+            },
+            Token::U32(42),
+            Token::Bool(true),
+            Token::TupleVariantEnd,
+            Token::StructVariant {
+                name: "Adjacent",
+                variant_index: 3,
+                variant: "Struct",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::StructVariantEnd,
+            Token::TupleEnd,
+        ])
+        .enum_tagging(EnumTagging::Adjacent {
+            tag: "t",
+            content: "c",
+        })
+        .self_describing(true)
+        .build();
+
+        assert_ok_eq!(
+            <(Adjacent, Adjacent, Adjacent, Adjacent)>::deserialize(&mut deserializer),
+            (
+                Adjacent::Unit,
+                Adjacent::Newtype(42),
+                Adjacent::Tuple(42, true),
+                Adjacent::Struct { foo: 42 }
+            )
         );
     }
 
     #[test]
-    fn display_error_expected_map_end() {
-        assert_eq!(
-            format!("{}", Error::ExpectedMapEnd),
-            "expected token MapEnd"
-        );
-    }
+    fn enum_tagging_external_unchanged() {
+        let mut deserializer = Deserializer::builder([
+            Token::NewtypeVariant {
+                name: "External",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::U32(42),
+        ])
+        .enum_tagging(EnumTagging::External)
+        .build();
 
-    #[test]
-    fn display_error_expected_struct_end() {
-        assert_eq!(
-            format!("{}", Error::ExpectedStructEnd),
-            "expected token StructEnd"
+        assert_ok_eq!(
+            External::deserialize(&mut deserializer),
+            External::Newtype(42)
         );
     }
 
     #[test]
-    fn display_error_expected_struct_variant_end() {
-        assert_eq!(
-            format!("{}", Error::ExpectedStructVariantEnd),
-            "expected token StructVariantEnd"
-        );
-    }
+    fn enum_tagging_preserves_labels() {
+        let mut deserializer = Deserializer::builder([
+            Token::StructVariant {
+                name: "Internal",
+                variant_index: 1,
+                variant: "Struct",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::labeled("foo", Token::U32(42)),
+            Token::StructVariantEnd,
+        ])
+        .enum_tagging(EnumTagging::Internal { tag: "type" })
+        .self_describing(true)
+        .record_consumptions(true)
+        .build();
 
-    #[test]
-    fn display_error_unsupported_enum_deserializer_method() {
-        assert_eq!(
-            format!("{}", Error::UnsupportedEnumDeserializerMethod),
-            "use of unsupported enum deserializer method"
+        assert_ok_eq!(
+            Internal::deserialize(&mut deserializer),
+            Internal::Struct { foo: 42 }
         );
+        assert!(deserializer
+            .consumptions()
+            .iter()
+            .any(|consumption| consumption.label() == Some("foo")));
     }
 
-    #[test]
-    fn display_error_not_self_describing() {
-        assert_eq!(format!("{}", Error::NotSelfDescribing), "attempted to deserialize as self-describing when deserializer is not set as self-describing");
-    }
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Wrapper(u32);
 
-    #[test]
-    fn display_error_custom() {
-        assert_eq!(format!("{}", Error::custom("foo")), "foo");
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(untagged)]
+    enum Buffered {
+        Struct { foo: u32, bar: Option<u32> },
+        External(External),
+        Wrapper(Wrapper, bool),
     }
 
     #[test]
-    fn display_error_invalid_type() {
-        assert_eq!(
-            format!(
-                "{}",
-                Error::invalid_type((&mut CanonicalToken::Bool(true)).into(), &"foo")
-            ),
-            "invalid type: expected foo, found boolean `true`"
+    fn content_compatible_struct_fields() {
+        let mut deserializer = Deserializer::builder([
+            Token::Struct {
+                name: "Struct",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::SkippedField("bar"),
+            Token::StructEnd,
+        ])
+        .self_describing(true)
+        .content_compatible(true)
+        .build();
+
+        assert_ok_eq!(
+            Buffered::deserialize(&mut deserializer),
+            Buffered::Struct { foo: 42, bar: None }
         );
     }
 
     #[test]
-    fn display_error_invalid_value() {
-        assert_eq!(
-            format!(
-                "{}",
-                Error::invalid_value((&mut CanonicalToken::Bool(true)).into(), &"foo")
-            ),
-            "invalid value: expected foo, found boolean `true`"
+    fn content_compatible_variants() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 4 },
+            Token::UnitVariant {
+                name: "External",
+                variant_index: 0,
+                variant: "Unit",
+            },
+            Token::NewtypeVariant {
+                name: "External",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::U32(42),
+            Token::TupleVariant {
+                name: "External",
+                variant_index: 2,
+                variant: "Tuple",
+                len: 2,
+            },
+            Token::U32(42),
+            Token::Bool(true),
+            Token::TupleVariantEnd,
+            Token::StructVariant {
+                name: "External",
+                variant_index: 3,
+                variant: "Struct",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::U32(42),
+            Token::StructVariantEnd,
+            Token::TupleEnd,
+        ])
+        .self_describing(true)
+        .content_compatible(true)
+        .build();
+
+        assert_ok_eq!(
+            <(Buffered, Buffered, Buffered, Buffered)>::deserialize(&mut deserializer),
+            (
+                Buffered::External(External::Unit),
+                Buffered::External(External::Newtype(42)),
+                Buffered::External(External::Tuple(42, true)),
+                Buffered::External(External::Struct { foo: 42 })
+            )
         );
     }
 
     #[test]
-    fn display_error_invalid_length() {
-        assert_eq!(
-            format!("{}", Error::invalid_length(42, &"foo")),
-            "invalid length 42, expected foo"
+    fn content_compatible_newtype_struct() {
+        let mut deserializer = Deserializer::builder([
+            Token::Tuple { len: 2 },
+            Token::NewtypeStruct { name: "Wrapper" },
+            Token::U32(42),
+            Token::Bool(true),
+            Token::TupleEnd,
+        ])
+        .self_describing(true)
+        .content_compatible(true)
+        .build();
+
+        assert_ok_eq!(
+            Buffered::deserialize(&mut deserializer),
+            Buffered::Wrapper(Wrapper(42), true)
         );
     }
 
     #[test]
-    fn display_error_unknown_variant() {
-        assert_eq!(
-            format!("{}", Error::unknown_variant("foo", &["bar", "baz"])),
-            "unknown variant foo, expected one of [\"bar\", \"baz\"]"
-        );
+    fn content_compatible_disabled() {
+        let mut deserializer = Deserializer::builder([Token::UnitVariant {
+            name: "External",
+            variant_index: 0,
+            variant: "Unit",
+        }])
+        .self_describing(true)
+        .build();
+
+        assert_err!(Buffered::deserialize(&mut deserializer));
     }
 
     #[test]
-    fn display_error_unknown_field() {
-        assert_eq!(
-            format!("{}", Error::unknown_field("foo", &["bar", "baz"])),
-            "unknown field foo, expected one of [\"bar\", \"baz\"]"
+    fn content_compatible_variant_map() {
+        let mut deserializer = Deserializer::builder([
+            Token::NewtypeVariant {
+                name: "External",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::U32(42),
+        ])
+        .self_describing(true)
+        .content_compatible(true)
+        .build();
+
+        assert_ok_eq!(
+            serde_json::Value::deserialize(&mut deserializer),
+            serde_json::json!({"Newtype": 42})
         );
     }
 
     #[test]
-    fn display_error_missing_field() {
-        assert_eq!(
-            format!("{}", Error::missing_field("foo")),
-            "missing field foo"
-        );
+    fn deserialize_enum_from_map_unit_str() {
+        let mut deserializer = Deserializer::builder([Token::Str("Unit".to_owned())])
+            .deserialize_enum_from_map(true)
+            .build();
+
+        assert_ok_eq!(External::deserialize(&mut deserializer), External::Unit);
     }
 
     #[test]
-    fn display_error_duplicate_field() {
-        assert_eq!(
-            format!("{}", Error::duplicate_field("foo")),
-            "duplicate field foo"
-        );
+    fn deserialize_enum_from_map_unit() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(1) },
+            Token::Str("Unit".to_owned()),
+            Token::Unit,
+            Token::MapEnd,
+        ])
+        .deserialize_enum_from_map(true)
+        .build();
+
+        assert_ok_eq!(External::deserialize(&mut deserializer), External::Unit);
     }
 
     #[test]
-    fn error_expected_end_token_seq() {
-        assert_eq!(
-            Error::expected_end_token(crate::de::EndToken::Seq),
-            Error::ExpectedSeqEnd
+    fn deserialize_enum_from_map_newtype() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(1) },
+            Token::Str("Newtype".to_owned()),
+            Token::U32(42),
+            Token::MapEnd,
+        ])
+        .deserialize_enum_from_map(true)
+        .build();
+
+        assert_ok_eq!(
+            External::deserialize(&mut deserializer),
+            External::Newtype(42)
         );
     }
 
     #[test]
-    fn error_expected_end_token_tuple() {
-        assert_eq!(
-            Error::expected_end_token(crate::de::EndToken::Tuple),
-            Error::ExpectedTupleEnd
+    fn deserialize_enum_from_map_tuple() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: None },
+            Token::Str("Tuple".to_owned()),
+            Token::Seq { len: Some(2) },
+            Token::U32(42),
+            Token::Bool(true),
+            Token::SeqEnd,
+            Token::MapEnd,
+        ])
+        .deserialize_enum_from_map(true)
+        .build();
+
+        assert_ok_eq!(
+            External::deserialize(&mut deserializer),
+            External::Tuple(42, true)
         );
     }
 
     #[test]
-    fn error_expected_end_token_tuple_struct() {
-        assert_eq!(
-            Error::expected_end_token(crate::de::EndToken::TupleStruct),
-            Error::ExpectedTupleStructEnd
+    fn deserialize_enum_from_map_struct() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(1) },
+            Token::Str("Struct".to_owned()),
+            Token::Map { len: Some(1) },
+            Token::Str("foo".to_owned()),
+            Token::U32(42),
+            Token::MapEnd,
+            Token::MapEnd,
+        ])
+        .deserialize_enum_from_map(true)
+        .build();
+
+        assert_ok_eq!(
+            External::deserialize(&mut deserializer),
+            External::Struct { foo: 42 }
         );
     }
 
     #[test]
-    fn error_expected_end_token_tuple_variant() {
-        assert_eq!(
-            Error::expected_end_token(crate::de::EndToken::TupleVariant),
-            Error::ExpectedTupleVariantEnd
+    fn deserialize_enum_from_map_variant_tokens() {
+        let mut deserializer = Deserializer::builder([
+            Token::NewtypeVariant {
+                name: "External",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::U32(42),
+        ])
+        .deserialize_enum_from_map(true)
+        .build();
+
+        assert_ok_eq!(
+            External::deserialize(&mut deserializer),
+            External::Newtype(42)
         );
     }
 
     #[test]
-    fn error_expected_end_token_map() {
-        assert_eq!(
-            Error::expected_end_token(crate::de::EndToken::Map),
+    fn deserialize_enum_from_map_expected_map_end() {
+        let mut deserializer = Deserializer::builder([
+            Token::Map { len: Some(2) },
+            Token::Str("Newtype".to_owned()),
+            Token::U32(42),
+            Token::Str("Unit".to_owned()),
+            Token::Unit,
+            Token::MapEnd,
+        ])
+        .deserialize_enum_from_map(true)
+        .build();
+
+        assert_err_eq!(
+            External::deserialize(&mut deserializer),
             Error::ExpectedMapEnd
         );
     }
 
     #[test]
-    fn error_expected_end_token_struct() {
-        assert_eq!(
-            Error::expected_end_token(crate::de::EndToken::Struct),
-            Error::ExpectedStructEnd
+    fn deserialize_enum_from_map_str_not_unit() {
+        let mut deserializer = Deserializer::builder([Token::Str("Newtype".to_owned())])
+            .deserialize_enum_from_map(true)
+            .build();
+
+        assert_err_eq!(
+            External::deserialize(&mut deserializer),
+            Error::invalid_type(Unexpected::UnitVariant, &"newtype variant")
         );
     }
 
     #[test]
-    fn error_expected_end_token_struct_variant() {
-        assert_eq!(
-            Error::expected_end_token(crate::de::EndToken::StructVariant),
-            Error::ExpectedStructVariantEnd
+    fn deserialize_enum_from_map_unknown_variant() {
+        let mut deserializer = Deserializer::builder([Token::Str("Unknown".to_owned())])
+            .deserialize_enum_from_map(true)
+            .build();
+
+        assert_err_eq!(
+            External::deserialize(&mut deserializer),
+            Error::UnknownVariant(
+                "Unknown".to_owned(),
+                &["Unit", "Newtype", "Tuple", "Struct"]
+            )
         );
     }
+
+    #[test]
+    fn deserialize_enum_from_map_disabled() {
+        let mut deserializer = Deserializer::builder([Token::Str("Unit".to_owned())]).build();
+
+        assert_err!(External::deserialize(&mut deserializer));
+    }
 }
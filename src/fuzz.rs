@@ -0,0 +1,826 @@
+//! Utilities for writing fuzz targets.
+//!
+//! This module provides ready-made harness functions for use within fuzz targets, such as those
+//! run by [`cargo-fuzz`]. Arbitrary input bytes are decoded into a structurally valid sequence of
+//! [`Token`]s using [`tokens()`], which can then be deserialized using [`deserialize()`] or
+//! round-tripped using [`roundtrip()`]. Any panic within a `Deserialize` or `Serialize`
+//! implementation is propagated, and is therefore reported by the fuzzer.
+//!
+//! This module is only available when the `fuzz` feature is enabled.
+//!
+//! # Example
+//! ``` rust,ignore
+//! #![no_main]
+//!
+//! use libfuzzer_sys::fuzz_target;
+//!
+//! fuzz_target!(|data: &[u8]| {
+//!     serde_assert::fuzz::roundtrip::<Vec<Option<u32>>>(data);
+//! });
+//! ```
+//!
+//! [`cargo-fuzz`]: https://github.com/rust-fuzz/cargo-fuzz
+
+use crate::{
+    de,
+    harness,
+    token::Tokens,
+    Harness,
+    Token,
+};
+use alloc::{
+    string::String,
+    vec::Vec,
+};
+use core::fmt::Debug;
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+
+/// The maximum nesting depth of decoded compound tokens.
+const MAX_DEPTH: usize = 32;
+
+/// The default names used for structs, enums, variants, and fields within decoded tokens.
+const NAMES: &[&str] = &["a", "b", "c", "d"];
+
+/// The number of token kinds that do not contain other values.
+const SCALAR_KINDS: u8 = 20;
+/// The number of token kinds in total.
+const KINDS: u8 = 30;
+
+/// Reads values from fuzzer input.
+///
+/// Once the input is exhausted, every read returns zeroed values.
+struct Input<'a> {
+    data: &'a [u8],
+    names: &'static [&'static str],
+}
+
+impl Input<'_> {
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn byte(&mut self) -> u8 {
+        let mut bytes = [0];
+        self.fill(&mut bytes);
+        bytes[0]
+    }
+
+    fn fill(&mut self, bytes: &mut [u8]) {
+        let len = bytes.len().min(self.data.len());
+        bytes[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+    }
+
+    fn take(&mut self) -> &[u8] {
+        let len = usize::from(self.byte() % 16).min(self.data.len());
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        taken
+    }
+
+    fn len(&mut self) -> usize {
+        usize::from(self.byte() % 8)
+    }
+
+    fn optional_len(&mut self) -> (Option<usize>, usize) {
+        let byte = self.byte();
+        let len = usize::from(byte % 8);
+        (if byte & 0x80 == 0 { Some(len) } else { None }, len)
+    }
+
+    fn name(&mut self) -> &'static str {
+        let byte = self.byte();
+        self.names
+            .get(usize::from(byte) % self.names.len().max(1))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn variant_index(&mut self) -> u32 {
+        u32::from(self.byte() % 4)
+    }
+}
+
+macro_rules! read_le {
+    ($input:ident, $ty:ty) => {{
+        let mut bytes = [0; core::mem::size_of::<$ty>()];
+        $input.fill(&mut bytes);
+        <$ty>::from_le_bytes(bytes)
+    }};
+}
+
+/// A compound value whose contents are still being decoded.
+struct Frame {
+    /// The number of values remaining.
+    remaining: usize,
+    /// Whether each value is preceded by a [`Token::Field`].
+    fields: bool,
+    /// The token ending the compound value.
+    end: Token,
+}
+
+/// Decodes arbitrary bytes into tokens representing exactly one value, using the given names.
+#[allow(clippy::too_many_lines)] // Every token kind must be handled.
+fn decode(data: &[u8], names: &'static [&'static str]) -> Tokens {
+    let mut input = Input { data, names };
+    let mut tokens = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut needs_value = true;
+    loop {
+        if needs_value {
+            needs_value = false;
+            if input.is_empty() {
+                tokens.push(Token::Unit);
+            } else {
+                let kinds = if stack.len() < MAX_DEPTH {
+                    KINDS
+                } else {
+                    SCALAR_KINDS
+                };
+                match input.byte() % kinds {
+                    0 => tokens.push(Token::Bool(input.byte() & 1 == 1)),
+                    1 => tokens.push(Token::I8(read_le!(input, i8))),
+                    2 => tokens.push(Token::I16(read_le!(input, i16))),
+                    3 => tokens.push(Token::I32(read_le!(input, i32))),
+                    4 => tokens.push(Token::I64(read_le!(input, i64))),
+                    5 => tokens.push(Token::I128(read_le!(input, i128))),
+                    6 => tokens.push(Token::U8(read_le!(input, u8))),
+                    7 => tokens.push(Token::U16(read_le!(input, u16))),
+                    8 => tokens.push(Token::U32(read_le!(input, u32))),
+                    9 => tokens.push(Token::U64(read_le!(input, u64))),
+                    10 => tokens.push(Token::U128(read_le!(input, u128))),
+                    11 => tokens.push(Token::F32(f32::from_bits(read_le!(input, u32)))),
+                    12 => tokens.push(Token::F64(f64::from_bits(read_le!(input, u64)))),
+                    13 => tokens.push(Token::Char(
+                        char::from_u32(read_le!(input, u32)).unwrap_or_default(),
+                    )),
+                    14 => tokens.push(Token::Str(String::from_utf8_lossy(input.take()).into())),
+                    15 => tokens.push(Token::Bytes(input.take().to_vec())),
+                    16 => tokens.push(Token::None),
+                    17 => tokens.push(Token::Unit),
+                    18 => tokens.push(Token::UnitStruct { name: input.name() }),
+                    19 => tokens.push(Token::UnitVariant {
+                        name: input.name(),
+                        variant_index: input.variant_index(),
+                        variant: input.name(),
+                    }),
+                    20 => {
+                        tokens.push(Token::Some);
+                        needs_value = true;
+                    }
+                    21 => {
+                        tokens.push(Token::NewtypeStruct { name: input.name() });
+                        needs_value = true;
+                    }
+                    22 => {
+                        tokens.push(Token::NewtypeVariant {
+                            name: input.name(),
+                            variant_index: input.variant_index(),
+                            variant: input.name(),
+                        });
+                        needs_value = true;
+                    }
+                    23 => {
+                        let (len, remaining) = input.optional_len();
+                        tokens.push(Token::Seq { len });
+                        stack.push(Frame {
+                            remaining,
+                            fields: false,
+                            end: Token::SeqEnd,
+                        });
+                    }
+                    24 => {
+                        let len = input.len();
+                        tokens.push(Token::Tuple { len });
+                        stack.push(Frame {
+                            remaining: len,
+                            fields: false,
+                            end: Token::TupleEnd,
+                        });
+                    }
+                    25 => {
+                        let len = input.len();
+                        tokens.push(Token::TupleStruct {
+                            name: input.name(),
+                            len,
+                        });
+                        stack.push(Frame {
+                            remaining: len,
+                            fields: false,
+                            end: Token::TupleStructEnd,
+                        });
+                    }
+                    26 => {
+                        let len = input.len();
+                        tokens.push(Token::TupleVariant {
+                            name: input.name(),
+                            variant_index: input.variant_index(),
+                            variant: input.name(),
+                            len,
+                        });
+                        stack.push(Frame {
+                            remaining: len,
+                            fields: false,
+                            end: Token::TupleVariantEnd,
+                        });
+                    }
+                    27 => {
+                        let (len, entries) = input.optional_len();
+                        tokens.push(Token::Map { len });
+                        stack.push(Frame {
+                            remaining: entries * 2,
+                            fields: false,
+                            end: Token::MapEnd,
+                        });
+                    }
+                    28 => {
+                        let len = input.len();
+                        tokens.push(Token::Struct {
+                            name: input.name(),
+                            len,
+                        });
+                        stack.push(Frame {
+                            remaining: len,
+                            fields: true,
+                            end: Token::StructEnd,
+                        });
+                    }
+                    _ => {
+                        let len = input.len();
+                        tokens.push(Token::StructVariant {
+                            name: input.name(),
+                            variant_index: input.variant_index(),
+                            variant: input.name(),
+                            len,
+                        });
+                        stack.push(Frame {
+                            remaining: len,
+                            fields: true,
+                            end: Token::StructVariantEnd,
+                        });
+                    }
+                }
+            }
+            if needs_value {
+                continue;
+            }
+        }
+        match stack.last_mut() {
+            None => break,
+            Some(frame) if frame.remaining == 0 => {
+                if let Some(frame) = stack.pop() {
+                    tokens.push(frame.end);
+                }
+            }
+            Some(frame) => {
+                frame.remaining -= 1;
+                if frame.fields {
+                    tokens.push(Token::Field(input.name()));
+                }
+                needs_value = true;
+            }
+        }
+    }
+    Tokens::canonicalize(tokens)
+}
+
+/// Decodes arbitrary bytes into [`Tokens`] representing exactly one value.
+///
+/// This is equivalent to `Fuzzer::new().tokens(data)`. See [`Fuzzer::tokens()`] for more details.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     fuzz,
+///     Token,
+/// };
+///
+/// assert_eq!(fuzz::tokens(&[]), [Token::Unit]);
+/// assert_eq!(fuzz::tokens(&[0, 1]), [Token::Bool(true)]);
+/// ```
+#[must_use]
+pub fn tokens(data: &[u8]) -> Tokens {
+    Fuzzer::new().tokens(data)
+}
+
+/// Deserializes a value of type `T` from tokens decoded from arbitrary bytes.
+///
+/// This is equivalent to `Fuzzer::new().deserialize(data)`. See [`Fuzzer::deserialize()`] for more
+/// details.
+///
+/// # Errors
+/// Returns a [`de::Error`] if the decoded tokens fail to deserialize as `T`.
+///
+/// # Example
+/// ``` rust
+/// use claims::{
+///     assert_err,
+///     assert_ok_eq,
+/// };
+/// use serde_assert::fuzz;
+///
+/// assert_ok_eq!(fuzz::deserialize::<bool>(&[0, 1]), true);
+/// assert_err!(fuzz::deserialize::<bool>(&[17]));
+/// ```
+pub fn deserialize<T>(data: &[u8]) -> Result<T, de::Error>
+where
+    T: DeserializeOwned,
+{
+    Fuzzer::new().deserialize(data)
+}
+
+/// Asserts that any value of type `T` deserialized from arbitrary bytes round-trips.
+///
+/// This is equivalent to `Fuzzer::new().roundtrip::<T>(data)`. See [`Fuzzer::roundtrip()`] for
+/// more details.
+///
+/// # Panics
+/// Panics if a deserialized value fails to serialize, if its serialized tokens fail to
+/// deserialize, or if the round-tripped value is not equal to the original value.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::fuzz;
+///
+/// fuzz::roundtrip::<Vec<Option<u32>>>(&[23, 2, 20, 8, 42, 0, 0, 0, 16]);
+/// ```
+pub fn roundtrip<T>(data: &[u8])
+where
+    T: Debug + DeserializeOwned + PartialEq + Serialize,
+{
+    Fuzzer::new().roundtrip::<T>(data);
+}
+
+/// A configurable harness for fuzz targets.
+///
+/// A `Fuzzer` decodes arbitrary bytes into tokens and runs deserialization and round-trips on
+/// them using a [`Harness`]. The names used within decoded tokens can be configured using
+/// [`Builder::names()`], allowing types whose `Deserialize` implementations check names, such as
+/// derived structs and enums, to be reached by the fuzzer.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde_assert::fuzz::Fuzzer;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Meters(u8);
+///
+/// let fuzzer = Fuzzer::builder().names(&["Meters"]).build();
+///
+/// assert_ok_eq!(fuzzer.deserialize::<Meters>(&[21, 0, 6, 42]), Meters(42));
+/// ```
+#[derive(Debug)]
+pub struct Fuzzer {
+    names: &'static [&'static str],
+    harness: Harness,
+}
+
+impl Fuzzer {
+    /// Creates a new `Fuzzer` using the default configuration.
+    ///
+    /// This is equivalent to `Fuzzer::builder().build()`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::fuzz::Fuzzer;
+    ///
+    /// let fuzzer = Fuzzer::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Returns a [`Builder`] for a [`Fuzzer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::fuzz::Fuzzer;
+    ///
+    /// let fuzzer = Fuzzer::builder().names(&["Foo", "bar"]).build();
+    /// ```
+    #[must_use]
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Decodes arbitrary bytes into [`Tokens`] representing exactly one value.
+    ///
+    /// Every possible input produces a structurally valid sequence of tokens: compound tokens are
+    /// always closed by their matching end tokens, and the lengths they declare match the number
+    /// of values they contain. Nesting is limited to a fixed depth, and the input being exhausted
+    /// ends all open values with `Unit` tokens.
+    ///
+    /// Names of structs, enums, variants, and fields are chosen from the configured
+    /// [`names()`], while the contents of `Str` and `Bytes` tokens are taken directly from the
+    /// input.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     fuzz::Fuzzer,
+    ///     Token,
+    /// };
+    ///
+    /// let fuzzer = Fuzzer::builder().names(&["Foo"]).build();
+    ///
+    /// assert_eq!(fuzzer.tokens(&[18, 0]), [Token::UnitStruct { name: "Foo" }]);
+    /// ```
+    ///
+    /// [`names()`]: Builder::names()
+    #[must_use]
+    pub fn tokens(&self, data: &[u8]) -> Tokens {
+        decode(data, self.names)
+    }
+
+    /// Deserializes a value of type `T` from tokens decoded from arbitrary bytes.
+    ///
+    /// The tokens are decoded using [`tokens()`] and deserialized using this `Fuzzer`'s
+    /// [`Harness`]. Most inputs will not represent a valid `T`, so errors are expected; a fuzz
+    /// target is instead looking for panics within the `Deserialize` implementation.
+    ///
+    /// # Errors
+    /// Returns a [`de::Error`] if the decoded tokens fail to deserialize as `T`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::fuzz::Fuzzer;
+    ///
+    /// let fuzzer = Fuzzer::new();
+    ///
+    /// assert_ok_eq!(fuzzer.deserialize::<Option<u8>>(&[20, 6, 42]), Some(42));
+    /// ```
+    ///
+    /// [`tokens()`]: Fuzzer::tokens()
+    pub fn deserialize<T>(&self, data: &[u8]) -> Result<T, de::Error>
+    where
+        T: DeserializeOwned,
+    {
+        self.harness.deserialize(self.tokens(data))
+    }
+
+    /// Asserts that any value of type `T` deserialized from arbitrary bytes round-trips.
+    ///
+    /// The tokens are decoded using [`tokens()`] and deserialized using this `Fuzzer`'s
+    /// [`Harness`]. If deserialization succeeds, the value is serialized and deserialized again,
+    /// and the result must equal the original value. Inputs that fail to deserialize are ignored.
+    ///
+    /// Note that values containing floating point numbers may not compare equal to themselves if
+    /// the input decodes to `NaN`.
+    ///
+    /// # Panics
+    /// Panics if a deserialized value fails to serialize, if its serialized tokens fail to
+    /// deserialize, or if the round-tripped value is not equal to the original value.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::fuzz::Fuzzer;
+    ///
+    /// let fuzzer = Fuzzer::builder()
+    ///     .harness(|harness| {
+    ///         harness.is_human_readable(false);
+    ///     })
+    ///     .build();
+    ///
+    /// fuzzer.roundtrip::<Option<u32>>(&[20, 8, 42, 0, 0, 0]);
+    /// ```
+    ///
+    /// [`tokens()`]: Fuzzer::tokens()
+    pub fn roundtrip<T>(&self, data: &[u8])
+    where
+        T: Debug + DeserializeOwned + PartialEq + Serialize,
+    {
+        let tokens = self.tokens(data);
+        if let Ok(value) = self.harness.deserialize::<T, _>(tokens.clone()) {
+            match self.harness.roundtrip(&value) {
+                Ok(roundtripped) => assert!(
+                    roundtripped == value,
+                    "value deserialized from {tokens:?} round-tripped to {roundtripped:?}, \
+                     expected {value:?}"
+                ),
+                Err(error) => {
+                    panic!("value deserialized from {tokens:?} failed to round-trip: {error}")
+                }
+            }
+        }
+    }
+}
+
+impl Default for Fuzzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A builder for a [`Fuzzer`].
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::fuzz::Fuzzer;
+///
+/// let fuzzer = Fuzzer::builder()
+///     .names(&["Foo", "bar", "baz"])
+///     .harness(|harness| {
+///         harness.is_human_readable(false);
+///     })
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct Builder {
+    names: &'static [&'static str],
+    harness: harness::Builder,
+}
+
+impl Builder {
+    /// Sets the names used for structs, enums, variants, and fields within decoded tokens.
+    ///
+    /// `Deserialize` implementations for structs and enums often check the names they are given,
+    /// so these should include the names of the types being fuzzed, along with their fields and
+    /// variants. If no names are given, empty names are used.
+    ///
+    /// If not set, a small default set of single-letter names is used.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::fuzz::Fuzzer;
+    ///
+    /// let fuzzer = Fuzzer::builder().names(&["Foo", "bar", "baz"]).build();
+    /// ```
+    pub fn names(&mut self, names: &'static [&'static str]) -> &mut Self {
+        self.names = names;
+        self
+    }
+
+    /// Configures the [`Harness`] used for deserialization and round-trips.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::fuzz::Fuzzer;
+    ///
+    /// let fuzzer = Fuzzer::builder()
+    ///     .harness(|harness| {
+    ///         harness.deserializer(|deserializer| {
+    ///             deserializer.self_describing(true);
+    ///         });
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn harness<F>(&mut self, configure: F) -> &mut Self
+    where
+        F: FnOnce(&mut harness::Builder),
+    {
+        configure(&mut self.harness);
+        self
+    }
+
+    /// Build a new [`Fuzzer`] using this `Builder`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::fuzz::Fuzzer;
+    ///
+    /// let fuzzer = Fuzzer::builder().names(&["Foo"]).build();
+    /// ```
+    #[must_use]
+    pub fn build(&self) -> Fuzzer {
+        Fuzzer {
+            names: self.names,
+            harness: self.harness.build(),
+        }
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            names: NAMES,
+            harness: Harness::builder(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        deserialize,
+        roundtrip,
+        tokens,
+        Fuzzer,
+        MAX_DEPTH,
+    };
+    use crate::{
+        Token,
+        Value,
+    };
+    use alloc::{
+        vec,
+        vec::Vec,
+    };
+    use claims::{
+        assert_err,
+        assert_ok,
+        assert_ok_eq,
+    };
+    use serde_derive::{
+        Deserialize,
+        Serialize,
+    };
+
+    /// Generates pseudo-random bytes from a seed.
+    fn pseudo_random(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state.to_le_bytes()[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tokens_empty() {
+        assert_eq!(tokens(&[]), [Token::Unit]);
+    }
+
+    #[test]
+    fn tokens_scalar() {
+        assert_eq!(tokens(&[8, 42, 0, 0, 0]), [Token::U32(42)]);
+    }
+
+    #[test]
+    fn tokens_truncated_scalar() {
+        assert_eq!(tokens(&[8, 42]), [Token::U32(42)]);
+    }
+
+    #[test]
+    fn tokens_str() {
+        assert_eq!(
+            tokens(&[14, 3, b'f', b'o', b'o']),
+            [Token::Str("foo".into())]
+        );
+    }
+
+    #[test]
+    fn tokens_some_exhausted() {
+        assert_eq!(tokens(&[20]), [Token::Some, Token::Unit]);
+    }
+
+    #[test]
+    fn tokens_seq_exhausted() {
+        assert_eq!(
+            tokens(&[23, 2]),
+            [
+                Token::Seq { len: Some(2) },
+                Token::Unit,
+                Token::Unit,
+                Token::SeqEnd
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_map_entries() {
+        assert_eq!(
+            tokens(&[27, 0x81, 6, 1, 17]),
+            [
+                Token::Map { len: None },
+                Token::U8(1),
+                Token::Unit,
+                Token::MapEnd
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_struct_fields() {
+        assert_eq!(
+            tokens(&[28, 1, 2, 3, 0, 1]),
+            [
+                Token::Struct { name: "c", len: 1 },
+                Token::Field("d"),
+                Token::Bool(true),
+                Token::StructEnd
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_max_depth() {
+        let tokens = tokens(&[23, 1].repeat(MAX_DEPTH * 2));
+
+        assert_eq!(
+            tokens
+                .clone()
+                .into_iter()
+                .filter(|token| matches!(token, Token::Seq { .. }))
+                .count(),
+            MAX_DEPTH
+        );
+        assert_ok!(Value::from_tokens(tokens));
+    }
+
+    #[test]
+    fn tokens_always_single_value() {
+        for seed in 0..1000 {
+            let data = pseudo_random(seed, 64);
+
+            assert_ok!(Value::from_tokens(tokens(&data)), "input: {:?}", data);
+        }
+    }
+
+    #[test]
+    fn tokens_names() {
+        assert_eq!(
+            Fuzzer::builder()
+                .names(&["Foo", "bar"])
+                .build()
+                .tokens(&[19, 0, 2, 1]),
+            [Token::UnitVariant {
+                name: "Foo",
+                variant_index: 2,
+                variant: "bar"
+            }]
+        );
+    }
+
+    #[test]
+    fn tokens_no_names() {
+        assert_eq!(
+            Fuzzer::builder().names(&[]).build().tokens(&[18, 3]),
+            [Token::UnitStruct { name: "" }]
+        );
+    }
+
+    #[test]
+    fn deserialize_ok() {
+        assert_ok_eq!(
+            deserialize::<Vec<Option<u32>>>(&[23, 2, 20, 8, 42, 0, 0, 0, 16]),
+            vec![Some(42), None]
+        );
+    }
+
+    #[test]
+    fn deserialize_err() {
+        assert_err!(deserialize::<u32>(&[0, 1]));
+    }
+
+    #[test]
+    fn roundtrip_ok() {
+        roundtrip::<Vec<Option<u32>>>(&[23, 2, 20, 8, 42, 0, 0, 0, 16]);
+    }
+
+    #[test]
+    fn roundtrip_ignores_errors() {
+        roundtrip::<u32>(&[0, 1]);
+    }
+
+    #[test]
+    fn deserialize_harness() {
+        assert_ok_eq!(
+            Fuzzer::builder()
+                .harness(|harness| {
+                    harness.deserializer(|deserializer| {
+                        deserializer.deserialize_bool_from_int(true);
+                    });
+                })
+                .build()
+                .deserialize::<bool>(&[6, 1]),
+            true
+        );
+    }
+
+    #[test]
+    fn roundtrip_many() {
+        for seed in 0..1000 {
+            roundtrip::<(Option<u8>, Vec<bool>, ())>(&pseudo_random(seed, 32));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "round-tripped to Lossy(0), expected Lossy(1)")]
+    fn roundtrip_mismatch() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Lossy(#[serde(serialize_with = "zero")] u8);
+
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        fn zero<S>(_value: &u8, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            serializer.serialize_u8(0)
+        }
+
+        Fuzzer::builder()
+            .names(&["Lossy"])
+            .build()
+            .roundtrip::<Lossy>(&[21, 0, 6, 1]);
+    }
+}
@@ -0,0 +1,814 @@
+//! A shared configuration for serializing and deserializing.
+//!
+//! This module provides a [`Harness`] type, which holds a single configuration for both a
+//! [`Serializer`] and a [`Deserializer`]. A `Harness` can create matching `Serializer`s and
+//! `Deserializer`s, and can run serialization, deserialization, and round-trips directly. This
+//! avoids having to keep two separate builders in sync by hand.
+//!
+//! # Example
+//! ``` rust
+//! use claims::assert_ok_eq;
+//! use serde_assert::{
+//!     Harness,
+//!     Token,
+//! };
+//!
+//! let harness = Harness::builder().is_human_readable(false).build();
+//!
+//! assert_ok_eq!(harness.serialize(&true), [Token::Bool(true)]);
+//! assert_ok_eq!(harness.deserialize::<bool, _>([Token::Bool(true)]), true);
+//! assert_ok_eq!(harness.roundtrip(&true), true);
+//! ```
+
+use crate::{
+    de,
+    profile::Profile,
+    ser,
+    token::Tokens,
+    Deserializer,
+    Serializer,
+    Token,
+};
+use alloc::vec::Vec;
+use core::{
+    fmt,
+    fmt::{
+        Debug,
+        Display,
+    },
+};
+use serde::{
+    de::DeserializeOwned,
+    Serialize,
+};
+
+/// A shared configuration for a [`Serializer`] and a [`Deserializer`].
+///
+/// A `Harness` is constructed using a [`Builder`], which configures both the `Serializer` and the
+/// `Deserializer` at once. Options shared by both, such as [`is_human_readable()`], are kept
+/// consistent between them.
+///
+/// # Example
+/// ``` rust
+/// use claims::{
+///     assert_ok,
+///     assert_ok_eq,
+/// };
+/// use serde::{
+///     Deserialize,
+///     Serialize,
+/// };
+/// use serde_assert::Harness;
+///
+/// let harness = Harness::builder().is_human_readable(false).build();
+///
+/// let tokens = assert_ok!(42u32.serialize(&harness.serializer()));
+/// let mut deserializer = harness.deserializer(tokens);
+///
+/// assert_ok_eq!(u32::deserialize(&mut deserializer), 42);
+/// ```
+///
+/// [`is_human_readable()`]: Builder::is_human_readable()
+#[derive(Debug)]
+pub struct Harness {
+    serializer: ser::Builder,
+    deserializer: de::Builder,
+}
+
+impl Harness {
+    /// Creates a new `Harness` using the default configuration.
+    ///
+    /// This is equivalent to `Harness::builder().build()`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Harness;
+    ///
+    /// let harness = Harness::new();
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Returns a [`Builder`] for a [`Harness`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Harness;
+    ///
+    /// let harness = Harness::builder().is_human_readable(false).build();
+    /// ```
+    #[must_use]
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+
+    /// Creates a new [`Serializer`] using this `Harness`'s configuration.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Harness,
+    ///     Token,
+    /// };
+    ///
+    /// let harness = Harness::new();
+    ///
+    /// assert_ok_eq!(true.serialize(&harness.serializer()), [Token::Bool(true)]);
+    /// ```
+    #[must_use]
+    pub fn serializer(&self) -> Serializer {
+        self.serializer.clone().build()
+    }
+
+    /// Creates a new [`Deserializer`] for the given tokens using this `Harness`'s configuration.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Harness,
+    ///     Token,
+    /// };
+    ///
+    /// let harness = Harness::new();
+    /// let mut deserializer = harness.deserializer([Token::Bool(true)]);
+    ///
+    /// assert_ok_eq!(bool::deserialize(&mut deserializer), true);
+    /// ```
+    pub fn deserializer<'a, T>(&self, tokens: T) -> Deserializer<'a>
+    where
+        T: IntoIterator<Item = Token>,
+    {
+        self.deserializer.clone().tokens(tokens).build()
+    }
+
+    /// Serializes a value using this `Harness`'s configuration.
+    ///
+    /// # Errors
+    /// Returns a [`ser::Error`] if the value fails to serialize.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::{
+    ///     Harness,
+    ///     Token,
+    /// };
+    ///
+    /// let harness = Harness::new();
+    ///
+    /// assert_ok_eq!(harness.serialize(&Some(42u8)), [Token::Some, Token::U8(42)]);
+    /// ```
+    pub fn serialize<T>(&self, value: &T) -> Result<Tokens, ser::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(&self.serializer())
+    }
+
+    /// Deserializes a value from the given tokens using this `Harness`'s configuration.
+    ///
+    /// # Errors
+    /// Returns a [`de::Error`] if the tokens fail to deserialize as `T`, or if tokens remain after
+    /// the value is deserialized and trailing tokens are not allowed.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::{
+    ///     Harness,
+    ///     Token,
+    /// };
+    ///
+    /// let harness = Harness::new();
+    ///
+    /// assert_ok_eq!(
+    ///     harness.deserialize::<Option<u8>, _>([Token::Some, Token::U8(42)]),
+    ///     Some(42)
+    /// );
+    /// ```
+    pub fn deserialize<T, I>(&self, tokens: I) -> Result<T, de::Error>
+    where
+        T: DeserializeOwned,
+        I: IntoIterator<Item = Token>,
+    {
+        let mut deserializer = self.deserializer(tokens);
+        let value = T::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    /// Serializes a value and deserializes the resulting tokens using this `Harness`'s
+    /// configuration.
+    ///
+    /// The deserialized value is returned, allowing it to be compared against the original value.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] if the value fails to serialize or the resulting tokens fail to
+    /// deserialize.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::Harness;
+    ///
+    /// let harness = Harness::new();
+    ///
+    /// assert_ok_eq!(harness.roundtrip(&vec![1u32, 2, 3]), vec![1, 2, 3]);
+    /// ```
+    pub fn roundtrip<T>(&self, value: &T) -> Result<T, Error>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let tokens = self.serialize(value).map_err(Error::Ser)?;
+        self.deserialize(tokens).map_err(Error::De)
+    }
+
+    /// Asserts that a value round-trips using this `Harness`'s configuration.
+    ///
+    /// The value is serialized and the resulting tokens are deserialized, as in [`roundtrip()`].
+    /// The deserialized value must then be equal to the original value. Since the `Serializer` and
+    /// `Deserializer` are configured independently, this can also check that a value round-trips
+    /// between asymmetric configurations.
+    ///
+    /// # Panics
+    /// Panics if the value fails to serialize, if the serialized tokens fail to deserialize, or if
+    /// the deserialized value is not equal to the original value. The panic message includes the
+    /// serialized tokens.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Harness;
+    ///
+    /// let harness = Harness::builder()
+    ///     .deserializer(|deserializer| {
+    ///         deserializer.zero_copy(false);
+    ///     })
+    ///     .build();
+    ///
+    /// harness.assert_roundtrip(&String::from("foo"));
+    /// ```
+    ///
+    /// [`roundtrip()`]: Harness::roundtrip()
+    pub fn assert_roundtrip<T>(&self, value: &T)
+    where
+        T: Debug + DeserializeOwned + PartialEq + Serialize,
+    {
+        let tokens = match self.serialize(value) {
+            Ok(tokens) => tokens,
+            Err(error) => panic!("{value:?} failed to serialize: {error}"),
+        };
+        match self.deserialize::<T, _>(tokens.clone()) {
+            Ok(roundtripped) => assert!(
+                roundtripped == *value,
+                "{value:?} round-tripped through {tokens:?} to {roundtripped:?}"
+            ),
+            Err(error) => panic!("{value:?} failed to deserialize from {tokens:?}: {error}"),
+        }
+    }
+
+    /// Runs a test once in each of the human-readable and compact modes.
+    ///
+    /// The test is given a `Harness` with this `Harness`'s configuration, except with
+    /// [`is_human_readable()`] set to `true` on the first run and `false` on the second. This
+    /// ensures both branches of implementations checking [`is_human_readable()`] are tested.
+    ///
+    /// The mode of the current run can be checked through the `Harness`'s [`Serializer`] or
+    /// [`Deserializer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Harness;
+    /// use std::net::Ipv4Addr;
+    ///
+    /// // `Ipv4Addr` is serialized as a string when human-readable, and as a tuple otherwise.
+    /// Harness::new().for_each_readability(|harness| {
+    ///     harness.assert_roundtrip(&Ipv4Addr::LOCALHOST);
+    /// });
+    /// ```
+    ///
+    /// [`is_human_readable()`]: Builder::is_human_readable()
+    pub fn for_each_readability<F>(&self, mut test: F)
+    where
+        F: FnMut(&Harness),
+    {
+        for is_human_readable in [true, false] {
+            let mut serializer = self.serializer.clone();
+            serializer.is_human_readable(is_human_readable);
+            let mut deserializer = self.deserializer.clone();
+            deserializer.is_human_readable(is_human_readable);
+
+            test(&Harness {
+                serializer,
+                deserializer,
+            });
+        }
+    }
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A builder for a [`Harness`].
+///
+/// Construction of a `Harness` follows the builder pattern. Configuration options shared by the
+/// [`Serializer`] and [`Deserializer`] can be set directly on the `Builder`, while options specific
+/// to one of them can be set using [`serializer()`] and [`deserializer()`]. The actual `Harness` is
+/// then constructed by calling [`build()`].
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     ser::SerializeStructAs,
+///     Harness,
+/// };
+///
+/// let harness = Harness::builder()
+///     .is_human_readable(false)
+///     .serializer(|serializer| {
+///         serializer.serialize_struct_as(SerializeStructAs::Seq);
+///     })
+///     .deserializer(|deserializer| {
+///         deserializer.self_describing(true);
+///     })
+///     .build();
+/// ```
+///
+/// [`build()`]: Builder::build()
+/// [`deserializer()`]: Builder::deserializer()
+/// [`serializer()`]: Builder::serializer()
+#[derive(Debug)]
+pub struct Builder {
+    serializer: ser::Builder,
+    deserializer: de::Builder,
+}
+
+impl Builder {
+    /// Determines whether the serializer and deserializer will use a readable format or a compact
+    /// format.
+    ///
+    /// This sets both [`ser::Builder::is_human_readable()`] and
+    /// [`de::Builder::is_human_readable()`].
+    ///
+    /// If not set, the default value is `true`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Harness;
+    ///
+    /// let harness = Harness::builder().is_human_readable(false).build();
+    /// ```
+    pub fn is_human_readable(&mut self, is_human_readable: bool) -> &mut Self {
+        self.serializer.is_human_readable(is_human_readable);
+        self.deserializer.is_human_readable(is_human_readable);
+        self
+    }
+
+    /// Applies a [`Profile`] approximating a commonly targeted format.
+    ///
+    /// This sets both [`ser::Builder::profile()`] and [`de::Builder::profile()`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     profile::Profile,
+    ///     Harness,
+    /// };
+    ///
+    /// let harness = Harness::builder().profile(Profile::Postcard).build();
+    /// ```
+    pub fn profile(&mut self, profile: Profile) -> &mut Self {
+        self.serializer.profile(profile);
+        self.deserializer.profile(profile);
+        self
+    }
+
+    /// Configures options specific to the [`Serializer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Harness;
+    ///
+    /// let harness = Harness::builder()
+    ///     .serializer(|serializer| {
+    ///         serializer.char_as_str(true);
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn serializer<F>(&mut self, configure: F) -> &mut Self
+    where
+        F: FnOnce(&mut ser::Builder),
+    {
+        configure(&mut self.serializer);
+        self
+    }
+
+    /// Configures options specific to the [`Deserializer`].
+    ///
+    /// Any tokens set on the [`de::Builder`] are replaced by the tokens provided when
+    /// deserializing.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Harness;
+    ///
+    /// let harness = Harness::builder()
+    ///     .deserializer(|deserializer| {
+    ///         deserializer.self_describing(true);
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn deserializer<F>(&mut self, configure: F) -> &mut Self
+    where
+        F: FnOnce(&mut de::Builder),
+    {
+        configure(&mut self.deserializer);
+        self
+    }
+
+    /// Build a new [`Harness`] using this `Builder`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Harness;
+    ///
+    /// let harness = Harness::builder().is_human_readable(false).build();
+    /// ```
+    #[must_use]
+    pub fn build(&self) -> Harness {
+        Harness {
+            serializer: self.serializer.clone(),
+            deserializer: self.deserializer.clone(),
+        }
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            serializer: Serializer::builder(),
+            deserializer: Deserializer::builder(Vec::new()),
+        }
+    }
+}
+
+/// An error encountered during a round-trip.
+///
+/// # Example
+/// ``` rust
+/// use serde::ser::Error as _;
+/// use serde_assert::{
+///     harness::Error,
+///     ser,
+/// };
+///
+/// assert_eq!(
+///     format!("{}", Error::Ser(ser::Error::custom("foo"))),
+///     "serialization failed: foo"
+/// );
+/// ```
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The value failed to serialize.
+    Ser(ser::Error),
+    /// The serialized tokens failed to deserialize.
+    De(de::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ser(error) => write!(f, "serialization failed: {error}"),
+            Self::De(error) => write!(f, "deserialization failed: {error}"),
+        }
+    }
+}
+
+impl serde::de::StdError for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Error,
+        Harness,
+    };
+    use crate::{
+        de,
+        profile::Profile,
+        ser,
+        ser::SerializeStructAs,
+        Token,
+    };
+    use alloc::{
+        borrow::ToOwned,
+        format,
+        vec,
+        vec::Vec,
+    };
+    use claims::{
+        assert_err_eq,
+        assert_ok,
+        assert_ok_eq,
+    };
+    use serde::{
+        de::{
+            Error as _,
+            IgnoredAny,
+        },
+        ser::Error as _,
+        Deserialize,
+        Deserializer,
+        Serialize,
+        Serializer,
+    };
+    use serde_derive::{
+        Deserialize,
+        Serialize,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct Readable(bool);
+
+    impl Serialize for Readable {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let is_human_readable = serializer.is_human_readable();
+            serializer.serialize_bool(is_human_readable)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Readable {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let is_human_readable = deserializer.is_human_readable();
+            if bool::deserialize(deserializer)? == is_human_readable {
+                Ok(Readable(is_human_readable))
+            } else {
+                Err(D::Error::custom("mismatched readability"))
+            }
+        }
+    }
+
+    #[test]
+    fn default_is_human_readable() {
+        let harness = Harness::new();
+
+        assert_ok_eq!(harness.serialize(&Readable(true)), [Token::Bool(true)]);
+        assert_ok_eq!(harness.roundtrip(&Readable(true)), Readable(true));
+    }
+
+    #[test]
+    fn is_human_readable_shared() {
+        let harness = Harness::builder().is_human_readable(false).build();
+
+        assert!(!(&harness.serializer()).is_human_readable());
+        assert_ok_eq!(harness.roundtrip(&Readable(false)), Readable(false));
+    }
+
+    #[test]
+    fn serializer_options() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Struct {
+            foo: bool,
+        }
+
+        let harness = Harness::builder()
+            .serializer(|serializer| {
+                serializer.serialize_struct_as(SerializeStructAs::Seq);
+            })
+            .build();
+
+        assert_ok_eq!(
+            harness.serialize(&Struct { foo: true }),
+            [
+                Token::Seq { len: Some(1) },
+                Token::Bool(true),
+                Token::SeqEnd,
+            ]
+        );
+        assert_ok_eq!(
+            harness.roundtrip(&Struct { foo: true }),
+            Struct { foo: true }
+        );
+    }
+
+    #[test]
+    fn deserializer_options() {
+        let harness = Harness::builder()
+            .deserializer(|deserializer| {
+                deserializer.tokens([Token::Unit]).self_describing(true);
+            })
+            .build();
+
+        assert_ok!(harness.deserialize::<IgnoredAny, _>([Token::Bool(true)]));
+    }
+
+    #[test]
+    fn deserialize_trailing_tokens() {
+        assert_err_eq!(
+            Harness::new().deserialize::<bool, _>([Token::Bool(true), Token::Unit]),
+            de::Error::TrailingTokens(1)
+        );
+    }
+
+    #[test]
+    fn deserialize_allow_trailing_tokens() {
+        let harness = Harness::builder()
+            .deserializer(|deserializer| {
+                deserializer.allow_trailing_tokens(true);
+            })
+            .build();
+
+        assert_ok_eq!(
+            harness.deserialize::<bool, _>([Token::Bool(true), Token::Unit]),
+            true
+        );
+    }
+
+    #[test]
+    fn roundtrip_ser_error() {
+        struct Failing;
+
+        impl Serialize for Failing {
+            fn serialize<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                Err(S::Error::custom("failed"))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Failing {
+            fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Ok(Failing)
+            }
+        }
+
+        assert_err_eq!(
+            Harness::new().roundtrip(&Failing).map(|_| ()),
+            Error::Ser(ser::Error::custom("failed"))
+        );
+    }
+
+    #[test]
+    fn roundtrip_de_error() {
+        let harness = Harness::builder()
+            .serializer(|serializer| {
+                serializer.is_human_readable(false);
+            })
+            .build();
+
+        assert_err_eq!(
+            harness.roundtrip(&Readable(true)),
+            Error::De(de::Error::custom("mismatched readability"))
+        );
+    }
+
+    #[test]
+    fn error_display() {
+        assert_eq!(
+            format!("{}", Error::De(de::Error::MissingField("foo"))),
+            "deserialization failed: missing field foo"
+        );
+        assert_eq!(
+            format!("{}", Error::Ser(ser::Error("foo".to_owned()))),
+            "serialization failed: foo"
+        );
+    }
+
+    #[test]
+    fn assert_roundtrip() {
+        Harness::new().assert_roundtrip(&Some(vec![1u8, 2, 3]));
+    }
+
+    #[test]
+    fn assert_roundtrip_asymmetric() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Struct {
+            foo: bool,
+        }
+
+        let harness = Harness::builder()
+            .serializer(|serializer| {
+                serializer.serialize_struct_as(SerializeStructAs::Seq);
+            })
+            .deserializer(|deserializer| {
+                deserializer.zero_copy(false);
+            })
+            .build();
+
+        harness.assert_roundtrip(&Struct { foo: true });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Lossy(1) round-tripped through Tokens([NewtypeStruct { name: \"Lossy\" }, \
+                    U8(0)]) to Lossy(0)"
+    )]
+    fn assert_roundtrip_mismatch() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Lossy(u8);
+
+        impl Serialize for Lossy {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_newtype_struct("Lossy", &0u8)
+            }
+        }
+
+        Harness::new().assert_roundtrip(&Lossy(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Readable(true) failed to deserialize from Tokens([Bool(false)])")]
+    fn assert_roundtrip_de_error() {
+        let harness = Harness::builder()
+            .serializer(|serializer| {
+                serializer.is_human_readable(false);
+            })
+            .build();
+
+        harness.assert_roundtrip(&Readable(true));
+    }
+
+    #[test]
+    fn for_each_readability() {
+        let mut readabilities = Vec::new();
+
+        Harness::builder()
+            .is_human_readable(false)
+            .build()
+            .for_each_readability(|harness| {
+                let is_human_readable = (&harness.serializer()).is_human_readable();
+                harness.assert_roundtrip(&Readable(is_human_readable));
+                readabilities.push(is_human_readable);
+            });
+
+        assert_eq!(readabilities, [true, false]);
+    }
+
+    #[test]
+    fn for_each_readability_keeps_configuration() {
+        Harness::builder()
+            .deserializer(|deserializer| {
+                deserializer.allow_trailing_tokens(true);
+            })
+            .build()
+            .for_each_readability(|harness| {
+                assert_ok_eq!(
+                    harness.deserialize::<bool, _>([Token::Bool(true), Token::Unit]),
+                    true
+                );
+            });
+    }
+
+    #[test]
+    fn profile_roundtrip() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Struct {
+            foo: Option<char>,
+            bar: u128,
+            #[serde(with = "serde_bytes")]
+            baz: Vec<u8>,
+        }
+
+        let value = Struct {
+            foo: None,
+            bar: 42,
+            baz: vec![1, 2],
+        };
+        for profile in [Profile::Json, Profile::Bincode, Profile::Postcard] {
+            Harness::builder()
+                .profile(profile)
+                .build()
+                .assert_roundtrip(&value);
+        }
+    }
+
+    #[test]
+    fn profile_overridden() {
+        let harness = Harness::builder()
+            .profile(Profile::Bincode)
+            .is_human_readable(true)
+            .build();
+
+        assert!((&harness.serializer()).is_human_readable());
+    }
+}
@@ -100,6 +100,11 @@
 //! assert_ok_eq!(bool::deserialize(&mut deserializer), value);
 //! ```
 //!
+//! # `no_std` Support
+//! This crate is `no_std` compatible, but it does require an allocator through the [`alloc`]
+//! crate. [`Token`]s own their string and byte data, and errors carry owned messages, so there is
+//! no fixed-capacity mode for targets without a global allocator.
+//!
 //! [`claims`]: https://docs.rs/claims/
 //! [`Deserialize`]: serde::Deserialize
 //! [`HashSet`]: std::collections::HashSet
@@ -108,18 +113,60 @@
 
 #![no_std]
 #![warn(clippy::pedantic)]
+#![cfg_attr(feature = "safe", forbid(unsafe_code))]
 
 extern crate alloc;
-#[cfg(any(test, doc))]
+#[cfg(any(feature = "std", test, doc))]
 extern crate std;
 
 pub mod de;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod harness;
+pub mod profile;
+pub mod schema;
 pub mod ser;
 pub mod token;
+pub mod value;
+
+mod assert;
 
+pub use assert::{
+    assert_cases,
+    assert_compatible,
+    assert_compatible_with,
+    assert_corpus,
+    assert_de_tokens,
+    assert_seed_cloned,
+    assert_seed_mut,
+    assert_ser_tokens,
+    assert_ser_unordered,
+};
+#[doc(inline)]
+pub use de::{
+    from_tokens,
+    Deserializer,
+};
 #[doc(inline)]
-pub use de::Deserializer;
+pub use harness::Harness;
 #[doc(inline)]
-pub use ser::Serializer;
+pub use ser::{
+    to_tokens,
+    to_tokens_with,
+    Serializer,
+};
 #[doc(inline)]
 pub use token::Token;
+#[doc(inline)]
+pub use value::Value;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::assert::{
+        assert_de_err_matches,
+        assert_tokens,
+        assert_tokens_eq,
+    };
+    pub use alloc::vec::Vec;
+    pub use serde::Deserialize;
+}
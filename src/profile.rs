@@ -0,0 +1,78 @@
+//! Presets approximating commonly targeted serialization formats.
+//!
+//! This module provides a [`Profile`] enum, which can be passed to the [`Serializer`],
+//! [`Deserializer`], and [`Harness`] builders to configure them in a single call. Each profile sets
+//! the options that determine how a format represents the data model, such as whether it is
+//! human-readable or self-describing.
+//!
+//! # Example
+//! ``` rust
+//! use claims::assert_ok_eq;
+//! use serde_assert::{
+//!     profile::Profile,
+//!     Harness,
+//! };
+//! use serde_derive::{
+//!     Deserialize,
+//!     Serialize,
+//! };
+//!
+//! #[derive(Debug, Deserialize, PartialEq, Serialize)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! let harness = Harness::builder().profile(Profile::Bincode).build();
+//!
+//! assert_ok_eq!(
+//!     harness.roundtrip(&Point { x: 1, y: 2 }),
+//!     Point { x: 1, y: 2 }
+//! );
+//! ```
+//!
+//! [`Deserializer`]: crate::Deserializer
+//! [`Harness`]: crate::Harness
+//! [`Serializer`]: crate::Serializer
+
+/// A preset configuration approximating a serialization format.
+///
+/// Applying a profile to a builder sets each option relevant to the format. Options not listed for
+/// a profile are left unchanged, and any option can still be set after applying a profile to
+/// override it.
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::{
+///     profile::Profile,
+///     Deserializer,
+///     Serializer,
+///     Token,
+/// };
+///
+/// let serializer = Serializer::builder().profile(Profile::Json).build();
+/// let deserializer = Deserializer::builder([Token::Unit])
+///     .profile(Profile::Json)
+///     .build();
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Profile {
+    /// Approximates JSON, as implemented by `serde_json`.
+    ///
+    /// The format is human-readable and self-describing. Bytes are represented as sequences of
+    /// `u8`s, `char`s as strings, and `None` as null. Structs are serialized as maps, but may
+    /// also be deserialized from sequences. Enums are serialized as maps of their variant name to
+    /// their contents, may also be deserialized from such maps, and are presented that way to
+    /// buffering `Deserialize` implementations.
+    Json,
+    /// Approximates `bincode`.
+    ///
+    /// The format is compact and not self-describing. Structs are serialized as sequences of their
+    /// fields, and 128-bit integers are supported natively.
+    Bincode,
+    /// Approximates `postcard`.
+    ///
+    /// The format is compact and not self-describing. Structs are serialized as sequences of their
+    /// fields, and 128-bit integers are supported natively.
+    Postcard,
+}
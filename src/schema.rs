@@ -0,0 +1,638 @@
+//! Structural schemas of serialized values.
+//!
+//! This module provides a [`Schema`] type describing the shape of a serialized value, without
+//! regard to the concrete values contained within it. A `Schema` can be inferred from a sequence
+//! of [`Token`]s and compared against another `Schema`, allowing assertions such as "the
+//! serialized shape of version 2 is a superset of the serialized shape of version 1."
+//!
+//! # Example
+//! ``` rust
+//! use claims::assert_ok;
+//! use serde_assert::{
+//!     schema::Schema,
+//!     Token,
+//! };
+//!
+//! let v1 = assert_ok!(Schema::infer([
+//!     Token::Struct {
+//!         name: "Foo",
+//!         len: 1,
+//!     },
+//!     Token::Field("a"),
+//!     Token::U32(1),
+//!     Token::StructEnd,
+//! ]));
+//! let v2 = assert_ok!(Schema::infer([
+//!     Token::Struct {
+//!         name: "Foo",
+//!         len: 2,
+//!     },
+//!     Token::Field("a"),
+//!     Token::U32(2),
+//!     Token::Field("b"),
+//!     Token::Bool(true),
+//!     Token::StructEnd,
+//! ]));
+//!
+//! assert!(v2.is_superset_of(&v1));
+//! assert!(!v1.is_superset_of(&v2));
+//! ```
+
+use crate::{
+    de::Error,
+    token::{
+        CanonicalToken,
+        Tokens,
+    },
+    Token,
+};
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    vec::Vec,
+};
+use serde::de::{
+    Error as _,
+    Unexpected,
+};
+
+/// The shape of a serialized value.
+///
+/// A `Schema` is inferred from a sequence of [`Token`]s using [`infer()`]. Names of structs and
+/// enums are not included, but names of fields and variants are.
+///
+/// As a `Schema` is inferred from concrete values, it only contains what those values describe.
+/// For example, the elements of an empty sequence are [`Unknown`], and an enum only contains the
+/// variants that were present. When multiple values of differing shapes are present in the same
+/// position (such as elements of a sequence), their schemas are merged: the fields of structs and
+/// the variants of enums are combined, and otherwise incompatible shapes become [`Any`].
+///
+/// [`Any`]: Schema::Any
+/// [`infer()`]: Schema::infer()
+/// [`Unknown`]: Schema::Unknown
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Schema {
+    /// No information is known about the shape.
+    ///
+    /// This is the shape of elements within empty sequences and maps, and of the value within
+    /// [`None`].
+    Unknown,
+    /// Values of multiple incompatible shapes.
+    Any,
+    /// A [`bool`].
+    Bool,
+    /// An [`i8`].
+    I8,
+    /// An [`i16`].
+    I16,
+    /// An [`i32`].
+    I32,
+    /// An [`i64`].
+    I64,
+    /// An [`i128`].
+    I128,
+    /// A [`u8`].
+    U8,
+    /// A [`u16`].
+    U16,
+    /// A [`u32`].
+    U32,
+    /// A [`u64`].
+    U64,
+    /// A [`u128`].
+    U128,
+    /// An [`f32`].
+    F32,
+    /// An [`f64`].
+    F64,
+    /// A [`char`].
+    Char,
+    /// A string.
+    Str,
+    /// Bytes.
+    Bytes,
+    /// An [`Option`] of the contained shape.
+    Option(Box<Schema>),
+    /// A unit or unit struct.
+    Unit,
+    /// A newtype struct containing the contained shape.
+    Newtype(Box<Schema>),
+    /// A sequence whose elements are of the contained shape.
+    Seq(Box<Schema>),
+    /// A tuple or tuple struct whose elements are of the contained shapes.
+    Tuple(Vec<Schema>),
+    /// A map whose keys and values are of the contained shapes.
+    Map(Box<Schema>, Box<Schema>),
+    /// A struct with the contained fields.
+    Struct(BTreeMap<&'static str, Schema>),
+    /// An enum with the contained variants.
+    ///
+    /// The shape of each variant is [`Schema::Unit`] for a unit variant, the contained shape for a
+    /// newtype variant, a [`Schema::Tuple`] for a tuple variant, and a [`Schema::Struct`] for a
+    /// struct variant.
+    Enum(BTreeMap<&'static str, Schema>),
+}
+
+impl Schema {
+    /// Infers a `Schema` from a sequence of [`Token`]s.
+    ///
+    /// The tokens must represent exactly one value. Any [`Unordered`] tokens are read in the order
+    /// they are provided.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] if the tokens do not represent exactly one complete value.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::{
+    ///     schema::Schema,
+    ///     Token,
+    /// };
+    ///
+    /// assert_ok_eq!(
+    ///     Schema::infer([
+    ///         Token::Seq { len: Some(2) },
+    ///         Token::Some,
+    ///         Token::U8(1),
+    ///         Token::None,
+    ///         Token::SeqEnd,
+    ///     ]),
+    ///     Schema::Seq(Box::new(Schema::Option(Box::new(Schema::U8))))
+    /// );
+    /// ```
+    ///
+    /// [`Unordered`]: Token::Unordered
+    pub fn infer<T>(tokens: T) -> Result<Self, Error>
+    where
+        T: IntoIterator<Item = Token>,
+    {
+        let mut tokens = Tokens::canonicalize(tokens).0.into_iter();
+        let schema = infer_value(&mut tokens)?;
+        if tokens.next().is_some() {
+            return Err(Error::custom("trailing tokens after value"));
+        }
+        Ok(schema)
+    }
+
+    /// Returns whether every value described by `other` is also described by this `Schema`.
+    ///
+    /// A struct is a superset of another struct if it contains all of the other's fields, and an
+    /// enum is a superset of another enum if it contains all of the other's variants. Every
+    /// `Schema` is a superset of [`Schema::Unknown`], and [`Schema::Any`] is a superset of every
+    /// `Schema`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde_assert::{
+    ///     schema::Schema,
+    ///     Token,
+    /// };
+    ///
+    /// let schema = assert_ok!(Schema::infer([Token::Some, Token::U8(1)]));
+    ///
+    /// assert!(schema.is_superset_of(&assert_ok!(Schema::infer([Token::None]))));
+    /// assert!(!schema.is_superset_of(&assert_ok!(Schema::infer([Token::Some, Token::U16(1)]))));
+    /// ```
+    #[must_use]
+    pub fn is_superset_of(&self, other: &Schema) -> bool {
+        match (self, other) {
+            (Schema::Any, _) | (_, Schema::Unknown) => true,
+            (Schema::Option(a), Schema::Option(b))
+            | (Schema::Newtype(a), Schema::Newtype(b))
+            | (Schema::Seq(a), Schema::Seq(b)) => a.is_superset_of(b),
+            (Schema::Tuple(a), Schema::Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.is_superset_of(b))
+            }
+            (Schema::Map(a_key, a_value), Schema::Map(b_key, b_value)) => {
+                a_key.is_superset_of(b_key) && a_value.is_superset_of(b_value)
+            }
+            (Schema::Struct(a), Schema::Struct(b)) | (Schema::Enum(a), Schema::Enum(b)) => b
+                .iter()
+                .all(|(name, b)| a.get(name).map_or(false, |a| a.is_superset_of(b))),
+            (a, b) => a == b,
+        }
+    }
+
+    /// Combines two schemas describing values in the same position.
+    fn merge(self, other: Schema) -> Schema {
+        match (self, other) {
+            (Schema::Unknown, schema) | (schema, Schema::Unknown) => schema,
+            (Schema::Option(a), Schema::Option(b)) => Schema::Option(Box::new(a.merge(*b))),
+            (Schema::Newtype(a), Schema::Newtype(b)) => Schema::Newtype(Box::new(a.merge(*b))),
+            (Schema::Seq(a), Schema::Seq(b)) => Schema::Seq(Box::new(a.merge(*b))),
+            (Schema::Tuple(a), Schema::Tuple(b)) if a.len() == b.len() => {
+                Schema::Tuple(a.into_iter().zip(b).map(|(a, b)| a.merge(b)).collect())
+            }
+            (Schema::Map(a_key, a_value), Schema::Map(b_key, b_value)) => Schema::Map(
+                Box::new(a_key.merge(*b_key)),
+                Box::new(a_value.merge(*b_value)),
+            ),
+            (Schema::Struct(a), Schema::Struct(b)) => Schema::Struct(merge_named(a, b)),
+            (Schema::Enum(a), Schema::Enum(b)) => Schema::Enum(merge_named(a, b)),
+            (a, b) => {
+                if a == b {
+                    a
+                } else {
+                    Schema::Any
+                }
+            }
+        }
+    }
+}
+
+/// Combines the fields or variants of two schemas.
+fn merge_named(
+    mut a: BTreeMap<&'static str, Schema>,
+    b: BTreeMap<&'static str, Schema>,
+) -> BTreeMap<&'static str, Schema> {
+    for (name, schema) in b {
+        let merged = match a.remove(name) {
+            Some(existing) => existing.merge(schema),
+            None => schema,
+        };
+        a.insert(name, merged);
+    }
+    a
+}
+
+fn infer_value<I>(tokens: &mut I) -> Result<Schema, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    let token = tokens.next().ok_or(Error::EndOfTokens)?;
+    infer_token(token, tokens)
+}
+
+fn infer_token<I>(mut token: CanonicalToken, tokens: &mut I) -> Result<Schema, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    Ok(match token {
+        CanonicalToken::Bool(_) => Schema::Bool,
+        CanonicalToken::I8(_) => Schema::I8,
+        CanonicalToken::I16(_) => Schema::I16,
+        CanonicalToken::I32(_) => Schema::I32,
+        CanonicalToken::I64(_) => Schema::I64,
+        CanonicalToken::I128(_) => Schema::I128,
+        CanonicalToken::U8(_) => Schema::U8,
+        CanonicalToken::U16(_) => Schema::U16,
+        CanonicalToken::U32(_) => Schema::U32,
+        CanonicalToken::U64(_) => Schema::U64,
+        CanonicalToken::U128(_) => Schema::U128,
+        CanonicalToken::F32(_) => Schema::F32,
+        CanonicalToken::F64(_) => Schema::F64,
+        CanonicalToken::Char(_) => Schema::Char,
+        CanonicalToken::Str(_) => Schema::Str,
+        CanonicalToken::Bytes(_) => Schema::Bytes,
+        CanonicalToken::None => Schema::Option(Box::new(Schema::Unknown)),
+        CanonicalToken::Some => Schema::Option(Box::new(infer_value(tokens)?)),
+        CanonicalToken::Unit | CanonicalToken::UnitStruct { .. } => Schema::Unit,
+        CanonicalToken::UnitVariant { id, .. } => variant_schema(id.variant, Schema::Unit),
+        CanonicalToken::NewtypeStruct { .. } => Schema::Newtype(Box::new(infer_value(tokens)?)),
+        CanonicalToken::NewtypeVariant { id, .. } => {
+            variant_schema(id.variant, infer_value(tokens)?)
+        }
+        CanonicalToken::Seq { .. } => {
+            let mut element = Schema::Unknown;
+            for schema in infer_elements(tokens, &CanonicalToken::SeqEnd, Error::ExpectedSeqEnd)? {
+                element = element.merge(schema);
+            }
+            Schema::Seq(Box::new(element))
+        }
+        CanonicalToken::Tuple { .. } => Schema::Tuple(infer_elements(
+            tokens,
+            &CanonicalToken::TupleEnd,
+            Error::ExpectedTupleEnd,
+        )?),
+        CanonicalToken::TupleStruct { .. } => Schema::Tuple(infer_elements(
+            tokens,
+            &CanonicalToken::TupleStructEnd,
+            Error::ExpectedTupleStructEnd,
+        )?),
+        CanonicalToken::TupleVariant { id, .. } => variant_schema(
+            id.variant,
+            Schema::Tuple(infer_elements(
+                tokens,
+                &CanonicalToken::TupleVariantEnd,
+                Error::ExpectedTupleVariantEnd,
+            )?),
+        ),
+        CanonicalToken::Map { .. } => {
+            let mut key = Schema::Unknown;
+            let mut value = Schema::Unknown;
+            loop {
+                match tokens.next() {
+                    Some(CanonicalToken::MapEnd) => break,
+                    Some(token) => {
+                        key = key.merge(infer_token(token, tokens)?);
+                        value = value.merge(infer_value(tokens)?);
+                    }
+                    None => return Err(Error::ExpectedMapEnd),
+                }
+            }
+            Schema::Map(Box::new(key), Box::new(value))
+        }
+        CanonicalToken::Struct { .. } => Schema::Struct(infer_fields(
+            tokens,
+            &CanonicalToken::StructEnd,
+            Error::ExpectedStructEnd,
+        )?),
+        CanonicalToken::StructVariant { id, .. } => variant_schema(
+            id.variant,
+            Schema::Struct(infer_fields(
+                tokens,
+                &CanonicalToken::StructVariantEnd,
+                Error::ExpectedStructVariantEnd,
+            )?),
+        ),
+        CanonicalToken::SeqEnd
+        | CanonicalToken::TupleEnd
+        | CanonicalToken::TupleStructEnd
+        | CanonicalToken::TupleVariantEnd
+        | CanonicalToken::MapEnd
+        | CanonicalToken::Field(..)
+        | CanonicalToken::SkippedField(..)
+        | CanonicalToken::StructEnd
+        | CanonicalToken::StructVariantEnd => {
+            return Err(Error::invalid_type(
+                Unexpected::from(&mut token),
+                &"a value",
+            ))
+        }
+        CanonicalToken::Error(message) => return Err(Error::Custom(message)),
+    })
+}
+
+fn variant_schema(variant: &'static str, schema: Schema) -> Schema {
+    let mut variants = BTreeMap::new();
+    variants.insert(variant, schema);
+    Schema::Enum(variants)
+}
+
+fn infer_elements<I>(
+    tokens: &mut I,
+    end: &CanonicalToken,
+    error: Error,
+) -> Result<Vec<Schema>, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    let mut elements = Vec::new();
+    loop {
+        match tokens.next() {
+            Some(token) if token == *end => return Ok(elements),
+            Some(token) => elements.push(infer_token(token, tokens)?),
+            None => return Err(error),
+        }
+    }
+}
+
+fn infer_fields<I>(
+    tokens: &mut I,
+    end: &CanonicalToken,
+    error: Error,
+) -> Result<BTreeMap<&'static str, Schema>, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    let mut fields = BTreeMap::new();
+    loop {
+        match tokens.next() {
+            Some(token) if token == *end => return Ok(fields),
+            Some(CanonicalToken::Field(field)) => {
+                let schema = infer_value(tokens)?;
+                let merged = match fields.remove(field) {
+                    Some(existing) => Schema::merge(existing, schema),
+                    None => schema,
+                };
+                fields.insert(field, merged);
+            }
+            Some(CanonicalToken::SkippedField(_)) => {}
+            Some(mut token) => {
+                return Err(Error::invalid_type(
+                    Unexpected::from(&mut token),
+                    &"a field",
+                ))
+            }
+            None => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Schema;
+    use crate::{
+        de::Error,
+        Token,
+    };
+    use alloc::{
+        borrow::ToOwned,
+        boxed::Box,
+        collections::BTreeMap,
+        vec,
+    };
+    use claims::{
+        assert_err_eq,
+        assert_ok,
+        assert_ok_eq,
+    };
+
+    #[test]
+    fn infer_primitive() {
+        assert_ok_eq!(Schema::infer([Token::I16(-1)]), Schema::I16);
+    }
+
+    #[test]
+    fn infer_seq_merges_elements() {
+        assert_ok_eq!(
+            Schema::infer([
+                Token::Seq { len: Some(2) },
+                Token::None,
+                Token::Some,
+                Token::Str("foo".to_owned()),
+                Token::SeqEnd,
+            ]),
+            Schema::Seq(Box::new(Schema::Option(Box::new(Schema::Str))))
+        );
+    }
+
+    #[test]
+    fn infer_seq_empty() {
+        assert_ok_eq!(
+            Schema::infer([Token::Seq { len: Some(0) }, Token::SeqEnd]),
+            Schema::Seq(Box::new(Schema::Unknown))
+        );
+    }
+
+    #[test]
+    fn infer_seq_incompatible_elements() {
+        assert_ok_eq!(
+            Schema::infer([
+                Token::Seq { len: Some(2) },
+                Token::U8(1),
+                Token::Bool(true),
+                Token::SeqEnd,
+            ]),
+            Schema::Seq(Box::new(Schema::Any))
+        );
+    }
+
+    #[test]
+    fn infer_tuple() {
+        assert_ok_eq!(
+            Schema::infer([
+                Token::Tuple { len: 2 },
+                Token::U8(1),
+                Token::Bool(true),
+                Token::TupleEnd,
+            ]),
+            Schema::Tuple(vec![Schema::U8, Schema::Bool])
+        );
+    }
+
+    #[test]
+    fn infer_map() {
+        assert_ok_eq!(
+            Schema::infer([
+                Token::Map { len: Some(1) },
+                Token::Char('a'),
+                Token::Unit,
+                Token::MapEnd,
+            ]),
+            Schema::Map(Box::new(Schema::Char), Box::new(Schema::Unit))
+        );
+    }
+
+    #[test]
+    fn infer_struct() {
+        let mut fields = BTreeMap::new();
+        fields.insert("a", Schema::U32);
+
+        assert_ok_eq!(
+            Schema::infer([
+                Token::Struct {
+                    name: "Foo",
+                    len: 1,
+                },
+                Token::Field("a"),
+                Token::U32(1),
+                Token::SkippedField("b"),
+                Token::StructEnd,
+            ]),
+            Schema::Struct(fields)
+        );
+    }
+
+    #[test]
+    fn infer_variants_merged() {
+        let mut variants = BTreeMap::new();
+        variants.insert("Foo", Schema::Unit);
+        variants.insert("Bar", Schema::Bool);
+
+        assert_ok_eq!(
+            Schema::infer([
+                Token::Seq { len: Some(2) },
+                Token::UnitVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Foo",
+                },
+                Token::NewtypeVariant {
+                    name: "Enum",
+                    variant_index: 1,
+                    variant: "Bar",
+                },
+                Token::Bool(true),
+                Token::SeqEnd,
+            ]),
+            Schema::Seq(Box::new(Schema::Enum(variants)))
+        );
+    }
+
+    #[test]
+    fn infer_end_of_tokens() {
+        assert_err_eq!(Schema::infer([Token::Some]), Error::EndOfTokens);
+    }
+
+    #[test]
+    fn infer_missing_end() {
+        assert_err_eq!(
+            Schema::infer([Token::Map { len: None }]),
+            Error::ExpectedMapEnd
+        );
+    }
+
+    #[test]
+    fn superset_same() {
+        assert!(Schema::U8.is_superset_of(&Schema::U8));
+    }
+
+    #[test]
+    fn superset_different_primitive() {
+        assert!(!Schema::U8.is_superset_of(&Schema::U16));
+    }
+
+    #[test]
+    fn superset_unknown() {
+        assert!(Schema::Seq(Box::new(Schema::U8))
+            .is_superset_of(&Schema::Seq(Box::new(Schema::Unknown))));
+        assert!(!Schema::Seq(Box::new(Schema::Unknown))
+            .is_superset_of(&Schema::Seq(Box::new(Schema::U8))));
+    }
+
+    #[test]
+    fn superset_any() {
+        assert!(Schema::Any.is_superset_of(&Schema::Bool));
+        assert!(!Schema::Bool.is_superset_of(&Schema::Any));
+    }
+
+    #[test]
+    fn superset_tuple_length() {
+        assert!(!Schema::Tuple(vec![Schema::U8, Schema::U8])
+            .is_superset_of(&Schema::Tuple(vec![Schema::U8])));
+    }
+
+    #[test]
+    fn superset_enum_variants() {
+        let v1 = assert_ok!(Schema::infer([Token::UnitVariant {
+            name: "Enum",
+            variant_index: 0,
+            variant: "Foo",
+        }]));
+        let v2 = assert_ok!(Schema::infer([
+            Token::Seq { len: Some(2) },
+            Token::UnitVariant {
+                name: "Enum",
+                variant_index: 0,
+                variant: "Foo",
+            },
+            Token::UnitVariant {
+                name: "Enum",
+                variant_index: 1,
+                variant: "Bar",
+            },
+            Token::SeqEnd,
+        ]));
+
+        match v2 {
+            Schema::Seq(element) => {
+                assert!(element.is_superset_of(&v1));
+                assert!(!v1.is_superset_of(&element));
+            }
+            _ => panic!("expected Schema::Seq"),
+        }
+    }
+
+    #[test]
+    fn superset_struct_field_type_changed() {
+        let mut a = BTreeMap::new();
+        a.insert("a", Schema::U8);
+        let mut b = BTreeMap::new();
+        b.insert("a", Schema::Str);
+
+        assert!(!Schema::Struct(a).is_superset_of(&Schema::Struct(b)));
+    }
+}
@@ -19,25 +19,47 @@
 //! assert_ok_eq!(true.serialize(&serializer), [Token::Bool(true)]);
 //! ```
 
-use crate::token::{
-    CanonicalToken,
-    Tokens,
+use crate::{
+    profile::Profile,
+    token::{
+        CanonicalToken,
+        Tokens,
+        VariantId,
+    },
+    Token,
 };
 use alloc::{
     borrow::ToOwned,
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    rc::Rc,
     string::{
         String,
         ToString,
     },
     vec,
+    vec::Vec,
+};
+#[cfg(target_has_atomic = "ptr")]
+use core::sync::atomic::{
+    AtomicUsize,
+    Ordering,
 };
 use core::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    cmp,
     fmt,
     fmt::Display,
+    ops::Deref,
 };
 use serde::{
     ser,
     ser::{
+        Error as _,
         SerializeMap,
         SerializeSeq,
         SerializeStructVariant,
@@ -103,6 +125,84 @@ pub enum SerializeStructAs {
     Seq,
 }
 
+/// Configuration for serializing 128-bit integers.
+///
+/// Can be passed to a [`Builder`] to determine how `i128` and `u128` values should be serialized
+/// by the [`Serializer`].
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde::Serialize;
+/// use serde_assert::{
+///     ser::Serialize128As,
+///     Serializer,
+///     Token,
+/// };
+///
+/// let serializer = Serializer::builder()
+///     .serialize_128_as(Serialize128As::Bytes)
+///     .build();
+///
+/// assert_ok_eq!(
+///     42i128.serialize(&serializer),
+///     [Token::Bytes(42i128.to_be_bytes().to_vec())]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Serialize128As {
+    /// Serialize 128-bit integers using [`Token::I128`] and [`Token::U128`].
+    ///
+    /// [`Token::I128`]: crate::Token::I128
+    /// [`Token::U128`]: crate::Token::U128
+    Integer,
+    /// Serialize 128-bit integers as a [`Token::Str`] containing their decimal representation.
+    ///
+    /// [`Token::Str`]: crate::Token::Str
+    Str,
+    /// Serialize 128-bit integers as a [`Token::Bytes`] containing their 16-byte big-endian
+    /// representation.
+    ///
+    /// [`Token::Bytes`]: crate::Token::Bytes
+    Bytes,
+}
+
+/// The representation used to emit an enum variant.
+///
+/// Serde lowers internally tagged, adjacently tagged, and untagged enums to structs, maps, or the
+/// variant's contents before they reach the [`Serializer`], so only representations emitted by the
+/// `Serializer` itself are recorded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum EnumRepresentation {
+    /// The variant was emitted as a variant token, such as [`Token::UnitVariant`], followed by its
+    /// contents.
+    ///
+    /// [`Token::UnitVariant`]: crate::Token::UnitVariant
+    External,
+    /// The variant was emitted as a map containing a single entry from the variant name to its
+    /// contents, or as a string containing the variant name for unit variants.
+    ///
+    /// Enabled by [`Builder::serialize_enum_as_map()`].
+    Map,
+}
+
+/// An enum variant emitted by the [`Serializer`].
+///
+/// These are recorded during serialization and can be obtained through
+/// [`Recording::enum_variants()`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EnumVariant {
+    /// The name of the enum.
+    pub name: &'static str,
+    /// The index of the variant within the enum.
+    pub variant_index: u32,
+    /// The name of the variant.
+    pub variant: &'static str,
+    /// The representation the variant was emitted with.
+    pub representation: EnumRepresentation,
+}
+
 /// Serializer for testing [`Serialize`] implementations.
 ///
 /// This serializer outputs [`Tokens`] representing the serialized value. The `Tokens` can be
@@ -118,6 +218,34 @@ pub enum SerializeStructAs {
 ///   formats often serialize structs as sequences. By enabling this setting, tokens can be produced
 ///   in this format, and can then be deserialized to ensure structs deserialized as sequences are
 ///   deserialized correctly.
+/// - [`serialize_bytes_as_seq()`]: Determines whether byte slices are serialized as sequences of
+///   `u8`s, as is done by formats without a native bytes type.
+/// - [`char_as_str()`]: Determines whether `char`s are serialized as single-character strings, as
+///   is done by JSON-like formats.
+/// - [`serialize_none_as_unit()`]: Determines whether `None` is serialized as a unit value, as is
+///   done by formats encoding it as null.
+/// - [`serialize_enum_as_map()`]: Determines whether enum variants are serialized as maps from
+///   their variant name to their contents, as is done by self-describing formats like JSON.
+/// - [`serialize_128_as()`]: Specifies how 128-bit integers are serialized, for emulating formats
+///   that only support them through string or byte encodings.
+/// - [`support_128_bit()`]: Determines whether 128-bit integers can be serialized at all, for
+///   emulating formats without support for them.
+/// - [`validate_tuple_lengths()`]: Determines whether tuples, tuple structs, and tuple variants
+///   must serialize exactly as many elements as their declared length.
+/// - [`sort_map_entries()`]: Determines whether the entries of maps and the fields of structs are
+///   sorted, producing deterministic output for types backed by hash maps.
+/// - [`max_depth()`]: Limits how deeply values may be nested, failing with an error rather than
+///   overflowing the stack when serializing runaway recursive values.
+/// - [`panic_on_error()`]: Determines whether the serializer panics as soon as an error occurs,
+///   preserving the location of the failure when debugging deeply nested implementations.
+/// - [`fail_after_calls()`]: Causes the serializer to fail once a number of its methods have been
+///   called, for testing how a [`Serialize`] implementation propagates errors.
+/// - [`coverage()`]: Records which `Serializer` methods are called into a [`Coverage`] shared
+///   across many serializers, for auditing which branches of an implementation are tested.
+/// - [`post_process()`]: Adds a pass that rewrites the output tokens, for emulating middleware or
+///   generating fixtures for older formats.
+/// - [`profile()`]: Applies a [`Profile`] configuring the options above to approximate a commonly
+///   targeted format.
 ///
 /// # Example
 ///
@@ -134,14 +262,92 @@ pub enum SerializeStructAs {
 /// assert_ok_eq!(true.serialize(&serializer), [Token::Bool(true)]);
 /// ```
 ///
+/// [`char_as_str()`]: Builder::char_as_str()
+/// [`coverage()`]: Builder::coverage()
 /// [`is_human_readable()`]: Builder::is_human_readable()
+/// [`fail_after_calls()`]: Builder::fail_after_calls()
+/// [`max_depth()`]: Builder::max_depth()
+/// [`panic_on_error()`]: Builder::panic_on_error()
+/// [`post_process()`]: Builder::post_process()
+/// [`profile()`]: Builder::profile()
+/// [`serialize_128_as()`]: Builder::serialize_128_as()
+/// [`serialize_bytes_as_seq()`]: Builder::serialize_bytes_as_seq()
+/// [`serialize_enum_as_map()`]: Builder::serialize_enum_as_map()
+/// [`serialize_none_as_unit()`]: Builder::serialize_none_as_unit()
 /// [`serialize_struct_as()`]: Builder::serialize_struct_as()
+/// [`sort_map_entries()`]: Builder::sort_map_entries()
+/// [`support_128_bit()`]: Builder::support_128_bit()
+/// [`validate_tuple_lengths()`]: Builder::validate_tuple_lengths()
 /// [`Serialize`]: serde::Serialize
 /// [`Token`]: crate::Token
+#[allow(clippy::struct_excessive_bools)] // Each bool is an independent configuration option.
 #[derive(Debug)]
 pub struct Serializer {
     is_human_readable: bool,
     serialize_struct_as: SerializeStructAs,
+    serialize_bytes_as_seq: bool,
+    char_as_str: bool,
+    serialize_none_as_unit: bool,
+    serialize_enum_as_map: bool,
+    serialize_128_as: Serialize128As,
+    support_128_bit: bool,
+    validate_tuple_lengths: bool,
+    sort_map_entries: bool,
+    max_depth: Option<usize>,
+    panic_on_error: bool,
+    fail_after_calls: Option<usize>,
+    #[cfg(target_has_atomic = "ptr")]
+    coverage: Option<&'static Coverage>,
+    post_processors: Vec<PostProcessor>,
+}
+
+/// The state of serializing a single outermost value.
+#[derive(Debug, Default)]
+struct State {
+    enum_variants: RefCell<Vec<EnumVariant>>,
+    calls: RefCell<Calls>,
+    /// The number of values currently being serialized within the outermost value.
+    depth: Cell<usize>,
+}
+
+/// A [`Serializer`] together with the state of the outermost value it is serializing.
+///
+/// Every outermost value serialized with a `Serializer` gets a new `Session`, leaving the
+/// `Serializer` itself as configuration only. This allows a single `Serializer` to be shared,
+/// including across threads.
+#[derive(Clone, Debug)]
+struct Session<'a> {
+    serializer: &'a Serializer,
+    state: Rc<State>,
+}
+
+impl<'a> Session<'a> {
+    /// Creates a new `Session` for serializing an outermost value with the given `Serializer`.
+    fn new(serializer: &'a Serializer) -> Self {
+        Self {
+            serializer,
+            state: Rc::new(State::default()),
+        }
+    }
+}
+
+impl Deref for Session<'_> {
+    type Target = Serializer;
+
+    fn deref(&self) -> &Self::Target {
+        self.serializer
+    }
+}
+
+/// Forwards methods of [`serde::Serializer`] to a new [`Session`].
+macro_rules! forward_to_session {
+    ($($method:ident($($arg:ident: $ty:ty),*) -> $ok:ty;)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<$ok, Error> {
+                Session::new(self).$method($($arg),*)
+            }
+        )*
+    };
 }
 
 impl<'a> ser::Serializer for &'a Serializer {
@@ -156,89 +362,242 @@ impl<'a> ser::Serializer for &'a Serializer {
     type SerializeStruct = SerializeStruct<'a>;
     type SerializeStructVariant = CompoundSerializer<'a>;
 
+    forward_to_session! {
+        serialize_bool(v: bool) -> Tokens;
+        serialize_i8(v: i8) -> Tokens;
+        serialize_i16(v: i16) -> Tokens;
+        serialize_i32(v: i32) -> Tokens;
+        serialize_i64(v: i64) -> Tokens;
+        serialize_i128(v: i128) -> Tokens;
+        serialize_u8(v: u8) -> Tokens;
+        serialize_u16(v: u16) -> Tokens;
+        serialize_u32(v: u32) -> Tokens;
+        serialize_u64(v: u64) -> Tokens;
+        serialize_u128(v: u128) -> Tokens;
+        serialize_f32(v: f32) -> Tokens;
+        serialize_f64(v: f64) -> Tokens;
+        serialize_char(v: char) -> Tokens;
+        serialize_str(v: &str) -> Tokens;
+        serialize_bytes(v: &[u8]) -> Tokens;
+        serialize_none() -> Tokens;
+        serialize_unit() -> Tokens;
+        serialize_unit_struct(name: &'static str) -> Tokens;
+        serialize_unit_variant(
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str
+        ) -> Tokens;
+        serialize_seq(len: Option<usize>) -> CompoundSerializer<'a>;
+        serialize_tuple(len: usize) -> CompoundSerializer<'a>;
+        serialize_tuple_struct(name: &'static str, len: usize) -> CompoundSerializer<'a>;
+        serialize_tuple_variant(
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize
+        ) -> CompoundSerializer<'a>;
+        serialize_map(len: Option<usize>) -> CompoundSerializer<'a>;
+        serialize_struct(name: &'static str, len: usize) -> SerializeStruct<'a>;
+        serialize_struct_variant(
+            name: &'static str,
+            variant_index: u32,
+            variant: &'static str,
+            len: usize
+        ) -> CompoundSerializer<'a>;
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Tokens, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Session::new(self).serialize_some(value)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Tokens, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Session::new(self).serialize_newtype_struct(name, value)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Tokens, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Session::new(self).serialize_newtype_variant(name, variant_index, variant, value)
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<Tokens, Error>
+    where
+        T: Display + ?Sized,
+    {
+        Session::new(self).collect_str(value)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.is_human_readable
+    }
+}
+
+impl<'a> ser::Serializer for Session<'a> {
+    type Ok = Tokens;
+    type Error = Error;
+
+    type SerializeSeq = CompoundSerializer<'a>;
+    type SerializeTuple = CompoundSerializer<'a>;
+    type SerializeTupleStruct = CompoundSerializer<'a>;
+    type SerializeTupleVariant = CompoundSerializer<'a>;
+    type SerializeMap = CompoundSerializer<'a>;
+    type SerializeStruct = SerializeStruct<'a>;
+    type SerializeStructVariant = CompoundSerializer<'a>;
+
     fn serialize_bool(self, v: bool) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::Bool(v)]))
+        self.record_call("serialize_bool", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::Bool(v)])))
     }
 
     fn serialize_i8(self, v: i8) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::I8(v)]))
+        self.record_call("serialize_i8", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::I8(v)])))
     }
 
     fn serialize_i16(self, v: i16) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::I16(v)]))
+        self.record_call("serialize_i16", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::I16(v)])))
     }
 
     fn serialize_i32(self, v: i32) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::I32(v)]))
+        self.record_call("serialize_i32", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::I32(v)])))
     }
 
     fn serialize_i64(self, v: i64) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::I64(v)]))
+        self.record_call("serialize_i64", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::I64(v)])))
     }
 
     fn serialize_i128(self, v: i128) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::I128(v)]))
+        self.record_call("serialize_i128", &[])?;
+        if !self.support_128_bit {
+            return Err(self.fail(Error::custom("i128 is not supported"), &[]));
+        }
+        match self.serialize_128_as {
+            Serialize128As::Integer => Ok(self.post_process(Tokens(vec![CanonicalToken::I128(v)]))),
+            Serialize128As::Str => {
+                Ok(self.post_process(Tokens(vec![CanonicalToken::Str(v.to_string())])))
+            }
+            Serialize128As::Bytes => Ok(self.post_process(Tokens(vec![CanonicalToken::Bytes(
+                v.to_be_bytes().to_vec(),
+            )]))),
+        }
     }
 
     fn serialize_u8(self, v: u8) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::U8(v)]))
+        self.record_call("serialize_u8", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::U8(v)])))
     }
 
     fn serialize_u16(self, v: u16) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::U16(v)]))
+        self.record_call("serialize_u16", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::U16(v)])))
     }
 
     fn serialize_u32(self, v: u32) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::U32(v)]))
+        self.record_call("serialize_u32", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::U32(v)])))
     }
 
     fn serialize_u64(self, v: u64) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::U64(v)]))
+        self.record_call("serialize_u64", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::U64(v)])))
     }
 
     fn serialize_u128(self, v: u128) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::U128(v)]))
+        self.record_call("serialize_u128", &[])?;
+        if !self.support_128_bit {
+            return Err(self.fail(Error::custom("u128 is not supported"), &[]));
+        }
+        match self.serialize_128_as {
+            Serialize128As::Integer => Ok(self.post_process(Tokens(vec![CanonicalToken::U128(v)]))),
+            Serialize128As::Str => {
+                Ok(self.post_process(Tokens(vec![CanonicalToken::Str(v.to_string())])))
+            }
+            Serialize128As::Bytes => Ok(self.post_process(Tokens(vec![CanonicalToken::Bytes(
+                v.to_be_bytes().to_vec(),
+            )]))),
+        }
     }
 
     fn serialize_f32(self, v: f32) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::F32(v)]))
+        self.record_call("serialize_f32", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::F32(v)])))
     }
 
     fn serialize_f64(self, v: f64) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::F64(v)]))
+        self.record_call("serialize_f64", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::F64(v)])))
     }
 
     fn serialize_char(self, v: char) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::Char(v)]))
+        self.record_call("serialize_char", &[])?;
+        if self.char_as_str {
+            Ok(self.post_process(Tokens(vec![CanonicalToken::Str(v.to_string())])))
+        } else {
+            Ok(self.post_process(Tokens(vec![CanonicalToken::Char(v)])))
+        }
     }
 
     fn serialize_str(self, v: &str) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::Str(v.to_owned())]))
+        self.record_call("serialize_str", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::Str(v.to_owned())])))
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::Bytes(v.to_owned())]))
+        self.record_call("serialize_bytes", &[])?;
+        if self.serialize_bytes_as_seq {
+            let mut tokens = Vec::with_capacity(v.len() + 2);
+            tokens.push(CanonicalToken::Seq { len: Some(v.len()) });
+            tokens.extend(v.iter().copied().map(CanonicalToken::U8));
+            tokens.push(CanonicalToken::SeqEnd);
+            Ok(self.post_process(Tokens(tokens)))
+        } else {
+            Ok(self.post_process(Tokens(vec![CanonicalToken::Bytes(v.to_owned())])))
+        }
     }
 
     fn serialize_none(self) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::None]))
+        self.record_call("serialize_none", &[])?;
+        if self.serialize_none_as_unit {
+            Ok(self.post_process(Tokens(vec![CanonicalToken::Unit])))
+        } else {
+            Ok(self.post_process(Tokens(vec![CanonicalToken::None])))
+        }
     }
 
     fn serialize_some<T>(self, value: &T) -> Result<Tokens, Error>
     where
         T: Serialize + ?Sized,
     {
+        self.record_call("serialize_some", &[])?;
         let mut tokens = Tokens(vec![CanonicalToken::Some]);
-        tokens.0.extend(value.serialize(self)?.0);
-        Ok(tokens)
+        tokens.0.extend(self.serialize_nested(value, &tokens.0)?.0);
+        Ok(self.post_process(tokens))
     }
 
     fn serialize_unit(self) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::Unit]))
+        self.record_call("serialize_unit", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::Unit])))
     }
 
     fn serialize_unit_struct(self, name: &'static str) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::UnitStruct { name }]))
+        self.record_call("serialize_unit_struct", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::UnitStruct { name }])))
     }
 
     fn serialize_unit_variant(
@@ -247,20 +606,30 @@ impl<'a> ser::Serializer for &'a Serializer {
         variant_index: u32,
         variant: &'static str,
     ) -> Result<Tokens, Error> {
-        Ok(Tokens(vec![CanonicalToken::UnitVariant {
-            name,
-            variant_index,
-            variant,
-        }]))
+        self.record_call("serialize_unit_variant", &[])?;
+        self.record_enum_variant(name, variant_index, variant);
+        let token = if self.serialize_enum_as_map {
+            CanonicalToken::Str(variant.into())
+        } else {
+            CanonicalToken::UnitVariant {
+                id: Box::new(VariantId {
+                    name,
+                    variant_index,
+                    variant,
+                }),
+            }
+        };
+        Ok(self.post_process(Tokens(vec![token])))
     }
 
     fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Tokens, Error>
     where
         T: Serialize + ?Sized,
     {
+        self.record_call("serialize_newtype_struct", &[])?;
         let mut tokens = Tokens(vec![CanonicalToken::NewtypeStruct { name }]);
-        tokens.0.extend(value.serialize(self)?.0);
-        Ok(tokens)
+        tokens.0.extend(self.serialize_nested(value, &tokens.0)?.0);
+        Ok(self.post_process(tokens))
     }
 
     fn serialize_newtype_variant<T>(
@@ -273,28 +642,55 @@ impl<'a> ser::Serializer for &'a Serializer {
     where
         T: Serialize + ?Sized,
     {
-        let mut tokens = Tokens(vec![CanonicalToken::NewtypeVariant {
-            name,
-            variant_index,
-            variant,
-        }]);
-        tokens.0.extend(value.serialize(self)?.0);
-        Ok(tokens)
+        self.record_call("serialize_newtype_variant", &[])?;
+        self.record_enum_variant(name, variant_index, variant);
+        let mut tokens = Tokens(if self.serialize_enum_as_map {
+            vec![
+                CanonicalToken::Map { len: Some(1) },
+                CanonicalToken::Str(variant.into()),
+            ]
+        } else {
+            vec![CanonicalToken::NewtypeVariant {
+                id: Box::new(VariantId {
+                    name,
+                    variant_index,
+                    variant,
+                }),
+            }]
+        });
+        tokens.0.extend(self.serialize_nested(value, &tokens.0)?.0);
+        if self.serialize_enum_as_map {
+            tokens.0.push(CanonicalToken::MapEnd);
+        }
+        Ok(self.post_process(tokens))
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<CompoundSerializer<'a>, Error> {
+        self.record_call("serialize_seq", &[])?;
         Ok(CompoundSerializer {
             tokens: Tokens(vec![CanonicalToken::Seq { len }]),
 
             serializer: self,
+
+            len: None,
+            count: 0,
+
+            entries: Vec::new(),
         })
     }
 
     fn serialize_tuple(self, len: usize) -> Result<CompoundSerializer<'a>, Error> {
+        self.record_call("serialize_tuple", &[])?;
+        let validated_len = self.validate_tuple_lengths.then(|| len);
         Ok(CompoundSerializer {
             tokens: Tokens(vec![CanonicalToken::Tuple { len }]),
 
             serializer: self,
+
+            len: validated_len,
+            count: 0,
+
+            entries: Vec::new(),
         })
     }
 
@@ -303,10 +699,17 @@ impl<'a> ser::Serializer for &'a Serializer {
         name: &'static str,
         len: usize,
     ) -> Result<CompoundSerializer<'a>, Error> {
+        self.record_call("serialize_tuple_struct", &[])?;
+        let validated_len = self.validate_tuple_lengths.then(|| len);
         Ok(CompoundSerializer {
             tokens: Tokens(vec![CanonicalToken::TupleStruct { name, len }]),
 
             serializer: self,
+
+            len: validated_len,
+            count: 0,
+
+            entries: Vec::new(),
         })
     }
 
@@ -317,23 +720,47 @@ impl<'a> ser::Serializer for &'a Serializer {
         variant: &'static str,
         len: usize,
     ) -> Result<CompoundSerializer<'a>, Error> {
+        self.record_call("serialize_tuple_variant", &[])?;
+        self.record_enum_variant(name, variant_index, variant);
+        let validated_len = self.validate_tuple_lengths.then(|| len);
         Ok(CompoundSerializer {
-            tokens: Tokens(vec![CanonicalToken::TupleVariant {
-                name,
-                variant_index,
-                variant,
-                len,
-            }]),
+            tokens: Tokens(if self.serialize_enum_as_map {
+                vec![
+                    CanonicalToken::Map { len: Some(1) },
+                    CanonicalToken::Str(variant.into()),
+                    CanonicalToken::Seq { len: Some(len) },
+                ]
+            } else {
+                vec![CanonicalToken::TupleVariant {
+                    id: Box::new(VariantId {
+                        name,
+                        variant_index,
+                        variant,
+                    }),
+                    len,
+                }]
+            }),
 
             serializer: self,
+
+            len: validated_len,
+            count: 0,
+
+            entries: Vec::new(),
         })
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<CompoundSerializer<'a>, Error> {
+        self.record_call("serialize_map", &[])?;
         Ok(CompoundSerializer {
             tokens: Tokens(vec![CanonicalToken::Map { len }]),
 
             serializer: self,
+
+            len: None,
+            count: 0,
+
+            entries: Vec::new(),
         })
     }
 
@@ -342,20 +769,23 @@ impl<'a> ser::Serializer for &'a Serializer {
         name: &'static str,
         len: usize,
     ) -> Result<SerializeStruct<'a>, Error> {
+        self.record_call("serialize_struct", &[])?;
         match self.serialize_struct_as {
             SerializeStructAs::Struct => Ok(SerializeStruct {
                 tokens: Tokens(vec![CanonicalToken::Struct { name, len }]),
 
                 serializer: self,
 
-                serialize_struct_as: self.serialize_struct_as,
+                serialize_struct_as: SerializeStructAs::Struct,
+                entries: Vec::new(),
             }),
             SerializeStructAs::Seq => Ok(SerializeStruct {
                 tokens: Tokens(vec![CanonicalToken::Seq { len: Some(len) }]),
 
                 serializer: self,
 
-                serialize_struct_as: self.serialize_struct_as,
+                serialize_struct_as: SerializeStructAs::Seq,
+                entries: Vec::new(),
             }),
         }
     }
@@ -367,15 +797,32 @@ impl<'a> ser::Serializer for &'a Serializer {
         variant: &'static str,
         len: usize,
     ) -> Result<CompoundSerializer<'a>, Error> {
+        self.record_call("serialize_struct_variant", &[])?;
+        self.record_enum_variant(name, variant_index, variant);
         Ok(CompoundSerializer {
-            tokens: Tokens(vec![CanonicalToken::StructVariant {
-                name,
-                variant_index,
-                variant,
-                len,
-            }]),
+            tokens: Tokens(if self.serialize_enum_as_map {
+                vec![
+                    CanonicalToken::Map { len: Some(1) },
+                    CanonicalToken::Str(variant.into()),
+                    CanonicalToken::Map { len: Some(len) },
+                ]
+            } else {
+                vec![CanonicalToken::StructVariant {
+                    id: Box::new(VariantId {
+                        name,
+                        variant_index,
+                        variant,
+                    }),
+                    len,
+                }]
+            }),
 
             serializer: self,
+
+            len: None,
+            count: 0,
+
+            entries: Vec::new(),
         })
     }
 
@@ -383,7 +830,8 @@ impl<'a> ser::Serializer for &'a Serializer {
     where
         T: Display + ?Sized,
     {
-        Ok(Tokens(vec![CanonicalToken::Str(value.to_string())]))
+        self.record_call("collect_str", &[])?;
+        Ok(self.post_process(Tokens(vec![CanonicalToken::Str(value.to_string())])))
     }
 
     fn is_human_readable(&self) -> bool {
@@ -392,6 +840,31 @@ impl<'a> ser::Serializer for &'a Serializer {
 }
 
 impl Serializer {
+    /// Creates a new `Serializer` using the default configuration.
+    ///
+    /// This is equivalent to `Serializer::builder().build()`. To configure the `Serializer`, use
+    /// [`builder()`] instead.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let serializer = Serializer::new();
+    ///
+    /// assert_ok_eq!(true.serialize(&serializer), [Token::Bool(true)]);
+    /// ```
+    ///
+    /// [`builder()`]: Serializer::builder()
+    #[must_use]
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
     /// Returns a [`Builder`] for a [`Serializer`].
     ///
     /// # Example
@@ -404,348 +877,2287 @@ impl Serializer {
     pub fn builder() -> Builder {
         Builder::default()
     }
-}
-
-/// A builder for a [`Serializer`].
-///
-/// Construction of a `Serializer` follows the builder pattern. Configuration options can be set on
-/// the `Builder`, and then the actual `Serializer` is constructed by calling [`build()`].
-///
-/// # Example
-/// ``` rust
-/// use serde_assert::Serializer;
-///
-/// let serializer = Serializer::builder().is_human_readable(false).build();
-/// ```
-///
-/// [`build()`]: Builder::build()
-#[derive(Debug)]
-pub struct Builder {
-    is_human_readable: bool,
-    serialize_struct_as: SerializeStructAs,
-}
 
-impl Builder {
-    /// Determines whether the serializer will serialize values in a readable format or a compact
-    /// format.
+    /// Serializes a value using a function with the signature used by `serialize_with`.
     ///
-    /// Useful for complicated structs wishing to provide different outputs depending on
-    /// the readability of the serialization type.
+    /// Functions used with the `#[serde(serialize_with = "...")]` and `#[serde(with = "...")]`
+    /// attributes have the signature `fn<S>(&T, S) -> Result<S::Ok, S::Error>`. This method runs
+    /// such a function using this `Serializer`, returning the output [`Tokens`], allowing these
+    /// functions to be tested without defining wrapper types.
     ///
-    /// If not set, the default value is `true`.
+    /// # Errors
+    /// Returns any [`Error`] returned by the provided function.
     ///
     /// # Example
     /// ``` rust
-    /// use serde_assert::Serializer;
+    /// use claims::assert_ok_eq;
+    /// use serde::Serializer as _;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
     ///
-    /// let serializer = Serializer::builder().is_human_readable(false).build();
+    /// fn serialize_as_string<S>(value: &u32, serializer: S) -> Result<S::Ok, S::Error>
+    /// where
+    ///     S: serde::Serializer,
+    /// {
+    ///     serializer.collect_str(value)
+    /// }
+    ///
+    /// let serializer = Serializer::builder().build();
+    ///
+    /// assert_ok_eq!(
+    ///     serializer.serialize_with(&42, serialize_as_string),
+    ///     [Token::Str("42".to_owned())]
+    /// );
     /// ```
-    pub fn is_human_readable(&mut self, is_human_readable: bool) -> &mut Self {
-        self.is_human_readable = is_human_readable;
-        self
+    pub fn serialize_with<'a, T, F>(&'a self, value: &T, serialize: F) -> Result<Tokens, Error>
+    where
+        T: ?Sized,
+        F: FnOnce(&T, &'a Serializer) -> Result<Tokens, Error>,
+    {
+        serialize(value, self)
     }
 
-    /// Specifies how the serializer should serialize structs.
+    /// Serializes the items of an iterator as a sequence.
     ///
-    /// Compact formats often serialize structs as sequences. By enabling this setting, tokens can
-    /// be produced in this format, and can then be deserialized to ensure structs deserialized as
-    /// sequences are deserialized correctly.
+    /// This is equivalent to calling [`collect_seq()`] on this `Serializer`, and is useful for
+    /// producing the expected [`Tokens`] of iterator-backed collections without defining a
+    /// wrapper type implementing [`Serialize`].
     ///
-    /// If not set, the default value is [`SerializeStructAs::Struct`].
+    /// # Errors
+    /// Returns an [`Error`] if any of the items fails to serialize.
     ///
     /// # Example
     /// ``` rust
     /// use claims::assert_ok_eq;
-    /// use serde::Serialize;
     /// use serde_assert::{
-    ///     ser::SerializeStructAs,
     ///     Serializer,
     ///     Token,
     /// };
-    /// # use serde_derive::Serialize;
-    ///
-    /// #[derive(Serialize)]
-    /// struct Struct {
-    ///     foo: bool,
-    ///     bar: u32,
-    /// }
     ///
-    /// let some_struct = Struct {
-    ///     foo: false,
-    ///     bar: 42,
-    /// };
-    /// let serializer = Serializer::builder()
-    ///     .serialize_struct_as(SerializeStructAs::Seq)
-    ///     .build();
+    /// let serializer = Serializer::builder().build();
     ///
     /// assert_ok_eq!(
-    ///     some_struct.serialize(&serializer),
+    ///     serializer.serialize_iter((1..=2).map(|x: u8| x * 2)),
     ///     [
     ///         Token::Seq { len: Some(2) },
-    ///         Token::Bool(false),
-    ///         Token::U32(42),
+    ///         Token::U8(2),
+    ///         Token::U8(4),
     ///         Token::SeqEnd,
     ///     ]
     /// );
     /// ```
-    pub fn serialize_struct_as(&mut self, serialize_struct_as: SerializeStructAs) -> &mut Self {
-        self.serialize_struct_as = serialize_struct_as;
-        self
+    ///
+    /// [`collect_seq()`]: serde::Serializer::collect_seq()
+    /// [`Serialize`]: serde::Serialize
+    pub fn serialize_iter<I>(&self, iter: I) -> Result<Tokens, Error>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        ser::Serializer::collect_seq(self, iter)
     }
 
-    /// Build a new [`Serializer`] using this `Builder`.
+    /// Transcodes the data from a [`serde::Deserializer`] into [`Tokens`].
     ///
-    /// Constructs a new `Serializer` using the configuration options set on this `Builder`.
+    /// This runs any `Deserializer`, such as one from a real data format, through this
+    /// `Serializer`, recording the exact tokens produced. The output can then be used as a fixture
+    /// for tests.
+    ///
+    /// Note that the data is transcoded using [`deserialize_any()`], so the output is limited to
+    /// what the format is able to describe about itself.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] if the `Deserializer` returns an error.
     ///
     /// # Example
     /// ``` rust
-    /// use serde_assert::Serializer;
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
     ///
-    /// let serializer = Serializer::builder().is_human_readable(false).build();
+    /// let serializer = Serializer::builder().build();
+    /// let mut deserializer = serde_json::Deserializer::from_str("[1, true]");
+    ///
+    /// assert_ok_eq!(
+    ///     serializer.transcode(&mut deserializer),
+    ///     [
+    ///         Token::Seq { len: None },
+    ///         Token::U64(1),
+    ///         Token::Bool(true),
+    ///         Token::SeqEnd,
+    ///     ]
+    /// );
     /// ```
-    pub fn build(&mut self) -> Serializer {
-        Serializer {
-            is_human_readable: self.is_human_readable,
-            serialize_struct_as: self.serialize_struct_as,
-        }
+    ///
+    /// [`deserialize_any()`]: serde::Deserializer::deserialize_any()
+    #[cfg(feature = "serde-transcode")]
+    pub fn transcode<'de, D>(&self, deserializer: D) -> Result<Tokens, Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        serde_transcode::transcode(deserializer, self)
     }
-}
 
-impl Default for Builder {
-    fn default() -> Self {
-        Self {
-            is_human_readable: true,
-            serialize_struct_as: SerializeStructAs::Struct,
-        }
-    }
-}
-
-/// Serializer for serializing compound types.
-///
-/// This type implements [`SerializeSeq`], [`SerializeTuple`], [`SerializeTupleStruct`],
-/// [`SerializeTupleVariant`], [`SerializeMap`], [`SerializeStruct`], and
-/// [`SerializeStructVariant`], and is used by [`Serializer`] for serialization of each of those
-/// compound data types.
-///
-/// Users normally will not need to interact with this type directly. It is primarily used by
-/// [`Serialize`] implementations through the various traits it implements.
-#[derive(Debug)]
-pub struct CompoundSerializer<'a> {
-    tokens: Tokens,
-
-    serializer: &'a Serializer,
-}
-
-impl SerializeSeq for CompoundSerializer<'_> {
-    type Ok = Tokens;
-    type Error = Error;
-
-    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    /// Serializes a value, recording information about how it was serialized.
+    ///
+    /// Along with the output [`Tokens`], the returned [`Recording`] contains the enum variants
+    /// emitted and the serializer methods called, which are not visible from the tokens alone.
+    /// This is otherwise equivalent to calling `value.serialize(&serializer)`.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] if the value fails to serialize.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde_assert::{
+    ///     ser::{
+    ///         EnumRepresentation,
+    ///         EnumVariant,
+    ///     },
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// enum Enum {
+    ///     Foo,
+    ///     Bar(bool),
+    /// }
+    ///
+    /// let serializer = Serializer::new();
+    ///
+    /// let recording = assert_ok!(serializer.record(&Enum::Bar(true)));
+    /// assert_eq!(
+    ///     *recording.tokens(),
+    ///     [
+    ///         Token::NewtypeVariant {
+    ///             name: "Enum",
+    ///             variant_index: 1,
+    ///             variant: "Bar",
+    ///         },
+    ///         Token::Bool(true),
+    ///     ]
+    /// );
+    /// assert_eq!(
+    ///     recording.enum_variants(),
+    ///     [EnumVariant {
+    ///         name: "Enum",
+    ///         variant_index: 1,
+    ///         variant: "Bar",
+    ///         representation: EnumRepresentation::External,
+    ///     }]
+    /// );
+    /// assert_eq!(recording.calls().count("serialize_newtype_variant"), 1);
+    /// ```
+    pub fn record<T>(&self, value: &T) -> Result<Recording, Error>
     where
         T: Serialize + ?Sized,
     {
-        self.tokens.0.extend(value.serialize(self.serializer)?.0);
-        Ok(())
-    }
-
-    fn end(mut self) -> Result<Tokens, Error> {
-        self.tokens.0.push(CanonicalToken::SeqEnd);
-        Ok(self.tokens)
+        let session = Session::new(self);
+        let tokens = value.serialize(session.clone())?;
+        Ok(Recording {
+            tokens,
+            enum_variants: session.state.enum_variants.take(),
+            calls: session.state.calls.take(),
+        })
     }
 }
 
-impl SerializeTuple for CompoundSerializer<'_> {
-    type Ok = Tokens;
-    type Error = Error;
-
-    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+impl Session<'_> {
+    /// Serializes a value nested within the value currently being serialized.
+    ///
+    /// `context` contains the tokens serialized so far for the enclosing value, and is reported if
+    /// the nested value fails to serialize while panicking on errors.
+    fn serialize_nested<T>(&self, value: &T, context: &[CanonicalToken]) -> Result<Tokens, Error>
     where
         T: Serialize + ?Sized,
     {
-        self.tokens.0.extend(value.serialize(self.serializer)?.0);
-        Ok(())
+        if let Some(max_depth) = self.max_depth {
+            if self.state.depth.get() >= max_depth {
+                return Err(self.fail(
+                    Error(format!("exceeded the maximum nesting depth of {max_depth}")),
+                    context,
+                ));
+            }
+        }
+        self.state.depth.set(self.state.depth.get() + 1);
+        let result = value.serialize(self.clone());
+        self.state.depth.set(self.state.depth.get() - 1);
+        result.map_err(|error| self.fail(error, context))
     }
 
-    fn end(mut self) -> Result<Tokens, Error> {
-        self.tokens.0.push(CanonicalToken::TupleEnd);
-        Ok(self.tokens)
+    /// Returns the given error, or panics with it if the `Serializer` panics on errors.
+    ///
+    /// `context` contains the tokens serialized so far for the value in which the error occurred.
+    fn fail(&self, error: Error, context: &[CanonicalToken]) -> Error {
+        assert!(
+            !self.panic_on_error,
+            "serialization failed: {error}\ntokens serialized so far at depth {}: {:?}",
+            self.state.depth.get(),
+            context.iter().cloned().map(Token::from).collect::<Vec<_>>()
+        );
+        error
     }
-}
-
-impl SerializeTupleStruct for CompoundSerializer<'_> {
-    type Ok = Tokens;
-    type Error = Error;
 
-    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
-    where
-        T: Serialize + ?Sized,
-    {
-        self.tokens.0.extend(value.serialize(self.serializer)?.0);
-        Ok(())
+    /// Applies the post-processing passes to the tokens of the outermost value.
+    ///
+    /// Tokens of nested values are returned unchanged, as they will be processed as part of the
+    /// outermost value.
+    fn post_process(&self, tokens: Tokens) -> Tokens {
+        if self.state.depth.get() > 0 {
+            return tokens;
+        }
+        let tokens = if self.post_processors.is_empty() {
+            tokens
+        } else {
+            let mut tokens = tokens.into_iter().collect::<Vec<_>>();
+            for post_processor in &self.post_processors {
+                (post_processor.0)(&mut tokens);
+            }
+            tokens.into_iter().collect()
+        };
+        self.state.calls.borrow_mut().tokens += tokens.0.len();
+        tokens
     }
 
-    fn end(mut self) -> Result<Tokens, Error> {
-        self.tokens.0.push(CanonicalToken::TupleStructEnd);
-        Ok(self.tokens)
+    /// Sorts the entries of a map or the fields of a struct, if the `Serializer` sorts map entries.
+    ///
+    /// `entries` contains the index within `tokens` at which each entry starts. Each entry extends
+    /// to the start of the next, with the last extending to the end of `tokens`.
+    fn sort_entries(&self, tokens: &mut Vec<CanonicalToken>, entries: &[usize]) {
+        if !self.sort_map_entries {
+            return;
+        }
+        let start = match entries.first() {
+            Some(start) => *start,
+            None => return,
+        };
+        let mut remaining = tokens.split_off(start);
+        let mut sorted = Vec::with_capacity(entries.len());
+        for entry in entries[1..].iter().rev() {
+            sorted.push(remaining.split_off(entry - start));
+        }
+        sorted.push(remaining);
+        // Entries are complete values, so no entry's tokens are a prefix of another's.
+        sorted.sort_by(|a, b| compare_tokens(a, b));
+        tokens.extend(sorted.into_iter().flatten());
     }
-}
-
-impl SerializeTupleVariant for CompoundSerializer<'_> {
-    type Ok = Tokens;
-    type Error = Error;
 
-    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
-    where
-        T: Serialize + ?Sized,
-    {
-        self.tokens.0.extend(value.serialize(self.serializer)?.0);
+    /// Records a call to the given method, failing if the injected failure point was reached.
+    ///
+    /// `context` contains the tokens serialized so far for the value on which the method was
+    /// called.
+    fn record_call(&self, method: &'static str, context: &[CanonicalToken]) -> Result<(), Error> {
+        let mut calls = self.state.calls.borrow_mut();
+        if let Some(fail_after_calls) = self.fail_after_calls {
+            if calls.total >= fail_after_calls {
+                return Err(self.fail(
+                    Error(format!("injected failure after {fail_after_calls} calls")),
+                    context,
+                ));
+            }
+        }
+        calls.total += 1;
+        *calls.counts.entry(method).or_insert(0) += 1;
+        #[cfg(target_has_atomic = "ptr")]
+        if let Some(coverage) = self.coverage {
+            coverage.record(method);
+        }
         Ok(())
     }
 
-    fn end(mut self) -> Result<Tokens, Error> {
-        self.tokens.0.push(CanonicalToken::TupleVariantEnd);
-        Ok(self.tokens)
+    fn record_enum_variant(&self, name: &'static str, variant_index: u32, variant: &'static str) {
+        self.state.enum_variants.borrow_mut().push(EnumVariant {
+            name,
+            variant_index,
+            variant,
+            representation: if self.serialize_enum_as_map {
+                EnumRepresentation::Map
+            } else {
+                EnumRepresentation::External
+            },
+        });
     }
 }
 
-impl SerializeMap for CompoundSerializer<'_> {
-    type Ok = Tokens;
-    type Error = Error;
-
-    fn serialize_key<T>(&mut self, value: &T) -> Result<(), Error>
-    where
-        T: Serialize + ?Sized,
-    {
-        self.tokens.0.extend(value.serialize(self.serializer)?.0);
-        Ok(())
+/// Compares sequences of tokens for sorting map entries and struct fields.
+///
+/// Tokens of the same kind are compared by their values, while tokens of different kinds are
+/// compared by their `Debug` representations, which begin with the name of their kind.
+fn compare_tokens(a: &[CanonicalToken], b: &[CanonicalToken]) -> cmp::Ordering {
+    for (a, b) in a.iter().zip(b) {
+        let ordering = match (a, b) {
+            (CanonicalToken::Bool(a), CanonicalToken::Bool(b)) => a.cmp(b),
+            (CanonicalToken::I8(a), CanonicalToken::I8(b)) => a.cmp(b),
+            (CanonicalToken::I16(a), CanonicalToken::I16(b)) => a.cmp(b),
+            (CanonicalToken::I32(a), CanonicalToken::I32(b)) => a.cmp(b),
+            (CanonicalToken::I64(a), CanonicalToken::I64(b)) => a.cmp(b),
+            (CanonicalToken::I128(a), CanonicalToken::I128(b)) => a.cmp(b),
+            (CanonicalToken::U8(a), CanonicalToken::U8(b)) => a.cmp(b),
+            (CanonicalToken::U16(a), CanonicalToken::U16(b)) => a.cmp(b),
+            (CanonicalToken::U32(a), CanonicalToken::U32(b)) => a.cmp(b),
+            (CanonicalToken::U64(a), CanonicalToken::U64(b)) => a.cmp(b),
+            (CanonicalToken::U128(a), CanonicalToken::U128(b)) => a.cmp(b),
+            // `NaN`s are ordered by their bits, keeping the order total.
+            (CanonicalToken::F32(a), CanonicalToken::F32(b)) => a
+                .partial_cmp(b)
+                .unwrap_or_else(|| a.to_bits().cmp(&b.to_bits())),
+            (CanonicalToken::F64(a), CanonicalToken::F64(b)) => a
+                .partial_cmp(b)
+                .unwrap_or_else(|| a.to_bits().cmp(&b.to_bits())),
+            (CanonicalToken::Char(a), CanonicalToken::Char(b)) => a.cmp(b),
+            (CanonicalToken::Str(a), CanonicalToken::Str(b)) => a.cmp(b),
+            (CanonicalToken::Bytes(a), CanonicalToken::Bytes(b)) => a.cmp(b),
+            // Lengths are ignored, so that sequences are ordered by their elements.
+            (CanonicalToken::Seq { .. }, CanonicalToken::Seq { .. })
+            | (CanonicalToken::Tuple { .. }, CanonicalToken::Tuple { .. })
+            | (CanonicalToken::Map { .. }, CanonicalToken::Map { .. }) => cmp::Ordering::Equal,
+            (
+                CanonicalToken::Field(a) | CanonicalToken::SkippedField(a),
+                CanonicalToken::Field(b) | CanonicalToken::SkippedField(b),
+            ) => a.cmp(b),
+            _ => format!("{a:?}").cmp(&format!("{b:?}")),
+        };
+        if ordering != cmp::Ordering::Equal {
+            return ordering;
+        }
     }
+    a.len().cmp(&b.len())
+}
 
-    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
-    where
-        T: Serialize + ?Sized,
-    {
-        self.tokens.0.extend(value.serialize(self.serializer)?.0);
-        Ok(())
+impl Default for Serializer {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn end(mut self) -> Result<Tokens, Error> {
-        self.tokens.0.push(CanonicalToken::MapEnd);
-        Ok(self.tokens)
-    }
+/// Serializes a value into [`Tokens`] using a default [`Serializer`].
+///
+/// This is equivalent to `value.serialize(&Serializer::builder().build())`. To serialize using a
+/// configured `Serializer`, use [`to_tokens_with()`].
+///
+/// # Errors
+/// Returns an [`Error`] if the value fails to serialize.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde_assert::{
+///     to_tokens,
+///     Token,
+/// };
+///
+/// assert_ok_eq!(to_tokens(&Some(42u8)), [Token::Some, Token::U8(42)]);
+/// ```
+pub fn to_tokens<T>(value: &T) -> Result<Tokens, Error>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(&Serializer::builder().build())
 }
 
-impl SerializeStructVariant for CompoundSerializer<'_> {
-    type Ok = Tokens;
-    type Error = Error;
+/// Serializes a value into [`Tokens`] using a [`Serializer`] built from the given [`Builder`].
+///
+/// # Errors
+/// Returns an [`Error`] if the value fails to serialize.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde_assert::{
+///     to_tokens_with,
+///     Serializer,
+///     Token,
+/// };
+///
+/// assert_ok_eq!(
+///     to_tokens_with(&'a', Serializer::builder().char_as_str(true)),
+///     [Token::Str("a".to_owned())]
+/// );
+/// ```
+pub fn to_tokens_with<T>(value: &T, builder: &mut Builder) -> Result<Tokens, Error>
+where
+    T: Serialize + ?Sized,
+{
+    value.serialize(&builder.build())
+}
 
-    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
-    where
-        T: Serialize + ?Sized,
-    {
-        self.tokens.0.push(CanonicalToken::Field(key));
-        self.tokens.0.extend(value.serialize(self.serializer)?.0);
-        Ok(())
+/// The output of serializing a value with [`Serializer::record()`].
+///
+/// Along with the serialized [`Tokens`], this contains information about how the value was
+/// serialized that is not visible from the tokens alone.
+#[derive(Clone, Debug)]
+pub struct Recording {
+    tokens: Tokens,
+    enum_variants: Vec<EnumVariant>,
+    calls: Calls,
+}
+
+impl Recording {
+    /// Returns the serialized tokens.
+    #[must_use]
+    pub fn tokens(&self) -> &Tokens {
+        &self.tokens
     }
 
-    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
-        self.tokens.0.push(CanonicalToken::SkippedField(key));
-        Ok(())
+    /// Returns the serialized tokens, consuming the `Recording`.
+    #[must_use]
+    pub fn into_tokens(self) -> Tokens {
+        self.tokens
     }
 
-    fn end(mut self) -> Result<Tokens, Error> {
-        self.tokens.0.push(CanonicalToken::StructVariantEnd);
-        Ok(self.tokens)
+    /// Returns the enum variants emitted, in the order they were emitted.
+    ///
+    /// This allows asserting which variant and representation were chosen directly, rather than
+    /// reverse-engineering them from the output [`Tokens`].
+    #[must_use]
+    pub fn enum_variants(&self) -> &[EnumVariant] {
+        &self.enum_variants
+    }
+
+    /// Returns counts of the serializer methods called.
+    ///
+    /// Every method of [`serde::Serializer`] and of the compound serialization traits, such as
+    /// [`SerializeSeq`], is counted. Unlike recording a full trace, counting is cheap, making it
+    /// suitable for asserting that serialization of a value does not make more calls than
+    /// expected.
+    #[must_use]
+    pub fn calls(&self) -> &Calls {
+        &self.calls
     }
 }
 
-/// Serializer for serializing `struct`s.
+/// Counts of the methods called on a [`Serializer`].
 ///
-/// Users normally will not need to interact with this type directly. It is primarily used by
-/// [`Serialize`] implementations through the [`serde::ser::SerializeStruct`] trait it implements.
-pub struct SerializeStruct<'a> {
-    tokens: Tokens,
-
-    serializer: &'a Serializer,
-
-    serialize_struct_as: SerializeStructAs,
+/// Obtained by calling [`Recording::calls()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Calls {
+    counts: BTreeMap<&'static str, usize>,
+    total: usize,
+    tokens: usize,
 }
 
-impl ser::SerializeStruct for SerializeStruct<'_> {
-    type Ok = Tokens;
-    type Error = Error;
+impl Calls {
+    /// Returns the number of calls to the method with the given name, such as `"serialize_u32"`
+    /// or `"serialize_element"`.
+    #[must_use]
+    pub fn count(&self, method: &str) -> usize {
+        self.counts.get(method).copied().unwrap_or(0)
+    }
 
-    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
-    where
-        T: Serialize + ?Sized,
-    {
-        if matches!(self.serialize_struct_as, SerializeStructAs::Struct) {
-            self.tokens.0.push(CanonicalToken::Field(key));
-        }
-        self.tokens.0.extend(value.serialize(self.serializer)?.0);
-        Ok(())
+    /// Returns the number of calls to each method that was called at least once.
+    #[must_use]
+    pub fn counts(&self) -> &BTreeMap<&'static str, usize> {
+        &self.counts
     }
 
-    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
-        self.tokens.0.push(CanonicalToken::SkippedField(key));
-        Ok(())
+    /// Returns the total number of method calls.
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.total
     }
 
-    fn end(mut self) -> Result<Tokens, Error> {
-        self.tokens.0.push(match self.serialize_struct_as {
-            SerializeStructAs::Struct => CanonicalToken::StructEnd,
-            SerializeStructAs::Seq => CanonicalToken::SeqEnd,
-        });
-        Ok(self.tokens)
+    /// Returns the total number of tokens output for serialized values.
+    #[must_use]
+    pub fn tokens(&self) -> usize {
+        self.tokens
     }
 }
 
-/// An error encountered during serialization.
+/// The entry points of [`serde::Serializer`] tracked by a [`Coverage`].
+#[cfg(target_has_atomic = "ptr")]
+const SERIALIZER_METHODS: [&str; 30] = [
+    "serialize_bool",
+    "serialize_i8",
+    "serialize_i16",
+    "serialize_i32",
+    "serialize_i64",
+    "serialize_i128",
+    "serialize_u8",
+    "serialize_u16",
+    "serialize_u32",
+    "serialize_u64",
+    "serialize_u128",
+    "serialize_f32",
+    "serialize_f64",
+    "serialize_char",
+    "serialize_str",
+    "serialize_bytes",
+    "serialize_none",
+    "serialize_some",
+    "serialize_unit",
+    "serialize_unit_struct",
+    "serialize_unit_variant",
+    "serialize_newtype_struct",
+    "serialize_newtype_variant",
+    "serialize_seq",
+    "serialize_tuple",
+    "serialize_tuple_struct",
+    "serialize_tuple_variant",
+    "serialize_map",
+    "serialize_struct",
+    "serialize_struct_variant",
+];
+
+/// A collector of which [`serde::Serializer`] entry points were called, aggregated across many
+/// [`Serializer`]s.
+///
+/// Unlike [`Calls`], which are recorded per `Serializer`, a `Coverage` is intended to be declared
+/// as a `static` and shared by every `Serializer` in a test run using [`Builder::coverage()`]. The
+/// entry points that were never called can then be listed, which helps audit that the rarely used
+/// branches of a hand-written [`Serialize`] implementation, such as those serializing 128-bit
+/// integers, bytes, or unit variants, are actually tested.
+///
+/// Only the methods of [`serde::Serializer`] itself are tracked, not the methods of the compound
+/// serialization traits. The [`Display`] implementation prints a summary of the methods that were
+/// and were not called.
+///
+/// `Coverage` counts calls atomically, and is therefore only available on targets supporting
+/// atomic operations on pointer-sized integers.
 ///
 /// # Example
-/// ```rust
-/// use serde::ser::Error as _;
-/// use serde_assert::ser::Error;
+/// ``` rust
+/// use claims::assert_ok;
+/// use serde::Serialize;
+/// use serde_assert::{
+///     ser::Coverage,
+///     Serializer,
+/// };
 ///
-/// assert_eq!(format!("{}", Error::custom("foo")), "foo");
+/// static COVERAGE: Coverage = Coverage::new();
+///
+/// let serializer = Serializer::builder().coverage(&COVERAGE).build();
+/// assert_ok!(Some(42u8).serialize(&serializer));
+///
+/// assert_eq!(COVERAGE.count("serialize_some"), 1);
+/// assert_eq!(COVERAGE.hit(), ["serialize_u8", "serialize_some"]);
+/// assert!(COVERAGE.missed().contains(&"serialize_none"));
+///
+/// println!("{COVERAGE}");
 /// ```
-#[derive(Debug, Eq, PartialEq)]
-pub struct Error(pub String);
-
-impl Display for Error {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        self.0.fmt(formatter)
-    }
+///
+/// [`Serialize`]: serde::Serialize
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug)]
+pub struct Coverage {
+    counts: [AtomicUsize; SERIALIZER_METHODS.len()],
 }
 
-impl ser::StdError for Error {}
-
-impl ser::Error for Error {
-    fn custom<T>(msg: T) -> Self
-    where
-        T: Display,
-    {
-        Self(msg.to_string())
+#[cfg(target_has_atomic = "ptr")]
+impl Coverage {
+    /// Creates a new `Coverage` with no recorded calls.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::ser::Coverage;
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    /// ```
+    #[must_use]
+    pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)] // Only used to initialize the array.
+        const ZERO: AtomicUsize = AtomicUsize::new(0);
+        Self {
+            counts: [ZERO; SERIALIZER_METHODS.len()],
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{
-        Error,
+    /// Returns the number of calls to the [`serde::Serializer`] method with the given name, such
+    /// as `"serialize_u32"`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     ser::Coverage,
+    ///     Serializer,
+    /// };
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// let serializer = Serializer::builder().coverage(&COVERAGE).build();
+    /// assert_ok!(42u32.serialize(&serializer));
+    ///
+    /// assert_eq!(COVERAGE.count("serialize_u32"), 1);
+    /// assert_eq!(COVERAGE.count("serialize_bool"), 0);
+    /// ```
+    #[must_use]
+    pub fn count(&self, method: &str) -> usize {
+        SERIALIZER_METHODS
+            .iter()
+            .position(|name| *name == method)
+            .map_or(0, |index| self.counts[index].load(Ordering::Relaxed))
+    }
+
+    /// Returns the names of the methods that were called at least once, in the order they are
+    /// declared on [`serde::Serializer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     ser::Coverage,
+    ///     Serializer,
+    /// };
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// let serializer = Serializer::builder().coverage(&COVERAGE).build();
+    /// assert_ok!(vec![true].serialize(&serializer));
+    ///
+    /// assert_eq!(COVERAGE.hit(), ["serialize_bool", "serialize_seq"]);
+    /// ```
+    #[must_use]
+    pub fn hit(&self) -> Vec<&'static str> {
+        self.methods(|count| count > 0)
+    }
+
+    /// Returns the names of the methods that were never called, in the order they are declared on
+    /// [`serde::Serializer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::ser::Coverage;
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// assert_eq!(COVERAGE.missed().len(), 30);
+    /// ```
+    #[must_use]
+    pub fn missed(&self) -> Vec<&'static str> {
+        self.methods(|count| count == 0)
+    }
+
+    /// Clears all recorded calls.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     ser::Coverage,
+    ///     Serializer,
+    /// };
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// let serializer = Serializer::builder().coverage(&COVERAGE).build();
+    /// assert_ok!(true.serialize(&serializer));
+    /// COVERAGE.reset();
+    ///
+    /// assert_eq!(COVERAGE.hit(), [] as [&str; 0]);
+    /// ```
+    pub fn reset(&self) {
+        for count in &self.counts {
+            count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn methods<F>(&self, filter: F) -> Vec<&'static str>
+    where
+        F: Fn(usize) -> bool,
+    {
+        SERIALIZER_METHODS
+            .iter()
+            .zip(&self.counts)
+            .filter(|(_, count)| filter(count.load(Ordering::Relaxed)))
+            .map(|(method, _)| *method)
+            .collect()
+    }
+
+    fn record(&self, method: &str) {
+        if let Some(index) = SERIALIZER_METHODS.iter().position(|name| *name == method) {
+            self.counts[index].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hit = self.hit();
+        write!(
+            f,
+            "serializer coverage: {} of {} methods called",
+            hit.len(),
+            SERIALIZER_METHODS.len()
+        )?;
+        for method in hit {
+            write!(f, "\n  hit:    {method} ({})", self.count(method))?;
+        }
+        for method in self.missed() {
+            write!(f, "\n  missed: {method}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A builder for a [`Serializer`].
+///
+/// Construction of a `Serializer` follows the builder pattern. Configuration options can be set on
+/// the `Builder`, and then the actual `Serializer` is constructed by calling [`build()`].
+///
+/// # Example
+/// ``` rust
+/// use serde_assert::Serializer;
+///
+/// let serializer = Serializer::builder().is_human_readable(false).build();
+/// ```
+///
+/// [`build()`]: Builder::build()
+#[allow(clippy::struct_excessive_bools)] // Each bool is an independent configuration option.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    is_human_readable: bool,
+    serialize_struct_as: SerializeStructAs,
+    serialize_bytes_as_seq: bool,
+    char_as_str: bool,
+    serialize_none_as_unit: bool,
+    serialize_enum_as_map: bool,
+    serialize_128_as: Serialize128As,
+    support_128_bit: bool,
+    validate_tuple_lengths: bool,
+    sort_map_entries: bool,
+    max_depth: Option<usize>,
+    panic_on_error: bool,
+    fail_after_calls: Option<usize>,
+    #[cfg(target_has_atomic = "ptr")]
+    coverage: Option<&'static Coverage>,
+    post_processors: Vec<PostProcessor>,
+}
+
+impl Builder {
+    /// Determines whether the serializer will serialize values in a readable format or a compact
+    /// format.
+    ///
+    /// Useful for complicated structs wishing to provide different outputs depending on
+    /// the readability of the serialization type.
+    ///
+    /// If not set, the default value is `true`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Serializer;
+    ///
+    /// let serializer = Serializer::builder().is_human_readable(false).build();
+    /// ```
+    pub fn is_human_readable(&mut self, is_human_readable: bool) -> &mut Self {
+        self.is_human_readable = is_human_readable;
+        self
+    }
+
+    /// Specifies how the serializer should serialize structs.
+    ///
+    /// Compact formats often serialize structs as sequences. By enabling this setting, tokens can
+    /// be produced in this format, and can then be deserialized to ensure structs deserialized as
+    /// sequences are deserialized correctly.
+    ///
+    /// If not set, the default value is [`SerializeStructAs::Struct`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     ser::SerializeStructAs,
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Struct {
+    ///     foo: bool,
+    ///     bar: u32,
+    /// }
+    ///
+    /// let some_struct = Struct {
+    ///     foo: false,
+    ///     bar: 42,
+    /// };
+    /// let serializer = Serializer::builder()
+    ///     .serialize_struct_as(SerializeStructAs::Seq)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(
+    ///     some_struct.serialize(&serializer),
+    ///     [
+    ///         Token::Seq { len: Some(2) },
+    ///         Token::Bool(false),
+    ///         Token::U32(42),
+    ///         Token::SeqEnd,
+    ///     ]
+    /// );
+    /// ```
+    pub fn serialize_struct_as(&mut self, serialize_struct_as: SerializeStructAs) -> &mut Self {
+        self.serialize_struct_as = serialize_struct_as;
+        self
+    }
+
+    /// Determines whether byte slices are serialized as sequences of `u8`s.
+    ///
+    /// Formats without a native bytes type serialize byte slices as sequences. When enabled,
+    /// [`serialize_bytes()`] outputs a [`Token::Seq`], a [`Token::U8`] for each byte, and a
+    /// [`Token::SeqEnd`] instead of a single [`Token::Bytes`].
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// use serde_bytes::Bytes;
+    ///
+    /// let serializer = Serializer::builder().serialize_bytes_as_seq(true).build();
+    ///
+    /// assert_ok_eq!(
+    ///     Bytes::new(b"ab").serialize(&serializer),
+    ///     [
+    ///         Token::Seq { len: Some(2) },
+    ///         Token::U8(b'a'),
+    ///         Token::U8(b'b'),
+    ///         Token::SeqEnd,
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`serialize_bytes()`]: serde::Serializer::serialize_bytes()
+    /// [`Token::Bytes`]: crate::Token::Bytes
+    /// [`Token::Seq`]: crate::Token::Seq
+    /// [`Token::SeqEnd`]: crate::Token::SeqEnd
+    /// [`Token::U8`]: crate::Token::U8
+    pub fn serialize_bytes_as_seq(&mut self, serialize_bytes_as_seq: bool) -> &mut Self {
+        self.serialize_bytes_as_seq = serialize_bytes_as_seq;
+        self
+    }
+
+    /// Determines whether `char`s are serialized as single-character strings.
+    ///
+    /// JSON-like formats encode `char`s as strings. When enabled, [`serialize_char()`] outputs a
+    /// [`Token::Str`] instead of a [`Token::Char`].
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let serializer = Serializer::builder().char_as_str(true).build();
+    ///
+    /// assert_ok_eq!('a'.serialize(&serializer), [Token::Str("a".to_owned())]);
+    /// ```
+    ///
+    /// [`serialize_char()`]: serde::Serializer::serialize_char()
+    /// [`Token::Char`]: crate::Token::Char
+    /// [`Token::Str`]: crate::Token::Str
+    pub fn char_as_str(&mut self, char_as_str: bool) -> &mut Self {
+        self.char_as_str = char_as_str;
+        self
+    }
+
+    /// Determines whether `None` is serialized as a unit value.
+    ///
+    /// Some formats encode `None` as a null value rather than an absent marker. When enabled,
+    /// [`serialize_none()`] outputs a [`Token::Unit`] instead of a [`Token::None`].
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let serializer = Serializer::builder().serialize_none_as_unit(true).build();
+    ///
+    /// assert_ok_eq!(Option::<u32>::None.serialize(&serializer), [Token::Unit]);
+    /// ```
+    ///
+    /// [`serialize_none()`]: serde::Serializer::serialize_none()
+    /// [`Token::None`]: crate::Token::None
+    /// [`Token::Unit`]: crate::Token::Unit
+    pub fn serialize_none_as_unit(&mut self, serialize_none_as_unit: bool) -> &mut Self {
+        self.serialize_none_as_unit = serialize_none_as_unit;
+        self
+    }
+
+    /// Determines whether enum variants are serialized as maps from their variant name to their
+    /// contents.
+    ///
+    /// Self-describing formats like JSON have no native representation of enums. When enabled,
+    /// unit variants are serialized as a [`Token::Str`] containing the variant name, and all other
+    /// variants are serialized as a map containing a single entry from the variant name to the
+    /// variant's contents. Tuple variants contain a sequence of their fields, and struct variants
+    /// contain a map from each field name to its value, omitting skipped fields.
+    ///
+    /// Tokens serialized this way can be deserialized by a [`Deserializer`] with
+    /// [`deserialize_enum_from_map()`] enabled.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// enum Shape {
+    ///     Point,
+    ///     Circle(u32),
+    /// }
+    ///
+    /// let serializer = Serializer::builder().serialize_enum_as_map(true).build();
+    ///
+    /// assert_ok_eq!(
+    ///     Shape::Point.serialize(&serializer),
+    ///     [Token::Str("Point".to_owned())]
+    /// );
+    /// assert_ok_eq!(
+    ///     Shape::Circle(42).serialize(&serializer),
+    ///     [
+    ///         Token::Map { len: Some(1) },
+    ///         Token::Str("Circle".to_owned()),
+    ///         Token::U32(42),
+    ///         Token::MapEnd,
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`Deserializer`]: crate::Deserializer
+    /// [`deserialize_enum_from_map()`]: crate::de::Builder::deserialize_enum_from_map()
+    /// [`Token::Str`]: crate::Token::Str
+    pub fn serialize_enum_as_map(&mut self, serialize_enum_as_map: bool) -> &mut Self {
+        self.serialize_enum_as_map = serialize_enum_as_map;
+        self
+    }
+
+    /// Specifies how the serializer should serialize 128-bit integers.
+    ///
+    /// Some formats only support `i128` and `u128` through a string or byte encoding. Setting this
+    /// to something other than [`Serialize128As::Integer`] causes 128-bit integers to be output in
+    /// that encoding.
+    ///
+    /// If not set, the default value is [`Serialize128As::Integer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     ser::Serialize128As,
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let serializer = Serializer::builder()
+    ///     .serialize_128_as(Serialize128As::Str)
+    ///     .build();
+    ///
+    /// assert_ok_eq!(42u128.serialize(&serializer), [Token::Str("42".to_owned())]);
+    /// ```
+    pub fn serialize_128_as(&mut self, serialize_128_as: Serialize128As) -> &mut Self {
+        self.serialize_128_as = serialize_128_as;
+        self
+    }
+
+    /// Determines whether the serializer supports 128-bit integers.
+    ///
+    /// Many formats don't support `i128` and `u128`, and return an error from
+    /// [`serialize_i128()`] and [`serialize_u128()`]. When disabled, those methods return an
+    /// [`Error`] regardless of [`serialize_128_as()`], allowing the fallback paths of
+    /// [`Serialize`] implementations targeting such formats to be tested.
+    ///
+    /// If not set, the default value is `true`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     ser::Error,
+    ///     Serializer,
+    /// };
+    ///
+    /// let serializer = Serializer::builder().support_128_bit(false).build();
+    ///
+    /// assert_err_eq!(
+    ///     42u128.serialize(&serializer),
+    ///     Error("u128 is not supported".to_owned())
+    /// );
+    /// ```
+    ///
+    /// [`serialize_128_as()`]: Builder::serialize_128_as()
+    /// [`serialize_i128()`]: serde::Serializer::serialize_i128()
+    /// [`serialize_u128()`]: serde::Serializer::serialize_u128()
+    pub fn support_128_bit(&mut self, support_128_bit: bool) -> &mut Self {
+        self.support_128_bit = support_128_bit;
+        self
+    }
+
+    /// Determines whether the serializer validates the lengths of tuples, tuple structs, and tuple
+    /// variants.
+    ///
+    /// Positional formats rely on the `len` passed to [`serialize_tuple()`],
+    /// [`serialize_tuple_struct()`], and [`serialize_tuple_variant()`] matching the number of
+    /// elements actually serialized. Enabling this causes serialization to fail with an [`Error`]
+    /// when they differ, catching mistakes in manual [`Serialize`] implementations.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err;
+    /// use serde::{
+    ///     ser::SerializeTuple,
+    ///     Serialize,
+    /// };
+    /// use serde_assert::Serializer;
+    ///
+    /// struct Pair;
+    ///
+    /// impl Serialize for Pair {
+    ///     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    ///     where
+    ///         S: serde::Serializer,
+    ///     {
+    ///         let mut tuple = serializer.serialize_tuple(2)?;
+    ///         tuple.serialize_element(&1u8)?;
+    ///         tuple.end()
+    ///     }
+    /// }
+    ///
+    /// let serializer = Serializer::builder().validate_tuple_lengths(true).build();
+    ///
+    /// assert_err!(Pair.serialize(&serializer));
+    /// ```
+    ///
+    /// [`serialize_tuple()`]: serde::Serializer::serialize_tuple()
+    /// [`serialize_tuple_struct()`]: serde::Serializer::serialize_tuple_struct()
+    /// [`serialize_tuple_variant()`]: serde::Serializer::serialize_tuple_variant()
+    pub fn validate_tuple_lengths(&mut self, validate_tuple_lengths: bool) -> &mut Self {
+        self.validate_tuple_lengths = validate_tuple_lengths;
+        self
+    }
+
+    /// Determines whether the entries of maps and the fields of structs are sorted.
+    ///
+    /// The entries of each map are buffered and output sorted by their key tokens, making the
+    /// output of types whose iteration order is unspecified, such as [`HashMap`], deterministic.
+    /// The expected tokens can then be written as a single sequence, rather than using
+    /// [`Token::Unordered`]. The fields of structs and struct variants are likewise sorted by
+    /// name.
+    ///
+    /// Tokens of the same kind are sorted by their values, so integer keys are sorted numerically
+    /// and string keys are sorted lexicographically. Tokens of different kinds are sorted by the
+    /// name of their kind. Structs serialized as sequences using [`serialize_struct_as()`] are not
+    /// sorted.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// use std::collections::HashMap;
+    ///
+    /// let serializer = Serializer::builder().sort_map_entries(true).build();
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert(10u8, 'b');
+    /// map.insert(2u8, 'a');
+    ///
+    /// assert_ok_eq!(
+    ///     map.serialize(&serializer),
+    ///     [
+    ///         Token::Map { len: Some(2) },
+    ///         Token::U8(2),
+    ///         Token::Char('a'),
+    ///         Token::U8(10),
+    ///         Token::Char('b'),
+    ///         Token::MapEnd,
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`HashMap`]: std::collections::HashMap
+    /// [`serialize_struct_as()`]: Builder::serialize_struct_as()
+    pub fn sort_map_entries(&mut self, sort_map_entries: bool) -> &mut Self {
+        self.sort_map_entries = sort_map_entries;
+        self
+    }
+
+    /// Limits how deeply values may be nested within the outermost value being serialized.
+    ///
+    /// The outermost value has a depth of `0`, and each value nested within it, such as an element
+    /// of a sequence or the value within a `Some`, is one level deeper than its parent. When a
+    /// value would be nested beyond `max_depth`, serialization fails with an [`Error`].
+    ///
+    /// Serialization follows the recursion of the [`Serialize`] implementation, so runaway or
+    /// extremely deep recursive values can otherwise overflow the stack. Setting a limit turns
+    /// these into ordinary errors that can be asserted on.
+    ///
+    /// If not set, nesting depth is unlimited.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::{
+    ///     assert_err,
+    ///     assert_ok,
+    /// };
+    /// use serde::Serialize;
+    /// use serde_assert::Serializer;
+    ///
+    /// let serializer = Serializer::builder().max_depth(2).build();
+    ///
+    /// assert_ok!(Some(Some(42u8)).serialize(&serializer));
+    /// assert_err!(Some(Some(Some(42u8))).serialize(&serializer));
+    /// ```
+    pub fn max_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Determines whether the serializer panics as soon as an error occurs.
+    ///
+    /// Errors are normally returned up through every enclosing [`Serialize`] implementation, losing
+    /// the location where serialization actually failed. When enabled, the serializer instead
+    /// panics as soon as an error is produced by the serializer itself or returned from a nested
+    /// value. The panic message includes the error along with the tokens serialized so far for the
+    /// enclosing value and its nesting depth. Running with `RUST_BACKTRACE=1` then shows the
+    /// implementations that were being serialized at the time of failure.
+    ///
+    /// Errors produced by the serializer itself, such as unsupported 128-bit integers or failures
+    /// injected by [`fail_after_calls()`], always panic, even when serializing the outermost value.
+    /// Errors returned directly by the outermost value's [`Serialize`] implementation are returned
+    /// as usual, since their location is already known.
+    ///
+    /// If not set, the default value is `false`.
+    ///
+    /// # Example
+    /// ``` rust,should_panic
+    /// use serde::Serialize;
+    /// use serde_assert::Serializer;
+    ///
+    /// let serializer = Serializer::builder()
+    ///     .max_depth(1)
+    ///     .panic_on_error(true)
+    ///     .build();
+    ///
+    /// // Panics, rather than returning an error.
+    /// let _ = vec![vec![1u8]].serialize(&serializer);
+    /// ```
+    ///
+    /// [`fail_after_calls()`]: Builder::fail_after_calls()
+    pub fn panic_on_error(&mut self, panic_on_error: bool) -> &mut Self {
+        self.panic_on_error = panic_on_error;
+        self
+    }
+
+    /// Causes the serializer to fail once `fail_after_calls` of its methods have been called.
+    ///
+    /// Every method counted by [`Recording::calls()`] is included, such as [`serialize_seq()`]
+    /// and each call to [`serialize_element()`]. Any further call returns an [`Error`]. Calls are
+    /// counted separately for each outermost value serialized. Injecting
+    /// a failure at each call in turn allows verifying that a [`Serialize`] implementation
+    /// propagates errors from every point, such as from within the loop serializing the elements
+    /// of a sequence.
+    ///
+    /// If not set, the serializer never fails because of the number of calls made.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     ser::Error,
+    ///     Serializer,
+    /// };
+    ///
+    /// let serializer = Serializer::builder().fail_after_calls(2).build();
+    ///
+    /// assert_err_eq!(
+    ///     vec![1u32, 2, 3].serialize(&serializer),
+    ///     Error("injected failure after 2 calls".to_owned())
+    /// );
+    /// ```
+    ///
+    /// [`serialize_element()`]: serde::ser::SerializeSeq::serialize_element()
+    /// [`serialize_seq()`]: serde::Serializer::serialize_seq()
+    pub fn fail_after_calls(&mut self, fail_after_calls: usize) -> &mut Self {
+        self.fail_after_calls = Some(fail_after_calls);
+        self
+    }
+
+    /// Records the [`serde::Serializer`] methods called by the serializer into the given
+    /// [`Coverage`].
+    ///
+    /// The `Coverage` is shared by every `Serializer` built with it, allowing calls to be
+    /// aggregated across an entire test run. See [`Coverage`] for more details.
+    ///
+    /// If not set, calls are not recorded into any `Coverage`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     ser::Coverage,
+    ///     Serializer,
+    /// };
+    ///
+    /// static COVERAGE: Coverage = Coverage::new();
+    ///
+    /// let serializer = Serializer::builder().coverage(&COVERAGE).build();
+    /// assert_ok!(true.serialize(&serializer));
+    ///
+    /// assert_eq!(COVERAGE.count("serialize_bool"), 1);
+    /// ```
+    #[cfg(target_has_atomic = "ptr")]
+    pub fn coverage(&mut self, coverage: &'static Coverage) -> &mut Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// Adds a pass that rewrites the tokens output by the serializer.
+    ///
+    /// Each pass is given the [`Token`]s produced for the outermost value being serialized, and
+    /// may modify them arbitrarily, such as renaming fields, dropping tokens, or reordering map
+    /// entries. Passes are run in the order they are added, after serialization of the value
+    /// completes. This is useful for emulating middleware that modifies serialized output, or for
+    /// generating fixtures for older versions of a format from current output.
+    ///
+    /// Any [`Unordered`] tokens produced by a pass are flattened, in the order they are provided.
+    ///
+    /// Passes must be `Send` and `Sync`, allowing the built [`Serializer`] to be shared across
+    /// threads.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Struct {
+    ///     foo: bool,
+    /// }
+    ///
+    /// let serializer = Serializer::builder()
+    ///     .post_process(|tokens| {
+    ///         for token in tokens {
+    ///             if let Token::Field("foo") = token {
+    ///                 *token = Token::Field("bar");
+    ///             }
+    ///         }
+    ///     })
+    ///     .build();
+    ///
+    /// assert_ok_eq!(
+    ///     Struct { foo: true }.serialize(&serializer),
+    ///     [
+    ///         Token::Struct {
+    ///             name: "Struct",
+    ///             len: 1,
+    ///         },
+    ///         Token::Field("bar"),
+    ///         Token::Bool(true),
+    ///         Token::StructEnd,
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`Unordered`]: Token::Unordered
+    pub fn post_process<F>(&mut self, pass: F) -> &mut Self
+    where
+        F: Fn(&mut Vec<Token>) + Send + Sync + 'static,
+    {
+        self.post_processors.push(PostProcessor(Shared::new(pass)));
+        self
+    }
+
+    /// Applies a [`Profile`] approximating a commonly targeted format.
+    ///
+    /// This sets [`is_human_readable()`], [`serialize_struct_as()`], [`serialize_bytes_as_seq()`],
+    /// [`char_as_str()`], [`serialize_none_as_unit()`], [`serialize_enum_as_map()`], and
+    /// [`serialize_128_as()`] to match the format. Any of these can be overridden by setting them
+    /// after the profile is applied.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     profile::Profile,
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let serializer = Serializer::builder().profile(Profile::Json).build();
+    ///
+    /// assert_ok_eq!('a'.serialize(&serializer), [Token::Str("a".to_owned())]);
+    /// ```
+    ///
+    /// [`char_as_str()`]: Builder::char_as_str()
+    /// [`is_human_readable()`]: Builder::is_human_readable()
+    /// [`serialize_128_as()`]: Builder::serialize_128_as()
+    /// [`serialize_bytes_as_seq()`]: Builder::serialize_bytes_as_seq()
+    /// [`serialize_enum_as_map()`]: Builder::serialize_enum_as_map()
+    /// [`serialize_none_as_unit()`]: Builder::serialize_none_as_unit()
+    /// [`serialize_struct_as()`]: Builder::serialize_struct_as()
+    pub fn profile(&mut self, profile: Profile) -> &mut Self {
+        match profile {
+            Profile::Json => self
+                .is_human_readable(true)
+                .serialize_struct_as(SerializeStructAs::Struct)
+                .serialize_bytes_as_seq(true)
+                .char_as_str(true)
+                .serialize_none_as_unit(true)
+                .serialize_enum_as_map(true)
+                .serialize_128_as(Serialize128As::Integer),
+            Profile::Bincode | Profile::Postcard => self
+                .is_human_readable(false)
+                .serialize_struct_as(SerializeStructAs::Seq)
+                .serialize_bytes_as_seq(false)
+                .char_as_str(false)
+                .serialize_none_as_unit(false)
+                .serialize_enum_as_map(false)
+                .serialize_128_as(Serialize128As::Integer),
+        }
+    }
+
+    /// Build a new [`Serializer`] using this `Builder`.
+    ///
+    /// Constructs a new `Serializer` using the configuration options set on this `Builder`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::Serializer;
+    ///
+    /// let serializer = Serializer::builder().is_human_readable(false).build();
+    /// ```
+    pub fn build(&mut self) -> Serializer {
+        Serializer {
+            is_human_readable: self.is_human_readable,
+            serialize_struct_as: self.serialize_struct_as,
+            serialize_bytes_as_seq: self.serialize_bytes_as_seq,
+            char_as_str: self.char_as_str,
+            serialize_none_as_unit: self.serialize_none_as_unit,
+            serialize_enum_as_map: self.serialize_enum_as_map,
+            serialize_128_as: self.serialize_128_as,
+            support_128_bit: self.support_128_bit,
+            validate_tuple_lengths: self.validate_tuple_lengths,
+            sort_map_entries: self.sort_map_entries,
+            max_depth: self.max_depth,
+            panic_on_error: self.panic_on_error,
+            fail_after_calls: self.fail_after_calls,
+            #[cfg(target_has_atomic = "ptr")]
+            coverage: self.coverage,
+            post_processors: self.post_processors.clone(),
+        }
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            is_human_readable: true,
+            serialize_struct_as: SerializeStructAs::Struct,
+            serialize_bytes_as_seq: false,
+            char_as_str: false,
+            serialize_none_as_unit: false,
+            serialize_enum_as_map: false,
+            serialize_128_as: Serialize128As::Integer,
+            support_128_bit: true,
+            validate_tuple_lengths: false,
+            sort_map_entries: false,
+            max_depth: None,
+            panic_on_error: false,
+            fail_after_calls: None,
+            #[cfg(target_has_atomic = "ptr")]
+            coverage: None,
+            post_processors: Vec::new(),
+        }
+    }
+}
+
+/// A function rewriting the tokens output by a [`Serializer`].
+///
+/// Passes must be `Send` and `Sync` so that a `Serializer` can be shared across threads.
+type Pass = dyn Fn(&mut Vec<Token>) + Send + Sync;
+
+/// A pointer to a pass shared between a [`Builder`] and the `Serializer`s it builds.
+///
+/// Atomic reference counting is only used where it is supported.
+#[cfg(target_has_atomic = "ptr")]
+type Shared<T> = alloc::sync::Arc<T>;
+#[cfg(not(target_has_atomic = "ptr"))]
+type Shared<T> = Rc<T>;
+
+/// A post-processing pass registered on a [`Builder`].
+#[derive(Clone)]
+struct PostProcessor(Shared<Pass>);
+
+impl fmt::Debug for PostProcessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PostProcessor")
+    }
+}
+
+/// Serializer for serializing compound types.
+///
+/// This type implements [`SerializeSeq`], [`SerializeTuple`], [`SerializeTupleStruct`],
+/// [`SerializeTupleVariant`], [`SerializeMap`], [`SerializeStruct`], and
+/// [`SerializeStructVariant`], and is used by [`Serializer`] for serialization of each of those
+/// compound data types.
+///
+/// Users normally will not need to interact with this type directly. It is primarily used by
+/// [`Serialize`] implementations through the various traits it implements.
+#[derive(Debug)]
+pub struct CompoundSerializer<'a> {
+    tokens: Tokens,
+
+    serializer: Session<'a>,
+
+    /// The declared length to validate against, if length validation applies.
+    len: Option<usize>,
+    /// The number of elements serialized so far.
+    count: usize,
+    /// The index within `tokens` at which each map entry or struct field starts.
+    entries: Vec<usize>,
+}
+
+impl CompoundSerializer<'_> {
+    fn validate_len(&self, kind: &str) -> Result<(), Error> {
+        match self.len {
+            Some(len) if len != self.count => Err(self.serializer.fail(
+                Error(format!(
+                    "{} declared a length of {} but {} elements were serialized",
+                    kind, len, self.count
+                )),
+                &self.tokens.0,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl SerializeSeq for CompoundSerializer<'_> {
+    type Ok = Tokens;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.serializer
+            .record_call("serialize_element", &self.tokens.0)?;
+        self.tokens
+            .0
+            .extend(self.serializer.serialize_nested(value, &self.tokens.0)?.0);
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Tokens, Error> {
+        self.serializer.record_call("end", &self.tokens.0)?;
+        self.tokens.0.push(CanonicalToken::SeqEnd);
+        Ok(self.serializer.post_process(self.tokens))
+    }
+}
+
+impl SerializeTuple for CompoundSerializer<'_> {
+    type Ok = Tokens;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.serializer
+            .record_call("serialize_element", &self.tokens.0)?;
+        self.tokens
+            .0
+            .extend(self.serializer.serialize_nested(value, &self.tokens.0)?.0);
+        self.count += 1;
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Tokens, Error> {
+        self.serializer.record_call("end", &self.tokens.0)?;
+        self.validate_len("tuple")?;
+        self.tokens.0.push(CanonicalToken::TupleEnd);
+        Ok(self.serializer.post_process(self.tokens))
+    }
+}
+
+impl SerializeTupleStruct for CompoundSerializer<'_> {
+    type Ok = Tokens;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.serializer
+            .record_call("serialize_field", &self.tokens.0)?;
+        self.tokens
+            .0
+            .extend(self.serializer.serialize_nested(value, &self.tokens.0)?.0);
+        self.count += 1;
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Tokens, Error> {
+        self.serializer.record_call("end", &self.tokens.0)?;
+        self.validate_len("tuple struct")?;
+        self.tokens.0.push(CanonicalToken::TupleStructEnd);
+        Ok(self.serializer.post_process(self.tokens))
+    }
+}
+
+impl SerializeTupleVariant for CompoundSerializer<'_> {
+    type Ok = Tokens;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.serializer
+            .record_call("serialize_field", &self.tokens.0)?;
+        self.tokens
+            .0
+            .extend(self.serializer.serialize_nested(value, &self.tokens.0)?.0);
+        self.count += 1;
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Tokens, Error> {
+        self.serializer.record_call("end", &self.tokens.0)?;
+        self.validate_len("tuple variant")?;
+        if self.serializer.serialize_enum_as_map {
+            self.tokens
+                .0
+                .extend([CanonicalToken::SeqEnd, CanonicalToken::MapEnd]);
+        } else {
+            self.tokens.0.push(CanonicalToken::TupleVariantEnd);
+        }
+        Ok(self.serializer.post_process(self.tokens))
+    }
+}
+
+impl SerializeMap for CompoundSerializer<'_> {
+    type Ok = Tokens;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.serializer
+            .record_call("serialize_key", &self.tokens.0)?;
+        self.entries.push(self.tokens.0.len());
+        self.tokens
+            .0
+            .extend(self.serializer.serialize_nested(value, &self.tokens.0)?.0);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.serializer
+            .record_call("serialize_value", &self.tokens.0)?;
+        self.tokens
+            .0
+            .extend(self.serializer.serialize_nested(value, &self.tokens.0)?.0);
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Tokens, Error> {
+        self.serializer.record_call("end", &self.tokens.0)?;
+        self.serializer
+            .sort_entries(&mut self.tokens.0, &self.entries);
+        self.tokens.0.push(CanonicalToken::MapEnd);
+        Ok(self.serializer.post_process(self.tokens))
+    }
+}
+
+impl SerializeStructVariant for CompoundSerializer<'_> {
+    type Ok = Tokens;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.serializer
+            .record_call("serialize_field", &self.tokens.0)?;
+        self.entries.push(self.tokens.0.len());
+        self.tokens
+            .0
+            .push(if self.serializer.serialize_enum_as_map {
+                CanonicalToken::Str(key.into())
+            } else {
+                CanonicalToken::Field(key)
+            });
+        self.tokens
+            .0
+            .extend(self.serializer.serialize_nested(value, &self.tokens.0)?.0);
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+        self.serializer.record_call("skip_field", &self.tokens.0)?;
+        // Skipped fields are absent from maps.
+        if !self.serializer.serialize_enum_as_map {
+            self.entries.push(self.tokens.0.len());
+            self.tokens.0.push(CanonicalToken::SkippedField(key));
+        }
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Tokens, Error> {
+        self.serializer.record_call("end", &self.tokens.0)?;
+        self.serializer
+            .sort_entries(&mut self.tokens.0, &self.entries);
+        if self.serializer.serialize_enum_as_map {
+            self.tokens
+                .0
+                .extend([CanonicalToken::MapEnd, CanonicalToken::MapEnd]);
+        } else {
+            self.tokens.0.push(CanonicalToken::StructVariantEnd);
+        }
+        Ok(self.serializer.post_process(self.tokens))
+    }
+}
+
+/// Serializer for serializing `struct`s.
+///
+/// Users normally will not need to interact with this type directly. It is primarily used by
+/// [`Serialize`] implementations through the [`serde::ser::SerializeStruct`] trait it implements.
+pub struct SerializeStruct<'a> {
+    tokens: Tokens,
+
+    serializer: Session<'a>,
+
+    #[allow(clippy::struct_field_names)] // Acceptable, as the name refers to the contained type.
+    serialize_struct_as: SerializeStructAs,
+    /// The index within `tokens` at which each field starts.
+    entries: Vec<usize>,
+}
+
+impl ser::SerializeStruct for SerializeStruct<'_> {
+    type Ok = Tokens;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.serializer
+            .record_call("serialize_field", &self.tokens.0)?;
+        self.entries.push(self.tokens.0.len());
+        if matches!(self.serialize_struct_as, SerializeStructAs::Struct) {
+            self.tokens.0.push(CanonicalToken::Field(key));
+        }
+        self.tokens
+            .0
+            .extend(self.serializer.serialize_nested(value, &self.tokens.0)?.0);
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Error> {
+        self.serializer.record_call("skip_field", &self.tokens.0)?;
+        self.entries.push(self.tokens.0.len());
+        self.tokens.0.push(CanonicalToken::SkippedField(key));
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Tokens, Error> {
+        self.serializer.record_call("end", &self.tokens.0)?;
+        if matches!(self.serialize_struct_as, SerializeStructAs::Struct) {
+            self.serializer
+                .sort_entries(&mut self.tokens.0, &self.entries);
+        }
+        self.tokens.0.push(match self.serialize_struct_as {
+            SerializeStructAs::Struct => CanonicalToken::StructEnd,
+            SerializeStructAs::Seq => CanonicalToken::SeqEnd,
+        });
+        Ok(self.serializer.post_process(self.tokens))
+    }
+}
+
+/// An error encountered during serialization.
+///
+/// # Example
+/// ```rust
+/// use serde::ser::Error as _;
+/// use serde_assert::ser::Error;
+///
+/// assert_eq!(format!("{}", Error::custom("foo")), "foo");
+/// ```
+#[derive(Debug, Eq, PartialEq)]
+pub struct Error(pub String);
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+impl ser::StdError for Error {}
+
+impl ser::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self(msg.to_string())
+    }
+}
+
+/// A [`Serializer`](ser::Serializer) adapter recording the [`Tokens`] of the serialized value.
+///
+/// Every call is forwarded both to the wrapped serializer and to a [`Serializer`], referred to as
+/// the recorder. Serializing through a `RecordingSerializer` therefore outputs both the result of
+/// the wrapped serializer and the `Tokens` the recorder would have produced. This allows checking
+/// that a [`Serialize`] implementation produces the expected output in a real format along with
+/// the expected calls to the data model.
+///
+/// Values nested within compound types are serialized separately by the wrapped serializer and
+/// the recorder, meaning their [`Serialize`] implementations are called once for each. The
+/// wrapped serializer determines the value returned by [`is_human_readable()`], so the recorder
+/// should be configured to match it.
+///
+/// Errors returned by the recorder are converted into the wrapped serializer's error type.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok;
+/// use serde::Serialize;
+/// use serde_assert::{
+///     ser::RecordingSerializer,
+///     Serializer,
+///     Token,
+/// };
+///
+/// let recorder = Serializer::builder().build();
+/// let mut output = Vec::new();
+/// let mut json = serde_json::Serializer::new(&mut output);
+///
+/// let ((), tokens) = assert_ok!([1, 2].serialize(RecordingSerializer::new(&mut json, &recorder)));
+///
+/// assert_eq!(output, b"[1,2]");
+/// assert_eq!(
+///     tokens,
+///     [
+///         Token::Tuple { len: 2 },
+///         Token::I32(1),
+///         Token::I32(2),
+///         Token::TupleEnd,
+///     ]
+/// );
+/// ```
+///
+/// [`is_human_readable()`]: ser::Serializer::is_human_readable()
+#[derive(Debug)]
+pub struct RecordingSerializer<'a, S> {
+    serializer: S,
+    recorder: &'a Serializer,
+}
+
+impl<'a, S> RecordingSerializer<'a, S> {
+    /// Creates a new `RecordingSerializer` wrapping the given serializer.
+    ///
+    /// The `Tokens` are recorded using the given `recorder`.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     ser::RecordingSerializer,
+    ///     Serializer,
+    /// };
+    ///
+    /// let recorder = Serializer::builder().build();
+    /// let mut output = Vec::new();
+    /// let mut json = serde_json::Serializer::new(&mut output);
+    ///
+    /// let serializer = RecordingSerializer::new(&mut json, &recorder);
+    /// ```
+    #[must_use]
+    pub fn new(serializer: S, recorder: &'a Serializer) -> Self {
+        Self {
+            serializer,
+            recorder,
+        }
+    }
+}
+
+/// Forwards a call to both the wrapped serializer and the recorder, returning both outputs.
+macro_rules! forward_recording {
+    ($self:ident, $method:ident($($arg:expr),*)) => {{
+        let ok = $self.serializer.$method($($arg),*)?;
+        let tokens = $self.recorder.$method($($arg),*).map_err(S::Error::custom)?;
+        Ok((ok, tokens))
+    }};
+}
+
+/// Begins a compound type on both the wrapped serializer and the recorder.
+macro_rules! forward_recording_compound {
+    ($self:ident, $method:ident($($arg:expr),*)) => {{
+        let compound = $self.serializer.$method($($arg),*)?;
+        let recorder = $self.recorder.$method($($arg),*).map_err(S::Error::custom)?;
+        Ok(RecordingCompound { compound, recorder })
+    }};
+}
+
+macro_rules! forward_recording_primitives {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                forward_recording!(self, $method(v))
+            }
+        )*
+    };
+}
+
+impl<'a, S> ser::Serializer for RecordingSerializer<'a, S>
+where
+    S: ser::Serializer,
+{
+    type Ok = (S::Ok, Tokens);
+    type Error = S::Error;
+
+    type SerializeSeq = RecordingCompound<S::SerializeSeq, CompoundSerializer<'a>>;
+    type SerializeTuple = RecordingCompound<S::SerializeTuple, CompoundSerializer<'a>>;
+    type SerializeTupleStruct = RecordingCompound<S::SerializeTupleStruct, CompoundSerializer<'a>>;
+    type SerializeTupleVariant =
+        RecordingCompound<S::SerializeTupleVariant, CompoundSerializer<'a>>;
+    type SerializeMap = RecordingCompound<S::SerializeMap, CompoundSerializer<'a>>;
+    type SerializeStruct = RecordingCompound<S::SerializeStruct, SerializeStruct<'a>>;
+    type SerializeStructVariant =
+        RecordingCompound<S::SerializeStructVariant, CompoundSerializer<'a>>;
+
+    forward_recording_primitives! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        forward_recording!(self, serialize_none())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        forward_recording!(self, serialize_some(value))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        forward_recording!(self, serialize_unit())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        forward_recording!(self, serialize_unit_struct(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        forward_recording!(self, serialize_unit_variant(name, variant_index, variant))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        forward_recording!(self, serialize_newtype_struct(name, value))
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        forward_recording!(
+            self,
+            serialize_newtype_variant(name, variant_index, variant, value)
+        )
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        forward_recording_compound!(self, serialize_seq(len))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        forward_recording_compound!(self, serialize_tuple(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        forward_recording_compound!(self, serialize_tuple_struct(name, len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        forward_recording_compound!(
+            self,
+            serialize_tuple_variant(name, variant_index, variant, len)
+        )
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        forward_recording_compound!(self, serialize_map(len))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        forward_recording_compound!(self, serialize_struct(name, len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        forward_recording_compound!(
+            self,
+            serialize_struct_variant(name, variant_index, variant, len)
+        )
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.serializer.is_human_readable()
+    }
+}
+
+/// Serializer for serializing compound types through a [`RecordingSerializer`].
+///
+/// This wraps the compound serializers of both the wrapped serializer and the recorder, forwarding
+/// each element to both.
+///
+/// Users normally will not need to interact with this type directly. It is primarily used by
+/// [`Serialize`] implementations through the various traits it implements.
+#[derive(Debug)]
+pub struct RecordingCompound<C, R> {
+    compound: C,
+    recorder: R,
+}
+
+impl<C, R> SerializeSeq for RecordingCompound<C, R>
+where
+    C: SerializeSeq,
+    R: SerializeSeq<Ok = Tokens, Error = Error>,
+{
+    type Ok = (C::Ok, Tokens);
+    type Error = C::Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.serialize_element(value)?;
+        self.recorder
+            .serialize_element(value)
+            .map_err(C::Error::custom)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let ok = self.compound.end()?;
+        let tokens = self.recorder.end().map_err(C::Error::custom)?;
+        Ok((ok, tokens))
+    }
+}
+
+impl<C, R> SerializeTuple for RecordingCompound<C, R>
+where
+    C: SerializeTuple,
+    R: SerializeTuple<Ok = Tokens, Error = Error>,
+{
+    type Ok = (C::Ok, Tokens);
+    type Error = C::Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.serialize_element(value)?;
+        self.recorder
+            .serialize_element(value)
+            .map_err(C::Error::custom)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let ok = self.compound.end()?;
+        let tokens = self.recorder.end().map_err(C::Error::custom)?;
+        Ok((ok, tokens))
+    }
+}
+
+impl<C, R> SerializeTupleStruct for RecordingCompound<C, R>
+where
+    C: SerializeTupleStruct,
+    R: SerializeTupleStruct<Ok = Tokens, Error = Error>,
+{
+    type Ok = (C::Ok, Tokens);
+    type Error = C::Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.serialize_field(value)?;
+        self.recorder
+            .serialize_field(value)
+            .map_err(C::Error::custom)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let ok = self.compound.end()?;
+        let tokens = self.recorder.end().map_err(C::Error::custom)?;
+        Ok((ok, tokens))
+    }
+}
+
+impl<C, R> SerializeTupleVariant for RecordingCompound<C, R>
+where
+    C: SerializeTupleVariant,
+    R: SerializeTupleVariant<Ok = Tokens, Error = Error>,
+{
+    type Ok = (C::Ok, Tokens);
+    type Error = C::Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.serialize_field(value)?;
+        self.recorder
+            .serialize_field(value)
+            .map_err(C::Error::custom)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let ok = self.compound.end()?;
+        let tokens = self.recorder.end().map_err(C::Error::custom)?;
+        Ok((ok, tokens))
+    }
+}
+
+impl<C, R> SerializeMap for RecordingCompound<C, R>
+where
+    C: SerializeMap,
+    R: SerializeMap<Ok = Tokens, Error = Error>,
+{
+    type Ok = (C::Ok, Tokens);
+    type Error = C::Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.serialize_key(key)?;
+        self.recorder.serialize_key(key).map_err(C::Error::custom)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.serialize_value(value)?;
+        self.recorder
+            .serialize_value(value)
+            .map_err(C::Error::custom)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let ok = self.compound.end()?;
+        let tokens = self.recorder.end().map_err(C::Error::custom)?;
+        Ok((ok, tokens))
+    }
+}
+
+impl<C, R> ser::SerializeStruct for RecordingCompound<C, R>
+where
+    C: ser::SerializeStruct,
+    R: ser::SerializeStruct<Ok = Tokens, Error = Error>,
+{
+    type Ok = (C::Ok, Tokens);
+    type Error = C::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.serialize_field(key, value)?;
+        self.recorder
+            .serialize_field(key, value)
+            .map_err(C::Error::custom)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.compound.skip_field(key)?;
+        self.recorder.skip_field(key).map_err(C::Error::custom)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let ok = self.compound.end()?;
+        let tokens = self.recorder.end().map_err(C::Error::custom)?;
+        Ok((ok, tokens))
+    }
+}
+
+impl<C, R> SerializeStructVariant for RecordingCompound<C, R>
+where
+    C: SerializeStructVariant,
+    R: SerializeStructVariant<Ok = Tokens, Error = Error>,
+{
+    type Ok = (C::Ok, Tokens);
+    type Error = C::Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.compound.serialize_field(key, value)?;
+        self.recorder
+            .serialize_field(key, value)
+            .map_err(C::Error::custom)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.compound.skip_field(key)?;
+        self.recorder.skip_field(key).map_err(C::Error::custom)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        let ok = self.compound.end()?;
+        let tokens = self.recorder.end().map_err(C::Error::custom)?;
+        Ok((ok, tokens))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        to_tokens,
+        to_tokens_with,
+        Coverage,
+        EnumRepresentation,
+        EnumVariant,
+        Error,
+        RecordingSerializer,
+        Serialize128As,
         SerializeStructAs,
         Serializer,
     };
-    use crate::Token;
+    use crate::{
+        profile::Profile,
+        Token,
+    };
     use alloc::{
         borrow::ToOwned,
+        boxed::Box,
+        collections::BTreeMap,
         format,
         string::String,
         vec,
+        vec::Vec,
+    };
+    use claims::{
+        assert_err,
+        assert_err_eq,
+        assert_ok,
+        assert_ok_eq,
     };
-    use claims::assert_ok_eq;
     use serde::ser::{
         Error as _,
         Serialize,
+        SerializeMap,
+        SerializeTuple,
+        SerializeTupleStruct,
+        SerializeTupleVariant,
         Serializer as _,
     };
     use serde_bytes::Bytes;
@@ -756,565 +3168,2037 @@ mod tests {
     };
 
     #[test]
-    fn serialize_bool() {
+    fn serialize_bool() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(true.serialize(&serializer), [Token::Bool(true)]);
+    }
+
+    #[test]
+    fn serialize_i8() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42i8.serialize(&serializer), [Token::I8(42)]);
+    }
+
+    #[test]
+    fn serialize_i16() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42i16.serialize(&serializer), [Token::I16(42)]);
+    }
+
+    #[test]
+    fn serialize_i32() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42i32.serialize(&serializer), [Token::I32(42)]);
+    }
+
+    #[test]
+    fn serialize_i64() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42i64.serialize(&serializer), [Token::I64(42)]);
+    }
+
+    #[test]
+    fn serialize_i128() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42i128.serialize(&serializer), [Token::I128(42)]);
+    }
+
+    #[test]
+    fn serialize_u8() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42u8.serialize(&serializer), [Token::U8(42)]);
+    }
+
+    #[test]
+    fn serialize_u16() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42u16.serialize(&serializer), [Token::U16(42)]);
+    }
+
+    #[test]
+    fn serialize_u32() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42u32.serialize(&serializer), [Token::U32(42)]);
+    }
+
+    #[test]
+    fn serialize_u64() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42u64.serialize(&serializer), [Token::U64(42)]);
+    }
+
+    #[test]
+    fn serialize_u128() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42u128.serialize(&serializer), [Token::U128(42)]);
+    }
+
+    #[test]
+    fn serialize_i128_as_str() {
+        let serializer = Serializer::builder()
+            .serialize_128_as(Serialize128As::Str)
+            .build();
+
+        assert_ok_eq!(
+            (-42i128).serialize(&serializer),
+            [Token::Str("-42".to_owned())]
+        );
+    }
+
+    #[test]
+    fn serialize_i128_as_bytes() {
+        let serializer = Serializer::builder()
+            .serialize_128_as(Serialize128As::Bytes)
+            .build();
+
+        assert_ok_eq!(
+            (-42i128).serialize(&serializer),
+            [Token::Bytes((-42i128).to_be_bytes().to_vec())]
+        );
+    }
+
+    #[test]
+    fn serialize_u128_as_str() {
+        let serializer = Serializer::builder()
+            .serialize_128_as(Serialize128As::Str)
+            .build();
+
+        assert_ok_eq!(
+            u128::MAX.serialize(&serializer),
+            [Token::Str(
+                "340282366920938463463374607431768211455".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn serialize_u128_as_bytes() {
+        let serializer = Serializer::builder()
+            .serialize_128_as(Serialize128As::Bytes)
+            .build();
+
+        assert_ok_eq!(
+            42u128.serialize(&serializer),
+            [Token::Bytes(42u128.to_be_bytes().to_vec())]
+        );
+    }
+
+    #[test]
+    fn serialize_i128_unsupported() {
+        let serializer = Serializer::builder().support_128_bit(false).build();
+
+        assert_err_eq!(
+            42i128.serialize(&serializer),
+            Error("i128 is not supported".to_owned())
+        );
+    }
+
+    #[test]
+    fn serialize_u128_unsupported() {
+        let serializer = Serializer::builder().support_128_bit(false).build();
+
+        assert_err_eq!(
+            42u128.serialize(&serializer),
+            Error("u128 is not supported".to_owned())
+        );
+    }
+
+    #[test]
+    fn serialize_128_as_str_unsupported() {
+        let serializer = Serializer::builder()
+            .serialize_128_as(Serialize128As::Str)
+            .support_128_bit(false)
+            .build();
+
+        assert_err_eq!(
+            42u128.serialize(&serializer),
+            Error("u128 is not supported".to_owned())
+        );
+    }
+
+    #[test]
+    fn serialize_u64_unaffected_by_unsupported_128_bit() {
+        let serializer = Serializer::builder().support_128_bit(false).build();
+
+        assert_ok_eq!(42u64.serialize(&serializer), [Token::U64(42)]);
+    }
+
+    #[test]
+    fn serialize_f32() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42f32.serialize(&serializer), [Token::F32(42.)]);
+    }
+
+    #[test]
+    fn serialize_f64() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(42f64.serialize(&serializer), [Token::F64(42.)]);
+    }
+
+    #[test]
+    fn serialize_char() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!('a'.serialize(&serializer), [Token::Char('a')]);
+    }
+
+    #[test]
+    fn char_as_str() {
+        let serializer = Serializer::builder().char_as_str(true).build();
+
+        assert_ok_eq!('a'.serialize(&serializer), [Token::Str("a".to_owned())]);
+    }
+
+    #[test]
+    fn serialize_str() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!("a".serialize(&serializer), [Token::Str("a".to_owned())]);
+    }
+
+    #[test]
+    fn serialize_bytes() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Bytes::new(b"a").serialize(&serializer),
+            [Token::Bytes(b"a".to_vec())]
+        );
+    }
+
+    #[test]
+    fn serialize_bytes_as_seq() {
+        let serializer = Serializer::builder().serialize_bytes_as_seq(true).build();
+
+        assert_ok_eq!(
+            Bytes::new(b"ab").serialize(&serializer),
+            [
+                Token::Seq { len: Some(2) },
+                Token::U8(b'a'),
+                Token::U8(b'b'),
+                Token::SeqEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_bytes_as_seq_empty() {
+        let serializer = Serializer::builder().serialize_bytes_as_seq(true).build();
+
+        assert_ok_eq!(
+            Bytes::new(b"").serialize(&serializer),
+            [Token::Seq { len: Some(0) }, Token::SeqEnd]
+        );
+    }
+
+    #[test]
+    fn serialize_none() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(Option::<()>::None.serialize(&serializer), [Token::None]);
+    }
+
+    #[test]
+    fn serialize_some() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Some(true).serialize(&serializer),
+            [Token::Some, Token::Bool(true)]
+        );
+    }
+
+    #[test]
+    fn serialize_none_as_unit() {
+        let serializer = Serializer::builder().serialize_none_as_unit(true).build();
+
+        assert_ok_eq!(Option::<()>::None.serialize(&serializer), [Token::Unit]);
+    }
+
+    #[test]
+    fn serialize_some_with_none_as_unit() {
+        let serializer = Serializer::builder().serialize_none_as_unit(true).build();
+
+        assert_ok_eq!(
+            Some(true).serialize(&serializer),
+            [Token::Some, Token::Bool(true)]
+        );
+    }
+
+    #[test]
+    fn serialize_nested_none_as_unit() {
+        let serializer = Serializer::builder().serialize_none_as_unit(true).build();
+
+        assert_ok_eq!(
+            vec![Some(1u8), None].serialize(&serializer),
+            [
+                Token::Seq { len: Some(2) },
+                Token::Some,
+                Token::U8(1),
+                Token::Unit,
+                Token::SeqEnd
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_unit() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(().serialize(&serializer), [Token::Unit]);
+    }
+
+    #[test]
+    fn serialize_unit_struct() {
+        #[derive(Serialize)]
+        struct Unit;
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Unit.serialize(&serializer),
+            [Token::UnitStruct { name: "Unit" }]
+        );
+    }
+
+    #[test]
+    fn serialize_unit_variant() {
+        #[derive(Serialize)]
+        enum Unit {
+            Variant,
+        }
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Unit::Variant.serialize(&serializer),
+            [Token::UnitVariant {
+                name: "Unit",
+                variant_index: 0,
+                variant: "Variant"
+            }]
+        );
+    }
+
+    #[test]
+    fn serialize_newtype_struct() {
+        #[derive(Serialize)]
+        struct Newtype(bool);
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Newtype(false).serialize(&serializer),
+            [Token::NewtypeStruct { name: "Newtype" }, Token::Bool(false)]
+        );
+    }
+
+    #[test]
+    fn serialize_newtype_variant() {
+        #[derive(Serialize)]
+        enum Newtype {
+            Variant(bool),
+        }
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Newtype::Variant(false).serialize(&serializer),
+            [
+                Token::NewtypeVariant {
+                    name: "Newtype",
+                    variant_index: 0,
+                    variant: "Variant"
+                },
+                Token::Bool(false)
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_seq() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            vec![1i8, 2i8, 3i8].serialize(&serializer),
+            [
+                Token::Seq { len: Some(3) },
+                Token::I8(1),
+                Token::I8(2),
+                Token::I8(3),
+                Token::SeqEnd
+            ],
+        );
+    }
+
+    #[test]
+    fn serialize_seq_unordered() {
+        let serializer = Serializer::builder().build();
+
+        let mut set = HashSet::new();
+        set.insert('a');
+        set.insert('b');
+        set.insert('c');
+
+        assert_ok_eq!(
+            set.serialize(&serializer),
+            [
+                Token::Seq { len: Some(3) },
+                Token::Unordered(&[
+                    &[Token::Char('a')],
+                    &[Token::Char('b')],
+                    &[Token::Char('c')],
+                ]),
+                Token::SeqEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_tuple() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            (1i8, 2i16, 3i32).serialize(&serializer),
+            [
+                Token::Tuple { len: 3 },
+                Token::I8(1),
+                Token::I16(2),
+                Token::I32(3),
+                Token::TupleEnd
+            ],
+        );
+    }
+
+    #[test]
+    fn serialize_tuple_struct() {
+        #[derive(Serialize)]
+        struct TupleStruct(i8, i16, i32);
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            TupleStruct(1i8, 2i16, 3i32).serialize(&serializer),
+            [
+                Token::TupleStruct {
+                    name: "TupleStruct",
+                    len: 3
+                },
+                Token::I8(1),
+                Token::I16(2),
+                Token::I32(3),
+                Token::TupleStructEnd
+            ],
+        );
+    }
+
+    #[test]
+    fn serialize_tuple_variant() {
+        #[derive(Serialize)]
+        enum Tuple {
+            Variant(i8, i16, i32),
+        }
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Tuple::Variant(1i8, 2i16, 3i32).serialize(&serializer),
+            [
+                Token::TupleVariant {
+                    name: "Tuple",
+                    variant_index: 0,
+                    variant: "Variant",
+                    len: 3
+                },
+                Token::I8(1),
+                Token::I16(2),
+                Token::I32(3),
+                Token::TupleVariantEnd
+            ],
+        );
+    }
+
+    #[test]
+    fn validate_tuple_lengths_tuple_too_few() {
+        let serializer = Serializer::builder().validate_tuple_lengths(true).build();
+
+        let mut tuple = assert_ok!((&serializer).serialize_tuple(2));
+        assert_ok!(tuple.serialize_element(&1u8));
+
+        assert_err_eq!(
+            SerializeTuple::end(tuple),
+            Error("tuple declared a length of 2 but 1 elements were serialized".to_owned())
+        );
+    }
+
+    #[test]
+    fn validate_tuple_lengths_tuple_struct_too_many() {
+        let serializer = Serializer::builder().validate_tuple_lengths(true).build();
+
+        let mut tuple_struct = assert_ok!((&serializer).serialize_tuple_struct("TupleStruct", 1));
+        assert_ok!(SerializeTupleStruct::serialize_field(
+            &mut tuple_struct,
+            &1u8
+        ));
+        assert_ok!(SerializeTupleStruct::serialize_field(
+            &mut tuple_struct,
+            &2u8
+        ));
+
+        assert_err_eq!(
+            SerializeTupleStruct::end(tuple_struct),
+            Error("tuple struct declared a length of 1 but 2 elements were serialized".to_owned())
+        );
+    }
+
+    #[test]
+    fn validate_tuple_lengths_tuple_variant() {
+        let serializer = Serializer::builder().validate_tuple_lengths(true).build();
+
+        let mut tuple_variant =
+            assert_ok!((&serializer).serialize_tuple_variant("Enum", 0, "Variant", 3));
+        assert_ok!(SerializeTupleVariant::serialize_field(
+            &mut tuple_variant,
+            &1u8
+        ));
+
+        assert_err_eq!(
+            SerializeTupleVariant::end(tuple_variant),
+            Error("tuple variant declared a length of 3 but 1 elements were serialized".to_owned())
+        );
+    }
+
+    #[test]
+    fn max_depth_within_limit() {
+        let serializer = Serializer::builder().max_depth(2).build();
+
+        assert_ok_eq!(
+            vec![Some(1u8)].serialize(&serializer),
+            [
+                Token::Seq { len: Some(1) },
+                Token::Some,
+                Token::U8(1),
+                Token::SeqEnd
+            ]
+        );
+    }
+
+    #[test]
+    fn max_depth_exceeded() {
+        let serializer = Serializer::builder().max_depth(2).build();
+
+        assert_err_eq!(
+            vec![vec![Some(1u8)]].serialize(&serializer),
+            Error("exceeded the maximum nesting depth of 2".to_owned())
+        );
+    }
+
+    #[test]
+    fn max_depth_zero() {
+        let serializer = Serializer::builder().max_depth(0).build();
+
+        assert_ok_eq!(42u8.serialize(&serializer), [Token::U8(42)]);
+        assert_err_eq!(
+            Some(42u8).serialize(&serializer),
+            Error("exceeded the maximum nesting depth of 0".to_owned())
+        );
+    }
+
+    #[test]
+    fn max_depth_recursive() {
+        struct List(Option<Box<List>>);
+
+        impl Serialize for List {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_newtype_struct("List", &self.0)
+            }
+        }
+
+        let mut list = List(None);
+        for _ in 0..1000 {
+            list = List(Some(Box::new(list)));
+        }
+        let serializer = Serializer::builder().max_depth(100).build();
+
+        assert_err_eq!(
+            list.serialize(&serializer),
+            Error("exceeded the maximum nesting depth of 100".to_owned())
+        );
+        // The depth is reset after an error.
+        assert_ok_eq!(
+            Some(42u8).serialize(&serializer),
+            [Token::Some, Token::U8(42)]
+        );
+    }
+
+    /// A value that always fails to serialize.
+    struct Failing;
+
+    impl Serialize for Failing {
+        fn serialize<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            Err(S::Error::custom("failed"))
+        }
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "serialization failed: failed\ntokens serialized so far at depth 0: [Tuple { \
+                    len: 2 }, U8(1)]"
+    )]
+    fn panic_on_error_nested() {
+        let serializer = Serializer::builder().panic_on_error(true).build();
+
+        let _ = (1u8, Failing).serialize(&serializer);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "serialization failed: tuple declared a length of 2 but 1 elements were \
+                    serialized\ntokens serialized so far at depth 0: [Tuple { len: 2 }, U8(1)]"
+    )]
+    fn panic_on_error_validate_tuple_lengths() {
+        let serializer = Serializer::builder()
+            .validate_tuple_lengths(true)
+            .panic_on_error(true)
+            .build();
+        let mut tuple = assert_ok!((&serializer).serialize_tuple(2));
+        assert_ok!(SerializeTuple::serialize_element(&mut tuple, &1u8));
+
+        let _ = SerializeTuple::end(tuple);
+    }
+
+    #[test]
+    fn panic_on_error_outermost() {
+        let serializer = Serializer::builder().panic_on_error(true).build();
+
+        assert_err_eq!(Failing.serialize(&serializer), Error::custom("failed"));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "serialization failed: i128 is not supported\ntokens serialized so far at depth \
+                    0: []"
+    )]
+    fn panic_on_error_unsupported_128_bit() {
+        let serializer = Serializer::builder()
+            .support_128_bit(false)
+            .panic_on_error(true)
+            .build();
+
+        let _ = 42i128.serialize(&serializer);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "serialization failed: injected failure after 0 calls\ntokens serialized so far \
+                    at depth 0: []"
+    )]
+    fn panic_on_error_fail_after_calls() {
+        let serializer = Serializer::builder()
+            .fail_after_calls(0)
+            .panic_on_error(true)
+            .build();
+
+        let _ = true.serialize(&serializer);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "serialization failed: injected failure after 3 calls\ntokens serialized so far \
+                    at depth 0: [Seq { len: Some(1) }, U8(1)]"
+    )]
+    fn panic_on_error_fail_after_calls_compound() {
+        let serializer = Serializer::builder()
+            .fail_after_calls(3)
+            .panic_on_error(true)
+            .build();
+
+        let _ = vec![1u8].serialize(&serializer);
+    }
+
+    #[test]
+    fn fail_after_calls_zero() {
+        let serializer = Serializer::builder().fail_after_calls(0).build();
+
+        assert_err_eq!(
+            true.serialize(&serializer),
+            Error("injected failure after 0 calls".to_owned())
+        );
+    }
+
+    #[test]
+    fn fail_after_calls_not_reached() {
+        let serializer = Serializer::builder().fail_after_calls(4).build();
+
+        assert_ok_eq!(
+            (true,).serialize(&serializer),
+            [Token::Tuple { len: 1 }, Token::Bool(true), Token::TupleEnd]
+        );
+    }
+
+    #[test]
+    fn fail_after_calls_every_position() {
+        #[derive(Serialize)]
+        struct Struct {
+            foo: Vec<u32>,
+            bar: bool,
+        }
+
+        let value = Struct {
+            foo: vec![1, 2],
+            bar: true,
+        };
+        let calls = assert_ok!(Serializer::new().record(&value)).calls().total();
+
+        for fail_after_calls in 0..calls {
+            let serializer = Serializer::builder()
+                .fail_after_calls(fail_after_calls)
+                .build();
+
+            assert_err_eq!(
+                value.serialize(&serializer),
+                Error(format!("injected failure after {fail_after_calls} calls"))
+            );
+        }
+    }
+
+    #[test]
+    fn validate_tuple_lengths_matching() {
+        #[derive(Serialize)]
+        struct TupleStruct(u8, (u8, u8));
+
+        let serializer = Serializer::builder().validate_tuple_lengths(true).build();
+
+        assert_ok_eq!(
+            TupleStruct(1, (2, 3)).serialize(&serializer),
+            [
+                Token::TupleStruct {
+                    name: "TupleStruct",
+                    len: 2
+                },
+                Token::U8(1),
+                Token::Tuple { len: 2 },
+                Token::U8(2),
+                Token::U8(3),
+                Token::TupleEnd,
+                Token::TupleStructEnd
+            ]
+        );
+    }
+
+    #[test]
+    fn tuple_lengths_not_validated_by_default() {
+        let serializer = Serializer::builder().build();
+
+        let mut tuple = assert_ok!((&serializer).serialize_tuple(2));
+        assert_ok!(tuple.serialize_element(&1u8));
+
+        assert_ok_eq!(
+            SerializeTuple::end(tuple),
+            [Token::Tuple { len: 2 }, Token::U8(1), Token::TupleEnd]
+        );
+    }
+
+    #[test]
+    fn sort_map_entries() {
+        let serializer = Serializer::builder().sort_map_entries(true).build();
+
+        let mut map = HashMap::new();
+        map.insert(10i8, 'c');
+        map.insert(-1i8, 'a');
+        map.insert(2i8, 'b');
+
+        assert_ok_eq!(
+            map.serialize(&serializer),
+            [
+                Token::Map { len: Some(3) },
+                Token::I8(-1),
+                Token::Char('a'),
+                Token::I8(2),
+                Token::Char('b'),
+                Token::I8(10),
+                Token::Char('c'),
+                Token::MapEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_map_entries_nested() {
+        let serializer = Serializer::builder().sort_map_entries(true).build();
+
+        let mut inner = HashMap::new();
+        inner.insert("foo", 1u8);
+        inner.insert("bar", 2u8);
+        let mut map = HashMap::new();
+        map.insert(vec!["b"], inner.clone());
+        map.insert(vec!["a", "c"], inner);
+
+        assert_ok_eq!(
+            map.serialize(&serializer),
+            [
+                Token::Map { len: Some(2) },
+                Token::Seq { len: Some(2) },
+                Token::Str("a".to_owned()),
+                Token::Str("c".to_owned()),
+                Token::SeqEnd,
+                Token::Map { len: Some(2) },
+                Token::Str("bar".to_owned()),
+                Token::U8(2),
+                Token::Str("foo".to_owned()),
+                Token::U8(1),
+                Token::MapEnd,
+                Token::Seq { len: Some(1) },
+                Token::Str("b".to_owned()),
+                Token::SeqEnd,
+                Token::Map { len: Some(2) },
+                Token::Str("bar".to_owned()),
+                Token::U8(2),
+                Token::Str("foo".to_owned()),
+                Token::U8(1),
+                Token::MapEnd,
+                Token::MapEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_map_entries_struct() {
+        #[allow(clippy::trivially_copy_pass_by_ref)] // Required by serde.
+        fn skip(_: &bool) -> bool {
+            true
+        }
+
+        #[derive(Serialize)]
+        struct Struct {
+            foo: bool,
+            #[serde(skip_serializing_if = "skip")]
+            baz: bool,
+            bar: u8,
+        }
+
+        let serializer = Serializer::builder().sort_map_entries(true).build();
+
+        assert_ok_eq!(
+            Struct {
+                foo: false,
+                baz: true,
+                bar: 42,
+            }
+            .serialize(&serializer),
+            [
+                Token::Struct {
+                    name: "Struct",
+                    len: 2,
+                },
+                Token::Field("bar"),
+                Token::U8(42),
+                Token::SkippedField("baz"),
+                Token::Field("foo"),
+                Token::Bool(false),
+                Token::StructEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_map_entries_struct_variant() {
+        #[derive(Serialize)]
+        enum Enum {
+            Variant { foo: bool, bar: u8 },
+        }
+
+        let serializer = Serializer::builder().sort_map_entries(true).build();
+
+        assert_ok_eq!(
+            Enum::Variant { foo: true, bar: 1 }.serialize(&serializer),
+            [
+                Token::StructVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Variant",
+                    len: 2,
+                },
+                Token::Field("bar"),
+                Token::U8(1),
+                Token::Field("foo"),
+                Token::Bool(true),
+                Token::StructVariantEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_map_entries_struct_as_seq() {
+        #[derive(Serialize)]
+        struct Struct {
+            foo: bool,
+            bar: u8,
+        }
+
+        let serializer = Serializer::builder()
+            .sort_map_entries(true)
+            .serialize_struct_as(SerializeStructAs::Seq)
+            .build();
+
+        assert_ok_eq!(
+            Struct { foo: true, bar: 1 }.serialize(&serializer),
+            [
+                Token::Seq { len: Some(2) },
+                Token::Bool(true),
+                Token::U8(1),
+                Token::SeqEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn map_entries_not_sorted_by_default() {
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(true.serialize(&serializer), [Token::Bool(true)]);
+        let mut map = assert_ok!((&serializer).serialize_map(Some(2)));
+        assert_ok!(map.serialize_entry(&2u8, &'b'));
+        assert_ok!(map.serialize_entry(&1u8, &'a'));
+
+        assert_ok_eq!(
+            SerializeMap::end(map),
+            [
+                Token::Map { len: Some(2) },
+                Token::U8(2),
+                Token::Char('b'),
+                Token::U8(1),
+                Token::Char('a'),
+                Token::MapEnd,
+            ]
+        );
     }
 
     #[test]
-    fn serialize_i8() {
+    fn serialize_map() {
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(42i8.serialize(&serializer), [Token::I8(42)]);
+        let mut map = HashMap::new();
+        map.insert(1i8, 'a');
+        map.insert(2i8, 'b');
+        map.insert(3i8, 'c');
+
+        assert_ok_eq!(
+            map.serialize(&serializer),
+            [
+                Token::Map { len: Some(3) },
+                Token::Unordered(&[
+                    &[Token::I8(1), Token::Char('a')],
+                    &[Token::I8(2), Token::Char('b')],
+                    &[Token::I8(3), Token::Char('c')],
+                ]),
+                Token::MapEnd,
+            ]
+        );
     }
 
     #[test]
-    fn serialize_i16() {
+    fn serialize_struct() {
+        #[derive(Serialize)]
+        struct Struct {
+            a: bool,
+            b: u16,
+            c: String,
+        }
+
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(42i16.serialize(&serializer), [Token::I16(42)]);
+        assert_ok_eq!(
+            Struct {
+                a: true,
+                b: 42,
+                c: "foo".to_owned(),
+            }
+            .serialize(&serializer),
+            [
+                Token::Struct {
+                    name: "Struct",
+                    len: 3,
+                },
+                Token::Field("a"),
+                Token::Bool(true),
+                Token::Field("b"),
+                Token::U16(42),
+                Token::Field("c"),
+                Token::Str("foo".to_owned()),
+                Token::StructEnd,
+            ]
+        );
     }
 
     #[test]
-    fn serialize_i32() {
+    fn serialize_struct_skipped_field() {
+        fn skip<T>(_: &T) -> bool {
+            true
+        }
+
+        #[derive(Serialize)]
+        struct Struct {
+            a: bool,
+            #[serde(skip_serializing_if = "skip")]
+            b: u16,
+            c: String,
+        }
+
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(42i32.serialize(&serializer), [Token::I32(42)]);
+        assert_ok_eq!(
+            Struct {
+                a: true,
+                b: 42,
+                c: "foo".to_owned(),
+            }
+            .serialize(&serializer),
+            [
+                Token::Struct {
+                    name: "Struct",
+                    len: 2,
+                },
+                Token::Field("a"),
+                Token::Bool(true),
+                Token::SkippedField("b"),
+                Token::Field("c"),
+                Token::Str("foo".to_owned()),
+                Token::StructEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_struct_as_seq() {
+        #[derive(Serialize)]
+        struct Struct {
+            foo: bool,
+            bar: u32,
+        }
+
+        let some_struct = Struct {
+            foo: false,
+            bar: 42,
+        };
+        let serializer = Serializer::builder()
+            .serialize_struct_as(SerializeStructAs::Seq)
+            .build();
+
+        assert_ok_eq!(
+            some_struct.serialize(&serializer),
+            [
+                Token::Seq { len: Some(2) },
+                Token::Bool(false),
+                Token::U32(42),
+                Token::SeqEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_struct_variant() {
+        #[derive(Serialize)]
+        enum Struct {
+            Variant { a: bool, b: u16, c: String },
+        }
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Struct::Variant {
+                a: true,
+                b: 42,
+                c: "foo".to_owned(),
+            }
+            .serialize(&serializer),
+            [
+                Token::StructVariant {
+                    name: "Struct",
+                    variant_index: 0,
+                    variant: "Variant",
+                    len: 3,
+                },
+                Token::Field("a"),
+                Token::Bool(true),
+                Token::Field("b"),
+                Token::U16(42),
+                Token::Field("c"),
+                Token::Str("foo".to_owned()),
+                Token::StructVariantEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_struct_variant_skipped_field() {
+        fn skip<T>(_: &T) -> bool {
+            true
+        }
+
+        #[derive(Serialize)]
+        enum Struct {
+            Variant {
+                a: bool,
+                #[serde(skip_serializing_if = "skip")]
+                b: u16,
+                c: String,
+            },
+        }
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            Struct::Variant {
+                a: true,
+                b: 42,
+                c: "foo".to_owned(),
+            }
+            .serialize(&serializer),
+            [
+                Token::StructVariant {
+                    name: "Struct",
+                    variant_index: 0,
+                    variant: "Variant",
+                    len: 2,
+                },
+                Token::Field("a"),
+                Token::Bool(true),
+                Token::SkippedField("b"),
+                Token::Field("c"),
+                Token::Str("foo".to_owned()),
+                Token::StructVariantEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn collect_str() {
+        struct CollectedString(String);
+
+        impl Serialize for CollectedString {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_str(&self.0)
+            }
+        }
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            CollectedString("foo".to_owned()).serialize(&serializer),
+            [Token::Str("foo".to_owned())]
+        );
+    }
+
+    #[test]
+    fn serialize_with() {
+        fn serialize_incremented<S>(value: &u128, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            serializer.serialize_u128(value + 1)
+        }
+
+        let serializer = Serializer::builder().build();
+
+        assert_ok_eq!(
+            serializer.serialize_with(&42, serialize_incremented),
+            [Token::U128(43)]
+        );
+    }
+
+    #[test]
+    fn serialize_with_error() {
+        fn serialize_error<S>(_value: &u128, _serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            Err(S::Error::custom("foo"))
+        }
+
+        let serializer = Serializer::builder().build();
+
+        assert_err_eq!(
+            serializer.serialize_with(&42, serialize_error),
+            Error::custom("foo")
+        );
+    }
+
+    #[cfg(feature = "serde-transcode")]
+    #[test]
+    fn transcode() {
+        let serializer = Serializer::builder().build();
+        let mut deserializer = serde_json::Deserializer::from_str(r#"{"foo": null}"#);
+
+        assert_ok_eq!(
+            serializer.transcode(&mut deserializer),
+            [
+                Token::Map { len: None },
+                Token::Str("foo".to_owned()),
+                Token::Unit,
+                Token::MapEnd,
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde-transcode")]
+    #[test]
+    fn transcode_error() {
+        let serializer = Serializer::builder().build();
+        let mut deserializer = serde_json::Deserializer::from_str("[1,");
+
+        assert_err_eq!(
+            serializer.transcode(&mut deserializer),
+            Error::custom("EOF while parsing a value at line 1 column 3")
+        );
     }
 
     #[test]
-    fn serialize_i64() {
-        let serializer = Serializer::builder().build();
+    fn new() {
+        let serializer = Serializer::new();
 
-        assert_ok_eq!(42i64.serialize(&serializer), [Token::I64(42)]);
+        assert!((&serializer).is_human_readable());
+        assert_ok_eq!(true.serialize(&serializer), [Token::Bool(true)]);
     }
 
     #[test]
-    fn serialize_i128() {
-        let serializer = Serializer::builder().build();
+    fn default() {
+        let serializer = Serializer::default();
 
-        assert_ok_eq!(42i128.serialize(&serializer), [Token::I128(42)]);
+        assert_ok_eq!(true.serialize(&serializer), [Token::Bool(true)]);
     }
 
     #[test]
-    fn serialize_u8() {
+    fn serialize_iter() {
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(42u8.serialize(&serializer), [Token::U8(42)]);
+        assert_ok_eq!(
+            serializer.serialize_iter(["foo", "bar"]),
+            [
+                Token::Seq { len: Some(2) },
+                Token::Str("foo".to_owned()),
+                Token::Str("bar".to_owned()),
+                Token::SeqEnd,
+            ]
+        );
     }
 
     #[test]
-    fn serialize_u16() {
+    fn serialize_iter_unknown_len() {
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(42u16.serialize(&serializer), [Token::U16(42)]);
+        assert_ok_eq!(
+            serializer.serialize_iter((0..4_u8).filter(|x| x % 2 == 0)),
+            [
+                Token::Seq { len: None },
+                Token::U8(0),
+                Token::U8(2),
+                Token::SeqEnd,
+            ]
+        );
     }
 
     #[test]
-    fn serialize_u32() {
+    fn is_human_readable_default() {
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(42u32.serialize(&serializer), [Token::U32(42)]);
+        assert!((&serializer).is_human_readable());
     }
 
     #[test]
-    fn serialize_u64() {
-        let serializer = Serializer::builder().build();
+    fn is_human_readable_false() {
+        let serializer = Serializer::builder().is_human_readable(false).build();
 
-        assert_ok_eq!(42u64.serialize(&serializer), [Token::U64(42)]);
+        assert!(!(&serializer).is_human_readable());
     }
 
     #[test]
-    fn serialize_u128() {
-        let serializer = Serializer::builder().build();
+    fn is_human_readable_true() {
+        let serializer = Serializer::builder().is_human_readable(true).build();
 
-        assert_ok_eq!(42u128.serialize(&serializer), [Token::U128(42)]);
+        assert!((&serializer).is_human_readable());
     }
 
     #[test]
-    fn serialize_f32() {
-        let serializer = Serializer::builder().build();
+    fn custom_error() {
+        let error = Error::custom("foo");
 
-        assert_ok_eq!(42f32.serialize(&serializer), [Token::F32(42.)]);
+        assert_eq!(error.0, "foo");
     }
 
     #[test]
-    fn serialize_f64() {
-        let serializer = Serializer::builder().build();
+    fn display_error() {
+        let formatted = format!("{}", Error::custom("foo"));
 
-        assert_ok_eq!(42f64.serialize(&serializer), [Token::F64(42.)]);
+        assert_eq!(formatted, "foo");
     }
 
     #[test]
-    fn serialize_char() {
+    fn enum_variants_empty() {
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!('a'.serialize(&serializer), [Token::Char('a')]);
+        let recording = assert_ok!(serializer.record(&true));
+        assert_eq!(recording.enum_variants(), []);
     }
 
     #[test]
-    fn serialize_str() {
+    fn enum_variants() {
+        #[derive(Serialize)]
+        enum Enum {
+            Unit,
+            Newtype(bool),
+            Tuple(bool, bool),
+            Struct { foo: bool },
+        }
+
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!("a".serialize(&serializer), [Token::Str("a".to_owned())]);
+        let recording = assert_ok!(serializer.record(&[
+            Enum::Unit,
+            Enum::Newtype(true),
+            Enum::Tuple(true, false),
+            Enum::Struct { foo: true },
+        ]));
+        assert_eq!(
+            recording.enum_variants(),
+            [
+                EnumVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Unit",
+                    representation: EnumRepresentation::External,
+                },
+                EnumVariant {
+                    name: "Enum",
+                    variant_index: 1,
+                    variant: "Newtype",
+                    representation: EnumRepresentation::External,
+                },
+                EnumVariant {
+                    name: "Enum",
+                    variant_index: 2,
+                    variant: "Tuple",
+                    representation: EnumRepresentation::External,
+                },
+                EnumVariant {
+                    name: "Enum",
+                    variant_index: 3,
+                    variant: "Struct",
+                    representation: EnumRepresentation::External,
+                },
+            ]
+        );
+    }
+
+    #[derive(Serialize)]
+    enum External {
+        Unit,
+        Newtype(u32),
+        Tuple(u32, bool),
+        Struct {
+            foo: u32,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            bar: Option<u32>,
+        },
     }
 
     #[test]
-    fn serialize_bytes() {
-        let serializer = Serializer::builder().build();
+    fn serialize_enum_as_map_unit() {
+        let serializer = Serializer::builder().serialize_enum_as_map(true).build();
 
         assert_ok_eq!(
-            Bytes::new(b"a").serialize(&serializer),
-            [Token::Bytes(b"a".to_vec())]
+            External::Unit.serialize(&serializer),
+            [Token::Str("Unit".to_owned())]
         );
     }
 
     #[test]
-    fn serialize_none() {
-        let serializer = Serializer::builder().build();
+    fn serialize_enum_as_map_newtype() {
+        let serializer = Serializer::builder().serialize_enum_as_map(true).build();
 
-        assert_ok_eq!(Option::<()>::None.serialize(&serializer), [Token::None]);
+        assert_ok_eq!(
+            External::Newtype(42).serialize(&serializer),
+            [
+                Token::Map { len: Some(1) },
+                Token::Str("Newtype".to_owned()),
+                Token::U32(42),
+                Token::MapEnd,
+            ]
+        );
     }
 
     #[test]
-    fn serialize_some() {
-        let serializer = Serializer::builder().build();
+    fn serialize_enum_as_map_tuple() {
+        let serializer = Serializer::builder().serialize_enum_as_map(true).build();
 
         assert_ok_eq!(
-            Some(true).serialize(&serializer),
-            [Token::Some, Token::Bool(true)]
+            External::Tuple(42, true).serialize(&serializer),
+            [
+                Token::Map { len: Some(1) },
+                Token::Str("Tuple".to_owned()),
+                Token::Seq { len: Some(2) },
+                Token::U32(42),
+                Token::Bool(true),
+                Token::SeqEnd,
+                Token::MapEnd,
+            ]
         );
     }
 
     #[test]
-    fn serialize_unit() {
-        let serializer = Serializer::builder().build();
+    fn serialize_enum_as_map_struct() {
+        let serializer = Serializer::builder().serialize_enum_as_map(true).build();
 
-        assert_ok_eq!(().serialize(&serializer), [Token::Unit]);
+        assert_ok_eq!(
+            External::Struct { foo: 42, bar: None }.serialize(&serializer),
+            [
+                Token::Map { len: Some(1) },
+                Token::Str("Struct".to_owned()),
+                Token::Map { len: Some(1) },
+                Token::Str("foo".to_owned()),
+                Token::U32(42),
+                Token::MapEnd,
+                Token::MapEnd,
+            ]
+        );
     }
 
     #[test]
-    fn serialize_unit_struct() {
-        #[derive(Serialize)]
-        struct Unit;
-
-        let serializer = Serializer::builder().build();
+    fn serialize_enum_as_map_sorted() {
+        let serializer = Serializer::builder()
+            .serialize_enum_as_map(true)
+            .sort_map_entries(true)
+            .build();
 
         assert_ok_eq!(
-            Unit.serialize(&serializer),
-            [Token::UnitStruct { name: "Unit" }]
+            External::Struct {
+                foo: 42,
+                bar: Some(1)
+            }
+            .serialize(&serializer),
+            [
+                Token::Map { len: Some(1) },
+                Token::Str("Struct".to_owned()),
+                Token::Map { len: Some(2) },
+                Token::Str("bar".to_owned()),
+                Token::Some,
+                Token::U32(1),
+                Token::Str("foo".to_owned()),
+                Token::U32(42),
+                Token::MapEnd,
+                Token::MapEnd,
+            ]
         );
     }
 
     #[test]
-    fn serialize_unit_variant() {
-        #[derive(Serialize)]
-        enum Unit {
-            Variant,
+    fn serialize_enum_as_map_roundtrip() {
+        #[derive(Debug, serde_derive::Deserialize, PartialEq, Serialize)]
+        enum Enum {
+            Unit,
+            Tuple(u32, bool),
+            Struct { foo: u32 },
         }
 
-        let serializer = Serializer::builder().build();
+        let serializer = Serializer::builder().serialize_enum_as_map(true).build();
+        let value = (Enum::Unit, Enum::Tuple(42, true), Enum::Struct { foo: 42 });
+        let tokens = assert_ok!(value.serialize(&serializer));
+        let mut deserializer = crate::Deserializer::builder(tokens)
+            .deserialize_enum_from_map(true)
+            .build();
 
         assert_ok_eq!(
-            Unit::Variant.serialize(&serializer),
-            [Token::UnitVariant {
-                name: "Unit",
-                variant_index: 0,
-                variant: "Variant"
+            <(Enum, Enum, Enum) as serde::Deserialize>::deserialize(&mut deserializer),
+            value
+        );
+    }
+
+    #[test]
+    fn enum_variants_map() {
+        let serializer = Serializer::builder().serialize_enum_as_map(true).build();
+
+        let recording = assert_ok!(serializer.record(&External::Newtype(42)));
+        assert_eq!(
+            recording.enum_variants(),
+            [EnumVariant {
+                name: "External",
+                variant_index: 1,
+                variant: "Newtype",
+                representation: EnumRepresentation::Map,
             }]
         );
     }
 
     #[test]
-    fn serialize_newtype_struct() {
+    fn enum_variants_internally_tagged_not_recorded() {
         #[derive(Serialize)]
-        struct Newtype(bool);
+        #[serde(tag = "type")]
+        enum Internal {
+            Variant,
+        }
 
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(
-            Newtype(false).serialize(&serializer),
-            [Token::NewtypeStruct { name: "Newtype" }, Token::Bool(false)]
-        );
+        let recording = assert_ok!(serializer.record(&Internal::Variant));
+        assert_eq!(recording.enum_variants(), []);
     }
 
     #[test]
-    fn serialize_newtype_variant() {
-        #[derive(Serialize)]
-        enum Newtype {
-            Variant(bool),
-        }
-
+    fn record_tokens() {
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(
-            Newtype::Variant(false).serialize(&serializer),
+        let recording = assert_ok!(serializer.record(&(true, 42u8)));
+
+        assert_eq!(
+            *recording.tokens(),
             [
-                Token::NewtypeVariant {
-                    name: "Newtype",
-                    variant_index: 0,
-                    variant: "Variant"
-                },
-                Token::Bool(false)
+                Token::Tuple { len: 2 },
+                Token::Bool(true),
+                Token::U8(42),
+                Token::TupleEnd,
+            ]
+        );
+        assert_eq!(
+            recording.into_tokens(),
+            [
+                Token::Tuple { len: 2 },
+                Token::Bool(true),
+                Token::U8(42),
+                Token::TupleEnd,
             ]
         );
     }
 
     #[test]
-    fn serialize_seq() {
-        let serializer = Serializer::builder().build();
+    fn serializer_is_sync() {
+        fn assert_sync<T: Sync>(_: &T) {}
 
-        assert_ok_eq!(
-            vec![1i8, 2i8, 3i8].serialize(&serializer),
-            [
-                Token::Seq { len: Some(3) },
-                Token::I8(1),
-                Token::I8(2),
-                Token::I8(3),
-                Token::SeqEnd
-            ],
-        );
+        assert_sync(&Serializer::builder().post_process(|_| {}).build());
     }
 
     #[test]
-    fn serialize_seq_unordered() {
+    fn calls_primitive() {
         let serializer = Serializer::builder().build();
 
-        let mut set = HashSet::new();
-        set.insert('a');
-        set.insert('b');
-        set.insert('c');
+        let recording = assert_ok!(serializer.record(&true));
+        let calls = recording.calls();
 
-        assert_ok_eq!(
-            set.serialize(&serializer),
-            [
-                Token::Seq { len: Some(3) },
-                Token::Unordered(&[
-                    &[Token::Char('a')],
-                    &[Token::Char('b')],
-                    &[Token::Char('c')],
-                ]),
-                Token::SeqEnd,
-            ]
-        );
+        assert_eq!(calls.count("serialize_bool"), 1);
+        assert_eq!(calls.count("serialize_u8"), 0);
+        assert_eq!(calls.total(), 1);
+        assert_eq!(calls.tokens(), 1);
     }
 
     #[test]
-    fn serialize_tuple() {
+    fn calls_struct() {
+        #[derive(Serialize)]
+        struct Struct {
+            foo: bool,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            bar: Option<u32>,
+        }
+
         let serializer = Serializer::builder().build();
 
-        assert_ok_eq!(
-            (1i8, 2i16, 3i32).serialize(&serializer),
-            [
-                Token::Tuple { len: 3 },
-                Token::I8(1),
-                Token::I16(2),
-                Token::I32(3),
-                Token::TupleEnd
-            ],
-        );
+        let recording = assert_ok!(serializer.record(&Struct {
+            foo: true,
+            bar: None
+        }));
+        let calls = recording.calls();
+
+        let mut expected = BTreeMap::new();
+        expected.insert("end", 1);
+        expected.insert("serialize_bool", 1);
+        expected.insert("serialize_field", 1);
+        expected.insert("serialize_struct", 1);
+        expected.insert("skip_field", 1);
+        assert_eq!(*calls.counts(), expected);
+        assert_eq!(calls.total(), 5);
+        assert_eq!(calls.tokens(), 5);
     }
 
     #[test]
-    fn serialize_tuple_struct() {
-        #[derive(Serialize)]
-        struct TupleStruct(i8, i16, i32);
+    fn coverage_empty() {
+        static COVERAGE: Coverage = Coverage::new();
 
-        let serializer = Serializer::builder().build();
+        assert_eq!(COVERAGE.hit(), [] as [&str; 0]);
+        assert_eq!(COVERAGE.missed().len(), 30);
+    }
 
-        assert_ok_eq!(
-            TupleStruct(1i8, 2i16, 3i32).serialize(&serializer),
-            [
-                Token::TupleStruct {
-                    name: "TupleStruct",
-                    len: 3
-                },
-                Token::I8(1),
-                Token::I16(2),
-                Token::I32(3),
-                Token::TupleStructEnd
-            ],
+    #[test]
+    fn coverage_aggregates_serializers() {
+        static COVERAGE: Coverage = Coverage::new();
+
+        let first = Serializer::builder().coverage(&COVERAGE).build();
+        let second = Serializer::builder().coverage(&COVERAGE).build();
+        assert_ok!(true.serialize(&first));
+        assert_ok!((false, 42u8).serialize(&second));
+
+        assert_eq!(COVERAGE.count("serialize_bool"), 2);
+        assert_eq!(COVERAGE.count("serialize_u8"), 1);
+        assert_eq!(COVERAGE.count("serialize_tuple"), 1);
+        assert_eq!(
+            COVERAGE.hit(),
+            ["serialize_bool", "serialize_u8", "serialize_tuple"]
         );
     }
 
     #[test]
-    fn serialize_tuple_variant() {
-        #[derive(Serialize)]
-        enum Tuple {
-            Variant(i8, i16, i32),
-        }
+    fn coverage_ignores_compound_methods() {
+        static COVERAGE: Coverage = Coverage::new();
 
-        let serializer = Serializer::builder().build();
+        let serializer = Serializer::builder().coverage(&COVERAGE).build();
+        assert_ok!(vec![1u8].serialize(&serializer));
 
-        assert_ok_eq!(
-            Tuple::Variant(1i8, 2i16, 3i32).serialize(&serializer),
-            [
-                Token::TupleVariant {
-                    name: "Tuple",
-                    variant_index: 0,
-                    variant: "Variant",
-                    len: 3
-                },
-                Token::I8(1),
-                Token::I16(2),
-                Token::I32(3),
-                Token::TupleVariantEnd
-            ],
-        );
+        assert_eq!(COVERAGE.count("serialize_element"), 0);
+        assert_eq!(COVERAGE.hit(), ["serialize_u8", "serialize_seq"]);
+    }
+
+    #[test]
+    fn coverage_missed() {
+        static COVERAGE: Coverage = Coverage::new();
+
+        let serializer = Serializer::builder().coverage(&COVERAGE).build();
+        assert_ok!(Some(()).serialize(&serializer));
+        let missed = COVERAGE.missed();
+
+        assert_eq!(missed.len(), 28);
+        assert!(!missed.contains(&"serialize_some"));
+        assert!(!missed.contains(&"serialize_unit"));
+        assert!(missed.contains(&"serialize_none"));
+    }
+
+    #[test]
+    fn coverage_reset() {
+        static COVERAGE: Coverage = Coverage::new();
+
+        let serializer = Serializer::builder().coverage(&COVERAGE).build();
+        assert_ok!(true.serialize(&serializer));
+        COVERAGE.reset();
+
+        assert_eq!(COVERAGE.count("serialize_bool"), 0);
     }
 
     #[test]
-    fn serialize_map() {
+    fn coverage_display() {
+        static COVERAGE: Coverage = Coverage::new();
+
+        let serializer = Serializer::builder().coverage(&COVERAGE).build();
+        assert_ok!(true.serialize(&serializer));
+        assert_ok!(false.serialize(&serializer));
+        let display = format!("{COVERAGE}");
+
+        assert!(display.starts_with(
+            "serializer coverage: 1 of 30 methods called\n  hit:    serialize_bool (2)\n  missed: \
+             serialize_i8\n"
+        ));
+        assert!(display.ends_with("\n  missed: serialize_struct_variant"));
+    }
+
+    #[test]
+    fn calls_map() {
         let serializer = Serializer::builder().build();
 
         let mut map = HashMap::new();
-        map.insert(1i8, 'a');
-        map.insert(2i8, 'b');
-        map.insert(3i8, 'c');
+        map.insert('a', 1u8);
+        map.insert('b', 2u8);
+        let recording = assert_ok!(serializer.record(&map));
+        let calls = recording.calls();
+
+        assert_eq!(calls.count("serialize_map"), 1);
+        assert_eq!(calls.count("serialize_key"), 2);
+        assert_eq!(calls.count("serialize_value"), 2);
+        assert_eq!(calls.count("serialize_char"), 2);
+        assert_eq!(calls.count("serialize_u8"), 2);
+        assert_eq!(calls.count("end"), 1);
+        assert_eq!(calls.tokens(), 6);
+    }
+
+    #[test]
+    fn calls_per_value() {
+        let serializer = Serializer::builder().build();
+
+        assert_ok!(serializer.record(&true));
+        let recording = assert_ok!(serializer.record(&Some(42u8)));
+        let calls = recording.calls();
+
+        assert_eq!(calls.total(), 2);
+        assert_eq!(calls.tokens(), 2);
+    }
+
+    #[test]
+    fn post_process_primitive() {
+        let serializer = Serializer::builder()
+            .post_process(|tokens| tokens.push(Token::Unit))
+            .build();
 
         assert_ok_eq!(
-            map.serialize(&serializer),
-            [
-                Token::Map { len: Some(3) },
-                Token::Unordered(&[
-                    &[Token::I8(1), Token::Char('a')],
-                    &[Token::I8(2), Token::Char('b')],
-                    &[Token::I8(3), Token::Char('c')],
-                ]),
-                Token::MapEnd,
-            ]
+            true.serialize(&serializer),
+            [Token::Bool(true), Token::Unit]
         );
     }
 
     #[test]
-    fn serialize_struct() {
-        #[derive(Serialize)]
-        struct Struct {
-            a: bool,
-            b: u16,
-            c: String,
-        }
-
-        let serializer = Serializer::builder().build();
+    fn post_process_applied_once_to_outermost_value() {
+        let serializer = Serializer::builder()
+            .post_process(|tokens| tokens.push(Token::Unit))
+            .build();
 
         assert_ok_eq!(
-            Struct {
-                a: true,
-                b: 42,
-                c: "foo".to_owned(),
-            }
-            .serialize(&serializer),
+            (Some(true), [1u8]).serialize(&serializer),
             [
-                Token::Struct {
-                    name: "Struct",
-                    len: 3,
-                },
-                Token::Field("a"),
+                Token::Tuple { len: 2 },
+                Token::Some,
                 Token::Bool(true),
-                Token::Field("b"),
-                Token::U16(42),
-                Token::Field("c"),
-                Token::Str("foo".to_owned()),
-                Token::StructEnd,
+                Token::Tuple { len: 1 },
+                Token::U8(1),
+                Token::TupleEnd,
+                Token::TupleEnd,
+                Token::Unit,
             ]
         );
     }
 
     #[test]
-    fn serialize_struct_skipped_field() {
-        fn skip<T>(_: &T) -> bool {
-            true
-        }
-
+    fn post_process_struct() {
         #[derive(Serialize)]
         struct Struct {
-            a: bool,
-            #[serde(skip_serializing_if = "skip")]
-            b: u16,
-            c: String,
+            foo: bool,
+            bar: u8,
         }
 
-        let serializer = Serializer::builder().build();
+        let serializer = Serializer::builder()
+            .post_process(|tokens| {
+                tokens.retain(|token| !matches!(token, Token::Field("bar") | Token::U8(_)));
+            })
+            .build();
 
         assert_ok_eq!(
-            Struct {
-                a: true,
-                b: 42,
-                c: "foo".to_owned(),
-            }
-            .serialize(&serializer),
+            Struct { foo: true, bar: 42 }.serialize(&serializer),
             [
                 Token::Struct {
                     name: "Struct",
                     len: 2,
                 },
-                Token::Field("a"),
+                Token::Field("foo"),
                 Token::Bool(true),
-                Token::SkippedField("b"),
-                Token::Field("c"),
-                Token::Str("foo".to_owned()),
                 Token::StructEnd,
             ]
         );
     }
 
     #[test]
-    fn serialize_struct_as_seq() {
-        #[derive(Serialize)]
-        struct Struct {
-            foo: bool,
-            bar: u32,
-        }
+    fn post_process_order() {
+        let serializer = Serializer::builder()
+            .post_process(|tokens| tokens.push(Token::U8(1)))
+            .post_process(|tokens| tokens.push(Token::U8(2)))
+            .build();
 
-        let some_struct = Struct {
-            foo: false,
-            bar: 42,
-        };
+        assert_ok_eq!(
+            ().serialize(&serializer),
+            [Token::Unit, Token::U8(1), Token::U8(2)]
+        );
+    }
+
+    #[test]
+    fn post_process_after_error() {
         let serializer = Serializer::builder()
-            .serialize_struct_as(SerializeStructAs::Seq)
+            .post_process(|tokens| tokens.push(Token::Unit))
             .build();
 
+        assert_err_eq!([Failing].serialize(&serializer), Error::custom("failed"));
         assert_ok_eq!(
-            some_struct.serialize(&serializer),
-            [
-                Token::Seq { len: Some(2) },
-                Token::Bool(false),
-                Token::U32(42),
-                Token::SeqEnd,
-            ]
+            true.serialize(&serializer),
+            [Token::Bool(true), Token::Unit]
         );
     }
 
     #[test]
-    fn serialize_struct_variant() {
+    fn post_process_builder_reused() {
+        let mut builder = Serializer::builder();
+        builder.post_process(Vec::clear);
+        let first = builder.build();
+        let second = builder.build();
+
+        assert_ok_eq!(true.serialize(&first), []);
+        assert_ok_eq!(true.serialize(&second), []);
+    }
+
+    #[test]
+    fn recording_serializer_primitive() {
+        let recorder = Serializer::builder().build();
+        let mut output = Vec::new();
+        let mut json = serde_json::Serializer::new(&mut output);
+
+        let ((), tokens) =
+            assert_ok!(42u8.serialize(RecordingSerializer::new(&mut json, &recorder)));
+
+        assert_eq!(output, b"42");
+        assert_eq!(tokens, [Token::U8(42)]);
+    }
+
+    #[test]
+    fn recording_serializer_struct() {
         #[derive(Serialize)]
-        enum Struct {
-            Variant { a: bool, b: u16, c: String },
+        struct Point {
+            x: u32,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            y: Option<u32>,
         }
 
-        let serializer = Serializer::builder().build();
+        let recorder = Serializer::builder().build();
+        let mut output = Vec::new();
+        let mut json = serde_json::Serializer::new(&mut output);
 
-        assert_ok_eq!(
-            Struct::Variant {
-                a: true,
-                b: 42,
-                c: "foo".to_owned(),
-            }
-            .serialize(&serializer),
+        let ((), tokens) = assert_ok!(
+            Point { x: 1, y: None }.serialize(RecordingSerializer::new(&mut json, &recorder))
+        );
+
+        assert_eq!(output, br#"{"x":1}"#);
+        assert_eq!(
+            tokens,
             [
-                Token::StructVariant {
-                    name: "Struct",
-                    variant_index: 0,
-                    variant: "Variant",
-                    len: 3,
+                Token::Struct {
+                    name: "Point",
+                    len: 1,
                 },
-                Token::Field("a"),
-                Token::Bool(true),
-                Token::Field("b"),
-                Token::U16(42),
-                Token::Field("c"),
-                Token::Str("foo".to_owned()),
-                Token::StructVariantEnd,
+                Token::Field("x"),
+                Token::U32(1),
+                Token::SkippedField("y"),
+                Token::StructEnd,
             ]
         );
     }
 
     #[test]
-    fn serialize_struct_variant_skipped_field() {
-        fn skip<T>(_: &T) -> bool {
-            true
-        }
-
+    fn recording_serializer_nested() {
         #[derive(Serialize)]
-        enum Struct {
-            Variant {
-                a: bool,
-                #[serde(skip_serializing_if = "skip")]
-                b: u16,
-                c: String,
-            },
+        enum Shape {
+            Line(Vec<(u8, u8)>),
+            Circle { radius: Option<u8> },
         }
 
-        let serializer = Serializer::builder().build();
+        let recorder = Serializer::builder().build();
+        let mut output = Vec::new();
+        let mut json = serde_json::Serializer::new(&mut output);
+        let value = [Shape::Line(vec![(1, 2)]), Shape::Circle { radius: Some(3) }];
 
-        assert_ok_eq!(
-            Struct::Variant {
-                a: true,
-                b: 42,
-                c: "foo".to_owned(),
-            }
-            .serialize(&serializer),
+        let ((), tokens) =
+            assert_ok!(value.serialize(RecordingSerializer::new(&mut json, &recorder)));
+
+        assert_eq!(output, br#"[{"Line":[[1,2]]},{"Circle":{"radius":3}}]"#);
+        assert_eq!(tokens.0, assert_ok!(value.serialize(&recorder)).0);
+    }
+
+    #[test]
+    fn recording_serializer_map() {
+        let recorder = Serializer::builder().sort_map_entries(true).build();
+        let mut output = Vec::new();
+        let mut json = serde_json::Serializer::new(&mut output);
+        let mut value = BTreeMap::new();
+        value.insert("b", 2u8);
+        value.insert("a", 1u8);
+
+        let ((), tokens) =
+            assert_ok!(value.serialize(RecordingSerializer::new(&mut json, &recorder)));
+
+        assert_eq!(output, br#"{"a":1,"b":2}"#);
+        assert_eq!(
+            tokens,
             [
-                Token::StructVariant {
-                    name: "Struct",
-                    variant_index: 0,
-                    variant: "Variant",
-                    len: 2,
-                },
-                Token::Field("a"),
-                Token::Bool(true),
-                Token::SkippedField("b"),
-                Token::Field("c"),
-                Token::Str("foo".to_owned()),
-                Token::StructVariantEnd,
+                Token::Map { len: Some(2) },
+                Token::Str("a".to_owned()),
+                Token::U8(1),
+                Token::Str("b".to_owned()),
+                Token::U8(2),
+                Token::MapEnd,
             ]
         );
     }
 
     #[test]
-    fn collect_str() {
-        struct CollectedString(String);
+    fn recording_serializer_recorder_error() {
+        let recorder = Serializer::builder().max_depth(1).build();
+        let mut output = Vec::new();
+        let mut json = serde_json::Serializer::new(&mut output);
 
-        impl Serialize for CollectedString {
+        let error =
+            assert_err!(Some(Some(42u8)).serialize(RecordingSerializer::new(&mut json, &recorder)));
+
+        let expected = assert_err!(Some(Some(42u8)).serialize(&recorder));
+        assert_eq!(format!("{error}"), format!("{expected}"));
+    }
+
+    #[test]
+    fn recording_serializer_is_human_readable() {
+        struct HumanReadable;
+
+        impl Serialize for HumanReadable {
             fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             where
                 S: serde::Serializer,
             {
-                serializer.collect_str(&self.0)
+                let is_human_readable = serializer.is_human_readable();
+                serializer.serialize_bool(is_human_readable)
             }
         }
 
-        let serializer = Serializer::builder().build();
+        let recorder = Serializer::builder().build();
+        let mut output = Vec::new();
+        let mut json = serde_json::Serializer::new(&mut output);
+
+        let ((), tokens) =
+            assert_ok!(HumanReadable.serialize(RecordingSerializer::new(&mut json, &recorder)));
+
+        assert_eq!(output, b"true");
+        assert_eq!(tokens, [Token::Bool(true)]);
+    }
 
+    #[test]
+    fn to_tokens_default() {
         assert_ok_eq!(
-            CollectedString("foo".to_owned()).serialize(&serializer),
-            [Token::Str("foo".to_owned())]
+            to_tokens(&(1u8, "foo")),
+            [
+                Token::Tuple { len: 2 },
+                Token::U8(1),
+                Token::Str("foo".to_owned()),
+                Token::TupleEnd,
+            ]
         );
     }
 
     #[test]
-    fn is_human_readable_default() {
-        let serializer = Serializer::builder().build();
+    fn to_tokens_error() {
+        struct Failing;
 
-        assert!((&serializer).is_human_readable());
+        impl Serialize for Failing {
+            fn serialize<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                Err(S::Error::custom("foo"))
+            }
+        }
+
+        assert_err_eq!(to_tokens(&Failing), Error("foo".to_owned()));
     }
 
     #[test]
-    fn is_human_readable_false() {
-        let serializer = Serializer::builder().is_human_readable(false).build();
+    fn to_tokens_with_builder() {
+        assert_ok_eq!(
+            to_tokens_with(
+                &None::<u8>,
+                Serializer::builder().serialize_none_as_unit(true)
+            ),
+            [Token::Unit]
+        );
+    }
 
-        assert!(!(&serializer).is_human_readable());
+    #[derive(Serialize)]
+    struct Profiled {
+        foo: Option<char>,
+        #[serde(with = "serde_bytes")]
+        bar: Vec<u8>,
     }
 
     #[test]
-    fn is_human_readable_true() {
-        let serializer = Serializer::builder().is_human_readable(true).build();
+    fn profile_json() {
+        let serializer = Serializer::builder().profile(Profile::Json).build();
 
+        assert_ok_eq!(
+            Profiled {
+                foo: None,
+                bar: vec![1],
+            }
+            .serialize(&serializer),
+            [
+                Token::Struct {
+                    name: "Profiled",
+                    len: 2,
+                },
+                Token::Field("foo"),
+                Token::Unit,
+                Token::Field("bar"),
+                Token::Seq { len: Some(1) },
+                Token::U8(1),
+                Token::SeqEnd,
+                Token::StructEnd,
+            ]
+        );
+        assert_ok_eq!(
+            Some('a').serialize(&serializer),
+            [Token::Some, Token::Str("a".to_owned())]
+        );
         assert!((&serializer).is_human_readable());
     }
 
     #[test]
-    fn custom_error() {
-        let error = Error::custom("foo");
+    fn profile_bincode() {
+        let serializer = Serializer::builder().profile(Profile::Bincode).build();
 
-        assert_eq!(error.0, "foo");
+        assert_ok_eq!(
+            Profiled {
+                foo: Some('a'),
+                bar: vec![1],
+            }
+            .serialize(&serializer),
+            [
+                Token::Seq { len: Some(2) },
+                Token::Some,
+                Token::Char('a'),
+                Token::Bytes(vec![1]),
+                Token::SeqEnd,
+            ]
+        );
+        assert_ok_eq!(42u128.serialize(&serializer), [Token::U128(42)]);
+        assert!(!(&serializer).is_human_readable());
     }
 
     #[test]
-    fn display_error() {
-        let formatted = format!("{}", Error::custom("foo"));
+    fn profile_overridden() {
+        let serializer = Serializer::builder()
+            .profile(Profile::Postcard)
+            .serialize_struct_as(SerializeStructAs::Struct)
+            .build();
 
-        assert_eq!(formatted, "foo");
+        assert_ok_eq!(
+            Profiled {
+                foo: None,
+                bar: Vec::new(),
+            }
+            .serialize(&serializer),
+            [
+                Token::Struct {
+                    name: "Profiled",
+                    len: 2,
+                },
+                Token::Field("foo"),
+                Token::None,
+                Token::Field("bar"),
+                Token::Bytes(Vec::new()),
+                Token::StructEnd,
+            ]
+        );
     }
 }
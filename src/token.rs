@@ -7,21 +7,52 @@
 //!
 //! [`Serializer`]: crate::Serializer
 
+use crate::{
+    ser,
+    Serializer,
+};
 use alloc::{
     boxed::Box,
+    collections::BTreeMap,
     slice,
     string::String,
     vec,
     vec::Vec,
 };
+#[cfg(feature = "safe")]
+use core::ops::DerefMut;
 use core::{
     fmt,
     fmt::Debug,
     marker::PhantomData,
+    mem,
+    ops::Deref,
+    ptr,
+};
+#[cfg(not(feature = "safe"))]
+use core::{
     mem::ManuallyDrop,
     ptr::NonNull,
 };
-use serde::de::Unexpected;
+#[cfg(feature = "rayon")]
+use rayon::iter::{
+    IntoParallelIterator,
+    ParallelIterator,
+};
+use serde::{
+    de::Unexpected,
+    ser::{
+        Error as _,
+        SerializeMap,
+        SerializeSeq,
+        SerializeStruct,
+        SerializeStructVariant,
+        SerializeTuple,
+        SerializeTupleStruct,
+        SerializeTupleVariant,
+    },
+    Serialize,
+};
 
 /// A single serialized value.
 ///
@@ -314,6 +345,64 @@ pub enum Token {
     /// ```
     Bytes(Vec<u8>),
 
+    /// A string that can be constructed in a `const` context.
+    ///
+    /// This is equivalent to [`Str`] when comparing or deserializing, but allows tokens containing
+    /// strings to be declared in `const` items, so that shared fixtures can be defined without
+    /// lazy initialization. This is never produced by the [`Serializer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// const EXPECTED: [Token; 4] = [
+    ///     Token::Tuple { len: 2 },
+    ///     Token::StaticStr("foo"),
+    ///     Token::StaticStr("bar"),
+    ///     Token::TupleEnd,
+    /// ];
+    ///
+    /// let serializer = Serializer::builder().build();
+    ///
+    /// assert_ok_eq!(("foo", "bar").serialize(&serializer), EXPECTED);
+    /// ```
+    ///
+    /// [`Serializer`]: crate::Serializer
+    /// [`Str`]: Token::Str
+    StaticStr(&'static str),
+
+    /// Bytes that can be constructed in a `const` context.
+    ///
+    /// This is equivalent to [`Bytes`] when comparing or deserializing, but allows tokens
+    /// containing bytes to be declared in `const` items. This is never produced by the
+    /// [`Serializer`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// use serde_bytes::Bytes;
+    ///
+    /// const EXPECTED: [Token; 1] = [Token::StaticBytes(b"foo")];
+    ///
+    /// let serializer = Serializer::builder().build();
+    ///
+    /// assert_ok_eq!(Bytes::new(b"foo").serialize(&serializer), EXPECTED);
+    /// ```
+    ///
+    /// [`Bytes`]: Token::Bytes
+    /// [`Serializer`]: crate::Serializer
+    StaticBytes(&'static [u8]),
+
     /// An [`Option::None`].
     ///
     /// # Example
@@ -822,6 +911,45 @@ pub enum Token {
     /// [`StructVariant`]: Token::StructVariant
     StructVariantEnd,
 
+    /// A failure at this point of the input.
+    ///
+    /// This token is never produced by the [`Serializer`]. When the [`Deserializer`] reaches it,
+    /// deserialization fails with a [`de::Error::Custom`] containing the given message. This
+    /// allows placing failures precisely within the input, such as within a specific map
+    /// value, for testing how a `Deserialize` implementation handles errors.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_err_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     de::Error,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// use std::collections::HashMap;
+    ///
+    /// let mut deserializer = Deserializer::builder([
+    ///     Token::Map { len: Some(2) },
+    ///     Token::Char('a'),
+    ///     Token::U32(1),
+    ///     Token::Char('b'),
+    ///     Token::Error("corrupt value".to_owned()),
+    ///     Token::MapEnd,
+    /// ])
+    /// .build();
+    ///
+    /// assert_err_eq!(
+    ///     HashMap::<char, u32>::deserialize(&mut deserializer),
+    ///     Error::Custom("corrupt value".to_owned())
+    /// );
+    /// ```
+    ///
+    /// [`de::Error::Custom`]: crate::de::Error::Custom
+    /// [`Deserializer`]: crate::Deserializer
+    /// [`Serializer`]: crate::Serializer
+    Error(String),
+
     /// Unordered sets of tokens.
     ///
     /// This token is primarily used for evaluating output from a [`Serializer`] for containers or
@@ -831,6 +959,13 @@ pub enum Token {
     /// equality of [`Tokens`]. In other words, the outer slice is unordered, while the inner
     /// slices are all ordered.
     ///
+    /// The groups are treated as a multiset: each group must match exactly one occurrence in the
+    /// compared tokens. If the same group is provided twice, it must therefore occur exactly
+    /// twice.
+    ///
+    /// When the `rayon` feature is enabled, comparisons involving very large unordered groups are
+    /// performed in parallel.
+    ///
     /// # Example
     /// ``` rust
     /// use claims::assert_ok_eq;
@@ -865,10 +1000,178 @@ pub enum Token {
     /// [`HashSet`]: std::collections::HashSet
     /// [`Serializer`]: crate::Serializer
     Unordered(&'static [&'static [Token]]),
+
+    /// Unordered sets of tokens, owning their groups.
+    ///
+    /// This behaves exactly like [`Unordered`], but owns its groups rather than borrowing them for
+    /// the `'static` lifetime. This allows unordered tokens to be constructed from data computed
+    /// at runtime, such as tokens generated for each element of a collection.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// use std::collections::HashSet;
+    ///
+    /// let serializer = Serializer::builder().build();
+    ///
+    /// let set = (0..10).collect::<HashSet<u32>>();
+    ///
+    /// assert_ok_eq!(
+    ///     set.serialize(&serializer),
+    ///     [
+    ///         Token::Seq { len: Some(10) },
+    ///         Token::UnorderedOwned((0..10).map(|i| vec![Token::U32(i)]).collect()),
+    ///         Token::SeqEnd
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`Unordered`]: Token::Unordered
+    UnorderedOwned(Vec<Vec<Token>>),
+
+    /// An ordered group of tokens.
+    ///
+    /// Consecutive `Group` tokens are treated as a set of groups that may appear in any order when
+    /// comparing equality of [`Tokens`], while the tokens within each group remain ordered. This
+    /// is equivalent to an [`Unordered`] token containing each group, but avoids nesting the
+    /// groups within another slice. This is convenient for maps, whose entries consist of multiple
+    /// ordered tokens.
+    ///
+    /// A `Group` that is not adjacent to any other `Group` is simply compared in order.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// use std::collections::HashMap;
+    ///
+    /// let serializer = Serializer::builder().build();
+    ///
+    /// let mut map = HashMap::<char, u32>::new();
+    /// map.insert('a', 1);
+    /// map.insert('b', 2);
+    /// map.insert('c', 3);
+    ///
+    /// assert_ok_eq!(
+    ///     map.serialize(&serializer),
+    ///     [
+    ///         Token::Map { len: Some(3) },
+    ///         Token::Group(&[Token::Char('a'), Token::U32(1)]),
+    ///         Token::Group(&[Token::Char('b'), Token::U32(2)]),
+    ///         Token::Group(&[Token::Char('c'), Token::U32(3)]),
+    ///         Token::MapEnd
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`Unordered`]: Token::Unordered
+    Group(&'static [Token]),
+
+    /// A token annotated with a label.
+    ///
+    /// Labels are ignored when comparing or deserializing tokens, behaving exactly like the
+    /// wrapped token. However, they are included in failure messages involving the token, such as
+    /// those reported by [`assert_cases()`] and [`assert_corpus()`], and in the [`Consumption`]s
+    /// recorded by the [`Deserializer`]. This allows failures in large generated fixtures to name
+    /// the logical field that failed, rather than just an index.
+    ///
+    /// If the wrapped token is an [`Unordered`] or [`Group`] token, the label applies to every
+    /// token within it. These tokens are most easily constructed using [`Token::labeled()`].
+    ///
+    /// [`assert_cases()`]: crate::assert_cases()
+    /// [`assert_corpus()`]: crate::assert_corpus()
+    /// [`Consumption`]: crate::de::Consumption
+    /// [`Deserializer`]: crate::Deserializer
+    /// [`Group`]: Token::Group
+    /// [`Unordered`]: Token::Unordered
+    Labeled {
+        label: &'static str,
+        token: Box<Token>,
+    },
+}
+
+impl Token {
+    /// Annotates a token with a label.
+    ///
+    /// This is a convenience for constructing a [`Token::Labeled`].
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut deserializer =
+    ///     Deserializer::builder([Token::labeled("user.created_at", Token::U64(42))])
+    ///         .record_consumptions(true)
+    ///         .build();
+    ///
+    /// assert_ok_eq!(u64::deserialize(&mut deserializer), 42);
+    /// assert_eq!(
+    ///     deserializer.consumptions()[0].label(),
+    ///     Some("user.created_at")
+    /// );
+    /// ```
+    #[must_use]
+    pub fn labeled(label: &'static str, token: Token) -> Self {
+        Self::Labeled {
+            label,
+            token: Box::new(token),
+        }
+    }
+
+    /// Serializes a single value into [`Tokens`] using a default [`Serializer`].
+    ///
+    /// This is useful for embedding the serialized form of a value within a larger expected
+    /// sequence of `Token`s, rather than maintaining that fragment by hand.
+    ///
+    /// # Errors
+    /// Returns any [`ser::Error`] encountered while serializing the value.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::{
+    ///     assert_ok,
+    ///     assert_ok_eq,
+    /// };
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let mut expected = vec![Token::Tuple { len: 2 }];
+    /// expected.extend(assert_ok!(Token::from_value(&Some(42u32))));
+    /// expected.extend([Token::Bool(true), Token::TupleEnd]);
+    ///
+    /// let serializer = Serializer::new();
+    /// assert_ok_eq!((Some(42u32), true).serialize(&serializer), expected);
+    /// ```
+    pub fn from_value<T>(value: &T) -> Result<Tokens, ser::Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(&Serializer::new())
+    }
 }
 
 /// An enumeration of all tokens that can be emitted by the [`Serializer`].
 ///
+/// Enum variant information is boxed to keep the size of each token small, as large streams of
+/// tokens are compared frequently.
+///
 /// [`Serializer`]: crate::Serializer
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum CanonicalToken {
@@ -891,68 +1194,201 @@ pub(crate) enum CanonicalToken {
     None,
     Some,
     Unit,
-    UnitStruct {
-        name: &'static str,
-    },
-    UnitVariant {
-        name: &'static str,
-        variant_index: u32,
-        variant: &'static str,
-    },
-    NewtypeStruct {
-        name: &'static str,
-    },
-    NewtypeVariant {
-        name: &'static str,
-        variant_index: u32,
-        variant: &'static str,
-    },
-    Seq {
-        len: Option<usize>,
-    },
+    UnitStruct { name: &'static str },
+    UnitVariant { id: Box<VariantId> },
+    NewtypeStruct { name: &'static str },
+    NewtypeVariant { id: Box<VariantId> },
+    Seq { len: Option<usize> },
     SeqEnd,
-    Tuple {
-        len: usize,
-    },
+    Tuple { len: usize },
     TupleEnd,
-    TupleStruct {
-        name: &'static str,
-        len: usize,
-    },
+    TupleStruct { name: &'static str, len: usize },
     TupleStructEnd,
-    TupleVariant {
-        name: &'static str,
-        variant_index: u32,
-        variant: &'static str,
-        len: usize,
-    },
+    TupleVariant { id: Box<VariantId>, len: usize },
     TupleVariantEnd,
-    Map {
-        len: Option<usize>,
-    },
+    Map { len: Option<usize> },
     MapEnd,
     Field(&'static str),
     SkippedField(&'static str),
-    Struct {
-        name: &'static str,
-        len: usize,
-    },
+    Struct { name: &'static str, len: usize },
     StructEnd,
-    StructVariant {
-        name: &'static str,
-        variant_index: u32,
-        variant: &'static str,
-        len: usize,
-    },
+    StructVariant { id: Box<VariantId>, len: usize },
     StructVariantEnd,
+    Error(String),
+}
+
+/// A function rewriting a sequence of tokens.
+pub(crate) type Pass = dyn Fn(&mut Vec<Token>);
+
+/// Identifies an enum variant within a [`CanonicalToken`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct VariantId {
+    pub(crate) name: &'static str,
+    pub(crate) variant_index: u32,
+    pub(crate) variant: &'static str,
+}
+
+/// Labels attached to canonical tokens, keyed by the index of each labeled token.
+pub(crate) type Labels = BTreeMap<usize, &'static str>;
+
+/// Returns the labels of the canonical tokens produced from the given tokens.
+///
+/// The tokens are traversed in the same order they are flattened by [`Tokens::canonicalize()`].
+/// When labels are nested, the outermost label is used.
+pub(crate) fn labels(tokens: &[Token]) -> Labels {
+    let mut labels = Labels::new();
+    let mut index = 0;
+    let mut stack = vec![(tokens.iter(), None)];
+    while let Some((iter, label)) = stack.last_mut() {
+        let label = *label;
+        match iter.next() {
+            None => {
+                stack.pop();
+            }
+            Some(Token::Labeled {
+                label: inner_label,
+                token,
+            }) => {
+                stack.push((
+                    slice::from_ref(&**token).iter(),
+                    label.or(Some(*inner_label)),
+                ));
+            }
+            Some(Token::Unordered(groups)) => {
+                stack.extend(groups.iter().rev().map(|group| (group.iter(), label)));
+            }
+            Some(Token::UnorderedOwned(groups)) => {
+                stack.extend(groups.iter().rev().map(|group| (group.iter(), label)));
+            }
+            Some(Token::Group(group)) => stack.push((group.iter(), label)),
+            Some(_) => {
+                if let Some(label) = label {
+                    labels.insert(index, label);
+                }
+                index += 1;
+            }
+        }
+    }
+    labels
+}
+
+/// Groups of tokens that may appear in any order.
+///
+/// This is returned when converting a [`Token::Unordered`] or [`Token::Group`] into a
+/// [`CanonicalToken`].
+pub(crate) struct UnorderedTokens(pub(crate) Vec<UnorderedGroup>);
+
+/// A single group of tokens within a set of unordered groups.
+///
+/// Groups provided by [`Token::Unordered`] and [`Token::Group`] are borrowed, while groups
+/// provided by [`Token::UnorderedOwned`] are reference counted, allowing them to be cheaply cloned
+/// between the contexts used when matching.
+#[derive(Clone, Debug)]
+pub(crate) enum UnorderedGroup {
+    Static(&'static [Token]),
+    Owned(Shared<[Token]>),
+}
+
+/// The reference-counted pointer used to share data between the contexts used when matching.
+///
+/// Contexts are sent between threads when matching in parallel, which requires an `Arc`.
+/// Otherwise an `Rc` is used, as `alloc::sync` is not available on all targets.
+#[cfg(feature = "rayon")]
+type Shared<T> = alloc::sync::Arc<T>;
+#[cfg(not(feature = "rayon"))]
+type Shared<T> = alloc::rc::Rc<T>;
+
+impl Deref for UnorderedGroup {
+    type Target = [Token];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Static(tokens) => tokens,
+            Self::Owned(tokens) => tokens,
+        }
+    }
+}
+
+/// An iterator over the tokens of an [`UnorderedGroup`], yielding cloned tokens.
+#[derive(Clone, Debug)]
+struct GroupIter {
+    group: UnorderedGroup,
+    index: usize,
+}
+
+impl GroupIter {
+    fn new(group: UnorderedGroup) -> Self {
+        Self { group, index: 0 }
+    }
+
+    /// Returns the tokens remaining in this iterator.
+    fn as_slice(&self) -> &[Token] {
+        &self.group[self.index..]
+    }
+}
+
+impl Iterator for GroupIter {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.group.get(self.index)?.clone();
+        self.index += 1;
+        Some(token)
+    }
 }
 
-pub(crate) struct UnorderedTokens(pub(crate) &'static [&'static [Token]]);
+impl CanonicalToken {
+    /// Returns the name of this token's variant.
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "Bool",
+            Self::I8(_) => "I8",
+            Self::I16(_) => "I16",
+            Self::I32(_) => "I32",
+            Self::I64(_) => "I64",
+            Self::I128(_) => "I128",
+            Self::U8(_) => "U8",
+            Self::U16(_) => "U16",
+            Self::U32(_) => "U32",
+            Self::U64(_) => "U64",
+            Self::U128(_) => "U128",
+            Self::F32(_) => "F32",
+            Self::F64(_) => "F64",
+            Self::Char(_) => "Char",
+            Self::Str(_) => "Str",
+            Self::Bytes(_) => "Bytes",
+            Self::None => "None",
+            Self::Some => "Some",
+            Self::Unit => "Unit",
+            Self::UnitStruct { .. } => "UnitStruct",
+            Self::UnitVariant { .. } => "UnitVariant",
+            Self::NewtypeStruct { .. } => "NewtypeStruct",
+            Self::NewtypeVariant { .. } => "NewtypeVariant",
+            Self::Seq { .. } => "Seq",
+            Self::SeqEnd => "SeqEnd",
+            Self::Tuple { .. } => "Tuple",
+            Self::TupleEnd => "TupleEnd",
+            Self::TupleStruct { .. } => "TupleStruct",
+            Self::TupleStructEnd => "TupleStructEnd",
+            Self::TupleVariant { .. } => "TupleVariant",
+            Self::TupleVariantEnd => "TupleVariantEnd",
+            Self::Map { .. } => "Map",
+            Self::MapEnd => "MapEnd",
+            Self::Field(_) => "Field",
+            Self::SkippedField(_) => "SkippedField",
+            Self::Struct { .. } => "Struct",
+            Self::StructEnd => "StructEnd",
+            Self::StructVariant { .. } => "StructVariant",
+            Self::StructVariantEnd => "StructVariantEnd",
+            Self::Error(_) => "Error",
+        }
+    }
+}
 
 impl TryFrom<Token> for CanonicalToken {
     type Error = UnorderedTokens;
 
-    fn try_from(token: Token) -> Result<Self, Self::Error> {
+    fn try_from(token: Token) -> Result<Self, UnorderedTokens> {
         match token {
             Token::Bool(value) => Ok(CanonicalToken::Bool(value)),
             Token::I8(value) => Ok(CanonicalToken::I8(value)),
@@ -970,6 +1406,8 @@ impl TryFrom<Token> for CanonicalToken {
             Token::Char(value) => Ok(CanonicalToken::Char(value)),
             Token::Str(value) => Ok(CanonicalToken::Str(value)),
             Token::Bytes(value) => Ok(CanonicalToken::Bytes(value)),
+            Token::StaticStr(value) => Ok(CanonicalToken::Str(value.into())),
+            Token::StaticBytes(value) => Ok(CanonicalToken::Bytes(value.into())),
             Token::None => Ok(CanonicalToken::None),
             Token::Some => Ok(CanonicalToken::Some),
             Token::Unit => Ok(CanonicalToken::Unit),
@@ -979,9 +1417,11 @@ impl TryFrom<Token> for CanonicalToken {
                 variant_index,
                 variant,
             } => Ok(CanonicalToken::UnitVariant {
-                name,
-                variant_index,
-                variant,
+                id: Box::new(VariantId {
+                    name,
+                    variant_index,
+                    variant,
+                }),
             }),
             Token::NewtypeStruct { name } => Ok(CanonicalToken::NewtypeStruct { name }),
             Token::NewtypeVariant {
@@ -989,9 +1429,11 @@ impl TryFrom<Token> for CanonicalToken {
                 variant_index,
                 variant,
             } => Ok(CanonicalToken::NewtypeVariant {
-                name,
-                variant_index,
-                variant,
+                id: Box::new(VariantId {
+                    name,
+                    variant_index,
+                    variant,
+                }),
             }),
             Token::Seq { len } => Ok(CanonicalToken::Seq { len }),
             Token::SeqEnd => Ok(CanonicalToken::SeqEnd),
@@ -1005,9 +1447,11 @@ impl TryFrom<Token> for CanonicalToken {
                 variant,
                 len,
             } => Ok(CanonicalToken::TupleVariant {
-                name,
-                variant_index,
-                variant,
+                id: Box::new(VariantId {
+                    name,
+                    variant_index,
+                    variant,
+                }),
                 len,
             }),
             Token::TupleVariantEnd => Ok(CanonicalToken::TupleVariantEnd),
@@ -1023,25 +1467,646 @@ impl TryFrom<Token> for CanonicalToken {
                 variant,
                 len,
             } => Ok(CanonicalToken::StructVariant {
-                name,
-                variant_index,
-                variant,
+                id: Box::new(VariantId {
+                    name,
+                    variant_index,
+                    variant,
+                }),
                 len,
             }),
             Token::StructVariantEnd => Ok(CanonicalToken::StructVariantEnd),
-            Token::Unordered(tokens) => Err(UnorderedTokens(tokens)),
+            Token::Error(message) => Ok(CanonicalToken::Error(message)),
+            Token::Unordered(tokens) => Err(UnorderedTokens(
+                tokens.iter().copied().map(UnorderedGroup::Static).collect(),
+            )),
+            Token::UnorderedOwned(tokens) => Err(UnorderedTokens(
+                tokens
+                    .into_iter()
+                    .map(|group| UnorderedGroup::Owned(group.into()))
+                    .collect(),
+            )),
+            Token::Group(tokens) => Err(UnorderedTokens(vec![UnorderedGroup::Static(tokens)])),
+            Token::Labeled { token, .. } => CanonicalToken::try_from(*token),
         }
     }
 }
 
-impl From<CanonicalToken> for Token {
-    fn from(token: CanonicalToken) -> Self {
-        match token {
-            CanonicalToken::Bool(value) => Token::Bool(value),
-            CanonicalToken::I8(value) => Token::I8(value),
-            CanonicalToken::I16(value) => Token::I16(value),
-            CanonicalToken::I32(value) => Token::I32(value),
-            CanonicalToken::I64(value) => Token::I64(value),
+/// Implements `From` for `Token` using the variant that directly contains the given type.
+macro_rules! impl_from_for_token {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for Token {
+                fn from(value: $ty) -> Self {
+                    Token::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_for_token! {
+    bool => Bool,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    i128 => I128,
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    u128 => U128,
+    f32 => F32,
+    f64 => F64,
+    char => Char,
+    String => Str,
+    &str => Str,
+    Vec<u8> => Bytes,
+    &[u8] => Bytes,
+}
+
+/// The error returned when converting a [`serde_test::Token::Enum`] into a [`Token`].
+///
+/// A `serde_test::Token::Enum` has no single-token equivalent. [`from_serde_test()`] converts it
+/// along with the tokens that follow it.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_err;
+/// use serde_assert::Token;
+///
+/// let error = assert_err!(Token::try_from(serde_test::Token::Enum { name: "Enum" }));
+///
+/// assert_eq!(error.name(), "Enum");
+/// ```
+#[cfg(feature = "serde_test")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FromSerdeTestError {
+    name: &'static str,
+}
+
+#[cfg(feature = "serde_test")]
+impl FromSerdeTestError {
+    /// Returns the name of the enum the token belongs to.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+#[cfg(feature = "serde_test")]
+impl fmt::Display for FromSerdeTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`serde_test::Token::Enum {{ name: {:?} }}` has no single-token equivalent",
+            self.name
+        )
+    }
+}
+
+#[cfg(feature = "serde_test")]
+impl serde::ser::StdError for FromSerdeTestError {}
+
+#[cfg(feature = "serde_test")]
+impl TryFrom<serde_test::Token> for Token {
+    type Error = FromSerdeTestError;
+
+    /// Converts a single [`serde_test::Token`] into a `Token`.
+    ///
+    /// `serde_test` tokens do not include variant indices, so variant tokens are converted using a
+    /// `variant_index` of `0`. Field names within structs are represented by `serde_test` as
+    /// string tokens, which this conversion cannot distinguish from other strings; use
+    /// [`from_serde_test()`] to convert entire sequences of tokens, including struct fields.
+    ///
+    /// # Errors
+    /// Returns an error if the token is a [`serde_test::Token::Enum`], which has no single-token
+    /// equivalent. [`from_serde_test()`] converts it along with the tokens that follow it.
+    fn try_from(token: serde_test::Token) -> Result<Self, FromSerdeTestError> {
+        Ok(match token {
+            serde_test::Token::Bool(value) => Token::Bool(value),
+            serde_test::Token::I8(value) => Token::I8(value),
+            serde_test::Token::I16(value) => Token::I16(value),
+            serde_test::Token::I32(value) => Token::I32(value),
+            serde_test::Token::I64(value) => Token::I64(value),
+            serde_test::Token::U8(value) => Token::U8(value),
+            serde_test::Token::U16(value) => Token::U16(value),
+            serde_test::Token::U32(value) => Token::U32(value),
+            serde_test::Token::U64(value) => Token::U64(value),
+            serde_test::Token::F32(value) => Token::F32(value),
+            serde_test::Token::F64(value) => Token::F64(value),
+            serde_test::Token::Char(value) => Token::Char(value),
+            serde_test::Token::Str(value)
+            | serde_test::Token::BorrowedStr(value)
+            | serde_test::Token::String(value) => Token::StaticStr(value),
+            serde_test::Token::Bytes(value)
+            | serde_test::Token::BorrowedBytes(value)
+            | serde_test::Token::ByteBuf(value) => Token::StaticBytes(value),
+            serde_test::Token::None => Token::None,
+            serde_test::Token::Some => Token::Some,
+            serde_test::Token::Unit => Token::Unit,
+            serde_test::Token::UnitStruct { name } => Token::UnitStruct { name },
+            serde_test::Token::UnitVariant { name, variant } => Token::UnitVariant {
+                name,
+                variant_index: 0,
+                variant,
+            },
+            serde_test::Token::NewtypeStruct { name } => Token::NewtypeStruct { name },
+            serde_test::Token::NewtypeVariant { name, variant } => Token::NewtypeVariant {
+                name,
+                variant_index: 0,
+                variant,
+            },
+            serde_test::Token::Seq { len } => Token::Seq { len },
+            serde_test::Token::SeqEnd => Token::SeqEnd,
+            serde_test::Token::Tuple { len } => Token::Tuple { len },
+            serde_test::Token::TupleEnd => Token::TupleEnd,
+            serde_test::Token::TupleStruct { name, len } => Token::TupleStruct { name, len },
+            serde_test::Token::TupleStructEnd => Token::TupleStructEnd,
+            serde_test::Token::TupleVariant { name, variant, len } => Token::TupleVariant {
+                name,
+                variant_index: 0,
+                variant,
+                len,
+            },
+            serde_test::Token::TupleVariantEnd => Token::TupleVariantEnd,
+            serde_test::Token::Map { len } => Token::Map { len },
+            serde_test::Token::MapEnd => Token::MapEnd,
+            serde_test::Token::Struct { name, len } => Token::Struct { name, len },
+            serde_test::Token::StructEnd => Token::StructEnd,
+            serde_test::Token::StructVariant { name, variant, len } => Token::StructVariant {
+                name,
+                variant_index: 0,
+                variant,
+                len,
+            },
+            serde_test::Token::StructVariantEnd => Token::StructVariantEnd,
+            serde_test::Token::Enum { name } => return Err(FromSerdeTestError { name }),
+        })
+    }
+}
+
+/// Converts a [`serde_test::Token`] other than [`serde_test::Token::Enum`] into a `Token`.
+#[cfg(feature = "serde_test")]
+fn convert_serde_test(token: serde_test::Token) -> Token {
+    Token::try_from(token).unwrap_or_else(|error| unreachable!("{error}"))
+}
+
+/// A compound value being converted by [`from_serde_test()`].
+#[cfg(feature = "serde_test")]
+struct SerdeTestFrame {
+    /// The end token emitted when this value ends.
+    end: Token,
+    /// Whether the keys of this value are field names.
+    fields: bool,
+    /// Whether the next token is a key.
+    at_key: bool,
+}
+
+/// Converts a sequence of [`serde_test::Token`]s into `Token`s.
+///
+/// This allows fixtures written for `serde_test` to be reused without rewriting them. Unlike
+/// converting each token individually, this converts the string keys of structs and struct
+/// variants into [`Field`] tokens, and converts each [`serde_test::Token::Enum`], along with the
+/// variant name and value following it, into the equivalent variant tokens.
+///
+/// `serde_test` tokens do not include variant indices, so variant tokens are converted using a
+/// `variant_index` of `0`. This has no effect on deserialization of variants identified by name,
+/// but comparisons against serialized variants other than the first will fail.
+///
+/// # Panics
+/// Panics if a [`serde_test::Token::Enum`] is not followed by a variant name.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde::Serialize;
+/// use serde_assert::{
+///     token,
+///     Serializer,
+/// };
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     bar: bool,
+/// }
+///
+/// let serializer = Serializer::new();
+///
+/// assert_ok_eq!(
+///     Foo { bar: true }.serialize(&serializer),
+///     token::from_serde_test(&[
+///         serde_test::Token::Struct {
+///             name: "Foo",
+///             len: 1,
+///         },
+///         serde_test::Token::Str("bar"),
+///         serde_test::Token::Bool(true),
+///         serde_test::Token::StructEnd,
+///     ])
+/// );
+/// ```
+///
+/// [`Field`]: Token::Field
+#[cfg(feature = "serde_test")]
+#[must_use]
+#[allow(clippy::too_many_lines)] // Every `serde_test` token must be handled.
+pub fn from_serde_test(tokens: &[serde_test::Token]) -> Vec<Token> {
+    let mut converted = Vec::with_capacity(tokens.len());
+    let mut stack = Vec::<SerdeTestFrame>::new();
+    let mut iter = tokens.iter();
+    while let Some(&token) = iter.next() {
+        if let Some(frame) = stack.last_mut() {
+            if frame.at_key {
+                match token {
+                    serde_test::Token::Str(key)
+                    | serde_test::Token::BorrowedStr(key)
+                    | serde_test::Token::String(key) => {
+                        frame.at_key = false;
+                        converted.push(Token::Field(key));
+                        continue;
+                    }
+                    serde_test::Token::MapEnd
+                    | serde_test::Token::StructEnd
+                    | serde_test::Token::StructVariantEnd => {}
+                    // Any other key is left as-is, allowing the mismatch to be reported when
+                    // compared.
+                    _ => frame.at_key = false,
+                }
+            }
+        }
+        let completes_value = match token {
+            serde_test::Token::Some
+            | serde_test::Token::NewtypeStruct { .. }
+            | serde_test::Token::NewtypeVariant { .. } => {
+                converted.push(convert_serde_test(token));
+                false
+            }
+            serde_test::Token::Seq { .. }
+            | serde_test::Token::Tuple { .. }
+            | serde_test::Token::TupleStruct { .. }
+            | serde_test::Token::TupleVariant { .. }
+            | serde_test::Token::Map { .. } => {
+                stack.push(SerdeTestFrame {
+                    end: match token {
+                        serde_test::Token::Seq { .. } => Token::SeqEnd,
+                        serde_test::Token::Tuple { .. } => Token::TupleEnd,
+                        serde_test::Token::TupleStruct { .. } => Token::TupleStructEnd,
+                        serde_test::Token::TupleVariant { .. } => Token::TupleVariantEnd,
+                        _ => Token::MapEnd,
+                    },
+                    fields: false,
+                    at_key: false,
+                });
+                converted.push(convert_serde_test(token));
+                false
+            }
+            serde_test::Token::Struct { .. } | serde_test::Token::StructVariant { .. } => {
+                stack.push(SerdeTestFrame {
+                    end: if matches!(token, serde_test::Token::Struct { .. }) {
+                        Token::StructEnd
+                    } else {
+                        Token::StructVariantEnd
+                    },
+                    fields: true,
+                    at_key: true,
+                });
+                converted.push(convert_serde_test(token));
+                false
+            }
+            serde_test::Token::SeqEnd
+            | serde_test::Token::TupleEnd
+            | serde_test::Token::TupleStructEnd
+            | serde_test::Token::TupleVariantEnd
+            | serde_test::Token::MapEnd
+            | serde_test::Token::StructEnd
+            | serde_test::Token::StructVariantEnd => {
+                converted.push(
+                    stack
+                        .pop()
+                        .map_or_else(|| convert_serde_test(token), |frame| frame.end),
+                );
+                true
+            }
+            serde_test::Token::Enum { name } => {
+                let variant = match iter.next() {
+                    Some(
+                        &(serde_test::Token::Str(variant)
+                        | serde_test::Token::BorrowedStr(variant)
+                        | serde_test::Token::String(variant)),
+                    ) => variant,
+                    token => panic!(
+                        "expected variant name following `serde_test::Token::Enum {{ name: \
+                         {name:?} }}`, found {token:?}"
+                    ),
+                };
+                match iter.as_slice().first() {
+                    Some(serde_test::Token::Unit) => {
+                        iter.next();
+                        converted.push(Token::UnitVariant {
+                            name,
+                            variant_index: 0,
+                            variant,
+                        });
+                        true
+                    }
+                    Some(&serde_test::Token::Seq { len }) => {
+                        converted.push(Token::TupleVariant {
+                            name,
+                            variant_index: 0,
+                            variant,
+                            len: len.unwrap_or(0),
+                        });
+                        iter.next();
+                        stack.push(SerdeTestFrame {
+                            end: Token::TupleVariantEnd,
+                            fields: false,
+                            at_key: false,
+                        });
+                        false
+                    }
+                    Some(&serde_test::Token::Map { len }) => {
+                        converted.push(Token::StructVariant {
+                            name,
+                            variant_index: 0,
+                            variant,
+                            len: len.unwrap_or(0),
+                        });
+                        iter.next();
+                        stack.push(SerdeTestFrame {
+                            end: Token::StructVariantEnd,
+                            fields: true,
+                            at_key: true,
+                        });
+                        false
+                    }
+                    _ => {
+                        converted.push(Token::NewtypeVariant {
+                            name,
+                            variant_index: 0,
+                            variant,
+                        });
+                        false
+                    }
+                }
+            }
+            _ => {
+                converted.push(convert_serde_test(token));
+                true
+            }
+        };
+        if completes_value {
+            if let Some(frame) = stack.last_mut() {
+                frame.at_key = frame.fields;
+            }
+        }
+    }
+    converted
+}
+
+/// Constructs a [`Vec`] of [`Token`]s, automatically emitting end tokens for nested values.
+///
+/// Each item is either a value convertible into a [`Token`] using [`From`], such as `42u32`,
+/// `"foo"`, or `Token::Unit`, or one of the following forms:
+///
+/// - `Some(item)`: [`Some`], followed by the item.
+/// - `Seq [items]`: [`Seq`], the items, and [`SeqEnd`].
+/// - `Tuple (items)`: [`Tuple`], the items, and [`TupleEnd`].
+/// - `Map { key => value, ... }`: [`Map`], the keys and values, and [`MapEnd`].
+/// - `NewtypeStruct(name) (item)`: [`NewtypeStruct`], followed by the item.
+/// - `TupleStruct(name) (items)`: [`TupleStruct`], the items, and [`TupleStructEnd`].
+/// - `Struct(name) { "field" => value, ... }`: [`Struct`], the fields and their values, and
+///   [`StructEnd`].
+/// - `NewtypeVariant(name, variant_index, variant) (item)`: [`NewtypeVariant`], followed by the
+///   item.
+/// - `TupleVariant(name, variant_index, variant) (items)`: [`TupleVariant`], the items, and
+///   [`TupleVariantEnd`].
+/// - `StructVariant(name, variant_index, variant) { "field" => value, ... }`: [`StructVariant`],
+///   the fields and their values, and [`StructVariantEnd`].
+///
+/// Items within these forms may themselves be any of these forms. The lengths of sequences, maps,
+/// tuples, and structs are the number of items they contain.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde::Serialize;
+/// use serde_assert::{
+///     tokens,
+///     Serializer,
+/// };
+/// use serde_derive::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     a: u32,
+///     b: (bool, Option<&'static str>),
+/// }
+///
+/// let serializer = Serializer::new();
+///
+/// assert_ok_eq!(
+///     Foo {
+///         a: 1,
+///         b: (true, Some("bar")),
+///     }
+///     .serialize(&serializer),
+///     tokens![Struct("Foo") {
+///         "a" => 1u32,
+///         "b" => Tuple (true, Some("bar")),
+///     }]
+/// );
+/// ```
+///
+/// [`Map`]: Token::Map
+/// [`MapEnd`]: Token::MapEnd
+/// [`NewtypeStruct`]: Token::NewtypeStruct
+/// [`NewtypeVariant`]: Token::NewtypeVariant
+/// [`Seq`]: Token::Seq
+/// [`SeqEnd`]: Token::SeqEnd
+/// [`Some`]: Token::Some
+/// [`Struct`]: Token::Struct
+/// [`StructEnd`]: Token::StructEnd
+/// [`StructVariant`]: Token::StructVariant
+/// [`StructVariantEnd`]: Token::StructVariantEnd
+/// [`Tuple`]: Token::Tuple
+/// [`TupleEnd`]: Token::TupleEnd
+/// [`TupleStruct`]: Token::TupleStruct
+/// [`TupleStructEnd`]: Token::TupleStructEnd
+/// [`TupleVariant`]: Token::TupleVariant
+/// [`TupleVariantEnd`]: Token::TupleVariantEnd
+/// [`Vec`]: alloc::vec::Vec
+#[macro_export]
+macro_rules! tokens {
+    // Comma-separated items, counted in `$len`.
+    (@items $tokens:ident, $len:ident;) => {};
+    (@items $tokens:ident, $len:ident; $($rest:tt)+) => {
+        $len += 1;
+        $crate::tokens!(@item $tokens [@items_next $tokens, $len;] $($rest)+);
+    };
+    (@items_next $tokens:ident, $len:ident;) => {};
+    (@items_next $tokens:ident, $len:ident; , $($rest:tt)*) => {
+        $crate::tokens!(@items $tokens, $len; $($rest)*);
+    };
+
+    // Comma-separated `"field" => value` pairs, counted in `$len`.
+    (@fields $tokens:ident, $len:ident;) => {};
+    (@fields $tokens:ident, $len:ident; $field:literal => $($rest:tt)+) => {
+        $len += 1;
+        $tokens.push($crate::Token::Field($field));
+        $crate::tokens!(@item $tokens [@fields_next $tokens, $len;] $($rest)+);
+    };
+    (@fields_next $tokens:ident, $len:ident;) => {};
+    (@fields_next $tokens:ident, $len:ident; , $($rest:tt)*) => {
+        $crate::tokens!(@fields $tokens, $len; $($rest)*);
+    };
+
+    // Comma-separated `key => value` pairs, counted in `$len`.
+    (@entries $tokens:ident, $len:ident;) => {};
+    (@entries $tokens:ident, $len:ident; $($rest:tt)+) => {
+        $len += 1;
+        $crate::tokens!(@item $tokens [@entry_value $tokens, $len;] $($rest)+);
+    };
+    (@entry_value $tokens:ident, $len:ident; => $($rest:tt)+) => {
+        $crate::tokens!(@item $tokens [@entries_next $tokens, $len;] $($rest)+);
+    };
+    (@entries_next $tokens:ident, $len:ident;) => {};
+    (@entries_next $tokens:ident, $len:ident; , $($rest:tt)*) => {
+        $crate::tokens!(@entries $tokens, $len; $($rest)*);
+    };
+
+    // A single item, followed by a continuation invoked with the remaining input.
+    (@item $tokens:ident [$($next:tt)*] Some ($($inner:tt)*) $($rest:tt)*) => {
+        $tokens.push($crate::Token::Some);
+        let (_, inner) = $crate::tokens!(@nested items; $($inner)*);
+        $tokens.extend(inner);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (@item $tokens:ident [$($next:tt)*] Seq [$($inner:tt)*] $($rest:tt)*) => {
+        let (len, inner) = $crate::tokens!(@nested items; $($inner)*);
+        $tokens.push($crate::Token::Seq {
+            len: ::core::option::Option::Some(len),
+        });
+        $tokens.extend(inner);
+        $tokens.push($crate::Token::SeqEnd);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (@item $tokens:ident [$($next:tt)*] Tuple ($($inner:tt)*) $($rest:tt)*) => {
+        let (len, inner) = $crate::tokens!(@nested items; $($inner)*);
+        $tokens.push($crate::Token::Tuple { len });
+        $tokens.extend(inner);
+        $tokens.push($crate::Token::TupleEnd);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (@item $tokens:ident [$($next:tt)*] Map {$($inner:tt)*} $($rest:tt)*) => {
+        let (len, inner) = $crate::tokens!(@nested entries; $($inner)*);
+        $tokens.push($crate::Token::Map {
+            len: ::core::option::Option::Some(len),
+        });
+        $tokens.extend(inner);
+        $tokens.push($crate::Token::MapEnd);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (@item $tokens:ident [$($next:tt)*] NewtypeStruct($name:expr) ($($inner:tt)*) $($rest:tt)*) => {
+        $tokens.push($crate::Token::NewtypeStruct { name: $name });
+        let (_, inner) = $crate::tokens!(@nested items; $($inner)*);
+        $tokens.extend(inner);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (@item $tokens:ident [$($next:tt)*] TupleStruct($name:expr) ($($inner:tt)*) $($rest:tt)*) => {
+        let (len, inner) = $crate::tokens!(@nested items; $($inner)*);
+        $tokens.push($crate::Token::TupleStruct { name: $name, len });
+        $tokens.extend(inner);
+        $tokens.push($crate::Token::TupleStructEnd);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (@item $tokens:ident [$($next:tt)*] Struct($name:expr) {$($inner:tt)*} $($rest:tt)*) => {
+        let (len, inner) = $crate::tokens!(@nested fields; $($inner)*);
+        $tokens.push($crate::Token::Struct { name: $name, len });
+        $tokens.extend(inner);
+        $tokens.push($crate::Token::StructEnd);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (
+        @item $tokens:ident [$($next:tt)*]
+        NewtypeVariant($name:expr, $variant_index:expr, $variant:expr $(,)?) ($($inner:tt)*)
+        $($rest:tt)*
+    ) => {
+        $tokens.push($crate::Token::NewtypeVariant {
+            name: $name,
+            variant_index: $variant_index,
+            variant: $variant,
+        });
+        let (_, inner) = $crate::tokens!(@nested items; $($inner)*);
+        $tokens.extend(inner);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (
+        @item $tokens:ident [$($next:tt)*]
+        TupleVariant($name:expr, $variant_index:expr, $variant:expr $(,)?) ($($inner:tt)*)
+        $($rest:tt)*
+    ) => {
+        let (len, inner) = $crate::tokens!(@nested items; $($inner)*);
+        $tokens.push($crate::Token::TupleVariant {
+            name: $name,
+            variant_index: $variant_index,
+            variant: $variant,
+            len,
+        });
+        $tokens.extend(inner);
+        $tokens.push($crate::Token::TupleVariantEnd);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (
+        @item $tokens:ident [$($next:tt)*]
+        StructVariant($name:expr, $variant_index:expr, $variant:expr $(,)?) {$($inner:tt)*}
+        $($rest:tt)*
+    ) => {
+        let (len, inner) = $crate::tokens!(@nested fields; $($inner)*);
+        $tokens.push($crate::Token::StructVariant {
+            name: $name,
+            variant_index: $variant_index,
+            variant: $variant,
+            len,
+        });
+        $tokens.extend(inner);
+        $tokens.push($crate::Token::StructVariantEnd);
+        $crate::tokens!($($next)* $($rest)*);
+    };
+    (@item $tokens:ident [$($next:tt)*] $value:expr => $($rest:tt)*) => {
+        $tokens.push($crate::Token::from($value));
+        $crate::tokens!($($next)* => $($rest)*);
+    };
+    (@item $tokens:ident [$($next:tt)*] $value:expr $(, $($rest:tt)*)?) => {
+        $tokens.push($crate::Token::from($value));
+        $crate::tokens!($($next)* $(, $($rest)*)?);
+    };
+
+    // Collects nested items into a new `Vec`, returning their count along with the `Vec`.
+    (@nested $kind:ident; $($inner:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut inner = $crate::__private::Vec::<$crate::Token>::new();
+        #[allow(unused_mut)]
+        let mut len = 0_usize;
+        $crate::tokens!(@$kind inner, len; $($inner)*);
+        (len, inner)
+    }};
+
+    ($($items:tt)*) => {{
+        let (_, tokens) = $crate::tokens!(@nested items; $($items)*);
+        tokens
+    }};
+}
+
+impl From<CanonicalToken> for Token {
+    fn from(token: CanonicalToken) -> Self {
+        match token {
+            CanonicalToken::Bool(value) => Token::Bool(value),
+            CanonicalToken::I8(value) => Token::I8(value),
+            CanonicalToken::I16(value) => Token::I16(value),
+            CanonicalToken::I32(value) => Token::I32(value),
+            CanonicalToken::I64(value) => Token::I64(value),
             CanonicalToken::I128(value) => Token::I128(value),
             CanonicalToken::U8(value) => Token::U8(value),
             CanonicalToken::U16(value) => Token::U16(value),
@@ -1057,24 +2122,16 @@ impl From<CanonicalToken> for Token {
             CanonicalToken::Some => Token::Some,
             CanonicalToken::Unit => Token::Unit,
             CanonicalToken::UnitStruct { name } => Token::UnitStruct { name },
-            CanonicalToken::UnitVariant {
-                name,
-                variant_index,
-                variant,
-            } => Token::UnitVariant {
-                name,
-                variant_index,
-                variant,
+            CanonicalToken::UnitVariant { id } => Token::UnitVariant {
+                name: id.name,
+                variant_index: id.variant_index,
+                variant: id.variant,
             },
             CanonicalToken::NewtypeStruct { name } => Token::NewtypeStruct { name },
-            CanonicalToken::NewtypeVariant {
-                name,
-                variant_index,
-                variant,
-            } => Token::NewtypeVariant {
-                name,
-                variant_index,
-                variant,
+            CanonicalToken::NewtypeVariant { id } => Token::NewtypeVariant {
+                name: id.name,
+                variant_index: id.variant_index,
+                variant: id.variant,
             },
             CanonicalToken::Seq { len } => Token::Seq { len },
             CanonicalToken::SeqEnd => Token::SeqEnd,
@@ -1082,15 +2139,10 @@ impl From<CanonicalToken> for Token {
             CanonicalToken::TupleEnd => Token::TupleEnd,
             CanonicalToken::TupleStruct { name, len } => Token::TupleStruct { name, len },
             CanonicalToken::TupleStructEnd => Token::TupleStructEnd,
-            CanonicalToken::TupleVariant {
-                name,
-                variant_index,
-                variant,
-                len,
-            } => Token::TupleVariant {
-                name,
-                variant_index,
-                variant,
+            CanonicalToken::TupleVariant { id, len } => Token::TupleVariant {
+                name: id.name,
+                variant_index: id.variant_index,
+                variant: id.variant,
                 len,
             },
             CanonicalToken::TupleVariantEnd => Token::TupleVariantEnd,
@@ -1100,18 +2152,14 @@ impl From<CanonicalToken> for Token {
             CanonicalToken::SkippedField(value) => Token::SkippedField(value),
             CanonicalToken::Struct { name, len } => Token::Struct { name, len },
             CanonicalToken::StructEnd => Token::StructEnd,
-            CanonicalToken::StructVariant {
-                name,
-                variant_index,
-                variant,
-                len,
-            } => Token::StructVariant {
-                name,
-                variant_index,
-                variant,
+            CanonicalToken::StructVariant { id, len } => Token::StructVariant {
+                name: id.name,
+                variant_index: id.variant_index,
+                variant: id.variant,
                 len,
             },
             CanonicalToken::StructVariantEnd => Token::StructVariantEnd,
+            CanonicalToken::Error(message) => Token::Error(message),
         }
     }
 }
@@ -1155,6 +2203,7 @@ impl<'a> From<&'a mut CanonicalToken> for Unexpected<'a> {
             CanonicalToken::StructEnd => Unexpected::Other("StructEnd"),
             CanonicalToken::StructVariant { .. } => Unexpected::StructVariant,
             CanonicalToken::StructVariantEnd => Unexpected::Other("StructVariantEnd"),
+            CanonicalToken::Error(..) => Unexpected::Other("Error"),
         }
     }
 }
@@ -1164,6 +2213,15 @@ impl<'a> From<&'a mut CanonicalToken> for Unexpected<'a> {
 /// `Tokens` can be compared with any other sequence of `Token`s to assert that the serialized
 /// values are as expected.
 ///
+/// The compared sequence must contain `Token`s themselves, since it is only borrowed for the
+/// comparison. Values convertible into `Token`s must be converted first, for example using
+/// `[1u8, 2u8].map(Token::from)`.
+///
+/// `Tokens` only contain the tokens themselves. Information about how they were serialized, such
+/// as which enum variants were emitted and with which representation, is returned alongside them
+/// by [`Serializer::record()`]. It is not stored on the `Tokens`, because it could not be kept
+/// accurate once the tokens are rewritten by passes such as [`Tokens::tag_enums()`].
+///
 /// # Examples
 ///
 /// `Tokens` are output from a [`Serializer`] and can be compared against a sequence of `Token`s.
@@ -1210,1309 +2268,5537 @@ impl<'a> From<&'a mut CanonicalToken> for Unexpected<'a> {
 ///
 /// [`Deserializer`]: crate::Deserializer
 /// [`Serializer`]: crate::Serializer
+/// [`Serializer::record()`]: crate::Serializer::record()
 #[derive(Clone, Debug)]
 pub struct Tokens(pub(crate) Vec<CanonicalToken>);
 
-#[derive(Clone, Debug)]
-struct Context {
-    current: slice::Iter<'static, Token>,
-    remaining: Vec<&'static [Token]>,
-    #[allow(clippy::struct_field_names)] // Acceptable, as the name refers to the contained type.
-    nested_context: Option<Box<Context>>,
-}
-
-impl Context {
-    /// Creates a new context from the given parts.
-    fn new(current: slice::Iter<'static, Token>, remaining: Vec<&'static [Token]>) -> Self {
-        Self {
-            current,
-            remaining,
-            nested_context: None,
-        }
-    }
-
-    /// Nests this context within the contexts in the given split, returning those contexts.
-    fn nest(self, mut split: Split) -> Vec<Self> {
-        for context in &mut split.contexts {
-            context.nested_context = Some(Box::new(self.clone()));
-        }
-        split.contexts
-    }
-}
-
-impl Iterator for Context {
-    type Item = &'static Token;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.current.next()
-    }
-}
-
-#[derive(Debug)]
-struct Split {
-    contexts: Vec<Context>,
-}
-
-impl Split {
-    /// Returns whether a path exists through these split tokens using the given iterator.
+impl Tokens {
+    /// Records the `Tokens` output by a serialization function.
+    ///
+    /// The provided function is called with a [`Serializer`] using the default configuration. This
+    /// allows obtaining `Tokens` generically from any serialization entry point, including those
+    /// that do not go through the [`Serialize`] trait directly.
+    ///
+    /// To record using a configured `Serializer`, call the function with that `Serializer`
+    /// directly instead.
+    ///
+    /// # Errors
+    /// Returns any [`ser::Error`] returned by the provided function.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Serializer as _;
+    /// use serde_assert::{
+    ///     token::Tokens,
+    ///     Token,
+    /// };
+    ///
+    /// fn serialize_pair<S>(a: u8, b: u8, serializer: S) -> Result<S::Ok, S::Error>
+    /// where
+    ///     S: serde::Serializer,
+    /// {
+    ///     serializer.collect_seq([a, b])
+    /// }
     ///
-    /// This will consume exactly the correct number of tokens from the given iterator.
-    fn search<'a, I>(mut self, mut tokens: I) -> bool
+    /// assert_ok_eq!(
+    ///     Tokens::record(|serializer| serialize_pair(1, 2, serializer)),
+    ///     [
+    ///         Token::Seq { len: Some(2) },
+    ///         Token::U8(1),
+    ///         Token::U8(2),
+    ///         Token::SeqEnd,
+    ///     ]
+    /// );
+    /// ```
+    pub fn record<F>(record: F) -> Result<Self, ser::Error>
     where
-        I: Iterator<Item = &'a CanonicalToken>,
+        F: FnOnce(&Serializer) -> Result<Self, ser::Error>,
     {
-        while let Some(canonical_tokens) = self.next() {
-            if canonical_tokens.is_empty() {
-                // All contexts have ended, and therefore no path could be found.
-                return false;
-            }
-            if let Some(token) = tokens.next() {
-                self.contexts = self
-                    .contexts
-                    .into_iter()
-                    .zip(canonical_tokens)
-                    .filter_map(|(context, canonical_token)| {
-                        if *token == canonical_token {
-                            Some(context)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-            } else {
-                // Both sides had a different number of canonical tokens.
-                return false;
-            }
-        }
-
-        // We have found the end of the split tokens without failing to find equality in tokens.
-        // This means that at least one path was found, and therefore the search succeeded.
-        true
+        record(&Serializer::builder().build())
     }
-}
 
-impl Iterator for Split {
-    /// Returns a token from each remaining context, removing contexts in-place if they split.
+    /// Converts a JSON document into the equivalent `Tokens`.
     ///
-    /// If this returns an empty `Vec`, that means there were no contexts remaining when it was
-    /// called. If this returns `None`, that means that all remaining contexts have hit the end of
-    /// their tokens.
-    type Item = Vec<CanonicalToken>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.contexts.is_empty() {
-            return Some(Vec::new());
+    /// This allows authoring deserializer input as readable JSON, rather than as long sequences of
+    /// [`Token`]s. The tokens are those produced by serializing the [`Value`] itself:
+    ///
+    /// - `null` becomes [`Token::Unit`].
+    /// - Booleans become [`Token::Bool`].
+    /// - Numbers become [`Token::U64`] if they are non-negative integers, [`Token::I64`] if they
+    ///   are negative integers, and [`Token::F64`] otherwise.
+    /// - Strings become [`Token::Str`].
+    /// - Arrays become a [`Token::Seq`] containing each element, followed by [`Token::SeqEnd`].
+    /// - Objects become a [`Token::Map`] containing each key and value, followed by
+    ///   [`Token::MapEnd`].
+    ///
+    /// As JSON does not distinguish between integer widths, nor between maps and structs, these
+    /// tokens are best suited to types deserialized from maps and 64-bit numbers, or to
+    /// self-describing deserialization.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     token::Tokens,
+    ///     Deserializer,
+    /// };
+    /// use serde_json::json;
+    /// use std::collections::HashMap;
+    ///
+    /// let tokens = Tokens::from_json_value(&json!({"foo": [1, 2, 3]}));
+    /// let mut deserializer = Deserializer::builder(tokens).build();
+    ///
+    /// assert_ok_eq!(
+    ///     HashMap::<String, Vec<u64>>::deserialize(&mut deserializer),
+    ///     HashMap::from([("foo".to_owned(), vec![1, 2, 3])])
+    /// );
+    /// ```
+    ///
+    /// [`Value`]: serde_json::Value
+    #[cfg(feature = "serde_json")]
+    #[must_use]
+    pub fn from_json_value(value: &serde_json::Value) -> Self {
+        enum Item<'a> {
+            Value(&'a serde_json::Value),
+            Key(&'a str),
+            End(CanonicalToken),
         }
 
-        let mut result = Vec::with_capacity(self.contexts.len());
-
-        let mut index = 0;
-        while index < self.contexts.len() {
-            match self.contexts[index]
-                .next()
-                .cloned()
-                .map(CanonicalToken::try_from)
-            {
-                Some(Ok(canonical_token)) => {
-                    result.push(canonical_token);
-                    index += 1;
+        let mut tokens = Vec::new();
+        // Nested values are converted using an explicit stack, so deeply nested documents cannot
+        // overflow the call stack.
+        let mut stack = vec![Item::Value(value)];
+        while let Some(item) = stack.pop() {
+            match item {
+                Item::Value(serde_json::Value::Null) => tokens.push(CanonicalToken::Unit),
+                Item::Value(serde_json::Value::Bool(value)) => {
+                    tokens.push(CanonicalToken::Bool(*value));
                 }
-                Some(Err(unordered_tokens)) => {
-                    // Split and nest.
-                    let context = self.contexts.swap_remove(index);
-                    if let Ok(split) = unordered_tokens.try_into() {
-                        self.contexts.extend(context.nest(split));
-                    }
+                Item::Value(serde_json::Value::Number(number)) => {
+                    tokens.push(if let Some(value) = number.as_u64() {
+                        CanonicalToken::U64(value)
+                    } else if let Some(value) = number.as_i64() {
+                        CanonicalToken::I64(value)
+                    } else {
+                        // Numbers that are not integers are always representable as `f64`.
+                        CanonicalToken::F64(number.as_f64().unwrap_or(f64::NAN))
+                    });
                 }
-                None => {
-                    // Split from remaining.
-                    let context = self.contexts.swap_remove(index);
-                    if let Ok(split) = Split::try_from(context) {
-                        self.contexts.extend(split.contexts);
+                Item::Value(serde_json::Value::String(value)) => {
+                    tokens.push(CanonicalToken::Str(value.clone()));
+                }
+                Item::Value(serde_json::Value::Array(values)) => {
+                    tokens.push(CanonicalToken::Seq {
+                        len: Some(values.len()),
+                    });
+                    stack.push(Item::End(CanonicalToken::SeqEnd));
+                    stack.extend(values.iter().rev().map(Item::Value));
+                }
+                Item::Value(serde_json::Value::Object(entries)) => {
+                    tokens.push(CanonicalToken::Map {
+                        len: Some(entries.len()),
+                    });
+                    stack.push(Item::End(CanonicalToken::MapEnd));
+                    for (key, value) in entries.iter().rev() {
+                        stack.push(Item::Value(value));
+                        stack.push(Item::Key(key));
                     }
                 }
+                Item::Key(key) => tokens.push(CanonicalToken::Str(key.into())),
+                Item::End(token) => tokens.push(token),
             }
         }
-
-        if result.is_empty() {
-            // No tokens returned, which means we are done processing this split.
-            None
-        } else {
-            Some(result)
-        }
-    }
-}
-
-impl<'a> TryFrom<&'a [&'static [Token]]> for Split {
-    type Error = ();
-
-    fn try_from(value: &'a [&'static [Token]]) -> Result<Self, Self::Error> {
-        if value.is_empty() {
-            Err(())
-        } else {
-            Ok(Self {
-                contexts: (0..value.len())
-                    .map(|index| {
-                        Context::new(
-                            value[index].iter(),
-                            value
-                                .iter()
-                                .enumerate()
-                                .filter_map(
-                                    |(i, tokens)| if i == index { None } else { Some(*tokens) },
-                                )
-                                .collect(),
-                        )
-                    })
-                    .collect(),
-            })
-        }
+        Tokens(tokens)
     }
-}
-
-impl TryFrom<Context> for Split {
-    type Error = ();
 
-    fn try_from(value: Context) -> Result<Self, Self::Error> {
-        if let Ok(mut split) = Split::try_from(value.remaining.as_slice()) {
-            for context in &mut split.contexts {
-                context.nested_context.clone_from(&value.nested_context);
-            }
-            Ok(split)
-        } else if let Some(nested_context) = value.nested_context {
-            Ok(Split {
-                contexts: vec![*nested_context],
-            })
-        } else {
-            Err(())
-        }
+    /// Parses a JSON document into the equivalent `Tokens`.
+    ///
+    /// The document is parsed into a [`Value`] and converted using [`from_json_value()`].
+    ///
+    /// # Errors
+    /// Returns a [`serde_json::Error`] if the string is not valid JSON.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::{
+    ///     token::Tokens,
+    ///     Token,
+    /// };
+    ///
+    /// assert_ok_eq!(
+    ///     Tokens::from_json_str("[true, null]"),
+    ///     [
+    ///         Token::Seq { len: Some(2) },
+    ///         Token::Bool(true),
+    ///         Token::Unit,
+    ///         Token::SeqEnd,
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`from_json_value()`]: Tokens::from_json_value()
+    /// [`Value`]: serde_json::Value
+    #[cfg(feature = "serde_json")]
+    pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json).map(|value| Self::from_json_value(&value))
     }
-}
 
-impl TryFrom<UnorderedTokens> for Split {
-    type Error = ();
-
-    fn try_from(value: UnorderedTokens) -> Result<Self, Self::Error> {
-        value.0.try_into()
+    /// Returns an iterator over the [`Token`]s.
+    ///
+    /// The returned [`Iter`] supports peeking at the next `Token` without consuming it, allowing
+    /// custom assertions and access wrappers to be written over a stream of tokens.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::{
+    ///     assert_matches,
+    ///     assert_ok,
+    /// };
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let serializer = Serializer::new();
+    /// let tokens = assert_ok!(Some(true).serialize(&serializer));
+    /// let mut iter = tokens.iter();
+    ///
+    /// assert_matches!(iter.peek(), Some(Token::Some));
+    /// assert_matches!(iter.next(), Some(Token::Some));
+    /// assert_matches!(iter.next(), Some(Token::Bool(true)));
+    /// ```
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            token_iter: self.0.iter(),
+        }
     }
-}
 
-impl<T> PartialEq<T> for Tokens
-where
-    for<'a> &'a T: IntoIterator<Item = &'a Token>,
-{
-    fn eq(&self, other: &T) -> bool {
-        let mut self_iter = self.0.iter();
+    /// Recomputes the length hints of compound tokens from the tokens they actually contain.
+    ///
+    /// The `len` of every [`Seq`], [`Tuple`], [`TupleStruct`], [`TupleVariant`], [`Map`],
+    /// [`Struct`], and [`StructVariant`] token is set to the number of elements, entries, or
+    /// fields that appear before its corresponding end token. `Seq` and `Map` tokens without a
+    /// length hint are left without one. [`SkippedField`]s are not counted.
+    ///
+    /// This is useful for fixing stale lengths in hand-edited tokens, which otherwise only cause
+    /// failures when a `Deserialize` implementation depends on the length.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     token::Tokens,
+    ///     Token,
+    /// };
+    ///
+    /// let mut tokens = [
+    ///     Token::Seq { len: Some(1) },
+    ///     Token::U8(1),
+    ///     Token::U8(2),
+    ///     Token::SeqEnd,
+    /// ]
+    /// .into_iter()
+    /// .collect::<Tokens>();
+    /// tokens.normalize_len_hints();
+    ///
+    /// assert_eq!(
+    ///     tokens,
+    ///     [
+    ///         Token::Seq { len: Some(2) },
+    ///         Token::U8(1),
+    ///         Token::U8(2),
+    ///         Token::SeqEnd,
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`Map`]: Token::Map
+    /// [`Seq`]: Token::Seq
+    /// [`SkippedField`]: Token::SkippedField
+    /// [`Struct`]: Token::Struct
+    /// [`StructVariant`]: Token::StructVariant
+    /// [`Tuple`]: Token::Tuple
+    /// [`TupleStruct`]: Token::TupleStruct
+    /// [`TupleVariant`]: Token::TupleVariant
+    pub fn normalize_len_hints(&mut self) {
+        /// A compound token whose contents are being counted.
+        struct Compound {
+            /// The index of the compound token.
+            index: usize,
+            /// The number of values counted so far.
+            count: usize,
+        }
 
-        for token in other {
-            if !match CanonicalToken::try_from(token.clone()) {
-                Ok(canonical_token) => {
-                    if let Some(self_token) = self_iter.next() {
-                        canonical_token == *self_token
-                    } else {
-                        // Both sides had a different number of canonical tokens.
-                        false
+        let mut compounds: Vec<Compound> = Vec::new();
+        let mut nesting = Nesting::default();
+        for index in 0..self.0.len() {
+            if matches!(
+                self.0[index],
+                CanonicalToken::Field(_) | CanonicalToken::SkippedField(_)
+            ) {
+                // Fields are counted by the values following them.
+                continue;
+            }
+            let depth = nesting.depth();
+            if nesting.step(&self.0[index]) != Step::Ends {
+                if nesting.depth() > depth {
+                    compounds.push(Compound { index, count: 0 });
+                }
+                continue;
+            }
+            if nesting.depth() < depth {
+                if let Some(compound) = compounds.pop() {
+                    match &mut self.0[compound.index] {
+                        CanonicalToken::Map { len: Some(len) } => *len = compound.count / 2,
+                        CanonicalToken::Seq { len: Some(len) }
+                        | CanonicalToken::Tuple { len }
+                        | CanonicalToken::TupleStruct { len, .. }
+                        | CanonicalToken::TupleVariant { len, .. }
+                        | CanonicalToken::Struct { len, .. }
+                        | CanonicalToken::StructVariant { len, .. } => *len = compound.count,
+                        _ => {}
                     }
                 }
-                Err(unordered_tokens) => Split::try_from(unordered_tokens)
-                    .map(|split| split.search(&mut self_iter))
-                    .unwrap_or(true),
-            } {
-                return false;
+            }
+            if let Some(compound) = compounds.last_mut() {
+                compound.count += 1;
             }
         }
+    }
 
-        if self_iter.next().is_some() {
-            // Both sides had a different number of canonical tokens.
-            return false;
+    /// Splits a stream of several complete top-level values into the `Tokens` of each value.
+    ///
+    /// Values are delimited using the structural balance of compound tokens, such as [`Seq`] and
+    /// [`SeqEnd`]. Tokens that always precede the rest of a value, such as [`Some`], remain
+    /// attached to that value. Any trailing tokens that do not form a complete value are returned
+    /// together as the final element.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     token::Tokens,
+    ///     Token,
+    /// };
+    ///
+    /// let tokens = [
+    ///     Token::Bool(true),
+    ///     Token::Seq { len: Some(1) },
+    ///     Token::U8(1),
+    ///     Token::SeqEnd,
+    ///     Token::Some,
+    ///     Token::Unit,
+    /// ]
+    /// .into_iter()
+    /// .collect::<Tokens>();
+    /// let values = tokens.split_values();
+    ///
+    /// assert_eq!(values.len(), 3);
+    /// assert_eq!(values[0], [Token::Bool(true)]);
+    /// assert_eq!(
+    ///     values[1],
+    ///     [Token::Seq { len: Some(1) }, Token::U8(1), Token::SeqEnd]
+    /// );
+    /// assert_eq!(values[2], [Token::Some, Token::Unit]);
+    /// ```
+    ///
+    /// [`Seq`]: Token::Seq
+    /// [`SeqEnd`]: Token::SeqEnd
+    /// [`Some`]: Token::Some
+    #[must_use]
+    pub fn split_values(self) -> Vec<Tokens> {
+        let mut values = Vec::new();
+        let mut current = Vec::new();
+        let mut nesting = Nesting::default();
+        for token in self.0 {
+            // Fields remain attached to the value following them.
+            let ends_value = !matches!(
+                token,
+                CanonicalToken::Field(_) | CanonicalToken::SkippedField(_)
+            ) && nesting.step(&token) != Step::Continues
+                && nesting.depth() == 0;
+            current.push(token);
+            if ends_value {
+                values.push(Tokens(mem::take(&mut current)));
+            }
+        }
+        if !current.is_empty() {
+            values.push(Tokens(current));
         }
+        values
+    }
 
-        true
+    /// Removes the length hints of all [`Seq`] and [`Map`] tokens.
+    ///
+    /// This emulates values serialized without a known length, such as those serialized from
+    /// iterators.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     token::Tokens,
+    ///     Token,
+    /// };
+    ///
+    /// let mut tokens = [Token::Seq { len: Some(1) }, Token::U8(1), Token::SeqEnd]
+    ///     .into_iter()
+    ///     .collect::<Tokens>();
+    /// tokens.clear_len_hints();
+    ///
+    /// assert_eq!(
+    ///     tokens,
+    ///     [Token::Seq { len: None }, Token::U8(1), Token::SeqEnd]
+    /// );
+    /// ```
+    ///
+    /// [`Map`]: Token::Map
+    /// [`Seq`]: Token::Seq
+    pub fn clear_len_hints(&mut self) {
+        for token in &mut self.0 {
+            if let CanonicalToken::Seq { len } | CanonicalToken::Map { len } = token {
+                *len = None;
+            }
+        }
     }
-}
 
-impl IntoIterator for Tokens {
-    type Item = Token;
-    type IntoIter = IntoIter;
+    /// Replaces the names of all structs, enums, and newtypes with the placeholder `"_"`.
+    ///
+    /// The `name` of each [`UnitStruct`], [`NewtypeStruct`], [`TupleStruct`], and [`Struct`]
+    /// token, along with the enum `name` of each variant token, is erased. Variant names and
+    /// indices are preserved. This allows asserting that two differently-named types serialize to
+    /// structurally identical data, such as when refactoring wrapper types.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    /// # use serde_derive::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Old(u32);
+    ///
+    /// #[derive(Serialize)]
+    /// struct New(u32);
+    ///
+    /// let serializer = Serializer::new();
+    /// let mut old = assert_ok!(Old(42).serialize(&serializer));
+    /// let mut new = assert_ok!(New(42).serialize(&serializer));
+    /// old.strip_names();
+    /// new.strip_names();
+    ///
+    /// assert_eq!(old, new.into_iter().collect::<Vec<Token>>());
+    /// ```
+    ///
+    /// [`NewtypeStruct`]: Token::NewtypeStruct
+    /// [`Struct`]: Token::Struct
+    /// [`TupleStruct`]: Token::TupleStruct
+    /// [`UnitStruct`]: Token::UnitStruct
+    pub fn strip_names(&mut self) {
+        for token in &mut self.0 {
+            match token {
+                CanonicalToken::UnitStruct { name }
+                | CanonicalToken::NewtypeStruct { name }
+                | CanonicalToken::TupleStruct { name, .. }
+                | CanonicalToken::Struct { name, .. } => *name = "_",
+                CanonicalToken::UnitVariant { id }
+                | CanonicalToken::NewtypeVariant { id }
+                | CanonicalToken::TupleVariant { id, .. }
+                | CanonicalToken::StructVariant { id, .. } => id.name = "_",
+                _ => {}
+            }
+        }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        IntoIter {
-            token_iter: self.0.into_iter(),
+    /// Rewrites every enum variant into the given [`EnumTagging`] representation.
+    ///
+    /// Each variant, along with its contents, is replaced by the tokens that serializing the
+    /// equivalent internally tagged (`#[serde(tag = "...")]`) or adjacently tagged
+    /// (`#[serde(tag = "...", content = "...")]`) enum would produce. This allows a single set of
+    /// tokens to be reused when testing every representation of an enum.
+    ///
+    /// Internally tagged enums can only contain unit variants, struct variants, and newtype
+    /// variants containing a struct or map. Other variants are left unchanged by
+    /// [`EnumTagging::Internal`], although any enums they contain are still rewritten.
+    /// [`EnumTagging::External`] leaves the tokens unchanged.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde_assert::{
+    ///     token::{
+    ///         EnumTagging,
+    ///         Tokens,
+    ///     },
+    ///     Token,
+    /// };
+    ///
+    /// let mut tokens: Tokens = [Token::UnitVariant {
+    ///     name: "Message",
+    ///     variant_index: 0,
+    ///     variant: "Ping",
+    /// }]
+    /// .into_iter()
+    /// .collect();
+    /// tokens.tag_enums(EnumTagging::Internal { tag: "type" });
+    ///
+    /// assert_eq!(
+    ///     tokens,
+    ///     [
+    ///         Token::Struct {
+    ///             name: "Message",
+    ///             len: 1,
+    ///         },
+    ///         Token::Field("type"),
+    ///         Token::Str("Ping".to_owned()),
+    ///         Token::StructEnd,
+    ///     ]
+    /// );
+    /// ```
+    pub fn tag_enums(&mut self, tagging: EnumTagging) {
+        let mut tagged = Vec::with_capacity(self.0.len());
+        tag_enums(&self.0, tagging, &mut tagged);
+        self.0 = tagged.into_iter().map(|(_, token)| token).collect();
+    }
+
+    /// Returns [`Stats`] describing these `Tokens`.
+    ///
+    /// The returned statistics are cheap to compute, and can be used as regression guards on the
+    /// size and shape of serialized output.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok;
+    /// use serde::Serialize;
+    /// use serde_assert::Serializer;
+    ///
+    /// let serializer = Serializer::new();
+    /// let tokens = assert_ok!(vec![vec!["foo"], vec!["bar", "baz"]].serialize(&serializer));
+    /// let stats = tokens.stats();
+    ///
+    /// assert_eq!(stats.count("Seq"), 3);
+    /// assert_eq!(stats.count("Str"), 3);
+    /// assert_eq!(stats.max_depth(), 2);
+    /// assert_eq!(stats.str_len(), 9);
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        let mut depth = 0_usize;
+        for token in &self.0 {
+            *stats.counts.entry(token.kind()).or_insert(0) += 1;
+            match token {
+                CanonicalToken::Str(value) => stats.str_len += value.len(),
+                CanonicalToken::Bytes(value) => stats.bytes_len += value.len(),
+                CanonicalToken::Seq { .. }
+                | CanonicalToken::Tuple { .. }
+                | CanonicalToken::TupleStruct { .. }
+                | CanonicalToken::TupleVariant { .. }
+                | CanonicalToken::Map { .. }
+                | CanonicalToken::Struct { .. }
+                | CanonicalToken::StructVariant { .. } => {
+                    depth += 1;
+                    stats.max_depth = stats.max_depth.max(depth);
+                }
+                CanonicalToken::SeqEnd
+                | CanonicalToken::TupleEnd
+                | CanonicalToken::TupleStructEnd
+                | CanonicalToken::TupleVariantEnd
+                | CanonicalToken::MapEnd
+                | CanonicalToken::StructEnd
+                | CanonicalToken::StructVariantEnd => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    /// Collects the given tokens into `Tokens`.
+    ///
+    /// Any [`Unordered`] tokens are flattened, in the order they are provided.
+    ///
+    /// [`Unordered`]: Token::Unordered
+    pub(crate) fn canonicalize<T>(tokens: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: Into<Token>,
+    {
+        let mut canonical_tokens = Vec::new();
+        for token in tokens.into_iter().map(Into::into) {
+            match token.try_into() {
+                Ok(canonical_token) => canonical_tokens.push(canonical_token),
+                Err(UnorderedTokens(groups)) => {
+                    flatten_groups(&mut canonical_tokens, groups, &mut |_| {});
+                }
+            }
+        }
+        Tokens(canonical_tokens)
+    }
+
+    /// Collects `Token`s into `Tokens`, flattening [`Unordered`] groups in a seeded random order.
+    ///
+    /// Each [`Unordered`] group, including nested groups, is shuffled using a pseudo-random number
+    /// generator initialized with `seed`. Runs of consecutive [`Group`]s are shuffled in the same
+    /// way. The same seed always produces the same ordering. This
+    /// allows covering many orderings of large maps and sets, where testing every permutation is
+    /// infeasible, while keeping any failure reproducible.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde::Deserialize;
+    /// use serde_assert::{
+    ///     token::Tokens,
+    ///     Deserializer,
+    ///     Token,
+    /// };
+    /// use std::collections::HashSet;
+    ///
+    /// let expected = (0..10).collect::<HashSet<u32>>();
+    ///
+    /// for seed in 0..100 {
+    ///     let tokens = Tokens::shuffled(
+    ///         [
+    ///             Token::Seq { len: Some(10) },
+    ///             Token::Unordered(&[
+    ///                 &[Token::U32(0)],
+    ///                 &[Token::U32(1)],
+    ///                 &[Token::U32(2)],
+    ///                 &[Token::U32(3)],
+    ///                 &[Token::U32(4)],
+    ///                 &[Token::U32(5)],
+    ///                 &[Token::U32(6)],
+    ///                 &[Token::U32(7)],
+    ///                 &[Token::U32(8)],
+    ///                 &[Token::U32(9)],
+    ///             ]),
+    ///             Token::SeqEnd,
+    ///         ],
+    ///         seed,
+    ///     );
+    ///     let mut deserializer = Deserializer::builder(tokens).build();
+    ///
+    ///     assert_ok_eq!(HashSet::<u32>::deserialize(&mut deserializer), expected);
+    /// }
+    /// ```
+    ///
+    /// [`Group`]: Token::Group
+    /// [`Unordered`]: Token::Unordered
+    pub fn shuffled<T>(tokens: T, seed: u64) -> Self
+    where
+        T: IntoIterator<Item = Token>,
+    {
+        let mut rng = SplitMix64(seed);
+        let mut shuffle = |groups: &mut Vec<UnorderedGroup>| {
+            // Fisher-Yates shuffle.
+            for index in (1..groups.len()).rev() {
+                groups.swap(index, rng.below(index + 1));
+            }
+        };
+
+        let mut canonical_tokens = Vec::new();
+        let mut iter = tokens.into_iter().peekable();
+        while let Some(token) = iter.next() {
+            let is_group = matches!(token, Token::Group(_));
+            match token.try_into() {
+                Ok(canonical_token) => canonical_tokens.push(canonical_token),
+                Err(UnorderedTokens(mut groups)) => {
+                    if is_group {
+                        // Consecutive groups are shuffled together.
+                        while let Some(Token::Group(group)) =
+                            iter.next_if(|token| matches!(token, Token::Group(_)))
+                        {
+                            groups.push(UnorderedGroup::Static(group));
+                        }
+                    }
+                    flatten_groups(&mut canonical_tokens, groups, &mut shuffle);
+                }
+            }
         }
+        Tokens(canonical_tokens)
     }
 }
 
-/// An iterator that moves [`Token`]s out of a [`Tokens`] `struct`.
+/// Flattens unordered groups of tokens into canonical tokens.
 ///
-/// This `struct` is created by the [`into_iter()`] method on `Tokens` (provided by the
-/// [`IntoIterator`] trait).
+/// Each set of groups, including nested sets, is passed to `order` before being flattened,
+/// allowing the groups to be reordered. Nested groups are flattened using an explicit stack rather
+/// than recursion, so deeply nested groups cannot overflow the call stack.
+fn flatten_groups<F>(
+    tokens: &mut Vec<CanonicalToken>,
+    mut groups: Vec<UnorderedGroup>,
+    order: &mut F,
+) where
+    F: FnMut(&mut Vec<UnorderedGroup>),
+{
+    order(&mut groups);
+    let mut stack = groups
+        .into_iter()
+        .rev()
+        .map(GroupIter::new)
+        .collect::<Vec<_>>();
+    while let Some(iter) = stack.last_mut() {
+        let token = if let Some(token) = iter.next() {
+            token
+        } else {
+            stack.pop();
+            continue;
+        };
+        let is_group = matches!(token, Token::Group(_));
+        match CanonicalToken::try_from(token) {
+            Ok(canonical_token) => tokens.push(canonical_token),
+            Err(UnorderedTokens(mut groups)) => {
+                if is_group {
+                    // Consecutive groups are ordered together.
+                    while let Some(Token::Group(group)) = iter.as_slice().first() {
+                        groups.push(UnorderedGroup::Static(group));
+                        iter.next();
+                    }
+                }
+                order(&mut groups);
+                stack.extend(groups.into_iter().rev().map(GroupIter::new));
+            }
+        }
+    }
+}
+
+/// A small, deterministic pseudo-random number generator.
 ///
-/// [`into_iter()`]: IntoIterator::into_iter()
-pub struct IntoIter {
-    token_iter: vec::IntoIter<CanonicalToken>,
+/// This is the `SplitMix64` generator, which is sufficient for choosing orderings in tests.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in the range `0..bound`.
+    #[allow(clippy::cast_possible_truncation)] // The result is less than `bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        ((u128::from(self.next_u64()) * bound as u128) >> 64) as usize
+    }
 }
 
-impl Iterator for IntoIter {
-    type Item = Token;
+/// A bitset of the groups within a [`GroupSet`].
+#[derive(Clone, Debug)]
+struct Bitset(Vec<u64>);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.token_iter.next().map(From::from)
+impl Bitset {
+    fn new(len: usize) -> Self {
+        Self(vec![0; (len + 63) / 64])
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    fn insert(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
     }
 }
 
-/// An iterator over tokens.
-///
-/// This iterator owns the tokens, iterating over references to them.
-pub(crate) struct OwningIter<'a> {
-    /// A pointer to the entire buffer that is owned by this struct.
-    ///
-    /// Immutable references to the `Token`s in this buffer can exist within the lifetime `'a`.
-    buf: NonNull<CanonicalToken>,
-    /// A pointer to the current position in iteration.
-    ptr: *mut CanonicalToken,
-    /// A pointer to the end of the allocated buffer.
-    end: *mut CanonicalToken,
-    /// The capacity of the underlying allocation.
-    ///
-    /// This is only used for deallocating when the struct is dropped.
-    cap: usize,
+/// A single element of a group compiled within a [`GroupSet`].
+#[derive(Debug, PartialEq)]
+enum Element {
+    Token(CanonicalToken),
+    /// Nested groups of tokens that may appear in any order.
+    Set(Shared<GroupSet>),
+}
 
-    /// The lifetime of the underlying `Token`s.
+/// A set of groups of tokens that may appear in any order, compiled for matching.
+///
+/// Compiling converts the tokens of each group into their canonical form, compiles any nested
+/// groups, and determines which groups are identical. This is done once for each set, and the
+/// result is shared between every context searching through it.
+#[derive(Debug)]
+struct GroupSet {
+    groups: Vec<Vec<Element>>,
+    /// The index of the first group identical to each group.
     ///
-    /// `Token`s can be borrowed for up to the lifetime `'a`, allowing for zero-copy
-    /// deserialization.
-    lifetime: PhantomData<&'a ()>,
+    /// Identical groups are interchangeable, so a path only needs to be started from one of them.
+    /// The others remain, ensuring each is still matched exactly once.
+    classes: Vec<usize>,
 }
 
-impl OwningIter<'_> {
-    /// Creates a new `Iter` from a list of `Tokens`.
-    ///
-    /// Takes ownership of the `Tokens` and its underlying buffer.
-    pub(crate) fn new(tokens: Tokens) -> Self {
-        let mut tokens = ManuallyDrop::new(tokens);
+/// A set of groups in the process of being compiled by [`GroupSet::compile()`].
+struct Compiling {
+    /// The groups that have not yet been started.
+    groups: vec::IntoIter<UnorderedGroup>,
+    /// The group currently being compiled.
+    group: Option<(GroupIter, Vec<Element>)>,
+    /// The groups that have been compiled.
+    compiled: Vec<Vec<Element>>,
+}
 
+impl Compiling {
+    fn new(groups: Vec<UnorderedGroup>) -> Self {
         Self {
-            // SAFETY: The pointer used by the `Vec` in `Tokens` is guaranteed to not be null.
-            buf: unsafe { NonNull::new_unchecked(tokens.0.as_mut_ptr()) },
-            ptr: tokens.0.as_mut_ptr(),
-            // SAFETY: The resulting pointer is one byte past the end of the allocated object.
-            end: unsafe { tokens.0.as_mut_ptr().add(tokens.0.len()) },
-            cap: tokens.0.capacity(),
+            compiled: Vec::with_capacity(groups.len()),
+            groups: groups.into_iter(),
+            group: None,
+        }
+    }
+}
 
-            lifetime: PhantomData,
+impl GroupSet {
+    /// Compiles the given groups, including any groups nested within them.
+    ///
+    /// Returns `None` if there are no groups.
+    fn compile(groups: Vec<UnorderedGroup>) -> Option<Shared<Self>> {
+        let mut stack = vec![Compiling::new(groups)];
+        loop {
+            let compiling = stack.last_mut()?;
+            if let Some((iter, elements)) = &mut compiling.group {
+                if let Some(token) = iter.next() {
+                    let is_group = matches!(token, Token::Group(_));
+                    match CanonicalToken::try_from(token) {
+                        Ok(canonical_token) => elements.push(Element::Token(canonical_token)),
+                        Err(UnorderedTokens(mut groups)) => {
+                            if is_group {
+                                // Consecutive groups are matched together.
+                                while let Some(Token::Group(group)) = iter.as_slice().first() {
+                                    groups.push(UnorderedGroup::Static(group));
+                                    iter.next();
+                                }
+                            }
+                            stack.push(Compiling::new(groups));
+                        }
+                    }
+                } else if let Some((_, elements)) = compiling.group.take() {
+                    compiling.compiled.push(elements);
+                }
+            } else if let Some(group) = compiling.groups.next() {
+                compiling.group = Some((GroupIter::new(group), Vec::new()));
+            } else {
+                let set = stack
+                    .pop()
+                    .and_then(|compiling| Self::new(compiling.compiled));
+                match stack.last_mut() {
+                    Some(Compiling {
+                        group: Some((_, elements)),
+                        ..
+                    }) => {
+                        // Nested sets without any groups match nothing, and are therefore omitted.
+                        elements.extend(set.map(Element::Set));
+                    }
+                    _ => return set,
+                }
+            }
+        }
+    }
+
+    /// Creates a set from its compiled groups, determining which groups are identical.
+    ///
+    /// Returns `None` if there are no groups.
+    fn new(groups: Vec<Vec<Element>>) -> Option<Shared<Self>> {
+        if groups.is_empty() {
+            return None;
+        }
+
+        let mut representatives = Vec::new();
+        let classes = groups
+            .iter()
+            .enumerate()
+            .map(|(index, group)| {
+                representatives
+                    .iter()
+                    .copied()
+                    .find(|&representative: &usize| groups[representative] == *group)
+                    .unwrap_or_else(|| {
+                        representatives.push(index);
+                        index
+                    })
+            })
+            .collect();
+
+        Some(Shared::new(Self { groups, classes }))
+    }
+}
+
+impl PartialEq for GroupSet {
+    /// Compares the sets, along with every set nested within them.
+    ///
+    /// Sets are only equal if their groups are identical in the same order. This is conservative,
+    /// but avoids searching for a matching order of groups.
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some((a, b)) = pending.pop() {
+            if ptr::eq(a, b) {
+                continue;
+            }
+            if a.classes != b.classes {
+                return false;
+            }
+            for (a, b) in a.groups.iter().zip(&b.groups) {
+                if a.len() != b.len() {
+                    return false;
+                }
+                for elements in a.iter().zip(b) {
+                    match elements {
+                        (Element::Token(a), Element::Token(b)) => {
+                            if a != b {
+                                return false;
+                            }
+                        }
+                        (Element::Set(a), Element::Set(b)) => pending.push((a, b)),
+                        _ => return false,
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Context {
+    set: Shared<GroupSet>,
+    /// The index of the group currently being matched.
+    group: usize,
+    /// The position within the current group.
+    position: usize,
+    /// The groups that were used before the current group.
+    ///
+    /// This is shared by every context split from the same context.
+    used: Shared<Bitset>,
+    #[allow(clippy::struct_field_names)] // Acceptable, as the name refers to the contained type.
+    nested_context: Option<Shared<Context>>,
+}
+
+impl Context {
+    /// Returns a context starting from each of the unused groups in `set`.
+    ///
+    /// Only one context is started for each class of identical groups.
+    fn split(
+        set: &Shared<GroupSet>,
+        used: &Shared<Bitset>,
+        nested_context: Option<&Shared<Context>>,
+    ) -> Vec<Self> {
+        let mut started = Bitset::new(set.groups.len());
+        let mut contexts = Vec::new();
+        for (index, &class) in set.classes.iter().enumerate() {
+            if used.contains(index) || started.contains(class) {
+                continue;
+            }
+            started.insert(class);
+            contexts.push(Self {
+                set: set.clone(),
+                group: index,
+                position: 0,
+                used: used.clone(),
+                nested_context: nested_context.cloned(),
+            });
+        }
+        contexts
+    }
+
+    /// Returns the element this context is positioned at, or `None` at the end of the group.
+    fn element(&self) -> Option<&Element> {
+        self.set.groups[self.group].get(self.position)
+    }
+
+    /// Advances this context to its next canonical token.
+    ///
+    /// This context is split when it reaches a nested unordered group or the end of its current
+    /// group, so every resulting context is pushed to `advanced` positioned at its next token. If
+    /// nothing is pushed, this context has reached the end of its tokens.
+    fn advance(self, ignore_some: bool, advanced: &mut Vec<Self>) {
+        // Most contexts are already positioned at a token, so the pending contexts are only
+        // allocated once this context is split.
+        let mut pending = Vec::new();
+        let mut next = Some(self);
+        while let Some(mut context) = next.take().or_else(|| pending.pop()) {
+            match context.element() {
+                Some(Element::Token(CanonicalToken::Some)) if ignore_some => {
+                    context.position += 1;
+                    next = Some(context);
+                }
+                Some(Element::Token(_)) => advanced.push(context),
+                Some(Element::Set(set)) => {
+                    // Split and nest.
+                    let set = set.clone();
+                    context.position += 1;
+                    pending.extend(Self::split(
+                        &set,
+                        &Shared::new(Bitset::new(set.groups.len())),
+                        Some(&Shared::new(context)),
+                    ));
+                }
+                None => {
+                    // Split from remaining.
+                    let mut used = (*context.used).clone();
+                    used.insert(context.group);
+                    let contexts = Self::split(
+                        &context.set,
+                        &Shared::new(used),
+                        context.nested_context.as_ref(),
+                    );
+                    if contexts.is_empty() {
+                        next = context
+                            .nested_context
+                            .map(|nested_context| (*nested_context).clone());
+                    } else {
+                        pending.extend(contexts);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns whether a path exists through the given set of groups using the given iterator.
+///
+/// This will consume exactly the correct number of tokens from the given iterator.
+fn search<'a, I>(set: &Shared<GroupSet>, ignore_some: bool, mut tokens: I) -> bool
+where
+    I: Iterator<Item = &'a CanonicalToken>,
+{
+    let mut contexts = Context::split(set, &Shared::new(Bitset::new(set.groups.len())), None);
+    loop {
+        if contexts.is_empty() {
+            // All contexts have ended, and therefore no path could be found.
+            return false;
+        }
+        let advanced = advance(contexts, ignore_some);
+        if advanced.is_empty() {
+            // We have found the end of the groups without failing to find equality in tokens.
+            // This means that at least one path was found, and therefore the search succeeded.
+            return true;
+        }
+        if let Some(token) = tokens.next() {
+            contexts = retain_matching(advanced, token);
+        } else {
+            // Both sides had a different number of canonical tokens.
+            return false;
+        }
+    }
+}
+
+/// The number of contexts at which matching is parallelized.
+///
+/// Each step of a search does little work for each context, so the overhead of distributing
+/// contexts between threads is only recovered when many contexts are live.
+#[cfg(feature = "rayon")]
+const PARALLEL_THRESHOLD: usize = 4096;
+
+/// Returns whether a step over `len` contexts should be performed in parallel.
+#[cfg(feature = "rayon")]
+fn parallel(len: usize) -> bool {
+    len >= PARALLEL_THRESHOLD && rayon::current_num_threads() > 1
+}
+
+/// Advances each context to its next canonical token.
+///
+/// Contexts are split in place when they reach a nested unordered group or the end of their
+/// current group. Contexts that have reached the end of their tokens are not returned.
+fn advance(contexts: Vec<Context>, ignore_some: bool) -> Vec<Context> {
+    #[cfg(feature = "rayon")]
+    if parallel(contexts.len()) {
+        return contexts
+            .into_par_iter()
+            .fold(Vec::new, |mut advanced, context| {
+                context.advance(ignore_some, &mut advanced);
+                advanced
+            })
+            .reduce(Vec::new, |mut advanced, mut other| {
+                advanced.append(&mut other);
+                advanced
+            });
+    }
+
+    let mut advanced = Vec::with_capacity(contexts.len());
+    for context in contexts {
+        context.advance(ignore_some, &mut advanced);
+    }
+    advanced
+}
+
+/// Retains only the contexts positioned at a canonical token equal to `token`, moving them past
+/// it.
+fn retain_matching(contexts: Vec<Context>, token: &CanonicalToken) -> Vec<Context> {
+    let matches = |mut context: Context| {
+        if matches!(context.element(), Some(Element::Token(canonical_token)) if canonical_token == token)
+        {
+            context.position += 1;
+            Some(context)
+        } else {
+            None
+        }
+    };
+
+    #[cfg(feature = "rayon")]
+    if parallel(contexts.len()) {
+        return contexts.into_par_iter().filter_map(matches).collect();
+    }
+
+    contexts.into_iter().filter_map(matches).collect()
+}
+
+impl<T> PartialEq<T> for Tokens
+where
+    for<'a> &'a T: IntoIterator<Item = &'a Token>,
+{
+    fn eq(&self, other: &T) -> bool {
+        Matcher::new(other).matches(self)
+    }
+}
+
+impl PartialEq for Tokens {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl PartialEq<Matcher> for Tokens {
+    fn eq(&self, other: &Matcher) -> bool {
+        other.matches(self)
+    }
+}
+
+/// A precompiled pattern of expected [`Token`]s.
+///
+/// Comparing [`Tokens`] against a sequence of `Token`s requires converting the expected `Token`s
+/// into a canonical form and preparing the search through any [`Token::Unordered`] groups,
+/// including groups nested within other groups and the sets of identical groups. A `Matcher`
+/// performs this work once, allowing the same expected pattern to be compared against
+/// many outputs cheaply. This is useful in property tests, where the same expected tokens are
+/// compared against thousands of generated values.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok;
+/// use serde::Serialize;
+/// use serde_assert::{
+///     token::Matcher,
+///     Serializer,
+///     Token,
+/// };
+///
+/// let matcher = Matcher::new(&[
+///     Token::Tuple { len: 2 },
+///     Token::Bool(true),
+///     Token::U8(42),
+///     Token::TupleEnd,
+/// ]);
+/// let serializer = Serializer::new();
+///
+/// for _ in 0..100 {
+///     let tokens = assert_ok!((true, 42u8).serialize(&serializer));
+///     assert!(matcher.matches(&tokens));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Matcher {
+    segments: Vec<Segment>,
+    ignore_some: bool,
+}
+
+impl Matcher {
+    /// Compiles the given expected tokens into a `Matcher`.
+    pub fn new<'a, I>(expected: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Token>,
+    {
+        let mut segments = Vec::new();
+        let mut ordered = Vec::new();
+        let mut groups = Vec::new();
+
+        for token in expected {
+            if let Token::Group(group) = token {
+                if !ordered.is_empty() {
+                    segments.push(Segment::Ordered(mem::take(&mut ordered)));
+                }
+                groups.push(UnorderedGroup::Static(group));
+                continue;
+            }
+            if !groups.is_empty() {
+                segments.push(Segment::Unordered(GroupSet::compile(mem::take(
+                    &mut groups,
+                ))));
+            }
+            match CanonicalToken::try_from(token.clone()) {
+                Ok(canonical_token) => ordered.push(canonical_token),
+                Err(unordered_tokens) => {
+                    if !ordered.is_empty() {
+                        segments.push(Segment::Ordered(mem::take(&mut ordered)));
+                    }
+                    segments.push(Segment::Unordered(GroupSet::compile(unordered_tokens.0)));
+                }
+            }
+        }
+        if !ordered.is_empty() {
+            segments.push(Segment::Ordered(ordered));
+        }
+        if !groups.is_empty() {
+            segments.push(Segment::Unordered(GroupSet::compile(groups)));
+        }
+
+        Self {
+            segments,
+            ignore_some: false,
+        }
+    }
+
+    /// Makes this `Matcher` insensitive to `Option` wrapping.
+    ///
+    /// Under this mode, a [`Token::Some`] followed by a value is considered equal to the bare
+    /// value, on both the expected and the compared side. This is useful for asserting the output
+    /// of types that are migrating between `T` and `Option<T>` representations of a field.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     token::Matcher,
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let matcher = Matcher::new(&[Token::U8(42)]).ignoring_some();
+    /// let serializer = Serializer::new();
+    ///
+    /// assert!(matcher.matches(&42u8.serialize(&serializer).unwrap()));
+    /// assert!(matcher.matches(&Some(42u8).serialize(&serializer).unwrap()));
+    /// ```
+    #[must_use]
+    pub fn ignoring_some(mut self) -> Self {
+        self.ignore_some = true;
+        for segment in &mut self.segments {
+            match segment {
+                Segment::Ordered(tokens) => {
+                    tokens.retain(|token| !matches!(token, CanonicalToken::Some));
+                }
+                Segment::Unordered(_) => {}
+            }
+        }
+        self
+    }
+
+    /// Returns whether the given [`Tokens`] match this pattern.
+    #[must_use]
+    pub fn matches(&self, tokens: &Tokens) -> bool {
+        let stripped;
+        let mut tokens_iter = if self.ignore_some {
+            stripped = tokens
+                .0
+                .iter()
+                .filter(|token| !matches!(token, CanonicalToken::Some))
+                .cloned()
+                .collect::<Vec<_>>();
+            stripped.iter()
+        } else {
+            tokens.0.iter()
+        };
+
+        for segment in &self.segments {
+            if !match segment {
+                Segment::Ordered(expected) => {
+                    let actual = tokens_iter.as_slice();
+                    if actual.len() < expected.len() {
+                        // Both sides had a different number of canonical tokens.
+                        false
+                    } else if actual[..expected.len()] == **expected {
+                        tokens_iter = actual[expected.len()..].iter();
+                        true
+                    } else {
+                        false
+                    }
+                }
+                Segment::Unordered(set) => set
+                    .as_ref()
+                    .map_or(true, |set| search(set, self.ignore_some, &mut tokens_iter)),
+            } {
+                return false;
+            }
+        }
+
+        if tokens_iter.next().is_some() {
+            // Both sides had a different number of canonical tokens.
+            return false;
+        }
+
+        true
+    }
+}
+
+/// A compiled portion of a [`Matcher`].
+#[derive(Clone, Debug)]
+enum Segment {
+    /// Tokens that must appear in exactly this order.
+    Ordered(Vec<CanonicalToken>),
+    /// Unordered groups, or `None` if there were no groups.
+    Unordered(Option<Shared<GroupSet>>),
+}
+
+/// The representation used for enum variants.
+///
+/// Used by [`Tokens::tag_enums()`] and [`de::Builder::enum_tagging()`] to rewrite enum variant
+/// tokens into the tokens produced by `#[serde(tag = "...")]` and
+/// `#[serde(tag = "...", content = "...")]` enums.
+///
+/// [`de::Builder::enum_tagging()`]: crate::de::Builder::enum_tagging()
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EnumTagging {
+    /// Variants are represented by their variant tokens.
+    ///
+    /// This is serde's default representation.
+    External,
+    /// Variants are represented as structs containing the variant name under the `tag` field
+    /// alongside the variant's fields.
+    Internal {
+        /// The field containing the variant.
+        tag: &'static str,
+    },
+    /// Variants are represented as structs containing the variant under the `tag` field and the
+    /// variant's contents under the `content` field.
+    Adjacent {
+        /// The field containing the variant.
+        tag: &'static str,
+        /// The field containing the variant's contents.
+        content: &'static str,
+    },
+}
+
+/// Statistics describing a set of [`Tokens`].
+///
+/// Obtained by calling [`Tokens::stats()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    counts: BTreeMap<&'static str, usize>,
+    max_depth: usize,
+    str_len: usize,
+    bytes_len: usize,
+}
+
+impl Stats {
+    /// Returns the number of tokens of the given kind.
+    ///
+    /// The kind is the name of a [`Token`] variant, such as `"Seq"` or `"Str"`. Kinds that do not
+    /// appear in the `Tokens` have a count of `0`.
+    #[must_use]
+    pub fn count(&self, kind: &str) -> usize {
+        self.counts.get(kind).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of tokens of each kind that appears in the `Tokens`.
+    #[must_use]
+    pub fn counts(&self) -> &BTreeMap<&'static str, usize> {
+        &self.counts
+    }
+
+    /// Returns the maximum nesting depth of compound tokens.
+    ///
+    /// `Tokens` containing no compound tokens have a depth of `0`, and each [`Seq`], [`Tuple`],
+    /// [`TupleStruct`], [`TupleVariant`], [`Map`], [`Struct`], or [`StructVariant`] increases the
+    /// depth of the tokens it contains by one.
+    ///
+    /// [`Map`]: Token::Map
+    /// [`Seq`]: Token::Seq
+    /// [`Struct`]: Token::Struct
+    /// [`StructVariant`]: Token::StructVariant
+    /// [`Tuple`]: Token::Tuple
+    /// [`TupleStruct`]: Token::TupleStruct
+    /// [`TupleVariant`]: Token::TupleVariant
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Returns the total length, in bytes, of all [`Str`] tokens.
+    ///
+    /// [`Str`]: Token::Str
+    #[must_use]
+    pub fn str_len(&self) -> usize {
+        self.str_len
+    }
+
+    /// Returns the total length of all [`Bytes`] tokens.
+    ///
+    /// [`Bytes`]: Token::Bytes
+    #[must_use]
+    pub fn bytes_len(&self) -> usize {
+        self.bytes_len
+    }
+}
+
+impl<'a> IntoIterator for &'a Tokens {
+    type Item = Token;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Token> for Tokens {
+    /// Collects `Token`s into `Tokens`.
+    ///
+    /// Any [`Unordered`] tokens are flattened, in the order they are provided.
+    ///
+    /// [`Unordered`]: Token::Unordered
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = Token>,
+    {
+        Self::canonicalize(iter)
+    }
+}
+
+impl IntoIterator for Tokens {
+    type Item = Token;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            token_iter: self.0.into_iter(),
         }
     }
+}
+
+/// An iterator that moves [`Token`]s out of a [`Tokens`] `struct`.
+///
+/// This `struct` is created by the [`into_iter()`] method on `Tokens` (provided by the
+/// [`IntoIterator`] trait).
+///
+/// [`into_iter()`]: IntoIterator::into_iter()
+pub struct IntoIter {
+    token_iter: vec::IntoIter<CanonicalToken>,
+}
+
+impl IntoIter {
+    /// Returns the next [`Token`] without advancing the iterator.
+    #[must_use]
+    pub fn peek(&self) -> Option<Token> {
+        self.token_iter.as_slice().first().cloned().map(From::from)
+    }
+}
+
+impl Iterator for IntoIter {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.token_iter.next().map(From::from)
+    }
+}
+
+/// An iterator over the [`Token`]s of a [`Tokens`] `struct`.
+///
+/// This `struct` is created by the [`iter()`] method on `Tokens`. As `Tokens` store their contents
+/// in a canonical form, this iterator yields owned `Token`s.
+///
+/// [`iter()`]: Tokens::iter()
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    token_iter: slice::Iter<'a, CanonicalToken>,
+}
+
+impl Iter<'_> {
+    /// Returns the next [`Token`] without advancing the iterator.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::{
+    ///     assert_matches,
+    ///     assert_ok,
+    /// };
+    /// use serde::Serialize;
+    /// use serde_assert::{
+    ///     Serializer,
+    ///     Token,
+    /// };
+    ///
+    /// let tokens = assert_ok!(true.serialize(&Serializer::new()));
+    /// let iter = tokens.iter();
+    ///
+    /// assert_matches!(iter.peek(), Some(Token::Bool(true)));
+    /// ```
+    #[must_use]
+    pub fn peek(&self) -> Option<Token> {
+        self.token_iter.as_slice().first().cloned().map(From::from)
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.token_iter.next().cloned().map(From::from)
+    }
+}
+
+/// Replays a sequence of [`Token`]s into any [`serde::Serializer`].
+///
+/// `Replay` implements [`Serialize`] by calling the serializer methods corresponding to each
+/// token. This allows checking that a sequence of tokens can be encoded by a real data format, or
+/// writing tokens out as a fixture.
+///
+/// The tokens must represent exactly one value. Any [`Unordered`] tokens are replayed in the
+/// order they are provided.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde_assert::{
+///     token::Replay,
+///     Token,
+/// };
+///
+/// let replay = Replay::new([
+///     Token::Seq { len: Some(2) },
+///     Token::U32(1),
+///     Token::Str("foo".to_owned()),
+///     Token::SeqEnd,
+/// ]);
+///
+/// assert_ok_eq!(serde_json::to_string(&replay), r#"[1,"foo"]"#);
+/// ```
+///
+/// [`Unordered`]: Token::Unordered
+#[derive(Clone, Debug)]
+pub struct Replay {
+    tokens: Tokens,
+}
+
+impl Replay {
+    /// Creates a new `Replay` of the given tokens.
+    pub fn new<T>(tokens: T) -> Self
+    where
+        T: IntoIterator<Item = Token>,
+    {
+        Self {
+            tokens: Tokens::canonicalize(tokens),
+        }
+    }
+}
+
+impl Serialize for Replay {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.tokens.serialize(serializer)
+    }
+}
+
+/// Replays the `Tokens` into any [`serde::Serializer`].
+///
+/// This behaves exactly like [`Replay`], allowing `Tokens` captured by a [`Serializer`] to be
+/// encoded by a real data format, such as for debugging or for writing fixtures to disk. The
+/// `Tokens` must represent exactly one value.
+///
+/// # Example
+/// ``` rust
+/// use claims::{
+///     assert_ok,
+///     assert_ok_eq,
+/// };
+/// use serde::Serialize;
+/// use serde_assert::Serializer;
+/// use std::collections::BTreeMap;
+///
+/// let serializer = Serializer::new();
+/// let tokens =
+///     assert_ok!(BTreeMap::from([("foo", Some(1u8)), ("bar", None)]).serialize(&serializer));
+///
+/// assert_ok_eq!(serde_json::to_string(&tokens), r#"{"bar":null,"foo":1}"#);
+/// ```
+impl Serialize for Tokens {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let (value, rest) = split_value(&self.0).map_err(S::Error::custom)?;
+        if !rest.is_empty() {
+            return Err(S::Error::custom("trailing tokens after value"));
+        }
+        ReplayValue(value).serialize(serializer)
+    }
+}
+
+/// Tracks the nesting of values within a stream of canonical tokens.
+///
+/// Values are delimited using the structural balance of compound tokens rather than by recursing
+/// into them, so deeply nested values cannot overflow the stack.
+#[derive(Debug, Default)]
+pub(crate) struct Nesting {
+    depth: usize,
+}
+
+/// The effect of a single token on a [`Nesting`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Step {
+    /// The token is followed by the rest of its value.
+    Continues,
+    /// The token completes the value at the resulting depth.
+    Ends,
+    /// The token ends a compound value that was never started.
+    Unbalanced,
+}
+
+impl Nesting {
+    /// Returns the number of compound values currently open.
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Advances past the given token.
+    pub(crate) fn step(&mut self, token: &CanonicalToken) -> Step {
+        match token {
+            CanonicalToken::Seq { .. }
+            | CanonicalToken::Tuple { .. }
+            | CanonicalToken::TupleStruct { .. }
+            | CanonicalToken::TupleVariant { .. }
+            | CanonicalToken::Map { .. }
+            | CanonicalToken::Struct { .. }
+            | CanonicalToken::StructVariant { .. } => {
+                self.depth += 1;
+                Step::Continues
+            }
+            CanonicalToken::SeqEnd
+            | CanonicalToken::TupleEnd
+            | CanonicalToken::TupleStructEnd
+            | CanonicalToken::TupleVariantEnd
+            | CanonicalToken::MapEnd
+            | CanonicalToken::StructEnd
+            | CanonicalToken::StructVariantEnd => match self.depth.checked_sub(1) {
+                Some(depth) => {
+                    self.depth = depth;
+                    Step::Ends
+                }
+                None => Step::Unbalanced,
+            },
+            // These tokens are always followed by the rest of the same value.
+            CanonicalToken::Some
+            | CanonicalToken::NewtypeStruct { .. }
+            | CanonicalToken::NewtypeVariant { .. } => Step::Continues,
+            _ => Step::Ends,
+        }
+    }
+}
+
+/// Returns the number of tokens making up the first value, or `None` if the tokens end before
+/// the value does.
+///
+/// A token ending a compound value that was never started is treated as a value on its own.
+pub(crate) fn value_len(tokens: &[CanonicalToken]) -> Option<usize> {
+    let mut nesting = Nesting::default();
+    tokens
+        .iter()
+        .position(|token| nesting.step(token) != Step::Continues && nesting.depth() == 0)
+        .map(|index| index + 1)
+}
+
+/// Splits the tokens representing the first value from the remaining tokens.
+fn split_value(
+    tokens: &[CanonicalToken],
+) -> Result<(&[CanonicalToken], &[CanonicalToken]), &'static str> {
+    value_len(tokens)
+        .map(|len| tokens.split_at(len))
+        .ok_or("unexpected end of tokens")
+}
+
+/// Rewrites the enum variants within `tokens` into the given [`EnumTagging`] representation.
+///
+/// Each rewritten token is paired with the index of the token it originates from, allowing labels
+/// to be carried over to the rewritten tokens. Variants whose values are incomplete are not
+/// rewritten.
+pub(crate) fn tag_enums(
+    tokens: &[CanonicalToken],
+    tagging: EnumTagging,
+    out: &mut Vec<(usize, CanonicalToken)>,
+) {
+    /// A rewritten variant whose value has not yet ended.
+    struct Open {
+        /// The index of the variant token.
+        index: usize,
+        /// The position of the variant's first rewritten token within `out`.
+        position: usize,
+        /// The depth at which the variant's value ends.
+        depth: usize,
+        tagged: Tagged,
+    }
+
+    let mut open: Vec<Open> = Vec::new();
+    let mut nesting = Nesting::default();
+    let mut replaced = false;
+    for (index, token) in tokens.iter().enumerate() {
+        let depth = nesting.depth();
+        let step = nesting.step(token);
+        if mem::take(&mut replaced) {
+            // This token was replaced by the tokens of the preceding variant.
+        } else if let Some(tagged) = tag_variant(token, tokens.get(index + 1), tagging) {
+            let position = out.len();
+            out.extend(tagged.start.iter().cloned().map(|token| (index, token)));
+            replaced = tagged.replaces_next;
+            open.push(Open {
+                index,
+                position,
+                depth,
+                tagged,
+            });
+        } else if !matches!(
+            open.last(),
+            Some(variant) if variant.tagged.replaces_end
+                && step == Step::Ends
+                && nesting.depth() == variant.depth
+                && nesting.depth() < depth
+        ) {
+            out.push((index, token.clone()));
+        }
+
+        if step == Step::Ends {
+            while let Some(variant) = open.pop() {
+                if variant.depth != nesting.depth() {
+                    open.push(variant);
+                    break;
+                }
+                out.extend(variant.tagged.end.into_iter().map(|token| (index, token)));
+            }
+        }
+    }
+
+    // Restore the original tokens of any variants that never ended.
+    for variant in open.into_iter().rev() {
+        let len = if variant.tagged.replaces_next { 2 } else { 1 };
+        out.splice(
+            variant.position..(variant.position + variant.tagged.start.len()),
+            (variant.index..(variant.index + len)).map(|index| (index, tokens[index].clone())),
+        );
+    }
+}
+
+/// The tokens an enum variant is rewritten into.
+struct Tagged {
+    /// The tokens replacing the variant token.
+    start: Vec<CanonicalToken>,
+    /// The tokens written once the variant's value ends.
+    end: Vec<CanonicalToken>,
+    /// Whether the token following the variant token is also replaced by `start`.
+    replaces_next: bool,
+    /// Whether the token ending the variant's value is replaced by `end`.
+    replaces_end: bool,
+}
+
+/// Returns the tokens the given variant token is rewritten into for the given [`EnumTagging`]
+/// representation, or `None` if it is not rewritten.
+///
+/// The rewritten tokens are the same as those produced by serializing the equivalent tagged enum.
+/// `next` is the token following the variant token, which determines whether a newtype variant can
+/// be internally tagged.
+fn tag_variant(
+    token: &CanonicalToken,
+    next: Option<&CanonicalToken>,
+    tagging: EnumTagging,
+) -> Option<Tagged> {
+    let id = match token {
+        CanonicalToken::UnitVariant { id }
+        | CanonicalToken::NewtypeVariant { id }
+        | CanonicalToken::TupleVariant { id, .. }
+        | CanonicalToken::StructVariant { id, .. } => id,
+        _ => return None,
+    };
+    match tagging {
+        EnumTagging::External => None,
+        EnumTagging::Internal { tag } => tag_internal(token, next, id, tag),
+        EnumTagging::Adjacent { tag, content } => Some(tag_adjacent(token, id, tag, content)),
+    }
+}
+
+/// Returns the tokens of an adjacently tagged variant.
+fn tag_adjacent(
+    token: &CanonicalToken,
+    id: &VariantId,
+    tag: &'static str,
+    content: &'static str,
+) -> Tagged {
+    let len = if matches!(token, CanonicalToken::UnitVariant { .. }) {
+        1
+    } else {
+        2
+    };
+    let mut start = vec![
+        CanonicalToken::Struct { name: id.name, len },
+        CanonicalToken::Field(tag),
+        CanonicalToken::UnitVariant {
+            id: Box::new(id.clone()),
+        },
+    ];
+    let mut end = Vec::with_capacity(2);
+    match token {
+        CanonicalToken::NewtypeVariant { .. } => {
+            start.push(CanonicalToken::Field(content));
+        }
+        CanonicalToken::TupleVariant { len, .. } => {
+            start.push(CanonicalToken::Field(content));
+            start.push(CanonicalToken::Tuple { len: *len });
+            end.push(CanonicalToken::TupleEnd);
+        }
+        CanonicalToken::StructVariant { len, .. } => {
+            start.push(CanonicalToken::Field(content));
+            start.push(CanonicalToken::Struct {
+                name: id.variant,
+                len: *len,
+            });
+            end.push(CanonicalToken::StructEnd);
+        }
+        _ => {}
+    }
+    end.push(CanonicalToken::StructEnd);
+    Tagged {
+        replaces_end: matches!(
+            token,
+            CanonicalToken::TupleVariant { .. } | CanonicalToken::StructVariant { .. }
+        ),
+        start,
+        end,
+        replaces_next: false,
+    }
+}
+
+/// Returns the tokens of an internally tagged variant, or `None` if the variant cannot be
+/// internally tagged.
+fn tag_internal(
+    token: &CanonicalToken,
+    next: Option<&CanonicalToken>,
+    id: &VariantId,
+    tag: &'static str,
+) -> Option<Tagged> {
+    let tag_entry = [
+        CanonicalToken::Field(tag),
+        CanonicalToken::Str(id.variant.into()),
+    ];
+    let (start, end, replaces_next) = match (token, next) {
+        (CanonicalToken::UnitVariant { .. }, _) => (
+            CanonicalToken::Struct {
+                name: id.name,
+                len: 1,
+            },
+            CanonicalToken::StructEnd,
+            false,
+        ),
+        (CanonicalToken::StructVariant { len, .. }, _) => (
+            CanonicalToken::Struct {
+                name: id.name,
+                len: len + 1,
+            },
+            CanonicalToken::StructEnd,
+            false,
+        ),
+        (CanonicalToken::NewtypeVariant { .. }, Some(CanonicalToken::Struct { name, len })) => (
+            CanonicalToken::Struct { name, len: len + 1 },
+            CanonicalToken::StructEnd,
+            true,
+        ),
+        (CanonicalToken::NewtypeVariant { .. }, Some(CanonicalToken::Map { len })) => {
+            return Some(Tagged {
+                start: vec![
+                    CanonicalToken::Map {
+                        len: len.map(|len| len + 1),
+                    },
+                    CanonicalToken::Str(tag.into()),
+                    CanonicalToken::Str(id.variant.into()),
+                ],
+                end: vec![CanonicalToken::MapEnd],
+                replaces_next: true,
+                replaces_end: true,
+            });
+        }
+        _ => return None,
+    };
+    let mut tokens = vec![start];
+    tokens.extend(tag_entry);
+    Some(Tagged {
+        start: tokens,
+        end: vec![end],
+        replaces_next,
+        replaces_end: !matches!(token, CanonicalToken::UnitVariant { .. }),
+    })
+}
+
+/// The tokens representing exactly one value.
+struct ReplayValue<'a>(&'a [CanonicalToken]);
+
+impl<'a> ReplayValue<'a> {
+    /// Returns the values contained within a compound value.
+    ///
+    /// Fails if the compound value is ended by the wrong token.
+    fn elements(&self) -> Result<Elements<'a>, &'static str> {
+        if matches!(
+            (&self.0[0], &self.0[self.0.len() - 1]),
+            (CanonicalToken::Seq { .. }, CanonicalToken::SeqEnd)
+                | (CanonicalToken::Tuple { .. }, CanonicalToken::TupleEnd)
+                | (
+                    CanonicalToken::TupleStruct { .. },
+                    CanonicalToken::TupleStructEnd
+                )
+                | (
+                    CanonicalToken::TupleVariant { .. },
+                    CanonicalToken::TupleVariantEnd
+                )
+                | (CanonicalToken::Map { .. }, CanonicalToken::MapEnd)
+                | (CanonicalToken::Struct { .. }, CanonicalToken::StructEnd)
+                | (
+                    CanonicalToken::StructVariant { .. },
+                    CanonicalToken::StructVariantEnd
+                )
+        ) {
+            Ok(Elements(&self.0[1..(self.0.len() - 1)]))
+        } else {
+            Err("mismatched end of compound value")
+        }
+    }
+}
+
+/// An iterator over the values contained within a compound value.
+struct Elements<'a>(&'a [CanonicalToken]);
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Result<ReplayValue<'a>, &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            None
+        } else {
+            Some(split_value(self.0).map(|(value, rest)| {
+                self.0 = rest;
+                ReplayValue(value)
+            }))
+        }
+    }
+}
+
+/// An iterator over the fields contained within a struct.
+struct Fields<'a>(Elements<'a>);
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(&'static str, Option<ReplayValue<'a>>), &'static str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (token, rest) = self.0 .0.split_first()?;
+        self.0 .0 = rest;
+        match token {
+            CanonicalToken::Field(key) => Some(
+                self.0
+                    .next()
+                    .unwrap_or(Err("unexpected end of tokens"))
+                    .map(|value| (*key, Some(value))),
+            ),
+            CanonicalToken::SkippedField(key) => Some(Ok((*key, None))),
+            _ => Some(Err("expected a field")),
+        }
+    }
+}
+
+impl Serialize for ReplayValue<'_> {
+    #[allow(clippy::too_many_lines)] // Every token must be handled.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match &self.0[0] {
+            CanonicalToken::Bool(v) => serializer.serialize_bool(*v),
+            CanonicalToken::I8(v) => serializer.serialize_i8(*v),
+            CanonicalToken::I16(v) => serializer.serialize_i16(*v),
+            CanonicalToken::I32(v) => serializer.serialize_i32(*v),
+            CanonicalToken::I64(v) => serializer.serialize_i64(*v),
+            CanonicalToken::I128(v) => serializer.serialize_i128(*v),
+            CanonicalToken::U8(v) => serializer.serialize_u8(*v),
+            CanonicalToken::U16(v) => serializer.serialize_u16(*v),
+            CanonicalToken::U32(v) => serializer.serialize_u32(*v),
+            CanonicalToken::U64(v) => serializer.serialize_u64(*v),
+            CanonicalToken::U128(v) => serializer.serialize_u128(*v),
+            CanonicalToken::F32(v) => serializer.serialize_f32(*v),
+            CanonicalToken::F64(v) => serializer.serialize_f64(*v),
+            CanonicalToken::Char(v) => serializer.serialize_char(*v),
+            CanonicalToken::Str(v) => serializer.serialize_str(v),
+            CanonicalToken::Bytes(v) => serializer.serialize_bytes(v),
+            CanonicalToken::None => serializer.serialize_none(),
+            CanonicalToken::Some => serializer.serialize_some(&ReplayValue(&self.0[1..])),
+            CanonicalToken::Unit => serializer.serialize_unit(),
+            CanonicalToken::UnitStruct { name } => serializer.serialize_unit_struct(name),
+            CanonicalToken::UnitVariant { id } => {
+                serializer.serialize_unit_variant(id.name, id.variant_index, id.variant)
+            }
+            CanonicalToken::NewtypeStruct { name } => {
+                serializer.serialize_newtype_struct(name, &ReplayValue(&self.0[1..]))
+            }
+            CanonicalToken::NewtypeVariant { id } => serializer.serialize_newtype_variant(
+                id.name,
+                id.variant_index,
+                id.variant,
+                &ReplayValue(&self.0[1..]),
+            ),
+            CanonicalToken::Seq { len } => {
+                let mut seq = serializer.serialize_seq(*len)?;
+                for element in self.elements().map_err(S::Error::custom)? {
+                    seq.serialize_element(&element.map_err(S::Error::custom)?)?;
+                }
+                seq.end()
+            }
+            CanonicalToken::Tuple { len } => {
+                let mut tuple = serializer.serialize_tuple(*len)?;
+                for element in self.elements().map_err(S::Error::custom)? {
+                    tuple.serialize_element(&element.map_err(S::Error::custom)?)?;
+                }
+                tuple.end()
+            }
+            CanonicalToken::TupleStruct { name, len } => {
+                let mut tuple_struct = serializer.serialize_tuple_struct(name, *len)?;
+                for field in self.elements().map_err(S::Error::custom)? {
+                    tuple_struct.serialize_field(&field.map_err(S::Error::custom)?)?;
+                }
+                tuple_struct.end()
+            }
+            CanonicalToken::TupleVariant { id, len } => {
+                let mut tuple_variant = serializer.serialize_tuple_variant(
+                    id.name,
+                    id.variant_index,
+                    id.variant,
+                    *len,
+                )?;
+                for field in self.elements().map_err(S::Error::custom)? {
+                    tuple_variant.serialize_field(&field.map_err(S::Error::custom)?)?;
+                }
+                tuple_variant.end()
+            }
+            CanonicalToken::Map { len } => {
+                let mut map = serializer.serialize_map(*len)?;
+                let mut elements = self.elements().map_err(S::Error::custom)?;
+                while let Some(key) = elements.next() {
+                    map.serialize_key(&key.map_err(S::Error::custom)?)?;
+                    map.serialize_value(
+                        &elements
+                            .next()
+                            .unwrap_or(Err("expected a map value"))
+                            .map_err(S::Error::custom)?,
+                    )?;
+                }
+                map.end()
+            }
+            CanonicalToken::Struct { name, len } => {
+                let mut r#struct = serializer.serialize_struct(name, *len)?;
+                for field in Fields(self.elements().map_err(S::Error::custom)?) {
+                    match field.map_err(S::Error::custom)? {
+                        (key, Some(value)) => r#struct.serialize_field(key, &value)?,
+                        (key, None) => r#struct.skip_field(key)?,
+                    }
+                }
+                r#struct.end()
+            }
+            CanonicalToken::StructVariant { id, len } => {
+                let mut struct_variant = serializer.serialize_struct_variant(
+                    id.name,
+                    id.variant_index,
+                    id.variant,
+                    *len,
+                )?;
+                for field in Fields(self.elements().map_err(S::Error::custom)?) {
+                    match field.map_err(S::Error::custom)? {
+                        (key, Some(value)) => struct_variant.serialize_field(key, &value)?,
+                        (key, None) => struct_variant.skip_field(key)?,
+                    }
+                }
+                struct_variant.end()
+            }
+            CanonicalToken::SeqEnd
+            | CanonicalToken::TupleEnd
+            | CanonicalToken::TupleStructEnd
+            | CanonicalToken::TupleVariantEnd
+            | CanonicalToken::MapEnd
+            | CanonicalToken::Field(_)
+            | CanonicalToken::SkippedField(_)
+            | CanonicalToken::StructEnd
+            | CanonicalToken::StructVariantEnd => Err(S::Error::custom("expected a value")),
+            CanonicalToken::Error(message) => Err(S::Error::custom(message)),
+        }
+    }
+}
+
+/// An iterator over tokens.
+///
+/// This iterator owns the tokens, iterating over references to them.
+#[cfg(not(feature = "safe"))]
+pub(crate) struct OwningIter<'a> {
+    /// A pointer to the entire buffer that is owned by this struct.
+    ///
+    /// Immutable references to the `Token`s in this buffer can exist within the lifetime `'a`.
+    buf: NonNull<CanonicalToken>,
+    /// A pointer to the current position in iteration.
+    ptr: *mut CanonicalToken,
+    /// A pointer to the end of the allocated buffer.
+    end: *mut CanonicalToken,
+    /// The capacity of the underlying allocation.
+    ///
+    /// This is only used for deallocating when the struct is dropped.
+    cap: usize,
+
+    /// The lifetime of the underlying `Token`s.
+    ///
+    /// `Token`s can be borrowed for up to the lifetime `'a`, allowing for zero-copy
+    /// deserialization.
+    lifetime: PhantomData<&'a ()>,
+}
+
+#[cfg(not(feature = "safe"))]
+impl OwningIter<'_> {
+    /// Creates a new `Iter` from a list of `Tokens`.
+    ///
+    /// Takes ownership of the `Tokens` and its underlying buffer.
+    pub(crate) fn new(tokens: Tokens) -> Self {
+        let mut tokens = ManuallyDrop::new(tokens);
+
+        Self {
+            // SAFETY: The pointer used by the `Vec` in `Tokens` is guaranteed to not be null.
+            buf: unsafe { NonNull::new_unchecked(tokens.0.as_mut_ptr()) },
+            ptr: tokens.0.as_mut_ptr(),
+            // SAFETY: The resulting pointer is one byte past the end of the allocated object.
+            end: unsafe { tokens.0.as_mut_ptr().add(tokens.0.len()) },
+            cap: tokens.0.capacity(),
+
+            lifetime: PhantomData,
+        }
+    }
+
+    /// Returns the number of `Token`s that have been iterated over.
+    pub(crate) fn position(&self) -> usize {
+        // SAFETY: `self.ptr` is guaranteed to be within the allocated object starting at
+        // `self.buf`, and at or after `self.buf`.
+        #[allow(clippy::cast_sign_loss)]
+        unsafe {
+            self.ptr.offset_from(self.buf.as_ptr()) as usize
+        }
+    }
+
+    /// Returns the remaining `Token`s as a slice.
+    pub(crate) fn as_slice(&self) -> &[CanonicalToken] {
+        // SAFETY: `self.ptr` is guaranteed to be less than `self.end`, and therefore a valid
+        // pointer within the allocated object.
+        unsafe {
+            slice::from_raw_parts(
+                self.ptr,
+                #[allow(clippy::cast_sign_loss)]
+                {
+                    self.end.offset_from(self.ptr) as usize
+                },
+            )
+        }
+    }
+}
+
+#[cfg(not(feature = "safe"))]
+impl<'a> Iterator for OwningIter<'a> {
+    type Item = &'a mut CanonicalToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ptr == self.end {
+            None
+        } else {
+            let current = self.ptr;
+            // SAFETY: Since `self.ptr` is not equal to `self.end`, it must be before it, and
+            // therefore incrementing by 1 will also result in a valid pointer within the allocated
+            // object, or 1 byte past the end if the iteration has completed.
+            self.ptr = unsafe { self.ptr.add(1) };
+            // SAFETY: The pointed-at object is guaranteed to be a valid `Token` that will live for
+            // the lifetime `'a`.
+            Some(unsafe { &mut *current })
+        }
+    }
+}
+
+impl Debug for OwningIter<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_tuple("OwningIter")
+            .field(&self.as_slice())
+            .finish()
+    }
+}
+
+#[cfg(not(feature = "safe"))]
+impl Drop for OwningIter<'_> {
+    fn drop(&mut self) {
+        // SAFETY: The raw parts stored in this struct are guaranteed to correspond to the valid
+        // parts of a `Vec`, since the parts were obtained directly from a `Vec` originally.
+        unsafe {
+            Vec::from_raw_parts(
+                self.buf.as_ptr(),
+                #[allow(clippy::cast_sign_loss)]
+                {
+                    self.end.offset_from(self.buf.as_ptr()) as usize
+                },
+                self.cap,
+            )
+        };
+    }
+}
+
+/// An iterator over tokens.
+///
+/// This iterator owns the tokens, moving each one out as it is iterated over. As there is no safe
+/// way for the iterator to lend out references to tokens it owns for the lifetime `'a`, the
+/// contents of the tokens can't be borrowed for that lifetime, and zero-copy deserialization is
+/// therefore not available.
+#[cfg(feature = "safe")]
+pub(crate) struct OwningIter<'a> {
+    /// The tokens that have not yet been iterated over.
+    tokens: vec::IntoIter<CanonicalToken>,
+    /// The number of tokens that have been iterated over.
+    position: usize,
+
+    /// The lifetime of the input.
+    ///
+    /// Nothing is borrowed for this lifetime, but it is kept to match the API of the iterator
+    /// used when the `safe` feature is disabled.
+    lifetime: PhantomData<&'a ()>,
+}
+
+#[cfg(feature = "safe")]
+impl OwningIter<'_> {
+    /// Creates a new `Iter` from a list of `Tokens`.
+    ///
+    /// Takes ownership of the `Tokens` and its underlying buffer.
+    pub(crate) fn new(tokens: Tokens) -> Self {
+        Self {
+            tokens: tokens.0.into_iter(),
+            position: 0,
+
+            lifetime: PhantomData,
+        }
+    }
+
+    /// Returns the number of `Token`s that have been iterated over.
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the remaining `Token`s as a slice.
+    pub(crate) fn as_slice(&self) -> &[CanonicalToken] {
+        self.tokens.as_slice()
+    }
+}
+
+#[cfg(feature = "safe")]
+impl Iterator for OwningIter<'_> {
+    type Item = TakenToken;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.tokens.next()?;
+        self.position += 1;
+        Some(TakenToken(token))
+    }
+}
+
+/// A token that has been moved out of an [`OwningIter`].
+///
+/// This dereferences to the token, allowing it to be used in the same way as the references
+/// yielded when the `safe` feature is disabled.
+#[cfg(feature = "safe")]
+#[derive(Debug)]
+pub(crate) struct TakenToken(pub(crate) CanonicalToken);
+
+#[cfg(feature = "safe")]
+impl Deref for TakenToken {
+    type Target = CanonicalToken;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "safe")]
+impl DerefMut for TakenToken {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// A token yielded by an [`OwningIter`].
+#[cfg(not(feature = "safe"))]
+pub(crate) type IterToken<'a> = &'a mut CanonicalToken;
+/// A token yielded by an [`OwningIter`].
+#[cfg(feature = "safe")]
+pub(crate) type IterToken<'a> = TakenToken;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "serde_test")]
+    use super::{
+        from_serde_test,
+        FromSerdeTestError,
+    };
+    use super::{
+        labels,
+        CanonicalToken,
+        Element,
+        EnumTagging,
+        Labels,
+        Matcher,
+        OwningIter,
+        Replay,
+        Segment,
+        Token,
+        Tokens,
+        VariantId,
+    };
+    use crate::{
+        ser,
+        Serializer,
+    };
+    use alloc::{
+        borrow::ToOwned,
+        boxed::Box,
+        format,
+        vec,
+        vec::Vec,
+    };
+    use claims::{
+        assert_err,
+        assert_err_eq,
+        assert_matches,
+        assert_none,
+        assert_ok,
+        assert_ok_eq,
+        assert_some,
+        assert_some_eq,
+    };
+    use core::iter;
+    use serde::{
+        de::Unexpected,
+        ser::Error as _,
+        Serialize,
+    };
+    use serde_derive::Serialize;
+
+    #[cfg(feature = "serde_test")]
+    #[test]
+    fn from_serde_test_token() {
+        assert_eq!(
+            [
+                serde_test::Token::Bool(true),
+                serde_test::Token::Str("foo"),
+                serde_test::Token::ByteBuf(b"bar"),
+                serde_test::Token::UnitVariant {
+                    name: "Enum",
+                    variant: "Unit",
+                },
+            ]
+            .into_iter()
+            .map(|token| assert_ok!(Token::try_from(token)))
+            .collect::<Tokens>(),
+            [
+                Token::Bool(true),
+                Token::Str("foo".to_owned()),
+                Token::Bytes(b"bar".to_vec()),
+                Token::UnitVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Unit",
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde_test")]
+    #[test]
+    fn from_serde_test_token_enum() {
+        let error = assert_err!(Token::try_from(serde_test::Token::Enum { name: "Enum" }));
+
+        assert_eq!(error, FromSerdeTestError { name: "Enum" });
+        assert_eq!(error.name(), "Enum");
+        assert_eq!(
+            format!("{error}"),
+            "`serde_test::Token::Enum { name: \"Enum\" }` has no single-token equivalent"
+        );
+    }
+
+    #[cfg(feature = "serde_test")]
+    #[test]
+    fn from_serde_test_struct_fields() {
+        assert_eq!(
+            from_serde_test(&[
+                serde_test::Token::Struct {
+                    name: "Outer",
+                    len: 2,
+                },
+                serde_test::Token::Str("inner"),
+                serde_test::Token::Struct {
+                    name: "Inner",
+                    len: 1,
+                },
+                serde_test::Token::BorrowedStr("foo"),
+                serde_test::Token::Seq { len: Some(1) },
+                serde_test::Token::Str("bar"),
+                serde_test::Token::SeqEnd,
+                serde_test::Token::StructEnd,
+                serde_test::Token::Str("baz"),
+                serde_test::Token::Some,
+                serde_test::Token::Str("qux"),
+                serde_test::Token::StructEnd,
+            ])
+            .into_iter()
+            .collect::<Tokens>(),
+            [
+                Token::Struct {
+                    name: "Outer",
+                    len: 2,
+                },
+                Token::Field("inner"),
+                Token::Struct {
+                    name: "Inner",
+                    len: 1,
+                },
+                Token::Field("foo"),
+                Token::Seq { len: Some(1) },
+                Token::Str("bar".to_owned()),
+                Token::SeqEnd,
+                Token::StructEnd,
+                Token::Field("baz"),
+                Token::Some,
+                Token::Str("qux".to_owned()),
+                Token::StructEnd,
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde_test")]
+    #[test]
+    fn from_serde_test_map_keys() {
+        assert_eq!(
+            from_serde_test(&[
+                serde_test::Token::Map { len: Some(1) },
+                serde_test::Token::Str("foo"),
+                serde_test::Token::Str("bar"),
+                serde_test::Token::MapEnd,
+            ])
+            .into_iter()
+            .collect::<Tokens>(),
+            [
+                Token::Map { len: Some(1) },
+                Token::Str("foo".to_owned()),
+                Token::Str("bar".to_owned()),
+                Token::MapEnd,
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde_test")]
+    #[test]
+    fn from_serde_test_enum() {
+        assert_eq!(
+            from_serde_test(&[
+                serde_test::Token::Seq { len: Some(4) },
+                serde_test::Token::Enum { name: "Enum" },
+                serde_test::Token::Str("Unit"),
+                serde_test::Token::Unit,
+                serde_test::Token::Enum { name: "Enum" },
+                serde_test::Token::Str("Newtype"),
+                serde_test::Token::U8(1),
+                serde_test::Token::Enum { name: "Enum" },
+                serde_test::Token::Str("Tuple"),
+                serde_test::Token::Seq { len: Some(1) },
+                serde_test::Token::U8(2),
+                serde_test::Token::SeqEnd,
+                serde_test::Token::Enum { name: "Enum" },
+                serde_test::Token::Str("Struct"),
+                serde_test::Token::Map { len: Some(1) },
+                serde_test::Token::Str("foo"),
+                serde_test::Token::U8(3),
+                serde_test::Token::MapEnd,
+                serde_test::Token::SeqEnd,
+            ])
+            .into_iter()
+            .collect::<Tokens>(),
+            [
+                Token::Seq { len: Some(4) },
+                Token::UnitVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Unit",
+                },
+                Token::NewtypeVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Newtype",
+                },
+                Token::U8(1),
+                Token::TupleVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Tuple",
+                    len: 1,
+                },
+                Token::U8(2),
+                Token::TupleVariantEnd,
+                Token::StructVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Struct",
+                    len: 1,
+                },
+                Token::Field("foo"),
+                Token::U8(3),
+                Token::StructVariantEnd,
+                Token::SeqEnd,
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde_test")]
+    #[test]
+    fn from_serde_test_deserialize() {
+        #[derive(Debug, PartialEq, serde_derive::Deserialize)]
+        enum Enum {
+            Unit,
+            Struct { foo: u8 },
+        }
+
+        let mut deserializer = crate::Deserializer::builder(from_serde_test(&[
+            serde_test::Token::Seq { len: Some(2) },
+            serde_test::Token::Enum { name: "Enum" },
+            serde_test::Token::Str("Struct"),
+            serde_test::Token::Map { len: Some(1) },
+            serde_test::Token::Str("foo"),
+            serde_test::Token::U8(3),
+            serde_test::Token::MapEnd,
+            serde_test::Token::Enum { name: "Enum" },
+            serde_test::Token::Str("Unit"),
+            serde_test::Token::Unit,
+            serde_test::Token::SeqEnd,
+        ]))
+        .build();
+
+        assert_ok_eq!(
+            <Vec<Enum> as serde::Deserialize>::deserialize(&mut deserializer),
+            vec![Enum::Struct { foo: 3 }, Enum::Unit]
+        );
+    }
+
+    #[cfg(feature = "serde_test")]
+    #[test]
+    #[should_panic(expected = "expected variant name following `serde_test::Token::Enum")]
+    fn from_serde_test_enum_without_variant() {
+        let _ = from_serde_test(&[
+            serde_test::Token::Enum { name: "Enum" },
+            serde_test::Token::Unit,
+        ]);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn from_json_value_scalars() {
+        assert_eq!(
+            Tokens::from_json_value(&serde_json::json!([null, true, 1, -1, 1.5, "foo"])),
+            [
+                Token::Seq { len: Some(6) },
+                Token::Unit,
+                Token::Bool(true),
+                Token::U64(1),
+                Token::I64(-1),
+                Token::F64(1.5),
+                Token::Str("foo".to_owned()),
+                Token::SeqEnd,
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn from_json_value_nested() {
+        assert_eq!(
+            Tokens::from_json_value(&serde_json::json!({
+                "a": {"b": []},
+                "c": [{}, [1]],
+            })),
+            [
+                Token::Map { len: Some(2) },
+                Token::Str("a".to_owned()),
+                Token::Map { len: Some(1) },
+                Token::Str("b".to_owned()),
+                Token::Seq { len: Some(0) },
+                Token::SeqEnd,
+                Token::MapEnd,
+                Token::Str("c".to_owned()),
+                Token::Seq { len: Some(2) },
+                Token::Map { len: Some(0) },
+                Token::MapEnd,
+                Token::Seq { len: Some(1) },
+                Token::U64(1),
+                Token::SeqEnd,
+                Token::SeqEnd,
+                Token::MapEnd,
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn from_json_value_matches_serialized_value() {
+        let value = serde_json::json!({"a": [1, -2, 3.5], "b": {"c": null}, "d": "e"});
+
+        assert_eq!(
+            Tokens::from_json_value(&value),
+            assert_ok!(value.serialize(&Serializer::new()))
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn from_json_str() {
+        assert_ok_eq!(
+            Tokens::from_json_str(r#"{"foo": false}"#),
+            [
+                Token::Map { len: Some(1) },
+                Token::Str("foo".to_owned()),
+                Token::Bool(false),
+                Token::MapEnd,
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn from_json_str_invalid() {
+        assert_err!(Tokens::from_json_str("[true,"));
+    }
+
+    #[test]
+    fn tokens_macro_empty() {
+        assert_eq!(tokens![].into_iter().collect::<Tokens>(), []);
+    }
+
+    #[test]
+    fn tokens_macro_values() {
+        assert_eq!(
+            tokens![true, 1u8, 'a', "foo", Token::Unit,]
+                .into_iter()
+                .collect::<Tokens>(),
+            [
+                Token::Bool(true),
+                Token::U8(1),
+                Token::Char('a'),
+                Token::Str("foo".to_owned()),
+                Token::Unit,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_macro_some() {
+        assert_eq!(
+            tokens![Some(Some(42u32))].into_iter().collect::<Tokens>(),
+            [Token::Some, Token::Some, Token::U32(42)]
+        );
+    }
+
+    #[test]
+    fn tokens_macro_seq() {
+        assert_eq!(
+            tokens![Seq [1u8, Seq [], 2u8]]
+                .into_iter()
+                .collect::<Tokens>(),
+            [
+                Token::Seq { len: Some(3) },
+                Token::U8(1),
+                Token::Seq { len: Some(0) },
+                Token::SeqEnd,
+                Token::U8(2),
+                Token::SeqEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_macro_tuple() {
+        assert_eq!(
+            tokens![Tuple(1u8, true)].into_iter().collect::<Tokens>(),
+            [
+                Token::Tuple { len: 2 },
+                Token::U8(1),
+                Token::Bool(true),
+                Token::TupleEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_macro_map() {
+        assert_eq!(
+            tokens![Map {
+                "a" => 1u8,
+                Tuple (1u8, 2u8) => Seq [true],
+            }]
+            .into_iter()
+            .collect::<Tokens>(),
+            [
+                Token::Map { len: Some(2) },
+                Token::Str("a".to_owned()),
+                Token::U8(1),
+                Token::Tuple { len: 2 },
+                Token::U8(1),
+                Token::U8(2),
+                Token::TupleEnd,
+                Token::Seq { len: Some(1) },
+                Token::Bool(true),
+                Token::SeqEnd,
+                Token::MapEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_macro_structs() {
+        assert_eq!(
+            tokens![
+                NewtypeStruct("Newtype") (1u8),
+                TupleStruct("Tuple") (1u8, 2u8),
+                Struct("Struct") {
+                    "a" => 1u8,
+                    "b" => Struct("Inner") {},
+                },
+            ]
+            .into_iter()
+            .collect::<Tokens>(),
+            [
+                Token::NewtypeStruct { name: "Newtype" },
+                Token::U8(1),
+                Token::TupleStruct {
+                    name: "Tuple",
+                    len: 2,
+                },
+                Token::U8(1),
+                Token::U8(2),
+                Token::TupleStructEnd,
+                Token::Struct {
+                    name: "Struct",
+                    len: 2,
+                },
+                Token::Field("a"),
+                Token::U8(1),
+                Token::Field("b"),
+                Token::Struct {
+                    name: "Inner",
+                    len: 0,
+                },
+                Token::StructEnd,
+                Token::StructEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_macro_variants() {
+        assert_eq!(
+            tokens![
+                NewtypeVariant("Enum", 0, "Newtype") (1u8),
+                TupleVariant("Enum", 1, "Tuple") (1u8, 2u8),
+                StructVariant("Enum", 2, "Struct") { "a" => 1u8 },
+            ]
+            .into_iter()
+            .collect::<Tokens>(),
+            [
+                Token::NewtypeVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Newtype",
+                },
+                Token::U8(1),
+                Token::TupleVariant {
+                    name: "Enum",
+                    variant_index: 1,
+                    variant: "Tuple",
+                    len: 2,
+                },
+                Token::U8(1),
+                Token::U8(2),
+                Token::TupleVariantEnd,
+                Token::StructVariant {
+                    name: "Enum",
+                    variant_index: 2,
+                    variant: "Struct",
+                    len: 1,
+                },
+                Token::Field("a"),
+                Token::U8(1),
+                Token::StructVariantEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_bool_eq() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Bool(true)]),
+            [Token::Bool(true)]
+        );
+    }
+
+    #[test]
+    fn tokens_bool_ne() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Bool(true)]),
+            [Token::Bool(false)]
+        );
+    }
+
+    #[test]
+    fn tokens_variant_ne() {
+        assert_ne!(Tokens(vec![CanonicalToken::Bool(true)]), [Token::U16(42)]);
+    }
+
+    #[test]
+    fn tokens_empty_eq() {
+        assert_eq!(Tokens(vec![]), []);
+    }
+
+    #[test]
+    fn tokens_multiple_eq() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
+            [Token::Bool(true), Token::U8(42)]
+        );
+    }
+
+    #[test]
+    fn tokens_multiple_ne_values() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
+            [Token::Bool(false), Token::U8(42)]
+        );
+    }
+
+    #[test]
+    fn tokens_multiple_ne_shorter() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
+            [Token::Bool(true)]
+        );
+    }
+
+    #[test]
+    fn tokens_multiple_ne_longer() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
+            [Token::Bool(true), Token::U8(42), Token::U8(42)]
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_eq_same_order() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
+            [Token::Unordered(&[&[Token::Bool(true)], &[Token::U8(42)]])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_eq_many_distinct() {
+        let unordered = [Token::UnorderedOwned(
+            (0..1100u32)
+                .map(|i| vec![Token::U32(i), Token::Str(format!("{i}"))])
+                .collect(),
+        )];
+
+        assert_eq!(
+            Tokens(
+                (0..1100u32)
+                    .rev()
+                    .flat_map(|i| [CanonicalToken::U32(i), CanonicalToken::Str(format!("{i}"))])
+                    .collect()
+            ),
+            unordered,
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_ne_large() {
+        let unordered = [Token::UnorderedOwned(
+            (0..1100u32)
+                .map(|i| vec![Token::U32(i), Token::Bool(true)])
+                .collect(),
+        )];
+
+        assert_ne!(
+            Tokens(vec![CanonicalToken::U32(1100), CanonicalToken::Bool(true)]),
+            unordered,
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_duplicates_eq() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::U8(1),
+            ]),
+            [Token::Unordered(&[
+                &[Token::U8(1)],
+                &[Token::U8(1)],
+                &[Token::U8(2)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_duplicates_ne_fewer_actual() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::U8(1), CanonicalToken::U8(2)]),
+            [Token::Unordered(&[
+                &[Token::U8(1)],
+                &[Token::U8(1)],
+                &[Token::U8(2)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_duplicates_ne_more_actual() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::U8(1), CanonicalToken::U8(1)]),
+            [Token::Unordered(&[&[Token::U8(1)], &[Token::U8(2)]])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_duplicates_ne_different_multiplicity() {
+        assert_ne!(
+            Tokens(vec![
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::U8(2),
+            ]),
+            [Token::Unordered(&[
+                &[Token::U8(1)],
+                &[Token::U8(1)],
+                &[Token::U8(2)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_duplicates_overlapping_prefix() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+            ]),
+            [Token::Unordered(&[
+                &[Token::U8(1), Token::U8(2)],
+                &[Token::U8(1)],
+                &[Token::U8(1), Token::U8(2)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_many_duplicates() {
+        // Every ordering of identical groups is equivalent, so this must not search all of them.
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Unit; 16]),
+            [Token::Unordered(&[&[Token::Unit] as &[Token]; 16])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_eq_different_order() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::U8(42), CanonicalToken::Bool(true)]),
+            [Token::Unordered(&[&[Token::Bool(true)], &[Token::U8(42)]])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_eq_within_other_tokens() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::Char('a'),
+                CanonicalToken::U8(42),
+                CanonicalToken::Bool(true),
+                CanonicalToken::I16(-42)
+            ]),
+            [
+                Token::Char('a'),
+                Token::Unordered(&[&[Token::Bool(true)], &[Token::U8(42)]]),
+                Token::I16(-42)
+            ],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_eq_multiple_tokens() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::U8(42),
+                CanonicalToken::Bool(true),
+                CanonicalToken::Char('a')
+            ]),
+            [Token::Unordered(&[
+                &[Token::Bool(true), Token::Char('a')],
+                &[Token::U8(42)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_ne_empty() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Bool(true)]),
+            [Token::Unordered(&[])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_ne_variant() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Bool(true)]),
+            [Token::Unordered(&[&[Token::I8(42)]])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_ne_value() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Bool(true)]),
+            [Token::Unordered(&[&[Token::Bool(false)]])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_nested() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::Unit,
+                CanonicalToken::U8(4),
+                CanonicalToken::U8(3),
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::Bool(true)
+            ]),
+            [Token::Unordered(&[
+                &[Token::Bool(true)],
+                &[Token::Unordered(&[
+                    &[Token::U8(1), Token::U8(2)],
+                    &[Token::U8(3)],
+                ])],
+                &[Token::Unit, Token::U8(4)],
+            ])]
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_empty() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Unit,]),
+            [Token::Unordered(&[]), Token::Unit]
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_empty_nested() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Unit,]),
+            [Token::Unordered(&[&[Token::Unordered(&[])]]), Token::Unit]
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_empty_at_end() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Unit,]),
+            [Token::Unit, Token::Unordered(&[])]
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_nonempty_at_end() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Unit,]),
+            [Token::Unit, Token::Unordered(&[&[Token::Unit]])]
+        );
+    }
+
+    #[test]
+    fn tokens_end_within_unordered() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Unit,]),
+            [Token::Unordered(&[&[Token::Unit,], &[Token::Unit]])]
+        );
+    }
+
+    #[test]
+    fn tokens_end_within_unordered_more_tokens() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Unit,]),
+            [Token::Unordered(&[&[Token::Unit, Token::Unit]])]
+        );
+    }
+
+    #[test]
+    fn tokens_end_within_unordered_nested_empty() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Unit,]),
+            [Token::Unordered(&[&[Token::Unit, Token::Unordered(&[])]])]
+        );
+    }
+
+    #[test]
+    fn tokens_end_within_unordered_nested_nonempty() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Unit,]),
+            [Token::Unordered(&[&[
+                Token::Unit,
+                Token::Unordered(&[&[Token::Unit, Token::Unit], &[Token::Unit]])
+            ]])]
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_owned_eq_different_order() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::U8(42), CanonicalToken::Bool(true)]),
+            [Token::UnorderedOwned(vec![
+                vec![Token::Bool(true)],
+                vec![Token::U8(42)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_owned_ne_value() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::U8(42), CanonicalToken::Bool(true)]),
+            [Token::UnorderedOwned(vec![
+                vec![Token::Bool(false)],
+                vec![Token::U8(42)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_owned_duplicates_eq() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::U8(1),
+            ]),
+            [Token::UnorderedOwned(vec![
+                vec![Token::U8(1)],
+                vec![Token::U8(1)],
+                vec![Token::U8(2)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_owned_duplicates_ne_fewer_actual() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::U8(1), CanonicalToken::U8(2)]),
+            [Token::UnorderedOwned(vec![
+                vec![Token::U8(1)],
+                vec![Token::U8(1)],
+                vec![Token::U8(2)]
+            ])],
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_owned_nested() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::Unit,
+                CanonicalToken::U8(4),
+                CanonicalToken::U8(3),
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::Bool(true)
+            ]),
+            [Token::UnorderedOwned(vec![
+                vec![Token::Bool(true)],
+                vec![Token::Unordered(&[
+                    &[Token::U8(1), Token::U8(2)],
+                    &[Token::U8(3)],
+                ])],
+                vec![Token::Unit, Token::UnorderedOwned(vec![vec![Token::U8(4)]])],
+            ])]
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_owned_generated() {
+        let tokens = (0..100u32)
+            .rev()
+            .flat_map(|i| [CanonicalToken::U32(i), CanonicalToken::Bool(i % 2 == 0)])
+            .collect();
+
+        assert_eq!(
+            Tokens(tokens),
+            [Token::UnorderedOwned(
+                (0..100u32)
+                    .map(|i| vec![Token::U32(i), Token::Bool(i % 2 == 0)])
+                    .collect()
+            )]
+        );
+    }
+
+    #[test]
+    fn tokens_unordered_owned_empty() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Unit]),
+            [Token::Unit, Token::UnorderedOwned(Vec::new())]
+        );
+    }
+
+    #[test]
+    fn tokens_labeled_eq() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
+            [
+                Token::labeled("flag", Token::Bool(true)),
+                Token::labeled("outer", Token::labeled("inner", Token::U8(42)))
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_labeled_ne() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Bool(true)]),
+            [Token::labeled("flag", Token::Bool(false))]
+        );
+    }
+
+    #[test]
+    fn labels_indices() {
+        assert_eq!(
+            labels(&[
+                Token::Seq { len: Some(2) },
+                Token::labeled("first", Token::U8(1)),
+                Token::U8(2),
+                Token::labeled("end", Token::SeqEnd),
+            ]),
+            Labels::from([(1, "first"), (3, "end")])
+        );
+    }
+
+    #[test]
+    fn labels_outermost_wins() {
+        assert_eq!(
+            labels(&[Token::labeled(
+                "outer",
+                Token::labeled("inner", Token::Unit)
+            )]),
+            Labels::from([(0, "outer")])
+        );
+    }
+
+    #[test]
+    fn labels_unordered() {
+        assert_eq!(
+            labels(&[
+                Token::Unit,
+                Token::labeled(
+                    "groups",
+                    Token::Unordered(&[&[Token::U8(1)], &[Token::U8(2), Token::U8(3)]])
+                ),
+                Token::Unit,
+            ]),
+            Labels::from([(1, "groups"), (2, "groups"), (3, "groups")])
+        );
+    }
+
+    #[test]
+    fn labels_unordered_owned() {
+        assert_eq!(
+            labels(&[
+                Token::Unit,
+                Token::labeled(
+                    "groups",
+                    Token::UnorderedOwned(vec![vec![Token::U8(1)], vec![Token::U8(2)]])
+                ),
+            ]),
+            Labels::from([(1, "groups"), (2, "groups")])
+        );
+    }
+
+    #[test]
+    fn tokens_group_eq_same_order() {
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
+            [
+                Token::Group(&[Token::Bool(true)]),
+                Token::Group(&[Token::U8(42)])
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_group_eq_different_order() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::Map { len: Some(2) },
+                CanonicalToken::Char('b'),
+                CanonicalToken::U8(2),
+                CanonicalToken::Char('a'),
+                CanonicalToken::U8(1),
+                CanonicalToken::MapEnd,
+            ]),
+            [
+                Token::Map { len: Some(2) },
+                Token::Group(&[Token::Char('a'), Token::U8(1)]),
+                Token::Group(&[Token::Char('b'), Token::U8(2)]),
+                Token::MapEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_group_ne_within_group_order() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::U8(1), CanonicalToken::Char('a')]),
+            [Token::Group(&[Token::Char('a'), Token::U8(1)])]
+        );
+    }
+
+    #[test]
+    fn tokens_group_ne_separated() {
+        assert_ne!(
+            Tokens(vec![
+                CanonicalToken::U8(2),
+                CanonicalToken::Unit,
+                CanonicalToken::U8(1),
+            ]),
+            [
+                Token::Group(&[Token::U8(1)]),
+                Token::Unit,
+                Token::Group(&[Token::U8(2)])
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_group_nested() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::U8(3),
+                CanonicalToken::Bool(true),
+                CanonicalToken::U8(2),
+                CanonicalToken::U8(1),
+            ]),
+            [
+                Token::Group(&[Token::Bool(true)]),
+                Token::Group(&[Token::Group(&[Token::U8(1)]), Token::Group(&[Token::U8(2)])]),
+                Token::Group(&[Token::U8(3)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_group_within_unordered() {
+        assert_eq!(
+            Tokens(vec![
+                CanonicalToken::U8(2),
+                CanonicalToken::U8(1),
+                CanonicalToken::Unit,
+            ]),
+            [Token::Unordered(&[&[
+                Token::Group(&[Token::U8(1)]),
+                Token::Group(&[Token::U8(2)]),
+                Token::Unit
+            ]])]
+        );
+    }
+
+    #[test]
+    fn token_from_value() {
+        assert_ok_eq!(
+            Token::from_value(&(1u8, "foo")),
+            [
+                Token::Tuple { len: 2 },
+                Token::U8(1),
+                Token::Str("foo".to_owned()),
+                Token::TupleEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn canonical_token_size() {
+        assert!(core::mem::size_of::<CanonicalToken>() <= 32);
+    }
+
+    #[test]
+    fn token_from_canonical_token_bool() {
+        assert_matches!(Token::from(CanonicalToken::Bool(true)), Token::Bool(true));
+    }
+
+    #[test]
+    fn token_from_canonical_token_i8() {
+        assert_matches!(Token::from(CanonicalToken::I8(42)), Token::I8(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_i16() {
+        assert_matches!(Token::from(CanonicalToken::I16(42)), Token::I16(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_i32() {
+        assert_matches!(Token::from(CanonicalToken::I32(42)), Token::I32(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_i64() {
+        assert_matches!(Token::from(CanonicalToken::I64(42)), Token::I64(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_i128() {
+        assert_matches!(Token::from(CanonicalToken::I128(42)), Token::I128(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_u8() {
+        assert_matches!(Token::from(CanonicalToken::U8(42)), Token::U8(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_u16() {
+        assert_matches!(Token::from(CanonicalToken::U16(42)), Token::U16(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_u32() {
+        assert_matches!(Token::from(CanonicalToken::U32(42)), Token::U32(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_u64() {
+        assert_matches!(Token::from(CanonicalToken::U64(42)), Token::U64(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_u128() {
+        assert_matches!(Token::from(CanonicalToken::U128(42)), Token::U128(42));
+    }
+
+    #[test]
+    fn token_from_canonical_token_f32() {
+        assert_matches!(Token::from(CanonicalToken::F32(42.9)), Token::F32(_));
+    }
+
+    #[test]
+    fn token_from_canonical_token_f64() {
+        assert_matches!(Token::from(CanonicalToken::F64(42.9)), Token::F64(_));
+    }
+
+    #[test]
+    fn token_from_canonical_token_char() {
+        assert_matches!(Token::from(CanonicalToken::Char('a')), Token::Char('a'));
+    }
+
+    #[test]
+    fn token_from_canonical_token_str() {
+        assert_matches!(
+            Token::from(CanonicalToken::Str("foo".to_owned())),
+            Token::Str(_)
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_bytes() {
+        assert_matches!(
+            Token::from(CanonicalToken::Bytes(b"foo".to_vec())),
+            Token::Bytes(_)
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_none() {
+        assert_matches!(Token::from(CanonicalToken::None), Token::None);
+    }
+
+    #[test]
+    fn token_from_canonical_token_some() {
+        assert_matches!(Token::from(CanonicalToken::Some), Token::Some);
+    }
+
+    #[test]
+    fn token_from_canonical_token_unit() {
+        assert_matches!(Token::from(CanonicalToken::Unit), Token::Unit);
+    }
+
+    #[test]
+    fn token_from_canonical_token_unit_struct() {
+        assert_matches!(
+            Token::from(CanonicalToken::UnitStruct { name: "foo" }),
+            Token::UnitStruct { name: "foo" }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_unit_variant() {
+        assert_matches!(
+            Token::from(CanonicalToken::UnitVariant {
+                id: Box::new(VariantId {
+                    name: "foo",
+                    variant_index: 42,
+                    variant: "bar"
+                })
+            }),
+            Token::UnitVariant {
+                name: "foo",
+                variant_index: 42,
+                variant: "bar"
+            }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_newtype_struct() {
+        assert_matches!(
+            Token::from(CanonicalToken::NewtypeStruct { name: "foo" }),
+            Token::NewtypeStruct { name: "foo" }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_newtype_variant() {
+        assert_matches!(
+            Token::from(CanonicalToken::NewtypeVariant {
+                id: Box::new(VariantId {
+                    name: "foo",
+                    variant_index: 42,
+                    variant: "bar"
+                })
+            }),
+            Token::NewtypeVariant {
+                name: "foo",
+                variant_index: 42,
+                variant: "bar"
+            }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_seq() {
+        assert_matches!(
+            Token::from(CanonicalToken::Seq { len: Some(42) }),
+            Token::Seq { len: Some(42) }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_seq_end() {
+        assert_matches!(Token::from(CanonicalToken::SeqEnd), Token::SeqEnd);
+    }
+
+    #[test]
+    fn token_from_canonical_token_tuple() {
+        assert_matches!(
+            Token::from(CanonicalToken::Tuple { len: 42 }),
+            Token::Tuple { len: 42 }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_tuple_end() {
+        assert_matches!(Token::from(CanonicalToken::TupleEnd), Token::TupleEnd);
+    }
+
+    #[test]
+    fn token_from_canonical_token_tuple_struct() {
+        assert_matches!(
+            Token::from(CanonicalToken::TupleStruct {
+                name: "foo",
+                len: 42
+            }),
+            Token::TupleStruct {
+                name: "foo",
+                len: 42
+            }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_tuple_struct_end() {
+        assert_matches!(
+            Token::from(CanonicalToken::TupleStructEnd),
+            Token::TupleStructEnd
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_tuple_variant() {
+        assert_matches!(
+            Token::from(CanonicalToken::TupleVariant {
+                id: Box::new(VariantId {
+                    name: "foo",
+                    variant_index: 42,
+                    variant: "bar"
+                }),
+                len: 42
+            }),
+            Token::TupleVariant {
+                name: "foo",
+                variant_index: 42,
+                variant: "bar",
+                len: 42
+            }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_tuple_variant_end() {
+        assert_matches!(
+            Token::from(CanonicalToken::TupleVariantEnd),
+            Token::TupleVariantEnd
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_map() {
+        assert_matches!(
+            Token::from(CanonicalToken::Map { len: Some(42) }),
+            Token::Map { len: Some(42) }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_map_end() {
+        assert_matches!(Token::from(CanonicalToken::MapEnd), Token::MapEnd);
+    }
+
+    #[test]
+    fn token_from_canonical_token_field() {
+        assert_matches!(
+            Token::from(CanonicalToken::Field("foo")),
+            Token::Field("foo")
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_skipped_field() {
+        assert_matches!(
+            Token::from(CanonicalToken::SkippedField("foo")),
+            Token::SkippedField("foo")
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_struct() {
+        assert_matches!(
+            Token::from(CanonicalToken::Struct {
+                name: "foo",
+                len: 42
+            }),
+            Token::Struct {
+                name: "foo",
+                len: 42
+            }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_struct_end() {
+        assert_matches!(Token::from(CanonicalToken::StructEnd), Token::StructEnd);
+    }
+
+    #[test]
+    fn token_from_canonical_token_struct_variant() {
+        assert_matches!(
+            Token::from(CanonicalToken::StructVariant {
+                id: Box::new(VariantId {
+                    name: "foo",
+                    variant_index: 42,
+                    variant: "bar"
+                }),
+                len: 42
+            }),
+            Token::StructVariant {
+                name: "foo",
+                variant_index: 42,
+                variant: "bar",
+                len: 42
+            }
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_struct_variant_end() {
+        assert_matches!(
+            Token::from(CanonicalToken::StructVariantEnd),
+            Token::StructVariantEnd
+        );
+    }
+
+    #[test]
+    fn token_from_canonical_token_error() {
+        assert_matches!(
+            Token::from(CanonicalToken::Error("foo".to_owned())),
+            Token::Error(message) if message == "foo"
+        );
+    }
+
+    #[test]
+    fn unexpected_from_canonical_token_bool() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::Bool(true)),
+            Unexpected::Bool(true)
+        );
+    }
 
-    /// Returns the remaining `Token`s as a slice.
-    fn as_slice(&self) -> &[CanonicalToken] {
-        // SAFETY: `self.ptr` is guaranteed to be less than `self.end`, and therefore a valid
-        // pointer within the allocated object.
-        unsafe {
-            slice::from_raw_parts(
-                self.ptr,
-                #[allow(clippy::cast_sign_loss)]
-                {
-                    self.end.offset_from(self.ptr) as usize
-                },
-            )
-        }
+    #[test]
+    fn unexpected_from_canonical_token_i8() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::I8(42)),
+            Unexpected::Signed(42)
+        );
     }
-}
 
-impl<'a> Iterator for OwningIter<'a> {
-    type Item = &'a mut CanonicalToken;
+    #[test]
+    fn unexpected_from_canonical_token_i16() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::I16(42)),
+            Unexpected::Signed(42)
+        );
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.ptr == self.end {
-            None
-        } else {
-            let current = self.ptr;
-            // SAFETY: Since `self.ptr` is not equal to `self.end`, it must be before it, and
-            // therefore incrementing by 1 will also result in a valid pointer within the allocated
-            // object, or 1 byte past the end if the iteration has completed.
-            self.ptr = unsafe { self.ptr.add(1) };
-            // SAFETY: The pointed-at object is guaranteed to be a valid `Token` that will live for
-            // the lifetime `'a`.
-            Some(unsafe { &mut *current })
-        }
+    #[test]
+    fn unexpected_from_canonical_token_i32() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::I32(42)),
+            Unexpected::Signed(42)
+        );
     }
-}
 
-impl Debug for OwningIter<'_> {
-    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-        formatter
-            .debug_tuple("OwningIter")
-            .field(&self.as_slice())
-            .finish()
+    #[test]
+    fn unexpected_from_canonical_token_i64() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::I64(42)),
+            Unexpected::Signed(42)
+        );
     }
-}
 
-impl Drop for OwningIter<'_> {
-    fn drop(&mut self) {
-        // SAFETY: The raw parts stored in this struct are guaranteed to correspond to the valid
-        // parts of a `Vec`, since the parts were obtained directly from a `Vec` originally.
-        unsafe {
-            Vec::from_raw_parts(
-                self.buf.as_ptr(),
-                #[allow(clippy::cast_sign_loss)]
-                {
-                    self.end.offset_from(self.buf.as_ptr()) as usize
-                },
-                self.cap,
-            )
-        };
+    #[test]
+    fn unexpected_from_canonical_token_i128() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::I128(42)),
+            Unexpected::Other("i128")
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::{
-        CanonicalToken,
-        OwningIter,
-        Token,
-        Tokens,
-    };
-    use alloc::{
-        borrow::ToOwned,
-        format,
-        vec,
-        vec::Vec,
-    };
-    use claims::{
-        assert_matches,
-        assert_none,
-        assert_some,
-        assert_some_eq,
-    };
-    use serde::de::Unexpected;
+    #[test]
+    fn unexpected_from_canonical_token_u8() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::U8(42)),
+            Unexpected::Unsigned(42)
+        );
+    }
 
     #[test]
-    fn tokens_bool_eq() {
+    fn unexpected_from_canonical_token_u16() {
         assert_eq!(
-            Tokens(vec![CanonicalToken::Bool(true)]),
-            [Token::Bool(true)]
+            Unexpected::from(&mut CanonicalToken::U16(42)),
+            Unexpected::Unsigned(42)
         );
     }
 
     #[test]
-    fn tokens_bool_ne() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Bool(true)]),
-            [Token::Bool(false)]
+    fn unexpected_from_canonical_token_u32() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::U32(42)),
+            Unexpected::Unsigned(42)
         );
     }
 
     #[test]
-    fn tokens_variant_ne() {
-        assert_ne!(Tokens(vec![CanonicalToken::Bool(true)]), [Token::U16(42)]);
+    fn unexpected_from_canonical_token_u64() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::U64(42)),
+            Unexpected::Unsigned(42)
+        );
     }
 
     #[test]
-    fn tokens_empty_eq() {
-        assert_eq!(Tokens(vec![]), []);
+    fn unexpected_from_canonical_token_u128() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::U128(42)),
+            Unexpected::Other("u128")
+        );
     }
 
     #[test]
-    fn tokens_multiple_eq() {
+    fn unexpected_from_canonical_token_f32() {
         assert_eq!(
-            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
-            [Token::Bool(true), Token::U8(42)]
+            Unexpected::from(&mut CanonicalToken::F32(42.)),
+            Unexpected::Float(42.)
         );
     }
 
     #[test]
-    fn tokens_multiple_ne_values() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
-            [Token::Bool(false), Token::U8(42)]
+    fn unexpected_from_canonical_token_f64() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::F64(42.)),
+            Unexpected::Float(42.)
         );
     }
 
     #[test]
-    fn tokens_multiple_ne_shorter() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
-            [Token::Bool(true)]
+    fn unexpected_from_canonical_token_char() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::Char('a')),
+            Unexpected::Char('a')
         );
     }
 
     #[test]
-    fn tokens_multiple_ne_longer() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
-            [Token::Bool(true), Token::U8(42), Token::U8(42)]
+    fn unexpected_from_canonical_token_str() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::Str("foo".to_owned())),
+            Unexpected::Str("foo")
         );
     }
 
     #[test]
-    fn tokens_unordered_eq_same_order() {
+    fn unexpected_from_canonical_token_bytes() {
         assert_eq!(
-            Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::U8(42)]),
-            [Token::Unordered(&[&[Token::Bool(true)], &[Token::U8(42)]])],
+            Unexpected::from(&mut CanonicalToken::Bytes(b"foo".to_vec())),
+            Unexpected::Bytes(b"foo")
         );
     }
 
     #[test]
-    fn tokens_unordered_eq_different_order() {
+    fn unexpected_from_canonical_token_some() {
         assert_eq!(
-            Tokens(vec![CanonicalToken::U8(42), CanonicalToken::Bool(true)]),
-            [Token::Unordered(&[&[Token::Bool(true)], &[Token::U8(42)]])],
+            Unexpected::from(&mut CanonicalToken::Some),
+            Unexpected::Option
         );
     }
 
     #[test]
-    fn tokens_unordered_eq_within_other_tokens() {
+    fn unexpected_from_canonical_token_none() {
         assert_eq!(
-            Tokens(vec![
-                CanonicalToken::Char('a'),
-                CanonicalToken::U8(42),
-                CanonicalToken::Bool(true),
-                CanonicalToken::I16(-42)
-            ]),
-            [
-                Token::Char('a'),
-                Token::Unordered(&[&[Token::Bool(true)], &[Token::U8(42)]]),
-                Token::I16(-42)
-            ],
+            Unexpected::from(&mut CanonicalToken::None),
+            Unexpected::Option
         );
     }
 
     #[test]
-    fn tokens_unordered_eq_multiple_tokens() {
+    fn unexpected_from_canonical_token_unit() {
         assert_eq!(
-            Tokens(vec![
-                CanonicalToken::U8(42),
-                CanonicalToken::Bool(true),
-                CanonicalToken::Char('a')
-            ]),
-            [Token::Unordered(&[
-                &[Token::Bool(true), Token::Char('a')],
-                &[Token::U8(42)]
-            ])],
+            Unexpected::from(&mut CanonicalToken::Unit),
+            Unexpected::Unit
         );
     }
 
     #[test]
-    fn tokens_unordered_ne_empty() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Bool(true)]),
-            [Token::Unordered(&[])],
+    fn unexpected_from_canonical_token_unit_struct() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::UnitStruct { name: "foo" }),
+            Unexpected::Unit
         );
     }
 
     #[test]
-    fn tokens_unordered_ne_variant() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Bool(true)]),
-            [Token::Unordered(&[&[Token::I8(42)]])],
+    fn unexpected_from_canonical_token_unit_variant() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::UnitVariant {
+                id: Box::new(VariantId {
+                    name: "foo",
+                    variant_index: 0,
+                    variant: "bar"
+                })
+            }),
+            Unexpected::UnitVariant
         );
     }
 
     #[test]
-    fn tokens_unordered_ne_value() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Bool(true)]),
-            [Token::Unordered(&[&[Token::Bool(false)]])],
+    fn unexpected_from_canonical_token_newtype_struct() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::NewtypeStruct { name: "foo" }),
+            Unexpected::NewtypeStruct
         );
     }
 
     #[test]
-    fn tokens_unordered_nested() {
+    fn unexpected_from_canonical_token_newtype_variant() {
         assert_eq!(
-            Tokens(vec![
-                CanonicalToken::Unit,
-                CanonicalToken::U8(4),
-                CanonicalToken::U8(3),
-                CanonicalToken::U8(1),
-                CanonicalToken::U8(2),
-                CanonicalToken::Bool(true)
-            ]),
-            [Token::Unordered(&[
-                &[Token::Bool(true)],
-                &[Token::Unordered(&[
-                    &[Token::U8(1), Token::U8(2)],
-                    &[Token::U8(3)],
-                ])],
-                &[Token::Unit, Token::U8(4)],
-            ])]
+            Unexpected::from(&mut CanonicalToken::NewtypeVariant {
+                id: Box::new(VariantId {
+                    name: "foo",
+                    variant_index: 0,
+                    variant: "bar"
+                })
+            }),
+            Unexpected::NewtypeVariant
         );
     }
 
     #[test]
-    fn tokens_unordered_empty() {
+    fn unexpected_from_canonical_token_seq() {
         assert_eq!(
-            Tokens(vec![CanonicalToken::Unit,]),
-            [Token::Unordered(&[]), Token::Unit]
+            Unexpected::from(&mut CanonicalToken::Seq { len: None }),
+            Unexpected::Seq
         );
     }
 
     #[test]
-    fn tokens_unordered_empty_nested() {
+    fn unexpected_from_canonical_token_tuple() {
         assert_eq!(
-            Tokens(vec![CanonicalToken::Unit,]),
-            [Token::Unordered(&[&[Token::Unordered(&[])]]), Token::Unit]
+            Unexpected::from(&mut CanonicalToken::Tuple { len: 0 }),
+            Unexpected::Seq
         );
     }
 
     #[test]
-    fn tokens_unordered_empty_at_end() {
+    fn unexpected_from_canonical_token_seq_end() {
         assert_eq!(
-            Tokens(vec![CanonicalToken::Unit,]),
-            [Token::Unit, Token::Unordered(&[])]
+            Unexpected::from(&mut CanonicalToken::SeqEnd),
+            Unexpected::Other("SeqEnd")
         );
     }
 
     #[test]
-    fn tokens_unordered_nonempty_at_end() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Unit,]),
-            [Token::Unit, Token::Unordered(&[&[Token::Unit]])]
+    fn unexpected_from_canonical_token_tuple_end() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::TupleEnd),
+            Unexpected::Other("TupleEnd")
         );
     }
 
     #[test]
-    fn tokens_end_within_unordered() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Unit,]),
-            [Token::Unordered(&[&[Token::Unit,], &[Token::Unit]])]
+    fn unexpected_from_canonical_token_tuple_struct() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::TupleStruct {
+                name: "foo",
+                len: 0
+            }),
+            Unexpected::Other("TupleStruct")
         );
     }
 
     #[test]
-    fn tokens_end_within_unordered_more_tokens() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Unit,]),
-            [Token::Unordered(&[&[Token::Unit, Token::Unit]])]
+    fn unexpected_from_canonical_token_tuple_struct_end() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::TupleStructEnd),
+            Unexpected::Other("TupleStructEnd")
         );
     }
 
     #[test]
-    fn tokens_end_within_unordered_nested_empty() {
+    fn unexpected_from_canonical_token_tuple_variant() {
         assert_eq!(
-            Tokens(vec![CanonicalToken::Unit,]),
-            [Token::Unordered(&[&[Token::Unit, Token::Unordered(&[])]])]
+            Unexpected::from(&mut CanonicalToken::TupleVariant {
+                id: Box::new(VariantId {
+                    name: "foo",
+                    variant_index: 0,
+                    variant: "bar"
+                }),
+                len: 0
+            }),
+            Unexpected::TupleVariant
         );
     }
 
     #[test]
-    fn tokens_end_within_unordered_nested_nonempty() {
-        assert_ne!(
-            Tokens(vec![CanonicalToken::Unit,]),
-            [Token::Unordered(&[&[
-                Token::Unit,
-                Token::Unordered(&[&[Token::Unit, Token::Unit], &[Token::Unit]])
-            ]])]
+    fn unexpected_from_canonical_token_tuple_variant_end() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::TupleVariantEnd),
+            Unexpected::Other("TupleVariantEnd")
         );
     }
 
     #[test]
-    fn token_from_canonical_token_bool() {
-        assert_matches!(Token::from(CanonicalToken::Bool(true)), Token::Bool(true));
+    fn unexpected_from_canonical_token_map() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::Map { len: None }),
+            Unexpected::Map
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_i8() {
-        assert_matches!(Token::from(CanonicalToken::I8(42)), Token::I8(42));
+    fn unexpected_from_canonical_token_map_end() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::MapEnd),
+            Unexpected::Other("MapEnd")
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_i16() {
-        assert_matches!(Token::from(CanonicalToken::I16(42)), Token::I16(42));
+    fn unexpected_from_canonical_token_field() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::Field("foo")),
+            Unexpected::Other("Field")
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_i32() {
-        assert_matches!(Token::from(CanonicalToken::I32(42)), Token::I32(42));
+    fn unexpected_from_canonical_token_skipped_field() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::SkippedField("foo")),
+            Unexpected::Other("SkippedField")
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_i64() {
-        assert_matches!(Token::from(CanonicalToken::I64(42)), Token::I64(42));
+    fn unexpected_from_canonical_token_struct() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::Struct {
+                name: "foo",
+                len: 0
+            }),
+            Unexpected::Other("Struct")
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_i128() {
-        assert_matches!(Token::from(CanonicalToken::I128(42)), Token::I128(42));
+    fn unexpected_from_canonical_token_struct_end() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::StructEnd),
+            Unexpected::Other("StructEnd")
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_u8() {
-        assert_matches!(Token::from(CanonicalToken::U8(42)), Token::U8(42));
+    fn unexpected_from_canonical_token_struct_variant() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::StructVariant {
+                id: Box::new(VariantId {
+                    name: "foo",
+                    variant_index: 0,
+                    variant: "bar"
+                }),
+                len: 0
+            }),
+            Unexpected::StructVariant
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_u16() {
-        assert_matches!(Token::from(CanonicalToken::U16(42)), Token::U16(42));
+    fn unexpected_from_canonical_token_struct_variant_end() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::StructVariantEnd),
+            Unexpected::Other("StructVariantEnd")
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_u32() {
-        assert_matches!(Token::from(CanonicalToken::U32(42)), Token::U32(42));
+    fn unexpected_from_canonical_token_error() {
+        assert_eq!(
+            Unexpected::from(&mut CanonicalToken::Error("foo".to_owned())),
+            Unexpected::Other("Error")
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_u64() {
-        assert_matches!(Token::from(CanonicalToken::U64(42)), Token::U64(42));
-    }
+    fn owning_iter_empty() {
+        let mut iter = OwningIter::new(Tokens(Vec::new()));
 
-    #[test]
-    fn token_from_canonical_token_u128() {
-        assert_matches!(Token::from(CanonicalToken::U128(42)), Token::U128(42));
+        assert_none!(iter.next());
     }
 
     #[test]
-    fn token_from_canonical_token_f32() {
-        assert_matches!(Token::from(CanonicalToken::F32(42.9)), Token::F32(_));
+    fn owning_iter_one_token() {
+        let mut iter = OwningIter::new(Tokens(vec![CanonicalToken::Bool(true)]));
+
+        assert_some_eq!(iter.next().as_deref(), &CanonicalToken::Bool(true));
+        assert_none!(iter.next());
     }
 
     #[test]
-    fn token_from_canonical_token_f64() {
-        assert_matches!(Token::from(CanonicalToken::F64(42.9)), Token::F64(_));
+    fn owning_iter_multiple_tokens() {
+        let mut iter = OwningIter::new(Tokens(vec![
+            CanonicalToken::Bool(true),
+            CanonicalToken::U64(42),
+            CanonicalToken::Str("foo".to_owned()),
+        ]));
+
+        assert_some_eq!(iter.next().as_deref(), &CanonicalToken::Bool(true));
+        assert_some_eq!(iter.next().as_deref(), &CanonicalToken::U64(42));
+        assert_some_eq!(
+            iter.next().as_deref(),
+            &CanonicalToken::Str("foo".to_owned())
+        );
+        assert_none!(iter.next());
     }
 
     #[test]
-    fn token_from_canonical_token_char() {
-        assert_matches!(Token::from(CanonicalToken::Char('a')), Token::Char('a'));
+    fn owning_iter_empty_debug() {
+        let iter = OwningIter::new(Tokens(Vec::new()));
+
+        assert_eq!(format!("{iter:?}"), "OwningIter([])");
     }
 
     #[test]
-    fn token_from_canonical_token_str() {
-        assert_matches!(
-            Token::from(CanonicalToken::Str("foo".to_owned())),
-            Token::Str(_)
+    fn owning_iter_uniterated_debug() {
+        let iter = OwningIter::new(Tokens(vec![
+            CanonicalToken::Bool(true),
+            CanonicalToken::U64(42),
+            CanonicalToken::Str("foo".to_owned()),
+        ]));
+
+        assert_eq!(
+            format!("{iter:?}"),
+            "OwningIter([Bool(true), U64(42), Str(\"foo\")])"
         );
     }
 
     #[test]
-    fn token_from_canonical_token_bytes() {
-        assert_matches!(
-            Token::from(CanonicalToken::Bytes(b"foo".to_vec())),
-            Token::Bytes(_)
-        );
+    fn owning_iter_partially_iterated_debug() {
+        let mut iter = OwningIter::new(Tokens(vec![
+            CanonicalToken::Bool(true),
+            CanonicalToken::U64(42),
+            CanonicalToken::Str("foo".to_owned()),
+        ]));
+
+        assert_some!(iter.next());
+
+        assert_eq!(format!("{iter:?}"), "OwningIter([U64(42), Str(\"foo\")])");
     }
 
     #[test]
-    fn token_from_canonical_token_none() {
-        assert_matches!(Token::from(CanonicalToken::None), Token::None);
+    fn owning_iter_fully_iterated_debug() {
+        let mut iter = OwningIter::new(Tokens(vec![
+            CanonicalToken::Bool(true),
+            CanonicalToken::U64(42),
+            CanonicalToken::Str("foo".to_owned()),
+        ]));
+
+        assert_some!(iter.next());
+        assert_some!(iter.next());
+        assert_some!(iter.next());
+
+        assert_eq!(format!("{iter:?}"), "OwningIter([])");
     }
 
     #[test]
-    fn token_from_canonical_token_some() {
-        assert_matches!(Token::from(CanonicalToken::Some), Token::Some);
+    fn replay_primitive() {
+        assert_ok_eq!(serde_json::to_string(&Replay::new([Token::I32(-1)])), "-1");
     }
 
     #[test]
-    fn token_from_canonical_token_unit() {
-        assert_matches!(Token::from(CanonicalToken::Unit), Token::Unit);
+    fn replay_option() {
+        assert_ok_eq!(
+            serde_json::to_string(&Replay::new([Token::Some, Token::Str("foo".to_owned())])),
+            r#""foo""#
+        );
     }
 
     #[test]
-    fn token_from_canonical_token_unit_struct() {
-        assert_matches!(
-            Token::from(CanonicalToken::UnitStruct { name: "foo" }),
-            Token::UnitStruct { name: "foo" }
+    fn replay_map() {
+        assert_ok_eq!(
+            serde_json::to_string(&Replay::new([
+                Token::Map { len: Some(2) },
+                Token::Str("a".to_owned()),
+                Token::Seq { len: Some(0) },
+                Token::SeqEnd,
+                Token::Str("b".to_owned()),
+                Token::None,
+                Token::MapEnd,
+            ])),
+            r#"{"a":[],"b":null}"#
         );
     }
 
     #[test]
-    fn token_from_canonical_token_unit_variant() {
-        assert_matches!(
-            Token::from(CanonicalToken::UnitVariant {
-                name: "foo",
-                variant_index: 42,
-                variant: "bar"
-            }),
-            Token::UnitVariant {
-                name: "foo",
-                variant_index: 42,
-                variant: "bar"
-            }
+    fn replay_struct_variant() {
+        assert_ok_eq!(
+            serde_json::to_string(&Replay::new([
+                Token::StructVariant {
+                    name: "Foo",
+                    variant_index: 0,
+                    variant: "Bar",
+                    len: 1,
+                },
+                Token::Field("a"),
+                Token::Bool(true),
+                Token::SkippedField("b"),
+                Token::StructVariantEnd,
+            ])),
+            r#"{"Bar":{"a":true}}"#
         );
     }
 
     #[test]
-    fn token_from_canonical_token_newtype_struct() {
-        assert_matches!(
-            Token::from(CanonicalToken::NewtypeStruct { name: "foo" }),
-            Token::NewtypeStruct { name: "foo" }
+    fn replay_unordered() {
+        assert_ok_eq!(
+            serde_json::to_string(&Replay::new([
+                Token::Seq { len: Some(2) },
+                Token::Unordered(&[&[Token::U8(1)], &[Token::U8(2)]]),
+                Token::SeqEnd,
+            ])),
+            "[1,2]"
         );
     }
 
     #[test]
-    fn token_from_canonical_token_newtype_variant() {
-        assert_matches!(
-            Token::from(CanonicalToken::NewtypeVariant {
-                name: "foo",
-                variant_index: 42,
-                variant: "bar"
-            }),
-            Token::NewtypeVariant {
-                name: "foo",
-                variant_index: 42,
-                variant: "bar"
-            }
+    fn replay_roundtrip() {
+        #[derive(Serialize)]
+        struct Foo {
+            a: (u8, char),
+            b: Option<Vec<u32>>,
+        }
+
+        let serializer = Serializer::builder().build();
+        let tokens = assert_ok!(Foo {
+            a: (1, 'a'),
+            b: Some(vec![2, 3]),
+        }
+        .serialize(&serializer));
+
+        assert_eq!(
+            assert_ok!(Replay::new(tokens.clone()).serialize(&serializer)).0,
+            tokens.0
         );
     }
 
     #[test]
-    fn token_from_canonical_token_seq() {
-        assert_matches!(
-            Token::from(CanonicalToken::Seq { len: Some(42) }),
-            Token::Seq { len: Some(42) }
-        );
+    fn replay_end_of_tokens() {
+        assert_err!(serde_json::to_string(&Replay::new([Token::Seq {
+            len: None
+        }])));
+    }
+
+    #[test]
+    fn replay_trailing_tokens() {
+        assert_err!(serde_json::to_string(&Replay::new([
+            Token::Unit,
+            Token::Unit
+        ])));
     }
 
     #[test]
-    fn token_from_canonical_token_seq_end() {
-        assert_matches!(Token::from(CanonicalToken::SeqEnd), Token::SeqEnd);
+    fn replay_unexpected_end() {
+        assert_err!(serde_json::to_string(&Replay::new([Token::SeqEnd])));
     }
 
     #[test]
-    fn token_from_canonical_token_tuple() {
-        assert_matches!(
-            Token::from(CanonicalToken::Tuple { len: 42 }),
-            Token::Tuple { len: 42 }
-        );
+    fn replay_mismatched_end() {
+        assert_err!(serde_json::to_string(&Replay::new([
+            Token::Seq { len: None },
+            Token::U8(42),
+            Token::MapEnd,
+        ])));
     }
 
     #[test]
-    fn token_from_canonical_token_tuple_end() {
-        assert_matches!(Token::from(CanonicalToken::TupleEnd), Token::TupleEnd);
+    fn replay_field_outside_struct() {
+        assert_err!(serde_json::to_string(&Replay::new([
+            Token::Seq { len: None },
+            Token::Field("a"),
+            Token::SeqEnd,
+        ])));
     }
 
     #[test]
-    fn token_from_canonical_token_tuple_struct() {
-        assert_matches!(
-            Token::from(CanonicalToken::TupleStruct {
-                name: "foo",
-                len: 42
-            }),
-            Token::TupleStruct {
-                name: "foo",
-                len: 42
-            }
+    fn tokens_serialize() {
+        #[derive(Serialize)]
+        struct Struct {
+            foo: Vec<u8>,
+            bar: Option<&'static str>,
+        }
+
+        let serializer = Serializer::new();
+        let tokens = assert_ok!(Struct {
+            foo: vec![1, 2],
+            bar: Some("baz"),
+        }
+        .serialize(&serializer));
+
+        assert_ok_eq!(
+            serde_json::to_string(&tokens),
+            r#"{"foo":[1,2],"bar":"baz"}"#
         );
     }
 
     #[test]
-    fn token_from_canonical_token_tuple_struct_end() {
-        assert_matches!(
-            Token::from(CanonicalToken::TupleStructEnd),
-            Token::TupleStructEnd
-        );
+    fn tokens_serialize_roundtrip() {
+        let serializer = Serializer::new();
+        let tokens = assert_ok!((1u8, "foo", [true, false]).serialize(&serializer));
+
+        assert_eq!(assert_ok!(tokens.serialize(&serializer)).0, tokens.0);
     }
 
     #[test]
-    fn token_from_canonical_token_tuple_variant() {
-        assert_matches!(
-            Token::from(CanonicalToken::TupleVariant {
-                name: "foo",
-                variant_index: 42,
-                variant: "bar",
-                len: 42
-            }),
-            Token::TupleVariant {
-                name: "foo",
-                variant_index: 42,
-                variant: "bar",
-                len: 42
-            }
-        );
+    fn tokens_serialize_trailing_tokens() {
+        assert_err!(serde_json::to_string(&Tokens(vec![
+            CanonicalToken::Unit,
+            CanonicalToken::Unit
+        ])));
     }
 
     #[test]
-    fn token_from_canonical_token_tuple_variant_end() {
-        assert_matches!(
-            Token::from(CanonicalToken::TupleVariantEnd),
-            Token::TupleVariantEnd
+    fn tokens_serialize_empty() {
+        assert_err!(serde_json::to_string(&Tokens(Vec::new())));
+    }
+
+    #[test]
+    fn record() {
+        assert_ok_eq!(
+            Tokens::record(|serializer| true.serialize(serializer)),
+            [Token::Bool(true)]
         );
     }
 
     #[test]
-    fn token_from_canonical_token_map() {
-        assert_matches!(
-            Token::from(CanonicalToken::Map { len: Some(42) }),
-            Token::Map { len: Some(42) }
+    fn record_error() {
+        assert_err_eq!(
+            Tokens::record(|_| Err(ser::Error::custom("foo"))),
+            ser::Error::custom("foo")
         );
     }
 
     #[test]
-    fn token_from_canonical_token_map_end() {
-        assert_matches!(Token::from(CanonicalToken::MapEnd), Token::MapEnd);
+    fn iter() {
+        let tokens = Tokens(vec![CanonicalToken::Bool(true), CanonicalToken::Unit]);
+        let mut iter = tokens.iter();
+
+        assert_matches!(iter.peek(), Some(Token::Bool(true)));
+        assert_matches!(iter.next(), Some(Token::Bool(true)));
+        assert_matches!(iter.peek(), Some(Token::Unit));
+        assert_matches!(iter.next(), Some(Token::Unit));
+        assert_none!(iter.peek());
+        assert_none!(iter.next());
     }
 
     #[test]
-    fn token_from_canonical_token_field() {
-        assert_matches!(
-            Token::from(CanonicalToken::Field("foo")),
-            Token::Field("foo")
-        );
+    fn into_iter_peek() {
+        let mut iter = Tokens(vec![CanonicalToken::Bool(true)]).into_iter();
+
+        assert_matches!(iter.peek(), Some(Token::Bool(true)));
+        assert_matches!(iter.next(), Some(Token::Bool(true)));
+        assert_none!(iter.peek());
     }
 
     #[test]
-    fn token_from_canonical_token_skipped_field() {
-        assert_matches!(
-            Token::from(CanonicalToken::SkippedField("foo")),
-            Token::SkippedField("foo")
-        );
+    fn token_from_primitives() {
+        assert_matches!(Token::from(true), Token::Bool(true));
+        assert_matches!(Token::from(-1i8), Token::I8(-1));
+        assert_matches!(Token::from(-1i16), Token::I16(-1));
+        assert_matches!(Token::from(-1i32), Token::I32(-1));
+        assert_matches!(Token::from(-1i64), Token::I64(-1));
+        assert_matches!(Token::from(-1i128), Token::I128(-1));
+        assert_matches!(Token::from(1u8), Token::U8(1));
+        assert_matches!(Token::from(1u16), Token::U16(1));
+        assert_matches!(Token::from(1u32), Token::U32(1));
+        assert_matches!(Token::from(1u64), Token::U64(1));
+        assert_matches!(Token::from(1u128), Token::U128(1));
+        assert_matches!(Token::from(1.5f32), Token::F32(value) if value.to_bits() == 1.5f32.to_bits());
+        assert_matches!(Token::from(1.5f64), Token::F64(value) if value.to_bits() == 1.5f64.to_bits());
+        assert_matches!(Token::from('a'), Token::Char('a'));
     }
 
     #[test]
-    fn token_from_canonical_token_struct() {
-        assert_matches!(
-            Token::from(CanonicalToken::Struct {
-                name: "foo",
-                len: 42
-            }),
-            Token::Struct {
-                name: "foo",
-                len: 42
-            }
-        );
+    fn token_from_strings() {
+        assert_matches!(Token::from("foo"), Token::Str(value) if value == "foo");
+        assert_matches!(Token::from("foo".to_owned()), Token::Str(value) if value == "foo");
     }
 
     #[test]
-    fn token_from_canonical_token_struct_end() {
-        assert_matches!(Token::from(CanonicalToken::StructEnd), Token::StructEnd);
+    fn token_from_bytes() {
+        assert_matches!(Token::from(&b"foo"[..]), Token::Bytes(value) if value == b"foo");
+        assert_matches!(Token::from(b"foo".to_vec()), Token::Bytes(value) if value == b"foo");
     }
 
     #[test]
-    fn token_from_canonical_token_struct_variant() {
-        assert_matches!(
-            Token::from(CanonicalToken::StructVariant {
-                name: "foo",
-                variant_index: 42,
-                variant: "bar",
-                len: 42
-            }),
-            Token::StructVariant {
-                name: "foo",
-                variant_index: 42,
-                variant: "bar",
-                len: 42
-            }
+    fn tokens_eq_static_str() {
+        const EXPECTED: [Token; 1] = [Token::StaticStr("foo")];
+
+        assert_eq!(
+            Tokens(vec![CanonicalToken::Str("foo".to_owned())]),
+            EXPECTED
         );
     }
 
     #[test]
-    fn token_from_canonical_token_struct_variant_end() {
-        assert_matches!(
-            Token::from(CanonicalToken::StructVariantEnd),
-            Token::StructVariantEnd
+    fn tokens_ne_static_str() {
+        assert_ne!(
+            Tokens(vec![CanonicalToken::Str("foo".to_owned())]),
+            [Token::StaticStr("bar")]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_bool() {
+    fn tokens_eq_static_bytes() {
+        const EXPECTED: [Token; 1] = [Token::StaticBytes(b"foo")];
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Bool(true)),
-            Unexpected::Bool(true)
+            Tokens(vec![CanonicalToken::Bytes(b"foo".to_vec())]),
+            EXPECTED
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_i8() {
+    fn tokens_eq_static_str_in_unordered() {
+        static EXPECTED: [Token; 1] = [Token::Unordered(&[
+            &[Token::StaticStr("foo")],
+            &[Token::StaticStr("bar")],
+        ])];
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::I8(42)),
-            Unexpected::Signed(42)
+            Tokens(vec![
+                CanonicalToken::Str("bar".to_owned()),
+                CanonicalToken::Str("foo".to_owned())
+            ]),
+            EXPECTED.clone()
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_i16() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::I16(42)),
-            Unexpected::Signed(42)
-        );
+    fn matcher_matches() {
+        let matcher = Matcher::new(&[Token::Bool(true), Token::U8(42)]);
+
+        assert!(matcher.matches(&Tokens(vec![
+            CanonicalToken::Bool(true),
+            CanonicalToken::U8(42)
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_i32() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::I32(42)),
-            Unexpected::Signed(42)
-        );
+    fn matcher_empty() {
+        let matcher = Matcher::new(&[]);
+
+        assert!(matcher.matches(&Tokens(Vec::new())));
+        assert!(!matcher.matches(&Tokens(vec![CanonicalToken::Bool(true)])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_i64() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::I64(42)),
-            Unexpected::Signed(42)
-        );
+    fn matcher_does_not_match_shorter() {
+        let matcher = Matcher::new(&[Token::Bool(true), Token::U8(42)]);
+
+        assert!(!matcher.matches(&Tokens(vec![CanonicalToken::Bool(true)])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_i128() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::I128(42)),
-            Unexpected::Other("i128")
-        );
+    fn matcher_does_not_match_longer() {
+        let matcher = Matcher::new(&[Token::Bool(true)]);
+
+        assert!(!matcher.matches(&Tokens(vec![
+            CanonicalToken::Bool(true),
+            CanonicalToken::U8(42)
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_u8() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::U8(42)),
-            Unexpected::Unsigned(42)
-        );
+    fn matcher_unordered_owned() {
+        let matcher = Matcher::new(&[
+            Token::Seq { len: Some(2) },
+            Token::UnorderedOwned(vec![vec![Token::Bool(true)], vec![Token::U8(42)]]),
+            Token::SeqEnd,
+        ]);
+
+        assert!(matcher.matches(&Tokens(vec![
+            CanonicalToken::Seq { len: Some(2) },
+            CanonicalToken::U8(42),
+            CanonicalToken::Bool(true),
+            CanonicalToken::SeqEnd,
+        ])));
+        assert!(!matcher.matches(&Tokens(vec![
+            CanonicalToken::Seq { len: Some(2) },
+            CanonicalToken::U8(42),
+            CanonicalToken::U8(42),
+            CanonicalToken::SeqEnd,
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_u16() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::U16(42)),
-            Unexpected::Unsigned(42)
-        );
+    fn matcher_unordered_many_groups() {
+        let matcher = Matcher::new(&[
+            Token::Seq { len: Some(100) },
+            Token::UnorderedOwned((0..100).map(|i| vec![Token::U8(i)]).collect()),
+            Token::SeqEnd,
+        ]);
+
+        for _ in 0..2 {
+            assert!(matcher.matches(&Tokens(
+                iter::once(CanonicalToken::Seq { len: Some(100) })
+                    .chain((0..100).rev().map(CanonicalToken::U8))
+                    .chain(iter::once(CanonicalToken::SeqEnd))
+                    .collect()
+            )));
+        }
+        assert!(!matcher.matches(&Tokens(
+            iter::once(CanonicalToken::Seq { len: Some(100) })
+                .chain((0..99).map(CanonicalToken::U8))
+                .chain([CanonicalToken::U8(0), CanonicalToken::SeqEnd])
+                .collect()
+        )));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_u32() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::U32(42)),
-            Unexpected::Unsigned(42)
-        );
+    fn matcher_unordered_reused() {
+        let matcher = Matcher::new(&[
+            Token::Seq { len: Some(2) },
+            Token::Unordered(&[&[Token::Bool(true)], &[Token::U8(42)]]),
+            Token::SeqEnd,
+        ]);
+
+        assert!(matcher.matches(&Tokens(vec![
+            CanonicalToken::Seq { len: Some(2) },
+            CanonicalToken::Bool(true),
+            CanonicalToken::U8(42),
+            CanonicalToken::SeqEnd,
+        ])));
+        assert!(matcher.matches(&Tokens(vec![
+            CanonicalToken::Seq { len: Some(2) },
+            CanonicalToken::U8(42),
+            CanonicalToken::Bool(true),
+            CanonicalToken::SeqEnd,
+        ])));
+        assert!(!matcher.matches(&Tokens(vec![
+            CanonicalToken::Seq { len: Some(2) },
+            CanonicalToken::U8(42),
+            CanonicalToken::U8(42),
+            CanonicalToken::SeqEnd,
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_u64() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::U64(42)),
-            Unexpected::Unsigned(42)
-        );
+    fn matcher_unordered_nested_reused() {
+        let matcher = Matcher::new(&[
+            Token::Seq { len: Some(2) },
+            Token::Unordered(&[
+                &[
+                    Token::Tuple { len: 2 },
+                    Token::Group(&[Token::Bool(true)]),
+                    Token::Group(&[Token::Bool(false)]),
+                    Token::TupleEnd,
+                ],
+                &[
+                    Token::Map { len: Some(2) },
+                    Token::Unordered(&[
+                        &[Token::U8(1), Token::U8(2)],
+                        &[Token::U8(3), Token::U8(4)],
+                    ]),
+                    Token::MapEnd,
+                ],
+            ]),
+            Token::SeqEnd,
+        ]);
+
+        // Nested groups are compiled along with the groups containing them.
+        let set = match &matcher.segments[1] {
+            Segment::Unordered(Some(set)) => set,
+            segment => panic!("unexpected segment: {segment:?}"),
+        };
+        assert!(set
+            .groups
+            .iter()
+            .all(|group| matches!(group[1], Element::Set(_)) && group.len() == 3));
+
+        for _ in 0..2 {
+            assert!(matcher.matches(&Tokens(vec![
+                CanonicalToken::Seq { len: Some(2) },
+                CanonicalToken::Map { len: Some(2) },
+                CanonicalToken::U8(3),
+                CanonicalToken::U8(4),
+                CanonicalToken::U8(1),
+                CanonicalToken::U8(2),
+                CanonicalToken::MapEnd,
+                CanonicalToken::Tuple { len: 2 },
+                CanonicalToken::Bool(false),
+                CanonicalToken::Bool(true),
+                CanonicalToken::TupleEnd,
+                CanonicalToken::SeqEnd,
+            ])));
+        }
+        assert!(!matcher.matches(&Tokens(vec![
+            CanonicalToken::Seq { len: Some(2) },
+            CanonicalToken::Map { len: Some(2) },
+            CanonicalToken::U8(3),
+            CanonicalToken::U8(2),
+            CanonicalToken::U8(1),
+            CanonicalToken::U8(4),
+            CanonicalToken::MapEnd,
+            CanonicalToken::Tuple { len: 2 },
+            CanonicalToken::Bool(false),
+            CanonicalToken::Bool(true),
+            CanonicalToken::TupleEnd,
+            CanonicalToken::SeqEnd,
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_u128() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::U128(42)),
-            Unexpected::Other("u128")
-        );
+    fn matcher_unordered_identical_nested() {
+        let matcher = Matcher::new(&[Token::UnorderedOwned(vec![
+            vec![Token::UnorderedOwned(vec![
+                vec![Token::U8(1)],
+                vec![Token::U8(2)],
+            ])],
+            vec![Token::UnorderedOwned(vec![
+                vec![Token::U8(1)],
+                vec![Token::U8(2)],
+            ])],
+        ])]);
+
+        // Groups containing identical nested groups are identical.
+        let set = match &matcher.segments[0] {
+            Segment::Unordered(Some(set)) => set,
+            segment => panic!("unexpected segment: {segment:?}"),
+        };
+        assert_eq!(set.classes, [0, 0]);
+
+        assert!(matcher.matches(&Tokens(vec![
+            CanonicalToken::U8(2),
+            CanonicalToken::U8(1),
+            CanonicalToken::U8(1),
+            CanonicalToken::U8(2),
+        ])));
+        assert!(!matcher.matches(&Tokens(vec![
+            CanonicalToken::U8(2),
+            CanonicalToken::U8(2),
+            CanonicalToken::U8(1),
+            CanonicalToken::U8(1),
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_f32() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::F32(42.)),
-            Unexpected::Float(42.)
-        );
+    fn matcher_unordered_nested_empty() {
+        let matcher = Matcher::new(&[Token::Unordered(&[&[
+            Token::Bool(true),
+            Token::Unordered(&[]),
+            Token::U8(42),
+        ]])]);
+
+        assert!(matcher.matches(&Tokens(vec![
+            CanonicalToken::Bool(true),
+            CanonicalToken::U8(42),
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_f64() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::F64(42.)),
-            Unexpected::Float(42.)
-        );
+    fn matcher_ignoring_some() {
+        let matcher = Matcher::new(&[Token::Some, Token::U8(42)]).ignoring_some();
+
+        assert!(matcher.matches(&Tokens(vec![CanonicalToken::U8(42)])));
+        assert!(matcher.matches(&Tokens(vec![CanonicalToken::Some, CanonicalToken::U8(42)])));
+        assert!(!matcher.matches(&Tokens(vec![CanonicalToken::None])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_char() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Char('a')),
-            Unexpected::Char('a')
-        );
+    fn matcher_ignoring_some_in_struct() {
+        let matcher = Matcher::new(&[
+            Token::Struct {
+                name: "Struct",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::U8(42),
+            Token::StructEnd,
+        ])
+        .ignoring_some();
+
+        assert!(matcher.matches(&Tokens(vec![
+            CanonicalToken::Struct {
+                name: "Struct",
+                len: 1,
+            },
+            CanonicalToken::Field("foo"),
+            CanonicalToken::Some,
+            CanonicalToken::U8(42),
+            CanonicalToken::StructEnd,
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_str() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Str("foo".to_owned())),
-            Unexpected::Str("foo")
-        );
+    fn matcher_ignoring_some_unordered() {
+        let matcher = Matcher::new(&[
+            Token::Seq { len: Some(2) },
+            Token::Unordered(&[&[Token::Some, Token::Bool(true)], &[Token::U8(42)]]),
+            Token::SeqEnd,
+        ])
+        .ignoring_some();
+
+        assert!(matcher.matches(&Tokens(vec![
+            CanonicalToken::Seq { len: Some(2) },
+            CanonicalToken::Some,
+            CanonicalToken::U8(42),
+            CanonicalToken::Bool(true),
+            CanonicalToken::SeqEnd,
+        ])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_bytes() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Bytes(b"foo".to_vec())),
-            Unexpected::Bytes(b"foo")
-        );
+    fn matcher_sensitive_to_some_by_default() {
+        let matcher = Matcher::new(&[Token::U8(42)]);
+
+        assert!(!matcher.matches(&Tokens(vec![CanonicalToken::Some, CanonicalToken::U8(42)])));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_some() {
+    fn matcher_empty_unordered() {
+        let matcher = Matcher::new(&[Token::Unordered(&[]), Token::Bool(true)]);
+
+        assert!(matcher.matches(&Tokens(vec![CanonicalToken::Bool(true)])));
+    }
+
+    #[test]
+    fn tokens_eq_matcher() {
+        let matcher = Matcher::new(&[Token::Unordered(&[&[Token::Bool(true)], &[Token::U8(42)]])]);
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Some),
-            Unexpected::Option
+            Tokens(vec![CanonicalToken::U8(42), CanonicalToken::Bool(true)]),
+            matcher
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_none() {
+    fn tokens_from_iter() {
+        let tokens = [Token::Bool(true), Token::U8(42)]
+            .into_iter()
+            .collect::<Tokens>();
+
+        assert_eq!(tokens, [Token::Bool(true), Token::U8(42)]);
+    }
+
+    #[test]
+    fn normalize_len_hints_nested() {
+        let mut tokens = [
+            Token::Struct {
+                name: "Struct",
+                len: 0,
+            },
+            Token::Field("foo"),
+            Token::Seq { len: Some(0) },
+            Token::Some,
+            Token::U8(1),
+            Token::NewtypeStruct { name: "Newtype" },
+            Token::Tuple { len: 0 },
+            Token::U8(2),
+            Token::U8(3),
+            Token::TupleEnd,
+            Token::SeqEnd,
+            Token::SkippedField("bar"),
+            Token::Field("baz"),
+            Token::Map { len: Some(5) },
+            Token::Char('a'),
+            Token::Unit,
+            Token::Char('b'),
+            Token::None,
+            Token::MapEnd,
+            Token::Field("qux"),
+            Token::Seq { len: None },
+            Token::U8(4),
+            Token::SeqEnd,
+            Token::StructEnd,
+        ]
+        .into_iter()
+        .collect::<Tokens>();
+        tokens.normalize_len_hints();
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::None),
-            Unexpected::Option
+            tokens,
+            [
+                Token::Struct {
+                    name: "Struct",
+                    len: 3,
+                },
+                Token::Field("foo"),
+                Token::Seq { len: Some(2) },
+                Token::Some,
+                Token::U8(1),
+                Token::NewtypeStruct { name: "Newtype" },
+                Token::Tuple { len: 2 },
+                Token::U8(2),
+                Token::U8(3),
+                Token::TupleEnd,
+                Token::SeqEnd,
+                Token::SkippedField("bar"),
+                Token::Field("baz"),
+                Token::Map { len: Some(2) },
+                Token::Char('a'),
+                Token::Unit,
+                Token::Char('b'),
+                Token::None,
+                Token::MapEnd,
+                Token::Field("qux"),
+                Token::Seq { len: None },
+                Token::U8(4),
+                Token::SeqEnd,
+                Token::StructEnd,
+            ]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_unit() {
+    fn normalize_len_hints_variants() {
+        let mut tokens = [
+            Token::TupleVariant {
+                name: "Enum",
+                variant_index: 0,
+                variant: "Tuple",
+                len: 5,
+            },
+            Token::NewtypeVariant {
+                name: "Enum",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::StructVariant {
+                name: "Enum",
+                variant_index: 2,
+                variant: "Struct",
+                len: 5,
+            },
+            Token::Field("foo"),
+            Token::Bool(true),
+            Token::StructVariantEnd,
+            Token::TupleStruct {
+                name: "TupleStruct",
+                len: 5,
+            },
+            Token::TupleStructEnd,
+            Token::TupleVariantEnd,
+        ]
+        .into_iter()
+        .collect::<Tokens>();
+        tokens.normalize_len_hints();
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Unit),
-            Unexpected::Unit
+            tokens,
+            [
+                Token::TupleVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Tuple",
+                    len: 2,
+                },
+                Token::NewtypeVariant {
+                    name: "Enum",
+                    variant_index: 1,
+                    variant: "Newtype",
+                },
+                Token::StructVariant {
+                    name: "Enum",
+                    variant_index: 2,
+                    variant: "Struct",
+                    len: 1,
+                },
+                Token::Field("foo"),
+                Token::Bool(true),
+                Token::StructVariantEnd,
+                Token::TupleStruct {
+                    name: "TupleStruct",
+                    len: 0,
+                },
+                Token::TupleStructEnd,
+                Token::TupleVariantEnd,
+            ]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_unit_struct() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::UnitStruct { name: "foo" }),
-            Unexpected::Unit
-        );
+    fn normalize_len_hints_unterminated() {
+        let mut tokens = [Token::Seq { len: Some(5) }, Token::U8(1)]
+            .into_iter()
+            .collect::<Tokens>();
+        tokens.normalize_len_hints();
+
+        assert_eq!(tokens, [Token::Seq { len: Some(5) }, Token::U8(1)]);
     }
 
     #[test]
-    fn unexpected_from_canonical_token_unit_variant() {
+    fn clear_len_hints() {
+        let mut tokens = [
+            Token::Seq { len: Some(1) },
+            Token::Map { len: Some(1) },
+            Token::U8(1),
+            Token::Tuple { len: 0 },
+            Token::TupleEnd,
+            Token::MapEnd,
+            Token::SeqEnd,
+        ]
+        .into_iter()
+        .collect::<Tokens>();
+        tokens.clear_len_hints();
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::UnitVariant {
-                name: "foo",
-                variant_index: 0,
-                variant: "bar"
-            }),
-            Unexpected::UnitVariant
+            tokens,
+            [
+                Token::Seq { len: None },
+                Token::Map { len: None },
+                Token::U8(1),
+                Token::Tuple { len: 0 },
+                Token::TupleEnd,
+                Token::MapEnd,
+                Token::SeqEnd,
+            ]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_newtype_struct() {
+    fn strip_names() {
+        let mut tokens = [
+            Token::Seq { len: Some(4) },
+            Token::UnitStruct { name: "Unit" },
+            Token::NewtypeStruct { name: "Newtype" },
+            Token::Bool(true),
+            Token::TupleVariant {
+                name: "Enum",
+                variant_index: 1,
+                variant: "Tuple",
+                len: 0,
+            },
+            Token::TupleVariantEnd,
+            Token::Struct {
+                name: "Struct",
+                len: 0,
+            },
+            Token::StructEnd,
+            Token::SeqEnd,
+        ]
+        .into_iter()
+        .collect::<Tokens>();
+        tokens.strip_names();
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::NewtypeStruct { name: "foo" }),
-            Unexpected::NewtypeStruct
+            tokens,
+            [
+                Token::Seq { len: Some(4) },
+                Token::UnitStruct { name: "_" },
+                Token::NewtypeStruct { name: "_" },
+                Token::Bool(true),
+                Token::TupleVariant {
+                    name: "_",
+                    variant_index: 1,
+                    variant: "Tuple",
+                    len: 0,
+                },
+                Token::TupleVariantEnd,
+                Token::Struct { name: "_", len: 0 },
+                Token::StructEnd,
+                Token::SeqEnd,
+            ]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_newtype_variant() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::NewtypeVariant {
-                name: "foo",
-                variant_index: 0,
-                variant: "bar"
-            }),
-            Unexpected::NewtypeVariant
-        );
+    fn strip_names_differently_named_types() {
+        #[derive(Serialize)]
+        struct Foo {
+            value: u32,
+        }
+
+        #[derive(Serialize)]
+        struct Bar {
+            value: u32,
+        }
+
+        let serializer = Serializer::new();
+        let mut foo = assert_ok!(Foo { value: 42 }.serialize(&serializer));
+        let mut bar = assert_ok!(Bar { value: 42 }.serialize(&serializer));
+        foo.strip_names();
+        bar.strip_names();
+
+        assert_eq!(foo, bar.into_iter().collect::<Vec<_>>());
     }
 
     #[test]
-    fn unexpected_from_canonical_token_seq() {
+    fn tag_enums_external() {
+        let mut tokens = [
+            Token::NewtypeVariant {
+                name: "Enum",
+                variant_index: 0,
+                variant: "Newtype",
+            },
+            Token::U32(42),
+        ]
+        .into_iter()
+        .collect::<Tokens>();
+        tokens.tag_enums(EnumTagging::External);
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Seq { len: None }),
-            Unexpected::Seq
+            tokens,
+            [
+                Token::NewtypeVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Newtype",
+                },
+                Token::U32(42),
+            ]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_tuple() {
+    fn tag_enums_internal() {
+        let mut tokens = [
+            Token::Seq { len: Some(4) },
+            Token::UnitVariant {
+                name: "Enum",
+                variant_index: 0,
+                variant: "Unit",
+            },
+            Token::NewtypeVariant {
+                name: "Enum",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::Map { len: Some(1) },
+            Token::Str("foo".to_owned()),
+            Token::U32(42),
+            Token::MapEnd,
+            Token::StructVariant {
+                name: "Enum",
+                variant_index: 3,
+                variant: "Struct",
+                len: 2,
+            },
+            Token::Field("inner"),
+            Token::UnitVariant {
+                name: "Enum",
+                variant_index: 0,
+                variant: "Unit",
+            },
+            Token::SkippedField("skipped"),
+            Token::StructVariantEnd,
+            Token::NewtypeVariant {
+                name: "Enum",
+                variant_index: 1,
+                variant: "Newtype",
+            },
+            Token::U32(42),
+            Token::SeqEnd,
+        ]
+        .into_iter()
+        .collect::<Tokens>();
+        tokens.tag_enums(EnumTagging::Internal { tag: "type" });
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Tuple { len: 0 }),
-            Unexpected::Seq
+            tokens,
+            [
+                Token::Seq { len: Some(4) },
+                Token::Struct {
+                    name: "Enum",
+                    len: 1,
+                },
+                Token::Field("type"),
+                Token::Str("Unit".to_owned()),
+                Token::StructEnd,
+                Token::Map { len: Some(2) },
+                Token::Str("type".to_owned()),
+                Token::Str("Newtype".to_owned()),
+                Token::Str("foo".to_owned()),
+                Token::U32(42),
+                Token::MapEnd,
+                Token::Struct {
+                    name: "Enum",
+                    len: 3,
+                },
+                Token::Field("type"),
+                Token::Str("Struct".to_owned()),
+                Token::Field("inner"),
+                Token::Struct {
+                    name: "Enum",
+                    len: 1,
+                },
+                Token::Field("type"),
+                Token::Str("Unit".to_owned()),
+                Token::StructEnd,
+                Token::SkippedField("skipped"),
+                Token::StructEnd,
+                Token::NewtypeVariant {
+                    name: "Enum",
+                    variant_index: 1,
+                    variant: "Newtype",
+                },
+                Token::U32(42),
+                Token::SeqEnd,
+            ]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_seq_end() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::SeqEnd),
-            Unexpected::Other("SeqEnd")
-        );
+    fn tag_enums_internal_matches_serialized() {
+        #[derive(Serialize)]
+        struct Inner {
+            foo: u32,
+        }
+
+        #[derive(Serialize)]
+        enum External {
+            Unit,
+            Newtype(Inner),
+            Struct { foo: u32, bar: bool },
+        }
+
+        #[derive(Serialize)]
+        #[serde(tag = "type")]
+        enum Internal {
+            Unit,
+            Newtype(Inner),
+            Struct { foo: u32, bar: bool },
+        }
+
+        let serializer = Serializer::new();
+        let mut external = assert_ok!((
+            External::Unit,
+            External::Newtype(Inner { foo: 42 }),
+            External::Struct { foo: 42, bar: true }
+        )
+            .serialize(&serializer));
+        let mut internal = assert_ok!((
+            Internal::Unit,
+            Internal::Newtype(Inner { foo: 42 }),
+            Internal::Struct { foo: 42, bar: true }
+        )
+            .serialize(&serializer));
+        external.tag_enums(EnumTagging::Internal { tag: "type" });
+        // The enums are named differently.
+        external.strip_names();
+        internal.strip_names();
+
+        assert_eq!(external.0, internal.0);
     }
 
     #[test]
-    fn unexpected_from_canonical_token_tuple_end() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::TupleEnd),
-            Unexpected::Other("TupleEnd")
-        );
+    fn tag_enums_adjacent_matches_serialized() {
+        #[derive(Serialize)]
+        enum External {
+            Unit,
+            Newtype(u32),
+            Tuple(u32, bool),
+            Struct { foo: u32 },
+        }
+
+        #[derive(Serialize)]
+        #[serde(tag = "t", content = "c")]
+        enum Adjacent {
+            Unit,
+            Newtype(u32),
+            Tuple(u32, bool),
+            Struct { foo: u32 },
+        }
+
+        let serializer = Serializer::new();
+        let mut external = assert_ok!((
+            External::Unit,
+            External::Newtype(42),
+            External::Tuple(42, true),
+            External::Struct { foo: 42 }
+        )
+            .serialize(&serializer));
+        let mut adjacent = assert_ok!((
+            Adjacent::Unit,
+            Adjacent::Newtype(42),
+            Adjacent::Tuple(42, true),
+            Adjacent::Struct { foo: 42 }
+        )
+            .serialize(&serializer));
+        external.tag_enums(EnumTagging::Adjacent {
+            tag: "t",
+            content: "c",
+        });
+        // The enums are named differently.
+        external.strip_names();
+        adjacent.strip_names();
+
+        assert_eq!(external.0, adjacent.0);
     }
 
     #[test]
-    fn unexpected_from_canonical_token_tuple_struct() {
+    fn tag_enums_incomplete() {
+        let mut tokens = [
+            Token::NewtypeVariant {
+                name: "Enum",
+                variant_index: 0,
+                variant: "Newtype",
+            },
+            Token::Struct {
+                name: "Struct",
+                len: 0,
+            },
+        ]
+        .into_iter()
+        .collect::<Tokens>();
+        tokens.tag_enums(EnumTagging::Internal { tag: "type" });
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::TupleStruct {
-                name: "foo",
-                len: 0
-            }),
-            Unexpected::Other("TupleStruct")
+            tokens,
+            [
+                Token::NewtypeVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Newtype",
+                },
+                Token::Struct {
+                    name: "Struct",
+                    len: 0,
+                },
+            ]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_tuple_struct_end() {
+    fn tag_enums_within_incomplete() {
+        let mut tokens = [
+            Token::TupleVariant {
+                name: "Enum",
+                variant_index: 0,
+                variant: "Tuple",
+                len: 1,
+            },
+            Token::UnitVariant {
+                name: "Enum",
+                variant_index: 1,
+                variant: "Unit",
+            },
+        ]
+        .into_iter()
+        .collect::<Tokens>();
+        tokens.tag_enums(EnumTagging::Adjacent {
+            tag: "t",
+            content: "c",
+        });
+
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::TupleStructEnd),
-            Unexpected::Other("TupleStructEnd")
+            tokens,
+            [
+                Token::TupleVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Tuple",
+                    len: 1,
+                },
+                Token::Struct {
+                    name: "Enum",
+                    len: 1,
+                },
+                Token::Field("t"),
+                Token::UnitVariant {
+                    name: "Enum",
+                    variant_index: 1,
+                    variant: "Unit",
+                },
+                Token::StructEnd,
+            ]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_tuple_variant() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::TupleVariant {
-                name: "foo",
-                variant_index: 0,
-                variant: "bar",
-                len: 0
-            }),
-            Unexpected::TupleVariant
-        );
-    }
+    fn tag_enums_deeply_nested() {
+        let mut tokens = iter::repeat(Token::NewtypeVariant {
+            name: "Enum",
+            variant_index: 0,
+            variant: "Newtype",
+        })
+        .take(100_000)
+        .chain(iter::once(Token::Unit))
+        .collect::<Tokens>();
+        tokens.tag_enums(EnumTagging::Adjacent {
+            tag: "t",
+            content: "c",
+        });
+
+        assert_eq!(tokens.0.len(), 500_001);
+        assert_eq!(tokens.0[400_000], CanonicalToken::Unit);
+        assert!(tokens.0[400_001..]
+            .iter()
+            .all(|token| *token == CanonicalToken::StructEnd));
+    }
+
+    const UNORDERED: [Token; 3] = [
+        Token::Seq { len: Some(4) },
+        Token::Unordered(&[
+            &[Token::U8(0)],
+            &[Token::U8(1)],
+            &[Token::U8(2)],
+            &[Token::U8(3)],
+        ]),
+        Token::SeqEnd,
+    ];
 
     #[test]
-    fn unexpected_from_canonical_token_tuple_variant_end() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::TupleVariantEnd),
-            Unexpected::Other("TupleVariantEnd")
-        );
+    fn shuffled_deterministic() {
+        for seed in 0..10 {
+            assert_eq!(
+                Tokens::shuffled(UNORDERED.iter().cloned(), seed),
+                Tokens::shuffled(UNORDERED.iter().cloned(), seed)
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            );
+        }
     }
 
     #[test]
-    fn unexpected_from_canonical_token_map() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Map { len: None }),
-            Unexpected::Map
-        );
+    fn shuffled_matches_unordered() {
+        for seed in 0..10 {
+            assert_eq!(Tokens::shuffled(UNORDERED.iter().cloned(), seed), UNORDERED);
+        }
     }
 
     #[test]
-    fn unexpected_from_canonical_token_map_end() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::MapEnd),
-            Unexpected::Other("MapEnd")
-        );
+    fn shuffled_matches_unordered_owned() {
+        let tokens = [
+            Token::Seq { len: Some(4) },
+            Token::UnorderedOwned((0..4).map(|i| vec![Token::U8(i)]).collect()),
+            Token::SeqEnd,
+        ];
+
+        for seed in 0..10 {
+            assert_eq!(Tokens::shuffled(tokens.iter().cloned(), seed), tokens);
+        }
     }
 
     #[test]
-    fn unexpected_from_canonical_token_field() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Field("foo")),
-            Unexpected::Other("Field")
-        );
+    fn shuffled_varies_by_seed() {
+        let orderings = (0..20)
+            .map(|seed| {
+                Tokens::shuffled(UNORDERED.iter().cloned(), seed)
+                    .into_iter()
+                    .map(|token| format!("{token:?}"))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        assert!(orderings.iter().any(|ordering| *ordering != orderings[0]));
     }
 
     #[test]
-    fn unexpected_from_canonical_token_skipped_field() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::SkippedField("foo")),
-            Unexpected::Other("SkippedField")
-        );
+    fn shuffled_nested() {
+        let tokens = [Token::Unordered(&[
+            &[Token::Bool(true)],
+            &[Token::Unordered(&[&[Token::U8(1)], &[Token::U8(2)]])],
+        ])];
+
+        for seed in 0..10 {
+            let shuffled = Tokens::shuffled(tokens.iter().cloned(), seed);
+
+            assert_eq!(shuffled.0.len(), 3);
+            assert_eq!(shuffled, tokens);
+        }
     }
 
     #[test]
-    fn unexpected_from_canonical_token_struct() {
-        assert_eq!(
-            Unexpected::from(&mut CanonicalToken::Struct {
-                name: "foo",
-                len: 0
-            }),
-            Unexpected::Other("Struct")
-        );
+    fn shuffled_groups() {
+        let tokens = [
+            Token::Group(&[Token::U8(1), Token::U8(2)]),
+            Token::Group(&[Token::U8(3), Token::U8(4)]),
+            Token::Group(&[Token::U8(5), Token::U8(6)]),
+        ];
+
+        let orderings = (0..20)
+            .map(|seed| {
+                let shuffled = Tokens::shuffled(tokens.iter().cloned(), seed);
+                assert_eq!(shuffled, tokens);
+                shuffled
+                    .into_iter()
+                    .map(|token| format!("{token:?}"))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        assert!(orderings.iter().any(|ordering| *ordering != orderings[0]));
+    }
+
+    /// Returns `Unit` nested within the given number of `Unordered` tokens.
+    fn deeply_nested(depth: usize) -> Token {
+        let mut token = Token::Unit;
+        for _ in 0..depth {
+            let group: &'static [Token] = Box::leak(Box::new([token]));
+            token = Token::Unordered(Box::leak(Box::new([group])));
+        }
+        token
     }
 
     #[test]
-    fn unexpected_from_canonical_token_struct_end() {
+    fn canonicalize_deeply_nested() {
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::StructEnd),
-            Unexpected::Other("StructEnd")
+            Tokens::canonicalize([deeply_nested(100_000)]).0,
+            [CanonicalToken::Unit]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_struct_variant() {
+    fn shuffled_deeply_nested() {
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::StructVariant {
-                name: "foo",
-                variant_index: 0,
-                variant: "bar",
-                len: 0
-            }),
-            Unexpected::StructVariant
+            Tokens::shuffled([deeply_nested(100_000)], 0).0,
+            [CanonicalToken::Unit]
         );
     }
 
     #[test]
-    fn unexpected_from_canonical_token_struct_variant_end() {
+    fn shuffled_without_unordered() {
         assert_eq!(
-            Unexpected::from(&mut CanonicalToken::StructVariantEnd),
-            Unexpected::Other("StructVariantEnd")
+            Tokens::shuffled([Token::Bool(true), Token::U8(42)], 0),
+            [Token::Bool(true), Token::U8(42)]
         );
     }
 
     #[test]
-    fn owning_iter_empty() {
-        let mut iter = OwningIter::new(Tokens(Vec::new()));
-
-        assert_none!(iter.next());
+    fn split_values_empty() {
+        assert!(Tokens(Vec::new()).split_values().is_empty());
     }
 
     #[test]
-    fn owning_iter_one_token() {
-        let mut iter = OwningIter::new(Tokens(vec![CanonicalToken::Bool(true)]));
+    fn split_values_primitives() {
+        let values = [Token::Bool(true), Token::U8(1), Token::Unit]
+            .into_iter()
+            .collect::<Tokens>()
+            .split_values();
 
-        assert_some_eq!(iter.next(), &mut CanonicalToken::Bool(true));
-        assert_none!(iter.next());
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0], [Token::Bool(true)]);
+        assert_eq!(values[1], [Token::U8(1)]);
+        assert_eq!(values[2], [Token::Unit]);
     }
 
     #[test]
-    fn owning_iter_multiple_tokens() {
-        let mut iter = OwningIter::new(Tokens(vec![
-            CanonicalToken::Bool(true),
-            CanonicalToken::U64(42),
-            CanonicalToken::Str("foo".to_owned()),
-        ]));
-
-        assert_some_eq!(iter.next(), &mut CanonicalToken::Bool(true));
-        assert_some_eq!(iter.next(), &mut CanonicalToken::U64(42));
-        assert_some_eq!(iter.next(), &mut CanonicalToken::Str("foo".to_owned()));
-        assert_none!(iter.next());
+    fn split_values_nested() {
+        let values = [
+            Token::Struct {
+                name: "Struct",
+                len: 1,
+            },
+            Token::Field("foo"),
+            Token::Seq { len: Some(1) },
+            Token::Some,
+            Token::U8(1),
+            Token::SeqEnd,
+            Token::StructEnd,
+            Token::NewtypeVariant {
+                name: "Enum",
+                variant_index: 0,
+                variant: "Newtype",
+            },
+            Token::Tuple { len: 0 },
+            Token::TupleEnd,
+        ]
+        .into_iter()
+        .collect::<Tokens>()
+        .split_values();
+
+        assert_eq!(values.len(), 2);
+        assert_eq!(
+            values[0],
+            [
+                Token::Struct {
+                    name: "Struct",
+                    len: 1,
+                },
+                Token::Field("foo"),
+                Token::Seq { len: Some(1) },
+                Token::Some,
+                Token::U8(1),
+                Token::SeqEnd,
+                Token::StructEnd,
+            ]
+        );
+        assert_eq!(
+            values[1],
+            [
+                Token::NewtypeVariant {
+                    name: "Enum",
+                    variant_index: 0,
+                    variant: "Newtype",
+                },
+                Token::Tuple { len: 0 },
+                Token::TupleEnd,
+            ]
+        );
     }
 
     #[test]
-    fn owning_iter_empty_debug() {
-        let iter = OwningIter::new(Tokens(Vec::new()));
+    fn split_values_incomplete() {
+        let values = [Token::Bool(true), Token::Seq { len: Some(1) }, Token::U8(1)]
+            .into_iter()
+            .collect::<Tokens>()
+            .split_values();
 
-        assert_eq!(format!("{iter:?}"), "OwningIter([])");
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0], [Token::Bool(true)]);
+        assert_eq!(values[1], [Token::Seq { len: Some(1) }, Token::U8(1)]);
     }
 
     #[test]
-    fn owning_iter_uniterated_debug() {
-        let iter = OwningIter::new(Tokens(vec![
-            CanonicalToken::Bool(true),
-            CanonicalToken::U64(42),
-            CanonicalToken::Str("foo".to_owned()),
-        ]));
+    fn split_values_deeply_nested() {
+        let values = iter::repeat(Token::Seq { len: Some(1) })
+            .take(100_000)
+            .chain(iter::once(Token::Unit))
+            .chain(iter::repeat(Token::SeqEnd).take(100_000))
+            .chain(iter::once(Token::Bool(true)))
+            .collect::<Tokens>()
+            .split_values();
 
-        assert_eq!(
-            format!("{iter:?}"),
-            "OwningIter([Bool(true), U64(42), Str(\"foo\")])"
-        );
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].0.len(), 200_001);
+        assert_eq!(values[1], [Token::Bool(true)]);
     }
 
     #[test]
-    fn owning_iter_partially_iterated_debug() {
-        let mut iter = OwningIter::new(Tokens(vec![
-            CanonicalToken::Bool(true),
-            CanonicalToken::U64(42),
-            CanonicalToken::Str("foo".to_owned()),
-        ]));
-
-        assert_some!(iter.next());
+    fn split_values_serialized() {
+        let serializer = Serializer::new();
+        let mut tokens = assert_ok!((1u8, "foo").serialize(&serializer));
+        tokens
+            .0
+            .extend(assert_ok!(Some(true).serialize(&serializer)).0);
+        let values = tokens.split_values();
 
-        assert_eq!(format!("{iter:?}"), "OwningIter([U64(42), Str(\"foo\")])");
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[1], [Token::Some, Token::Bool(true)]);
     }
 
     #[test]
-    fn owning_iter_fully_iterated_debug() {
-        let mut iter = OwningIter::new(Tokens(vec![
-            CanonicalToken::Bool(true),
-            CanonicalToken::U64(42),
-            CanonicalToken::Str("foo".to_owned()),
-        ]));
+    fn stats_empty() {
+        let stats = Tokens(Vec::new()).stats();
 
-        assert_some!(iter.next());
-        assert_some!(iter.next());
-        assert_some!(iter.next());
+        assert!(stats.counts().is_empty());
+        assert_eq!(stats.count("Bool"), 0);
+        assert_eq!(stats.max_depth(), 0);
+        assert_eq!(stats.str_len(), 0);
+        assert_eq!(stats.bytes_len(), 0);
+    }
 
-        assert_eq!(format!("{iter:?}"), "OwningIter([])");
+    #[test]
+    fn stats() {
+        let stats = [
+            Token::Struct {
+                name: "Struct",
+                len: 2,
+            },
+            Token::Field("foo"),
+            Token::Seq { len: Some(2) },
+            Token::Str("abc".to_owned()),
+            Token::Map { len: Some(1) },
+            Token::Bytes(b"de".to_vec()),
+            Token::Str("fg".to_owned()),
+            Token::MapEnd,
+            Token::SeqEnd,
+            Token::Field("bar"),
+            Token::Tuple { len: 1 },
+            Token::Bytes(b"hij".to_vec()),
+            Token::TupleEnd,
+            Token::StructEnd,
+        ]
+        .into_iter()
+        .collect::<Tokens>()
+        .stats();
+
+        assert_eq!(stats.count("Struct"), 1);
+        assert_eq!(stats.count("Field"), 2);
+        assert_eq!(stats.count("Str"), 2);
+        assert_eq!(stats.count("Bytes"), 2);
+        assert_eq!(stats.count("SeqEnd"), 1);
+        assert_eq!(stats.count("Bool"), 0);
+        assert_eq!(stats.counts().values().sum::<usize>(), 14);
+        assert_eq!(stats.max_depth(), 3);
+        assert_eq!(stats.str_len(), 5);
+        assert_eq!(stats.bytes_len(), 5);
     }
 }
@@ -0,0 +1,571 @@
+//! A structural representation of a serialized value.
+//!
+//! This module provides a [`Value`] type that can be constructed from a sequence of [`Token`]s.
+//! Comparing `Value`s is less strict than comparing `Token`s: it ignores representational details
+//! such as integer widths, the order of map entries and struct fields, and container names.
+//!
+//! # Example
+//! ``` rust
+//! use claims::assert_ok_eq;
+//! use serde_assert::{
+//!     Token,
+//!     Value,
+//! };
+//!
+//! assert_ok_eq!(
+//!     Value::from_tokens([Token::U8(42)]),
+//!     Value::from_tokens([Token::I64(42)]).unwrap()
+//! );
+//! ```
+
+use crate::{
+    de::Error,
+    token::{
+        CanonicalToken,
+        Tokens,
+    },
+    Token,
+};
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    string::String,
+    vec,
+    vec::Vec,
+};
+use serde::de::{
+    Error as _,
+    Unexpected,
+};
+
+/// A structural representation of a serialized value.
+///
+/// A `Value` is constructed from a sequence of [`Token`]s using [`from_tokens()`]. Unlike
+/// [`Token`]s, `Value`s are compared structurally:
+///
+/// - Integers are compared by their numeric value, regardless of their width or signedness.
+/// - Floats are compared by their numeric value, regardless of their width.
+/// - Entries of maps and fields of structs are compared regardless of their order.
+/// - Names of structs, tuple structs, and enums are not compared.
+///
+/// This is useful when a test cares about what data was serialized rather than exactly how it
+/// was represented.
+///
+/// # Example
+/// ``` rust
+/// use claims::assert_ok_eq;
+/// use serde_assert::{
+///     Token,
+///     Value,
+/// };
+///
+/// assert_ok_eq!(
+///     Value::from_tokens([
+///         Token::Struct {
+///             name: "Foo",
+///             len: 2,
+///         },
+///         Token::Field("a"),
+///         Token::U8(1),
+///         Token::Field("b"),
+///         Token::Bool(true),
+///         Token::StructEnd,
+///     ]),
+///     Value::from_tokens([
+///         Token::Map { len: Some(2) },
+///         Token::Str("b".to_owned()),
+///         Token::Bool(true),
+///         Token::Str("a".to_owned()),
+///         Token::U64(1),
+///         Token::MapEnd,
+///     ])
+///     .unwrap()
+/// );
+/// ```
+///
+/// [`from_tokens()`]: Value::from_tokens()
+#[derive(Clone, Debug)]
+pub enum Value {
+    /// A [`bool`].
+    Bool(bool),
+    /// An unsigned integer of any width.
+    Unsigned(u128),
+    /// A signed integer of any width.
+    Signed(i128),
+    /// A floating point number of any width.
+    Float(f64),
+    /// A [`char`].
+    Char(char),
+    /// A string.
+    Str(String),
+    /// Bytes.
+    Bytes(Vec<u8>),
+    /// An [`Option`] containing no value.
+    None,
+    /// An [`Option`] containing a value.
+    Some(Box<Value>),
+    /// A unit or unit struct.
+    Unit,
+    /// A newtype struct.
+    Newtype(Box<Value>),
+    /// A sequence, tuple, or tuple struct.
+    Seq(Vec<Value>),
+    /// A map or struct.
+    ///
+    /// The fields of a struct are represented as entries keyed by [`Value::Str`]. Skipped fields
+    /// are not included.
+    Map(Vec<(Value, Value)>),
+    /// An enum variant.
+    ///
+    /// The contained value is [`Value::Unit`] for a unit variant, the contained value for a
+    /// newtype variant, a [`Value::Seq`] for a tuple variant, and a [`Value::Map`] for a struct
+    /// variant.
+    Variant {
+        /// The name of the variant.
+        variant: &'static str,
+        /// The value contained within the variant.
+        value: Box<Value>,
+    },
+}
+
+impl Value {
+    /// Constructs a `Value` from a sequence of [`Token`]s.
+    ///
+    /// The tokens must represent exactly one value. Any [`Unordered`] tokens are read in the order
+    /// they are provided.
+    ///
+    /// # Errors
+    /// Returns an [`Error`] if the tokens do not represent exactly one complete value.
+    ///
+    /// # Example
+    /// ``` rust
+    /// use claims::assert_ok_eq;
+    /// use serde_assert::{
+    ///     Token,
+    ///     Value,
+    /// };
+    ///
+    /// assert_ok_eq!(
+    ///     Value::from_tokens([
+    ///         Token::Seq { len: Some(2) },
+    ///         Token::U32(1),
+    ///         Token::I8(-1),
+    ///         Token::SeqEnd,
+    ///     ]),
+    ///     Value::Seq(vec![Value::Unsigned(1), Value::Signed(-1)])
+    /// );
+    /// ```
+    ///
+    /// [`Unordered`]: Token::Unordered
+    pub fn from_tokens<T>(tokens: T) -> Result<Self, Error>
+    where
+        T: IntoIterator<Item = Token>,
+    {
+        let mut tokens = Tokens::canonicalize(tokens).0.into_iter();
+        let value = parse_value(&mut tokens)?;
+        if tokens.next().is_some() {
+            return Err(Error::custom("trailing tokens after value"));
+        }
+        Ok(value)
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Unsigned(a), Value::Unsigned(b)) => a == b,
+            (Value::Signed(a), Value::Signed(b)) => a == b,
+            (Value::Unsigned(a), Value::Signed(b)) | (Value::Signed(b), Value::Unsigned(a)) => {
+                u128::try_from(*b) == Ok(*a)
+            }
+            #[allow(clippy::float_cmp)] // Values are compared exactly.
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Char(a), Value::Char(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::None, Value::None) | (Value::Unit, Value::Unit) => true,
+            (Value::Some(a), Value::Some(b)) | (Value::Newtype(a), Value::Newtype(b)) => a == b,
+            (Value::Seq(a), Value::Seq(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => unordered_eq(a, b),
+            (
+                Value::Variant {
+                    variant: a_variant,
+                    value: a_value,
+                },
+                Value::Variant {
+                    variant: b_variant,
+                    value: b_value,
+                },
+            ) => a_variant == b_variant && a_value == b_value,
+            _ => false,
+        }
+    }
+}
+
+/// Returns whether the two sets of entries contain the same entries, in any order.
+fn unordered_eq(a: &[(Value, Value)], b: &[(Value, Value)]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut matched = vec![false; b.len()];
+    a.iter().all(|entry| {
+        b.iter()
+            .zip(matched.iter_mut())
+            .find(|(other, matched)| !**matched && entry == *other)
+            .map_or(false, |(_, matched)| {
+                *matched = true;
+                true
+            })
+    })
+}
+
+fn parse_value<I>(tokens: &mut I) -> Result<Value, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    let token = tokens.next().ok_or(Error::EndOfTokens)?;
+    parse_token(token, tokens)
+}
+
+fn parse_token<I>(mut token: CanonicalToken, tokens: &mut I) -> Result<Value, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    Ok(match token {
+        CanonicalToken::Bool(v) => Value::Bool(v),
+        CanonicalToken::I8(v) => Value::Signed(v.into()),
+        CanonicalToken::I16(v) => Value::Signed(v.into()),
+        CanonicalToken::I32(v) => Value::Signed(v.into()),
+        CanonicalToken::I64(v) => Value::Signed(v.into()),
+        CanonicalToken::I128(v) => Value::Signed(v),
+        CanonicalToken::U8(v) => Value::Unsigned(v.into()),
+        CanonicalToken::U16(v) => Value::Unsigned(v.into()),
+        CanonicalToken::U32(v) => Value::Unsigned(v.into()),
+        CanonicalToken::U64(v) => Value::Unsigned(v.into()),
+        CanonicalToken::U128(v) => Value::Unsigned(v),
+        CanonicalToken::F32(v) => Value::Float(v.into()),
+        CanonicalToken::F64(v) => Value::Float(v),
+        CanonicalToken::Char(v) => Value::Char(v),
+        CanonicalToken::Str(v) => Value::Str(v),
+        CanonicalToken::Bytes(v) => Value::Bytes(v),
+        CanonicalToken::None => Value::None,
+        CanonicalToken::Some => Value::Some(Box::new(parse_value(tokens)?)),
+        CanonicalToken::Unit | CanonicalToken::UnitStruct { .. } => Value::Unit,
+        CanonicalToken::UnitVariant { id, .. } => Value::Variant {
+            variant: id.variant,
+            value: Box::new(Value::Unit),
+        },
+        CanonicalToken::NewtypeStruct { .. } => Value::Newtype(Box::new(parse_value(tokens)?)),
+        CanonicalToken::NewtypeVariant { id, .. } => Value::Variant {
+            variant: id.variant,
+            value: Box::new(parse_value(tokens)?),
+        },
+        CanonicalToken::Seq { .. } => Value::Seq(parse_seq(
+            tokens,
+            &CanonicalToken::SeqEnd,
+            Error::ExpectedSeqEnd,
+        )?),
+        CanonicalToken::Tuple { .. } => Value::Seq(parse_seq(
+            tokens,
+            &CanonicalToken::TupleEnd,
+            Error::ExpectedTupleEnd,
+        )?),
+        CanonicalToken::TupleStruct { .. } => Value::Seq(parse_seq(
+            tokens,
+            &CanonicalToken::TupleStructEnd,
+            Error::ExpectedTupleStructEnd,
+        )?),
+        CanonicalToken::TupleVariant { id, .. } => Value::Variant {
+            variant: id.variant,
+            value: Box::new(Value::Seq(parse_seq(
+                tokens,
+                &CanonicalToken::TupleVariantEnd,
+                Error::ExpectedTupleVariantEnd,
+            )?)),
+        },
+        CanonicalToken::Map { .. } => Value::Map(parse_map(tokens)?),
+        CanonicalToken::Struct { .. } => Value::Map(parse_struct(
+            tokens,
+            &CanonicalToken::StructEnd,
+            Error::ExpectedStructEnd,
+        )?),
+        CanonicalToken::StructVariant { id, .. } => Value::Variant {
+            variant: id.variant,
+            value: Box::new(Value::Map(parse_struct(
+                tokens,
+                &CanonicalToken::StructVariantEnd,
+                Error::ExpectedStructVariantEnd,
+            )?)),
+        },
+        CanonicalToken::SeqEnd
+        | CanonicalToken::TupleEnd
+        | CanonicalToken::TupleStructEnd
+        | CanonicalToken::TupleVariantEnd
+        | CanonicalToken::MapEnd
+        | CanonicalToken::Field(..)
+        | CanonicalToken::SkippedField(..)
+        | CanonicalToken::StructEnd
+        | CanonicalToken::StructVariantEnd => {
+            return Err(Error::invalid_type(
+                Unexpected::from(&mut token),
+                &"a value",
+            ))
+        }
+        CanonicalToken::Error(message) => return Err(Error::Custom(message)),
+    })
+}
+
+fn parse_seq<I>(tokens: &mut I, end: &CanonicalToken, error: Error) -> Result<Vec<Value>, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    let mut values = Vec::new();
+    loop {
+        match tokens.next() {
+            Some(token) if token == *end => return Ok(values),
+            Some(token) => values.push(parse_token(token, tokens)?),
+            None => return Err(error),
+        }
+    }
+}
+
+fn parse_map<I>(tokens: &mut I) -> Result<Vec<(Value, Value)>, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    let mut entries = Vec::new();
+    loop {
+        match tokens.next() {
+            Some(CanonicalToken::MapEnd) => return Ok(entries),
+            Some(token) => {
+                let key = parse_token(token, tokens)?;
+                entries.push((key, parse_value(tokens)?));
+            }
+            None => return Err(Error::ExpectedMapEnd),
+        }
+    }
+}
+
+fn parse_struct<I>(
+    tokens: &mut I,
+    end: &CanonicalToken,
+    error: Error,
+) -> Result<Vec<(Value, Value)>, Error>
+where
+    I: Iterator<Item = CanonicalToken>,
+{
+    let mut entries = Vec::new();
+    loop {
+        match tokens.next() {
+            Some(token) if token == *end => return Ok(entries),
+            Some(CanonicalToken::Field(field)) => {
+                entries.push((Value::Str(field.to_owned()), parse_value(tokens)?));
+            }
+            Some(CanonicalToken::SkippedField(_)) => {}
+            Some(mut token) => {
+                return Err(Error::invalid_type(
+                    Unexpected::from(&mut token),
+                    &"a field",
+                ))
+            }
+            None => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Value;
+    use crate::{
+        de::Error,
+        Token,
+    };
+    use alloc::{
+        borrow::ToOwned,
+        boxed::Box,
+        vec,
+    };
+    use claims::{
+        assert_err_eq,
+        assert_ok,
+        assert_ok_eq,
+    };
+    use serde::de::{
+        Error as _,
+        Unexpected,
+    };
+
+    #[test]
+    fn from_tokens_bool() {
+        assert_ok_eq!(Value::from_tokens([Token::Bool(true)]), Value::Bool(true));
+    }
+
+    #[test]
+    fn from_tokens_option() {
+        assert_ok_eq!(
+            Value::from_tokens([Token::Some, Token::Char('a')]),
+            Value::Some(Box::new(Value::Char('a')))
+        );
+    }
+
+    #[test]
+    fn from_tokens_seq() {
+        assert_ok_eq!(
+            Value::from_tokens([
+                Token::Tuple { len: 2 },
+                Token::U8(1),
+                Token::Str("foo".to_owned()),
+                Token::TupleEnd,
+            ]),
+            Value::Seq(vec![Value::Unsigned(1), Value::Str("foo".to_owned())])
+        );
+    }
+
+    #[test]
+    fn from_tokens_struct() {
+        assert_ok_eq!(
+            Value::from_tokens([
+                Token::Struct {
+                    name: "Foo",
+                    len: 2,
+                },
+                Token::Field("a"),
+                Token::Unit,
+                Token::SkippedField("b"),
+                Token::StructEnd,
+            ]),
+            Value::Map(vec![(Value::Str("a".to_owned()), Value::Unit)])
+        );
+    }
+
+    #[test]
+    fn from_tokens_variant() {
+        assert_ok_eq!(
+            Value::from_tokens([
+                Token::TupleVariant {
+                    name: "Foo",
+                    variant_index: 0,
+                    variant: "Bar",
+                    len: 1,
+                },
+                Token::Bool(false),
+                Token::TupleVariantEnd,
+            ]),
+            Value::Variant {
+                variant: "Bar",
+                value: Box::new(Value::Seq(vec![Value::Bool(false)])),
+            }
+        );
+    }
+
+    #[test]
+    fn from_tokens_unordered() {
+        assert_ok_eq!(
+            Value::from_tokens([
+                Token::Map { len: Some(1) },
+                Token::Unordered(&[&[Token::U8(1), Token::U8(2)]]),
+                Token::MapEnd,
+            ]),
+            Value::Map(vec![(Value::Unsigned(1), Value::Unsigned(2))])
+        );
+    }
+
+    #[test]
+    fn from_tokens_end_of_tokens() {
+        assert_err_eq!(Value::from_tokens([]), Error::EndOfTokens);
+    }
+
+    #[test]
+    fn from_tokens_missing_end() {
+        assert_err_eq!(
+            Value::from_tokens([Token::Seq { len: None }, Token::U8(1)]),
+            Error::ExpectedSeqEnd
+        );
+    }
+
+    #[test]
+    fn from_tokens_unexpected_end() {
+        assert_err_eq!(
+            Value::from_tokens([Token::MapEnd]),
+            Error::invalid_type(Unexpected::Other("MapEnd"), &"a value")
+        );
+    }
+
+    #[test]
+    fn from_tokens_trailing_tokens() {
+        assert_err_eq!(
+            Value::from_tokens([Token::Unit, Token::Unit]),
+            Error::custom("trailing tokens after value")
+        );
+    }
+
+    #[test]
+    fn eq_integer_widths() {
+        assert_eq!(Value::Unsigned(1), Value::Signed(1));
+        assert_eq!(Value::Signed(1), Value::Unsigned(1));
+    }
+
+    #[test]
+    fn ne_negative_integer() {
+        assert_ne!(Value::Signed(-1), Value::Unsigned(u128::MAX));
+    }
+
+    #[test]
+    fn eq_float_widths() {
+        assert_eq!(
+            assert_ok!(Value::from_tokens([Token::F32(1.5)])),
+            assert_ok!(Value::from_tokens([Token::F64(1.5)]))
+        );
+    }
+
+    #[test]
+    fn eq_map_order() {
+        assert_eq!(
+            Value::Map(vec![
+                (Value::Unsigned(1), Value::Bool(true)),
+                (Value::Unsigned(2), Value::Bool(false)),
+            ]),
+            Value::Map(vec![
+                (Value::Unsigned(2), Value::Bool(false)),
+                (Value::Unsigned(1), Value::Bool(true)),
+            ])
+        );
+    }
+
+    #[test]
+    fn ne_map_duplicate_entries() {
+        assert_ne!(
+            Value::Map(vec![
+                (Value::Unsigned(1), Value::Unit),
+                (Value::Unsigned(1), Value::Unit),
+            ]),
+            Value::Map(vec![
+                (Value::Unsigned(1), Value::Unit),
+                (Value::Unsigned(2), Value::Unit),
+            ])
+        );
+    }
+
+    #[test]
+    fn ne_seq_order() {
+        assert_ne!(
+            Value::Seq(vec![Value::Unsigned(1), Value::Unsigned(2)]),
+            Value::Seq(vec![Value::Unsigned(2), Value::Unsigned(1)])
+        );
+    }
+
+    #[test]
+    fn ne_variant() {
+        assert_ne!(
+            Value::Variant {
+                variant: "Foo",
+                value: Box::new(Value::Unit),
+            },
+            Value::Variant {
+                variant: "Bar",
+                value: Box::new(Value::Unit),
+            }
+        );
+    }
+}